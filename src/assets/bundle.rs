@@ -0,0 +1,254 @@
+// 资源包格式 (.pak)
+// 开发心理：发布版本扫描成千上万个散文件太慢，打包成单个文件可以一次打开、按索引读取
+// 设计原则：文件头 + JSON索引 + 连续数据区；打开时只读索引，读取资源时按偏移定位
+
+use super::compression::{compress_asset, decompress_asset, CompressionType};
+use super::{calculate_file_checksum, AssetType, ChecksumAlgorithm};
+use crate::core::{GameError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use log::{debug, info};
+
+// 文件头：魔数 + 版本 + 索引长度
+const BUNDLE_MAGIC: &[u8; 4] = b"PPAK";
+const BUNDLE_VERSION: u32 = 1;
+const HEADER_SIZE: u64 = 4 + 4 + 8;
+
+// 索引条目，偏移相对于数据区起点
+// length和checksum始终对应原始数据；压缩存储时compressed_length为数据区中的实际字节数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleEntry {
+    pub id: String,
+    pub offset: u64,
+    pub length: u64,
+    pub checksum: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compressed_length: Option<u64>,
+}
+
+impl BundleEntry {
+    // 数据区中占用的字节数
+    pub fn stored_length(&self) -> u64 {
+        self.compressed_length.unwrap_or(self.length)
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.compressed_length.is_some()
+    }
+}
+
+// 已打开的资源包
+#[derive(Debug, Clone)]
+pub struct AssetBundle {
+    path: PathBuf,
+    entries: HashMap<String, BundleEntry>,
+    data_offset: u64,
+}
+
+impl AssetBundle {
+    // 将目录下所有文件打包，资源ID为相对路径（与scan_assets一致）
+    pub fn pack(dir: &Path, out_path: &Path) -> Result<AssetBundle> {
+        Self::pack_with_compression(dir, out_path, false)
+    }
+
+    // 打包并按资源类型选择压缩算法；压缩后没有变小的资源仍按原样存储
+    pub fn pack_with_compression(dir: &Path, out_path: &Path, compress: bool) -> Result<AssetBundle> {
+        let mut files = Vec::new();
+        collect_files(dir, dir, &mut files)?;
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut index = Vec::with_capacity(files.len());
+        let mut payloads: Vec<Option<Vec<u8>>> = Vec::with_capacity(files.len());
+        let mut offset = 0u64;
+        for (id, path) in &files {
+            let length = std::fs::metadata(path)?.len();
+            let checksum = calculate_file_checksum(path, ChecksumAlgorithm::Xxh3)?;
+
+            let payload = if compress {
+                compress_entry(id, path, length)?
+            } else {
+                None
+            };
+            let compressed_length = payload.as_ref().map(|data| data.len() as u64);
+
+            let entry = BundleEntry {
+                id: id.clone(),
+                offset,
+                length,
+                checksum,
+                compressed_length,
+            };
+            offset += entry.stored_length();
+            index.push(entry);
+            payloads.push(payload);
+        }
+
+        let index_bytes = serde_json::to_vec(&index)
+            .map_err(|e| GameError::SerializationError(format!("序列化资源包索引失败: {}", e)))?;
+
+        if let Some(parent) = out_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut writer = BufWriter::new(File::create(out_path)?);
+        writer.write_all(BUNDLE_MAGIC)?;
+        writer.write_all(&BUNDLE_VERSION.to_le_bytes())?;
+        writer.write_all(&(index_bytes.len() as u64).to_le_bytes())?;
+        writer.write_all(&index_bytes)?;
+
+        // 未压缩的文件流式拷贝，不把整个目录读入内存
+        for ((_, path), payload) in files.iter().zip(&payloads) {
+            match payload {
+                Some(data) => writer.write_all(data)?,
+                None => {
+                    let mut file = File::open(path)?;
+                    std::io::copy(&mut file, &mut writer)?;
+                },
+            }
+        }
+        writer.flush()?;
+
+        info!("资源包已生成: {:?} ({} 个资源, {} 字节)", out_path, index.len(), offset);
+
+        Ok(Self {
+            path: out_path.to_path_buf(),
+            entries: index.into_iter().map(|entry| (entry.id.clone(), entry)).collect(),
+            data_offset: HEADER_SIZE + index_bytes.len() as u64,
+        })
+    }
+
+    // 打开资源包，只读取文件头和索引
+    pub fn open(path: &Path) -> Result<AssetBundle> {
+        let mut file = File::open(path)
+            .map_err(|e| GameError::AssetError(format!("打开资源包失败: {:?}: {}", path, e)))?;
+
+        let mut magic = [0u8; 4];
+        file.read_exact(&mut magic)?;
+        if &magic != BUNDLE_MAGIC {
+            return Err(GameError::AssetError(format!("不是有效的资源包: {:?}", path)));
+        }
+
+        let mut version = [0u8; 4];
+        file.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != BUNDLE_VERSION {
+            return Err(GameError::AssetError(format!("不支持的资源包版本: {}", version)));
+        }
+
+        let mut index_len = [0u8; 8];
+        file.read_exact(&mut index_len)?;
+        let index_len = u64::from_le_bytes(index_len);
+
+        // 索引长度来自文件本身，分配前先确认没有超出文件剩余部分
+        let remaining = file.metadata()?.len().saturating_sub(HEADER_SIZE);
+        if index_len > remaining {
+            return Err(GameError::AssetError(format!(
+                "资源包索引长度超出文件大小: {:?} ({} > {})", path, index_len, remaining
+            )));
+        }
+
+        let mut index_bytes = vec![0u8; index_len as usize];
+        file.read_exact(&mut index_bytes)?;
+        let index: Vec<BundleEntry> = serde_json::from_slice(&index_bytes)
+            .map_err(|e| GameError::AssetError(format!("资源包索引损坏: {:?}: {}", path, e)))?;
+
+        debug!("打开资源包: {:?} ({} 个资源)", path, index.len());
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries: index.into_iter().map(|entry| (entry.id.clone(), entry)).collect(),
+            data_offset: HEADER_SIZE + index_len,
+        })
+    }
+
+    // 按ID读取资源数据并校验
+    pub fn read(&self, id: &str) -> Result<Vec<u8>> {
+        let entry = self.entries.get(id)
+            .ok_or_else(|| GameError::AssetError(format!("资源包中不存在: {}", id)))?;
+
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.data_offset + entry.offset))?;
+
+        let mut data = vec![0u8; entry.stored_length() as usize];
+        file.read_exact(&mut data)?;
+
+        if entry.is_compressed() {
+            data = decompress_asset(&data)
+                .map_err(|e| GameError::AssetError(format!("资源包数据解压失败: {}: {}", id, e)))?;
+        }
+
+        let checksum = format!("{:032x}", xxhash_rust::xxh3::xxh3_128(&data));
+        if checksum != entry.checksum {
+            return Err(GameError::AssetError(format!("资源包数据校验失败: {}", id)));
+        }
+
+        Ok(data)
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.entries.contains_key(id)
+    }
+
+    pub fn entry(&self, id: &str) -> Option<&BundleEntry> {
+        self.entries.get(id)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &BundleEntry> {
+        self.entries.values()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// 按资源类型压缩单个文件，压缩无收益时返回None
+fn compress_entry(id: &str, path: &Path, length: u64) -> Result<Option<Vec<u8>>> {
+    let extension = Path::new(id).extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+    let algorithm = CompressionType::for_asset_type(
+        AssetType::from_extension(extension).unwrap_or(AssetType::Data)
+    );
+    if algorithm == CompressionType::None {
+        return Ok(None);
+    }
+
+    let compressed = compress_asset(&std::fs::read(path)?, algorithm)?;
+    if compressed.len() as u64 >= length {
+        return Ok(None);
+    }
+
+    debug!("压缩资源: {} ({} -> {} 字节)", id, length, compressed.len());
+    Ok(Some(compressed))
+}
+
+fn collect_files(dir: &Path, base_path: &Path, files: &mut Vec<(String, PathBuf)>) -> Result<()> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| GameError::IOError(format!("读取目录失败: {:?}: {}", dir, e)))?;
+
+    for entry in entries {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            collect_files(&path, base_path, files)?;
+        } else if path.is_file() {
+            let relative_path = path.strip_prefix(base_path).unwrap_or(&path);
+            let id = relative_path.to_string_lossy().replace('\\', "/");
+            files.push((id, path));
+        }
+    }
+
+    Ok(())
+}
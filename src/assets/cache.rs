@@ -3,6 +3,7 @@
 // 设计原则：LRU策略、内存压力感知、统计追踪、线程安全
 
 use crate::core::{GameError, Result};
+use crate::utils::CacheEvictionPolicy;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
 use std::hash::{Hash, Hasher};
@@ -135,6 +136,7 @@ pub struct AssetCache {
     max_size: usize,
     current_size: RwLock<usize>,
     max_entries: usize,
+    eviction_policy: CacheEvictionPolicy,
     
     // 统计信息
     stats: RwLock<CacheStats>,
@@ -153,6 +155,10 @@ pub struct AssetCache {
 
 impl AssetCache {
     pub fn new(max_size: usize) -> Self {
+        Self::with_policy(max_size, CacheEvictionPolicy::LRU)
+    }
+    
+    pub fn with_policy(max_size: usize, eviction_policy: CacheEvictionPolicy) -> Self {
         Self {
             entries: RwLock::new(HashMap::new()),
             lru_order: Mutex::new(HashMap::new()),
@@ -161,7 +167,8 @@ impl AssetCache {
             
             max_size,
             current_size: RwLock::new(0),
-            max_entries: max_size / 1024, // 假设平均1KB per entry
+            max_entries: (max_size / 1024).max(1), // 假设平均1KB per entry，至少容纳一个条目
+            eviction_policy,
             
             stats: RwLock::new(CacheStats {
                 max_size,
@@ -341,8 +348,73 @@ impl AssetCache {
         self.utilization() > self.cleanup_threshold
     }
     
+    pub fn eviction_policy(&self) -> CacheEvictionPolicy {
+        self.eviction_policy
+    }
+    
+    // 按驱逐策略排列的候选键，最先被驱逐的在前
+    fn eviction_candidates(&self) -> Vec<String> {
+        match self.eviction_policy {
+            CacheEvictionPolicy::LRU => {
+                // 从LRU链表尾部（最久未访问）向前遍历
+                let lru_order = self.lru_order.lock().unwrap();
+                let mut candidates = Vec::with_capacity(lru_order.len());
+                let mut current_key = self.lru_tail.lock().unwrap().clone();
+                
+                while let Some(key) = current_key {
+                    current_key = lru_order.get(&key).and_then(|node| node.prev.clone());
+                    candidates.push(key);
+                }
+                candidates
+            },
+            CacheEvictionPolicy::LFU => {
+                let entries = self.entries.read().unwrap();
+                let mut candidates: Vec<&CacheEntry> = entries.values().collect();
+                candidates.sort_by(|a, b| {
+                    a.access_count.cmp(&b.access_count).then(a.last_accessed.cmp(&b.last_accessed))
+                });
+                candidates.into_iter().map(|entry| entry.key.clone()).collect()
+            },
+            CacheEvictionPolicy::FIFO => {
+                let entries = self.entries.read().unwrap();
+                let mut candidates: Vec<&CacheEntry> = entries.values().collect();
+                candidates.sort_by_key(|entry| entry.created_at);
+                candidates.into_iter().map(|entry| entry.key.clone()).collect()
+            },
+            CacheEvictionPolicy::Random => {
+                let mut candidates: Vec<String> = self.entries.read().unwrap().keys().cloned().collect();
+                fastrand::shuffle(&mut candidates);
+                candidates
+            },
+        }
+    }
+    
     // 为新条目腾出空间
     fn make_space(&self, needed_size: usize) {
+        let candidates = self.eviction_candidates();
+        
+        // 第一阶段：按策略顺序清理已空闲足够久的条目，降到目标使用率
+        self.evict_idle(&candidates, needed_size);
+        
+        // 第二阶段：仍超出预算时，按策略顺序强制驱逐（关键资源除外）
+        let mut current_size = *self.current_size.read().unwrap();
+        for key in &candidates {
+            if current_size + needed_size <= self.max_size {
+                break;
+            }
+            
+            let evictable = self.entries.read().unwrap()
+                .get(key)
+                .map_or(false, |entry| entry.priority != CachePriority::Critical);
+            
+            if evictable && self.remove(key).is_some() {
+                debug!("超出缓存预算，按{:?}策略驱逐: {}", self.eviction_policy, key);
+                current_size = *self.current_size.read().unwrap();
+            }
+        }
+    }
+    
+    fn evict_idle(&self, candidates: &[String], needed_size: usize) {
         let target_size = (self.max_size as f64 * self.cleanup_target) as usize;
         let current_size = *self.current_size.read().unwrap();
         
@@ -354,16 +426,15 @@ impl AssetCache {
         let mut freed_space = 0;
         let mut removed_keys = Vec::new();
         
-        // 从尾部开始移除条目（最少使用的）
-        let mut current_key = self.lru_tail.lock().unwrap().clone();
-        
-        while freed_space < space_to_free && current_key.is_some() {
-            let key = current_key.unwrap();
+        for key in candidates {
+            if freed_space >= space_to_free {
+                break;
+            }
             
             // 检查是否可以移除（考虑优先级和最小空闲时间）
             let can_remove = {
                 let entries = self.entries.read().unwrap();
-                if let Some(entry) = entries.get(&key) {
+                if let Some(entry) = entries.get(key) {
                     let idle_time = entry.idle_time();
                     let min_idle = Duration::from_secs_f64(
                         self.min_idle_time.as_secs_f64() / entry.priority.retention_multiplier()
@@ -378,18 +449,12 @@ impl AssetCache {
             if can_remove {
                 let entry_size = {
                     let entries = self.entries.read().unwrap();
-                    entries.get(&key).map(|e| e.size).unwrap_or(0)
+                    entries.get(key).map(|e| e.size).unwrap_or(0)
                 };
                 
                 removed_keys.push(key.clone());
                 freed_space += entry_size;
             }
-            
-            // 移动到前一个条目
-            current_key = {
-                let lru_order = self.lru_order.lock().unwrap();
-                lru_order.get(&key).and_then(|node| node.prev.clone())
-            };
         }
         
         // 移除选中的条目
@@ -767,4 +832,55 @@ mod tests {
         assert_eq!(stats.total_requests, 2);
         assert_eq!(stats.hit_rate(), 0.5);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_lru_policy_evicts_least_recently_accessed_over_budget() {
+        let cache = AssetCache::with_policy(300, CacheEvictionPolicy::LRU);
+        
+        cache.insert("a".to_string(), vec![0; 100]);
+        cache.insert("b".to_string(), vec![0; 100]);
+        cache.insert("c".to_string(), vec![0; 100]);
+        
+        // a刚被访问，b成为最久未访问的条目
+        cache.get("a");
+        cache.insert("d".to_string(), vec![0; 100]);
+        
+        assert!(!cache.contains("b"));
+        assert!(cache.contains("a"));
+        assert!(cache.contains("c"));
+        assert!(cache.contains("d"));
+        assert!(cache.get_memory_usage() <= 300);
+        assert_eq!(cache.get_stats().evictions, 1);
+    }
+    
+    #[test]
+    fn test_fifo_policy_ignores_access() {
+        let cache = AssetCache::with_policy(300, CacheEvictionPolicy::FIFO);
+        
+        cache.insert("a".to_string(), vec![0; 100]);
+        std::thread::sleep(Duration::from_millis(2));
+        cache.insert("b".to_string(), vec![0; 100]);
+        std::thread::sleep(Duration::from_millis(2));
+        cache.insert("c".to_string(), vec![0; 100]);
+        
+        cache.get("a");
+        cache.insert("d".to_string(), vec![0; 100]);
+        
+        assert!(!cache.contains("a"));
+        assert!(cache.contains("b"));
+    }
+    
+    #[test]
+    fn test_small_budget_keeps_entries_within_size() {
+        let cache = AssetCache::new(512);
+        
+        cache.insert("a".to_string(), vec![0; 10]);
+        cache.insert("b".to_string(), vec![0; 10]);
+        cache.insert("c".to_string(), vec![0; 10]);
+        
+        assert!(cache.contains("a"));
+        assert!(cache.contains("b"));
+        assert!(cache.contains("c"));
+        assert_eq!(cache.get_stats().evictions, 0);
+    }
+}
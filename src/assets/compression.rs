@@ -3,6 +3,7 @@
 // 设计原则：算法选择、压缩率优化、解压速度、内存友好
 
 use crate::core::{GameError, Result};
+use crate::assets::AssetType;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::{Read, Write, Cursor};
@@ -69,6 +70,39 @@ impl CompressionType {
         }
     }
     
+    // 根据资源类型选择压缩算法：图片和音频本身已是压缩格式，文本类资源压缩效果好
+    pub fn for_asset_type(asset_type: AssetType) -> Self {
+        match asset_type {
+            AssetType::Texture | AssetType::Audio => CompressionType::None,
+            AssetType::Data | AssetType::Map | AssetType::Script | AssetType::Config |
+            AssetType::Shader | AssetType::Animation | AssetType::Model | AssetType::Font => CompressionType::Zlib,
+        }
+    }
+    
+    // 压缩帧头中的算法标记
+    fn to_tag(&self) -> u8 {
+        match self {
+            CompressionType::None => 0,
+            CompressionType::LZ4 => 1,
+            CompressionType::Zlib => 2,
+            CompressionType::Zstd => 3,
+            CompressionType::Brotli => 4,
+            CompressionType::Snappy => 5,
+        }
+    }
+    
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(CompressionType::None),
+            1 => Some(CompressionType::LZ4),
+            2 => Some(CompressionType::Zlib),
+            3 => Some(CompressionType::Zstd),
+            4 => Some(CompressionType::Brotli),
+            5 => Some(CompressionType::Snappy),
+            _ => None,
+        }
+    }
+    
     // 根据数据类型推荐压缩算法
     pub fn recommend_for_data(data: &[u8]) -> Self {
         let size = data.len();
@@ -254,81 +288,24 @@ impl Compressor for LZ4Compressor {
     }
 }
 
-// Zlib压缩器（简化实现）
+// Zlib压缩器（基于flate2）
 pub struct ZlibCompressor;
 
 impl Compressor for ZlibCompressor {
     fn compress(&self, data: &[u8], config: &CompressionConfig) -> Result<Vec<u8>> {
-        // 简化的Zlib实现 - 实际项目中应使用flate2库
-        use std::io::Write;
-        
-        let mut compressed = Vec::new();
-        
-        // Zlib头部（简化）
-        compressed.write_all(&[0x78, 0x9C]).unwrap();
-        
-        // 简单的字节频率压缩
-        let mut frequency = [0u32; 256];
-        for &byte in data {
-            frequency[byte as usize] += 1;
-        }
-        
-        // 存储频率表（简化）
-        for &freq in &frequency {
-            compressed.write_all(&(freq as u16).to_le_bytes()).unwrap();
-        }
-        
-        // 使用最频繁字节的索引替代
-        let most_frequent_byte = frequency
-            .iter()
-            .enumerate()
-            .max_by_key(|(_, &freq)| freq)
-            .map(|(idx, _)| idx as u8)
-            .unwrap_or(0);
-        
-        compressed.push(most_frequent_byte);
-        
-        // 简单替换编码
-        for &byte in data {
-            if byte == most_frequent_byte {
-                compressed.push(0xFF); // 特殊标记
-            } else {
-                compressed.push(byte);
-            }
-        }
-        
-        Ok(compressed)
+        let level = flate2::Compression::new(config.level.clamp(0, 9) as u32);
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), level);
+        encoder.write_all(data)
+            .map_err(|e| GameError::CompressionError(format!("Zlib压缩失败: {}", e)))?;
+        encoder.finish()
+            .map_err(|e| GameError::CompressionError(format!("Zlib压缩失败: {}", e)))
     }
     
     fn decompress(&self, compressed: &[u8]) -> Result<Vec<u8>> {
-        if compressed.len() < 514 { // 2 + 256*2
-            return Err(GameError::CompressionError("Zlib数据不完整".to_string()));
-        }
-        
-        // 跳过头部
-        let mut i = 2;
-        
-        // 读取频率表
-        let mut frequency = [0u32; 256];
-        for freq in &mut frequency {
-            *freq = u16::from_le_bytes([compressed[i], compressed[i + 1]]) as u32;
-            i += 2;
-        }
-        
-        let most_frequent_byte = compressed[i];
-        i += 1;
-        
-        // 解压缩数据
+        let mut decoder = flate2::read::ZlibDecoder::new(compressed);
         let mut decompressed = Vec::new();
-        while i < compressed.len() {
-            if compressed[i] == 0xFF {
-                decompressed.push(most_frequent_byte);
-            } else {
-                decompressed.push(compressed[i]);
-            }
-            i += 1;
-        }
-        
+        decoder.read_to_end(&mut decompressed)
+            .map_err(|e| GameError::CompressionError(format!("Zlib数据损坏: {}", e)))?;
         Ok(decompressed)
     }
     
@@ -690,6 +667,64 @@ fn calculate_checksum(data: &[u8]) -> u32 {
     data.iter().fold(0u32, |acc, &byte| acc.wrapping_add(byte as u32))
 }
 
+// 压缩资源帧头：魔数 + 算法标记 + 原始长度
+// 加载器通过魔数识别压缩资源，无需额外的清单标记
+const COMPRESSED_ASSET_MAGIC: &[u8; 4] = b"PKCZ";
+const COMPRESSED_HEADER_SIZE: usize = 4 + 1 + 8;
+
+// 判断数据是否为带帧头的压缩资源
+pub fn is_compressed_asset(data: &[u8]) -> bool {
+    data.len() >= COMPRESSED_HEADER_SIZE && &data[..4] == COMPRESSED_ASSET_MAGIC
+}
+
+// 压缩资源数据并写入帧头
+pub fn compress_asset(data: &[u8], algorithm: CompressionType) -> Result<Vec<u8>> {
+    let payload = match algorithm {
+        CompressionType::None => data.to_vec(),
+        CompressionType::Zlib => ZlibCompressor.compress(data, &CompressionConfig::default())?,
+        other => {
+            return Err(GameError::CompressionError(format!("资源压缩不支持该算法: {:?}", other)));
+        }
+    };
+
+    let mut framed = Vec::with_capacity(COMPRESSED_HEADER_SIZE + payload.len());
+    framed.extend_from_slice(COMPRESSED_ASSET_MAGIC);
+    framed.push(algorithm.to_tag());
+    framed.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    framed.extend_from_slice(&payload);
+    Ok(framed)
+}
+
+// 解析帧头并解压，返回原始数据
+pub fn decompress_asset(data: &[u8]) -> Result<Vec<u8>> {
+    if !is_compressed_asset(data) {
+        return Err(GameError::CompressionError("缺少压缩资源帧头".to_string()));
+    }
+
+    let algorithm = CompressionType::from_tag(data[4])
+        .ok_or_else(|| GameError::CompressionError(format!("未知的压缩算法标记: {}", data[4])))?;
+    let mut length_bytes = [0u8; 8];
+    length_bytes.copy_from_slice(&data[5..COMPRESSED_HEADER_SIZE]);
+    let original_size = u64::from_le_bytes(length_bytes) as usize;
+    let payload = &data[COMPRESSED_HEADER_SIZE..];
+
+    let decompressed = match algorithm {
+        CompressionType::None => payload.to_vec(),
+        CompressionType::Zlib => ZlibCompressor.decompress(payload)?,
+        other => {
+            return Err(GameError::CompressionError(format!("资源解压不支持该算法: {:?}", other)));
+        }
+    };
+
+    if decompressed.len() != original_size {
+        return Err(GameError::CompressionError(format!(
+            "解压后长度不符: 期望 {} 字节, 实际 {} 字节", original_size, decompressed.len()
+        )));
+    }
+
+    Ok(decompressed)
+}
+
 impl Default for CompressionManager {
     fn default() -> Self {
         Self::new()
@@ -741,6 +776,24 @@ mod tests {
         assert_eq!(stats.total_compressions, 1);
     }
     
+    #[test]
+    fn test_compressed_asset_round_trip() {
+        let text = "{\"name\": \"pikachu\", \"type\": \"electric\"}\n".repeat(50);
+        
+        let framed = compress_asset(text.as_bytes(), CompressionType::for_asset_type(AssetType::Data)).unwrap();
+        assert!(is_compressed_asset(&framed));
+        assert!(framed.len() < text.len());
+        assert!(!is_compressed_asset(text.as_bytes()));
+        
+        let decompressed = decompress_asset(&framed).unwrap();
+        assert_eq!(decompressed, text.as_bytes());
+        
+        // 帧头长度被篡改时应报错
+        let mut corrupted = framed.clone();
+        corrupted[5] ^= 0x01;
+        assert!(decompress_asset(&corrupted).is_err());
+    }
+    
     #[test]
     fn test_entropy_calculation() {
         let uniform_data = (0..=255u8).collect::<Vec<_>>();
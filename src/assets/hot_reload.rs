@@ -0,0 +1,101 @@
+// 资源热重载
+// 开发心理：美术和策划修改资源后希望立刻在游戏里看到效果，不必重启
+// 设计原则：文件监听只负责收集变更路径，真正的重载在主线程按帧处理，避免并发修改注册表
+
+use crate::core::{GameError, Result};
+use crate::core::event_system::Event;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use log::{debug, warn};
+
+// 资源重载完成事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetReloadedEvent {
+    pub asset_id: String,
+    pub path: PathBuf,
+    // 因依赖变化而重载时，记录触发变化的资源
+    pub changed_dependency: Option<String>,
+}
+
+impl Event for AssetReloadedEvent {
+    fn event_type(&self) -> &'static str { "AssetReloaded" }
+    fn as_any(&self) -> &dyn std::any::Any { self }
+}
+
+// 重载回调
+pub type AssetReloadCallback = Box<dyn Fn(&AssetReloadedEvent) + Send + Sync>;
+
+// 文件监听器 - 在后台线程收集变更的文件路径
+pub struct HotReloadWatcher {
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<PathBuf>,
+    watched_paths: Vec<PathBuf>,
+}
+
+impl HotReloadWatcher {
+    pub fn new(paths: &[PathBuf]) -> Result<Self> {
+        let (sender, receiver) = mpsc::channel();
+
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+            match result {
+                Ok(event) => {
+                    if matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) {
+                        for path in event.paths {
+                            // 接收端已释放说明热重载已关闭，忽略即可
+                            let _ = sender.send(path);
+                        }
+                    }
+                },
+                Err(e) => warn!("资源文件监听出错: {}", e),
+            }
+        }).map_err(|e| GameError::AssetError(format!("创建文件监听器失败: {}", e)))?;
+
+        let mut watched_paths = Vec::new();
+        for path in paths {
+            if !path.exists() {
+                continue;
+            }
+
+            watcher.watch(path, RecursiveMode::Recursive)
+                .map_err(|e| GameError::AssetError(format!("监听资源目录失败: {:?}: {}", path, e)))?;
+            debug!("监听资源目录: {:?}", path);
+            watched_paths.push(path.clone());
+        }
+
+        Ok(Self {
+            _watcher: watcher,
+            receiver,
+            watched_paths,
+        })
+    }
+
+    // 取出自上次调用以来变更过的文件（去重）
+    pub fn drain_changed_paths(&self) -> Vec<PathBuf> {
+        let mut seen = HashSet::new();
+        self.receiver
+            .try_iter()
+            .map(|path| normalize_path(&path))
+            .filter(|path| seen.insert(path.clone()))
+            .collect()
+    }
+
+    pub fn watched_paths(&self) -> &[PathBuf] {
+        &self.watched_paths
+    }
+}
+
+impl std::fmt::Debug for HotReloadWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HotReloadWatcher")
+            .field("watched_paths", &self.watched_paths)
+            .finish()
+    }
+}
+
+// 监听器上报的路径可能经过符号链接解析，比较前统一规范化
+pub fn normalize_path(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,244 @@
+// 后台资源加载队列
+// 开发心理：加载界面需要显示进度，主线程不能被文件IO阻塞
+// 设计原则：固定数量的工作线程限制并发，结果通过通道回传，由主线程轮询时合并
+
+use super::loader::AssetLoader;
+use crate::core::Result;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use log::{debug, warn};
+
+// 全局递增，工作线程池重建后句柄也不会重复
+static NEXT_LOAD_TOKEN: AtomicU64 = AtomicU64::new(1);
+
+// 异步加载句柄
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LoadToken(u64);
+
+impl LoadToken {
+    pub fn next() -> Self {
+        LoadToken(NEXT_LOAD_TOKEN.fetch_add(1, Ordering::Relaxed))
+    }
+
+    pub fn id(&self) -> u64 {
+        self.0
+    }
+}
+
+// 异步加载状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadStatus {
+    Loading,
+    Loaded,
+    Failed(String),
+}
+
+// 加载任务
+#[derive(Debug)]
+struct LoadJob {
+    token: LoadToken,
+    path: PathBuf,
+}
+
+// 加载结果
+#[derive(Debug)]
+pub struct LoadJobResult {
+    pub token: LoadToken,
+    pub result: Result<Vec<u8>>,
+}
+
+// 工作线程池
+#[derive(Debug)]
+pub struct AssetJobQueue {
+    sender: Option<Sender<LoadJob>>,
+    results: Receiver<LoadJobResult>,
+    workers: Vec<JoinHandle<()>>,
+    in_flight: usize,
+}
+
+impl AssetJobQueue {
+    pub fn new(max_concurrent_loads: usize) -> Self {
+        let worker_count = max_concurrent_loads.max(1);
+        let (job_sender, job_receiver) = mpsc::channel::<LoadJob>();
+        let (result_sender, result_receiver) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        let workers = (0..worker_count)
+            .map(|index| {
+                let jobs = Arc::clone(&job_receiver);
+                let results = result_sender.clone();
+
+                thread::Builder::new()
+                    .name(format!("asset-loader-{}", index))
+                    .spawn(move || {
+                        let loader = AssetLoader::new();
+                        loop {
+                            // 只在取任务时持锁，加载过程不阻塞其他工作线程
+                            let job = match jobs.lock() {
+                                Ok(receiver) => receiver.recv(),
+                                Err(_) => break,
+                            };
+
+                            // 发送端已释放，队列关闭
+                            let Ok(job) = job else { break };
+
+                            debug!("后台加载资源: {:?}", job.path);
+                            let result = loader.load_asset(&job.path);
+                            if results.send(LoadJobResult { token: job.token, result }).is_err() {
+                                break;
+                            }
+                        }
+                    })
+                    .expect("创建资源加载线程失败")
+            })
+            .collect();
+
+        Self {
+            sender: Some(job_sender),
+            results: result_receiver,
+            workers,
+            in_flight: 0,
+        }
+    }
+
+    // 提交加载任务，超过并发上限的任务在通道中排队
+    pub fn submit(&mut self, token: LoadToken, path: PathBuf) {
+        match self.sender.as_ref() {
+            Some(sender) if sender.send(LoadJob { token, path }).is_ok() => {
+                self.in_flight += 1;
+            },
+            _ => warn!("资源加载队列已关闭，无法提交任务"),
+        }
+    }
+
+    // 取出已完成的任务结果
+    pub fn drain_results(&mut self) -> Vec<LoadJobResult> {
+        let results: Vec<LoadJobResult> = self.results.try_iter().collect();
+        self.in_flight = self.in_flight.saturating_sub(results.len());
+        results
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    pub fn worker_count(&self) -> usize {
+        self.workers.len()
+    }
+}
+
+impl Drop for AssetJobQueue {
+    fn drop(&mut self) {
+        // 关闭任务通道，工作线程处理完当前任务后退出
+        self.sender.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+// 后台加载的进度快照，用于加载界面的进度条
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueueProgress {
+    pub total: usize,               // 已提交的加载数
+    pub completed: usize,           // 已结束的加载数(含失败)
+    pub current: Option<String>,    // 最早提交且仍在加载的资源
+}
+
+impl QueueProgress {
+    // 完成比例（0.0-1.0），没有任何加载时视为完成
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        self.completed as f32 / self.total as f32
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.completed >= self.total
+    }
+}
+
+// 保留的已结束加载数，超出时丢弃最早的记录
+pub const FINISHED_HISTORY: usize = 256;
+
+// 记录进行中的加载和最近结束的加载；结束或取消的句柄移出进行中列表，不会无限累积
+#[derive(Debug, Default)]
+pub struct LoadTracker {
+    loading: HashMap<LoadToken, String>,
+    finished: VecDeque<(LoadToken, LoadStatus)>,
+    total: usize,       // 本批提交的加载数，上一批全部结束后重新计数
+    completed: usize,   // 本批已结束的加载数(含失败和取消)
+}
+
+impl LoadTracker {
+    pub fn insert(&mut self, token: LoadToken, asset_id: String, status: LoadStatus) {
+        if self.loading.is_empty() {
+            self.total = 0;
+            self.completed = 0;
+        }
+        self.total += 1;
+        match status {
+            LoadStatus::Loading => {
+                self.loading.insert(token, asset_id);
+            },
+            status => self.record_finished(token, status),
+        }
+    }
+
+    pub fn asset_id(&self, token: LoadToken) -> Option<&str> {
+        self.loading.get(&token).map(|asset_id| asset_id.as_str())
+    }
+
+    // 加载结束，句柄移入已结束记录
+    pub fn finish(&mut self, token: LoadToken, status: LoadStatus) {
+        if self.loading.remove(&token).is_some() {
+            self.record_finished(token, status);
+        }
+    }
+
+    // 取消进行中的加载并返回对应的资源，之后到达的结果会被忽略
+    pub fn cancel(&mut self, token: LoadToken) -> Option<String> {
+        let asset_id = self.loading.remove(&token)?;
+        self.completed += 1;
+        Some(asset_id)
+    }
+
+    pub fn status(&self, token: LoadToken) -> Option<LoadStatus> {
+        if self.loading.contains_key(&token) {
+            return Some(LoadStatus::Loading);
+        }
+        self.finished.iter()
+            .find(|(finished, _)| *finished == token)
+            .map(|(_, status)| status.clone())
+    }
+
+    // 加载进度（0.0-1.0），用于加载界面
+    pub fn progress(&self) -> f32 {
+        self.snapshot().fraction()
+    }
+
+    pub fn snapshot(&self) -> QueueProgress {
+        let current = self.loading.iter()
+            .min_by_key(|(token, _)| token.id())
+            .map(|(_, asset_id)| asset_id.clone());
+
+        QueueProgress {
+            total: self.total,
+            completed: self.completed,
+            current,
+        }
+    }
+
+    fn record_finished(&mut self, token: LoadToken, status: LoadStatus) {
+        self.completed += 1;
+        self.finished.push_back((token, status));
+        while self.finished.len() > FINISHED_HISTORY {
+            self.finished.pop_front();
+        }
+    }
+}
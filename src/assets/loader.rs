@@ -4,6 +4,7 @@
 
 use crate::core::{GameError, Result};
 use crate::assets::{AssetType, AssetMetadata};
+use crate::assets::compression::{decompress_asset, is_compressed_asset};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::Read;
@@ -352,6 +353,9 @@ pub struct LoadStats {
     pub total_bytes_loaded: u64,
     pub total_load_time: Duration,
     pub cache_hits: u64,
+    pub compressed_loads: u64,
+    pub compressed_bytes: u64,   // 压缩资源在磁盘上的字节数
+    pub decompressed_bytes: u64, // 压缩资源解压后的字节数
 }
 
 impl LoadStats {
@@ -379,6 +383,15 @@ impl LoadStats {
             mb_loaded / self.total_load_time.as_secs_f64()
         }
     }
+    
+    // 压缩比（压缩后/原始），没有压缩资源时为1.0
+    pub fn compression_ratio(&self) -> f64 {
+        if self.decompressed_bytes == 0 {
+            1.0
+        } else {
+            self.compressed_bytes as f64 / self.decompressed_bytes as f64
+        }
+    }
 }
 
 impl std::fmt::Debug for AssetLoader {
@@ -550,6 +563,19 @@ impl AssetLoader {
         progress.stage = LoadStage::Parsing;
         self.notify_progress(progress, options);
         
+        // 带压缩帧头的资源先透明解压，解析器只看到原始数据
+        if is_compressed_asset(&buffer) {
+            let compressed_size = buffer.len() as u64;
+            buffer = decompress_asset(&buffer)
+                .map_err(|e| GameError::AssetError(format!("解压资源失败: {:?}: {}", path, e)))?;
+            
+            let mut stats = self.load_stats.lock().unwrap();
+            stats.compressed_loads += 1;
+            stats.compressed_bytes += compressed_size;
+            stats.decompressed_bytes += buffer.len() as u64;
+            debug!("解压资源: {:?} ({} -> {} 字节)", path, compressed_size, buffer.len());
+        }
+        
         let asset_type = AssetType::from_extension(
             path.extension().and_then(|e| e.to_str()).unwrap_or("")
         ).unwrap_or(AssetType::Data);
@@ -752,6 +778,26 @@ mod tests {
         assert_eq!(stats.successful_loads, 1);
     }
     
+    #[test]
+    fn test_load_compressed_asset() {
+        use crate::assets::compression::{compress_asset, CompressionType};
+        
+        let loader = AssetLoader::new();
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("dialog.txt");
+        let text = "Professor Oak: Welcome to the world of Pokemon!\n".repeat(40);
+        
+        fs::write(&file_path, compress_asset(text.as_bytes(), CompressionType::Zlib).unwrap()).unwrap();
+        
+        let data = loader.load_asset(&file_path).unwrap();
+        assert_eq!(data, text.as_bytes());
+        
+        let stats = loader.get_stats();
+        assert_eq!(stats.compressed_loads, 1);
+        assert_eq!(stats.decompressed_bytes, text.len() as u64);
+        assert!(stats.compression_ratio() < 1.0);
+    }
+    
     #[test]
     fn test_load_progress() {
         let progress = LoadProgress {
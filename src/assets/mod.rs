@@ -5,11 +5,17 @@
 pub mod cache;
 pub mod compression;
 pub mod loader;
+pub mod hot_reload;
+pub mod jobs;
+pub mod bundle;
 
 use crate::core::{GameError, Result};
 use crate::core::resource_manager::{ResourceManager, ResourceHandle, ResourceType};
+use crate::core::event_system::EventSystem;
+use crate::core::services::{ServiceGuard, Services};
+use crate::utils::CacheEvictionPolicy;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant, SystemTime};
@@ -18,6 +24,9 @@ use log::{info, debug, warn, error};
 pub use cache::*;
 pub use compression::*;
 pub use loader::*;
+pub use hot_reload::{AssetReloadedEvent, AssetReloadCallback, HotReloadWatcher};
+pub use jobs::{AssetJobQueue, LoadStatus, LoadToken, QueueProgress};
+pub use bundle::{AssetBundle, BundleEntry};
 
 // 资源类型枚举
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -67,6 +76,82 @@ impl AssetType {
     }
 }
 
+// 资源校验和算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChecksumAlgorithm {
+    Xxh3,   // 速度快，用于热重载变更检测
+    Sha256, // 加密哈希，用于完整性校验（需要sha256-checksum特性）
+}
+
+impl ChecksumAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Xxh3 => "xxh3-128",
+            ChecksumAlgorithm::Sha256 => "sha256",
+        }
+    }
+    
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "xxh3-128" => Some(ChecksumAlgorithm::Xxh3),
+            "sha256" => Some(ChecksumAlgorithm::Sha256),
+            _ => None,
+        }
+    }
+    
+    // 编译时启用的默认算法
+    pub fn preferred() -> Self {
+        if cfg!(feature = "sha256-checksum") {
+            ChecksumAlgorithm::Sha256
+        } else {
+            ChecksumAlgorithm::Xxh3
+        }
+    }
+}
+
+// 分块计算文件哈希，大文件不会整体读入内存
+const CHECKSUM_CHUNK_SIZE: usize = 64 * 1024;
+
+pub fn calculate_file_checksum(path: &Path, algorithm: ChecksumAlgorithm) -> Result<String> {
+    use std::io::Read;
+    
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| GameError::IOError(format!("读取文件失败: {}", e)))?;
+    let mut buffer = vec![0u8; CHECKSUM_CHUNK_SIZE];
+    
+    match algorithm {
+        ChecksumAlgorithm::Xxh3 => {
+            let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+            loop {
+                let read = file.read(&mut buffer)?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..read]);
+            }
+            Ok(format!("{:032x}", hasher.digest128()))
+        },
+        #[cfg(feature = "sha256-checksum")]
+        ChecksumAlgorithm::Sha256 => {
+            use sha2::{Digest, Sha256};
+            
+            let mut hasher = Sha256::new();
+            loop {
+                let read = file.read(&mut buffer)?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..read]);
+            }
+            Ok(format!("{:x}", hasher.finalize()))
+        },
+        #[cfg(not(feature = "sha256-checksum"))]
+        ChecksumAlgorithm::Sha256 => Err(GameError::AssetError(
+            "sha256校验和需要启用sha256-checksum特性".to_string()
+        )),
+    }
+}
+
 // 资源元数据
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AssetMetadata {
@@ -75,6 +160,8 @@ pub struct AssetMetadata {
     pub asset_type: AssetType,
     pub size: u64,
     pub checksum: String,
+    #[serde(default)]
+    pub checksum_algorithm: String,
     pub created_at: SystemTime,
     pub modified_at: SystemTime,
     pub dependencies: Vec<String>,
@@ -94,8 +181,9 @@ impl AssetMetadata {
         let asset_type = AssetType::from_extension(extension)
             .unwrap_or(AssetType::Data);
         
-        // 计算文件校验和（简化版）
-        let checksum = Self::calculate_checksum(path)?;
+        // 计算文件校验和
+        let algorithm = ChecksumAlgorithm::preferred();
+        let checksum = calculate_file_checksum(path, algorithm)?;
         
         Ok(Self {
             id,
@@ -103,6 +191,7 @@ impl AssetMetadata {
             asset_type,
             size: metadata.len(),
             checksum,
+            checksum_algorithm: algorithm.name().to_string(),
             created_at: metadata.created().unwrap_or(SystemTime::UNIX_EPOCH),
             modified_at: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
             dependencies: Vec::new(),
@@ -111,17 +200,39 @@ impl AssetMetadata {
         })
     }
     
-    fn calculate_checksum(path: &Path) -> Result<String> {
-        // 简化的校验和计算（实际项目中应使用更好的哈希算法）
-        let data = std::fs::read(path)
-            .map_err(|e| GameError::IOError(format!("读取文件失败: {}", e)))?;
+    // 用记录时的算法重新计算，判断文件内容是否真的变化（修改时间变化不代表内容变化）
+    pub fn content_changed(&self) -> Result<bool> {
+        let algorithm = match ChecksumAlgorithm::from_name(&self.checksum_algorithm) {
+            Some(algorithm) => algorithm,
+            // 旧元数据没有记录算法，无法比较，视为已变化
+            None => return Ok(true),
+        };
         
-        let mut hash = 0u64;
-        for byte in data.iter() {
-            hash = hash.wrapping_mul(31).wrapping_add(*byte as u64);
-        }
+        Ok(calculate_file_checksum(&self.path, algorithm)? != self.checksum)
+    }
+    
+    // 资源包内资源的元数据，路径指向资源包文件
+    pub fn from_bundle(bundle: &AssetBundle, entry: &BundleEntry) -> Self {
+        let extension = Path::new(&entry.id).extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("");
+        let modified_at = std::fs::metadata(bundle.path())
+            .and_then(|metadata| metadata.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH);
         
-        Ok(format!("{:x}", hash))
+        Self {
+            id: entry.id.clone(),
+            path: bundle.path().to_path_buf(),
+            asset_type: AssetType::from_extension(extension).unwrap_or(AssetType::Data),
+            size: entry.length,
+            checksum: entry.checksum.clone(),
+            checksum_algorithm: ChecksumAlgorithm::Xxh3.name().to_string(),
+            created_at: modified_at,
+            modified_at,
+            dependencies: Vec::new(),
+            tags: Vec::new(),
+            properties: HashMap::new(),
+        }
     }
     
     pub fn is_modified_since(&self, time: SystemTime) -> bool {
@@ -146,10 +257,18 @@ pub enum AssetLoadState {
     Failed,
 }
 
+// 资源来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetSource {
+    Loose,         // 散文件
+    Bundle(usize), // 已挂载资源包的序号
+}
+
 // 资源条目
 #[derive(Debug)]
 pub struct AssetEntry {
     pub metadata: AssetMetadata,
+    pub source: AssetSource,
     pub state: AssetLoadState,
     pub data: Option<Vec<u8>>,
     pub handle: Option<ResourceHandle<Vec<u8>>>,
@@ -164,6 +283,7 @@ impl AssetEntry {
     pub fn new(metadata: AssetMetadata) -> Self {
         Self {
             metadata,
+            source: AssetSource::Loose,
             state: AssetLoadState::NotLoaded,
             data: None,
             handle: None,
@@ -202,10 +322,39 @@ pub struct AssetRegistry {
     total_load_time: Arc<RwLock<Duration>>,
     cache_hits: Arc<RwLock<u64>>,
     cache_misses: Arc<RwLock<u64>>,
+    // 从资源包读取的压缩数据量（磁盘字节数, 解压后字节数）
+    bundle_compression: Arc<RwLock<(u64, u64)>>,
+    
+    // 热重载（默认关闭）
+    hot_reload: Option<HotReloadWatcher>,
+    reload_callbacks: ReloadCallbacks,
+    
+    // 后台加载（首次使用时创建工作线程）
+    job_queue: Option<AssetJobQueue>,
+    load_tracker: jobs::LoadTracker,
+    max_concurrent_loads: usize,
+    
+    // 已挂载的资源包
+    bundles: Vec<AssetBundle>,
+}
+
+// 重载回调列表（闭包无法派生Debug）
+#[derive(Default)]
+struct ReloadCallbacks(Vec<AssetReloadCallback>);
+
+impl std::fmt::Debug for ReloadCallbacks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ReloadCallbacks({})", self.0.len())
+    }
 }
 
 impl AssetRegistry {
     pub fn new() -> Self {
+        Self::new_with_cache(1024 * 1024 * 256, CacheEvictionPolicy::LRU) // 256MB cache
+    }
+    
+    // 指定缓存预算（字节）和驱逐策略
+    pub fn new_with_cache(budget: usize, policy: CacheEvictionPolicy) -> Self {
         Self {
             assets: RwLock::new(HashMap::new()),
             base_paths: vec![
@@ -214,12 +363,22 @@ impl AssetRegistry {
                 PathBuf::from("resources"),
             ],
             loader: AssetLoader::new(),
-            cache: AssetCache::new(1024 * 1024 * 256), // 256MB cache
+            cache: AssetCache::with_policy(budget, policy),
             
             total_loads: Arc::new(RwLock::new(0)),
             total_load_time: Arc::new(RwLock::new(Duration::ZERO)),
             cache_hits: Arc::new(RwLock::new(0)),
             cache_misses: Arc::new(RwLock::new(0)),
+            bundle_compression: Arc::new(RwLock::new((0, 0))),
+            
+            hot_reload: None,
+            reload_callbacks: ReloadCallbacks::default(),
+            
+            job_queue: None,
+            load_tracker: jobs::LoadTracker::default(),
+            max_concurrent_loads: 4,
+            
+            bundles: Vec::new(),
         }
     }
     
@@ -232,6 +391,10 @@ impl AssetRegistry {
         }
     }
     
+    pub fn base_paths(&self) -> &[PathBuf] {
+        &self.base_paths
+    }
+    
     // 扫描并注册所有资源
     pub fn scan_assets(&mut self) -> Result<()> {
         info!("开始扫描资源文件...");
@@ -286,12 +449,91 @@ impl AssetRegistry {
         Ok(count)
     }
     
+    // 挂载资源包，已注册的同名散文件优先（之后扫描到的散文件同样会覆盖包内资源）
+    pub fn mount_bundle<P: AsRef<Path>>(&mut self, path: P) -> Result<usize> {
+        let bundle = AssetBundle::open(path.as_ref())?;
+        let bundle_index = self.bundles.len();
+        let mut mounted = 0;
+        
+        {
+            let mut assets = self.assets.write().unwrap();
+            for bundle_entry in bundle.entries() {
+                if assets.contains_key(&bundle_entry.id) {
+                    debug!("散文件覆盖资源包内资源: {}", bundle_entry.id);
+                    continue;
+                }
+                
+                let mut entry = AssetEntry::new(AssetMetadata::from_bundle(&bundle, bundle_entry));
+                entry.source = AssetSource::Bundle(bundle_index);
+                assets.insert(bundle_entry.id.clone(), entry);
+                mounted += 1;
+            }
+        }
+        
+        info!("挂载资源包: {:?} ({} 个资源)", bundle.path(), mounted);
+        self.bundles.push(bundle);
+        Ok(mounted)
+    }
+    
     // 预加载指定资源
     pub fn preload_asset(&mut self, asset_id: &str) -> Result<()> {
         debug!("预加载资源: {}", asset_id);
         self.load_asset_internal(asset_id, false)
     }
     
+    // 按依赖顺序预加载资源：先加载依赖，再加载自身，返回实际的加载顺序
+    pub fn preload_with_dependencies(&mut self, asset_id: &str) -> Result<Vec<String>> {
+        let order = self.resolve_load_order(asset_id)?;
+        
+        for id in &order {
+            if !self.is_asset_loaded(id) {
+                self.preload_asset(id)?;
+            }
+        }
+        
+        debug!("按依赖顺序预加载: {:?}", order);
+        Ok(order)
+    }
+    
+    // 依赖图的拓扑排序（深度优先），遇到环时返回包含完整环路的错误
+    fn resolve_load_order(&self, asset_id: &str) -> Result<Vec<String>> {
+        fn visit(
+            assets: &HashMap<String, AssetEntry>,
+            asset_id: &str,
+            path: &mut Vec<String>,
+            done: &mut HashSet<String>,
+            order: &mut Vec<String>,
+        ) -> Result<()> {
+            if done.contains(asset_id) {
+                return Ok(());
+            }
+            
+            if let Some(start) = path.iter().position(|id| id == asset_id) {
+                let mut cycle = path[start..].to_vec();
+                cycle.push(asset_id.to_string());
+                return Err(GameError::AssetError(format!("检测到循环依赖: {}", cycle.join(" -> "))));
+            }
+            
+            let entry = assets.get(asset_id)
+                .ok_or_else(|| GameError::AssetError(format!("资源不存在: {}", asset_id)))?;
+            
+            path.push(asset_id.to_string());
+            for dependency in &entry.dependencies {
+                visit(assets, dependency, path, done, order)?;
+            }
+            path.pop();
+            
+            done.insert(asset_id.to_string());
+            order.push(asset_id.to_string());
+            Ok(())
+        }
+        
+        let assets = self.assets.read().unwrap();
+        let mut order = Vec::new();
+        visit(&assets, asset_id, &mut Vec::new(), &mut HashSet::new(), &mut order)?;
+        Ok(order)
+    }
+    
     // 异步加载资源
     pub fn load_asset(&mut self, asset_id: &str) -> Result<ResourceHandle<Vec<u8>>> {
         self.load_asset_internal(asset_id, true)?;
@@ -308,6 +550,147 @@ impl AssetRegistry {
         }
     }
     
+    // 设置后台加载的最大并发数，在没有进行中的任务时生效
+    pub fn set_max_concurrent_loads(&mut self, limit: usize) {
+        self.max_concurrent_loads = limit.max(1);
+        
+        let idle = self.job_queue.as_ref().is_none_or(|queue| queue.in_flight() == 0);
+        if idle {
+            self.job_queue = None;
+        } else {
+            warn!("仍有后台加载任务进行中，并发数将在队列空闲后生效");
+        }
+    }
+    
+    // 在后台线程加载资源，通过poll查询状态
+    pub fn load_asset_async(&mut self, asset_id: &str) -> LoadToken {
+        // 队列空闲且并发数已变更时重建工作线程
+        let rebuild = self.job_queue.as_ref().is_none_or(|queue| {
+            queue.in_flight() == 0 && queue.worker_count() != self.max_concurrent_loads
+        });
+        if rebuild {
+            self.job_queue = Some(AssetJobQueue::new(self.max_concurrent_loads));
+        }
+        let queue = self.job_queue.as_mut().expect("加载队列已创建");
+        let token = LoadToken::next();
+        
+        let path = {
+            let mut assets = self.assets.write().unwrap();
+            match assets.get_mut(asset_id) {
+                Some(entry) if entry.is_loaded() || self.cache.contains(asset_id) => {
+                    entry.mark_accessed();
+                    None
+                },
+                // 资源包内资源直接按偏移读取，在下面同步加载
+                Some(entry) if entry.source != AssetSource::Loose => None,
+                Some(entry) => {
+                    entry.state = AssetLoadState::Loading;
+                    entry.load_time = Some(Instant::now());
+                    Some(Ok(entry.metadata.path.clone()))
+                },
+                None => Some(Err(format!("资源不存在: {}", asset_id))),
+            }
+        };
+        
+        let status = match path {
+            None => match self.load_asset_internal(asset_id, false) {
+                Ok(()) => LoadStatus::Loaded,
+                Err(e) => LoadStatus::Failed(e.to_string()),
+            },
+            Some(Ok(path)) => {
+                queue.submit(token, path);
+                LoadStatus::Loading
+            },
+            Some(Err(message)) => LoadStatus::Failed(message),
+        };
+        
+        debug!("后台加载资源: {} ({:?})", asset_id, status);
+        self.load_tracker.insert(token, asset_id.to_string(), status);
+        token
+    }
+    
+    // 查询后台加载状态
+    pub fn poll(&mut self, token: LoadToken) -> LoadStatus {
+        self.process_async_results();
+        self.load_tracker
+            .status(token)
+            .unwrap_or_else(|| LoadStatus::Failed(format!("未知的加载句柄: {}", token.id())))
+    }
+    
+    // 取消后台加载，已在工作线程中的任务照常完成但结果被丢弃；返回句柄是否仍在加载
+    pub fn cancel_async_load(&mut self, token: LoadToken) -> bool {
+        self.process_async_results();
+        let Some(asset_id) = self.load_tracker.cancel(token) else {
+            return false;
+        };
+        
+        if let Some(entry) = self.assets.write().unwrap().get_mut(&asset_id) {
+            if entry.state == AssetLoadState::Loading {
+                entry.state = AssetLoadState::NotLoaded;
+                entry.load_time = None;
+            }
+        }
+        debug!("取消后台加载: {}", asset_id);
+        true
+    }
+    
+    // 所有已提交后台任务的完成比例
+    pub fn async_load_progress(&mut self) -> f32 {
+        self.process_async_results();
+        self.load_tracker.progress()
+    }
+    
+    // 已提交与已完成的后台加载数，以及当前正在加载的资源
+    pub fn load_progress(&mut self) -> QueueProgress {
+        self.process_async_results();
+        self.load_tracker.snapshot()
+    }
+    
+    fn process_async_results(&mut self) {
+        let results = match self.job_queue.as_mut() {
+            Some(queue) => queue.drain_results(),
+            None => return,
+        };
+        
+        for jobs::LoadJobResult { token, result } in results {
+            let asset_id = match self.load_tracker.asset_id(token) {
+                Some(asset_id) => asset_id.to_string(),
+                None => continue,
+            };
+            
+            let mut assets = self.assets.write().unwrap();
+            let entry = assets.get_mut(&asset_id);
+            
+            match result {
+                Ok(data) => {
+                    self.cache.insert(asset_id.clone(), data.clone());
+                    
+                    if let Some(entry) = entry {
+                        if let Some(start_time) = entry.load_time {
+                            *self.total_load_time.write().unwrap() += start_time.elapsed();
+                        }
+                        entry.data = Some(data);
+                        entry.state = AssetLoadState::Loaded;
+                        entry.mark_accessed();
+                    }
+                    link_dependents(&mut assets, &asset_id);
+                    *self.total_loads.write().unwrap() += 1;
+                    
+                    debug!("后台加载完成: {}", asset_id);
+                    self.load_tracker.finish(token, LoadStatus::Loaded);
+                },
+                Err(e) => {
+                    if let Some(entry) = entry {
+                        entry.state = AssetLoadState::Failed;
+                    }
+                    
+                    warn!("后台加载失败 {}: {}", asset_id, e);
+                    self.load_tracker.finish(token, LoadStatus::Failed(e.to_string()));
+                }
+            }
+        }
+    }
+    
     fn load_asset_internal(&mut self, asset_id: &str, create_handle: bool) -> Result<()> {
         let start_time = Instant::now();
         
@@ -327,6 +710,7 @@ impl AssetRegistry {
                     entry.state = AssetLoadState::Loaded;
                     entry.mark_accessed();
                 }
+                link_dependents(&mut assets, asset_id);
             }
             
             return Ok(());
@@ -335,11 +719,11 @@ impl AssetRegistry {
         *self.cache_misses.write().unwrap() += 1;
         
         // 查找资源条目
-        let asset_path = {
+        let (asset_path, source) = {
             let assets = self.assets.read().unwrap();
             let entry = assets.get(asset_id)
                 .ok_or_else(|| GameError::AssetError(format!("资源不存在: {}", asset_id)))?;
-            entry.metadata.path.clone()
+            (entry.metadata.path.clone(), entry.source)
         };
         
         // 标记为加载中
@@ -352,7 +736,21 @@ impl AssetRegistry {
         }
         
         // 加载资源数据
-        let data = match self.loader.load_asset(&asset_path) {
+        let loaded = match source {
+            AssetSource::Loose => self.loader.load_asset(&asset_path),
+            AssetSource::Bundle(index) => match self.bundles.get(index) {
+                Some(bundle) => bundle.read(asset_id).map(|data| {
+                    if let Some(stored) = bundle.entry(asset_id).and_then(|entry| entry.compressed_length) {
+                        let mut compression = self.bundle_compression.write().unwrap();
+                        compression.0 += stored;
+                        compression.1 += data.len() as u64;
+                    }
+                    data
+                }),
+                None => Err(GameError::AssetError(format!("资源包未挂载: {}", asset_id))),
+            },
+        };
+        let data = match loaded {
             Ok(data) => data,
             Err(e) => {
                 // 标记为失败
@@ -386,6 +784,7 @@ impl AssetRegistry {
                 entry.state = AssetLoadState::Loaded;
                 entry.mark_accessed();
             }
+            link_dependents(&mut assets, asset_id);
         }
         
         // 更新统计信息
@@ -412,6 +811,9 @@ impl AssetRegistry {
             debug!("卸载资源: {}", asset_id);
         }
         
+        // 已卸载的资源不再占用其依赖，使依赖可以被清理
+        unlink_dependents(&mut assets, asset_id);
+        
         Ok(())
     }
     
@@ -446,6 +848,116 @@ impl AssetRegistry {
         Ok(())
     }
     
+    // 开启热重载：监听所有资源路径，变更在process_hot_reload中处理
+    pub fn enable_hot_reload(&mut self) -> Result<()> {
+        if self.hot_reload.is_some() {
+            return Ok(());
+        }
+        
+        let watcher = HotReloadWatcher::new(&self.base_paths)?;
+        info!("资源热重载已开启，监听 {} 个目录", watcher.watched_paths().len());
+        self.hot_reload = Some(watcher);
+        Ok(())
+    }
+    
+    pub fn disable_hot_reload(&mut self) {
+        if self.hot_reload.take().is_some() {
+            info!("资源热重载已关闭");
+        }
+    }
+    
+    pub fn is_hot_reload_enabled(&self) -> bool {
+        self.hot_reload.is_some()
+    }
+    
+    // 注册重载回调
+    pub fn on_asset_reloaded<F>(&mut self, callback: F)
+    where
+        F: Fn(&AssetReloadedEvent) + Send + Sync + 'static,
+    {
+        self.reload_callbacks.0.push(Box::new(callback));
+    }
+    
+    // 处理文件变更（每帧调用），返回本次重载的资源ID
+    pub fn process_hot_reload(&mut self) -> Vec<String> {
+        let changed_paths = match self.hot_reload.as_ref() {
+            Some(watcher) => watcher.drain_changed_paths(),
+            None => return Vec::new(),
+        };
+        
+        if changed_paths.is_empty() {
+            return Vec::new();
+        }
+        
+        // 只重载内容确实变化的资源（仅修改时间变化时跳过）
+        let changed_ids: Vec<String> = {
+            let assets = self.assets.read().unwrap();
+            assets.iter()
+                .filter(|(_, entry)| changed_paths.contains(&hot_reload::normalize_path(&entry.metadata.path)))
+                .filter(|(id, entry)| match entry.metadata.content_changed() {
+                    Ok(changed) => changed,
+                    Err(e) => {
+                        warn!("检查资源变更失败 {}: {}", id, e);
+                        false
+                    }
+                })
+                .map(|(id, _)| id.clone())
+                .collect()
+        };
+        
+        // 依赖于变更资源的资源也需要传递重载
+        let mut queue: VecDeque<(String, Option<String>)> = changed_ids
+            .into_iter()
+            .map(|id| (id, None))
+            .collect();
+        let mut visited = HashSet::new();
+        let mut reloaded = Vec::new();
+        
+        while let Some((asset_id, changed_dependency)) = queue.pop_front() {
+            if !visited.insert(asset_id.clone()) {
+                continue;
+            }
+            
+            if let Err(e) = self.reload_asset(&asset_id) {
+                warn!("热重载资源失败 {}: {}", asset_id, e);
+                continue;
+            }
+            
+            let (path, dependents) = {
+                let assets = self.assets.read().unwrap();
+                match assets.get(&asset_id) {
+                    Some(entry) => (entry.metadata.path.clone(), entry.dependents.clone()),
+                    None => continue,
+                }
+            };
+            
+            let root = changed_dependency.clone().unwrap_or_else(|| asset_id.clone());
+            for dependent in dependents {
+                queue.push_back((dependent, Some(root.clone())));
+            }
+            
+            let event = AssetReloadedEvent {
+                asset_id: asset_id.clone(),
+                path,
+                changed_dependency,
+            };
+            for callback in &self.reload_callbacks.0 {
+                callback(&event);
+            }
+            if let Err(e) = EventSystem::dispatch(event) {
+                warn!("分发资源重载事件失败: {}", e);
+            }
+            
+            reloaded.push(asset_id);
+        }
+        
+        if !reloaded.is_empty() {
+            info!("热重载了 {} 个资源", reloaded.len());
+        }
+        
+        reloaded
+    }
+    
     // 检查资源是否已加载
     pub fn is_asset_loaded(&self, asset_id: &str) -> bool {
         let assets = self.assets.read().unwrap();
@@ -500,6 +1012,7 @@ impl AssetRegistry {
         }
         
         for id in to_remove {
+            unlink_dependents(&mut assets, &id);
             if let Some(mut entry) = assets.remove(&id) {
                 entry.data = None;
                 entry.handle = None;
@@ -508,11 +1021,17 @@ impl AssetRegistry {
                 debug!("清理未使用资源: {}", id);
             }
         }
+        drop(assets);
         
         if removed_count > 0 {
             info!("清理了 {} 个未使用的资源", removed_count);
         }
         
+        // 注册表释放的句柄若是最后一个，宽限期过后由资源管理器卸载
+        if let Some(manager) = ResourceManager::try_instance() {
+            manager.collect_released();
+        }
+        
         removed_count
     }
     
@@ -526,15 +1045,25 @@ impl AssetRegistry {
             .filter(|entry| entry.state == AssetLoadState::Failed)
             .count();
         
+        // 合并散文件和资源包中压缩资源的数据量
+        let loader_stats = self.loader.get_stats();
+        let (bundle_compressed, bundle_decompressed) = *self.bundle_compression.read().unwrap();
+        let compressed_bytes = loader_stats.compressed_bytes + bundle_compressed;
+        let decompressed_bytes = loader_stats.decompressed_bytes + bundle_decompressed;
+        
         AssetStats {
             total_assets: assets.len(),
             loaded_assets: loaded_count,
             failed_assets: failed_count,
             cache_size: self.cache.get_memory_usage(),
+            cache_evictions: self.cache.get_stats().evictions,
+            eviction_policy: self.cache.eviction_policy(),
             total_loads: *self.total_loads.read().unwrap(),
             total_load_time: *self.total_load_time.read().unwrap(),
             cache_hits: *self.cache_hits.read().unwrap(),
             cache_misses: *self.cache_misses.read().unwrap(),
+            compressed_bytes,
+            decompressed_bytes,
         }
     }
     
@@ -549,33 +1078,130 @@ impl AssetRegistry {
             .map_err(|e| GameError::SerializationError(format!("导出清单失败: {}", e)))
     }
     
+    // 按导出的清单校验资源完整性，缺失和损坏的资源汇总为一个错误返回
+    pub fn verify_against_manifest(&self, manifest_path: &Path) -> Result<()> {
+        let content = std::fs::read_to_string(manifest_path)
+            .map_err(|e| GameError::AssetError(format!("读取资源清单失败: {:?}: {}", manifest_path, e)))?;
+        let manifest: Vec<AssetMetadata> = serde_json::from_str(&content)
+            .map_err(|e| GameError::AssetError(format!("解析资源清单失败: {:?}: {}", manifest_path, e)))?;
+        
+        let assets = self.assets.read().unwrap();
+        let mut missing = Vec::new();
+        let mut corrupt = Vec::new();
+        
+        for expected in &manifest {
+            let Some(algorithm) = ChecksumAlgorithm::from_name(&expected.checksum_algorithm) else {
+                corrupt.push(format!("{} (未知校验算法: {})", expected.id, expected.checksum_algorithm));
+                continue;
+            };
+            
+            let entry = assets.get(&expected.id);
+            let actual = match entry.map(|entry| entry.source) {
+                // 资源包读取时已按索引校验数据，这里比较索引中的校验和
+                Some(AssetSource::Bundle(index)) => {
+                    let bundle = &self.bundles[index];
+                    match bundle.read(&expected.id) {
+                        Ok(_) if algorithm == ChecksumAlgorithm::Xxh3 => {
+                            bundle.entry(&expected.id).map(|entry| entry.checksum.clone())
+                        },
+                        Ok(_) => None,
+                        Err(e) => {
+                            corrupt.push(format!("{} ({})", expected.id, e));
+                            continue;
+                        }
+                    }
+                },
+                _ => {
+                    // 注册表中没有的资源按清单中的路径查找
+                    let path = entry
+                        .map(|entry| entry.metadata.path.clone())
+                        .unwrap_or_else(|| expected.path.clone());
+                    if !path.is_file() {
+                        missing.push(expected.id.clone());
+                        continue;
+                    }
+                    calculate_file_checksum(&path, algorithm).ok()
+                }
+            };
+            
+            if actual.as_deref() != Some(expected.checksum.as_str()) {
+                corrupt.push(expected.id.clone());
+            }
+        }
+        
+        if missing.is_empty() && corrupt.is_empty() {
+            info!("资源清单校验通过: {} 个资源", manifest.len());
+            return Ok(());
+        }
+        
+        missing.sort();
+        corrupt.sort();
+        error!("资源清单校验失败: 缺失 {} 个, 损坏 {} 个", missing.len(), corrupt.len());
+        Err(GameError::AssetError(format!(
+            "资源清单校验失败: 缺失 {} 个 [{}]; 损坏 {} 个 [{}]",
+            missing.len(), missing.join(", "),
+            corrupt.len(), corrupt.join(", ")
+        )))
+    }
+    
     // 更新资源依赖关系
     pub fn update_dependencies(&mut self, asset_id: &str, dependencies: Vec<String>) {
         let mut assets = self.assets.write().unwrap();
         
-        if let Some(entry) = assets.get_mut(asset_id) {
-            // 移除旧的依赖关系
-            for old_dep in &entry.dependencies {
-                if let Some(dep_entry) = assets.get_mut(old_dep) {
-                    dep_entry.dependents.retain(|dep| dep != asset_id);
-                }
+        // 设置新的依赖关系，同时取出旧的依赖
+        let old_dependencies = match assets.get_mut(asset_id) {
+            Some(entry) => std::mem::replace(&mut entry.dependencies, dependencies.clone()),
+            None => return,
+        };
+        
+        // 移除旧的依赖关系
+        for old_dep in &old_dependencies {
+            if let Some(dep_entry) = assets.get_mut(old_dep) {
+                dep_entry.dependents.retain(|dep| dep != asset_id);
             }
-            
-            // 设置新的依赖关系
-            entry.dependencies = dependencies.clone();
-            
-            // 建立新的依赖关系
-            for dep in dependencies {
-                if let Some(dep_entry) = assets.get_mut(&dep) {
-                    if !dep_entry.dependents.contains(&asset_id.to_string()) {
-                        dep_entry.dependents.push(asset_id.to_string());
-                    }
+        }
+        
+        // 建立新的依赖关系
+        for dep in dependencies {
+            if let Some(dep_entry) = assets.get_mut(&dep) {
+                if !dep_entry.dependents.contains(&asset_id.to_string()) {
+                    dep_entry.dependents.push(asset_id.to_string());
                 }
             }
         }
     }
 }
 
+// 资源加载后重新登记到其依赖的dependents中
+fn link_dependents(assets: &mut HashMap<String, AssetEntry>, asset_id: &str) {
+    let dependencies = match assets.get(asset_id) {
+        Some(entry) => entry.dependencies.clone(),
+        None => return,
+    };
+    
+    for dep in dependencies {
+        if let Some(dep_entry) = assets.get_mut(&dep) {
+            if !dep_entry.dependents.iter().any(|id| id == asset_id) {
+                dep_entry.dependents.push(asset_id.to_string());
+            }
+        }
+    }
+}
+
+// 资源卸载或移除后，从其依赖的dependents中删除
+fn unlink_dependents(assets: &mut HashMap<String, AssetEntry>, asset_id: &str) {
+    let dependencies = match assets.get(asset_id) {
+        Some(entry) => entry.dependencies.clone(),
+        None => return,
+    };
+    
+    for dep in dependencies {
+        if let Some(dep_entry) = assets.get_mut(&dep) {
+            dep_entry.dependents.retain(|id| id != asset_id);
+        }
+    }
+}
+
 // 统计信息结构
 #[derive(Debug, Clone)]
 pub struct AssetStats {
@@ -583,13 +1209,26 @@ pub struct AssetStats {
     pub loaded_assets: usize,
     pub failed_assets: usize,
     pub cache_size: usize,
+    pub cache_evictions: u64,
+    pub eviction_policy: CacheEvictionPolicy,
     pub total_loads: u64,
     pub total_load_time: Duration,
     pub cache_hits: u64,
     pub cache_misses: u64,
+    pub compressed_bytes: u64,
+    pub decompressed_bytes: u64,
 }
 
 impl AssetStats {
+    // 压缩比（压缩后/原始），没有加载过压缩资源时为1.0
+    pub fn compression_ratio(&self) -> f64 {
+        if self.decompressed_bytes == 0 {
+            1.0
+        } else {
+            self.compressed_bytes as f64 / self.decompressed_bytes as f64
+        }
+    }
+    
     pub fn cache_hit_rate(&self) -> f64 {
         if self.cache_hits + self.cache_misses == 0 {
             0.0
@@ -607,18 +1246,19 @@ impl AssetStats {
     }
 }
 
-// 全局资源管理器实例
-static mut ASSET_REGISTRY: Option<AssetRegistry> = None;
-static INIT: std::sync::Once = std::sync::Once::new();
+// 全局资源注册表的访问守卫
+pub type AssetRegistryGuard = ServiceGuard<AssetRegistry>;
 
 impl AssetRegistry {
-    pub fn instance() -> &'static mut AssetRegistry {
-        unsafe {
-            INIT.call_once(|| {
-                ASSET_REGISTRY = Some(AssetRegistry::new());
-            });
-            ASSET_REGISTRY.as_mut().unwrap()
+    // 首次访问时创建全局实例
+    pub fn instance() -> Result<AssetRegistryGuard> {
+        let mut registry = Services::exclusive::<AssetRegistry>()
+            .lock()
+            .map_err(|_| GameError::AssetError("资源注册表锁已损坏".to_string()))?;
+        if registry.is_none() {
+            *registry = Some(AssetRegistry::new());
         }
+        Ok(ServiceGuard::new(registry).expect("资源注册表刚刚创建"))
     }
 }
 
@@ -654,4 +1294,333 @@ mod tests {
         assert_eq!(metadata.asset_type, AssetType::Texture);
         assert_eq!(metadata.size, 13);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_checksum_detects_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let first = temp_dir.path().join("first.json");
+        let second = temp_dir.path().join("second.json");
+        let copy = temp_dir.path().join("copy.json");
+        
+        // 超过一个分块，覆盖分块读取路径
+        let content: Vec<u8> = (0..CHECKSUM_CHUNK_SIZE * 2 + 7).map(|i| (i % 251) as u8).collect();
+        let mut altered = content.clone();
+        altered[CHECKSUM_CHUNK_SIZE + 1] ^= 0xFF;
+        fs::write(&first, &content).unwrap();
+        fs::write(&copy, &content).unwrap();
+        fs::write(&second, &altered).unwrap();
+        
+        let checksum = |path: &Path| AssetMetadata::from_path(path, "id".to_string()).unwrap().checksum;
+        assert_eq!(checksum(&first), checksum(&copy));
+        assert_ne!(checksum(&first), checksum(&second));
+        
+        let metadata = AssetMetadata::from_path(&first, "first".to_string()).unwrap();
+        assert_eq!(metadata.checksum_algorithm, ChecksumAlgorithm::preferred().name());
+        assert!(!metadata.content_changed().unwrap());
+        
+        fs::write(&first, &altered).unwrap();
+        assert!(metadata.content_changed().unwrap());
+    }
+    
+    #[test]
+    fn test_hot_reload_triggers_callback() {
+        EventSystem::init().unwrap();
+        
+        let temp_dir = TempDir::new().unwrap();
+        let tiles = temp_dir.path().join("tiles.png");
+        let route = temp_dir.path().join("route1.json");
+        fs::write(&tiles, b"tiles v1").unwrap();
+        fs::write(&route, b"{\"tileset\": \"tiles.png\"}").unwrap();
+        
+        let mut registry = AssetRegistry::new();
+        registry.base_paths = vec![temp_dir.path().to_path_buf()];
+        registry.scan_assets().unwrap();
+        registry.update_dependencies("route1.json", vec!["tiles.png".to_string()]);
+        
+        let reloaded = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = reloaded.clone();
+        registry.on_asset_reloaded(move |event| {
+            sink.lock().unwrap().push((event.asset_id.clone(), event.changed_dependency.clone()));
+        });
+        registry.enable_hot_reload().unwrap();
+        
+        fs::write(&tiles, b"tiles v2").unwrap();
+        
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while reloaded.lock().unwrap().len() < 2 && Instant::now() < deadline {
+            registry.process_hot_reload();
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        
+        assert_eq!(*reloaded.lock().unwrap(), vec![
+            ("tiles.png".to_string(), None),
+            ("route1.json".to_string(), Some("tiles.png".to_string())),
+        ]);
+        assert!(!registry.get_asset_metadata("tiles.png").unwrap().content_changed().unwrap());
+    }
+    
+    #[test]
+    fn test_async_loads_complete() {
+        let temp_dir = TempDir::new().unwrap();
+        for i in 0..5 {
+            fs::write(temp_dir.path().join(format!("route{}.json", i)), format!("{{\"id\": {}}}", i)).unwrap();
+        }
+        
+        let mut registry = AssetRegistry::new();
+        registry.base_paths = vec![temp_dir.path().to_path_buf()];
+        registry.scan_assets().unwrap();
+        registry.set_max_concurrent_loads(2);
+        
+        let tokens: Vec<LoadToken> = (0..5)
+            .map(|i| registry.load_asset_async(&format!("route{}.json", i)))
+            .collect();
+        let missing = registry.load_asset_async("missing.json");
+        assert_eq!(registry.job_queue.as_ref().unwrap().worker_count(), 2);
+        
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while tokens.iter().any(|token| registry.poll(*token) == LoadStatus::Loading) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        
+        for (i, token) in tokens.iter().enumerate() {
+            assert_eq!(registry.poll(*token), LoadStatus::Loaded);
+            assert!(registry.is_asset_loaded(&format!("route{}.json", i)));
+        }
+        assert!(matches!(registry.poll(missing), LoadStatus::Failed(_)));
+        assert_eq!(registry.async_load_progress(), 1.0);
+        
+        // 已加载的资源直接返回完成
+        let again = registry.load_asset_async("route0.json");
+        assert_eq!(registry.poll(again), LoadStatus::Loaded);
+        
+        // 重建工作线程后句柄不会与之前的重复
+        registry.set_max_concurrent_loads(3);
+        let rebuilt = registry.load_asset_async("route1.json");
+        assert_eq!(registry.job_queue.as_ref().unwrap().worker_count(), 3);
+        assert!(!tokens.contains(&rebuilt) && rebuilt != again);
+        assert_eq!(registry.poll(tokens[0]), LoadStatus::Loaded);
+    }
+    
+    #[test]
+    fn test_load_tracker_prunes_finished_and_cancelled() {
+        let mut tracker = jobs::LoadTracker::default();
+        let (first, second) = (LoadToken::next(), LoadToken::next());
+        tracker.insert(first, "a.png".to_string(), LoadStatus::Loading);
+        tracker.insert(second, "b.png".to_string(), LoadStatus::Loading);
+        
+        // 取消后不再记录，之后到达的结果被忽略
+        assert_eq!(tracker.cancel(first).as_deref(), Some("a.png"));
+        assert_eq!(tracker.asset_id(first), None);
+        tracker.finish(first, LoadStatus::Loaded);
+        assert_eq!(tracker.status(first), None);
+        assert_eq!(tracker.snapshot().current.as_deref(), Some("b.png"));
+        
+        tracker.finish(second, LoadStatus::Loaded);
+        assert_eq!(tracker.status(second), Some(LoadStatus::Loaded));
+        assert_eq!(tracker.asset_id(second), None);
+        assert_eq!(tracker.progress(), 1.0);
+        
+        // 已结束的记录有上限
+        for _ in 0..jobs::FINISHED_HISTORY {
+            let token = LoadToken::next();
+            tracker.insert(token, "c.png".to_string(), LoadStatus::Loading);
+            tracker.finish(token, LoadStatus::Loaded);
+        }
+        assert_eq!(tracker.status(second), None);
+        assert_eq!(tracker.snapshot().total, 1);
+    }
+    
+    #[test]
+    fn test_bundle_round_trip() {
+        let source_dir = TempDir::new().unwrap();
+        fs::create_dir_all(source_dir.path().join("maps")).unwrap();
+        fs::write(source_dir.path().join("maps/route1.json"), b"{\"name\": \"route1\"}").unwrap();
+        fs::write(source_dir.path().join("player.png"), b"player sprite").unwrap();
+        fs::write(source_dir.path().join("theme.ogg"), b"theme music").unwrap();
+        
+        let out_dir = TempDir::new().unwrap();
+        let bundle_path = out_dir.path().join("game.pak");
+        let bundle = AssetBundle::pack(source_dir.path(), &bundle_path).unwrap();
+        assert_eq!(bundle.len(), 3);
+        
+        let reopened = AssetBundle::open(&bundle_path).unwrap();
+        assert_eq!(reopened.read("player.png").unwrap(), b"player sprite");
+        
+        // 散文件与包内资源同名时，散文件优先
+        let loose_dir = TempDir::new().unwrap();
+        fs::write(loose_dir.path().join("theme.ogg"), b"modded theme").unwrap();
+        
+        let mut registry = AssetRegistry::new();
+        registry.base_paths = vec![loose_dir.path().to_path_buf()];
+        registry.scan_assets().unwrap();
+        assert_eq!(registry.mount_bundle(&bundle_path).unwrap(), 2);
+        
+        registry.preload_asset("maps/route1.json").unwrap();
+        registry.preload_asset("theme.ogg").unwrap();
+        
+        let assets = registry.assets.read().unwrap();
+        assert_eq!(assets["maps/route1.json"].source, AssetSource::Bundle(0));
+        assert_eq!(assets["maps/route1.json"].data.as_deref(), Some(&b"{\"name\": \"route1\"}"[..]));
+        assert_eq!(assets["theme.ogg"].source, AssetSource::Loose);
+        assert_eq!(assets["theme.ogg"].data.as_deref(), Some(&b"modded theme"[..]));
+    }
+    
+    #[test]
+    fn test_bundle_rejects_oversized_index_len() {
+        let out_dir = TempDir::new().unwrap();
+        let bundle_path = out_dir.path().join("broken.pak");
+
+        // 文件头声明的索引长度远大于文件本身
+        let mut bytes = b"PPAK".to_vec();
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        bytes.extend_from_slice(b"[]");
+        fs::write(&bundle_path, &bytes).unwrap();
+
+        assert!(matches!(AssetBundle::open(&bundle_path), Err(GameError::AssetError(_))));
+    }
+
+    #[test]
+    fn test_compressed_bundle_round_trip() {
+        let source_dir = TempDir::new().unwrap();
+        let dialog = "{\"speaker\": \"Nurse Joy\", \"text\": \"We hope to see you again!\"}\n".repeat(64);
+        let sprite = vec![0x89u8, 0x50, 0x4E, 0x47, 0, 0, 0, 0].repeat(64);
+        fs::write(source_dir.path().join("dialog.json"), &dialog).unwrap();
+        fs::write(source_dir.path().join("nurse.png"), &sprite).unwrap();
+        
+        let out_dir = TempDir::new().unwrap();
+        let bundle_path = out_dir.path().join("game.pak");
+        let bundle = AssetBundle::pack_with_compression(source_dir.path(), &bundle_path, true).unwrap();
+        
+        // 文本资源压缩存储，图片保持原样
+        assert!(bundle.entry("dialog.json").unwrap().is_compressed());
+        assert!(!bundle.entry("nurse.png").unwrap().is_compressed());
+        assert_eq!(bundle.entry("dialog.json").unwrap().length, dialog.len() as u64);
+        
+        let mut registry = AssetRegistry::new();
+        registry.base_paths = Vec::new();
+        registry.mount_bundle(&bundle_path).unwrap();
+        registry.preload_asset("dialog.json").unwrap();
+        registry.preload_asset("nurse.png").unwrap();
+        
+        {
+            let assets = registry.assets.read().unwrap();
+            assert_eq!(assets["dialog.json"].data.as_deref(), Some(dialog.as_bytes()));
+            assert_eq!(assets["nurse.png"].data.as_deref(), Some(&sprite[..]));
+        }
+        
+        let stats = registry.get_stats();
+        assert_eq!(stats.decompressed_bytes, dialog.len() as u64);
+        assert!(stats.compression_ratio() < 1.0);
+    }
+    
+    #[test]
+    fn test_unloaded_dependent_releases_dependency() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut registry = registry_with_files(&temp_dir, &["tiles.png", "route1.json"]);
+        registry.update_dependencies("route1.json", vec!["tiles.png".to_string()]);
+        registry.preload_with_dependencies("route1.json").unwrap();
+        
+        // 依赖方先被清理，依赖在下一轮清理中释放
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(registry.cleanup_unused_assets(Duration::ZERO), 1);
+        assert!(registry.get_asset_metadata("tiles.png").is_some());
+        assert!(registry.get_asset_metadata("route1.json").is_none());
+        assert_eq!(registry.cleanup_unused_assets(Duration::ZERO), 1);
+        assert!(registry.get_asset_metadata("tiles.png").is_none());
+        
+        // 重新扫描后卸载依赖方，依赖随后可被清理
+        let mut registry = registry_with_files(&temp_dir, &["tiles.png", "route1.json"]);
+        registry.update_dependencies("route1.json", vec!["tiles.png".to_string()]);
+        registry.preload_with_dependencies("route1.json").unwrap();
+        registry.unload_asset("route1.json").unwrap();
+        assert!(registry.assets.read().unwrap()["tiles.png"].dependents.is_empty());
+        
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(registry.cleanup_unused_assets(Duration::ZERO), 1);
+        assert!(registry.get_asset_metadata("tiles.png").is_none());
+    }
+    
+    #[test]
+    fn test_verify_against_manifest() {
+        let asset_dir = TempDir::new().unwrap();
+        let registry = registry_with_files(&asset_dir, &["route1.json", "route2.json", "oak.png"]);
+        
+        let manifest_dir = TempDir::new().unwrap();
+        let manifest_path = manifest_dir.path().join("manifest.json");
+        fs::write(&manifest_path, registry.export_manifest().unwrap()).unwrap();
+        registry.verify_against_manifest(&manifest_path).unwrap();
+        
+        // 删除一个文件、篡改一个文件，应在同一个错误中报告
+        fs::remove_file(asset_dir.path().join("route2.json")).unwrap();
+        fs::write(asset_dir.path().join("oak.png"), b"tampered").unwrap();
+        
+        let message = match registry.verify_against_manifest(&manifest_path) {
+            Err(GameError::AssetError(message)) => message,
+            other => panic!("应返回资源错误: {:?}", other),
+        };
+        assert!(message.contains("缺失 1 个 [route2.json]"));
+        assert!(message.contains("损坏 1 个 [oak.png]"));
+    }
+    
+    fn registry_with_files(dir: &TempDir, files: &[&str]) -> AssetRegistry {
+        for file in files {
+            fs::write(dir.path().join(file), file.as_bytes()).unwrap();
+        }
+        
+        let mut registry = AssetRegistry::new();
+        registry.base_paths = vec![dir.path().to_path_buf()];
+        registry.scan_assets().unwrap();
+        registry
+    }
+    
+    #[test]
+    fn test_preload_with_dependencies_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut registry = registry_with_files(&temp_dir, &["route1.tmx", "tiles.png", "palette.json", "npc.png"]);
+        registry.update_dependencies("route1.tmx", vec!["tiles.png".to_string(), "npc.png".to_string()]);
+        registry.update_dependencies("tiles.png", vec!["palette.json".to_string()]);
+        registry.update_dependencies("npc.png", vec!["palette.json".to_string()]);
+        
+        let order = registry.preload_with_dependencies("route1.tmx").unwrap();
+        assert_eq!(order, vec!["palette.json", "tiles.png", "npc.png", "route1.tmx"]);
+        assert!(order.iter().all(|id| registry.is_asset_loaded(id)));
+    }
+    
+    #[test]
+    fn test_preload_with_dependencies_detects_cycle() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut registry = registry_with_files(&temp_dir, &["a.json", "b.json", "c.json"]);
+        registry.update_dependencies("a.json", vec!["b.json".to_string()]);
+        registry.update_dependencies("b.json", vec!["c.json".to_string()]);
+        registry.update_dependencies("c.json", vec!["b.json".to_string()]);
+        
+        let error = registry.preload_with_dependencies("a.json").unwrap_err();
+        assert!(error.to_string().contains("b.json -> c.json -> b.json"), "{}", error);
+        assert!(!registry.is_asset_loaded("a.json"));
+    }
+    
+    #[test]
+    fn test_registry_cache_budget_evicts_lru() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["a.bin", "b.bin", "c.bin"] {
+            fs::write(temp_dir.path().join(name), vec![7u8; 100]).unwrap();
+        }
+        
+        let mut registry = AssetRegistry::new_with_cache(250, CacheEvictionPolicy::LRU);
+        registry.base_paths = vec![temp_dir.path().to_path_buf()];
+        registry.scan_assets().unwrap();
+        
+        registry.preload_asset("a.bin").unwrap();
+        registry.preload_asset("b.bin").unwrap();
+        registry.preload_asset("c.bin").unwrap();
+        
+        assert!(!registry.cache.contains("a.bin"));
+        assert!(registry.cache.contains("c.bin"));
+        
+        let stats = registry.get_stats();
+        assert_eq!(stats.eviction_policy, CacheEvictionPolicy::LRU);
+        assert_eq!(stats.cache_evictions, 1);
+        assert!(stats.cache_size <= 250);
+    }
+}
@@ -3,7 +3,7 @@
 // 设计原则：设备枚举、音频流管理、延迟优化、错误恢复
 
 use crate::core::{GameError, Result};
-use crate::audio::{AudioSystemConfig, AudioChannels, AudioBitDepth, ThreadPriority};
+use crate::audio::{AudioSystemConfig, AudioChannels, AudioBitDepth, ThreadPriority, ChannelLayout};
 use serde::{Deserialize, Serialize};
 
 // 音频传输方式
@@ -150,6 +150,12 @@ pub struct AudioManager {
     // 活跃音频实例
     active_sounds: HashMap<u64, ActiveSound>,
     
+    // 流式播放的音乐及其采样格式，采样由AudioSystem按需补充
+    music_streams: HashMap<String, MusicStreamFormat>,
+    
+    // 音乐声道增益（淡入淡出、交叉淡化）
+    music: MusicMixer,
+    
     // 主音量控制
     master_volume: f32,
 }
@@ -163,6 +169,144 @@ struct QueuedAudio {
     queued_at: Instant,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MusicStreamFormat {
+    pub sample_rate: u32,
+    pub channels: ChannelLayout,
+}
+
+// 单个音乐声道的增益渐变
+#[derive(Debug, Clone)]
+struct MusicChannel {
+    gain: f32,
+    start_gain: f32,
+    rising: bool,
+    duration: Duration,
+    elapsed: Duration,
+}
+
+impl MusicChannel {
+    fn ramp(start_gain: f32, rising: bool, duration: Duration) -> Self {
+        let mut channel = Self {
+            gain: start_gain,
+            start_gain,
+            rising,
+            duration,
+            elapsed: Duration::ZERO,
+        };
+        channel.advance(Duration::ZERO);
+        channel
+    }
+    
+    // 与PlaylistCrossfade相同的等功率曲线，淡入淡出同时进行时中点没有音量凹陷
+    fn advance(&mut self, delta_time: Duration) {
+        self.elapsed = (self.elapsed + delta_time).min(self.duration);
+        let progress = if self.duration.is_zero() {
+            1.0
+        } else {
+            self.elapsed.as_secs_f32() / self.duration.as_secs_f32()
+        };
+        let curve = progress * std::f32::consts::FRAC_PI_2;
+        
+        self.gain = if self.rising {
+            self.start_gain + (1.0 - self.start_gain) * curve.sin()
+        } else {
+            self.start_gain * curve.cos()
+        };
+    }
+    
+    fn is_silent(&self) -> bool {
+        !self.rising && self.elapsed >= self.duration
+    }
+}
+
+// 音乐混音：每帧推进各轨道增益，交叉淡化时旧轨道淡出、新轨道同时淡入
+#[derive(Debug, Clone, Default)]
+pub struct MusicMixer {
+    volume: f32,
+    current_track: Option<String>,
+    channels: HashMap<String, MusicChannel>,
+}
+
+impl MusicMixer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    // 直接播放，其他轨道立即静音
+    pub fn play(&mut self, track_id: &str, volume: f32, fade_in: Option<Duration>) {
+        self.volume = volume;
+        self.channels.clear();
+        self.channels.insert(track_id.to_string(), MusicChannel::ramp(0.0, true, fade_in.unwrap_or_default()));
+        self.current_track = Some(track_id.to_string());
+    }
+    
+    // 旧轨道从当前增益淡出，新轨道从当前增益淡入；同一轨道不做任何事
+    pub fn crossfade(&mut self, from_track: &str, to_track: &str, volume: f32, duration: Duration) {
+        if from_track == to_track && self.current_track.as_deref() == Some(to_track) {
+            return;
+        }
+        
+        self.volume = volume;
+        if let Some(from) = self.channels.get_mut(from_track) {
+            *from = MusicChannel::ramp(from.gain, false, duration);
+        }
+        
+        let start_gain = self.channels.get(to_track).map_or(0.0, |channel| channel.gain);
+        self.channels.insert(to_track.to_string(), MusicChannel::ramp(start_gain, true, duration));
+        self.current_track = Some(to_track.to_string());
+    }
+    
+    pub fn stop(&mut self, fade_out: Option<Duration>) {
+        match fade_out {
+            Some(duration) => {
+                for channel in self.channels.values_mut() {
+                    *channel = MusicChannel::ramp(channel.gain, false, duration);
+                }
+            },
+            None => self.channels.clear(),
+        }
+        self.current_track = None;
+    }
+    
+    // 推进一帧，淡出完成的轨道被移除
+    pub fn update(&mut self, delta_time: Duration) {
+        for channel in self.channels.values_mut() {
+            channel.advance(delta_time);
+        }
+        
+        self.channels.retain(|track_id, channel| {
+            if channel.is_silent() {
+                debug!("音乐 {} 淡出完成", track_id);
+                false
+            } else {
+                true
+            }
+        });
+    }
+    
+    pub fn current_track(&self) -> Option<&str> {
+        self.current_track.as_deref()
+    }
+    
+    pub fn is_playing(&self, track_id: &str) -> bool {
+        self.channels.contains_key(track_id)
+    }
+    
+    // 轨道当前的输出增益（含音乐音量），未在播放时为0
+    pub fn gain(&self, track_id: &str) -> f32 {
+        self.channels.get(track_id).map_or(0.0, |channel| channel.gain * self.volume)
+    }
+    
+    // 按当前增益把轨道的采样混入输出缓冲区
+    pub fn mix_into(&self, track_id: &str, source: &AudioBuffer, output: &mut AudioBuffer) {
+        let gain = self.gain(track_id);
+        if gain > 0.0 {
+            output.mix_in(source, gain);
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct ActiveSound {
     instance_id: u64,
@@ -228,6 +372,8 @@ impl AudioManager {
             callback_times: VecDeque::with_capacity(60),
             
             active_sounds: HashMap::new(),
+            music_streams: HashMap::new(),
+            music: MusicMixer::new(),
             master_volume: 1.0,
         };
         
@@ -407,6 +553,18 @@ impl AudioManager {
         Ok(())
     }
     
+    // 注册流式音乐，采样率与输出设备不同时播放时需要重采样
+    pub fn register_music_stream(&mut self, track_id: &str, format: MusicStreamFormat) {
+        if format.sample_rate != self.config.sample_rate {
+            debug!("流式音乐 {} 采样率 {} 将重采样到 {}", track_id, format.sample_rate, self.config.sample_rate);
+        }
+        self.music_streams.insert(track_id.to_string(), format);
+    }
+    
+    pub fn music_stream_format(&self, track_id: &str) -> Option<MusicStreamFormat> {
+        self.music_streams.get(track_id).copied()
+    }
+    
     // 播放音乐
     pub fn play_music(
         &mut self,
@@ -415,14 +573,27 @@ impl AudioManager {
         loop_music: bool,
         fade_in: Option<Duration>,
     ) -> Result<()> {
-        // TODO: 实现音乐播放逻辑
+        self.music.play(track_id, volume, fade_in);
         info!("播放音乐: {} (音量: {}, 循环: {})", track_id, volume, loop_music);
         Ok(())
     }
     
+    // 交叉淡化音乐
+    pub fn crossfade_music(
+        &mut self,
+        from_track: &str,
+        to_track: &str,
+        volume: f32,
+        duration: Duration,
+    ) -> Result<()> {
+        self.music.crossfade(from_track, to_track, volume, duration);
+        info!("交叉淡化音乐: {} -> {} (音量: {}, 时长: {:?})", from_track, to_track, volume, duration);
+        Ok(())
+    }
+    
     // 停止音乐
     pub fn stop_music(&mut self, fade_out: Option<Duration>) -> Result<()> {
-        // TODO: 实现音乐停止逻辑
+        self.music.stop(fade_out);
         info!("停止音乐 (淡出: {:?})", fade_out);
         Ok(())
     }
@@ -466,11 +637,24 @@ impl AudioManager {
         Ok(())
     }
     
+    // 音乐轨道当前的输出增益
+    pub fn music_gain(&self, track_id: &str) -> f32 {
+        self.music.gain(track_id)
+    }
+    
+    // 把一帧音乐采样按当前增益混入混音缓冲区
+    pub fn mix_music(&mut self, track_id: &str, samples: &AudioBuffer) {
+        self.music.mix_into(track_id, samples, &mut self.mix_buffer);
+    }
+    
     // 更新音频管理器状态
     pub fn update(&mut self, delta_time: Duration) -> Result<()> {
         // 更新淡入淡出效果
         self.update_fading_sounds();
         
+        // 推进音乐淡入淡出与交叉淡化
+        self.music.update(delta_time);
+        
         // 移除已停止的音频
         self.cleanup_stopped_sounds();
         
@@ -628,6 +812,7 @@ impl AudioManager {
         
         // 清理所有音频
         self.active_sounds.clear();
+        self.music_streams.clear();
         
         if let Ok(mut queue) = self.playback_queue.lock() {
             queue.clear();
@@ -704,6 +889,71 @@ mod tests {
         assert!((buffer1.data[0] - 0.8).abs() < 0.001);
     }
     
+    #[test]
+    fn test_music_crossfade_gain_curve() {
+        let mut mixer = MusicMixer::new();
+        mixer.play("route_1", 0.8, None);
+        assert!((mixer.gain("route_1") - 0.8).abs() < 0.001);
+        
+        mixer.crossfade("route_1", "wild_battle", 0.8, Duration::from_secs(1));
+        assert!((mixer.gain("route_1") - 0.8).abs() < 0.001);
+        assert_eq!(mixer.gain("wild_battle"), 0.0);
+        
+        // 按帧推进：旧轨道单调下降，新轨道单调上升，中途两者都不为0
+        let mut last = (mixer.gain("route_1"), mixer.gain("wild_battle"));
+        for frame in 1..10 {
+            mixer.update(Duration::from_millis(100));
+            let (outgoing, incoming) = (mixer.gain("route_1"), mixer.gain("wild_battle"));
+            assert!(outgoing < last.0 && incoming > last.1, "第{}帧增益未按曲线变化", frame);
+            assert!(outgoing > 0.0 && incoming > 0.0);
+            last = (outgoing, incoming);
+        }
+        
+        // 中点处等功率，总功率不出现凹陷
+        let mut mixer_mid = MusicMixer::new();
+        mixer_mid.play("a", 1.0, None);
+        mixer_mid.crossfade("a", "b", 1.0, Duration::from_secs(1));
+        mixer_mid.update(Duration::from_millis(500));
+        let power = mixer_mid.gain("a").powi(2) + mixer_mid.gain("b").powi(2);
+        assert!((power - 1.0).abs() < 0.001);
+        
+        // 结束后旧轨道被移除，新轨道保持满音量
+        mixer.update(Duration::from_millis(100));
+        assert!(!mixer.is_playing("route_1"));
+        assert!((mixer.gain("wild_battle") - 0.8).abs() < 0.001);
+        assert_eq!(mixer.current_track(), Some("wild_battle"));
+        
+        // 增益作用到混入的采样上
+        let mut samples = AudioBuffer::new(2, 44100, 4);
+        samples.data.iter_mut().for_each(|s| *s = 0.5);
+        let mut output = AudioBuffer::new(2, 44100, 4);
+        mixer.mix_into("wild_battle", &samples, &mut output);
+        mixer.mix_into("route_1", &samples, &mut output);
+        assert!((output.data[0] - 0.4).abs() < 0.001);
+    }
+    
+    #[test]
+    fn test_music_fade_in_and_stop() {
+        let mut mixer = MusicMixer::new();
+        
+        // 没有旧轨道时的交叉淡化就是普通淡入
+        mixer.crossfade("", "main_theme", 1.0, Duration::from_secs(1));
+        mixer.update(Duration::from_millis(500));
+        assert!(mixer.gain("main_theme") > 0.0 && mixer.gain("main_theme") < 1.0);
+        
+        // 请求同一轨道不会重置淡入进度
+        let before = mixer.gain("main_theme");
+        mixer.crossfade("main_theme", "main_theme", 1.0, Duration::from_secs(1));
+        assert_eq!(mixer.gain("main_theme"), before);
+        
+        mixer.stop(Some(Duration::from_millis(200)));
+        mixer.update(Duration::from_millis(100));
+        assert!(mixer.gain("main_theme") > 0.0 && mixer.gain("main_theme") < before);
+        mixer.update(Duration::from_millis(100));
+        assert!(!mixer.is_playing("main_theme"));
+        assert_eq!(mixer.current_track(), None);
+    }
+    
     #[test]
     fn test_sample_rate_conversion() {
         let input = vec![0.0, 1.0, 0.0, -1.0];
@@ -7,16 +7,18 @@ pub mod sound;
 pub mod music;
 
 // 重新导出主要类型
-pub use manager::{AudioManager, AudioDevice, DeviceStats, DeliveryMethod};
+pub use manager::{AudioManager, AudioDevice, DeviceStats, DeliveryMethod, MusicStreamFormat};
 pub use sound::{SoundBuffer, SoundInstance, SampleFormat, ChannelLayout, SoundEffect, SoundEffectType};
-pub use music::{MusicTrack, MusicCategory, MoodTag, GameContext, PlaylistManager};
+pub use music::{MusicTrack, MusicCategory, MoodTag, GameContext, PlaylistManager, MusicStream};
 
 use crate::core::{GameError, Result};
 use crate::core::resource_manager::{ResourceManager, ResourceHandle};
 use crate::core::event_system::{Event, EventSystem};
+use crate::core::services::{ServiceGuard, Services};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
+use std::sync::MutexGuard;
 use std::time::Duration;
 use log::{info, debug, warn, error};
 
@@ -45,6 +47,7 @@ pub struct AudioSystemConfig {
     
     // 性能配置
     pub max_simultaneous_sounds: u32,
+    pub voice_steal_policy: VoiceStealPolicy,
     pub audio_thread_priority: ThreadPriority,
     pub enable_audio_streaming: bool,
     pub streaming_buffer_size: u32,
@@ -65,6 +68,15 @@ pub enum AudioBitDepth {
     Bit32 = 32,
 }
 
+// 达到同时播放上限时的抢占策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VoiceStealPolicy {
+    Disabled,       // 不抢占，直接报错
+    Oldest,         // 抢占最早开始播放的实例
+    Quietest,       // 抢占音量最低的实例
+    LowestPriority, // 抢占分类优先级最低的实例
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ThreadPriority {
     Low,
@@ -95,6 +107,7 @@ impl Default for AudioSystemConfig {
             rolloff_factor: 1.0,
             
             max_simultaneous_sounds: 32,
+            voice_steal_policy: VoiceStealPolicy::Oldest,
             audio_thread_priority: ThreadPriority::High,
             enable_audio_streaming: true,
             streaming_buffer_size: 4096,
@@ -124,6 +137,21 @@ pub enum AudioCategory {
     Battle,         // 战斗音效
 }
 
+impl AudioCategory {
+    // 抢占优先级，数值越高越不容易被抢占
+    pub fn steal_priority(&self) -> u8 {
+        match self {
+            AudioCategory::Music => 6,
+            AudioCategory::Voice => 5,
+            AudioCategory::UI => 4,
+            AudioCategory::Battle => 3,
+            AudioCategory::Pokemon => 2,
+            AudioCategory::SFX => 1,
+            AudioCategory::Ambient => 0,
+        }
+    }
+}
+
 // 音频状态
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AudioState {
@@ -242,6 +270,37 @@ pub struct AudioStats {
     pub sample_rate: u32,
     pub latency_ms: f64,
     pub channels_used: u32,
+    pub streaming_tracks: u32,
+    pub streaming_memory_saved: u64,
+}
+
+// 分类音量渐变
+#[derive(Debug, Clone)]
+struct CategoryFade {
+    start_volume: f32,
+    target_volume: f32,
+    duration: Duration,
+    elapsed: Duration,
+}
+
+impl CategoryFade {
+    fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+    
+    fn current_volume(&self) -> f32 {
+        if self.is_finished() {
+            return self.target_volume;
+        }
+        self.start_volume + (self.target_volume - self.start_volume) * self.progress()
+    }
+    
+    fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
 }
 
 // 音频系统
@@ -260,12 +319,15 @@ pub struct AudioSystem {
     // 音频资源
     sound_buffers: HashMap<String, ResourceHandle<SoundBuffer>>,
     music_tracks: HashMap<String, ResourceHandle<MusicTrack>>,
+    music_streams: HashMap<String, MusicStream>,
     
     // 监听器
     listener: AudioListener,
     
     // 分类音量
     category_volumes: HashMap<AudioCategory, f32>,
+    category_fades: HashMap<AudioCategory, CategoryFade>,
+    muted_categories: HashMap<AudioCategory, f32>,
     
     // 性能监控
     last_stats_update: std::time::Instant,
@@ -305,9 +367,12 @@ impl AudioSystem {
             
             sound_buffers: HashMap::new(),
             music_tracks: HashMap::new(),
+            music_streams: HashMap::new(),
             
             listener: AudioListener::default(),
             category_volumes,
+            category_fades: HashMap::new(),
+            muted_categories: HashMap::new(),
             
             last_stats_update: std::time::Instant::now(),
         })
@@ -329,9 +394,12 @@ impl AudioSystem {
             
             sound_buffers: HashMap::new(),
             music_tracks: HashMap::new(),
+            music_streams: HashMap::new(),
             
             listener: AudioListener::default(),
             category_volumes: HashMap::new(),
+            category_fades: HashMap::new(),
+            muted_categories: HashMap::new(),
             
             last_stats_update: std::time::Instant::now(),
         }
@@ -358,12 +426,45 @@ impl AudioSystem {
     
     // 加载音乐
     pub fn load_music(&mut self, id: &str, path: &Path) -> Result<()> {
+        self.load_music_with_tags(id, path, None)
+    }
+    
+    // 按轨道描述加载音乐，流式文件本身不带选曲标签，由描述提供
+    pub fn load_tagged_music(&mut self, track: &MusicTrack) -> Result<()> {
+        self.load_music_with_tags(&track.id, Path::new(&track.file_path), Some(track))
+    }
+    
+    fn load_music_with_tags(&mut self, id: &str, path: &Path, tags: Option<&MusicTrack>) -> Result<()> {
         if !self.config.enable_audio {
             return Ok(());
         }
         
         debug!("加载音乐: {} -> {:?}", id, path);
         
+        // 长音乐优先流式播放，避免整首解码常驻内存
+        if self.config.enable_audio_streaming {
+            match MusicStream::open(id, path, self.config.streaming_buffer_size as usize, true) {
+                Ok(stream) => {
+                    debug!("音乐 {} 使用流式播放 (节省内存: {} 字节)", id, stream.memory_saved());
+                    if let Some(ref mut manager) = self.manager {
+                        manager.register_music_stream(id, MusicStreamFormat {
+                            sample_rate: stream.sample_rate(),
+                            channels: stream.channels(),
+                        });
+                    }
+                    
+                    // 先注册选曲标签再返回，流式轨道同样要能被情境切换选中
+                    let mut track = tags.cloned().unwrap_or_else(|| untagged_track(id, path));
+                    track.duration = stream.duration();
+                    self.playlist_manager.register_track(&track);
+                    
+                    self.music_streams.insert(id.to_string(), stream);
+                    return Ok(());
+                },
+                Err(e) => debug!("音乐 {} 无法流式播放，改为完整加载: {}", id, e),
+            }
+        }
+        
         let handle = ResourceManager::instance().load::<MusicTrack>(
             &format!("music_{}", id),
             path,
@@ -371,6 +472,15 @@ impl AudioSystem {
             crate::core::resource_manager::ResourcePriority::High,
         )?;
         
+        // 注册选曲标签，供情境切换使用
+        if let Some(tags) = tags {
+            self.playlist_manager.register_track(tags);
+        } else if let Some(track) = handle.get() {
+            if let Ok(track) = track.read() {
+                self.playlist_manager.register_track(&track);
+            }
+        }
+        
         self.music_tracks.insert(id.to_string(), handle);
         Ok(())
     }
@@ -398,8 +508,21 @@ impl AudioSystem {
             self.cleanup_finished_sounds();
             
             if self.active_instances.len() >= self.config.max_simultaneous_sounds as usize {
-                warn!("达到最大同时播放数量限制");
-                return Err(GameError::AudioError("音频播放数量已达上限".to_string()));
+                match select_steal_victim(&self.active_instances, self.config.voice_steal_policy) {
+                    Some(victim) => {
+                        if let Some(instance) = self.active_instances.get(&victim) {
+                            debug!(
+                                "抢占音频实例 {} ({}, {:?}, 音量: {:.2}) 以播放 {}",
+                                victim, instance.sound_id, instance.category, instance.volume, sound_id
+                            );
+                        }
+                        self.stop_sound(victim, None)?;
+                    },
+                    None => {
+                        warn!("达到最大同时播放数量限制");
+                        return Err(GameError::AudioError("音频播放数量已达上限".to_string()));
+                    }
+                }
             }
         }
         
@@ -481,13 +604,18 @@ impl AudioSystem {
         Ok(())
     }
     
+    // 音乐是否已加载（完整加载或流式）
+    pub fn has_music(&self, track_id: &str) -> bool {
+        self.music_tracks.contains_key(track_id) || self.music_streams.contains_key(track_id)
+    }
+    
     // 播放音乐
     pub fn play_music(&mut self, track_id: &str, loop_music: bool, fade_in: Option<Duration>) -> Result<()> {
         if !self.config.enable_audio {
             return Ok(());
         }
         
-        if !self.music_tracks.contains_key(track_id) {
+        if !self.has_music(track_id) {
             return Err(GameError::AudioError(format!("音乐资源不存在: {}", track_id)));
         }
         
@@ -497,10 +625,63 @@ impl AudioSystem {
             manager.play_music(track_id, volume, loop_music, fade_in)?;
         }
         
+        self.playlist_manager.set_current_track(Some(track_id.to_string()));
+        
         info!("播放音乐: {} (循环: {})", track_id, loop_music);
         Ok(())
     }
     
+    // 交叉淡化到新音乐，旧轨道淡出的同时新轨道淡入
+    pub fn crossfade_music(&mut self, track_id: &str, duration: Duration) -> Result<()> {
+        if !self.config.enable_audio {
+            return Ok(());
+        }
+        
+        if !self.has_music(track_id) {
+            return Err(GameError::AudioError(format!("音乐资源不存在: {}", track_id)));
+        }
+        
+        let from_track = self.playlist_manager.current_track().cloned();
+        if !self.playlist_manager.begin_crossfade(track_id, duration) {
+            debug!("音乐 {} 已在播放，忽略交叉淡化", track_id);
+            return Ok(());
+        }
+        
+        self.start_music_crossfade(from_track.as_deref(), track_id, duration)
+    }
+    
+    // 切换音乐情境，自动交叉淡化到该情境的轨道
+    pub fn set_music_context(&mut self, context: GameContext) -> Result<()> {
+        if !self.config.enable_audio {
+            return Ok(());
+        }
+        
+        let from_track = self.playlist_manager.current_track().cloned();
+        if let Some(track_id) = self.playlist_manager.set_context(context) {
+            let duration = self.playlist_manager.crossfade()
+                .map(|crossfade| crossfade.duration)
+                .unwrap_or_default();
+            self.start_music_crossfade(from_track.as_deref(), &track_id, duration)?;
+        }
+        
+        Ok(())
+    }
+    
+    fn start_music_crossfade(&mut self, from_track: Option<&str>, track_id: &str, duration: Duration) -> Result<()> {
+        let volume = self.category_volumes.get(&AudioCategory::Music).copied().unwrap_or(1.0) * self.config.master_volume;
+        
+        if let Some(ref mut manager) = self.manager {
+            match from_track {
+                Some(from) => manager.crossfade_music(from, track_id, volume, duration)?,
+                // 没有正在播放的音乐时退化为普通淡入
+                None => manager.play_music(track_id, volume, true, Some(duration))?,
+            }
+        }
+        
+        info!("交叉淡化音乐: {:?} -> {} (时长: {:?})", from_track, track_id, duration);
+        Ok(())
+    }
+    
     // 停止音乐
     pub fn stop_music(&mut self, fade_out: Option<Duration>) -> Result<()> {
         if !self.config.enable_audio {
@@ -511,6 +692,8 @@ impl AudioSystem {
             manager.stop_music(fade_out)?;
         }
         
+        self.playlist_manager.set_current_track(None);
+        
         debug!("停止音乐");
         Ok(())
     }
@@ -533,25 +716,111 @@ impl AudioSystem {
     // 设置分类音量
     pub fn set_category_volume(&mut self, category: AudioCategory, volume: f32) -> Result<()> {
         let clamped_volume = volume.clamp(0.0, 1.0);
-        self.category_volumes.insert(category, clamped_volume);
+        self.apply_category_volume(category, clamped_volume)?;
+        
+        // 发送事件
+        EventSystem::dispatch(AudioVolumeChangeEvent {
+            category,
+            volume: clamped_volume,
+        })?;
+        
+        debug!("设置分类音量: {:?} = {}", category, clamped_volume);
+        Ok(())
+    }
+    
+    fn apply_category_volume(&mut self, category: AudioCategory, volume: f32) -> Result<()> {
+        self.category_volumes.insert(category, volume);
         
         // 更新所有该分类的活跃音频
         for instance in self.active_instances.values_mut() {
             if instance.category == category {
-                let new_volume = instance.volume * clamped_volume * self.config.master_volume;
+                let new_volume = instance.volume * volume * self.config.master_volume;
                 if let Some(ref mut manager) = self.manager {
                     manager.set_sound_volume(instance.id, new_volume)?;
                 }
             }
         }
         
-        // 发送事件
-        EventSystem::dispatch(AudioVolumeChangeEvent {
-            category,
-            volume: clamped_volume,
-        })?;
+        Ok(())
+    }
+    
+    // 获取分类音量
+    pub fn get_category_volume(&self, category: AudioCategory) -> f32 {
+        self.category_volumes.get(&category).copied().unwrap_or(1.0)
+    }
+    
+    // 分类音量渐变，由update逐帧推进
+    pub fn fade_category(&mut self, category: AudioCategory, target: f32, duration: Duration) -> Result<()> {
+        let target = target.clamp(0.0, 1.0);
+        
+        // 静音期间只更新恢复后的音量
+        if let Some(saved_volume) = self.muted_categories.get_mut(&category) {
+            *saved_volume = target;
+            return Ok(());
+        }
+        
+        if duration.is_zero() {
+            self.category_fades.remove(&category);
+            return self.set_category_volume(category, target);
+        }
+        
+        self.category_fades.insert(category, CategoryFade {
+            start_volume: self.get_category_volume(category),
+            target_volume: target,
+            duration,
+            elapsed: Duration::ZERO,
+        });
+        
+        debug!("开始分类音量渐变: {:?} -> {} (时长: {:?})", category, target, duration);
+        Ok(())
+    }
+    
+    // 静音分类，保留之前的音量以便恢复
+    pub fn mute_category(&mut self, category: AudioCategory) -> Result<()> {
+        if self.muted_categories.contains_key(&category) {
+            return Ok(());
+        }
+        
+        // 渐变中静音时，恢复到渐变的目标音量
+        let saved_volume = match self.category_fades.remove(&category) {
+            Some(fade) => fade.target_volume,
+            None => self.get_category_volume(category),
+        };
+        
+        self.muted_categories.insert(category, saved_volume);
+        self.set_category_volume(category, 0.0)
+    }
+    
+    // 取消静音，恢复静音前的音量
+    pub fn unmute_category(&mut self, category: AudioCategory) -> Result<()> {
+        match self.muted_categories.remove(&category) {
+            Some(saved_volume) => self.set_category_volume(category, saved_volume),
+            None => Ok(()),
+        }
+    }
+    
+    pub fn is_category_muted(&self, category: AudioCategory) -> bool {
+        self.muted_categories.contains_key(&category)
+    }
+    
+    // 推进分类音量渐变
+    fn update_category_fades(&mut self, delta_time: Duration) -> Result<()> {
+        let mut updates = Vec::new();
+        
+        for (&category, fade) in self.category_fades.iter_mut() {
+            fade.elapsed += delta_time;
+            updates.push((category, fade.current_volume(), fade.is_finished()));
+        }
+        
+        for (category, volume, finished) in updates {
+            if finished {
+                self.category_fades.remove(&category);
+                self.set_category_volume(category, volume)?;
+            } else {
+                self.apply_category_volume(category, volume)?;
+            }
+        }
         
-        debug!("设置分类音量: {:?} = {}", category, clamped_volume);
         Ok(())
     }
     
@@ -622,6 +891,12 @@ impl AudioSystem {
             manager.update(delta_time)?;
         }
         
+        // 推进分类音量渐变
+        self.update_category_fades(delta_time)?;
+        
+        // 补充流式音乐的环形缓冲区
+        self.refill_music_streams();
+        
         // 更新统计信息
         self.update_stats();
         
@@ -657,6 +932,55 @@ impl AudioSystem {
         }
     }
     
+    // 补充正在播放的流式音乐
+    fn refill_music_streams(&mut self) {
+        let mut playing: Vec<&str> = Vec::new();
+        if let Some(track_id) = self.playlist_manager.current_track() {
+            playing.push(track_id);
+        }
+        if let Some(from_track) = self.playlist_manager.crossfade().and_then(|c| c.from_track.as_ref()) {
+            playing.push(from_track);
+        }
+        
+        for track_id in playing {
+            if let Some(stream) = self.music_streams.get_mut(track_id) {
+                if let Err(e) = stream.refill() {
+                    warn!("补充音乐流 {} 失败: {}", track_id, e);
+                    self.stats.buffer_underruns += 1;
+                }
+            }
+        }
+        
+        self.stats.streaming_tracks = self.music_streams.len() as u32;
+        self.stats.streaming_memory_saved = self.music_streams.values().map(|s| s.memory_saved()).sum();
+        self.stats.audio_memory_usage = self.sound_memory_usage() + self.music_memory_usage();
+    }
+    
+    fn sound_memory_usage(&self) -> u64 {
+        self.sound_buffers.values()
+            .filter_map(|handle| handle.get())
+            .filter_map(|buffer| buffer.read().ok().map(|b| b.data.len() as u64))
+            .sum()
+    }
+    
+    // 流式音乐按常驻的缓冲区计算，完整加载的音乐按解码后的采样计算
+    fn music_memory_usage(&self) -> u64 {
+        let streamed: u64 = self.music_streams.values().map(|s| s.resident_bytes()).sum();
+        let bytes_per_second = self.config.sample_rate as f64
+            * self.config.channels as u32 as f64
+            * std::mem::size_of::<f32>() as f64;
+        let loaded: u64 = self.music_tracks.values()
+            .filter_map(|handle| handle.get())
+            .filter_map(|track| track.read().ok().map(|t| (t.duration.as_secs_f64() * bytes_per_second) as u64))
+            .sum();
+        streamed + loaded
+    }
+    
+    // 获取流式音乐
+    pub fn get_music_stream_mut(&mut self, track_id: &str) -> Option<&mut MusicStream> {
+        self.music_streams.get_mut(track_id)
+    }
+    
     // 更新统计信息
     fn update_stats(&mut self) {
         let now = std::time::Instant::now();
@@ -701,6 +1025,11 @@ impl AudioSystem {
         &self.config
     }
     
+    // 获取音乐交叉淡化增益 (旧轨道, 新轨道)
+    pub fn music_crossfade_gains(&self) -> (f32, f32) {
+        self.playlist_manager.crossfade_gains()
+    }
+    
     // 获取活跃音频实例
     pub fn get_active_instances(&self) -> &HashMap<u64, AudioInstance> {
         &self.active_instances
@@ -730,6 +1059,7 @@ impl AudioSystem {
         self.active_instances.clear();
         self.sound_buffers.clear();
         self.music_tracks.clear();
+        self.music_streams.clear();
         
         info!("音频系统已关闭");
     }
@@ -742,43 +1072,126 @@ impl Drop for AudioSystem {
 }
 
 // 全局音频系统
-static mut AUDIO_SYSTEM: Option<AudioSystem> = None;
-static AUDIO_INIT: std::sync::Once = std::sync::Once::new();
-
 pub struct Audio;
 
+// 全局音频系统的访问守卫，持有期间独占音频系统
+pub type AudioGuard = ServiceGuard<AudioSystem>;
+
 impl Audio {
+    fn lock() -> Result<MutexGuard<'static, Option<AudioSystem>>> {
+        Services::exclusive::<AudioSystem>()
+            .lock()
+            .map_err(|_| GameError::AudioError("音频系统锁已损坏".to_string()))
+    }
+    
     pub fn init(config: AudioSystemConfig) -> Result<()> {
-        unsafe {
-            AUDIO_INIT.call_once(|| {
-                match AudioSystem::new(config) {
-                    Ok(system) => {
-                        AUDIO_SYSTEM = Some(system);
-                    },
-                    Err(e) => {
-                        error!("音频系统初始化失败: {}", e);
-                    }
+        let mut system = Self::lock()?;
+        
+        if system.is_none() {
+            match AudioSystem::new(config) {
+                Ok(audio_system) => {
+                    *system = Some(audio_system);
+                },
+                Err(e) => {
+                    error!("音频系统初始化失败: {}", e);
+                    return Err(e);
                 }
-            });
+            }
         }
         
         Ok(())
     }
     
-    pub fn instance() -> Result<&'static mut AudioSystem> {
-        unsafe {
-            AUDIO_SYSTEM.as_mut()
-                .ok_or_else(|| GameError::AudioError("音频系统未初始化".to_string()))
-        }
+    pub fn instance() -> Result<AudioGuard> {
+        ServiceGuard::new(Self::lock()?)
+            .ok_or_else(|| GameError::AudioError("音频系统未初始化".to_string()))
     }
     
     pub fn cleanup() {
-        unsafe {
-            if let Some(ref mut system) = AUDIO_SYSTEM {
-                system.shutdown();
+        if let Ok(mut system) = Self::lock() {
+            if let Some(ref mut audio_system) = *system {
+                audio_system.shutdown();
             }
-            AUDIO_SYSTEM = None;
+            *system = None;
+        }
+    }
+}
+
+// 音频插件：初始化全局音频系统，并让音乐情境跟随游戏状态
+pub struct PokemonAudioPlugin;
+
+impl bevy::prelude::Plugin for PokemonAudioPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        use bevy::prelude::IntoSystemConfigs;
+        
+        // 没有音频设备时游戏照常运行，情境同步会跳过
+        if let Err(e) = Audio::init(AudioSystemConfig::default()) {
+            warn!("音频系统不可用: {}", e);
         }
+        
+        app.add_systems(
+            bevy::prelude::Update,
+            sync_music_context_system.run_if(bevy::prelude::state_changed::<crate::states::BevyGameState>),
+        );
+    }
+}
+
+// Bevy系统：游戏状态变化时切换音乐情境（进入战斗自动换成战斗音乐）
+pub fn sync_music_context_system(state: bevy::prelude::Res<bevy::prelude::State<crate::states::BevyGameState>>) {
+    let context = match state.get() {
+        crate::states::BevyGameState::Battle => GameContext::Battle,
+        crate::states::BevyGameState::MainMenu => GameContext::MainMenu,
+        crate::states::BevyGameState::Credits => GameContext::Credits,
+        crate::states::BevyGameState::Overworld => GameContext::Overworld,
+        _ => return,
+    };
+    
+    match Audio::instance() {
+        Ok(mut audio) => {
+            if let Err(e) = audio.set_music_context(context) {
+                warn!("切换音乐情境失败: {}", e);
+            }
+        },
+        Err(e) => debug!("跳过音乐情境同步: {}", e),
+    }
+}
+
+// 按抢占策略选出要停止的非循环实例
+pub fn select_steal_victim(instances: &HashMap<u64, AudioInstance>, policy: VoiceStealPolicy) -> Option<u64> {
+    let candidates = instances.values().filter(|instance| !instance.is_looping);
+    
+    let victim = match policy {
+        VoiceStealPolicy::Disabled => None,
+        VoiceStealPolicy::Oldest => candidates
+            .min_by_key(|instance| (instance.start_time, instance.id)),
+        VoiceStealPolicy::Quietest => candidates
+            .min_by(|a, b| a.volume.total_cmp(&b.volume).then_with(|| a.start_time.cmp(&b.start_time))),
+        VoiceStealPolicy::LowestPriority => candidates
+            .min_by_key(|instance| (instance.category.steal_priority(), instance.start_time, instance.id)),
+    };
+    
+    victim.map(|instance| instance.id)
+}
+
+// 未提供描述时流式轨道的默认标签：只登记轨道本身，不参与情境选曲
+fn untagged_track(id: &str, path: &Path) -> MusicTrack {
+    MusicTrack {
+        id: id.to_string(),
+        title: id.to_string(),
+        artist: None,
+        category: MusicCategory::Ambient,
+        file_path: path.to_string_lossy().into_owned(),
+        duration: Duration::ZERO,
+        intro_duration: None,
+        loop_start: None,
+        loop_end: None,
+        bpm: None,
+        key_signature: None,
+        mood_tags: Vec::new(),
+        game_contexts: Vec::new(),
+        fade_in_duration: Duration::from_secs(1),
+        fade_out_duration: Duration::from_secs(1),
+        priority: 0,
     }
 }
 
@@ -842,4 +1255,188 @@ mod tests {
         assert!(volume_close > volume_far);
         assert_eq!(volume_far, 0.0);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_global_audio_concurrent_play_sound() {
+        // 启用音频但没有输出设备，play_sound走完整的播放路径
+        ResourceManager::init().unwrap();
+        let mut system = test_audio_system();
+        system.config.max_simultaneous_sounds = 256;
+        let buffer = SoundBuffer::new(
+            "ui_click".to_string(),
+            AudioFormat::WAV,
+            SampleFormat::I16,
+            44100,
+            ChannelLayout::Stereo,
+            vec![0u8; 1024],
+        );
+        let handle = ResourceManager::instance().store_resource("sound_ui_click".to_string(), buffer);
+        system.sound_buffers.insert("ui_click".to_string(), handle);
+        *Audio::lock().unwrap() = Some(system);
+        
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    (0..100)
+                        .map(|_| {
+                            let mut audio = Audio::instance().unwrap();
+                            audio.play_sound("ui_click", AudioCategory::UI, 1.0, 1.0, None).unwrap()
+                        })
+                        .collect::<Vec<u64>>()
+                })
+            })
+            .collect();
+        
+        let mut instance_ids: Vec<u64> = handles.into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect();
+        instance_ids.sort_unstable();
+        instance_ids.dedup();
+        
+        // 每次播放都拿到不同的实例ID，计数没有丢失更新
+        let audio = Audio::instance().unwrap();
+        assert_eq!(instance_ids.len(), 200);
+        assert_eq!(audio.get_active_instances().len(), 200);
+        assert_eq!(audio.stats.total_sounds_played, 200);
+        assert!(instance_ids.iter().all(|id| audio.get_active_instances().contains_key(id)));
+        drop(audio);
+        
+        Audio::cleanup();
+    }
+    
+    fn test_instance(id: u64, category: AudioCategory, volume: f32, age_ms: u64, is_looping: bool) -> AudioInstance {
+        AudioInstance {
+            id,
+            sound_id: format!("sound_{}", id),
+            category,
+            state: AudioState::Playing,
+            volume,
+            pitch: 1.0,
+            transform: None,
+            is_looping,
+            start_time: std::time::Instant::now() - Duration::from_millis(age_ms),
+            duration: None,
+        }
+    }
+    
+    #[test]
+    fn test_voice_steal_oldest_first() {
+        let mut instances = HashMap::new();
+        instances.insert(1, test_instance(1, AudioCategory::SFX, 0.9, 500, false));
+        instances.insert(2, test_instance(2, AudioCategory::SFX, 0.1, 100, false));
+        instances.insert(3, test_instance(3, AudioCategory::Ambient, 0.5, 900, true));
+        
+        // 循环音效不会被抢占
+        assert_eq!(select_steal_victim(&instances, VoiceStealPolicy::Oldest), Some(1));
+        assert_eq!(select_steal_victim(&instances, VoiceStealPolicy::Disabled), None);
+    }
+    
+    #[test]
+    fn test_voice_steal_quietest_first() {
+        let mut instances = HashMap::new();
+        instances.insert(1, test_instance(1, AudioCategory::SFX, 0.9, 500, false));
+        instances.insert(2, test_instance(2, AudioCategory::Battle, 0.1, 100, false));
+        instances.insert(3, test_instance(3, AudioCategory::Ambient, 0.05, 900, true));
+        
+        assert_eq!(select_steal_victim(&instances, VoiceStealPolicy::Quietest), Some(2));
+        assert_eq!(select_steal_victim(&instances, VoiceStealPolicy::LowestPriority), Some(1));
+    }
+    
+    fn test_audio_system() -> AudioSystem {
+        EventSystem::init().unwrap();
+        
+        let mut system = AudioSystem::new_disabled();
+        system.config.enable_audio = true;
+        system.category_volumes.insert(AudioCategory::Music, 0.7);
+        system.category_volumes.insert(AudioCategory::SFX, 0.8);
+        system
+    }
+    
+    // 写入一段静音的16位立体声WAV
+    fn write_test_wav(seconds: u32) -> tempfile::NamedTempFile {
+        use std::io::Write;
+        
+        let sample_rate = 44100u32;
+        let data_len = sample_rate * seconds * 4;
+        let mut file = tempfile::Builder::new().suffix(".wav").tempfile().unwrap();
+        
+        let mut header = Vec::with_capacity(44);
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&(36 + data_len).to_le_bytes());
+        header.extend_from_slice(b"WAVEfmt ");
+        header.extend_from_slice(&16u32.to_le_bytes());
+        header.extend_from_slice(&1u16.to_le_bytes());
+        header.extend_from_slice(&2u16.to_le_bytes());
+        header.extend_from_slice(&sample_rate.to_le_bytes());
+        header.extend_from_slice(&(sample_rate * 4).to_le_bytes());
+        header.extend_from_slice(&4u16.to_le_bytes());
+        header.extend_from_slice(&16u16.to_le_bytes());
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&data_len.to_le_bytes());
+        file.write_all(&header).unwrap();
+        file.write_all(&vec![0u8; data_len as usize]).unwrap();
+        file.flush().unwrap();
+        file
+    }
+    
+    #[test]
+    fn test_streamed_music_selectable_by_context() {
+        let mut system = test_audio_system();
+        let wav = write_test_wav(2);
+        
+        let track = MusicTrack {
+            game_contexts: vec![GameContext::Battle],
+            mood_tags: vec![MoodTag::Energetic],
+            category: MusicCategory::Battle,
+            priority: 128,
+            ..untagged_track("wild_battle", wav.path())
+        };
+        system.load_tagged_music(&track).unwrap();
+        assert!(system.get_music_stream_mut("wild_battle").is_some());
+        
+        // 流式轨道提前返回前已注册到播放列表
+        system.set_music_context(GameContext::Battle).unwrap();
+        assert_eq!(system.playlist_manager.current_track().map(String::as_str), Some("wild_battle"));
+        
+        // 未带标签的流式轨道也会登记，可以直接交叉淡化过去
+        let ambient = write_test_wav(1);
+        system.load_music("cave_ambience", ambient.path()).unwrap();
+        system.crossfade_music("cave_ambience", Duration::from_secs(1)).unwrap();
+        assert_eq!(system.playlist_manager.current_track().map(String::as_str), Some("cave_ambience"));
+    }
+    
+    #[test]
+    fn test_unmute_restores_previous_volume() {
+        let mut system = test_audio_system();
+        
+        system.mute_category(AudioCategory::Music).unwrap();
+        assert!(system.is_category_muted(AudioCategory::Music));
+        assert_eq!(system.get_category_volume(AudioCategory::Music), 0.0);
+        
+        // 重复静音不会覆盖记住的音量
+        system.mute_category(AudioCategory::Music).unwrap();
+        system.unmute_category(AudioCategory::Music).unwrap();
+        assert!(!system.is_category_muted(AudioCategory::Music));
+        assert_eq!(system.get_category_volume(AudioCategory::Music), 0.7);
+        assert_eq!(system.get_category_volume(AudioCategory::SFX), 0.8);
+    }
+    
+    #[test]
+    fn test_category_fade_driven_by_update() {
+        let mut system = test_audio_system();
+        
+        system.fade_category(AudioCategory::SFX, 0.2, Duration::from_secs(1)).unwrap();
+        assert_eq!(system.get_category_volume(AudioCategory::SFX), 0.8);
+        
+        system.update(Duration::from_millis(500)).unwrap();
+        assert!((system.get_category_volume(AudioCategory::SFX) - 0.5).abs() < 0.001);
+        
+        // 渐变途中静音，取消静音后回到渐变目标
+        system.mute_category(AudioCategory::SFX).unwrap();
+        system.update(Duration::from_millis(500)).unwrap();
+        assert_eq!(system.get_category_volume(AudioCategory::SFX), 0.0);
+        
+        system.unmute_category(AudioCategory::SFX).unwrap();
+        assert_eq!(system.get_category_volume(AudioCategory::SFX), 0.2);
+    }
+}
@@ -5,9 +5,11 @@
 use crate::core::{GameError, Result};
 use crate::core::resource_manager::{ResourceManager, ResourceHandle};
 use crate::audio::{AudioCategory, AudioInstance, AudioTransform};
-use crate::audio::sound::{SoundBuffer, SoundInstance, SoundEffect, SoundEffectType};
+use crate::audio::sound::{SoundBuffer, SoundInstance, SoundEffect, SoundEffectType, SampleFormat, ChannelLayout};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
 use std::time::{Duration, Instant};
 use std::sync::{Arc, Mutex};
@@ -85,6 +87,36 @@ pub enum GameContext {
     LegendaryEncounter,
     Evolution,
     Credits,
+    Overworld,
+    Battle,
+    Victory,
+}
+
+impl GameContext {
+    // 情境对应的播放列表名称
+    pub fn playlist_name(&self) -> &'static str {
+        match self {
+            GameContext::Battle
+            | GameContext::GymLeader
+            | GameContext::EliteFour
+            | GameContext::Champion
+            | GameContext::LegendaryEncounter => "battle",
+            GameContext::Victory | GameContext::Evolution => "victory",
+            GameContext::MainMenu | GameContext::CharacterSelection | GameContext::Credits => "menu",
+            _ => "overworld",
+        }
+    }
+    
+    // 默认情绪权重
+    fn default_mood_weights(&self) -> HashMap<MoodTag, f32> {
+        let weights: &[(MoodTag, f32)] = match self.playlist_name() {
+            "battle" => &[(MoodTag::Energetic, 1.0), (MoodTag::Tense, 0.8), (MoodTag::Epic, 0.6), (MoodTag::Heroic, 0.4)],
+            "victory" => &[(MoodTag::Triumphant, 1.0), (MoodTag::Heroic, 0.6), (MoodTag::Energetic, 0.3)],
+            "menu" => &[(MoodTag::Heroic, 0.6), (MoodTag::Epic, 0.6), (MoodTag::Nostalgic, 0.4)],
+            _ => &[(MoodTag::Calm, 1.0), (MoodTag::Peaceful, 0.8), (MoodTag::Nostalgic, 0.4), (MoodTag::Mysterious, 0.2)],
+        };
+        weights.iter().cloned().collect()
+    }
 }
 
 // 音乐播放状态
@@ -834,6 +866,293 @@ pub struct MusicStats {
     pub active_players: usize,
 }
 
+// 流式音乐数据源 - 按块提供解码后的采样
+pub trait MusicStreamSource: Send {
+    fn read_samples(&mut self, out: &mut [f32]) -> Result<usize>;
+    fn rewind(&mut self) -> Result<()>;
+    fn total_samples(&self) -> u64;
+    fn sample_rate(&self) -> u32;
+    fn channels(&self) -> ChannelLayout;
+}
+
+// WAV文件流式读取，只解析文件头，数据块按需读取
+pub struct WavStreamSource {
+    reader: BufReader<File>,
+    sample_rate: u32,
+    channels: ChannelLayout,
+    sample_format: SampleFormat,
+    data_offset: u64,
+    total_samples: u64,
+    position: u64,
+}
+
+impl WavStreamSource {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .map_err(|e| GameError::AudioError(format!("打开WAV文件失败: {}", e)))?;
+        let file_len = file.metadata()
+            .map_err(|e| GameError::AudioError(format!("读取WAV文件信息失败: {}", e)))?
+            .len();
+        let mut reader = BufReader::new(file);
+        
+        let mut riff = [0u8; 12];
+        reader.read_exact(&mut riff)
+            .map_err(|_| GameError::AudioError("WAV文件头太短".to_string()))?;
+        if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+            return Err(GameError::AudioError("不是有效的WAV文件".to_string()));
+        }
+        
+        // 逐个解析块直到data，中间可能有LIST、fact等其它块
+        let mut format = None;
+        let data_len = loop {
+            let mut chunk_header = [0u8; 8];
+            reader.read_exact(&mut chunk_header)
+                .map_err(|_| GameError::AudioError("WAV文件缺少data块".to_string()))?;
+            let chunk_len = u32::from_le_bytes([chunk_header[4], chunk_header[5], chunk_header[6], chunk_header[7]]);
+            
+            match &chunk_header[0..4] {
+                b"fmt " => {
+                    if chunk_len < 16 {
+                        return Err(GameError::AudioError(format!("WAV fmt块太短: {} 字节", chunk_len)));
+                    }
+                    let mut fmt = vec![0u8; chunk_len as usize];
+                    reader.read_exact(&mut fmt)
+                        .map_err(|e| GameError::AudioError(format!("读取WAV fmt块失败: {}", e)))?;
+                    format = Some(fmt);
+                },
+                b"data" => break chunk_len as u64,
+                _ => {
+                    reader.seek_relative(chunk_len as i64)
+                        .map_err(|e| GameError::AudioError(format!("跳过WAV块失败: {}", e)))?;
+                },
+            }
+            // 奇数长度的块后面有一个填充字节
+            if chunk_len % 2 == 1 {
+                reader.seek_relative(1)
+                    .map_err(|e| GameError::AudioError(format!("跳过WAV块失败: {}", e)))?;
+            }
+        };
+        
+        let fmt = format.ok_or_else(|| GameError::AudioError("WAV文件在data块之前缺少fmt块".to_string()))?;
+        let audio_format = u16::from_le_bytes([fmt[0], fmt[1]]);
+        let channel_count = u16::from_le_bytes([fmt[2], fmt[3]]);
+        let sample_rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+        let bits_per_sample = u16::from_le_bytes([fmt[14], fmt[15]]);
+        
+        let channels = match channel_count {
+            1 => ChannelLayout::Mono,
+            2 => ChannelLayout::Stereo,
+            6 => ChannelLayout::Surround5_1,
+            8 => ChannelLayout::Surround7_1,
+            _ => return Err(GameError::AudioError(format!("不支持的声道数: {}", channel_count))),
+        };
+        
+        // 格式码1为整数PCM，3为浮点
+        let sample_format = match (audio_format, bits_per_sample) {
+            (1, 16) => SampleFormat::I16,
+            (1, 32) => SampleFormat::I32,
+            (3, 32) => SampleFormat::F32,
+            _ => return Err(GameError::AudioError(format!(
+                "不支持的WAV格式: 格式码 {}, 位深度 {}", audio_format, bits_per_sample
+            ))),
+        };
+        
+        let data_offset = reader.stream_position()
+            .map_err(|e| GameError::AudioError(format!("读取WAV文件位置失败: {}", e)))?;
+        // data块长度可能被写成占位值，不超过文件实际剩余的长度
+        let data_len = data_len.min(file_len.saturating_sub(data_offset));
+        let total_samples = data_len / Self::bytes_per_sample(sample_format);
+        
+        Ok(Self {
+            reader,
+            sample_rate,
+            channels,
+            sample_format,
+            data_offset,
+            total_samples,
+            position: 0,
+        })
+    }
+    
+    fn bytes_per_sample(format: SampleFormat) -> u64 {
+        match format {
+            SampleFormat::I16 => 2,
+            SampleFormat::I32 | SampleFormat::F32 => 4,
+        }
+    }
+}
+
+impl MusicStreamSource for WavStreamSource {
+    fn read_samples(&mut self, out: &mut [f32]) -> Result<usize> {
+        let remaining = (self.total_samples - self.position) as usize;
+        let count = out.len().min(remaining);
+        let bytes_per_sample = Self::bytes_per_sample(self.sample_format) as usize;
+        
+        let mut bytes = vec![0u8; count * bytes_per_sample];
+        self.reader.read_exact(&mut bytes)
+            .map_err(|e| GameError::AudioError(format!("读取音乐数据块失败: {}", e)))?;
+        
+        for (sample, chunk) in out.iter_mut().zip(bytes.chunks_exact(bytes_per_sample)) {
+            *sample = match self.sample_format {
+                SampleFormat::I16 => i16::from_le_bytes([chunk[0], chunk[1]]) as f32 / i16::MAX as f32,
+                SampleFormat::I32 => i32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as f32 / i32::MAX as f32,
+                SampleFormat::F32 => f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]),
+            };
+        }
+        
+        self.position += count as u64;
+        Ok(count)
+    }
+    
+    fn rewind(&mut self) -> Result<()> {
+        self.reader.seek(SeekFrom::Start(self.data_offset))
+            .map_err(|e| GameError::AudioError(format!("音乐流定位失败: {}", e)))?;
+        self.position = 0;
+        Ok(())
+    }
+    
+    fn total_samples(&self) -> u64 {
+        self.total_samples
+    }
+    
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    
+    fn channels(&self) -> ChannelLayout {
+        self.channels
+    }
+}
+
+// 流式音乐 - 内存中只保留一个环形缓冲区，由AudioSystem::update补充
+pub struct MusicStream {
+    pub track_id: String,
+    source: Box<dyn MusicStreamSource>,
+    ring: VecDeque<f32>,
+    capacity: usize,
+    chunk_size: usize,
+    chunk: Vec<f32>,
+    pub looping: bool,
+    pub samples_streamed: u64,
+    finished: bool,
+}
+
+impl MusicStream {
+    pub fn new(track_id: &str, source: Box<dyn MusicStreamSource>, buffer_size: usize, looping: bool) -> Self {
+        // 环形缓冲区保留4个数据块，避免播放线程读空
+        let chunk_size = buffer_size.max(1) * source.channels().channel_count() as usize;
+        let capacity = chunk_size * 4;
+        
+        Self {
+            track_id: track_id.to_string(),
+            source,
+            ring: VecDeque::with_capacity(capacity),
+            capacity,
+            chunk_size,
+            chunk: vec![0.0; chunk_size],
+            looping,
+            samples_streamed: 0,
+            finished: false,
+        }
+    }
+    
+    pub fn open(track_id: &str, path: &Path, buffer_size: usize, looping: bool) -> Result<Self> {
+        let is_wav = path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.eq_ignore_ascii_case("wav"))
+            .unwrap_or(false);
+        
+        if !is_wav {
+            return Err(GameError::AudioError(format!("不支持流式播放的音乐格式: {:?}", path)));
+        }
+        
+        let mut stream = Self::new(track_id, Box::new(WavStreamSource::open(path)?), buffer_size, looping);
+        stream.refill()?;
+        Ok(stream)
+    }
+    
+    // 补充环形缓冲区，返回新读取的采样数
+    pub fn refill(&mut self) -> Result<usize> {
+        let mut total_read = 0;
+        
+        while !self.finished && self.capacity - self.ring.len() >= self.chunk_size {
+            let read = self.source.read_samples(&mut self.chunk)?;
+            
+            if read == 0 {
+                if self.looping && self.source.total_samples() > 0 {
+                    self.source.rewind()?;
+                    continue;
+                }
+                self.finished = true;
+                break;
+            }
+            
+            self.ring.extend(&self.chunk[..read]);
+            self.samples_streamed += read as u64;
+            total_read += read;
+        }
+        
+        Ok(total_read)
+    }
+    
+    // 混音器从环形缓冲区取走采样
+    pub fn read(&mut self, out: &mut [f32]) -> usize {
+        let count = out.len().min(self.ring.len());
+        for (sample, value) in out.iter_mut().zip(self.ring.drain(..count)) {
+            *sample = value;
+        }
+        count
+    }
+    
+    pub fn buffered_samples(&self) -> usize {
+        self.ring.len()
+    }
+    
+    // 播放完毕且缓冲区已读空
+    pub fn is_finished(&self) -> bool {
+        self.finished && self.ring.is_empty()
+    }
+    
+    // 常驻内存：环形缓冲区与读取块
+    pub fn resident_bytes(&self) -> u64 {
+        ((self.capacity + self.chunk.len()) * std::mem::size_of::<f32>()) as u64
+    }
+    
+    // 完整解码该轨道所需的内存
+    pub fn full_decode_bytes(&self) -> u64 {
+        self.source.total_samples() * std::mem::size_of::<f32>() as u64
+    }
+    
+    pub fn memory_saved(&self) -> u64 {
+        self.full_decode_bytes().saturating_sub(self.resident_bytes())
+    }
+    
+    pub fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+    
+    pub fn channels(&self) -> ChannelLayout {
+        self.source.channels()
+    }
+    
+    pub fn duration(&self) -> Duration {
+        let frames = self.source.total_samples() / self.source.channels().channel_count() as u64;
+        Duration::from_secs_f64(frames as f64 / self.source.sample_rate().max(1) as f64)
+    }
+}
+
+impl std::fmt::Debug for MusicStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MusicStream")
+            .field("track_id", &self.track_id)
+            .field("buffered_samples", &self.ring.len())
+            .field("capacity", &self.capacity)
+            .field("looping", &self.looping)
+            .field("finished", &self.finished)
+            .finish()
+    }
+}
+
 // 播放列表管理器 - 简化版本
 #[derive(Debug, Clone)]
 pub struct PlaylistManager {
@@ -841,6 +1160,25 @@ pub struct PlaylistManager {
     current_playlist: Option<String>,
     shuffle_enabled: bool,
     repeat_mode: RepeatMode,
+    
+    // 当前音乐与交叉淡化
+    current_track: Option<String>,
+    crossfade: Option<PlaylistCrossfade>,
+    
+    // 情境选曲
+    track_tags: HashMap<String, PlaylistTrackTags>,
+    current_context: Option<GameContext>,
+    context_mood_weights: HashMap<GameContext, HashMap<MoodTag, f32>>,
+    context_crossfade_duration: Duration,
+}
+
+// 选曲所需的轨道标签
+#[derive(Debug, Clone)]
+pub struct PlaylistTrackTags {
+    pub category: MusicCategory,
+    pub mood_tags: Vec<MoodTag>,
+    pub game_contexts: Vec<GameContext>,
+    pub priority: u8,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -850,6 +1188,40 @@ pub enum RepeatMode {
     Playlist,
 }
 
+// 交叉淡化进度 - 由update按帧推进，旧轨道淡出的同时新轨道淡入
+#[derive(Debug, Clone)]
+pub struct PlaylistCrossfade {
+    pub from_track: Option<String>,
+    pub to_track: String,
+    pub duration: Duration,
+    pub elapsed: Duration,
+}
+
+impl PlaylistCrossfade {
+    pub fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+    
+    // 等功率曲线，避免中点处的音量凹陷
+    pub fn outgoing_gain(&self) -> f32 {
+        if self.from_track.is_none() {
+            return 0.0;
+        }
+        (self.progress() * std::f32::consts::FRAC_PI_2).cos()
+    }
+    
+    pub fn incoming_gain(&self) -> f32 {
+        (self.progress() * std::f32::consts::FRAC_PI_2).sin()
+    }
+    
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
 impl PlaylistManager {
     pub fn new() -> Self {
         Self {
@@ -857,6 +1229,14 @@ impl PlaylistManager {
             current_playlist: None,
             shuffle_enabled: false,
             repeat_mode: RepeatMode::Off,
+            
+            current_track: None,
+            crossfade: None,
+            
+            track_tags: HashMap::new(),
+            current_context: None,
+            context_mood_weights: HashMap::new(),
+            context_crossfade_duration: Duration::from_secs(2),
         }
     }
 
@@ -875,9 +1255,144 @@ impl PlaylistManager {
     pub fn get_current_playlist(&self) -> Option<&String> {
         self.current_playlist.as_ref()
     }
+    
+    pub fn current_track(&self) -> Option<&String> {
+        self.current_track.as_ref()
+    }
+    
+    // 直接切换当前轨道（无交叉淡化）
+    pub fn set_current_track(&mut self, track_id: Option<String>) {
+        self.current_track = track_id;
+        self.crossfade = None;
+    }
+    
+    // 开始交叉淡化，请求的轨道已在播放时返回false
+    pub fn begin_crossfade(&mut self, track_id: &str, duration: Duration) -> bool {
+        if self.current_track.as_deref() == Some(track_id) {
+            return false;
+        }
+        
+        self.crossfade = Some(PlaylistCrossfade {
+            from_track: self.current_track.take(),
+            to_track: track_id.to_string(),
+            duration,
+            elapsed: Duration::ZERO,
+        });
+        self.current_track = Some(track_id.to_string());
+        true
+    }
+    
+    // 注册轨道标签，并加入其情境对应的播放列表
+    pub fn register_track(&mut self, track: &MusicTrack) {
+        for context in &track.game_contexts {
+            let playlist = self.playlists.entry(context.playlist_name().to_string()).or_default();
+            if !playlist.contains(&track.id) {
+                playlist.push(track.id.clone());
+            }
+        }
+        
+        self.track_tags.insert(track.id.clone(), PlaylistTrackTags {
+            category: track.category,
+            mood_tags: track.mood_tags.clone(),
+            game_contexts: track.game_contexts.clone(),
+            priority: track.priority,
+        });
+    }
+    
+    pub fn set_context_mood_weight(&mut self, context: GameContext, mood: MoodTag, weight: f32) {
+        self.context_mood_weights
+            .entry(context.clone())
+            .or_insert_with(|| context.default_mood_weights())
+            .insert(mood, weight);
+    }
+    
+    pub fn set_context_crossfade_duration(&mut self, duration: Duration) {
+        self.context_crossfade_duration = duration;
+    }
+    
+    pub fn current_context(&self) -> Option<&GameContext> {
+        self.current_context.as_ref()
+    }
+    
+    // 为情境选择轨道：优先带有该情境标签的轨道，按情绪权重与优先级打分
+    pub fn select_track_for_context(&self, context: &GameContext) -> Option<String> {
+        let default_weights;
+        let weights = match self.context_mood_weights.get(context) {
+            Some(weights) => weights,
+            None => {
+                default_weights = context.default_mood_weights();
+                &default_weights
+            }
+        };
+        
+        let candidates: Vec<&String> = match self.playlists.get(context.playlist_name()) {
+            Some(playlist) => playlist.iter().collect(),
+            None => self.track_tags.keys().collect(),
+        };
+        
+        candidates
+            .into_iter()
+            .filter_map(|track_id| self.track_tags.get(track_id).map(|tags| (track_id, tags)))
+            .filter(|(_, tags)| {
+                tags.game_contexts.iter().any(|c| c == context || c.playlist_name() == context.playlist_name())
+            })
+            .map(|(track_id, tags)| {
+                let mut score: f32 = tags.mood_tags.iter().filter_map(|m| weights.get(m)).sum();
+                if tags.game_contexts.contains(context) {
+                    score += 10.0;
+                }
+                score += tags.priority as f32 / 255.0;
+                (track_id, score)
+            })
+            .max_by(|(a_id, a), (b_id, b)| a.total_cmp(b).then_with(|| b_id.cmp(a_id)))
+            .map(|(track_id, _)| track_id.clone())
+    }
+    
+    // 切换游戏情境，返回需要交叉淡化到的新轨道
+    pub fn set_context(&mut self, context: GameContext) -> Option<String> {
+        if self.current_context.as_ref() == Some(&context) {
+            return None;
+        }
+        
+        debug!("播放列表情境切换: {:?} -> {:?}", self.current_context, context);
+        self.current_playlist = Some(context.playlist_name().to_string());
+        let track_id = self.select_track_for_context(&context);
+        self.current_context = Some(context);
+        
+        let track_id = track_id?;
+        if self.begin_crossfade(&track_id, self.context_crossfade_duration) {
+            Some(track_id)
+        } else {
+            None
+        }
+    }
+    
+    pub fn crossfade(&self) -> Option<&PlaylistCrossfade> {
+        self.crossfade.as_ref()
+    }
+    
+    pub fn is_crossfading(&self) -> bool {
+        self.crossfade.is_some()
+    }
+    
+    // (旧轨道增益, 新轨道增益)
+    pub fn crossfade_gains(&self) -> (f32, f32) {
+        match &self.crossfade {
+            Some(crossfade) => (crossfade.outgoing_gain(), crossfade.incoming_gain()),
+            None if self.current_track.is_some() => (0.0, 1.0),
+            None => (0.0, 0.0),
+        }
+    }
 
-    pub fn update(&mut self, _delta_time: Duration) {
-        // 播放列表更新逻辑
+    pub fn update(&mut self, delta_time: Duration) {
+        if let Some(ref mut crossfade) = self.crossfade {
+            crossfade.elapsed += delta_time;
+            
+            if crossfade.is_finished() {
+                debug!("播放列表交叉淡化完成: {:?} -> {}", crossfade.from_track, crossfade.to_track);
+                self.crossfade = None;
+            }
+        }
     }
 
     pub fn set_shuffle(&mut self, enabled: bool) {
@@ -941,4 +1456,175 @@ mod tests {
         assert!((exponential - 0.25).abs() < 0.001);
         assert!((s_curve - 0.5).abs() < 0.001);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_playlist_crossfade_gains_overlap() {
+        let mut playlist = PlaylistManager::new();
+        assert!(playlist.begin_crossfade("route_1", Duration::ZERO));
+        playlist.update(Duration::from_millis(16));
+        assert_eq!(playlist.crossfade_gains(), (0.0, 1.0));
+        
+        assert!(playlist.begin_crossfade("battle_wild", Duration::from_secs(2)));
+        
+        // 以60fps推进到交叉淡化窗口中点
+        for _ in 0..60 {
+            playlist.update(Duration::from_secs_f32(1.0 / 60.0));
+        }
+        
+        let (outgoing, incoming) = playlist.crossfade_gains();
+        assert!(playlist.is_crossfading());
+        assert!(outgoing > 0.0);
+        assert!(incoming > 0.0);
+        assert!(outgoing * outgoing + incoming * incoming > 0.99);
+        
+        playlist.update(Duration::from_secs(2));
+        assert!(!playlist.is_crossfading());
+        assert_eq!(playlist.crossfade_gains(), (0.0, 1.0));
+        assert_eq!(playlist.current_track().map(String::as_str), Some("battle_wild"));
+    }
+    
+    #[test]
+    fn test_playlist_crossfade_same_track_and_fade_in() {
+        let mut playlist = PlaylistManager::new();
+        
+        // 没有正在播放的轨道时只淡入
+        assert!(playlist.begin_crossfade("main_theme", Duration::from_secs(1)));
+        playlist.update(Duration::from_millis(500));
+        let (outgoing, incoming) = playlist.crossfade_gains();
+        assert_eq!(outgoing, 0.0);
+        assert!(incoming > 0.0 && incoming < 1.0);
+        
+        // 请求同一轨道不做任何事
+        assert!(!playlist.begin_crossfade("main_theme", Duration::from_secs(1)));
+        assert_eq!(playlist.crossfade().unwrap().elapsed, Duration::from_millis(500));
+    }
+    
+    fn context_track(id: &str, category: MusicCategory, moods: Vec<MoodTag>, contexts: Vec<GameContext>) -> MusicTrack {
+        MusicTrack {
+            id: id.to_string(),
+            title: id.to_string(),
+            artist: None,
+            category,
+            file_path: format!("assets/music/{}.ogg", id),
+            duration: Duration::from_secs(90),
+            intro_duration: None,
+            loop_start: None,
+            loop_end: None,
+            bpm: None,
+            key_signature: None,
+            mood_tags: moods,
+            game_contexts: contexts,
+            fade_in_duration: Duration::from_secs(1),
+            fade_out_duration: Duration::from_secs(1),
+            priority: 128,
+        }
+    }
+    
+    #[test]
+    fn test_playlist_context_selects_tagged_track() {
+        let mut playlist = PlaylistManager::new();
+        playlist.register_track(&context_track("route_1", MusicCategory::Route, vec![MoodTag::Calm], vec![GameContext::Overworld]));
+        playlist.register_track(&context_track("wild_battle", MusicCategory::Battle, vec![MoodTag::Energetic], vec![GameContext::Battle]));
+        playlist.register_track(&context_track("victory_fanfare", MusicCategory::Victory, vec![MoodTag::Triumphant], vec![GameContext::Victory]));
+        
+        assert_eq!(playlist.set_context(GameContext::Overworld).as_deref(), Some("route_1"));
+        assert_eq!(playlist.set_context(GameContext::Battle).as_deref(), Some("wild_battle"));
+        assert!(playlist.is_crossfading());
+        assert_eq!(playlist.get_current_playlist().map(String::as_str), Some("battle"));
+        
+        // 重复设置同一情境不会重新切歌
+        assert_eq!(playlist.set_context(GameContext::Battle), None);
+        assert_eq!(playlist.set_context(GameContext::Victory).as_deref(), Some("victory_fanfare"));
+    }
+    
+    #[test]
+    fn test_playlist_context_mood_weighting() {
+        let mut playlist = PlaylistManager::new();
+        playlist.register_track(&context_track("battle_tense", MusicCategory::Battle, vec![MoodTag::Tense], vec![GameContext::Battle]));
+        playlist.register_track(&context_track("battle_epic", MusicCategory::Battle, vec![MoodTag::Epic], vec![GameContext::Battle]));
+        
+        assert_eq!(playlist.select_track_for_context(&GameContext::Battle).as_deref(), Some("battle_tense"));
+        
+        playlist.set_context_mood_weight(GameContext::Battle, MoodTag::Epic, 2.0);
+        assert_eq!(playlist.select_track_for_context(&GameContext::Battle).as_deref(), Some("battle_epic"));
+        assert_eq!(playlist.select_track_for_context(&GameContext::Victory), None);
+    }
+    
+    fn write_test_wav(seconds: u32) -> tempfile::NamedTempFile {
+        write_test_wav_with_chunks(seconds, &[])
+    }
+    
+    // extra_chunks插在fmt块之前，模拟带LIST等元数据块的文件
+    fn write_test_wav_with_chunks(seconds: u32, extra_chunks: &[(&[u8; 4], &[u8])]) -> tempfile::NamedTempFile {
+        use std::io::Write;
+        
+        let sample_rate = 44100u32;
+        let data_len = sample_rate * seconds * 2 * 2;
+        let mut file = tempfile::Builder::new().suffix(".wav").tempfile().unwrap();
+        
+        let mut extra = Vec::new();
+        for (id, body) in extra_chunks {
+            extra.extend_from_slice(*id);
+            extra.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            extra.extend_from_slice(body);
+            if body.len() % 2 == 1 {
+                extra.push(0);
+            }
+        }
+        
+        let mut header = Vec::with_capacity(44 + extra.len());
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&(36 + extra.len() as u32 + data_len).to_le_bytes());
+        header.extend_from_slice(b"WAVE");
+        header.extend_from_slice(&extra);
+        header.extend_from_slice(b"fmt ");
+        header.extend_from_slice(&16u32.to_le_bytes());
+        header.extend_from_slice(&1u16.to_le_bytes());
+        header.extend_from_slice(&2u16.to_le_bytes());
+        header.extend_from_slice(&sample_rate.to_le_bytes());
+        header.extend_from_slice(&(sample_rate * 4).to_le_bytes());
+        header.extend_from_slice(&4u16.to_le_bytes());
+        header.extend_from_slice(&16u16.to_le_bytes());
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&data_len.to_le_bytes());
+        
+        file.write_all(&header).unwrap();
+        file.write_all(&vec![0u8; data_len as usize]).unwrap();
+        file.flush().unwrap();
+        file
+    }
+    
+    #[test]
+    fn test_streamed_track_uses_less_memory() {
+        let wav = write_test_wav(5);
+        
+        let full = SoundBuffer::load_from_file(wav.path()).unwrap();
+        let mut stream = MusicStream::open("long_track", wav.path(), 4096, false).unwrap();
+        
+        assert!(stream.buffered_samples() > 0);
+        assert!(stream.resident_bytes() < full.data.len() as u64);
+        assert!(stream.memory_saved() > 0);
+        assert!((stream.duration().as_secs_f32() - 5.0).abs() < 0.01);
+        
+        // 消费缓冲区后可以继续补充
+        let mut out = vec![0.0; 8192];
+        assert_eq!(stream.read(&mut out), 8192);
+        assert!(stream.refill().unwrap() > 0);
+        assert!(!stream.is_finished());
+    }
+    
+    #[test]
+    fn test_stream_skips_chunks_before_data() {
+        let wav = write_test_wav_with_chunks(1, &[(b"LIST", &b"INFOISFT\x03\x00\x00\x00abc"[..]), (b"junk", &[0u8; 7][..])]);
+        let mut stream = MusicStream::open("tagged_track", wav.path(), 1024, false).unwrap();
+        
+        assert_eq!(stream.sample_rate(), 44100);
+        assert_eq!(stream.channels(), ChannelLayout::Stereo);
+        assert!((stream.duration().as_secs_f32() - 1.0).abs() < 0.01);
+        
+        // 数据全是静音，说明没有把元数据块当成采样读出来
+        let mut out = vec![1.0; 2048];
+        assert_eq!(stream.read(&mut out), 2048);
+        assert!(out.iter().all(|&sample| sample == 0.0));
+    }
+}
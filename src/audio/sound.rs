@@ -693,8 +693,8 @@ mod tests {
             data,
         );
         
-        // 创建资源句柄（简化测试）
-        let handle = ResourceManager::instance().create_handle("test_sound".to_string());
+        ResourceManager::init().unwrap();
+        let handle = ResourceManager::instance().store_resource("test_sound".to_string(), buffer);
         let instance = SoundInstance::new(1, handle, AudioCategory::SFX, 0.8, 1.0);
         
         assert_eq!(instance.volume, 0.8);
@@ -702,6 +702,29 @@ mod tests {
         assert!(!instance.is_playing);
     }
     
+    #[test]
+    fn test_sound_buffer_round_trip() {
+        ResourceManager::init().unwrap();
+        let manager = ResourceManager::instance();
+        let buffer = SoundBuffer::new(
+            "round_trip".to_string(),
+            AudioFormat::WAV,
+            SampleFormat::I16,
+            22050,
+            ChannelLayout::Mono,
+            vec![0u8; 512],
+        );
+        let handle = manager.store_resource("round_trip_sound".to_string(), buffer);
+        
+        let stored = manager.get_resource(&handle).unwrap();
+        assert_eq!((stored.sample_rate, stored.channels), (22050, ChannelLayout::Mono));
+        assert_eq!(manager.resolve::<SoundBuffer>("round_trip_sound").unwrap().data.len(), 512);
+        
+        // 以错误的类型取出时报错
+        assert!(manager.resolve::<Vec<u8>>("round_trip_sound").is_err());
+        assert!(manager.get::<String>("round_trip_sound").is_none());
+    }
+    
     #[test]
     fn test_channel_layout() {
         assert_eq!(ChannelLayout::Mono.channel_count(), 1);
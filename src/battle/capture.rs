@@ -0,0 +1,174 @@
+// 捕获判定
+// 开发心理：野生战斗只能打倒或逃跑，没有投球的入口，精灵球道具也只是背包里的一个数字
+// 设计原则：按正作公式由种族捕获率、精灵球倍率、状态加成和剩余HP算出捕获值，再做四次摇晃判定；随机数来自战斗的BattleRng，同一种子结果可复现
+
+use crate::battle::BattleRng;
+use crate::pokemon::{Pokemon, StatusCondition};
+
+// 摇晃判定的次数，全部通过才算捕获成功
+pub const SHAKE_CHECKS: u8 = 4;
+
+// 捕获值达到此值时必定成功
+const GUARANTEED_CATCH_VALUE: f32 = 255.0;
+
+// 精灵球种类，编号与物品数据库中的道具ID一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PokeBall {
+    Poke,
+    Great,
+    Ultra,
+    Master,
+}
+
+impl PokeBall {
+    pub fn from_item_id(item_id: u32) -> Option<Self> {
+        match item_id {
+            1 => Some(PokeBall::Poke),
+            2 => Some(PokeBall::Great),
+            3 => Some(PokeBall::Ultra),
+            4 => Some(PokeBall::Master),
+            _ => None,
+        }
+    }
+
+    pub fn item_id(&self) -> u32 {
+        match self {
+            PokeBall::Poke => 1,
+            PokeBall::Great => 2,
+            PokeBall::Ultra => 3,
+            PokeBall::Master => 4,
+        }
+    }
+
+    // 大师球的倍率为None，表示必定捕获
+    pub fn multiplier(&self) -> Option<f32> {
+        match self {
+            PokeBall::Poke => Some(1.0),
+            PokeBall::Great => Some(1.5),
+            PokeBall::Ultra => Some(2.0),
+            PokeBall::Master => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptureResult {
+    pub caught: bool,
+    // 精灵球摇晃的次数，捕获成功时为SHAKE_CHECKS
+    pub shakes: u8,
+    // 本次投球的成功率，用于界面提示
+    pub probability: f32,
+}
+
+// 睡眠和冰冻的加成最高，其他异常状态次之
+pub fn status_bonus(status: &StatusCondition) -> f32 {
+    match status {
+        StatusCondition::Sleep { .. } | StatusCondition::Freeze => 2.5,
+        StatusCondition::Paralysis
+        | StatusCondition::Burn
+        | StatusCondition::Poison
+        | StatusCondition::BadlyPoisoned => 1.5,
+        _ => 1.0,
+    }
+}
+
+// 捕获值 a = (3 * 最大HP - 2 * 当前HP) / (3 * 最大HP) * 捕获率 * 球倍率 * 状态加成
+pub fn catch_value(catch_rate: u8, ball: PokeBall, status: &StatusCondition, hp_ratio: f32) -> f32 {
+    let Some(ball_multiplier) = ball.multiplier() else {
+        return GUARANTEED_CATCH_VALUE;
+    };
+    let hp_factor = (3.0 - 2.0 * hp_ratio.clamp(0.0, 1.0)) / 3.0;
+    (hp_factor * catch_rate as f32 * ball_multiplier * status_bonus(status)).max(1.0)
+}
+
+// 每次摇晃判定的通过率 b / 65536，其中 b = 65536 / (255 / a)^(1/4)
+fn shake_probability(catch_value: f32) -> f32 {
+    if catch_value >= GUARANTEED_CATCH_VALUE {
+        return 1.0;
+    }
+    (catch_value / GUARANTEED_CATCH_VALUE).powf(0.25)
+}
+
+// 一次投球的成功率，即四次摇晃全部通过的概率
+pub fn catch_probability(catch_rate: u8, ball: PokeBall, status: &StatusCondition, hp_ratio: f32) -> f32 {
+    shake_probability(catch_value(catch_rate, ball, status, hp_ratio)).powi(SHAKE_CHECKS as i32)
+}
+
+// 对宝可梦投出一个精灵球；hp_ratio为当前HP占最大HP的比例
+pub fn attempt(
+    pokemon: &Pokemon,
+    ball: PokeBall,
+    status: &StatusCondition,
+    hp_ratio: f32,
+    rng: &mut BattleRng,
+) -> CaptureResult {
+    // 种族数据缺失时按最难捕获处理
+    let catch_rate = pokemon.get_species().map_or(3, |species| species.catch_rate);
+    let value = catch_value(catch_rate, ball, status, hp_ratio);
+    let shake = shake_probability(value);
+
+    let mut shakes = 0;
+    while shakes < SHAKE_CHECKS && rng.chance(shake) {
+        shakes += 1;
+    }
+
+    CaptureResult {
+        caught: shakes == SHAKE_CHECKS,
+        shakes,
+        probability: shake.powi(SHAKE_CHECKS as i32),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_low_hp_status_and_better_ball_raise_catch_rate() {
+        let none = StatusCondition::None;
+        let sleep = StatusCondition::Sleep { turns_remaining: 2 };
+
+        // 满HP、无状态、普通精灵球：成功率约为 捕获率 / 3 / 255
+        let baseline = catch_probability(45, PokeBall::Poke, &none, 1.0);
+        assert!((baseline - 15.0 / 255.0).abs() < 1e-3, "基础成功率: {}", baseline);
+
+        let low_hp = catch_probability(45, PokeBall::Poke, &none, 0.05);
+        let low_hp_asleep = catch_probability(45, PokeBall::Poke, &sleep, 0.05);
+        let best = catch_probability(45, PokeBall::Ultra, &sleep, 0.05);
+        assert!(baseline < low_hp && low_hp < low_hp_asleep && low_hp_asleep < best);
+
+        // 捕获值按各项因子相乘
+        let expected = (3.0 - 2.0 * 0.05) / 3.0 * 45.0 * 2.0 * 2.5;
+        assert!((catch_value(45, PokeBall::Ultra, &sleep, 0.05) - expected).abs() < 1e-3);
+        assert!((best - (expected / 255.0).powf(0.25).powi(4)).abs() < 1e-4);
+
+        // 麻痹的加成低于睡眠，大师球和足够高的捕获值必定成功
+        assert!(catch_probability(45, PokeBall::Poke, &StatusCondition::Paralysis, 0.05) < low_hp_asleep);
+        assert_eq!(catch_probability(3, PokeBall::Master, &none, 1.0), 1.0);
+        assert_eq!(catch_probability(255, PokeBall::Ultra, &sleep, 0.1), 1.0);
+    }
+
+    #[test]
+    fn test_attempts_follow_probability() {
+        let pokemon = Pokemon::new(25, 10, None, "Test".to_string(), "Test Location".to_string()).unwrap();
+        let catch_rate = pokemon.get_species().unwrap().catch_rate;
+        let mut rng = BattleRng::with_seed(3);
+
+        // 满HP、无状态时成功率明显低于1，成功的比例应接近理论值
+        let attempts = 2000;
+        let results: Vec<CaptureResult> = (0..attempts)
+            .map(|_| attempt(&pokemon, PokeBall::Poke, &StatusCondition::None, 1.0, &mut rng))
+            .collect();
+        assert!(results.iter().all(|result| result.shakes <= SHAKE_CHECKS));
+        let caught = results.iter().filter(|result| result.caught).count();
+        let expected = catch_probability(catch_rate, PokeBall::Poke, &StatusCondition::None, 1.0);
+        assert!(expected < 0.5);
+        assert!((caught as f32 / attempts as f32 - expected).abs() < 0.05);
+
+        let master = attempt(&pokemon, PokeBall::Master, &StatusCondition::None, 1.0, &mut rng);
+        assert!(master.caught);
+        assert_eq!(master.shakes, SHAKE_CHECKS);
+        assert_eq!(PokeBall::from_item_id(PokeBall::Ultra.item_id()), Some(PokeBall::Ultra));
+        assert_eq!(PokeBall::from_item_id(101), None);
+    }
+}
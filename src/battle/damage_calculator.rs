@@ -3,27 +3,43 @@
 // 设计原则：数学精确性、性能优化、可扩展的修正系统
 
 use crate::core::{GameError, Result};
-use crate::pokemon::{Pokemon, PokemonType, Move, MoveCategory};
-use crate::battle::{BattleEnvironment, WeatherType};
+use crate::pokemon::{AbilityId, Pokemon, PokemonType, Move, MoveCategory};
+use crate::data::{DataRegistry, DataType};
+use crate::battle::{BattleEnvironment, BattleRng, WeatherType};
+use crate::battle::rng::{MAX_DAMAGE_ROLL_PERCENT, MIN_DAMAGE_ROLL_PERCENT};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use log::{debug, warn};
 
 // 伤害计算器主结构
+#[derive(Debug)]
 pub struct DamageCalculator {
-    type_chart: TypeEffectivenessChart,
+    type_chart: Arc<TypeEffectivenessChart>,
     critical_hit_multipliers: HashMap<u8, f32>,
     weather_modifiers: HashMap<WeatherType, HashMap<PokemonType, f32>>,
     ability_modifiers: HashMap<String, DamageModifier>,
     item_modifiers: HashMap<u32, DamageModifier>,
 }
 
-// 类型相性表
-#[derive(Debug, Clone)]
+// 类型相性表，数据文件的格式是 攻击属性 -> 防御属性 -> 倍率，表中没有的组合为1倍
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "HashMap<String, HashMap<String, f32>>")]
 pub struct TypeEffectivenessChart {
     effectiveness: HashMap<(PokemonType, PokemonType), f32>,
 }
 
+// 特性数据，只有带伤害倍率的特性会影响伤害计算
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AbilityData {
+    pub id: AbilityId,
+    pub name: String,
+    #[serde(default)]
+    pub damage_multiplier: Option<f32>,
+    #[serde(default)]
+    pub stage: ModifierStage,
+}
+
 // 伤害修正器
 #[derive(Debug, Clone)]
 pub struct DamageModifier {
@@ -42,10 +58,11 @@ pub enum ModifierCondition {
     Custom(String),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum ModifierStage {
     BeforeTypeEffectiveness,
     AfterTypeEffectiveness,
+    #[default]
     Final,
 }
 
@@ -57,7 +74,7 @@ pub struct DamageContext<'a> {
     pub move_data: &'a Move,
     pub environment: &'a BattleEnvironment,
     pub critical_hit: bool,
-    pub random_factor: f32,        // 0.85 - 1.0
+    pub random_factor: f32,        // 0.85 - 1.0，在所有修正之后最后乘上
     pub stab_bonus: bool,         // 本系技能加成
     pub multi_target: bool,       // 多目标技能
     pub weather_boost: bool,      // 天气加成
@@ -88,7 +105,7 @@ pub struct AppliedModifier {
 impl DamageCalculator {
     pub fn new() -> Self {
         Self {
-            type_chart: TypeEffectivenessChart::new(),
+            type_chart: TypeEffectivenessChart::global(),
             critical_hit_multipliers: Self::init_critical_multipliers(),
             weather_modifiers: Self::init_weather_modifiers(),
             ability_modifiers: Self::init_ability_modifiers(),
@@ -138,10 +155,7 @@ impl DamageCalculator {
             });
         }
         
-        // 4.2 随机因子 (85%-100%)
-        final_damage *= context.random_factor;
-        
-        // 4.3 本系加成 (STAB)
+        // 4.2 本系加成 (STAB)
         if context.stab_bonus {
             final_damage *= 1.5;
             modifiers.push(AppliedModifier {
@@ -151,7 +165,7 @@ impl DamageCalculator {
             });
         }
         
-        // 4.4 类型相性
+        // 4.3 类型相性
         let type_effectiveness = self.calculate_type_effectiveness(context)?;
         final_damage *= type_effectiveness;
         if type_effectiveness != 1.0 {
@@ -167,7 +181,7 @@ impl DamageCalculator {
             });
         }
         
-        // 4.5 天气修正
+        // 4.4 天气修正
         let weather_multiplier = self.calculate_weather_modifier(context)?;
         if weather_multiplier != 1.0 {
             final_damage *= weather_multiplier;
@@ -178,6 +192,21 @@ impl DamageCalculator {
             });
         }
         
+        // 4.5 场地修正
+        let terrain_multiplier = context.environment.terrain_damage_modifier(
+            context.move_data.move_type,
+            context.attacker,
+            context.defender,
+        );
+        if terrain_multiplier != 1.0 {
+            final_damage *= terrain_multiplier;
+            modifiers.push(AppliedModifier {
+                name: "场地效果".to_string(),
+                multiplier: terrain_multiplier,
+                description: format!("场地修正 x{}", terrain_multiplier),
+            });
+        }
+        
         // 4.6 能力修正
         let ability_multiplier = self.calculate_ability_modifier(context)?;
         if ability_multiplier != 1.0 {
@@ -210,13 +239,10 @@ impl DamageCalculator {
             });
         }
         
-        // 5. 计算伤害范围 (考虑随机因子)
-        let min_damage = (base_damage * 0.85 * 
-            modifiers.iter().map(|m| m.multiplier).product::<f32>()).round() as u32;
-        let max_damage = (base_damage * 
-            modifiers.iter().map(|m| m.multiplier).product::<f32>()).round() as u32;
-        
-        let final_damage_int = final_damage.round() as u32;
+        // 5. 随机浮动 (85%-100%)，作为最后一步乘上
+        let final_damage_int = Self::apply_roll(final_damage, context.random_factor);
+        let min_damage = Self::apply_roll(final_damage, MIN_DAMAGE_ROLL_PERCENT as f32 / 100.0);
+        let max_damage = Self::apply_roll(final_damage, MAX_DAMAGE_ROLL_PERCENT as f32 / 100.0);
         
         // 6. 计算伤害百分比
         let defender_max_hp = context.defender.get_stats()?.hp as f32;
@@ -224,7 +250,7 @@ impl DamageCalculator {
         
         Ok(DamageResult {
             base_damage: base_damage as u32,
-            final_damage: final_damage_int.max(1), // 最少造成1点伤害
+            final_damage: final_damage_int,
             is_critical: context.critical_hit,
             type_effectiveness,
             modifiers,
@@ -233,6 +259,33 @@ impl DamageCalculator {
         })
     }
     
+    // 乘上浮动系数后向下取整，最少造成1点伤害
+    fn apply_roll(damage: f32, roll: f32) -> u32 {
+        let min_roll = MIN_DAMAGE_ROLL_PERCENT as f32 / 100.0;
+        let max_roll = MAX_DAMAGE_ROLL_PERCENT as f32 / 100.0;
+        ((damage * roll.clamp(min_roll, max_roll)).floor() as u32).max(1)
+    }
+    
+    // 用战斗随机数源掷出浮动系数后计算伤害
+    pub fn roll_damage(&self, context: &DamageContext, rng: &mut BattleRng) -> Result<DamageResult> {
+        let mut context = context.clone();
+        context.random_factor = rng.damage_roll();
+        self.calculate_damage(&context)
+    }
+    
+    // 浮动取最低和最高时的伤害，供AI评估和界面显示伤害范围
+    pub fn damage_range(&self, context: &DamageContext) -> Result<(u32, u32)> {
+        Ok(self.calculate_damage(context)?.damage_range)
+    }
+    
+    pub fn min_damage(&self, context: &DamageContext) -> Result<u32> {
+        Ok(self.damage_range(context)?.0)
+    }
+    
+    pub fn max_damage(&self, context: &DamageContext) -> Result<u32> {
+        Ok(self.damage_range(context)?.1)
+    }
+    
     // 计算一击必杀成功率
     pub fn calculate_ohko_chance(&self, context: &DamageContext) -> f32 {
         let level_diff = context.attacker.level as i16 - context.defender.level as i16;
@@ -401,24 +454,19 @@ impl DamageCalculator {
         weather_mods
     }
     
+    // 特性修正来自特性表，通过数据注册表读取data/abilities/abilities.json，没有时使用内置特性
     fn init_ability_modifiers() -> HashMap<String, DamageModifier> {
-        let mut modifiers = HashMap::new();
-        
-        // 技师特性 - 威力60以下的技能威力提升50%
-        modifiers.insert("technician".to_string(), DamageModifier {
-            multiplier: 1.5,
-            condition: None, // 这里应该检查技能威力
-            stage: ModifierStage::BeforeTypeEffectiveness,
-        });
-        
-        // 适应力特性 - 本系加成从1.5倍提升到2倍
-        modifiers.insert("adaptability".to_string(), DamageModifier {
-            multiplier: 2.0 / 1.5, // 补充到2倍
-            condition: None,
-            stage: ModifierStage::AfterTypeEffectiveness,
-        });
-        
-        modifiers
+        let abilities = DataRegistry::global().get_or_builtin(DataType::Abilities, builtin_abilities);
+        
+        abilities.iter()
+            .filter_map(|ability| {
+                ability.damage_multiplier.map(|multiplier| (ability.name.clone(), DamageModifier {
+                    multiplier,
+                    condition: None, // 这里应该检查技能威力等条件
+                    stage: ability.stage,
+                }))
+            })
+            .collect()
     }
     
     fn init_item_modifiers() -> HashMap<u32, DamageModifier> {
@@ -442,7 +490,54 @@ impl DamageCalculator {
     }
 }
 
+// 内置特性数据，数据目录中没有特性表时使用
+fn builtin_abilities() -> Vec<AbilityData> {
+    vec![
+        // 技师特性 - 威力60以下的技能威力提升50%
+        AbilityData {
+            id: 101,
+            name: "technician".to_string(),
+            damage_multiplier: Some(1.5),
+            stage: ModifierStage::BeforeTypeEffectiveness,
+        },
+        // 适应力特性 - 本系加成从1.5倍提升到2倍
+        AbilityData {
+            id: 91,
+            name: "adaptability".to_string(),
+            damage_multiplier: Some(2.0 / 1.5), // 补充到2倍
+            stage: ModifierStage::AfterTypeEffectiveness,
+        },
+    ]
+}
+
+impl TryFrom<HashMap<String, HashMap<String, f32>>> for TypeEffectivenessChart {
+    type Error = String;
+    
+    fn try_from(rows: HashMap<String, HashMap<String, f32>>) -> std::result::Result<Self, Self::Error> {
+        let parse = |name: &str| {
+            serde_json::from_value::<PokemonType>(serde_json::Value::String(name.to_string()))
+                .map_err(|_| format!("未知属性: {}", name))
+        };
+        
+        let mut chart = Self {
+            effectiveness: HashMap::new(),
+        };
+        for (attacking, row) in &rows {
+            let attacking = parse(attacking)?;
+            for (defending, &multiplier) in row {
+                chart.add_effectiveness(attacking, parse(defending)?, multiplier);
+            }
+        }
+        Ok(chart)
+    }
+}
+
 impl TypeEffectivenessChart {
+    // 通过数据注册表读取data/types/type_chart.json，没有时使用内置相克表
+    pub fn global() -> Arc<Self> {
+        DataRegistry::global().get_or_builtin(DataType::TypeChart, Self::new)
+    }
+    
     pub fn new() -> Self {
         let mut chart = Self {
             effectiveness: HashMap::new(),
@@ -633,16 +728,14 @@ pub fn create_damage_context<'a>(
         species.types.contains(&move_data.move_type)
     });
     
-    // 生成随机因子
-    let random_factor = fastrand::f32() * 0.15 + 0.85; // 0.85 - 1.0
-    
     DamageContext {
         attacker,
         defender,
         move_data,
         environment,
         critical_hit,
-        random_factor,
+        // 浮动系数由roll_damage用战斗随机数源掷出，默认不浮动
+        random_factor: MAX_DAMAGE_ROLL_PERCENT as f32 / 100.0,
         stab_bonus,
         multi_target: false,
         weather_boost: false,
@@ -680,6 +773,63 @@ mod tests {
         assert!(!calculator.type_chart.effectiveness.is_empty());
     }
     
+    #[test]
+    fn test_misty_terrain_halves_dragon_damage() {
+        use crate::battle::TerrainType;
+        
+        let calculator = DamageCalculator::new();
+        let attacker = Pokemon::new(25, 50, None, "Test".to_string(), "Test Location".to_string()).unwrap();
+        let defender = Pokemon::new(25, 50, None, "Test".to_string(), "Test Location".to_string()).unwrap();
+        let mut dragon_move = Move::clone(&Move::get(1).unwrap());
+        dragon_move.move_type = PokemonType::Dragon;
+        
+        let damage = |environment: &BattleEnvironment| {
+            let mut context = create_damage_context(&attacker, &defender, &dragon_move, environment, false);
+            context.random_factor = 1.0;
+            calculator.calculate_damage(&context).unwrap()
+        };
+        
+        let normal = damage(&BattleEnvironment::default());
+        let mut misty = BattleEnvironment::default();
+        misty.set_terrain(TerrainType::Misty, Some(5));
+        let halved = damage(&misty);
+        
+        assert!((normal.final_damage as f32 * 0.5 - halved.final_damage as f32).abs() <= 1.0);
+        assert!(halved.modifiers.iter().any(|m| m.name == "场地效果" && m.multiplier == 0.5));
+        
+        // 其他属性的技能不受薄雾场地影响
+        let tackle = Move::get(1).unwrap();
+        let mut context = create_damage_context(&attacker, &defender, &tackle, &misty, false);
+        context.random_factor = 1.0;
+        assert!(calculator.calculate_damage(&context).unwrap().modifiers.iter().all(|m| m.name != "场地效果"));
+    }
+    
+    #[test]
+    fn test_damage_rolls_within_range() {
+        let calculator = DamageCalculator::new();
+        let attacker = Pokemon::new(25, 50, None, "Test".to_string(), "Test Location".to_string()).unwrap();
+        let defender = Pokemon::new(25, 50, None, "Test".to_string(), "Test Location".to_string()).unwrap();
+        let tackle = Move::get(1).unwrap();
+        let environment = BattleEnvironment::default();
+        let context = create_damage_context(&attacker, &defender, &tackle, &environment, false);
+        
+        let (min, max) = calculator.damage_range(&context).unwrap();
+        assert_eq!(max, calculator.calculate_damage(&context).unwrap().final_damage);
+        assert_eq!(calculator.min_damage(&context).unwrap(), min);
+        assert!(min <= max);
+        
+        let mut rng = BattleRng::with_seed(1);
+        let rolls: Vec<u32> = (0..200)
+            .map(|_| calculator.roll_damage(&context, &mut rng).unwrap().final_damage)
+            .collect();
+        assert!(rolls.iter().all(|damage| (min..=max).contains(damage)));
+        assert!(rolls.iter().all(|&damage| damage as f32 >= max as f32 * 0.85 - 1.0));
+        
+        // 同一种子掷出同样的伤害序列
+        let mut replay = BattleRng::with_seed(1);
+        assert_eq!(calculator.roll_damage(&context, &mut replay).unwrap().final_damage, rolls[0]);
+    }
+    
     // 注意：完整的伤害计算测试需要创建完整的Pokemon和Move实例
     // 这里只是基本的结构测试
 }
\ No newline at end of file
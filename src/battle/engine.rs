@@ -4,10 +4,12 @@
 
 use crate::core::{GameError, Result};
 use crate::pokemon::{Pokemon, Move, MoveId};
+use crate::pokemon::moves::MoveEffect;
 use crate::battle::{
     BattleAction, BattleParticipant, BattleEnvironment, 
     TurnManager, DamageCalculator, StatusManager, BattleAnimator,
-    TurnPhase, DamageResult, SecondaryEffect
+    TurnPhase, BattleRng, apply_secondary_effect, pair_mut, create_damage_context,
+    DamageResult, CRITICAL_HIT_CHANCE,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -51,6 +53,7 @@ pub struct BattleEngine {
     damage_calculator: DamageCalculator,
     status_manager: StatusManager,
     animator: BattleAnimator,
+    rng: BattleRng,
     
     // 战斗统计
     turn_count: u32,
@@ -152,6 +155,7 @@ impl BattleEngine {
             damage_calculator: DamageCalculator::new(),
             status_manager: StatusManager::new(),
             animator: BattleAnimator::new(),
+            rng: BattleRng::new(),
             turn_count: 0,
             battle_log: Vec::new(),
             debug_mode,
@@ -198,7 +202,7 @@ impl BattleEngine {
     // 处理行动执行阶段
     fn handle_action_execution(&mut self) -> Result<()> {
         // 获取按速度排序的行动列表
-        let actions = self.turn_manager.get_sorted_actions(&self.participants)?;
+        let actions = self.turn_manager.get_sorted_actions(&self.participants, &mut self.rng)?;
         
         for (trainer_id, action) in actions {
             if !self.state.is_active {
@@ -313,6 +317,7 @@ impl BattleEngine {
         let target_pokemon_id = self.determine_target(target_id, &participant.team)?;
         
         // 计算伤害
+        let mut hit = true;
         let damage_result = if let Some(target_id) = target_pokemon_id {
             let target_participant = self.participants.iter()
                 .find(|p| p.team.contains(&target_id))
@@ -321,36 +326,44 @@ impl BattleEngine {
             let target_pokemon = target_participant.get_pokemon(target_id)
                 .ok_or_else(|| GameError::BattleError("找不到目标宝可梦数据".to_string()))?;
             
-            let damage_result = self.damage_calculator.calculate_damage(
+            let rolled = roll_move_damage(
+                &self.damage_calculator,
+                &mut self.rng,
                 pokemon,
                 target_pokemon,
-                move_data,
-                &self.environment
+                &move_data,
+                &self.environment,
             )?;
+            hit = rolled.is_some();
+            if !hit {
+                info!("{}的攻击没有命中", pokemon.get_display_name());
+            }
+            let damage = rolled.as_ref().map_or(0, |r| r.final_damage.min(u16::MAX as u32) as u16);
             
             // 应用伤害
-            if damage_result.hit && damage_result.damage > 0 {
+            if damage > 0 {
                 let target_participant = self.participants.iter_mut()
                     .find(|p| p.team.contains(&target_id))
                     .unwrap();
                 let target_pokemon = target_participant.get_pokemon_mut(target_id).unwrap();
                 
-                let is_fainted = target_pokemon.take_damage(damage_result.damage);
+                let is_fainted = target_pokemon.take_damage(damage);
                 if is_fainted {
                     info!("{}失去了战斗能力！", target_pokemon.get_display_name());
                 }
             }
             
-            Some(damage_result)
+            rolled
         } else {
             None
         };
         
-        // 应用附加效果
-        if let Some(ref secondary_effect) = move_data.secondary_effect {
-            if fastrand::f32() < secondary_effect.chance {
-                if let Some(target_id) = target_pokemon_id {
-                    self.status_manager.apply_effect(target_id, secondary_effect.clone())?;
+        // 应用附加效果，和BattleContext一样用战斗随机数判定并直接作用在双方宝可梦上；没有命中时跳过
+        if let Some(target_id) = target_pokemon_id.filter(|_| hit) {
+            let damage_dealt = damage_result.as_ref().map_or(0, |r| r.final_damage.min(u16::MAX as u32) as u16);
+            for secondary in &move_data.secondary_effects {
+                if self.rng.chance(secondary.trigger_chance()) {
+                    self.apply_move_secondary_effect(&secondary.effect, pokemon_id, target_id, damage_dealt)?;
                 }
             }
         }
@@ -359,13 +372,48 @@ impl BattleEngine {
             user_id: pokemon_id,
             target_id: target_pokemon_id.unwrap_or(0),
             move_id,
-            damage: damage_result.as_ref().map(|r| r.damage),
-            hit: damage_result.as_ref().map_or(true, |r| r.hit),
-            critical: damage_result.as_ref().map_or(false, |r| r.critical),
+            damage: damage_result.as_ref().map(|r| r.final_damage.min(u16::MAX as u32) as u16),
+            hit,
+            critical: damage_result.as_ref().is_some_and(|r| r.is_critical),
             effectiveness: damage_result.as_ref().map_or(1.0, |r| r.type_effectiveness),
         })
     }
     
+    // 按宝可梦ID找到(参战者序号, 队伍中的位置)
+    fn locate_pokemon(&self, pokemon_id: u64) -> Result<(usize, usize)> {
+        self.participants.iter()
+            .enumerate()
+            .find_map(|(slot, p)| p.pokemon.iter().position(|pokemon| pokemon.id == pokemon_id).map(|index| (slot, index)))
+            .ok_or_else(|| GameError::BattleError("找不到指定的宝可梦".to_string()))
+    }
+    
+    // 找到使用者和目标后结算附加效果
+    fn apply_move_secondary_effect(
+        &mut self,
+        effect: &MoveEffect,
+        user_id: u64,
+        target_id: u64,
+        damage_dealt: u16,
+    ) -> Result<()> {
+        let (user_slot, user_index) = self.locate_pokemon(user_id)?;
+        let (target_slot, target_index) = self.locate_pokemon(target_id)?;
+        
+        let outcome = if (user_slot, user_index) == (target_slot, target_index) {
+            let user = &mut self.participants[user_slot].pokemon[user_index];
+            apply_secondary_effect(effect, &self.environment, user, None, damage_dealt, &mut self.rng)?
+        } else {
+            let (user, target) = if user_slot == target_slot {
+                pair_mut(&mut self.participants[user_slot].pokemon, user_index, target_index)
+            } else {
+                let (user_participant, target_participant) = pair_mut(&mut self.participants, user_slot, target_slot);
+                (&mut user_participant.pokemon[user_index], &mut target_participant.pokemon[target_index])
+            };
+            apply_secondary_effect(effect, &self.environment, user, Some(target), damage_dealt, &mut self.rng)?
+        };
+        debug!("附加效果 {:?}: {:?}", effect, outcome);
+        Ok(())
+    }
+    
     // 执行换宝可梦行动
     fn execute_switch_action(
         &mut self,
@@ -575,6 +623,23 @@ impl BattleEngine {
     }
 }
 
+// 命中、暴击和伤害浮动都使用战斗随机数，和BattleContext一样同一种子可以回放；没有命中时返回None
+fn roll_move_damage(
+    calculator: &DamageCalculator,
+    rng: &mut BattleRng,
+    attacker: &Pokemon,
+    defender: &Pokemon,
+    move_data: &Move,
+    environment: &BattleEnvironment,
+) -> Result<Option<DamageResult>> {
+    if !move_data.accuracy.map_or(true, |accuracy| rng.chance(accuracy as f32 / 100.0)) {
+        return Ok(None);
+    }
+    let critical = rng.chance(CRITICAL_HIT_CHANCE);
+    let context = create_damage_context(attacker, defender, move_data, environment, critical);
+    calculator.roll_damage(&context, rng).map(Some)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -585,4 +650,33 @@ mod tests {
         // 这个测试需要更完整的宝可梦数据才能运行
         // 现在只测试基础结构
     }
+    
+    #[test]
+    fn test_move_rolls_misses_crits_and_damage() {
+        let pokemon = || Pokemon::new(25, 50, None, "Test".to_string(), "Test Location".to_string()).unwrap();
+        let (attacker, defender) = (pokemon(), pokemon());
+        let mut move_data = (*Move::get(84).unwrap()).clone();
+        move_data.accuracy = Some(70);
+        let calculator = DamageCalculator::new();
+        let environment = BattleEnvironment::default();
+        
+        let rolls: Vec<_> = (0..200)
+            .map(|seed| {
+                let mut rng = BattleRng::with_seed(seed);
+                roll_move_damage(&calculator, &mut rng, &attacker, &defender, &move_data, &environment).unwrap()
+            })
+            .collect();
+        
+        let hits: Vec<_> = rolls.iter().flatten().collect();
+        assert!(hits.len() < rolls.len(), "70%命中率的技能应该有未命中");
+        assert!(hits.iter().any(|r| r.is_critical));
+        assert!(hits.iter().any(|r| !r.is_critical));
+        // 非暴击的伤害带随机浮动，不是固定值
+        let normal: Vec<_> = hits.iter().filter(|r| !r.is_critical).map(|r| r.final_damage).collect();
+        assert!(normal.iter().any(|&damage| damage != normal[0]));
+        
+        // 同一种子结果相同
+        let replay = roll_move_damage(&calculator, &mut BattleRng::with_seed(0), &attacker, &defender, &move_data, &environment).unwrap();
+        assert_eq!(replay.map(|r| r.final_damage), rolls[0].as_ref().map(|r| r.final_damage));
+    }
 }
\ No newline at end of file
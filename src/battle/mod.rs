@@ -6,21 +6,27 @@
 pub mod engine;
 pub mod turn_manager;
 pub mod damage_calculator;
+pub mod rng;
+pub mod capture;
 // pub mod status_effects;
 // pub mod animation;
 
 // 重新导出已实现的类型
 pub use engine::{BattleEngine, BattleLogEntry, BattleActionResult};
 pub use turn_manager::{TurnManager as NewTurnManager, BattleAction, ActionResult, TurnResult, ParticipantId};
-pub use damage_calculator::{DamageCalculator as NewDamageCalculator, DamageResult as NewDamageResult, DamageContext};
+pub use damage_calculator::{DamageCalculator, DamageResult, DamageContext, create_damage_context};
+pub use rng::{BattleRng, CRITICAL_HIT_CHANCE};
+pub use capture::{CaptureResult, PokeBall};
 // pub use status_effects::{StatusEffect, StatusManager, EffectTrigger};
 // pub use animation::{BattleAnimator, AnimationType, AnimationQueue};
 
 use crate::core::{GameError, Result};
-use crate::pokemon::{Pokemon, Move, MoveId};
+use crate::pokemon::{Pokemon, Move, MoveId, StatusCondition, ItemId, AbilityId};
+use crate::pokemon::moves::{WeatherType, MoveEffect, EffectTarget, StatType, StatusEffect};
 use crate::core::event_system::{Event, EventSystem};
+use crate::game_modes::{FaintOutcome, GameModeManager, SharedGameModes, StatType as GameStat};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::time::{Duration, Instant};
 use log::{info, debug, warn};
 
@@ -32,61 +38,211 @@ pub enum TurnPhase {
     EndTurn,
 }
 
-pub struct TurnManager;
-pub struct DamageCalculator;
+pub struct TurnManager {
+    actions: Vec<(u64, BattleAction)>,
+}
 pub struct StatusManager;
 pub struct BattleAnimator;
 
-// 临时结构定义
-#[derive(Debug, Clone)]
-pub struct DamageResult {
-    pub damage: u16,
-    pub is_critical: bool,
-    pub effectiveness: f32,
+// 能力等级的上下限
+pub const MAX_STAT_STAGE: i8 = 6;
+
+// 附加效果的结算结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EffectOutcome {
+    // 能力等级的实际变化量，已到上下限时为0
+    pub stages_changed: i8,
+    pub status_applied: bool,
+    pub flinched: bool,
+    // 使用者HP的变化，回复为正、反伤为负
+    pub user_hp_change: i32,
 }
 
-#[derive(Debug, Clone)]
-pub struct SecondaryEffect {
-    pub effect_type: String,
-    pub chance: f32,
+fn stat_stage_mut(stages: &mut crate::pokemon::StatStages, stat: StatType) -> &mut i8 {
+    match stat {
+        StatType::Attack => &mut stages.attack,
+        StatType::Defense => &mut stages.defense,
+        StatType::SpecialAttack => &mut stages.special_attack,
+        StatType::SpecialDefense => &mut stages.special_defense,
+        StatType::Speed => &mut stages.speed,
+        StatType::Accuracy => &mut stages.accuracy,
+        StatType::Evasion => &mut stages.evasion,
+    }
 }
 
-impl TurnManager {
-    pub fn new() -> Self { Self }
-    pub fn add_action(&mut self, _trainer_id: u64, _action: BattleAction) -> Result<()> { Ok(()) }
-    pub fn all_actions_submitted(&self, _participants: &[BattleParticipant]) -> bool { true }
-    pub fn get_sorted_actions(&self, _participants: &[BattleParticipant]) -> Result<Vec<(u64, BattleAction)>> { Ok(vec![]) }
-    pub fn clear_actions(&mut self) {}
+// 同时可变借用切片中两个不同的元素
+fn pair_mut<T>(items: &mut [T], first: usize, second: usize) -> (&mut T, &mut T) {
+    assert_ne!(first, second, "不能同时借用同一个元素");
+    if first < second {
+        let (left, right) = items.split_at_mut(second);
+        (&mut left[first], &mut right[0])
+    } else {
+        let (left, right) = items.split_at_mut(first);
+        (&mut right[0], &mut left[second])
+    }
 }
 
-impl DamageCalculator {
-    pub fn new() -> Self { Self }
-    pub fn calculate_damage(
-        &self, 
-        _user: &Pokemon, 
-        _target: &Pokemon, 
-        _move_data: &Move, 
-        _env: &BattleEnvironment
-    ) -> Result<DamageResult> {
-        Ok(DamageResult {
-            damage: 50,
-            hit: true,
-            critical: false,
-            type_effectiveness: 1.0,
-        })
+// 结算一个已经触发的附加效果；target为None表示技能以使用者自己为目标，damage_dealt为本次造成的伤害
+// 畏缩只在结果中标记，由战斗在本回合内记录，不会作为异常状态留在宝可梦身上
+pub fn apply_secondary_effect(
+    effect: &MoveEffect,
+    environment: &BattleEnvironment,
+    user: &mut Pokemon,
+    target: Option<&mut Pokemon>,
+    damage_dealt: u16,
+    rng: &mut BattleRng,
+) -> Result<EffectOutcome> {
+    let mut outcome = EffectOutcome::default();
+    // 反伤和吸取至少1点，没有造成伤害时不生效
+    let share = |fraction: f32| {
+        if damage_dealt == 0 {
+            0
+        } else {
+            ((damage_dealt as f32 * fraction).round() as u16).max(1)
+        }
+    };
+    
+    match effect {
+        MoveEffect::StatChange { target: affected, stat, stages, .. } => {
+            let pokemon = match target {
+                Some(target) if *affected != EffectTarget::User => target,
+                _ => user,
+            };
+            let stage = stat_stage_mut(&mut pokemon.stat_stages, *stat);
+            let old = *stage;
+            *stage = (old + stages).clamp(-MAX_STAT_STAGE, MAX_STAT_STAGE);
+            outcome.stages_changed = *stage - old;
+        },
+        MoveEffect::StatusChange { target: affected, status, .. } => {
+            if *status == StatusEffect::None {
+                return Ok(outcome);
+            }
+            let pokemon = match target {
+                Some(target) if *affected != EffectTarget::User => target,
+                _ => user,
+            };
+            // 睡眠持续1到3回合
+            let status = status.to_condition(1 + rng.index(3) as u8);
+            outcome.status_applied = inflict_status(environment, pokemon, status);
+        },
+        MoveEffect::Confusion { .. } => {
+            if let Some(target) = target {
+                // 混乱持续2到5回合
+                let confusion = StatusCondition::Confusion { turns_remaining: 2 + rng.index(4) as u8 };
+                outcome.status_applied = inflict_status(environment, target, confusion);
+            }
+        },
+        MoveEffect::Flinch { .. } => {
+            if let Some(target) = target {
+                outcome.flinched = !target.is_fainted();
+            }
+        },
+        MoveEffect::Recoil { damage_ratio } => {
+            let recoil = share(*damage_ratio).min(user.current_hp);
+            user.take_damage(recoil);
+            outcome.user_hp_change = -(recoil as i32);
+        },
+        MoveEffect::Drain { drain_ratio } => {
+            if !user.is_fainted() {
+                outcome.user_hp_change = user.heal(share(*drain_ratio))? as i32;
+            }
+        },
+        _ => {
+            debug!("暂不支持的附加效果: {:?}", effect);
+        },
     }
+    
+    Ok(outcome)
 }
 
-// DamageResult重复定义已移除，使用第一个定义
+// 让宝可梦陷入异常状态，受场地保护、已濒死或已有同类状态时不生效
+fn inflict_status(environment: &BattleEnvironment, pokemon: &mut Pokemon, status: StatusCondition) -> bool {
+    if environment.terrain_blocks_status(pokemon, &status) {
+        debug!("{} 受到场地保护，没有陷入 {:?}", pokemon.get_display_name(), status);
+        false
+    } else if !pokemon.is_fainted() && !pokemon.has_status(&status) {
+        pokemon.apply_status(status)
+    } else {
+        false
+    }
+}
+
+// 换人、道具、投球和逃跑先于所有技能行动
+const NON_MOVE_ACTION_PRIORITY: i8 = 7;
+
+impl TurnManager {
+    pub fn new() -> Self { Self { actions: Vec::new() } }
+    
+    // 每名训练师每回合一个行动，重复提交时以最后一次为准
+    pub fn add_action(&mut self, trainer_id: u64, action: BattleAction) -> Result<()> {
+        self.actions.retain(|(id, _)| *id != trainer_id);
+        self.actions.push((trainer_id, action));
+        Ok(())
+    }
+    
+    // 所有还有宝可梦在场、没有认输的玩家训练师都已提交行动；AI的行动在回合开始前自动选择
+    pub fn all_actions_submitted(&self, participants: &[BattleParticipant]) -> bool {
+        participants
+            .iter()
+            .filter(|p| !p.forfeited && !p.is_ai)
+            .filter(|p| p.active_pokemon.iter().flatten().any(|&index| !p.pokemon[index].is_fainted()))
+            .all(|p| self.actions.iter().any(|(id, _)| *id == p.trainer_id))
+    }
+    
+    // 先按行动优先度、再按出手宝可梦的速度从高到低排序，完全相同时由战斗随机数决定先后；已认输训练师的行动被丢弃
+    pub fn get_sorted_actions(&self, participants: &[BattleParticipant], rng: &mut BattleRng) -> Result<Vec<(u64, BattleAction)>> {
+        let mut keyed: Vec<((i8, u32), (u64, BattleAction))> = self.actions
+            .iter()
+            .filter_map(|(trainer_id, action)| {
+                let participant = participants.iter().find(|p| p.trainer_id == *trainer_id && !p.forfeited)?;
+                Some(((Self::action_priority(participant, action), Self::action_speed(participant, action)), (*trainer_id, action.clone())))
+            })
+            .collect();
+        // 先打乱再稳定排序，同速的行动之间顺序随机
+        rng.shuffle(&mut keyed);
+        keyed.sort_by(|a, b| b.0.cmp(&a.0));
+        Ok(keyed.into_iter().map(|(_, action)| action).collect())
+    }
+    
+    pub fn clear_actions(&mut self) {
+        self.actions.clear();
+    }
+    
+    fn action_priority(participant: &BattleParticipant, action: &BattleAction) -> i8 {
+        match action {
+            BattleAction::UseMove { pokemon_index, move_index, .. } => participant.pokemon
+                .get(*pokemon_index)
+                .and_then(|pokemon| pokemon.moves.get(*move_index))
+                .and_then(|slot| Move::get(slot.move_id))
+                .map_or(0, |move_data| move_data.priority),
+            _ => NON_MOVE_ACTION_PRIORITY,
+        }
+    }
+    
+    // 计入能力等级的速度
+    fn action_speed(participant: &BattleParticipant, action: &BattleAction) -> u32 {
+        let pokemon_index = match action {
+            BattleAction::UseMove { pokemon_index, .. } => Some(*pokemon_index),
+            _ => participant.active_pokemon.first().copied().flatten(),
+        };
+        let Some(pokemon) = pokemon_index.and_then(|index| participant.pokemon.get(index)) else {
+            return 0;
+        };
+        let speed = pokemon.get_stats().map_or(0, |stats| stats.speed as u32);
+        let stage = pokemon.stat_stages.speed.clamp(-MAX_STAT_STAGE, MAX_STAT_STAGE) as i32;
+        if stage >= 0 {
+            speed * (2 + stage) as u32 / 2
+        } else {
+            speed * 2 / (2 - stage) as u32
+        }
+    }
+}
 
 impl StatusManager {
     pub fn new() -> Self { Self }
-    pub fn apply_effect(&mut self, _target_id: u64, _effect: SecondaryEffect) -> Result<()> { Ok(()) }
     pub fn process_end_turn_effects(&mut self, _participants: &mut [BattleParticipant]) -> Result<()> { Ok(()) }
 }
 
-// SecondaryEffect重复定义已移除，使用第一个定义
-
 impl BattleAnimator {
     pub fn new() -> Self { Self }
     pub fn start_move_animation(&mut self, _trainer_id: u64, _pokemon_index: usize, _move_id: MoveId) -> Result<()> { Ok(()) }
@@ -198,6 +354,15 @@ pub struct BattleParticipant {
     pub pokemon: Vec<Pokemon>, // 原名为team，但为兼容性改为pokemon
     pub active_pokemon_index: usize,
     pub active_pokemon: Vec<Option<usize>>, // 场上宝可梦索引
+    // 所属的一方，由BattleContext::new按参与者顺序分配，同一方的训练师互为队友
+    #[serde(default)]
+    pub side: usize,
+    // 多人对战中认输的训练师留在参与者列表里，但不再行动、不能被选为目标
+    #[serde(default)]
+    pub forfeited: bool,
+    // 带进战斗的道具及数量，由发起战斗的一方从背包填入，投球等消耗直接在这里扣除
+    #[serde(default)]
+    pub items: HashMap<ItemId, u32>,
     pub is_ai: bool,
     pub ai_difficulty: AIDifficulty,
 }
@@ -210,11 +375,32 @@ impl BattleParticipant {
             pokemon,
             active_pokemon_index: 0,
             active_pokemon: vec![Some(0)],
+            side: 0,
+            forfeited: false,
+            items: HashMap::new(),
             is_ai: false,
             ai_difficulty: AIDifficulty::Normal,
         }
     }
     
+    pub fn item_count(&self, item_id: ItemId) -> u32 {
+        self.items.get(&item_id).copied().unwrap_or(0)
+    }
+    
+    // 消耗一个道具，数量不足时返回错误
+    pub fn consume_item(&mut self, item_id: ItemId) -> Result<()> {
+        match self.items.get_mut(&item_id) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                if *count == 0 {
+                    self.items.remove(&item_id);
+                }
+                Ok(())
+            }
+            _ => Err(GameError::BattleError(format!("道具 {} 已经用完", item_id))),
+        }
+    }
+    
     pub fn team(&self) -> &[Pokemon] {
         &self.pokemon
     }
@@ -244,6 +430,11 @@ pub enum BattleAction {
         item_id: u32,
         target: Option<usize>,
     },
+    // 野生战斗中对场上的野生宝可梦投出精灵球，target为对方场上位置的序号
+    ThrowBall {
+        ball_id: u32,
+        target: usize,
+    },
     Run,
     Forfeit,
 }
@@ -332,6 +523,7 @@ pub struct BattleEnvironment {
     pub weather: Option<crate::pokemon::moves::WeatherType>,
     pub weather_turns: Option<u8>,
     pub terrain: TerrainType,
+    pub terrain_turns: Option<u8>,
     pub field_effects: Vec<FieldEffect>,
     pub trick_room: bool,
     pub gravity: bool,
@@ -389,6 +581,7 @@ impl Default for BattleEnvironment {
             weather: None,
             weather_turns: None,
             terrain: TerrainType::None,
+            terrain_turns: None,
             field_effects: Vec::new(),
             trick_room: false,
             gravity: false,
@@ -398,6 +591,171 @@ impl Default for BattleEnvironment {
     }
 }
 
+impl BattleEnvironment {
+    // 由野外天气初始化场地，野外带入的天气不计回合
+    pub fn from_overworld(weather: crate::world::Weather) -> Self {
+        Self {
+            weather: weather.to_battle_weather(),
+            ..Self::default()
+        }
+    }
+    
+    // 设置天气，turns为None时天气不会自然结束；晴朗等同于清除天气
+    pub fn set_weather(&mut self, weather: WeatherType, turns: Option<u8>) {
+        if weather == WeatherType::Clear {
+            self.weather = None;
+            self.weather_turns = None;
+        } else {
+            self.weather = Some(weather);
+            self.weather_turns = turns;
+        }
+    }
+    
+    pub fn set_terrain(&mut self, terrain: TerrainType, turns: Option<u8>) {
+        self.terrain = terrain;
+        self.terrain_turns = if terrain == TerrainType::None { None } else { turns };
+    }
+    
+    // 回合结束时调用，剩余回合归零的天气和场地被清除
+    pub fn tick_durations(&mut self) {
+        if let Some(turns) = self.weather_turns {
+            let remaining = turns.saturating_sub(1);
+            if remaining == 0 {
+                self.set_weather(WeatherType::Clear, None);
+            } else {
+                self.weather_turns = Some(remaining);
+            }
+        }
+        
+        if let Some(turns) = self.terrain_turns {
+            let remaining = turns.saturating_sub(1);
+            if remaining == 0 {
+                self.set_terrain(TerrainType::None, None);
+            } else {
+                self.terrain_turns = Some(remaining);
+            }
+        }
+    }
+    
+    // 场地只影响着地的宝可梦：飞行属性和飘浮特性不着地，重力下全部着地
+    pub fn is_grounded(&self, pokemon: &Pokemon) -> bool {
+        if self.gravity {
+            return true;
+        }
+        let flying = pokemon.get_species()
+            .is_ok_and(|species| species.types.contains(&crate::pokemon::PokemonType::Flying));
+        !flying && pokemon.ability_id != ABILITY_LEVITATE
+    }
+    
+    // 场地对技能威力的修正：强化着地使用者的对应属性技能，薄雾场地减半对着地目标的龙属性伤害
+    pub fn terrain_damage_modifier(
+        &self,
+        move_type: crate::pokemon::PokemonType,
+        attacker: &Pokemon,
+        defender: &Pokemon,
+    ) -> f32 {
+        use crate::pokemon::PokemonType;
+        
+        match (self.terrain, move_type) {
+            (TerrainType::Grassy, PokemonType::Grass)
+            | (TerrainType::Electric, PokemonType::Electric)
+            | (TerrainType::Psychic, PokemonType::Psychic)
+                if self.is_grounded(attacker) => TERRAIN_BOOST,
+            (TerrainType::Misty, PokemonType::Dragon) if self.is_grounded(defender) => 0.5,
+            _ => 1.0,
+        }
+    }
+    
+    // 电气场地阻止着地的宝可梦睡眠，薄雾场地阻止着地的宝可梦陷入异常状态和混乱
+    pub fn terrain_blocks_status(&self, target: &Pokemon, status: &StatusCondition) -> bool {
+        if !self.is_grounded(target) {
+            return false;
+        }
+        match self.terrain {
+            TerrainType::Electric => matches!(status, StatusCondition::Sleep { .. }),
+            TerrainType::Misty => !matches!(
+                status,
+                StatusCondition::None | StatusCondition::Flinch | StatusCondition::Infatuation
+            ),
+            _ => false,
+        }
+    }
+    
+    // 精神场地保护着地的宝可梦不受先制技能攻击
+    pub fn terrain_blocks_priority(&self, priority: i8, target: &Pokemon) -> bool {
+        self.terrain == TerrainType::Psychic && priority > 0 && self.is_grounded(target)
+    }
+    
+    // 青草场地每回合为着地的宝可梦回复最大HP的1/16
+    pub fn terrain_heal_amount(&self, pokemon: &Pokemon) -> Result<u16> {
+        if self.terrain != TerrainType::Grassy || pokemon.is_fainted() || !self.is_grounded(pokemon) {
+            return Ok(0);
+        }
+        Ok((pokemon.get_stats()?.hp / 16).max(1))
+    }
+}
+
+// 场地对对应属性技能的威力加成
+pub const TERRAIN_BOOST: f32 = 1.3;
+
+// 飘浮特性的宝可梦不受场地影响
+pub const ABILITY_LEVITATE: AbilityId = 26;
+
+// 延长天气和场地持续时间的携带道具
+pub const ITEM_HEAT_ROCK: ItemId = 284;
+pub const ITEM_DAMP_ROCK: ItemId = 285;
+pub const ITEM_SMOOTH_ROCK: ItemId = 283;
+pub const ITEM_ICY_ROCK: ItemId = 282;
+pub const ITEM_TERRAIN_EXTENDER: ItemId = 879;
+
+// 携带对应道具时天气和场地额外持续的回合数
+pub const EXTENDED_DURATION_BONUS: u8 = 3;
+
+// 原始回归的特性带来的天气不会自然结束
+pub const ABILITY_PRIMORDIAL_SEA: AbilityId = 189;
+pub const ABILITY_DESOLATE_LAND: AbilityId = 190;
+
+// 延长该天气的岩石道具
+fn weather_rock(weather: WeatherType) -> Option<ItemId> {
+    match weather {
+        WeatherType::Sun => Some(ITEM_HEAT_ROCK),
+        WeatherType::Rain => Some(ITEM_DAMP_ROCK),
+        WeatherType::Sandstorm => Some(ITEM_SMOOTH_ROCK),
+        WeatherType::Hail => Some(ITEM_ICY_ROCK),
+        WeatherType::Fog | WeatherType::Clear => None,
+    }
+}
+
+// 天气持续回合：原始特性为无限，携带对应岩石时延长，否则为基础回合数
+pub fn weather_duration(weather: WeatherType, base_turns: u8, setter: Option<&Pokemon>) -> Option<u8> {
+    let Some(setter) = setter else {
+        return Some(base_turns);
+    };
+    
+    let permanent = matches!(
+        (setter.ability_id, weather),
+        (ABILITY_PRIMORDIAL_SEA, WeatherType::Rain) | (ABILITY_DESOLATE_LAND, WeatherType::Sun)
+    );
+    if permanent {
+        return None;
+    }
+    
+    if setter.held_item.is_some() && setter.held_item == weather_rock(weather) {
+        Some(base_turns.saturating_add(EXTENDED_DURATION_BONUS))
+    } else {
+        Some(base_turns)
+    }
+}
+
+pub fn terrain_duration(base_turns: u8, setter: Option<&Pokemon>) -> Option<u8> {
+    match setter {
+        Some(setter) if setter.held_item == Some(ITEM_TERRAIN_EXTENDER) => {
+            Some(base_turns.saturating_add(EXTENDED_DURATION_BONUS))
+        },
+        _ => Some(base_turns),
+    }
+}
+
 // 战斗配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BattleConfig {
@@ -405,6 +763,8 @@ pub struct BattleConfig {
     pub battle_format: BattleFormat,
     pub time_limit_seconds: Option<u32>,
     pub level_cap: Option<u8>,
+    #[serde(default)]
+    pub level_cap_mode: LevelCapMode,
     pub item_clause: bool,
     pub sleep_clause: bool,
     pub species_clause: bool,
@@ -422,6 +782,7 @@ impl Default for BattleConfig {
             battle_format: BattleFormat::Trainer,
             time_limit_seconds: Some(300), // 5分钟
             level_cap: None,
+            level_cap_mode: LevelCapMode::Reject,
             item_clause: false,
             sleep_clause: false,
             species_clause: false,
@@ -434,6 +795,34 @@ impl Default for BattleConfig {
     }
 }
 
+// 队伍中有宝可梦超过等级上限时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LevelCapMode {
+    // 拒绝组建战斗
+    #[default]
+    Reject,
+    // 战斗中临时降到上限等级并重新计算能力值，低于上限的不变
+    Scale,
+}
+
+// 返回降到指定等级后的副本，HP按原来的比例换算，传入的宝可梦不变
+pub fn scale_to_level(pokemon: &Pokemon, level: u8) -> Result<Pokemon> {
+    let mut scaled = pokemon.clone();
+    if pokemon.level <= level {
+        return Ok(scaled);
+    }
+    
+    let old_max_hp = pokemon.get_stats()?.hp.max(1) as u32;
+    scaled.level = level;
+    scaled.calculate_stats()?;
+    let new_max_hp = scaled.get_stats()?.hp as u32;
+    
+    // 换算后仍然保留至少1点HP，避免未濒死的宝可梦因取整变成濒死
+    let hp = (pokemon.current_hp as u32 * new_max_hp + old_max_hp / 2) / old_max_hp;
+    scaled.current_hp = if pokemon.is_fainted() { 0 } else { hp.clamp(1, new_max_hp) as u16 };
+    Ok(scaled)
+}
+
 // 战斗上下文
 pub struct BattleContext {
     pub battle_id: u64,
@@ -446,6 +835,18 @@ pub struct BattleContext {
     pub start_time: Instant,
     pub last_action_time: Instant,
     
+    pub rng: BattleRng,
+    // 野生战斗中捕获成功的宝可梦，由调用方取走放入队伍或电脑
+    pub captured_pokemon: Option<Pokemon>,
+    // 本回合畏缩的宝可梦(训练师ID, 队伍中的位置)，回合结束时清空
+    pub flinched: HashSet<(u64, usize)>,
+    
+    // 规则模式和战斗所在的道路，捕获和濒死经过它的钩子
+    pub route: Option<String>,
+    game_modes: SharedGameModes,
+    // 战斗结束时规则要求永久失去的玩家宝可梦ID，由调用方从队伍中放生
+    pub released_pokemon: Vec<u64>,
+    
     // 战斗统计
     pub stats: BattleStats,
     
@@ -472,16 +873,59 @@ impl BattleContext {
         battle_id: u64,
         config: BattleConfig,
         participants: Vec<BattleParticipant>,
+    ) -> Result<Self> {
+        Self::with_game_modes(battle_id, config, participants, GameModeManager::global())
+    }
+    
+    // 使用指定的规则模式创建战斗
+    pub fn with_game_modes(
+        battle_id: u64,
+        config: BattleConfig,
+        mut participants: Vec<BattleParticipant>,
+        game_modes: SharedGameModes,
     ) -> Result<Self> {
         if participants.len() < 2 {
             return Err(GameError::BattleError("至少需要两个参与者".to_string()));
         }
         
+        // 多人对战为四名训练师两两组队：前两名为一方，后两名为另一方；其他类型每名训练师各自为一方
+        if config.battle_type == BattleType::Multi {
+            if participants.len() != crate::constants::MAX_BATTLE_PARTICIPANTS {
+                return Err(GameError::BattleError(format!(
+                    "多人对战需要{}名训练师，当前 {}",
+                    crate::constants::MAX_BATTLE_PARTICIPANTS,
+                    participants.len()
+                )));
+            }
+            for (slot, participant) in participants.iter_mut().enumerate() {
+                participant.side = slot / 2;
+            }
+        } else {
+            for (slot, participant) in participants.iter_mut().enumerate() {
+                participant.side = slot;
+            }
+        }
+        
         // 验证参与者队伍
         for participant in &participants {
             if participant.pokemon.is_empty() {
                 return Err(GameError::BattleError("参与者队伍不能为空".to_string()));
             }
+            Self::validate_team_clauses(&config, participant)?;
+        }
+        
+        // 规则模式可能禁止某些玩家宝可梦出场(如Nuzlocke中已经永久失去的)
+        {
+            let modes = game_modes.lock().unwrap();
+            for participant in participants.iter().filter(|p| !p.is_ai) {
+                for pokemon in &participant.pokemon {
+                    modes.check_battle_pokemon(pokemon.id)?;
+                }
+            }
+        }
+        
+        if let Some(level_cap) = config.level_cap {
+            Self::apply_level_cap(level_cap, config.level_cap_mode, &mut participants)?;
         }
         
         Ok(Self {
@@ -495,6 +939,14 @@ impl BattleContext {
             start_time: Instant::now(),
             last_action_time: Instant::now(),
             
+            rng: BattleRng::new(),
+            captured_pokemon: None,
+            flinched: HashSet::new(),
+            
+            route: None,
+            game_modes,
+            released_pokemon: Vec::new(),
+            
             stats: BattleStats::default(),
             
             turn_manager: TurnManager::new(),
@@ -504,6 +956,123 @@ impl BattleContext {
         })
     }
     
+    // 检查队伍是否符合对战规则：种族条款禁止重复的种族，道具条款禁止重复的携带道具
+    fn validate_team_clauses(config: &BattleConfig, participant: &BattleParticipant) -> Result<()> {
+        for (index, pokemon) in participant.pokemon.iter().enumerate() {
+            let earlier = &participant.pokemon[..index];
+            
+            if config.species_clause && earlier.iter().any(|other| other.species_id == pokemon.species_id) {
+                return Err(GameError::BattleError(format!(
+                    "{} 的队伍违反种族条款: 重复的种族 {}",
+                    participant.trainer_name, pokemon.species_id
+                )));
+            }
+            
+            if let Some(item) = pokemon.held_item {
+                if config.item_clause && earlier.iter().any(|other| other.held_item == Some(item)) {
+                    return Err(GameError::BattleError(format!(
+                        "{} 的队伍违反道具条款: 重复的携带道具 {}",
+                        participant.trainer_name, item
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    // 按等级上限检查或调整参战的宝可梦；战斗使用的是传入队伍的副本，调整不会影响训练师保存的宝可梦
+    fn apply_level_cap(level_cap: u8, mode: LevelCapMode, participants: &mut [BattleParticipant]) -> Result<()> {
+        for participant in participants {
+            for pokemon in &mut participant.pokemon {
+                if pokemon.level <= level_cap {
+                    continue;
+                }
+                match mode {
+                    LevelCapMode::Reject => {
+                        return Err(GameError::BattleError(format!(
+                            "{} 的 {} 等级 {} 超过上限 {}",
+                            participant.trainer_name,
+                            pokemon.get_display_name(),
+                            pokemon.level,
+                            level_cap
+                        )));
+                    },
+                    LevelCapMode::Scale => {
+                        debug!("{} 等级 {} 调整为 {}", pokemon.get_display_name(), pokemon.level, level_cap);
+                        *pokemon = scale_to_level(pokemon, level_cap)?;
+                    },
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    // 睡眠条款：对手队伍中已有其他宝可梦被催眠时，不能再让另一只陷入睡眠
+    fn sleep_clause_blocks(&self, target_slot: usize, target_index: usize, effect: &MoveEffect) -> bool {
+        if !self.config.sleep_clause || !matches!(effect, MoveEffect::StatusChange { status: StatusEffect::Sleep, .. }) {
+            return false;
+        }
+        self.participants[target_slot].pokemon
+            .iter()
+            .enumerate()
+            .any(|(index, pokemon)| {
+                index != target_index
+                    && !pokemon.is_fainted()
+                    && pokemon.has_status(&StatusCondition::Sleep { turns_remaining: 0 })
+            })
+    }
+    
+    // 从野外地图进入的野生战斗，当前天气带入战斗场地；第一名参与者为玩家，其余为由AI行动的野生宝可梦
+    pub fn new_wild(
+        battle_id: u64,
+        mut config: BattleConfig,
+        mut participants: Vec<BattleParticipant>,
+        overworld_weather: crate::world::Weather,
+    ) -> Result<Self> {
+        config.battle_format = BattleFormat::Wild;
+        for wild in participants.iter_mut().skip(1) {
+            wild.is_ai = true;
+        }
+        let mut battle = Self::new(battle_id, config, participants)?;
+        battle.environment = BattleEnvironment::from_overworld(overworld_weather);
+        debug!("野生战斗 #{} 天气: {:?}", battle_id, battle.environment.weather);
+        Ok(battle)
+    }
+    
+    // 设置战斗所在的道路，规则模式按道路限制捕获
+    pub fn with_route(mut self, route: impl Into<String>) -> Self {
+        self.route = Some(route.into());
+        self
+    }
+    
+    // 由技能或特性设置天气，setter为(训练师ID, 队伍中的位置)，持续回合受其携带道具和特性影响
+    pub fn set_weather(&mut self, weather: WeatherType, setter: Option<(u64, usize)>) -> Result<()> {
+        let setter = self.setter_pokemon(setter)?;
+        let turns = weather_duration(weather, self.config.weather_turns, setter);
+        self.environment.set_weather(weather, turns);
+        debug!("天气变为 {:?}，持续回合: {:?}", weather, turns);
+        Ok(())
+    }
+    
+    pub fn set_terrain(&mut self, terrain: TerrainType, setter: Option<(u64, usize)>) -> Result<()> {
+        let setter = self.setter_pokemon(setter)?;
+        let turns = terrain_duration(self.config.terrain_turns, setter);
+        self.environment.set_terrain(terrain, turns);
+        debug!("场地变为 {:?}，持续回合: {:?}", terrain, turns);
+        Ok(())
+    }
+    
+    fn setter_pokemon(&self, setter: Option<(u64, usize)>) -> Result<Option<&Pokemon>> {
+        let Some((trainer_id, pokemon_index)) = setter else {
+            return Ok(None);
+        };
+        self.get_participant(trainer_id)?
+            .pokemon
+            .get(pokemon_index)
+            .map(Some)
+            .ok_or_else(|| GameError::BattleError(format!("无效的宝可梦位置: {}", pokemon_index)))
+    }
+    
     // 开始战斗
     pub fn start_battle(&mut self) -> Result<()> {
         info!("开始战斗 #{}", self.battle_id);
@@ -513,6 +1082,8 @@ impl BattleContext {
             let active_count = match self.config.battle_type {
                 BattleType::Single => 1,
                 BattleType::Double => 2,
+                // 四名训练师各派出一只，每方两只
+                BattleType::Multi => 1,
                 _ => 1,
             };
             
@@ -554,12 +1125,51 @@ impl BattleContext {
         
         // 检查是否所有参与者都提交了行动
         if self.turn_manager.all_actions_submitted(&self.participants) {
+            self.queue_ai_actions()?;
             self.process_turn()?;
         }
         
         Ok(())
     }
     
+    // 为AI控制的训练师和野生宝可梦选择行动：随机使用一个还有PP的技能攻击随机对手
+    fn queue_ai_actions(&mut self) -> Result<()> {
+        for slot in 0..self.participants.len() {
+            let participant = &self.participants[slot];
+            if !participant.is_ai || !Self::can_battle(participant) {
+                continue;
+            }
+            let Some(pokemon_index) = participant.active_pokemon
+                .iter()
+                .flatten()
+                .copied()
+                .find(|&index| !participant.pokemon[index].is_fainted())
+            else {
+                continue;
+            };
+            
+            let usable: Vec<usize> = participant.pokemon[pokemon_index].moves
+                .iter()
+                .enumerate()
+                .filter(|(_, move_slot)| move_slot.current_pp > 0)
+                .map(|(index, _)| index)
+                .collect();
+            if usable.is_empty() {
+                debug!("{} 没有可用的技能，本回合不行动", participant.trainer_name);
+                continue;
+            }
+            
+            let trainer_id = participant.trainer_id;
+            let move_index = usable[self.rng.index(usable.len())];
+            self.turn_manager.add_action(trainer_id, BattleAction::UseMove {
+                pokemon_index,
+                move_index,
+                target: BattleTarget::Random,
+            })?;
+        }
+        Ok(())
+    }
+    
     // 处理回合
     fn process_turn(&mut self) -> Result<()> {
         self.state = BattleStatus::ProcessingTurn;
@@ -567,13 +1177,17 @@ impl BattleContext {
         debug!("处理回合 #{}", self.turn_number);
         
         // 按优先级排序行动
-        let actions = self.turn_manager.get_sorted_actions(&self.participants)?;
+        let actions = self.turn_manager.get_sorted_actions(&self.participants, &mut self.rng)?;
         
         // 执行每个行动
         for (trainer_id, action) in actions {
-            if self.is_battle_ended() {
+            if self.is_battle_ended() || self.state == BattleStatus::BattleEnd {
                 break;
             }
+            // 本回合早些时候认输的训练师不再行动
+            if self.get_participant(trainer_id)?.forfeited {
+                continue;
+            }
             
             self.execute_action(trainer_id, action)?;
         }
@@ -603,7 +1217,12 @@ impl BattleContext {
     fn execute_action(&mut self, trainer_id: u64, action: BattleAction) -> Result<()> {
         match action {
             BattleAction::UseMove { pokemon_index, move_index, target } => {
-                self.execute_move(trainer_id, pokemon_index, move_index, target)?;
+                // 本回合早些时候被畏缩的宝可梦无法行动
+                if self.flinched.contains(&(trainer_id, pokemon_index)) {
+                    info!("训练师 {} 的宝可梦畏缩了，无法行动", trainer_id);
+                } else {
+                    self.execute_move(trainer_id, pokemon_index, move_index, target)?;
+                }
             },
             BattleAction::SwitchPokemon { from_index, to_index } => {
                 self.execute_switch(trainer_id, from_index, to_index)?;
@@ -611,6 +1230,9 @@ impl BattleContext {
             BattleAction::UseItem { item_id, target } => {
                 self.execute_item_use(trainer_id, item_id, target)?;
             },
+            BattleAction::ThrowBall { ball_id, target } => {
+                self.execute_throw_ball(trainer_id, ball_id, target)?;
+            },
             BattleAction::Run => {
                 self.execute_run(trainer_id)?;
             },
@@ -631,13 +1253,9 @@ impl BattleContext {
         target: BattleTarget,
     ) -> Result<()> {
         // 获取使用者信息
-        let participant = self.get_participant_mut(trainer_id)?;
-        let active_slot = participant.active_pokemon
-            .iter()
-            .position(|&slot| slot == Some(pokemon_index))
-            .ok_or_else(|| GameError::BattleError("宝可梦不在场上".to_string()))?;
-        
-        let pokemon = &mut participant.pokemon[pokemon_index];
+        let user = self.user_position(trainer_id, pokemon_index)?;
+        let user_slot = self.participant_slot(trainer_id)?;
+        let pokemon = &mut self.participants[user_slot].pokemon[pokemon_index];
         
         // 检查宝可梦状态
         if pokemon.is_fainted() {
@@ -654,7 +1272,8 @@ impl BattleContext {
         }
         
         // 获取技能信息
-        let move_data = crate::pokemon::Move::get(move_slot.move_id)
+        let move_id = move_slot.move_id;
+        let move_data = crate::pokemon::Move::get(move_id)
             .ok_or_else(|| GameError::BattleError("技能数据不存在".to_string()))?;
         
         // 消耗PP
@@ -662,65 +1281,90 @@ impl BattleContext {
         
         // 动画开始
         self.state = BattleStatus::AnimatingMove;
-        self.animator.start_move_animation(trainer_id, pokemon_index, move_slot.move_id)?;
+        self.animator.start_move_animation(trainer_id, pokemon_index, move_id)?;
         
         // 计算伤害和效果
-        let targets = self.resolve_targets(trainer_id, target)?;
+        let targets = self.resolve_targets(user, target)?;
         let mut move_success = false;
         
-        for target_id in targets {
-            let damage_result = self.damage_calculator.calculate_damage(
-                pokemon,
-                &self.get_target_pokemon(target_id)?,
-                move_data,
-                &self.environment,
-            )?;
+        for target_position in targets {
+            let (target_id, _) = target_position;
+            if self.environment.terrain_blocks_priority(move_data.priority, self.get_target_pokemon(target_position)?) {
+                debug!("精神场地保护目标 {:?} 不受先制技能攻击", target_position);
+                continue;
+            }
             
-            if damage_result.hit {
-                move_success = true;
-                
-                // 应用伤害
-                self.apply_damage(target_id, damage_result.damage)?;
+            // 命中、暴击和伤害浮动都使用战斗随机数，同一种子可以回放
+            let hit = move_data.accuracy.map_or(true, |accuracy| self.rng.chance(accuracy as f32 / 100.0));
+            if !hit {
+                debug!("技能 {} 没有命中 {:?}", move_data.name, target_position);
+                continue;
+            }
+            let critical = self.rng.chance(CRITICAL_HIT_CHANCE);
+            let target_slot = self.participant_slot(target_id)?;
+            let target_index = self.active_index(target_position)?;
+            let damage_result = {
+                let context = create_damage_context(
+                    &self.participants[user_slot].pokemon[pokemon_index],
+                    &self.participants[target_slot].pokemon[target_index],
+                    &move_data,
+                    &self.environment,
+                    critical,
+                );
+                self.damage_calculator.roll_damage(&context, &mut self.rng)?
+            };
+            let damage = damage_result.final_damage.min(u16::MAX as u32) as u16;
+            move_success = true;
+            
+            // 变化技能没有伤害，只结算附加效果
+            if damage > 0 {
+                self.apply_damage(target_position, damage)?;
                 
-                // 发送伤害事件
-                EventSystem::dispatch(DamageDealtEvent {
+                // 发送伤害事件，延迟到帧末分发，处理器不会看到执行到一半的战斗状态
+                EventSystem::queue(DamageDealtEvent {
                     attacker_id: trainer_id,
                     defender_id: target_id,
-                    damage: damage_result.damage,
-                    critical_hit: damage_result.critical,
+                    damage,
+                    critical_hit: damage_result.is_critical,
                     type_effectiveness: damage_result.type_effectiveness,
                 })?;
-                
-                // 应用附加效果
-                if let Some(effect) = &move_data.secondary_effect {
-                    if fastrand::f32() < effect.chance {
-                        self.status_manager.apply_effect(target_id, effect.clone())?;
-                    }
-                }
-                
-                // 更新统计
-                self.stats.total_damage_dealt
-                    .entry(trainer_id)
-                    .and_modify(|d| *d += damage_result.damage as u32)
-                    .or_insert(damage_result.damage as u32);
-                
-                if damage_result.critical {
-                    self.stats.critical_hits += 1;
+            }
+            
+            // 伤害结算后依次判定附加效果，概率由战斗随机数决定
+            for secondary in &move_data.secondary_effects {
+                if self.rng.chance(secondary.trigger_chance()) {
+                    self.resolve_secondary_effect(
+                        trainer_id,
+                        pokemon_index,
+                        target_position,
+                        &secondary.effect,
+                        damage,
+                    )?;
                 }
             }
+            
+            // 更新统计
+            self.stats.total_damage_dealt
+                .entry(trainer_id)
+                .and_modify(|d| *d += damage as u32)
+                .or_insert(damage as u32);
+            
+            if damage_result.is_critical {
+                self.stats.critical_hits += 1;
+            }
         }
         
         // 更新技能使用统计
         self.stats.moves_used
-            .entry(move_slot.move_id)
+            .entry(move_id)
             .and_modify(|c| *c += 1)
             .or_insert(1);
         
         // 发送技能使用事件
-        EventSystem::dispatch(PokemonMoveEvent {
+        EventSystem::queue(PokemonMoveEvent {
             user_id: trainer_id,
             pokemon_index,
-            move_id: move_slot.move_id,
+            move_id,
             target,
             success: move_success,
         })?;
@@ -766,6 +1410,62 @@ impl BattleContext {
         Ok(())
     }
     
+    // 投掷精灵球，捕获成功时战斗结束，被捕获的宝可梦放入captured_pokemon
+    fn execute_throw_ball(&mut self, trainer_id: u64, ball_id: u32, target: usize) -> Result<CaptureResult> {
+        if self.config.battle_format != BattleFormat::Wild {
+            return Err(GameError::BattleError("只能在野生战斗中投掷精灵球".to_string()));
+        }
+        let ball = PokeBall::from_item_id(ball_id)
+            .ok_or_else(|| GameError::BattleError(format!("道具 {} 不是精灵球", ball_id)))?;
+        
+        let position = self.wild_target(trainer_id, target)?;
+        let wild_slot = self.participant_slot(position.0)?;
+        let wild_index = self.active_index(position)?;
+        
+        // 规则不允许捕获时不消耗精灵球
+        let species_id = self.participants[wild_slot].pokemon[wild_index].species_id;
+        self.game_modes.lock().unwrap().check_capture(self.route.as_deref().unwrap_or_default(), species_id)?;
+        
+        // 先扣除精灵球，捕获失败时球同样消耗掉
+        self.get_participant_mut(trainer_id)?.consume_item(ball_id)?;
+        
+        let wild = &self.participants[wild_slot].pokemon[wild_index];
+        let hp_ratio = wild.current_hp as f32 / wild.get_stats()?.hp.max(1) as f32;
+        let status = wild.status_conditions
+            .iter()
+            .find(|status| **status != StatusCondition::None)
+            .cloned()
+            .unwrap_or(StatusCondition::None);
+        
+        let result = capture::attempt(wild, ball, &status, hp_ratio, &mut self.rng);
+        self.stats.items_used += 1;
+        info!("投出 {:?}: 摇晃 {} 次, 成功率 {:.1}%", ball, result.shakes, result.probability * 100.0);
+        
+        if result.caught {
+            let participant = &mut self.participants[wild_slot];
+            let mut pokemon = participant.pokemon.remove(wild_index);
+            // 移除后其余出场宝可梦的索引整体前移
+            for active in participant.active_pokemon.iter_mut() {
+                *active = match *active {
+                    Some(index) if index == wild_index => None,
+                    Some(index) if index > wild_index => Some(index - 1),
+                    other => other,
+                };
+            }
+            pokemon.trainer_id = Some(trainer_id);
+            info!("捕获了 {}!", pokemon.get_display_name());
+            self.captured_pokemon = Some(pokemon);
+            self.end_battle_with_result(Some(trainer_id))?;
+        }
+        
+        Ok(result)
+    }
+    
+    // 取走捕获的宝可梦
+    pub fn take_captured_pokemon(&mut self) -> Option<Pokemon> {
+        self.captured_pokemon.take()
+    }
+    
     // 执行逃跑
     fn execute_run(&mut self, trainer_id: u64) -> Result<()> {
         if self.config.battle_format != BattleFormat::Wild {
@@ -785,26 +1485,59 @@ impl BattleContext {
         Ok(())
     }
     
-    // 执行认输
+    // 执行认输；多人对战中队友还能继续战斗时，只有认输的训练师退出
     fn execute_forfeit(&mut self, trainer_id: u64) -> Result<()> {
         info!("训练师 {} 认输", trainer_id);
         
+        let side = self.get_participant(trainer_id)?.side;
+        self.get_participant_mut(trainer_id)?.forfeited = true;
+        let ally_remains = self.allies_of(trainer_id)?
+            .iter()
+            .any(|&ally| self.get_participant(ally).is_ok_and(Self::can_battle));
+        if ally_remains {
+            return Ok(());
+        }
+        
         // 找到获胜者
         let winner_id = self.participants
             .iter()
-            .find(|p| p.trainer_id != trainer_id)
+            .find(|p| p.side != side)
             .map(|p| p.trainer_id);
         
         self.end_battle_with_result(winner_id)?;
         Ok(())
     }
     
+    fn can_battle(participant: &BattleParticipant) -> bool {
+        !participant.forfeited && participant.pokemon.iter().any(|pokemon| !pokemon.is_fainted())
+    }
+    
+    // 同一方的其他训练师
+    fn allies_of(&self, trainer_id: u64) -> Result<Vec<u64>> {
+        let side = self.get_participant(trainer_id)?.side;
+        Ok(self.participants
+            .iter()
+            .filter(|p| p.side == side && p.trainer_id != trainer_id)
+            .map(|p| p.trainer_id)
+            .collect())
+    }
+    
+    // 对方还没有认输的训练师，按参与者顺序排列
+    fn opponents_of(&self, trainer_id: u64) -> Result<Vec<u64>> {
+        let side = self.get_participant(trainer_id)?.side;
+        Ok(self.participants
+            .iter()
+            .filter(|p| p.side != side && !p.forfeited)
+            .map(|p| p.trainer_id)
+            .collect())
+    }
+    
     // 辅助方法
     fn validate_action(&self, trainer_id: u64, action: &BattleAction) -> Result<()> {
         let participant = self.get_participant(trainer_id)?;
         
         match action {
-            BattleAction::UseMove { pokemon_index, move_index, .. } => {
+            BattleAction::UseMove { pokemon_index, move_index, target } => {
                 if *pokemon_index >= participant.pokemon.len() {
                     return Err(GameError::BattleError("无效的宝可梦索引".to_string()));
                 }
@@ -821,6 +1554,8 @@ impl BattleContext {
                 if pokemon.moves[*move_index].current_pp == 0 {
                     return Err(GameError::BattleError("技能PP不足".to_string()));
                 }
+                
+                self.validate_target(self.user_position(trainer_id, *pokemon_index)?, target)?;
             },
             BattleAction::SwitchPokemon { to_index, .. } => {
                 if *to_index >= participant.pokemon.len() {
@@ -831,6 +1566,18 @@ impl BattleContext {
                     return Err(GameError::BattleError("无法切换到濒死宝可梦".to_string()));
                 }
             },
+            BattleAction::ThrowBall { ball_id, target } => {
+                if self.config.battle_format != BattleFormat::Wild {
+                    return Err(GameError::BattleError("只能在野生战斗中投掷精灵球".to_string()));
+                }
+                if PokeBall::from_item_id(*ball_id).is_none() {
+                    return Err(GameError::BattleError(format!("道具 {} 不是精灵球", ball_id)));
+                }
+                if participant.item_count(*ball_id) == 0 {
+                    return Err(GameError::BattleError(format!("道具 {} 已经用完", ball_id)));
+                }
+                self.wild_target(trainer_id, *target)?;
+            },
             _ => {}
         }
         
@@ -851,39 +1598,199 @@ impl BattleContext {
             .ok_or_else(|| GameError::BattleError("参与者不存在".to_string()))
     }
     
-    fn resolve_targets(&self, user_id: u64, target: BattleTarget) -> Result<Vec<u64>> {
-        // TODO: 实现目标解析逻辑
+    fn participant_slot(&self, trainer_id: u64) -> Result<usize> {
+        self.participants
+            .iter()
+            .position(|p| p.trainer_id == trainer_id)
+            .ok_or_else(|| GameError::BattleError("参与者不存在".to_string()))
+    }
+    
+    // 取出使用者和目标位置上的宝可梦后结算附加效果
+    fn resolve_secondary_effect(
+        &mut self,
+        user_id: u64,
+        user_index: usize,
+        target: (u64, usize),
+        effect: &MoveEffect,
+        damage_dealt: u16,
+    ) -> Result<EffectOutcome> {
+        let user_slot = self.participant_slot(user_id)?;
+        let target_slot = self.participant_slot(target.0)?;
+        let target_index = self.active_index(target)?;
+        
+        let outcome = if (user_slot, user_index) == (target_slot, target_index) {
+            // 以己方为目标的技能只作用于使用者
+            apply_secondary_effect(
+                effect,
+                &self.environment,
+                &mut self.participants[user_slot].pokemon[user_index],
+                None,
+                damage_dealt,
+                &mut self.rng,
+            )?
+        } else if self.sleep_clause_blocks(target_slot, target_index, effect) {
+            debug!("睡眠条款: 对手已有宝可梦处于睡眠状态");
+            EffectOutcome::default()
+        } else {
+            // 双打中目标可能是同一名训练师的另一只宝可梦
+            let (user_pokemon, target_pokemon) = if user_slot == target_slot {
+                pair_mut(&mut self.participants[user_slot].pokemon, user_index, target_index)
+            } else {
+                let (user_participant, target_participant) = pair_mut(&mut self.participants, user_slot, target_slot);
+                (&mut user_participant.pokemon[user_index], &mut target_participant.pokemon[target_index])
+            };
+            apply_secondary_effect(
+                effect,
+                &self.environment,
+                user_pokemon,
+                Some(target_pokemon),
+                damage_dealt,
+                &mut self.rng,
+            )?
+        };
+        
+        if outcome.status_applied {
+            self.stats.status_conditions_applied += 1;
+        }
+        if outcome.flinched {
+            self.flinched.insert((target.0, target_index));
+        }
+        debug!("附加效果 {:?}: {:?}", effect, outcome);
+        Ok(outcome)
+    }
+    
+    // 单体目标的序号必须落在对应一方的场上位置范围内，没有队友时不能选择队友
+    fn validate_target(&self, user: (u64, usize), target: &BattleTarget) -> Result<()> {
+        match *target {
+            BattleTarget::Opponent(index) if index >= self.opponent_positions(user.0)?.len() => {
+                Err(GameError::BattleError(format!("无效的对手目标: {}", index)))
+            },
+            BattleTarget::Ally(index) if index >= self.ally_positions(user)?.len() => {
+                Err(GameError::BattleError(format!("无效的队友目标: {}", index)))
+            },
+            _ => Ok(()),
+        }
+    }
+    
+    // 使用者所在的场上位置：(训练师ID, 出场位)
+    fn user_position(&self, trainer_id: u64, pokemon_index: usize) -> Result<(u64, usize)> {
+        self.get_participant(trainer_id)?
+            .active_pokemon
+            .iter()
+            .position(|&active| active == Some(pokemon_index))
+            .map(|slot| (trainer_id, slot))
+            .ok_or_else(|| GameError::BattleError("宝可梦不在场上".to_string()))
+    }
+    
+    // 训练师的全部出场位，双打中一名训练师占两个位置
+    fn field_positions(&self, trainer_id: u64) -> Vec<(u64, usize)> {
+        let slots = self.get_participant(trainer_id).map_or(0, |p| p.active_pokemon.len());
+        (0..slots).map(|slot| (trainer_id, slot)).collect()
+    }
+    
+    // 对方的全部场上位置，按参与者顺序排列
+    fn opponent_positions(&self, trainer_id: u64) -> Result<Vec<(u64, usize)>> {
+        Ok(self.opponents_of(trainer_id)?
+            .into_iter()
+            .flat_map(|id| self.field_positions(id))
+            .collect())
+    }
+    
+    // 己方除使用者以外的场上位置，包括同一名训练师的另一只出场宝可梦
+    fn ally_positions(&self, user: (u64, usize)) -> Result<Vec<(u64, usize)>> {
+        Ok(std::iter::once(user.0)
+            .chain(self.allies_of(user.0)?)
+            .flat_map(|id| self.field_positions(id))
+            .filter(|&position| position != user)
+            .collect())
+    }
+    
+    // 位置上有还能战斗的宝可梦，认输的训练师的位置视为空位
+    fn position_standing(&self, (trainer_id, slot): (u64, usize)) -> bool {
+        self.get_participant(trainer_id)
+            .ok()
+            .filter(|p| !p.forfeited)
+            .and_then(|p| p.active_pokemon.get(slot).copied().flatten().map(|index| &p.pokemon[index]))
+            .is_some_and(|pokemon| !pokemon.is_fainted())
+    }
+    
+    // 把技能目标解析为场上位置，跳过已经空出或濒死的位置；单体目标倒下时改打另一只还在场的对手
+    fn resolve_targets(&mut self, user: (u64, usize), target: BattleTarget) -> Result<Vec<(u64, usize)>> {
+        let opponents = self.opponent_positions(user.0)?;
+        let allies = self.ally_positions(user)?;
+        let standing = |positions: Vec<(u64, usize)>| -> Vec<(u64, usize)> {
+            positions.into_iter().filter(|&position| self.position_standing(position)).collect()
+        };
+        
         match target {
-            BattleTarget::Opponent(_) => {
-                // 返回对手ID
-                Ok(self.participants
-                    .iter()
-                    .filter(|p| p.trainer_id != user_id)
-                    .map(|p| p.trainer_id)
-                    .collect())
+            BattleTarget::Self_ | BattleTarget::User => Ok(vec![user]),
+            BattleTarget::Opponent(index) => {
+                let chosen = *opponents
+                    .get(index)
+                    .ok_or_else(|| GameError::BattleError(format!("无效的对手目标: {}", index)))?;
+                if self.position_standing(chosen) {
+                    Ok(vec![chosen])
+                } else {
+                    Ok(standing(opponents).into_iter().take(1).collect())
+                }
+            },
+            BattleTarget::Ally(index) => {
+                let chosen = *allies
+                    .get(index)
+                    .ok_or_else(|| GameError::BattleError(format!("无效的队友目标: {}", index)))?;
+                Ok(standing(vec![chosen]))
+            },
+            BattleTarget::AllOpponents => Ok(standing(opponents)),
+            BattleTarget::AllAllies => Ok(standing(std::iter::once(user).chain(allies).collect())),
+            // 全场技能同样会打到队友
+            BattleTarget::All => Ok(standing(allies.into_iter().chain(opponents).collect())),
+            BattleTarget::Random => {
+                let candidates = standing(opponents);
+                if candidates.is_empty() {
+                    return Ok(candidates);
+                }
+                Ok(vec![candidates[self.rng.index(candidates.len())]])
             },
-            _ => Ok(vec![user_id]),
         }
     }
     
-    fn get_target_pokemon(&self, target_id: u64) -> Result<&Pokemon> {
-        let participant = self.get_participant(target_id)?;
-        let active_index = participant.active_pokemon[0]
-            .ok_or_else(|| GameError::BattleError("目标没有活跃宝可梦".to_string()))?;
-        Ok(&participant.pokemon[active_index])
+    // 投球的目标：对方场上第target个位置上还能战斗的野生宝可梦
+    fn wild_target(&self, trainer_id: u64, target: usize) -> Result<(u64, usize)> {
+        let position = self.opponent_positions(trainer_id)?
+            .get(target)
+            .copied()
+            .ok_or_else(|| GameError::BattleError(format!("无效的投球目标 {}", target)))?;
+        if !self.position_standing(position) {
+            return Err(GameError::BattleError("目标位置没有可以捕获的野生宝可梦".to_string()));
+        }
+        Ok(position)
     }
     
-    fn apply_damage(&mut self, target_id: u64, damage: u16) -> Result<()> {
-        let participant = self.get_participant_mut(target_id)?;
-        let active_index = participant.active_pokemon[0]
-            .ok_or_else(|| GameError::BattleError("目标没有活跃宝可梦".to_string()))?;
+    // 场上位置对应的队伍序号
+    fn active_index(&self, (trainer_id, slot): (u64, usize)) -> Result<usize> {
+        self.get_participant(trainer_id)?
+            .active_pokemon
+            .get(slot)
+            .copied()
+            .flatten()
+            .ok_or_else(|| GameError::BattleError("目标没有活跃宝可梦".to_string()))
+    }
+    
+    fn get_target_pokemon(&self, target: (u64, usize)) -> Result<&Pokemon> {
+        let active_index = self.active_index(target)?;
+        Ok(&self.get_participant(target.0)?.pokemon[active_index])
+    }
+    
+    fn apply_damage(&mut self, target: (u64, usize), damage: u16) -> Result<()> {
+        let active_index = self.active_index(target)?;
+        let participant = self.get_participant_mut(target.0)?;
         
         let pokemon = &mut participant.pokemon[active_index];
         let fainted = pokemon.take_damage(damage);
         
         if fainted {
-            EventSystem::dispatch(PokemonFaintedEvent {
-                trainer_id: target_id,
+            EventSystem::queue(PokemonFaintedEvent {
+                trainer_id: target.0,
                 pokemon_index: active_index,
                 pokemon_name: pokemon.get_display_name(),
             })?;
@@ -936,6 +1843,9 @@ impl BattleContext {
         // 更新环境效果持续时间
         self.update_environment_durations();
         
+        // 畏缩不会持续到下一回合
+        self.flinched.clear();
+        
         Ok(())
     }
     
@@ -965,11 +1875,26 @@ impl BattleContext {
     }
     
     fn process_field_effects(&mut self) -> Result<()> {
-        // TODO: 实现场地效果处理
+        // 青草场地回复
+        for participant in &mut self.participants {
+            for &pokemon_index in participant.active_pokemon.iter().flatten() {
+                let pokemon = &mut participant.pokemon[pokemon_index];
+                let amount = self.environment.terrain_heal_amount(pokemon)?;
+                if amount > 0 {
+                    let healed = pokemon.heal(amount)?;
+                    debug!("{} 受到青草场地回复: {}", pokemon.get_display_name(), healed);
+                }
+            }
+        }
+        
+        // TODO: 实现其他场地效果处理
         Ok(())
     }
     
     fn update_environment_durations(&mut self) {
+        // 天气和场地回合数递减，归零时清除
+        self.environment.tick_durations();
+        
         // 更新场地效果持续时间
         self.environment.field_effects.retain_mut(|effect| {
             effect.duration = effect.duration.saturating_sub(1);
@@ -977,27 +1902,87 @@ impl BattleContext {
         });
     }
     
+    // 战斗限时，未配置时使用全局默认值
+    pub fn time_limit(&self) -> Duration {
+        let seconds = self.config.time_limit_seconds
+            .unwrap_or(crate::constants::DEFAULT_BATTLE_TIMEOUT_MINUTES * 60);
+        Duration::from_secs(seconds as u64)
+    }
+    
+    // 每帧调用，超过限时的战斗按剩余HP比例判定胜负；返回本次调用是否结束了战斗
+    pub fn update(&mut self, now: Instant) -> Result<bool> {
+        if self.state == BattleStatus::BattleEnd {
+            return Ok(false);
+        }
+        
+        let elapsed = now.saturating_duration_since(self.start_time);
+        if elapsed < self.time_limit() {
+            return Ok(false);
+        }
+        
+        let winner_id = self.timeout_winner();
+        info!("战斗 #{} 超时，按剩余HP判定获胜者: {:?}", self.battle_id, winner_id);
+        self.finish_battle(winner_id, elapsed)?;
+        Ok(true)
+    }
+    
+    // 剩余HP比例最高的一方获胜，最高比例并列时为平局；认输的训练师不参与判定
+    pub fn timeout_winner(&self) -> Option<u64> {
+        // (剩余HP, 最大HP)，用交叉相乘比较比例，避免浮点误差导致的误判
+        let ratios: Vec<(u64, u64, u64)> = self.participants
+            .iter()
+            .filter(|participant| !participant.forfeited)
+            .map(|participant| {
+                let (remaining, max) = participant.pokemon.iter().fold((0u64, 0u64), |(remaining, max), pokemon| {
+                    let max_hp = pokemon.get_stats().map_or(0, |stats| stats.hp as u64);
+                    (remaining + (pokemon.current_hp as u64).min(max_hp), max + max_hp)
+                });
+                (participant.trainer_id, remaining, max.max(1))
+            })
+            .collect();
+        
+        let &(best_id, best_remaining, best_max) = ratios
+            .iter()
+            .max_by(|a, b| (a.1 * b.2).cmp(&(b.1 * a.2)))?;
+        let tied = ratios
+            .iter()
+            .filter(|(_, remaining, max)| remaining * best_max == best_remaining * max)
+            .count() > 1;
+        
+        if tied { None } else { Some(best_id) }
+    }
+    
     fn is_battle_ended(&self) -> bool {
-        // 检查是否有参与者失去所有宝可梦
-        self.participants.iter().any(|p| {
-            p.pokemon.iter().all(|pokemon| pokemon.is_fainted())
-        })
+        // 某一方的所有训练师都失去全部宝可梦时结束，多人对战中队友还能战斗就继续
+        let mut standing_sides: Vec<usize> = self.participants
+            .iter()
+            .filter(|p| Self::can_battle(p))
+            .map(|p| p.side)
+            .collect();
+        standing_sides.sort_unstable();
+        standing_sides.dedup();
+        standing_sides.len() < 2
     }
     
     fn end_battle(&mut self) -> Result<()> {
+        // 多人对战中获胜方的第一名训练师作为获胜者
         let winner_id = self.participants
             .iter()
-            .find(|p| p.pokemon.iter().any(|pokemon| !pokemon.is_fainted()))
+            .find(|p| Self::can_battle(p))
             .map(|p| p.trainer_id);
         
         self.end_battle_with_result(winner_id)
     }
     
     fn end_battle_with_result(&mut self, winner_id: Option<u64>) -> Result<()> {
+        self.finish_battle(winner_id, self.start_time.elapsed())
+    }
+    
+    fn finish_battle(&mut self, winner_id: Option<u64>, duration: Duration) -> Result<()> {
         self.state = BattleStatus::BattleEnd;
-        let duration = self.start_time.elapsed();
         
         info!("战斗结束! 获胜者: {:?}, 持续时间: {:?}", winner_id, duration);
+        self.apply_game_mode_results(winner_id);
         
         EventSystem::dispatch(BattleEndEvent {
             winner_id,
@@ -1009,6 +1994,38 @@ impl BattleContext {
         Ok(())
     }
     
+    // 把濒死的宝可梦和胜负交给规则模式，需要放生的玩家宝可梦记入released_pokemon
+    fn apply_game_mode_results(&mut self, winner_id: Option<u64>) {
+        let route = self.route.as_deref().unwrap_or_default();
+        let winner_side = winner_id.and_then(|id| self.get_participant(id).ok()).map(|p| p.side);
+        let mut modes = self.game_modes.lock().unwrap();
+        
+        for participant in &self.participants {
+            if participant.is_ai {
+                if self.config.battle_format == BattleFormat::Wild
+                    && participant.pokemon.iter().any(|pokemon| pokemon.is_fainted())
+                {
+                    modes.on_faint(route, None);
+                }
+                continue;
+            }
+            
+            for pokemon in participant.pokemon.iter().filter(|pokemon| pokemon.is_fainted()) {
+                if modes.on_faint(route, Some(pokemon.id)) == FaintOutcome::Release {
+                    self.released_pokemon.push(pokemon.id);
+                }
+            }
+        }
+        
+        // 胜负按玩家(第一名非AI训练师)所在的一方统计
+        let player_side = self.participants.iter().find(|p| !p.is_ai).map(|p| p.side);
+        match (player_side, winner_side) {
+            (Some(player), Some(winner)) if player == winner => modes.update_stats(GameStat::BattleWon, 1),
+            (Some(_), Some(_)) => modes.update_stats(GameStat::BattleLost, 1),
+            _ => {},
+        }
+    }
+    
     fn calculate_escape_chance(&self, trainer_id: u64) -> Result<f32> {
         // 简单的逃跑成功率计算
         let participant = self.get_participant(trainer_id)?;
@@ -1025,6 +2042,20 @@ impl BattleContext {
     }
 }
 
+// 当前进行中的战斗，由战斗状态插件每帧驱动
+#[derive(bevy::prelude::Resource, Default)]
+pub struct ActiveBattle(pub Option<BattleContext>);
+
+// 每帧推进进行中的战斗，超时的战斗在这里结束
+pub fn update_active_battle(mut active: bevy::prelude::ResMut<ActiveBattle>) {
+    let Some(battle) = active.0.as_mut() else {
+        return;
+    };
+    if let Err(e) = battle.update(Instant::now()) {
+        warn!("战斗 #{} 更新失败: {}", battle.battle_id, e);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1044,6 +2075,660 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_wild_battle_inherits_overworld_weather() {
+        use crate::pokemon::moves::WeatherType;
+        use crate::world::Weather;
+        
+        let participant = || {
+            let pokemon = Pokemon::new(25, 50, None, "Test".to_string(), "Test Location".to_string()).unwrap();
+            BattleParticipant::new(vec![pokemon])
+        };
+        
+        let battle = BattleContext::new_wild(1, BattleConfig::default(), vec![participant(), participant()], Weather::Sandstorm).unwrap();
+        assert_eq!(battle.config.battle_format, BattleFormat::Wild);
+        assert_eq!(battle.environment.weather, Some(WeatherType::Sandstorm));
+        assert_eq!(battle.environment.weather_turns, None);
+        
+        assert_eq!(BattleEnvironment::from_overworld(Weather::Clear).weather, None);
+        assert_eq!(BattleEnvironment::from_overworld(Weather::Storm).weather, Some(WeatherType::Rain));
+    }
+    
+    fn test_pokemon() -> Pokemon {
+        Pokemon::new(25, 50, None, "Test".to_string(), "Test Location".to_string()).unwrap()
+    }
+    
+    #[test]
+    fn test_damp_rock_extends_rain() {
+        let mut holder = test_pokemon();
+        holder.held_item = Some(ITEM_DAMP_ROCK);
+        let participants = vec![BattleParticipant::new(vec![holder]), BattleParticipant::new(vec![test_pokemon()])];
+        let setter_id = participants[0].trainer_id;
+        let other_id = participants[1].trainer_id;
+        let mut battle = BattleContext::new(1, BattleConfig::default(), participants).unwrap();
+        
+        battle.set_weather(WeatherType::Rain, Some((setter_id, 0))).unwrap();
+        assert_eq!(battle.environment.weather_turns, Some(8));
+        for _ in 0..7 {
+            battle.update_environment_durations();
+        }
+        assert_eq!(battle.environment.weather, Some(WeatherType::Rain));
+        battle.update_environment_durations();
+        assert_eq!(battle.environment.weather, None);
+        assert_eq!(battle.environment.weather_turns, None);
+        
+        // 岩石只延长对应的天气，没有道具时为默认回合数
+        battle.set_weather(WeatherType::Sun, Some((setter_id, 0))).unwrap();
+        assert_eq!(battle.environment.weather_turns, Some(5));
+        battle.set_weather(WeatherType::Rain, Some((other_id, 0))).unwrap();
+        assert_eq!(battle.environment.weather_turns, Some(5));
+        assert!(battle.set_weather(WeatherType::Rain, Some((setter_id, 3))).is_err());
+    }
+    
+    #[test]
+    fn test_permanent_weather_and_terrain_extender() {
+        let mut primal = test_pokemon();
+        primal.ability_id = ABILITY_PRIMORDIAL_SEA;
+        assert_eq!(weather_duration(WeatherType::Rain, 5, Some(&primal)), None);
+        assert_eq!(weather_duration(WeatherType::Sun, 5, Some(&primal)), Some(5));
+        
+        let mut environment = BattleEnvironment::default();
+        environment.set_weather(WeatherType::Rain, None);
+        for _ in 0..20 {
+            environment.tick_durations();
+        }
+        assert_eq!(environment.weather, Some(WeatherType::Rain));
+        
+        let mut extender = test_pokemon();
+        extender.held_item = Some(ITEM_TERRAIN_EXTENDER);
+        environment.set_terrain(TerrainType::Electric, terrain_duration(5, Some(&extender)));
+        assert_eq!(environment.terrain_turns, Some(8));
+        for _ in 0..8 {
+            environment.tick_durations();
+        }
+        assert_eq!(environment.terrain, TerrainType::None);
+        assert_eq!(terrain_duration(5, None), Some(5));
+    }
+    
+    fn status_effect(status: StatusEffect) -> MoveEffect {
+        MoveEffect::StatusChange { target: EffectTarget::Target, status, chance: 1.0 }
+    }
+    
+    #[test]
+    fn test_growl_lowers_target_attack() {
+        let growl = MoveEffect::StatChange {
+            target: EffectTarget::Target,
+            stat: StatType::Attack,
+            stages: -1,
+            chance: 1.0,
+        };
+        let environment = BattleEnvironment::default();
+        let mut rng = BattleRng::with_seed(1);
+        let mut user = test_pokemon();
+        let mut target = test_pokemon();
+        
+        let outcome = apply_secondary_effect(&growl, &environment, &mut user, Some(&mut target), 0, &mut rng).unwrap();
+        assert_eq!(outcome.stages_changed, -1);
+        assert_eq!(target.stat_stages.attack, -1);
+        assert_eq!(user.stat_stages.attack, 0);
+        
+        // 能力等级最低降到-6
+        for _ in 0..10 {
+            apply_secondary_effect(&growl, &environment, &mut user, Some(&mut target), 0, &mut rng).unwrap();
+        }
+        assert_eq!(target.stat_stages.attack, -MAX_STAT_STAGE);
+        assert_eq!(apply_secondary_effect(&growl, &environment, &mut user, Some(&mut target), 0, &mut rng).unwrap().stages_changed, 0);
+        
+        // 以自己为目标的能力提升
+        let swords_dance = MoveEffect::StatChange { target: EffectTarget::User, stat: StatType::Attack, stages: 2, chance: 1.0 };
+        apply_secondary_effect(&swords_dance, &environment, &mut user, Some(&mut target), 0, &mut rng).unwrap();
+        assert_eq!(user.stat_stages.attack, 2);
+        
+        // 畏缩只体现在结算结果里，不会作为异常状态留在目标身上
+        let outcome = apply_secondary_effect(&MoveEffect::Flinch { chance: 1.0 }, &environment, &mut user, Some(&mut target), 30, &mut rng).unwrap();
+        assert!(outcome.flinched);
+        assert!(!target.has_status(&StatusCondition::Flinch));
+    }
+    
+    #[test]
+    fn test_flinch_skips_action_for_current_turn_only() {
+        EventSystem::init().unwrap();
+        let trainer = || {
+            let mut pokemon = test_pokemon();
+            pokemon.learn_move(1, None).unwrap();
+            BattleParticipant::new(vec![pokemon])
+        };
+        let mut battle = BattleContext::new(1, BattleConfig::default(), vec![trainer(), trainer()]).unwrap();
+        battle.start_battle().unwrap();
+        let ids = trainer_ids(&battle);
+        let attack = BattleAction::UseMove { pokemon_index: 0, move_index: 0, target: BattleTarget::Opponent(0) };
+        let full_pp = battle.participants[1].pokemon[0].moves[0].current_pp;
+        
+        let outcome = battle.resolve_secondary_effect(ids[0], 0, (ids[1], 0), &MoveEffect::Flinch { chance: 1.0 }, 10).unwrap();
+        assert!(outcome.flinched);
+        
+        // 被畏缩的宝可梦本回合不出手，也不消耗PP
+        battle.execute_action(ids[1], attack.clone()).unwrap();
+        assert_eq!(battle.participants[1].pokemon[0].moves[0].current_pp, full_pp);
+        
+        // 回合结束后畏缩解除
+        battle.end_turn_effects().unwrap();
+        assert!(battle.flinched.is_empty());
+        battle.execute_action(ids[1], attack).unwrap();
+        assert_eq!(battle.participants[1].pokemon[0].moves[0].current_pp, full_pp - 1);
+    }
+    
+    #[test]
+    fn test_drain_heals_user_and_recoil_hurts() {
+        let environment = BattleEnvironment::default();
+        let mut rng = BattleRng::with_seed(1);
+        let mut user = test_pokemon();
+        let mut target = test_pokemon();
+        let max_hp = user.current_hp;
+        user.current_hp = 10;
+        
+        let drain = MoveEffect::Drain { drain_ratio: 0.5 };
+        let outcome = apply_secondary_effect(&drain, &environment, &mut user, Some(&mut target), 40, &mut rng).unwrap();
+        assert_eq!(outcome.user_hp_change, 20);
+        assert_eq!(user.current_hp, 30);
+        
+        // 回复不超过最大HP
+        user.current_hp = max_hp - 1;
+        apply_secondary_effect(&drain, &environment, &mut user, Some(&mut target), 40, &mut rng).unwrap();
+        assert_eq!(user.current_hp, max_hp);
+        
+        let recoil = MoveEffect::Recoil { damage_ratio: 0.25 };
+        let outcome = apply_secondary_effect(&recoil, &environment, &mut user, Some(&mut target), 40, &mut rng).unwrap();
+        assert_eq!(outcome.user_hp_change, -10);
+        assert_eq!(user.current_hp, max_hp - 10);
+    }
+    
+    #[test]
+    fn test_electric_terrain_blocks_sleep() {
+        let mut environment = BattleEnvironment::default();
+        environment.set_terrain(TerrainType::Electric, Some(5));
+        let mut rng = BattleRng::with_seed(1);
+        let mut user = test_pokemon();
+        let mut target = test_pokemon();
+        let sleep = status_effect(StatusEffect::Sleep);
+        
+        let outcome = apply_secondary_effect(&sleep, &environment, &mut user, Some(&mut target), 0, &mut rng).unwrap();
+        assert!(!outcome.status_applied);
+        assert!(!target.has_status(&sleep_status()));
+        
+        // 只阻止睡眠，其他异常状态照常生效
+        let paralysis = status_effect(StatusEffect::Paralysis);
+        assert!(apply_secondary_effect(&paralysis, &environment, &mut user, Some(&mut target), 0, &mut rng).unwrap().status_applied);
+        
+        // 不着地的宝可梦不受场地保护
+        user.ability_id = ABILITY_LEVITATE;
+        assert!(apply_secondary_effect(&sleep, &environment, &mut target, Some(&mut user), 0, &mut rng).unwrap().status_applied);
+        
+        // 薄雾场地阻止所有异常状态
+        environment.set_terrain(TerrainType::Misty, Some(5));
+        let mut fresh = test_pokemon();
+        assert!(!apply_secondary_effect(&paralysis, &environment, &mut target, Some(&mut fresh), 0, &mut rng).unwrap().status_applied);
+        assert!(environment.terrain_blocks_status(&fresh, &StatusCondition::Burn));
+        assert!(!environment.terrain_blocks_status(&fresh, &StatusCondition::Flinch));
+    }
+    
+    #[test]
+    fn test_timeout_ends_battle_with_higher_hp_winner() {
+        use std::sync::{Arc, Mutex};
+        
+        EventSystem::init().unwrap();
+        let mut weakened = test_pokemon();
+        weakened.current_hp /= 2;
+        let participants = vec![BattleParticipant::new(vec![test_pokemon()]), BattleParticipant::new(vec![weakened])];
+        let healthy_id = participants[0].trainer_id;
+        let trainer_ids = [participants[0].trainer_id, participants[1].trainer_id];
+        
+        let config = BattleConfig { time_limit_seconds: Some(60), ..BattleConfig::default() };
+        let mut battle = BattleContext::new(1, config, participants).unwrap();
+        
+        // 其他测试也可能发出战斗结束事件，只记录本场战斗的
+        let winners = Arc::new(Mutex::new(Vec::new()));
+        let recorded = winners.clone();
+        let _subscription = EventSystem::subscribe_scoped(move |event: &BattleEndEvent| {
+            if event.winner_id.is_some_and(|id| trainer_ids.contains(&id)) {
+                recorded.lock().unwrap().push(event.winner_id);
+            }
+            Ok(())
+        }).unwrap();
+        
+        let start = battle.start_time;
+        assert!(!battle.update(start + Duration::from_secs(59)).unwrap());
+        assert_ne!(battle.state, BattleStatus::BattleEnd);
+        
+        assert!(battle.update(start + Duration::from_secs(61)).unwrap());
+        assert_eq!(battle.state, BattleStatus::BattleEnd);
+        assert_eq!(*winners.lock().unwrap(), vec![Some(healthy_id)]);
+        
+        // 已结束的战斗不会再次结束
+        assert!(!battle.update(start + Duration::from_secs(120)).unwrap());
+        assert_eq!(winners.lock().unwrap().len(), 1);
+        
+        // HP比例相同时为平局
+        let participants = vec![BattleParticipant::new(vec![test_pokemon()]), BattleParticipant::new(vec![test_pokemon()])];
+        let draw = BattleContext::new(2, BattleConfig::default(), participants).unwrap();
+        assert_eq!(draw.timeout_winner(), None);
+        assert_eq!(draw.time_limit(), Duration::from_secs(300));
+    }
+    
+    #[test]
+    fn test_active_battle_system_ends_timed_out_battle() {
+        use bevy::prelude::{App, Update};
+        
+        EventSystem::init().unwrap();
+        let participants = vec![BattleParticipant::new(vec![test_pokemon()]), BattleParticipant::new(vec![test_pokemon()])];
+        let mut battle = BattleContext::new(1, BattleConfig::default(), participants).unwrap();
+        battle.start_time = Instant::now() - battle.time_limit() - Duration::from_secs(1);
+        
+        let mut app = App::new();
+        app.insert_resource(ActiveBattle(Some(battle)))
+           .add_systems(Update, update_active_battle);
+        app.update();
+        
+        let active = app.world().resource::<ActiveBattle>();
+        assert_eq!(active.0.as_ref().unwrap().state, BattleStatus::BattleEnd);
+    }
+    
+    #[test]
+    fn test_species_and_item_clauses_reject_teams() {
+        let config = BattleConfig { species_clause: true, item_clause: true, ..BattleConfig::default() };
+        let opponent = || BattleParticipant::new(vec![test_pokemon()]);
+        
+        // 两只同种族的宝可梦
+        let duplicates = BattleParticipant::new(vec![test_pokemon(), test_pokemon()]);
+        assert!(BattleContext::new(1, config.clone(), vec![duplicates, opponent()]).is_err());
+        
+        // 不同种族但携带同一道具
+        let mut first = test_pokemon();
+        let mut second = Pokemon::new(1, 50, None, "Test".to_string(), "Test Location".to_string()).unwrap();
+        first.held_item = Some(ITEM_DAMP_ROCK);
+        second.held_item = Some(ITEM_DAMP_ROCK);
+        let same_items = BattleParticipant::new(vec![first.clone(), second.clone()]);
+        assert!(BattleContext::new(1, config.clone(), vec![same_items, opponent()]).is_err());
+        
+        second.held_item = Some(ITEM_HEAT_ROCK);
+        let valid = BattleParticipant::new(vec![first.clone(), second]);
+        assert!(BattleContext::new(1, config, vec![valid, opponent()]).is_ok());
+        
+        // 未启用条款时允许重复
+        let duplicates = BattleParticipant::new(vec![first.clone(), first]);
+        assert!(BattleContext::new(1, BattleConfig::default(), vec![duplicates, opponent()]).is_ok());
+    }
+    
+    #[test]
+    fn test_sleep_clause_blocks_second_sleeper() {
+        let config = BattleConfig { sleep_clause: true, ..BattleConfig::default() };
+        let user = BattleParticipant::new(vec![test_pokemon()]);
+        let mut opponent = BattleParticipant::new(vec![test_pokemon(), test_pokemon()]);
+        opponent.pokemon[1].apply_status(StatusCondition::Sleep { turns_remaining: 2 });
+        let (user_id, opponent_id) = (user.trainer_id, opponent.trainer_id);
+        let mut battle = BattleContext::new(1, config, vec![user, opponent]).unwrap();
+        
+        let sleep = status_effect(StatusEffect::Sleep);
+        let outcome = battle.resolve_secondary_effect(user_id, 0, (opponent_id, 0), &sleep, 0).unwrap();
+        assert!(!outcome.status_applied);
+        assert!(!battle.participants[1].pokemon[0].has_status(&sleep_status()));
+        
+        // 其他异常状态不受睡眠条款限制
+        let burn = status_effect(StatusEffect::Burn);
+        assert!(battle.resolve_secondary_effect(user_id, 0, (opponent_id, 0), &burn, 0).unwrap().status_applied);
+        
+        // 睡着的宝可梦醒来后可以再次催眠
+        battle.participants[1].pokemon[1].clear_status(&sleep_status());
+        assert!(battle.resolve_secondary_effect(user_id, 0, (opponent_id, 0), &sleep, 0).unwrap().status_applied);
+    }
+    
+    fn sleep_status() -> StatusCondition {
+        StatusCondition::Sleep { turns_remaining: 0 }
+    }
+    
+    #[test]
+    fn test_level_cap_scales_team() {
+        let strong = Pokemon::new(25, 100, None, "Test".to_string(), "Test Location".to_string()).unwrap();
+        let config = BattleConfig { level_cap: Some(50), ..BattleConfig::default() };
+        let participants = || vec![
+            BattleParticipant::new(vec![strong.clone(), test_pokemon()]),
+            BattleParticipant::new(vec![test_pokemon()]),
+        ];
+        
+        // 默认拒绝超过上限的队伍
+        assert!(BattleContext::new(1, config.clone(), participants()).is_err());
+        
+        let config = BattleConfig { level_cap_mode: LevelCapMode::Scale, ..config };
+        let battle = BattleContext::new(1, config, participants()).unwrap();
+        let scaled = &battle.participants[0].pokemon[0];
+        let species = strong.get_species().unwrap();
+        let expected = crate::pokemon::PokemonStats::calculate(
+            &species.base_stats,
+            &strong.individual_values,
+            &strong.effort_values,
+            50,
+            strong.nature,
+        );
+        let stats = scaled.get_stats().unwrap();
+        assert_eq!(scaled.level, 50);
+        assert_eq!(
+            (stats.hp, stats.attack, stats.defense, stats.special_attack, stats.special_defense, stats.speed),
+            (expected.hp, expected.attack, expected.defense, expected.special_attack, expected.special_defense, expected.speed)
+        );
+        assert_eq!(scaled.current_hp, expected.hp);
+        
+        // 原来的宝可梦不受影响
+        assert_eq!(strong.level, 100);
+        assert_eq!(battle.participants[0].pokemon[1].level, 50);
+        
+        // HP按比例换算
+        let mut injured = strong.clone();
+        injured.current_hp = strong.get_stats().unwrap().hp / 2;
+        let scaled = scale_to_level(&injured, 50).unwrap();
+        assert!((scaled.current_hp as i32 - expected.hp as i32 / 2).abs() <= 1);
+    }
+    
+    #[test]
+    fn test_throw_ball_captures_wild_pokemon() {
+        EventSystem::init().unwrap();
+        let master = PokeBall::Master.item_id();
+        let mut player = BattleParticipant::new(vec![test_pokemon()]);
+        player.items.insert(PokeBall::Poke.item_id(), 1);
+        player.items.insert(master, 1);
+        let player_id = player.trainer_id;
+        let wild = BattleParticipant::new(vec![test_pokemon()]);
+        let wild_species = wild.pokemon[0].species_id;
+        
+        // 训练师对战不能投球
+        let mut trainer_battle = BattleContext::new(1, BattleConfig::default(), vec![player.clone(), wild.clone()]).unwrap();
+        assert!(trainer_battle.validate_action(player_id, &BattleAction::ThrowBall { ball_id: 1, target: 0 }).is_err());
+        assert!(trainer_battle.execute_throw_ball(player_id, 1, 0).is_err());
+        
+        let mut battle = BattleContext::new_wild(2, BattleConfig::default(), vec![player, wild], crate::world::Weather::Clear).unwrap();
+        battle.rng = BattleRng::with_seed(7);
+        assert!(battle.validate_action(player_id, &BattleAction::ThrowBall { ball_id: 101, target: 0 }).is_err());
+        // 背包里没有的球和不存在的目标位置都不能投
+        assert!(battle.validate_action(player_id, &BattleAction::ThrowBall { ball_id: PokeBall::Ultra.item_id(), target: 0 }).is_err());
+        assert!(battle.execute_throw_ball(player_id, PokeBall::Ultra.item_id(), 0).is_err());
+        assert!(battle.validate_action(player_id, &BattleAction::ThrowBall { ball_id: master, target: 1 }).is_err());
+        assert!(battle.execute_throw_ball(player_id, master, 1).is_err());
+        assert_eq!(battle.participants[0].item_count(master), 1);
+        
+        let result = battle.execute_throw_ball(player_id, master, 0).unwrap();
+        assert!(result.caught);
+        assert_eq!(battle.participants[0].item_count(master), 0);
+        assert_eq!(battle.participants[0].item_count(PokeBall::Poke.item_id()), 1);
+        assert_eq!(battle.state, BattleStatus::BattleEnd);
+        assert!(battle.participants[1].pokemon.is_empty());
+        assert_eq!(battle.participants[1].active_pokemon, vec![None]);
+        
+        let captured = battle.take_captured_pokemon().unwrap();
+        assert_eq!(captured.species_id, wild_species);
+        assert_eq!(captured.trainer_id, Some(player_id));
+        assert!(battle.take_captured_pokemon().is_none());
+    }
+    
+    #[test]
+    fn test_battle_goes_through_game_mode() {
+        EventSystem::init().unwrap();
+        let game_modes = GameModeManager::shared();
+        game_modes.lock().unwrap().set_mode(crate::game_modes::Nuzlocke::new());
+        let wild_config = || BattleConfig { battle_format: BattleFormat::Wild, ..BattleConfig::default() };
+        let wild = || {
+            let mut wild = BattleParticipant::new(vec![test_pokemon()]);
+            wild.is_ai = true;
+            wild
+        };
+        
+        // 这条道路的首次遭遇是别的种类，不能投球，也不消耗精灵球
+        game_modes.lock().unwrap().on_encounter("route_1", 16);
+        let master = PokeBall::Master.item_id();
+        let mut player = BattleParticipant::new(vec![test_pokemon(), test_pokemon()]);
+        player.items.insert(master, 1);
+        let player_id = player.trainer_id;
+        let mut battle = BattleContext::with_game_modes(1, wild_config(), vec![player.clone(), wild()], game_modes.clone())
+            .unwrap()
+            .with_route("route_1");
+        assert!(battle.execute_throw_ball(player_id, master, 0).is_err());
+        assert_eq!(battle.participants[0].item_count(master), 1);
+        
+        // 战斗结束时濒死的宝可梦按规则永久失去
+        let fainted_id = battle.participants[0].pokemon[0].id;
+        battle.participants[0].pokemon[0].current_hp = 0;
+        battle.end_battle_with_result(None).unwrap();
+        assert_eq!(battle.released_pokemon, vec![fainted_id]);
+        
+        // 永久失去的宝可梦不能再出场，标准规则下没有限制
+        assert!(BattleContext::with_game_modes(2, wild_config(), vec![player.clone(), wild()], game_modes.clone()).is_err());
+        game_modes.lock().unwrap().set_mode(crate::game_modes::Standard);
+        assert!(BattleContext::with_game_modes(3, wild_config(), vec![player, wild()], game_modes).is_ok());
+    }
+    
+    #[test]
+    fn test_wild_battle_turn_runs_through_submit_action() {
+        EventSystem::init().unwrap();
+        let trainer = || {
+            let mut pokemon = test_pokemon();
+            pokemon.learn_move(1, None).unwrap();
+            BattleParticipant::new(vec![pokemon])
+        };
+        let player = trainer();
+        let player_id = player.trainer_id;
+        let mut battle = BattleContext::new_wild(1, BattleConfig::default(), vec![player, trainer()], crate::world::Weather::Clear).unwrap();
+        battle.start_battle().unwrap();
+        assert!(battle.participants[1].is_ai);
+        
+        // 玩家提交行动后野生宝可梦自动选择行动，回合立即结算
+        let max_pp = battle.participants[1].pokemon[0].moves[0].current_pp;
+        battle.submit_action(player_id, BattleAction::UseMove { pokemon_index: 0, move_index: 0, target: BattleTarget::Opponent(0) }).unwrap();
+        assert_eq!(battle.turn_number, 2);
+        assert_eq!(battle.state, BattleStatus::WaitingForAction);
+        assert_eq!(battle.participants[0].pokemon[0].moves[0].current_pp, max_pp - 1);
+        assert_eq!(battle.participants[1].pokemon[0].moves[0].current_pp, max_pp - 1);
+    }
+    
+    #[test]
+    fn test_execute_move_boosted_by_electric_terrain() {
+        EventSystem::init().unwrap();
+        let damage_taken = |terrain: TerrainType| {
+            let mut attacker = test_pokemon();
+            attacker.learn_move(84, None).unwrap();
+            let participants = vec![BattleParticipant::new(vec![attacker]), BattleParticipant::new(vec![test_pokemon()])];
+            let mut battle = BattleContext::new(1, BattleConfig::default(), participants).unwrap();
+            battle.start_battle().unwrap();
+            battle.rng = BattleRng::with_seed(3);
+            battle.environment.set_terrain(terrain, Some(5));
+            
+            let attacker_id = battle.participants[0].trainer_id;
+            let before = battle.participants[1].pokemon[0].current_hp;
+            battle.execute_move(attacker_id, 0, 0, BattleTarget::Opponent(0)).unwrap();
+            before - battle.participants[1].pokemon[0].current_hp
+        };
+        
+        // 着地的使用者在电气场地上使用电属性技能，伤害约为1.3倍
+        let plain = damage_taken(TerrainType::None);
+        let boosted = damage_taken(TerrainType::Electric);
+        assert!(plain > 0);
+        assert!(boosted > plain);
+        assert!((boosted as f32 / plain as f32 - TERRAIN_BOOST).abs() < 0.15);
+    }
+    
+    fn multi_battle() -> BattleContext {
+        let participants = (0..4).map(|_| BattleParticipant::new(vec![test_pokemon(), test_pokemon()])).collect();
+        let config = BattleConfig { battle_type: BattleType::Multi, ..BattleConfig::default() };
+        BattleContext::new(1, config, participants).unwrap()
+    }
+    
+    fn trainer_ids(battle: &BattleContext) -> Vec<u64> {
+        battle.participants.iter().map(|p| p.trainer_id).collect()
+    }
+    
+    #[test]
+    fn test_multi_battle_active_slots_and_targets() {
+        EventSystem::init().unwrap();
+        let mut battle = multi_battle();
+        battle.start_battle().unwrap();
+        
+        // 四名训练师各派出一只，前两名和后两名分别组队
+        assert_eq!(battle.participants.iter().map(|p| p.side).collect::<Vec<_>>(), vec![0, 0, 1, 1]);
+        assert!(battle.participants.iter().all(|p| p.active_pokemon == vec![Some(0)]));
+        
+        let ids = trainer_ids(&battle);
+        let user = (ids[0], 0);
+        assert_eq!(battle.resolve_targets(user, BattleTarget::Ally(0)).unwrap(), vec![(ids[1], 0)]);
+        assert_eq!(battle.resolve_targets(user, BattleTarget::Opponent(1)).unwrap(), vec![(ids[3], 0)]);
+        assert_eq!(battle.resolve_targets((ids[2], 0), BattleTarget::AllOpponents).unwrap(), vec![(ids[0], 0), (ids[1], 0)]);
+        assert_eq!(battle.resolve_targets(user, BattleTarget::All).unwrap(), vec![(ids[1], 0), (ids[2], 0), (ids[3], 0)]);
+        assert!(battle.resolve_targets(user, BattleTarget::Opponent(2)).is_err());
+        assert!(battle.validate_target(user, &BattleTarget::Ally(1)).is_err());
+        for _ in 0..10 {
+            let target = battle.resolve_targets(user, BattleTarget::Random).unwrap();
+            assert!(target == vec![(ids[2], 0)] || target == vec![(ids[3], 0)]);
+        }
+        
+        // 单打没有队友可选
+        let single = BattleContext::new(
+            2,
+            BattleConfig::default(),
+            vec![BattleParticipant::new(vec![test_pokemon()]), BattleParticipant::new(vec![test_pokemon()])],
+        ).unwrap();
+        assert!(single.validate_target((single.participants[0].trainer_id, 0), &BattleTarget::Ally(0)).is_err());
+        
+        // 多人对战必须正好四名训练师
+        let config = BattleConfig { battle_type: BattleType::Multi, ..BattleConfig::default() };
+        let three = (0..3).map(|_| BattleParticipant::new(vec![test_pokemon()])).collect();
+        assert!(BattleContext::new(3, config, three).is_err());
+    }
+    
+    #[test]
+    fn test_double_battle_targets_field_slots() {
+        EventSystem::init().unwrap();
+        let participants = (0..2).map(|_| BattleParticipant::new(vec![test_pokemon(), test_pokemon(), test_pokemon()])).collect();
+        let config = BattleConfig { battle_type: BattleType::Double, ..BattleConfig::default() };
+        let mut battle = BattleContext::new(1, config, participants).unwrap();
+        battle.start_battle().unwrap();
+        let ids = trainer_ids(&battle);
+        let user = (ids[0], 0);
+        
+        // 两名训练师各占两个位置，对手的第二个位置和自己的另一只宝可梦都可以选中
+        assert!(battle.validate_target(user, &BattleTarget::Opponent(1)).is_ok());
+        assert!(battle.validate_target(user, &BattleTarget::Opponent(2)).is_err());
+        assert_eq!(battle.resolve_targets(user, BattleTarget::Opponent(1)).unwrap(), vec![(ids[1], 1)]);
+        assert_eq!(battle.resolve_targets(user, BattleTarget::Ally(0)).unwrap(), vec![(ids[0], 1)]);
+        assert_eq!(battle.resolve_targets(user, BattleTarget::AllOpponents).unwrap(), vec![(ids[1], 0), (ids[1], 1)]);
+        
+        // 伤害落在所选位置上的宝可梦
+        let full_hp = battle.participants[1].pokemon[1].current_hp;
+        battle.apply_damage((ids[1], 1), 10).unwrap();
+        assert_eq!(battle.participants[1].pokemon[1].current_hp, full_hp - 10);
+        assert_eq!(battle.participants[1].pokemon[0].current_hp, full_hp);
+        
+        // 濒死的位置被跳过，单体技能改打另一只对手
+        battle.participants[1].pokemon[0].current_hp = 0;
+        assert_eq!(battle.resolve_targets(user, BattleTarget::Opponent(0)).unwrap(), vec![(ids[1], 1)]);
+        assert_eq!(battle.resolve_targets(user, BattleTarget::AllOpponents).unwrap(), vec![(ids[1], 1)]);
+        assert_eq!(battle.resolve_targets(user, BattleTarget::Random).unwrap(), vec![(ids[1], 1)]);
+        battle.participants[0].pokemon[1].current_hp = 0;
+        assert!(battle.resolve_targets(user, BattleTarget::Ally(0)).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_multi_battle_turn_order() {
+        let mut battle = multi_battle();
+        for (participant, speed) in battle.participants.iter_mut().zip([40, 120, 80, 10]) {
+            let pokemon = &mut participant.pokemon[0];
+            let mut stats = pokemon.get_stats().unwrap().clone();
+            stats.speed = speed;
+            pokemon.current_stats = Some(stats);
+        }
+        
+        let ids = trainer_ids(&battle);
+        let attack = || BattleAction::UseMove { pokemon_index: 0, move_index: 0, target: BattleTarget::Opponent(0) };
+        for &id in &ids[..3] {
+            battle.turn_manager.add_action(id, attack()).unwrap();
+        }
+        assert!(!battle.turn_manager.all_actions_submitted(&battle.participants));
+        battle.turn_manager.add_action(ids[3], BattleAction::SwitchPokemon { from_index: 0, to_index: 1 }).unwrap();
+        assert!(battle.turn_manager.all_actions_submitted(&battle.participants));
+        
+        // 换人最先，其余按速度从高到低
+        let order = |battle: &mut BattleContext| -> Vec<u64> {
+            battle.turn_manager.get_sorted_actions(&battle.participants, &mut battle.rng).unwrap().into_iter().map(|(id, _)| id).collect()
+        };
+        assert_eq!(order(&mut battle), vec![ids[3], ids[1], ids[2], ids[0]]);
+        
+        // 速度等级提升后超过原本更快的宝可梦
+        battle.participants[0].pokemon[0].stat_stages.speed = 3;
+        assert_eq!(order(&mut battle), vec![ids[3], ids[1], ids[0], ids[2]]);
+        
+        // 同速时先后由战斗随机数决定，不取决于提交顺序
+        battle.participants[0].pokemon[0].stat_stages.speed = 0;
+        battle.participants[2].pokemon[0].current_stats = battle.participants[0].pokemon[0].current_stats.clone();
+        battle.turn_manager.add_action(ids[3], attack()).unwrap();
+        battle.participants[3].pokemon[0].current_stats = battle.participants[0].pokemon[0].current_stats.clone();
+        let mut first_movers = std::collections::HashSet::new();
+        for seed in 0..32 {
+            battle.rng = BattleRng::with_seed(seed);
+            let sorted = order(&mut battle);
+            assert_eq!(sorted[0], ids[1]);
+            first_movers.insert(sorted[1]);
+        }
+        assert_eq!(first_movers.len(), 3);
+        
+        // 同一种子得到同样的顺序
+        battle.rng = BattleRng::with_seed(5);
+        let replay = order(&mut battle);
+        battle.rng = BattleRng::with_seed(5);
+        assert_eq!(order(&mut battle), replay);
+    }
+    
+    #[test]
+    fn test_multi_battle_continues_after_one_trainer_defeated() {
+        EventSystem::init().unwrap();
+        let mut battle = multi_battle();
+        battle.start_battle().unwrap();
+        let ids = trainer_ids(&battle);
+        
+        // 一名训练师的宝可梦全部倒下，队友还在，战斗继续
+        for pokemon in &mut battle.participants[0].pokemon {
+            pokemon.current_hp = 0;
+        }
+        assert!(!battle.is_battle_ended());
+        
+        // 队友还能战斗时认输只让自己退出，参与者列表保持不变
+        battle.execute_forfeit(ids[2]).unwrap();
+        assert_ne!(battle.state, BattleStatus::BattleEnd);
+        assert_eq!(trainer_ids(&battle), ids);
+        assert!(battle.get_participant(ids[2]).unwrap().forfeited);
+        assert!(!BattleContext::can_battle(battle.get_participant(ids[2]).unwrap()));
+        assert_eq!(battle.opponents_of(ids[1]).unwrap(), vec![ids[3]]);
+        assert_eq!(battle.resolve_targets((ids[1], 0), BattleTarget::AllOpponents).unwrap(), vec![(ids[3], 0)]);
+        
+        // 认输的训练师不再需要提交行动，已经提交的行动也被丢弃
+        let attack = BattleAction::UseMove { pokemon_index: 0, move_index: 0, target: BattleTarget::Opponent(0) };
+        battle.turn_manager.add_action(ids[2], attack.clone()).unwrap();
+        battle.turn_manager.add_action(ids[1], attack.clone()).unwrap();
+        battle.turn_manager.add_action(ids[3], attack).unwrap();
+        assert!(battle.turn_manager.all_actions_submitted(&battle.participants));
+        let order = battle.turn_manager.get_sorted_actions(&battle.participants, &mut battle.rng).unwrap();
+        assert!(order.iter().all(|(id, _)| *id != ids[2]));
+        
+        // 一方全部倒下后战斗结束
+        for pokemon in &mut battle.participants[1].pokemon {
+            pokemon.current_hp = 0;
+        }
+        assert!(battle.is_battle_ended());
+        
+        // 队友已经倒下时认输，战斗直接结束
+        let mut battle = multi_battle();
+        let ids = trainer_ids(&battle);
+        for pokemon in &mut battle.participants[2].pokemon {
+            pokemon.current_hp = 0;
+        }
+        battle.execute_forfeit(ids[3]).unwrap();
+        assert_eq!(battle.state, BattleStatus::BattleEnd);
+    }
+    
     #[test]
     fn test_battle_target_resolution() {
         // TODO: 测试目标解析逻辑
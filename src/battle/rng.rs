@@ -0,0 +1,75 @@
+// 战斗随机数
+// 开发心理：伤害浮动、暴击和附加效果各自调用全局随机数，战斗无法回放，测试也只能断言一个范围
+// 设计原则：一场战斗持有一个可指定种子的随机数源，同一种子和同一串行动得到同样的结果
+
+// 伤害浮动的下限和上限(百分比)，与正作一致按整数百分比取值
+pub const MIN_DAMAGE_ROLL_PERCENT: u8 = 85;
+pub const MAX_DAMAGE_ROLL_PERCENT: u8 = 100;
+
+// 基础暴击率
+pub const CRITICAL_HIT_CHANCE: f32 = 1.0 / 16.0;
+
+#[derive(Debug, Clone)]
+pub struct BattleRng {
+    seed: u64,
+    rng: fastrand::Rng,
+}
+
+impl BattleRng {
+    pub fn new() -> Self {
+        Self::with_seed(fastrand::u64(..))
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: fastrand::Rng::with_seed(seed),
+        }
+    }
+
+    // 回放时用同一种子重建
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // 伤害浮动系数 [0.85, 1.0]
+    pub fn damage_roll(&mut self) -> f32 {
+        self.rng.u8(MIN_DAMAGE_ROLL_PERCENT..=MAX_DAMAGE_ROLL_PERCENT) as f32 / 100.0
+    }
+
+    pub fn chance(&mut self, probability: f32) -> bool {
+        probability >= 1.0 || self.rng.f32() < probability
+    }
+
+    // 从len个候选中随机选一个，len必须大于0
+    pub fn index(&mut self, len: usize) -> usize {
+        self.rng.usize(..len)
+    }
+
+    // 打乱顺序，用于决定速度完全相同的行动谁先出手
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        self.rng.shuffle(items);
+    }
+}
+
+impl Default for BattleRng {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_rolls() {
+        let mut first = BattleRng::with_seed(9);
+        let mut second = BattleRng::with_seed(first.seed());
+        let rolls: Vec<f32> = (0..100).map(|_| first.damage_roll()).collect();
+        assert_eq!(rolls, (0..100).map(|_| second.damage_roll()).collect::<Vec<_>>());
+        assert!(rolls.iter().all(|roll| (0.85..=1.0).contains(roll)));
+        assert!(first.chance(1.0));
+        assert!(!first.chance(0.0));
+    }
+}
@@ -4,7 +4,9 @@
 
 use crate::core::{GameError, Result};
 use crate::pokemon::{Pokemon, Move, StatusCondition, MoveId};
-use crate::battle::{BattleState, BattleParticipant, DamageCalculator, DamageContext, BattleEnvironment};
+use crate::battle::{BattleState, BattleParticipant, DamageContext, BattleEnvironment, BattleRng};
+use crate::battle::damage_calculator::DamageCalculator;
+use crate::battle::rng::CRITICAL_HIT_CHANCE;
 use serde::{Deserialize, Serialize};
 use std::collections::{VecDeque, HashMap};
 use log::{info, debug, warn};
@@ -17,6 +19,7 @@ pub struct TurnManager {
     battle_state: BattleState,
     damage_calculator: DamageCalculator,
     environment: BattleEnvironment,
+    rng: BattleRng,
     turn_history: Vec<TurnResult>,
     speed_modifiers: HashMap<ParticipantId, f32>,
     priority_modifiers: HashMap<ParticipantId, i8>,
@@ -132,12 +135,21 @@ impl TurnManager {
             battle_state,
             damage_calculator: DamageCalculator::new(),
             environment,
+            rng: BattleRng::new(),
             turn_history: Vec::new(),
             speed_modifiers: HashMap::new(),
             priority_modifiers: HashMap::new(),
         }
     }
     
+    // 指定随机数种子，用于回放和测试
+    pub fn with_seed(participants: Vec<BattleParticipant>, environment: BattleEnvironment, seed: u64) -> Self {
+        Self {
+            rng: BattleRng::with_seed(seed),
+            ..Self::new(participants, environment)
+        }
+    }
+    
     // 添加行动到队列
     pub fn queue_action(&mut self, action: BattleAction) -> Result<()> {
         debug!("添加行动到队列: {:?}", action.action_type);
@@ -266,9 +278,10 @@ impl TurnManager {
             type_effectiveness: 1.0,
         };
         
-        // 获取使用者的宝可梦
-        let user_pokemon = self.battle_state.get_active_pokemon(action.participant_id)
-            .ok_or_else(|| GameError::BattleError("无效的参与者ID".to_string()))?;
+        // 获取使用者的名字，之后还要修改战斗状态，不能一直借用宝可梦
+        let user_name = self.battle_state.get_active_pokemon(action.participant_id)
+            .ok_or_else(|| GameError::BattleError("无效的参与者ID".to_string()))?
+            .get_display_name();
         
         // 获取技能数据
         let move_data = crate::pokemon::moves::get_move(move_id)
@@ -287,7 +300,7 @@ impl TurnManager {
         let battle_context = self.create_battle_context(action.participant_id);
         if !move_data.check_accuracy(&battle_context) {
             result.messages.push(format!("{}的{}没有命中！", 
-                user_pokemon.get_display_name(), move_data.name));
+                user_name, move_data.name));
             result.effects.push(ActionEffect::Miss { 
                 target: target_id.unwrap_or(action.participant_id) 
             });
@@ -295,17 +308,17 @@ impl TurnManager {
         }
         
         result.messages.push(format!("{}使用了{}！", 
-            user_pokemon.get_display_name(), move_data.name));
+            user_name, move_data.name));
         
         // 根据技能目标类型处理
-        let actual_targets = self.resolve_move_targets(action.participant_id, move_data, targets)?;
+        let actual_targets = self.resolve_move_targets(action.participant_id, &move_data, targets)?;
         
         // 对每个目标执行技能效果
         for target_id in actual_targets {
             let target_effects = self.apply_move_to_target(
                 action.participant_id, 
                 target_id, 
-                move_data,
+                &move_data,
                 &battle_context
             )?;
             
@@ -326,9 +339,10 @@ impl TurnManager {
     ) -> Result<Vec<ActionEffect>> {
         let mut effects = Vec::new();
         
-        // 获取用户和目标宝可梦
+        // 获取用户和目标宝可梦；使用者取副本，目标需要可变借用
         let user_pokemon = self.battle_state.get_active_pokemon(user_id)
-            .ok_or_else(|| GameError::BattleError("用户宝可梦不存在".to_string()))?;
+            .ok_or_else(|| GameError::BattleError("用户宝可梦不存在".to_string()))?
+            .clone();
         
         let target_pokemon = self.battle_state.get_active_pokemon_mut(target_id)
             .ok_or_else(|| GameError::BattleError("目标宝可梦不存在".to_string()))?;
@@ -336,14 +350,14 @@ impl TurnManager {
         // 处理伤害技能
         if let Some(_power) = move_data.power {
             let damage_context = crate::battle::damage_calculator::create_damage_context(
-                user_pokemon,
+                &user_pokemon,
                 target_pokemon,
                 move_data,
                 &self.environment,
-                fastrand::f32() < 0.0625, // 1/16概率暴击
+                self.rng.chance(CRITICAL_HIT_CHANCE),
             );
             
-            let damage_result = self.damage_calculator.calculate_damage(&damage_context)?;
+            let damage_result = self.damage_calculator.roll_damage(&damage_context, &mut self.rng)?;
             
             if damage_result.final_damage > 0 {
                 let fainted = target_pokemon.take_damage(damage_result.final_damage as u16);
@@ -372,7 +386,7 @@ impl TurnManager {
         
         // 处理次要效果
         for secondary_effect in &move_data.secondary_effects {
-            if fastrand::f32() < secondary_effect.chance {
+            if self.rng.chance(secondary_effect.trigger_chance()) {
                 let effect_results = self.apply_move_effect(&secondary_effect.effect, target_id)?;
                 effects.extend(effect_results);
             }
@@ -398,8 +412,8 @@ impl TurnManager {
                     _ => StatusCondition::None,
                 };
                 
-                if !status_condition.eq(&StatusCondition::None) {
-                    target_pokemon.apply_status(status_condition.clone());
+                // 属性免疫时状态不生效，也不产生状态变化效果
+                if !status_condition.eq(&StatusCondition::None) && target_pokemon.apply_status(status_condition.clone()) {
                     effects.push(ActionEffect::StatusChange {
                         status: status_condition,
                         target: target_id,
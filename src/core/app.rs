@@ -13,7 +13,7 @@ use bevy::prelude::*;
 use std::time::{Duration, Instant};
 use tracing::{info, warn, error};
 
-use crate::core::{config::GameConfig, error::GameResult, time::GameTimer};
+use crate::core::{config::GameConfig, error::GameResult, event_system::EventSystem, time::GameTimer};
 
 #[derive(Resource, Debug)]
 pub struct PokemonAppState {
@@ -74,6 +74,11 @@ impl Plugin for PokemonApp {
     fn build(&self, app: &mut App) {
         info!("初始化Pokemon应用程序核心");
         
+        // 战斗等模块通过全局事件系统排队事件，启动阶段的系统就可能用到
+        if let Err(e) = EventSystem::init() {
+            error!("事件系统初始化失败: {}", e);
+        }
+        
         app.init_resource::<PokemonAppState>()
            .add_event::<AppInitializedEvent>()
            .add_event::<AppShutdownEvent>()
@@ -92,6 +97,7 @@ impl Plugin for PokemonApp {
            .add_systems(Last, (
                cleanup_expired_data,
                send_performance_events,
+               flush_queued_events,
            ));
 
         #[cfg(debug_assertions)]
@@ -249,6 +255,13 @@ fn cleanup_expired_data(
     }
 }
 
+// 本帧排队的事件在所有逻辑系统运行完后统一分发，与自定义引擎主循环的做法一致
+fn flush_queued_events() {
+    if let Err(e) = EventSystem::flush() {
+        error!("分发排队事件失败: {}", e);
+    }
+}
+
 fn send_performance_events(
     app_state: Res<PokemonAppState>,
     mut events: EventWriter<PerformanceUpdateEvent>,
@@ -341,6 +354,31 @@ mod tests {
         assert!(app.world().contains_resource::<PokemonAppState>());
     }
 
+    #[test]
+    fn test_queued_events_flushed_each_frame() {
+        use crate::core::event_system::StateChangeEvent;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        EventSystem::init().unwrap();
+        let mut app = App::new();
+        app.add_systems(Last, flush_queued_events);
+
+        let delivered = Arc::new(AtomicUsize::new(0));
+        let counter = delivered.clone();
+        let _subscription = EventSystem::subscribe_scoped(move |event: &StateChangeEvent| {
+            if event.to_state == "flush_test" {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+            Ok(())
+        }).unwrap();
+
+        EventSystem::queue(StateChangeEvent { from_state: String::new(), to_state: "flush_test".to_string() }).unwrap();
+        assert_eq!(delivered.load(Ordering::SeqCst), 0);
+        app.update();
+        assert_eq!(delivered.load(Ordering::SeqCst), 1);
+    }
+
     #[test]
     fn test_performance_stats_default() {
         let stats = PerformanceStats::default();
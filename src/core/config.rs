@@ -22,6 +22,10 @@ use bevy::prelude::*;
 use tracing::{info, warn, error, debug};
 
 use crate::core::error::{GameError, GameResult};
+use crate::utils::ConfigValidator;
+
+// 环境变量覆盖的前缀，字段层级用双下划线分隔，如 POKEMON_GRAPHICS__WIDTH
+pub const ENV_PREFIX: &str = "POKEMON_";
 
 #[derive(Debug, Clone, Resource, Serialize, Deserialize)]
 pub struct GameConfig {
@@ -510,64 +514,203 @@ impl ConfigManager {
     }
 
     fn validate_config(config: &GameConfig) -> GameResult<()> {
-        // 验证图形配置
-        if config.graphics.width == 0 || config.graphics.height == 0 {
-            return Err(GameError::ConfigError("无效的屏幕分辨率".to_string()));
-        }
+        config.validate_layers(&HashMap::new())
+    }
 
-        if config.graphics.max_fps == 0 {
-            return Err(GameError::ConfigError("无效的最大FPS设置".to_string()));
-        }
+    // 分层加载：默认值 -> 配置文件 -> 环境变量 -> 命令行参数，最后统一校验
+    // 只读取 --config 显式指定的文件，不会在当前目录下隐式读写配置
+    pub fn load_from_args(args: &[String]) -> GameResult<GameConfig> {
+        let file = Self::config_file_from_args(args);
+        GameConfig::load(file.as_deref(), &Self::overrides_from_args(args))
+    }
+
+    fn config_file_from_args(args: &[String]) -> Option<PathBuf> {
+        args.iter()
+            .position(|arg| arg == "--config" || arg == "-c")
+            .and_then(|i| args.get(i + 1))
+            .map(PathBuf::from)
+    }
 
-        // 验证音频配置
-        if !(0.0..=1.0).contains(&config.audio.master_volume) {
-            return Err(GameError::ConfigError("音量必须在0.0-1.0之间".to_string()));
+    // 从命令行参数提取覆盖项，支持 --set key=value 和常用快捷参数
+    fn overrides_from_args(args: &[String]) -> Vec<(String, String)> {
+        let mut overrides = Vec::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let pair = |key: &str, value: &str| (key.to_string(), value.to_string());
+            match arg.as_str() {
+                "--set" => overrides.extend(iter.next()
+                    .and_then(|kv| kv.split_once('='))
+                    .map(|(key, value)| pair(key, value))),
+                "--width" => overrides.extend(iter.next().map(|value| pair("graphics.width", value))),
+                "--height" => overrides.extend(iter.next().map(|value| pair("graphics.height", value))),
+                "--fullscreen" => overrides.push(pair("graphics.fullscreen", "true")),
+                "--windowed" => overrides.push(pair("graphics.fullscreen", "false")),
+                "--debug" => {
+                    overrides.push(pair("debug.show_fps", "true"));
+                    overrides.push(pair("debug.dev_console", "true"));
+                },
+                _ => {},
+            }
         }
+        overrides
+    }
+}
+
+// 配置项的来源层，越靠后优先级越高
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigLayer {
+    Default,
+    File(PathBuf),
+    Env(String),
+    Override,
+}
 
-        // 验证Pokemon配置
-        if config.pokemon.max_party_size == 0 || config.pokemon.max_party_size > 6 {
-            return Err(GameError::ConfigError("队伍大小必须在1-6之间".to_string()));
+impl std::fmt::Display for ConfigLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigLayer::Default => write!(f, "默认值"),
+            ConfigLayer::File(path) => write!(f, "配置文件 {:?}", path),
+            ConfigLayer::Env(name) => write!(f, "环境变量 {}", name),
+            ConfigLayer::Override => write!(f, "命令行参数"),
         }
+    }
+}
 
-        Ok(())
+impl GameConfig {
+    // 分层加载：默认值 -> 配置文件 -> 环境变量 -> 显式覆盖，最后统一校验
+    pub fn load(file: Option<&Path>, overrides: &[(String, String)]) -> GameResult<Self> {
+        Self::load_from_sources(file, env::vars(), overrides)
     }
 
-    pub fn load_from_args(args: &[String]) -> GameResult<GameConfig> {
-        let mut config = GameConfig::default();
+    // 同load，环境变量由调用方提供
+    pub fn load_from_sources<I>(file: Option<&Path>, env_vars: I, overrides: &[(String, String)]) -> GameResult<Self>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut merged = toml::Value::try_from(GameConfig::default())
+            .map_err(|e| GameError::ConfigError(format!("序列化默认配置失败: {}", e)))?;
+        let mut origins: HashMap<String, ConfigLayer> = HashMap::new();
+
+        if let Some(path) = file {
+            let content = fs::read_to_string(path)
+                .map_err(|e| GameError::ConfigError(format!("读取配置文件 {:?} 失败: {}", path, e)))?;
+            let file_value: toml::Value = toml::from_str(&content)
+                .map_err(|e| GameError::ConfigError(format!("解析配置文件 {:?} 失败: {}", path, e)))?;
+            merge_value(&mut merged, file_value, "", &ConfigLayer::File(path.to_path_buf()), &mut origins);
+        }
 
-        for (i, arg) in args.iter().enumerate() {
-            match arg.as_str() {
-                "--config" | "-c" => {
-                    if let Some(config_path) = args.get(i + 1) {
-                        let path = PathBuf::from(config_path);
-                        config = Self::load_from_file(&path)?;
-                    }
-                }
-                "--width" => {
-                    if let Some(width) = args.get(i + 1) {
-                        config.graphics.width = width.parse().unwrap_or(1280);
-                    }
-                }
-                "--height" => {
-                    if let Some(height) = args.get(i + 1) {
-                        config.graphics.height = height.parse().unwrap_or(720);
-                    }
-                }
-                "--fullscreen" => config.graphics.fullscreen = true,
-                "--windowed" => config.graphics.fullscreen = false,
-                "--debug" => config.debug = DebugConfig {
-                    show_fps: true,
-                    dev_console: true,
-                    ..Default::default()
-                },
-                _ => {}
+        let mut env_vars: Vec<(String, String)> = env_vars.into_iter()
+            .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+            .collect();
+        env_vars.sort();
+        for (name, raw) in env_vars {
+            let key = name[ENV_PREFIX.len()..].to_lowercase().replace("__", ".");
+            // 同前缀的环境变量可能属于别的程序，不认识的只提示
+            if lookup_value(&mut merged, &key).is_none() {
+                warn!("忽略未知的配置环境变量 {}", name);
+                continue;
             }
+            set_value(&mut merged, &key, &raw, ConfigLayer::Env(name), &mut origins)?;
         }
 
-        Self::validate_config(&config)?;
+        for (key, raw) in overrides {
+            set_value(&mut merged, key, raw, ConfigLayer::Override, &mut origins)?;
+        }
+
+        let config: GameConfig = merged.try_into()
+            .map_err(|e| GameError::ConfigError(format!("合并后的配置无效: {}", e)))?;
+        config.validate_layers(&origins)?;
         Ok(config)
     }
+
+    // 用ConfigValidator校验取值范围，出错时指明是哪一层设置的
+    fn validate_layers(&self, origins: &HashMap<String, ConfigLayer>) -> GameResult<()> {
+        let layered = |key: &str, result: GameResult<()>| {
+            result.map_err(|e| {
+                let layer = origins.get(key).cloned().unwrap_or(ConfigLayer::Default);
+                GameError::ConfigError(format!("{} (来源: {})", e, layer))
+            })
+        };
+
+        layered("graphics.width", ConfigValidator::validate_range(self.graphics.width, 1, 7680, "graphics.width").map(drop))?;
+        layered("graphics.height", ConfigValidator::validate_range(self.graphics.height, 1, 4320, "graphics.height").map(drop))?;
+        layered("graphics.max_fps", ConfigValidator::validate_range(self.graphics.max_fps, 1, 1000, "graphics.max_fps").map(drop))?;
+        for (key, volume) in [
+            ("audio.master_volume", self.audio.master_volume),
+            ("audio.music_volume", self.audio.music_volume),
+            ("audio.sfx_volume", self.audio.sfx_volume),
+            ("audio.voice_volume", self.audio.voice_volume),
+        ] {
+            layered(key, ConfigValidator::validate_range(volume, 0.0, 1.0, key).map(drop))?;
+        }
+        layered("network.server_port", ConfigValidator::validate_port(self.network.server_port).map(drop))?;
+        layered("pokemon.max_party_size", ConfigValidator::validate_range(self.pokemon.max_party_size, 1, 6, "pokemon.max_party_size").map(drop))?;
+        Ok(())
+    }
+}
+
+// 把source递归合并进target，记录每个叶子字段的来源
+fn merge_value(
+    target: &mut toml::Value,
+    source: toml::Value,
+    prefix: &str,
+    layer: &ConfigLayer,
+    origins: &mut HashMap<String, ConfigLayer>,
+) {
+    match (target, source) {
+        (toml::Value::Table(target), toml::Value::Table(source)) => {
+            for (key, value) in source {
+                let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                match target.get_mut(&key) {
+                    Some(existing) => merge_value(existing, value, &path, layer, origins),
+                    None => {
+                        origins.insert(path, layer.clone());
+                        target.insert(key, value);
+                    },
+                }
+            }
+        },
+        (target, source) => {
+            origins.insert(prefix.to_string(), layer.clone());
+            *target = source;
+        },
+    }
+}
+
+// 按点分路径找到字段
+fn lookup_value<'a>(root: &'a mut toml::Value, key: &str) -> Option<&'a mut toml::Value> {
+    key.split('.').try_fold(root, |current, segment| {
+        current.as_table_mut().and_then(|table| table.get_mut(segment))
+    })
+}
+
+// 按点分路径设置单个字段，字符串按原字段的类型解析
+fn set_value(
+    root: &mut toml::Value,
+    key: &str,
+    raw: &str,
+    layer: ConfigLayer,
+    origins: &mut HashMap<String, ConfigLayer>,
+) -> GameResult<()> {
+    let current = lookup_value(root, key)
+        .ok_or_else(|| GameError::ConfigError(format!("未知配置项 {} (来源: {})", key, layer)))?;
+
+    let parsed = match current {
+        toml::Value::Integer(_) => raw.parse().ok().map(toml::Value::Integer),
+        toml::Value::Float(_) => raw.parse().ok().map(toml::Value::Float),
+        toml::Value::Boolean(_) => raw.parse().ok().map(toml::Value::Boolean),
+        toml::Value::String(_) => Some(toml::Value::String(raw.to_string())),
+        _ => toml::from_str::<toml::Table>(&format!("value = {}", raw)).ok()
+            .and_then(|mut table| table.remove("value")),
+    };
+    *current = parsed.ok_or_else(|| GameError::ConfigError(format!(
+        "配置项 {} 的值 {:?} 无法解析 (来源: {})", key, raw, layer
+    )))?;
+    debug!("配置项 {} 被{}覆盖", key, layer);
+    origins.insert(key.to_string(), layer);
+    Ok(())
 }
+
 // Bevy系统插件
 pub struct ConfigPlugin;
 
@@ -640,4 +783,76 @@ mod tests {
         let loaded_config = ConfigManager::load_from_file(&config_path).unwrap();
         assert_eq!(config.graphics.width, loaded_config.graphics.width);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_env_overrides_file_value() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("game.toml");
+        fs::write(&config_path, "[graphics]\nwidth = 1920\nheight = 1080\n\n[audio]\nmusic_volume = 0.5\n").unwrap();
+        let args = |extra: &[&str]| -> Vec<String> {
+            ["game", "--config", config_path.to_str().unwrap()].iter().chain(extra).map(|arg| arg.to_string()).collect()
+        };
+
+        let env_vars = vec![
+            ("POKEMON_GRAPHICS__WIDTH".to_string(), "2560".to_string()),
+            ("HOME".to_string(), "/root".to_string()),
+        ];
+        let overrides = ConfigManager::overrides_from_args(&args(&["--fullscreen", "--debug"]));
+        let config = GameConfig::load_from_sources(Some(&config_path), env_vars, &overrides).unwrap();
+
+        assert_eq!(config.graphics.width, 2560);
+        assert_eq!(config.graphics.height, 1080);
+        assert_eq!(config.audio.music_volume, 0.5);
+        assert!(config.graphics.fullscreen);
+        assert!(config.debug.show_fps && config.debug.dev_console);
+        // 文件中没有的字段保持默认值
+        assert_eq!(config.pokemon.max_party_size, 6);
+
+        // 命令行参数优先于环境变量
+        let env_vars = vec![("POKEMON_GRAPHICS__WIDTH".to_string(), "2560".to_string())];
+        let overrides = ConfigManager::overrides_from_args(&args(&["--set", "graphics.width=800"]));
+        let config = GameConfig::load_from_sources(Some(&config_path), env_vars, &overrides).unwrap();
+        assert_eq!(config.graphics.width, 800);
+    }
+
+    #[test]
+    fn test_layered_validation_reports_layer() {
+        let env_vars = vec![("POKEMON_POKEMON__MAX_PARTY_SIZE".to_string(), "9".to_string())];
+        let error = GameConfig::load_from_sources(None, env_vars, &[]).unwrap_err().to_string();
+        assert!(error.contains("pokemon.max_party_size"));
+        assert!(error.contains("POKEMON_POKEMON__MAX_PARTY_SIZE"));
+
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("game.toml");
+        fs::write(&config_path, "[audio]\nmaster_volume = 1.5\n").unwrap();
+        let error = GameConfig::load_from_sources(Some(&config_path), Vec::new(), &[]).unwrap_err().to_string();
+        assert!(error.contains("audio.master_volume") && error.contains("配置文件"));
+
+        // ConfigValidator的端口范围同样生效
+        let overrides = vec![("network.server_port".to_string(), "80".to_string())];
+        let error = GameConfig::load_from_sources(None, Vec::new(), &overrides).unwrap_err().to_string();
+        assert!(error.contains("端口号") && error.contains("命令行参数"));
+
+        // 不认识的环境变量只提示，拼错的命令行参数和无法解析的值报出来源
+        let env_vars = vec![("POKEMON_GRAPHICS__WIDHT".to_string(), "800".to_string())];
+        assert_eq!(GameConfig::load_from_sources(None, env_vars, &[]).unwrap().graphics.width, 1280);
+        let overrides = ConfigManager::overrides_from_args(&["--set".to_string(), "graphics.widht=800".to_string()]);
+        assert!(GameConfig::load_from_sources(None, Vec::new(), &overrides).is_err());
+        let overrides = ConfigManager::overrides_from_args(&["--width".to_string(), "wide".to_string()]);
+        let error = GameConfig::load_from_sources(None, Vec::new(), &overrides).unwrap_err().to_string();
+        assert!(error.contains("命令行参数"));
+    }
+
+    #[test]
+    fn test_config_file_only_from_explicit_flag() {
+        assert_eq!(ConfigManager::config_file_from_args(&["game".to_string(), "--debug".to_string()]), None);
+
+        // 显式指定但不存在的文件直接报错，不会被创建
+        let temp_dir = TempDir::new().unwrap();
+        let missing = temp_dir.path().join("missing.toml");
+        let args = vec!["game".to_string(), "-c".to_string(), missing.to_str().unwrap().to_string()];
+        assert_eq!(ConfigManager::config_file_from_args(&args), Some(missing.clone()));
+        assert!(ConfigManager::load_from_args(&args).is_err());
+        assert!(!missing.exists());
+    }
+}
@@ -3,13 +3,16 @@
 // 设计模式：使用状态机管理游戏状态，事件驱动的架构
 
 use crate::core::{GameError, Result, GameConfig};
+use crate::core::main_loop::{LoopHooks, MainLoop};
+use crate::core::time::DEFAULT_FIXED_TIMESTEP;
+use crate::core::event_system::EventSystem;
 use crate::graphics::Renderer;
 use crate::audio::AudioManager;
 use crate::input::InputManager;
 use crate::world::WorldManager;
 use crate::battle::BattleEngine;
 use crate::network::NetworkManager;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use log::{info, warn, error};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -38,10 +41,10 @@ pub struct Engine {
     network_manager: Option<NetworkManager>,
     
     // 时间管理
-    last_frame_time: Instant,
     delta_time: Duration,
     frame_count: u64,
     fps: f64,
+    render_alpha: f64,
     
     // 性能监控
     frame_time_buffer: Vec<Duration>,
@@ -64,10 +67,10 @@ impl Engine {
             battle_engine: None,
             network_manager: None,
             
-            last_frame_time: Instant::now(),
             delta_time: Duration::from_secs(0),
             frame_count: 0,
             fps: 0.0,
+            render_alpha: 0.0,
             
             frame_time_buffer: Vec::with_capacity(120),
             avg_frame_time: Duration::from_secs(0),
@@ -77,6 +80,8 @@ impl Engine {
     pub fn initialize(&mut self) -> Result<()> {
         info!("初始化引擎子系统...");
         
+        EventSystem::init()?;
+        
         // 初始化渲染器
         self.renderer = Some(Renderer::new(&self.config.graphics)?);
         
@@ -107,43 +112,17 @@ impl Engine {
     pub fn run(&mut self) -> Result<()> {
         info!("开始游戏主循环");
         
-        while self.state != GameState::Shutdown {
-            self.update_timing();
-            
-            // 处理输入
-            if let Some(ref mut input) = self.input_manager {
-                input.update()?;
-                self.handle_input(input)?;
-            }
-            
-            // 更新游戏逻辑
-            self.update(self.delta_time)?;
-            
-            // 渲染
-            if let Some(ref mut renderer) = self.renderer {
-                renderer.begin_frame()?;
-                self.render(renderer)?;
-                renderer.end_frame()?;
-            }
-            
-            // 性能统计
-            self.update_performance_stats();
-            
-            // 垂直同步
-            if self.config.graphics.vsync {
-                self.limit_framerate();
-            }
-        }
+        // 逻辑按固定步长更新，渲染每帧一次，模拟结果与显示器刷新率无关
+        let mut main_loop = MainLoop::new(DEFAULT_FIXED_TIMESTEP);
+        main_loop.run(self)?;
         
         info!("游戏主循环结束");
         Ok(())
     }
     
-    fn update_timing(&mut self) {
-        let now = Instant::now();
-        self.delta_time = now.duration_since(self.last_frame_time);
-        self.last_frame_time = now;
-        self.frame_count += 1;
+    // 最近一帧渲染时在两次逻辑更新之间的插值比例
+    pub fn render_alpha(&self) -> f64 {
+        self.render_alpha
     }
     
     fn handle_input(&mut self, input: &InputManager) -> Result<()> {
@@ -420,6 +399,53 @@ impl Engine {
     }
 }
 
+impl LoopHooks for Engine {
+    fn on_frame_start(&mut self, real_dt: Duration) -> Result<()> {
+        self.delta_time = real_dt;
+        self.frame_count += 1;
+        
+        // 输入每帧处理一次
+        if let Some(ref mut input) = self.input_manager {
+            input.update()?;
+            self.handle_input(input)?;
+        }
+        Ok(())
+    }
+    
+    fn on_update(&mut self, dt: Duration) -> Result<()> {
+        self.update(dt)?;
+        
+        // 每步排队的事件在逻辑更新完成后统一分发
+        EventSystem::flush()?;
+        Ok(())
+    }
+    
+    fn on_render(&mut self, alpha: f64) -> Result<()> {
+        self.render_alpha = alpha;
+        if let Some(ref mut renderer) = self.renderer {
+            renderer.begin_frame()?;
+            self.render(renderer)?;
+            renderer.end_frame()?;
+        }
+        Ok(())
+    }
+    
+    fn on_frame_end(&mut self) -> Result<()> {
+        // 性能统计
+        self.update_performance_stats();
+        
+        // 垂直同步
+        if self.config.graphics.vsync {
+            self.limit_framerate();
+        }
+        Ok(())
+    }
+    
+    fn should_exit(&self) -> bool {
+        self.state == GameState::Shutdown
+    }
+}
+
 impl Drop for Engine {
     fn drop(&mut self) {
         if self.state != GameState::Shutdown {
@@ -117,8 +117,16 @@ impl fmt::Display for GameError {
             GameError::Map(msg) => write!(f, "地图错误: {}", msg),
             GameError::Network(msg) => write!(f, "网络错误: {}", msg),
             
+            GameError::IOError(msg) => write!(f, "IO错误: {}", msg),
+            GameError::State(msg) => write!(f, "状态错误: {}", msg),
+            GameError::SystemError(msg) => write!(f, "系统错误: {}", msg),
+            GameError::CompressionError(msg) => write!(f, "压缩错误: {}", msg),
+            GameError::SerializationError(msg) => write!(f, "序列化错误: {}", msg),
+            GameError::Player(msg) => write!(f, "玩家错误: {}", msg),
+            GameError::Inventory(msg) => write!(f, "物品错误: {}", msg),
+
             GameError::GenericError(msg) => write!(f, "错误: {}", msg),
-            
+
             GameError::FileError(msg) => write!(f, "文件错误: {}", msg),
             GameError::ParseError(msg) => write!(f, "解析错误: {}", msg),
             
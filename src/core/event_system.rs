@@ -3,8 +3,10 @@
 // 高性能事件分发，支持优先级、过滤器和异步处理
 
 use crate::core::{GameError, Result};
+use crate::core::services::Services;
 use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::any::{Any, TypeId};
 use std::fmt::Debug;
 use serde::{Serialize, Deserialize};
@@ -31,10 +33,21 @@ pub enum EventPriority {
     Highest = 4,
 }
 
+// 处理器处理完事件后是否继续传给更低优先级的处理器
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventPropagation {
+    Continue,
+    Stop,       // 事件已被消费，例如UI拦截了输入
+}
+
+// 订阅ID，用于取消订阅
+pub type SubscriptionId = u64;
+
 // 事件处理器包装
 pub struct EventHandler {
+    pub subscription: SubscriptionId,
     pub priority: EventPriority,
-    pub handler: Box<dyn Fn(&dyn Event) -> Result<()> + Send + Sync>,
+    pub handler: Box<dyn Fn(&dyn Event) -> Result<EventPropagation> + Send + Sync>,
 }
 
 // 事件分发器
@@ -43,6 +56,7 @@ pub struct EventDispatcher {
     event_queue: Mutex<VecDeque<Box<dyn Event>>>,
     enabled: RwLock<bool>,
     stats: RwLock<EventStats>,
+    next_subscription: AtomicU64,
 }
 
 #[derive(Debug, Default)]
@@ -59,6 +73,7 @@ impl EventDispatcher {
             event_queue: Mutex::new(VecDeque::new()),
             enabled: RwLock::new(true),
             stats: RwLock::new(EventStats::default()),
+            next_subscription: AtomicU64::new(1),
         }
     }
 
@@ -66,10 +81,43 @@ impl EventDispatcher {
     pub fn register_handler<T: Event + 'static, F>(&self, handler: F, priority: EventPriority) -> Result<()>
     where
         F: Fn(&T) -> Result<()> + Send + Sync + 'static,
+    {
+        self.subscribe_with_priority(handler, priority).map(|_| ())
+    }
+
+    // 订阅某一类型的事件，返回的ID用于取消订阅
+    pub fn subscribe<T: Event + 'static, F>(&self, handler: F) -> Result<SubscriptionId>
+    where
+        F: Fn(&T) -> Result<()> + Send + Sync + 'static,
+    {
+        self.subscribe_with_priority(handler, EventPriority::Normal)
+    }
+
+    // 订阅并返回守卫，守卫释放时自动取消订阅
+    pub fn subscribe_scoped<T: Event + 'static, F>(&self, handler: F) -> Result<Subscription<'_>>
+    where
+        F: Fn(&T) -> Result<()> + Send + Sync + 'static,
+    {
+        let id = self.subscribe(handler)?;
+        Ok(Subscription { dispatcher: self, id })
+    }
+
+    pub fn subscribe_with_priority<T: Event + 'static, F>(&self, handler: F, priority: EventPriority) -> Result<SubscriptionId>
+    where
+        F: Fn(&T) -> Result<()> + Send + Sync + 'static,
+    {
+        self.subscribe_consuming(move |event: &T| handler(event).map(|_| EventPropagation::Continue), priority)
+    }
+
+    // 订阅可以消费事件的处理器，返回Stop时更低优先级的处理器不再收到该事件
+    pub fn subscribe_consuming<T: Event + 'static, F>(&self, handler: F, priority: EventPriority) -> Result<SubscriptionId>
+    where
+        F: Fn(&T) -> Result<EventPropagation> + Send + Sync + 'static,
     {
         let type_id = TypeId::of::<T>();
+        let subscription = self.next_subscription.fetch_add(1, Ordering::Relaxed);
         
-        let wrapped_handler = Box::new(move |event: &dyn Event| -> Result<()> {
+        let wrapped_handler = Box::new(move |event: &dyn Event| -> Result<EventPropagation> {
             if let Some(typed_event) = event.as_any().downcast_ref::<T>() {
                 handler(typed_event)
             } else {
@@ -78,6 +126,7 @@ impl EventDispatcher {
         });
 
         let event_handler = EventHandler {
+            subscription,
             priority,
             handler: wrapped_handler,
         };
@@ -86,15 +135,35 @@ impl EventDispatcher {
         let handler_list = handlers.entry(type_id).or_insert_with(Vec::new);
         handler_list.push(event_handler);
         
-        // 按优先级排序（高优先级在前）
+        // 按优先级排序（高优先级在前，同优先级保持注册顺序）
         handler_list.sort_by(|a, b| b.priority.cmp(&a.priority));
 
         // 更新统计
         let mut stats = self.stats.write().unwrap();
         stats.handlers_registered += 1;
 
-        debug!("注册事件处理器: {}", std::any::type_name::<T>());
-        Ok(())
+        debug!("注册事件处理器: {} (订阅 {})", std::any::type_name::<T>(), subscription);
+        Ok(subscription)
+    }
+
+    // 取消订阅，返回该订阅是否存在
+    // 注意：不能在事件处理器内部调用，分发期间处理器列表处于读锁中
+    pub fn unsubscribe(&self, subscription: SubscriptionId) -> bool {
+        let mut handlers = self.handlers.write().unwrap();
+        let mut removed = false;
+        handlers.retain(|_, handler_list| {
+            let before = handler_list.len();
+            handler_list.retain(|handler| handler.subscription != subscription);
+            removed |= handler_list.len() != before;
+            !handler_list.is_empty()
+        });
+
+        if removed {
+            let mut stats = self.stats.write().unwrap();
+            stats.handlers_registered = stats.handlers_registered.saturating_sub(1);
+            debug!("取消订阅: {}", subscription);
+        }
+        removed
     }
 
     // 立即分发事件
@@ -109,11 +178,7 @@ impl EventDispatcher {
         if let Some(handler_list) = handlers.get(&type_id) {
             debug!("分发事件: {} 到 {} 个处理器", event.event_type(), handler_list.len());
             
-            for handler in handler_list {
-                if let Err(e) = (handler.handler)(&event) {
-                    warn!("事件处理器执行失败: {}", e);
-                }
-            }
+            Self::run_handlers(handler_list, &event);
         }
 
         // 更新统计
@@ -141,15 +206,26 @@ impl EventDispatcher {
 
     // 处理队列中的所有事件
     pub fn process_queued_events(&self) -> Result<()> {
+        self.flush().map(|_| ())
+    }
+
+    // 按入队顺序分发队列中的事件，返回分发数量
+    // 处理器在分发期间再入队的事件留到下一次flush，每帧调用一次即可
+    pub fn flush(&self) -> Result<usize> {
         let mut queue = self.event_queue.lock().unwrap();
         let events_to_process: Vec<_> = queue.drain(..).collect();
         drop(queue);
 
+        let count = events_to_process.len();
         for event in events_to_process {
             self.dispatch_boxed_event(event)?;
         }
 
-        Ok(())
+        Ok(count)
+    }
+
+    pub fn pending_events(&self) -> usize {
+        self.event_queue.lock().unwrap().len()
     }
 
     // 处理装箱的事件
@@ -164,16 +240,26 @@ impl EventDispatcher {
         if let Some(handler_list) = handlers.get(&type_id) {
             debug!("分发装箱事件: {} 到 {} 个处理器", event.event_type(), handler_list.len());
             
-            for handler in handler_list {
-                if let Err(e) = (handler.handler)(event.as_ref()) {
-                    warn!("事件处理器执行失败: {}", e);
-                }
-            }
+            Self::run_handlers(handler_list, event.as_ref());
         }
 
         Ok(())
     }
 
+    // 按优先级依次调用处理器，直到某个处理器消费了事件
+    fn run_handlers(handler_list: &[EventHandler], event: &dyn Event) {
+        for handler in handler_list {
+            match (handler.handler)(event) {
+                Ok(EventPropagation::Continue) => {},
+                Ok(EventPropagation::Stop) => {
+                    debug!("事件 {} 被订阅 {} 消费", event.event_type(), handler.subscription);
+                    break;
+                },
+                Err(e) => warn!("事件处理器执行失败: {}", e),
+            }
+        }
+    }
+
     // 清空事件队列
     pub fn clear_queue(&self) {
         let mut queue = self.event_queue.lock().unwrap();
@@ -204,34 +290,45 @@ impl EventDispatcher {
     }
 }
 
-// 全局事件系统
-static mut EVENT_SYSTEM: Option<EventDispatcher> = None;
-static INIT: std::sync::Once = std::sync::Once::new();
+// 订阅守卫，释放时取消订阅，避免处理器随对象销毁后仍被调用
+pub struct Subscription<'a> {
+    dispatcher: &'a EventDispatcher,
+    id: SubscriptionId,
+}
+
+impl Subscription<'_> {
+    pub fn id(&self) -> SubscriptionId {
+        self.id
+    }
+}
+
+impl Drop for Subscription<'_> {
+    fn drop(&mut self) {
+        self.dispatcher.unsubscribe(self.id);
+    }
+}
 
+// 全局事件系统
 pub struct EventSystem;
 
 impl EventSystem {
+    fn global() -> &'static std::sync::OnceLock<EventDispatcher> {
+        Services::shared::<EventDispatcher>()
+    }
+
     pub fn init() -> Result<()> {
-        unsafe {
-            INIT.call_once(|| {
-                EVENT_SYSTEM = Some(EventDispatcher::new());
-            });
-        }
+        Self::global().get_or_init(EventDispatcher::new);
         Ok(())
     }
 
     pub fn instance() -> &'static EventDispatcher {
-        unsafe {
-            EVENT_SYSTEM.as_ref().expect("事件系统未初始化")
-        }
+        Self::global().get().expect("事件系统未初始化")
     }
 
     pub fn cleanup() {
-        unsafe {
-            if let Some(ref system) = EVENT_SYSTEM {
-                system.clear_handlers();
-                system.clear_queue();
-            }
+        if let Some(system) = Self::global().get() {
+            system.clear_handlers();
+            system.clear_queue();
         }
     }
 
@@ -251,9 +348,38 @@ impl EventSystem {
         Self::instance().register_handler(handler, priority)
     }
 
+    pub fn subscribe<T: Event + 'static, F>(handler: F) -> Result<SubscriptionId>
+    where
+        F: Fn(&T) -> Result<()> + Send + Sync + 'static,
+    {
+        Self::instance().subscribe(handler)
+    }
+
+    pub fn subscribe_scoped<T: Event + 'static, F>(handler: F) -> Result<Subscription<'static>>
+    where
+        F: Fn(&T) -> Result<()> + Send + Sync + 'static,
+    {
+        Self::instance().subscribe_scoped(handler)
+    }
+
+    pub fn subscribe_consuming<T: Event + 'static, F>(handler: F, priority: EventPriority) -> Result<SubscriptionId>
+    where
+        F: Fn(&T) -> Result<EventPropagation> + Send + Sync + 'static,
+    {
+        Self::instance().subscribe_consuming(handler, priority)
+    }
+
+    pub fn unsubscribe(subscription: SubscriptionId) -> bool {
+        Self::instance().unsubscribe(subscription)
+    }
+
     pub fn process_queue() -> Result<()> {
         Self::instance().process_queued_events()
     }
+
+    pub fn flush() -> Result<usize> {
+        Self::instance().flush()
+    }
 }
 
 // 常用游戏事件定义
@@ -463,4 +589,106 @@ mod tests {
         let result = order.lock().unwrap();
         assert_eq!(*result, vec![2, 3, 1]); // High, Normal, Low
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_subscribe_and_unsubscribe() {
+        let dispatcher = EventDispatcher::new();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        
+        let received_clone = received.clone();
+        let id = dispatcher.subscribe(move |event: &TestEvent| {
+            received_clone.lock().unwrap().push(event.message.clone());
+            Ok(())
+        }).unwrap();
+
+        // 只收到订阅类型的事件
+        dispatcher.dispatch(TestEvent { message: "first".to_string() }).unwrap();
+        dispatcher.dispatch(GameStartEvent).unwrap();
+        assert_eq!(*received.lock().unwrap(), vec!["first".to_string()]);
+
+        assert!(dispatcher.unsubscribe(id));
+        assert!(!dispatcher.unsubscribe(id));
+        dispatcher.dispatch(TestEvent { message: "second".to_string() }).unwrap();
+        assert_eq!(received.lock().unwrap().len(), 1);
+        assert_eq!(dispatcher.get_stats().handlers_registered, 0);
+    }
+
+    #[test]
+    fn test_scoped_subscription_drop() {
+        let dispatcher = EventDispatcher::new();
+        let counter = Arc::new(Mutex::new(0));
+        
+        let counter_clone = counter.clone();
+        let subscription = dispatcher.subscribe_scoped(move |_: &TestEvent| {
+            *counter_clone.lock().unwrap() += 1;
+            Ok(())
+        }).unwrap();
+
+        dispatcher.dispatch(TestEvent { message: "kept".to_string() }).unwrap();
+        drop(subscription);
+        dispatcher.dispatch(TestEvent { message: "dropped".to_string() }).unwrap();
+
+        assert_eq!(*counter.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_consumed_event_stops_propagation() {
+        let dispatcher = EventDispatcher::new();
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let low = order.clone();
+        dispatcher.register_handler(
+            move |_: &TestEvent| {
+                low.lock().unwrap().push("low");
+                Ok(())
+            },
+            EventPriority::Low
+        ).unwrap();
+
+        // 高优先级的UI处理器只拦截"blocked"事件
+        let high = order.clone();
+        dispatcher.subscribe_consuming(
+            move |event: &TestEvent| {
+                high.lock().unwrap().push("high");
+                if event.message == "blocked" {
+                    Ok(EventPropagation::Stop)
+                } else {
+                    Ok(EventPropagation::Continue)
+                }
+            },
+            EventPriority::Highest
+        ).unwrap();
+
+        dispatcher.dispatch(TestEvent { message: "blocked".to_string() }).unwrap();
+        assert_eq!(*order.lock().unwrap(), vec!["high"]);
+
+        dispatcher.queue_event(TestEvent { message: "passed".to_string() }).unwrap();
+        dispatcher.process_queued_events().unwrap();
+        assert_eq!(*order.lock().unwrap(), vec!["high", "high", "low"]);
+    }
+
+    #[test]
+    fn test_flush_delivers_in_fifo_order() {
+        let dispatcher = EventDispatcher::new();
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let received_clone = received.clone();
+        dispatcher.subscribe(move |event: &TestEvent| {
+            received_clone.lock().unwrap().push(event.message.clone());
+            Ok(())
+        }).unwrap();
+
+        for message in ["a", "b", "c"] {
+            dispatcher.queue_event(TestEvent { message: message.to_string() }).unwrap();
+        }
+        // flush之前处理器看不到任何事件
+        assert!(received.lock().unwrap().is_empty());
+        assert_eq!(dispatcher.pending_events(), 3);
+
+        // 立即分发的事件不受队列影响
+        dispatcher.dispatch(TestEvent { message: "now".to_string() }).unwrap();
+        assert_eq!(dispatcher.flush().unwrap(), 3);
+        assert_eq!(*received.lock().unwrap(), vec!["now", "a", "b", "c"]);
+        assert_eq!(dispatcher.flush().unwrap(), 0);
+    }
+}
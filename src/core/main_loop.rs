@@ -0,0 +1,163 @@
+// 固定步长主循环
+// 开发心理：逻辑更新跟着渲染帧走时，同一段操作在60Hz和144Hz的显示器上会得到不同的模拟结果
+// 设计原则：渲染每帧一次，逻辑按GameTime累积器以固定步长更新；真实帧间隔由外部传入，无窗口的测试可以直接模拟时间流逝
+
+use super::time::{FixedSteps, GameTime};
+use super::Result;
+use std::time::{Duration, Instant};
+
+// 主循环回调，由引擎或测试实现
+pub trait LoopHooks {
+    // 每帧开始时调用一次，用于处理输入等按帧进行的工作
+    fn on_frame_start(&mut self, _real_dt: Duration) -> Result<()> {
+        Ok(())
+    }
+
+    // 以固定步长推进模拟，一帧内可能调用零次或多次
+    fn on_update(&mut self, dt: Duration) -> Result<()>;
+
+    // 每帧渲染一次，alpha为当前时刻在上一步和下一步之间的比例[0, 1)，用于插值显示
+    fn on_render(&mut self, alpha: f64) -> Result<()>;
+
+    fn on_frame_end(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn should_exit(&self) -> bool;
+}
+
+#[derive(Debug, Clone)]
+pub struct MainLoop {
+    game_time: GameTime,
+    frame_count: u64,
+    update_count: u64,
+}
+
+impl MainLoop {
+    pub fn new(fixed_timestep: Duration) -> Self {
+        let mut game_time = GameTime::new();
+        game_time.set_fixed_timestep(fixed_timestep);
+        Self {
+            game_time,
+            frame_count: 0,
+            update_count: 0,
+        }
+    }
+
+    pub fn game_time(&self) -> &GameTime {
+        &self.game_time
+    }
+
+    // 暂停、时间缩放通过GameTime控制
+    pub fn game_time_mut(&mut self) -> &mut GameTime {
+        &mut self.game_time
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    pub fn update_count(&self) -> u64 {
+        self.update_count
+    }
+
+    // 按给定的真实帧间隔推进一帧
+    pub fn frame<H: LoopHooks + ?Sized>(&mut self, real_dt: Duration, hooks: &mut H) -> Result<FixedSteps> {
+        hooks.on_frame_start(real_dt)?;
+
+        self.game_time.update(real_dt);
+        let steps = self.game_time.advance(real_dt);
+        let dt = self.game_time.fixed_timestep();
+        for _ in 0..steps.steps {
+            hooks.on_update(dt)?;
+            self.update_count += 1;
+        }
+
+        hooks.on_render(steps.alpha)?;
+        hooks.on_frame_end()?;
+        self.frame_count += 1;
+        Ok(steps)
+    }
+
+    // 用真实时钟驱动，直到回调要求退出
+    pub fn run<H: LoopHooks + ?Sized>(&mut self, hooks: &mut H) -> Result<()> {
+        let mut last_frame = Instant::now();
+        while !hooks.should_exit() {
+            let now = Instant::now();
+            let real_dt = now.duration_since(last_frame);
+            last_frame = now;
+            self.frame(real_dt, hooks)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 以恒定速度移动的物体，记录回调次数
+    #[derive(Default)]
+    struct Simulation {
+        position: f64,
+        updates: u32,
+        renders: u32,
+        alphas: Vec<f64>,
+    }
+
+    impl LoopHooks for Simulation {
+        fn on_update(&mut self, dt: Duration) -> Result<()> {
+            self.position += 3.0 * dt.as_secs_f64();
+            self.updates += 1;
+            Ok(())
+        }
+
+        fn on_render(&mut self, alpha: f64) -> Result<()> {
+            self.renders += 1;
+            self.alphas.push(alpha);
+            Ok(())
+        }
+
+        fn should_exit(&self) -> bool {
+            self.renders >= 1000
+        }
+    }
+
+    fn simulate(frame_time: Duration, frames: u32) -> Simulation {
+        let mut main_loop = MainLoop::new(Duration::from_millis(10));
+        let mut simulation = Simulation::default();
+        for _ in 0..frames {
+            main_loop.frame(frame_time, &mut simulation).unwrap();
+        }
+        assert_eq!(main_loop.update_count(), simulation.updates as u64);
+        assert_eq!(main_loop.frame_count(), frames as u64);
+        simulation
+    }
+
+    #[test]
+    fn test_fixed_updates_independent_of_frame_rate() {
+        // 同样模拟1秒，10ms步长应更新100次，与渲染帧率无关
+        let fast = simulate(Duration::from_millis(4), 250);
+        let slow = simulate(Duration::from_millis(25), 40);
+        assert_eq!(fast.updates, 100);
+        assert_eq!(slow.updates, 100);
+        assert_eq!(fast.renders, 250);
+        assert_eq!(slow.renders, 40);
+        assert_eq!(fast.position, slow.position);
+        assert!(fast.alphas.iter().all(|alpha| (0.0..1.0).contains(alpha)));
+
+        // 帧间隔不是步长的整数倍时，余下的时间体现在alpha里
+        let uneven = simulate(Duration::from_millis(7), 3);
+        assert_eq!(uneven.updates, 2);
+        assert!((uneven.alphas[2] - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_run_until_exit() {
+        let mut main_loop = MainLoop::new(Duration::from_millis(1));
+        let mut simulation = Simulation::default();
+        main_loop.run(&mut simulation).unwrap();
+        assert_eq!(simulation.renders, 1000);
+        assert_eq!(main_loop.frame_count(), 1000);
+    }
+}
@@ -7,7 +7,9 @@ pub mod error;
 pub mod config;
 pub mod event_system;
 pub mod resource_manager;
+pub mod services;
 pub mod time;
+pub mod main_loop;
 
 // 实验性模块 - 需要feature启用
 #[cfg(feature = "custom-engine")]
@@ -23,7 +25,8 @@ pub mod engine;
 // 重新导出核心类型
 pub use error::{GameError, Result};
 pub use config::GameConfig;
-pub use time::{GameTime, Timer};
+pub use time::{GameTime, Timer, FixedSteps};
+pub use main_loop::{LoopHooks, MainLoop};
 
 // 仅在相应feature启用时导出
 #[cfg(feature = "custom-engine")]
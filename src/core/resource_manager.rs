@@ -3,6 +3,7 @@
 // 设计原则：智能缓存、预加载、内存池管理、资源热重载
 
 use crate::core::{GameError, Result};
+use crate::core::services::Services;
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock, Mutex};
 use std::path::{Path, PathBuf};
@@ -48,6 +49,8 @@ pub struct ResourceMetadata {
     pub last_used: Instant,
     pub ref_count: u32,
     pub priority: ResourcePriority,
+    pub type_name: &'static str,    // 存入时的Rust类型，取出时据此检查
+    pub released_at: Option<Instant>, // 最后一个句柄被释放的时间，宽限期过后卸载
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -58,20 +61,49 @@ pub enum ResourcePriority {
     Critical = 4,
 }
 
-// 资源句柄
-#[derive(Debug, Clone)]
+// 资源句柄，克隆和释放时维护元数据中的引用计数
+#[derive(Debug)]
 pub struct ResourceHandle<T> {
     id: ResourceId,
     resource: Arc<RwLock<Option<T>>>,
     metadata: Arc<RwLock<ResourceMetadata>>,
 }
 
-impl<T> ResourceHandle<T> {
+impl<T> Clone for ResourceHandle<T> {
+    fn clone(&self) -> Self {
+        {
+            let mut meta = self.metadata.write().unwrap();
+            meta.ref_count += 1;
+            meta.released_at = None;
+        }
+        Self {
+            id: self.id,
+            resource: self.resource.clone(),
+            metadata: self.metadata.clone(),
+        }
+    }
+}
+
+impl<T> Drop for ResourceHandle<T> {
+    fn drop(&mut self) {
+        // 锁已中毒时放弃计数，最坏情况是资源不会被自动卸载
+        if let Ok(mut meta) = self.metadata.write() {
+            meta.ref_count = meta.ref_count.saturating_sub(1);
+            if meta.ref_count == 0 {
+                meta.released_at = Some(Instant::now());
+            }
+        }
+    }
+}
+
+impl<T: Clone> ResourceHandle<T> {
     pub fn get(&self) -> Option<Arc<RwLock<T>>> {
         let resource = self.resource.read().unwrap();
         resource.as_ref().map(|r| Arc::new(RwLock::new(r.clone())))
     }
-    
+}
+
+impl<T> ResourceHandle<T> {
     pub fn is_loaded(&self) -> bool {
         let metadata = self.metadata.read().unwrap();
         metadata.state == ResourceState::Loaded
@@ -80,6 +112,10 @@ impl<T> ResourceHandle<T> {
     pub fn get_id(&self) -> ResourceId {
         self.id
     }
+    
+    pub fn ref_count(&self) -> u32 {
+        self.metadata.read().unwrap().ref_count
+    }
 }
 
 // 资源加载器特征
@@ -138,6 +174,12 @@ pub struct CacheConfig {
     pub auto_cleanup: bool,
     pub cleanup_interval_secs: u64,
     pub lru_enabled: bool,
+    #[serde(default = "default_unload_grace_secs")]
+    pub unload_grace_secs: u64,     // 最后一个句柄释放后保留多久，期间再次获取不必重新加载
+}
+
+fn default_unload_grace_secs() -> u64 {
+    30
 }
 
 impl Default for CacheConfig {
@@ -148,6 +190,7 @@ impl Default for CacheConfig {
             auto_cleanup: true,
             cleanup_interval_secs: 60,
             lru_enabled: true,
+            unload_grace_secs: default_unload_grace_secs(),
         }
     }
 }
@@ -204,12 +247,13 @@ impl ResourceManager {
         manager
     }
     
-    // 注册资源加载器
+    // 注册资源加载器，按产出类型装箱，加载时才能按T取回
     pub fn register_loader<T: 'static, L: ResourceLoader<T> + 'static>(
         &self,
         resource_type: ResourceType,
         loader: L,
     ) {
+        let loader: Box<dyn ResourceLoader<T>> = Box::new(loader);
         let mut loaders = self.loaders.write().unwrap();
         loaders.insert(resource_type, Box::new(loader));
     }
@@ -232,14 +276,11 @@ impl ResourceManager {
     ) -> Result<ResourceHandle<T>> {
         let start_time = Instant::now();
         
-        // 检查是否已经加载
-        if let Some(&id) = self.name_to_id.read().unwrap().get(name) {
-            let mut stats = self.stats.write().unwrap();
-            stats.cache_hits += 1;
-            
-            if let Some(handle) = self.get_handle::<T>(id) {
-                return Ok(handle);
-            }
+        // 检查是否已经加载，同名资源类型不符时报错而不是重复加载
+        let cached_id = self.name_to_id.read().unwrap().get(name).copied();
+        if let Some(id) = cached_id {
+            self.stats.write().unwrap().cache_hits += 1;
+            return self.typed_handle::<T>(id);
         }
         
         // 创建新资源
@@ -254,6 +295,8 @@ impl ResourceManager {
             last_used: Instant::now(),
             ref_count: 1,
             priority,
+            type_name: std::any::type_name::<T>(),
+            released_at: None,
         };
         
         let metadata_arc = Arc::new(RwLock::new(metadata));
@@ -306,57 +349,75 @@ impl ResourceManager {
         path: &Path,
     ) -> Result<T> {
         let loaders = self.loaders.read().unwrap();
-        if let Some(loader_any) = loaders.get(resource_type) {
-            if let Some(loader) = loader_any.downcast_ref::<dyn ResourceLoader<T>>() {
-                return loader.load(path);
-            }
-        }
-        Err(GameError::ResourceNotFound(format!(
+        let loader_any = loaders.get(resource_type).ok_or_else(|| GameError::ResourceNotFound(format!(
             "未找到资源类型 {:?} 的加载器",
             resource_type
-        )))
+        )))?;
+        let loader = loader_any.downcast_ref::<Box<dyn ResourceLoader<T>>>().ok_or_else(|| GameError::AssetError(format!(
+            "资源类型 {:?} 的加载器不能产出 {}",
+            resource_type,
+            std::any::type_name::<T>()
+        )))?;
+        loader.load(path)
     }
     
-    // 获取资源句柄
-    pub fn get_handle<T: 'static + Clone>(&self, id: ResourceId) -> Option<ResourceHandle<T>> {
+    // 获取资源句柄，类型与存入时不符时报错
+    pub fn typed_handle<T: 'static + Clone>(&self, id: ResourceId) -> Result<ResourceHandle<T>> {
         let resources = self.resources.read().unwrap();
         let metadata = self.metadata.read().unwrap();
         
-        if let (Some(resource_any), Some(metadata_arc)) = (resources.get(&id), metadata.get(&id)) {
-            if let Some(resource) = resource_any.downcast_ref::<T>() {
-                // 更新最后使用时间
-                {
-                    let mut meta = metadata_arc.write().unwrap();
-                    meta.last_used = Instant::now();
-                    meta.ref_count += 1;
-                }
-                
-                return Some(ResourceHandle {
-                    id,
-                    resource: Arc::new(RwLock::new(Some(resource.clone()))),
-                    metadata: metadata_arc.clone(),
-                });
-            }
+        let (resource_any, metadata_arc) = resources.get(&id).zip(metadata.get(&id))
+            .ok_or_else(|| GameError::ResourceNotFound(format!("资源不存在: {}", id)))?;
+        let resource = resource_any.downcast_ref::<T>()
+            .ok_or_else(|| Self::type_mismatch::<T>(&metadata_arc.read().unwrap()))?;
+        
+        // 更新最后使用时间
+        {
+            let mut meta = metadata_arc.write().unwrap();
+            meta.last_used = Instant::now();
+            meta.ref_count += 1;
+            meta.released_at = None;
         }
         
-        None
+        Ok(ResourceHandle {
+            id,
+            resource: Arc::new(RwLock::new(Some(resource.clone()))),
+            metadata: metadata_arc.clone(),
+        })
+    }
+    
+    pub fn get_handle<T: 'static + Clone>(&self, id: ResourceId) -> Option<ResourceHandle<T>> {
+        self.typed_handle(id).ok()
     }
     
     // 根据名称获取资源
     pub fn get<T: 'static + Clone>(&self, name: &str) -> Option<ResourceHandle<T>> {
-        if let Some(&id) = self.name_to_id.read().unwrap().get(name) {
-            self.get_handle(id)
-        } else {
-            None
-        }
+        let id = self.name_to_id.read().unwrap().get(name).copied()?;
+        self.get_handle(id)
+    }
+    
+    fn type_mismatch<T>(meta: &ResourceMetadata) -> GameError {
+        GameError::AssetError(format!(
+            "资源 {} 的类型是 {}，不能作为 {} 取出",
+            meta.name,
+            meta.type_name,
+            std::any::type_name::<T>()
+        ))
     }
 
-    // 存储资源到管理器中
+    // 存储资源到管理器中，同名的旧资源会被替换
     pub fn store_resource<T: 'static + Clone + Send + Sync>(
         &self,
         name: String,
         resource: T,
     ) -> ResourceHandle<T> {
+        let existing = self.name_to_id.read().unwrap().get(&name).copied();
+        if let Some(old_id) = existing {
+            if let Err(e) = self.unload(old_id) {
+                warn!("替换资源 {} 时卸载旧资源失败: {}", name, e);
+            }
+        }
+        
         let id = self.generate_id();
         let metadata = ResourceMetadata {
             id,
@@ -368,6 +429,8 @@ impl ResourceManager {
             last_used: Instant::now(),
             ref_count: 1,
             priority: ResourcePriority::Normal,
+            type_name: std::any::type_name::<T>(),
+            released_at: None,
         };
 
         let metadata_arc = Arc::new(RwLock::new(metadata));
@@ -395,14 +458,31 @@ impl ResourceManager {
         handle
     }
 
-    // 根据名称获取资源数据
-    pub fn get_resource<T: 'static + Clone>(&self, name: &str) -> Option<T> {
-        if let Some(handle) = self.get::<T>(name) {
-            if let Some(resource_arc) = handle.get() {
-                return Some(resource_arc.read().unwrap().clone());
-            }
+    // 根据名称取出资源数据，不存在或类型不符时报错
+    pub fn resolve<T: 'static + Clone>(&self, name: &str) -> Result<T> {
+        let id = self.name_to_id.read().unwrap().get(name).copied()
+            .ok_or_else(|| GameError::ResourceNotFound(format!("资源名称不存在: {}", name)))?;
+        self.fetch(id)
+    }
+    
+    // 通过句柄取出管理器中的最新数据(热重载后句柄里的副本可能已过期)
+    pub fn get_resource<T: 'static + Clone>(&self, handle: &ResourceHandle<T>) -> Option<T> {
+        self.fetch(handle.get_id()).ok()
+    }
+    
+    fn fetch<T: 'static + Clone>(&self, id: ResourceId) -> Result<T> {
+        let resources = self.resources.read().unwrap();
+        let metadata = self.metadata.read().unwrap();
+        let (resource_any, metadata_arc) = resources.get(&id).zip(metadata.get(&id))
+            .ok_or_else(|| GameError::ResourceNotFound(format!("资源不存在: {}", id)))?;
+        
+        match resource_any.downcast_ref::<T>() {
+            Some(resource) => {
+                metadata_arc.write().unwrap().last_used = Instant::now();
+                Ok(resource.clone())
+            },
+            None => Err(Self::type_mismatch::<T>(&metadata_arc.read().unwrap())),
         }
-        None
     }
     
     // 卸载资源
@@ -412,11 +492,11 @@ impl ResourceManager {
         let mut name_to_id = self.name_to_id.write().unwrap();
         let mut path_to_id = self.path_to_id.write().unwrap();
         
-        if let Some(meta_arc) = metadata.get(&id) {
-            let meta = meta_arc.read().unwrap();
-            let size = meta.size;
-            let name = meta.name.clone();
-            let path = meta.path.clone();
+        if let Some(meta_arc) = metadata.get(&id).cloned() {
+            let (size, name, path) = {
+                let meta = meta_arc.read().unwrap();
+                (meta.size, meta.name.clone(), meta.path.clone())
+            };
             
             resources.remove(&id);
             metadata.remove(&id);
@@ -500,6 +580,36 @@ impl ResourceManager {
         count
     }
     
+    // 卸载所有句柄都已释放且超过宽限期的资源
+    pub fn collect_released(&self) -> usize {
+        let grace = std::time::Duration::from_secs(self.config.unload_grace_secs);
+        let expired: Vec<ResourceId> = self.metadata.read().unwrap()
+            .iter()
+            .filter(|(_, meta_arc)| {
+                let meta = meta_arc.read().unwrap();
+                meta.ref_count == 0 && meta.released_at.is_some_and(|released| released.elapsed() >= grace)
+            })
+            .map(|(&id, _)| id)
+            .collect();
+        
+        let mut count = 0;
+        for id in expired {
+            match self.unload(id) {
+                Ok(()) => count += 1,
+                Err(e) => warn!("卸载已释放资源失败: {}", e),
+            }
+        }
+        
+        if count > 0 {
+            debug!("卸载了 {} 个不再被引用的资源", count);
+        }
+        count
+    }
+    
+    pub fn contains(&self, name: &str) -> bool {
+        self.name_to_id.read().unwrap().contains_key(name)
+    }
+    
     // 内存压力下的紧急清理
     pub fn emergency_cleanup(&self) -> u64 {
         let mut freed_memory = 0u64;
@@ -605,30 +715,28 @@ impl Clone for ResourceStats {
 }
 
 // 全局资源管理器
-static mut RESOURCE_MANAGER: Option<ResourceManager> = None;
-static RESOURCE_INIT: std::sync::Once = std::sync::Once::new();
-
 impl ResourceManager {
+    fn global() -> &'static std::sync::OnceLock<ResourceManager> {
+        Services::shared::<ResourceManager>()
+    }
+    
     pub fn init() -> Result<()> {
-        unsafe {
-            RESOURCE_INIT.call_once(|| {
-                RESOURCE_MANAGER = Some(ResourceManager::new(CacheConfig::default()));
-            });
-        }
+        Self::global().get_or_init(|| ResourceManager::new(CacheConfig::default()));
         Ok(())
     }
     
     pub fn instance() -> &'static ResourceManager {
-        unsafe {
-            RESOURCE_MANAGER.as_ref().expect("资源管理器未初始化")
-        }
+        Self::global().get().expect("资源管理器未初始化")
+    }
+    
+    // 未初始化时返回None，供可选使用全局管理器的模块调用
+    pub fn try_instance() -> Option<&'static ResourceManager> {
+        Self::global().get()
     }
     
     pub fn cleanup() {
-        unsafe {
-            if let Some(ref manager) = RESOURCE_MANAGER {
-                manager.clear();
-            }
+        if let Some(manager) = Self::global().get() {
+            manager.clear();
         }
     }
 }
@@ -711,4 +819,65 @@ mod tests {
         let stats = manager.get_stats();
         assert_eq!(stats.loaded_resources, 0);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_retrieval_checks_stored_type() {
+        let manager = ResourceManager::new(CacheConfig::default());
+        let handle = manager.store_resource("bytes".to_string(), vec![1u8, 2, 3]);
+        
+        assert_eq!(manager.get_resource(&handle), Some(vec![1u8, 2, 3]));
+        assert_eq!(manager.resolve::<Vec<u8>>("bytes").unwrap(), vec![1, 2, 3]);
+        
+        // 类型不符时报错，而不是静默返回None或重复加载
+        let error = manager.resolve::<String>("bytes").unwrap_err();
+        assert!(error.to_string().contains("Vec<u8>"));
+        assert!(manager.get::<String>("bytes").is_none());
+        assert!(manager.typed_handle::<String>(handle.get_id()).is_err());
+        assert!(manager.resolve::<Vec<u8>>("missing").is_err());
+        
+        // 文本加载器不能产出字节数组
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "text").unwrap();
+        assert!(manager.load::<Vec<u8>>(
+            "text",
+            temp_file.path(),
+            ResourceType::Data,
+            ResourcePriority::Normal,
+        ).is_err());
+    }
+    
+    #[test]
+    fn test_dropped_handles_unload_after_grace_period() {
+        let manager = ResourceManager::new(CacheConfig {
+            unload_grace_secs: 0,
+            ..CacheConfig::default()
+        });
+        let handle = manager.store_resource("transient".to_string(), vec![0u8; 16]);
+        let copy = handle.clone();
+        let fetched = manager.get::<Vec<u8>>("transient").unwrap();
+        assert_eq!(handle.ref_count(), 3);
+        
+        drop(handle);
+        drop(fetched);
+        assert_eq!(manager.collect_released(), 0);
+        assert!(manager.contains("transient"));
+        
+        drop(copy);
+        assert_eq!(manager.collect_released(), 1);
+        assert!(!manager.contains("transient"));
+        assert_eq!(manager.get_stats().loaded_resources, 0);
+    }
+    
+    #[test]
+    fn test_reacquired_handle_survives_grace_period() {
+        let manager = ResourceManager::new(CacheConfig::default());
+        drop(manager.store_resource("level".to_string(), "map".to_string()));
+        
+        // 宽限期内仍保留，重新获取后不再等待卸载
+        assert_eq!(manager.collect_released(), 0);
+        let handle = manager.get::<String>("level").unwrap();
+        assert_eq!(handle.ref_count(), 1);
+        assert_eq!(manager.collect_released(), 0);
+        assert!(manager.contains("level"));
+    }
+}
@@ -0,0 +1,129 @@
+// 全局服务注册表
+// 开发心理：各子系统原本各自用static mut保存全局实例，多线程同时访问时是未定义行为，新版编译器也会报警
+// 设计原则：所有全局子系统都经过同一个注册表，按类型懒创建槽位；需要独占修改的服务放在Mutex里，内部已同步的服务放在OnceLock里
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::{LockResult, Mutex, MutexGuard, OnceLock, PoisonError};
+
+// 类型 -> 槽位，槽位创建后永不释放，因此可以返回'static引用
+static REGISTRY: OnceLock<Mutex<HashMap<TypeId, &'static (dyn Any + Send + Sync)>>> = OnceLock::new();
+
+pub struct Services;
+
+impl Services {
+    fn slot<S: Any + Send + Sync + Default>() -> &'static S {
+        // 注册表只在查找槽位时短暂加锁，其中不会执行用户代码，锁中毒时数据仍然完整
+        let mut registry = REGISTRY
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let slot: &'static (dyn Any + Send + Sync) = *registry
+            .entry(TypeId::of::<S>())
+            .or_insert_with(|| Box::leak(Box::new(S::default())));
+        slot.downcast_ref::<S>().expect("服务槽位类型与TypeId不一致")
+    }
+
+    // 需要独占访问的服务(音频、输入、网络、图形、资源注册表)
+    pub fn exclusive<T: Send + 'static>() -> &'static ServiceSlot<T> {
+        Self::slot()
+    }
+
+    // 内部已同步、可共享访问的服务(资源管理器、事件系统)
+    pub fn shared<T: Send + Sync + 'static>() -> &'static OnceLock<T> {
+        Self::slot()
+    }
+}
+
+// 独占服务的槽位，未初始化时为None
+pub struct ServiceSlot<T> {
+    value: Mutex<Option<T>>,
+}
+
+impl<T> Default for ServiceSlot<T> {
+    fn default() -> Self {
+        Self {
+            value: Mutex::new(None),
+        }
+    }
+}
+
+impl<T> ServiceSlot<T> {
+    pub fn lock(&'static self) -> LockResult<MutexGuard<'static, Option<T>>> {
+        self.value.lock()
+    }
+}
+
+// 独占服务的访问守卫，持有期间其他线程无法访问该服务
+pub struct ServiceGuard<T: 'static> {
+    guard: MutexGuard<'static, Option<T>>,
+}
+
+impl<T> ServiceGuard<T> {
+    // 服务未初始化时返回None
+    pub fn new(guard: MutexGuard<'static, Option<T>>) -> Option<Self> {
+        guard.is_some().then_some(Self { guard })
+    }
+}
+
+impl<T> Deref for ServiceGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // new()只在服务存在时创建守卫
+        self.guard.as_ref().expect("服务未初始化")
+    }
+}
+
+impl<T> DerefMut for ServiceGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.guard.as_mut().expect("服务未初始化")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::AssetRegistry;
+    use crate::core::resource_manager::ResourceManager;
+    use std::thread;
+
+    // 在 RUSTFLAGS="-Zsanitizer=thread" 下运行可检查数据竞争
+    #[test]
+    fn test_concurrent_access_to_two_services() {
+        ResourceManager::init().unwrap();
+
+        let workers: Vec<_> = (0..8)
+            .map(|worker| {
+                thread::spawn(move || {
+                    for round in 0..25 {
+                        let name = format!("services_test_{}_{}", worker, round);
+                        drop(ResourceManager::instance().store_resource(name.clone(), vec![worker as u8]));
+                        assert!(ResourceManager::instance().contains(&name));
+
+                        let mut assets = AssetRegistry::instance().unwrap();
+                        assets.add_base_path(format!("services_test_{}", worker));
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        // 两个服务在所有线程中看到的是同一个实例
+        assert!(std::ptr::eq(Services::shared::<ResourceManager>(), Services::shared::<ResourceManager>()));
+        assert!(ResourceManager::instance().contains("services_test_7_24"));
+        let assets = AssetRegistry::instance().unwrap();
+        assert!((0..8).all(|worker| assets.base_paths().contains(&format!("services_test_{}", worker).into())));
+    }
+
+    #[test]
+    fn test_uninitialized_exclusive_service() {
+        struct Unused;
+        let slot = Services::exclusive::<Unused>();
+        assert!(ServiceGuard::new(slot.lock().unwrap()).is_none());
+        assert!(std::ptr::eq(slot, Services::exclusive::<Unused>()));
+    }
+}
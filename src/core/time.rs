@@ -5,6 +5,18 @@
 use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
 
+// 默认固定步长，60Hz
+pub const DEFAULT_FIXED_TIMESTEP: Duration = Duration::from_nanos(16_666_667);
+// 单帧最多追赶的固定步数，避免卡顿后越追越慢
+pub const MAX_FIXED_STEPS_PER_FRAME: u32 = 8;
+
+// 一帧内需要执行的固定步数，alpha用于在上一步和当前步之间插值渲染
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedSteps {
+    pub steps: u32,
+    pub alpha: f64,
+}
+
 // 游戏时间
 #[derive(Debug, Clone)]
 pub struct GameTime {
@@ -15,6 +27,8 @@ pub struct GameTime {
     start_time: Instant,
     last_frame_time: Instant,
     real_delta_time: Duration,
+    fixed_timestep: Duration,
+    accumulator: Duration,
 }
 
 impl GameTime {
@@ -28,9 +42,46 @@ impl GameTime {
             start_time: now,
             last_frame_time: now,
             real_delta_time: Duration::ZERO,
+            fixed_timestep: DEFAULT_FIXED_TIMESTEP,
+            accumulator: Duration::ZERO,
         }
     }
     
+    // 累积真实帧时间，返回本帧应执行的固定步数，模拟结果与帧率无关
+    pub fn advance(&mut self, real_dt: Duration) -> FixedSteps {
+        if !self.is_paused {
+            self.accumulator += Duration::from_secs_f64(real_dt.as_secs_f64() * self.time_scale);
+        }
+        
+        let step_nanos = self.fixed_timestep.as_nanos();
+        let mut steps = (self.accumulator.as_nanos() / step_nanos) as u32;
+        if steps > MAX_FIXED_STEPS_PER_FRAME {
+            // 追不上时丢弃多余的时间，只保留不足一步的部分
+            steps = MAX_FIXED_STEPS_PER_FRAME;
+            self.accumulator = Duration::from_nanos((self.accumulator.as_nanos() % step_nanos) as u64);
+        } else {
+            self.accumulator -= self.fixed_timestep * steps;
+        }
+        
+        FixedSteps {
+            steps,
+            alpha: self.accumulator.as_secs_f64() / self.fixed_timestep.as_secs_f64(),
+        }
+    }
+    
+    pub fn fixed_timestep(&self) -> Duration {
+        self.fixed_timestep
+    }
+    
+    pub fn set_fixed_timestep(&mut self, step: Duration) {
+        self.fixed_timestep = step.max(Duration::from_micros(100));
+        self.accumulator = Duration::ZERO;
+    }
+    
+    pub fn accumulator(&self) -> Duration {
+        self.accumulator
+    }
+    
     pub fn update(&mut self, delta: Duration) {
         let now = Instant::now();
         self.real_delta_time = now.duration_since(self.last_frame_time);
@@ -230,12 +281,37 @@ mod tests {
         
         // 模拟16ms帧时间（约60FPS）
         std::thread::sleep(Duration::from_millis(16));
-        game_time.update();
+        game_time.update(Duration::from_millis(16));
         
         // 由于时间缩放为2.0，游戏时间应该比实际时间快
         assert!(game_time.delta_time > game_time.real_delta_time);
     }
     
+    #[test]
+    fn test_fixed_timestep_accumulator() {
+        let mut game_time = GameTime::new();
+        game_time.set_fixed_timestep(Duration::from_millis(16));
+        
+        // 50ms = 3 * 16ms + 2ms
+        let frame = game_time.advance(Duration::from_millis(50));
+        assert_eq!(frame.steps, 3);
+        assert_eq!(game_time.accumulator(), Duration::from_millis(2));
+        assert!((frame.alpha - 0.125).abs() < 1e-9);
+        
+        // 剩余的2ms累积到下一帧
+        let frame = game_time.advance(Duration::from_millis(14));
+        assert_eq!(frame.steps, 1);
+        assert_eq!(game_time.accumulator(), Duration::ZERO);
+        
+        // 暂停时不累积，长时间卡顿最多追赶固定步数
+        game_time.pause();
+        assert_eq!(game_time.advance(Duration::from_millis(50)).steps, 0);
+        game_time.resume();
+        let frame = game_time.advance(Duration::from_secs(1));
+        assert_eq!(frame.steps, MAX_FIXED_STEPS_PER_FRAME);
+        assert!(game_time.accumulator() < game_time.fixed_timestep());
+    }
+    
     #[test]
     fn test_format_duration() {
         let duration = Duration::from_millis(125500); // 2分5.5秒
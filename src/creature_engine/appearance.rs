@@ -0,0 +1,293 @@
+/*
+ * Pokemon Go - Procedural Creature Appearance
+ * 开发心理过程:
+ * 1. 设计器做出来的种族在美术资源到位前也需要能区分的外观,否则所有同人生物都是同一个占位图
+ * 2. 外观完全由种子和属性决定,同一个种族在任何机器上生成的图案都一样,不需要保存图片
+ * 3. 轮廓只由种子决定,调色板由属性决定:换属性时形状不变、颜色跟着属性走
+ * 4. 输出RGBA8像素,可以直接交给纹理管理器和精灵渲染器使用
+ */
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::{CreatureEngineError, CreatureEngineResult};
+use crate::utils::Color;
+
+// 图案按16x16的格子生成,再放大到目标尺寸
+const GRID: usize = 16;
+
+// 调色板使用独立的随机数流,属性不同也不会影响轮廓
+const PALETTE_STREAM: u64 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Empty,
+    Body,
+    Shade,
+    Accent,
+    Outline,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CreaturePalette {
+    pub primary: Color,
+    pub secondary: Color,
+    pub accent: Color,
+    pub outline: Color,
+}
+
+impl CreaturePalette {
+    /// 由种子和属性生成调色板;主色来自第一个属性,副色来自第二个属性,单属性时取主色的邻近色
+    pub fn generate<S: AsRef<str>>(seed: u64, types: &[S]) -> CreatureEngineResult<Self> {
+        let (primary_type, secondary_type) = match types {
+            [primary] => (primary.as_ref(), None),
+            [primary, secondary] => (primary.as_ref(), Some(secondary.as_ref())),
+            _ => {
+                return Err(CreatureEngineError::ValidationError(format!(
+                    "属性数量必须为1或2个: {}",
+                    types.len()
+                )));
+            },
+        };
+
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(PALETTE_STREAM);
+
+        // 种子只在属性色附近小幅浮动,保证一眼能认出属性
+        let (hue, saturation, value) = type_hsv(primary_type);
+        let primary = Color::from_hsv(
+            hue + rng.gen_range(-10.0..10.0),
+            saturation + rng.gen_range(-0.08..0.08),
+            value + rng.gen_range(-0.08..0.08),
+        );
+
+        let secondary = match secondary_type {
+            Some(secondary_type) => {
+                let (hue, saturation, value) = type_hsv(secondary_type);
+                Color::from_hsv(hue + rng.gen_range(-10.0..10.0), saturation, value * 0.85)
+            },
+            None => Color::from_hsv(hue + rng.gen_range(25.0..50.0), saturation, value * 0.7),
+        };
+
+        // 点缀色取主色的补色,用于眼睛等细节
+        let accent = Color::from_hsv(hue + 180.0, saturation * 0.5, 1.0);
+        let outline = primary.darken(0.6);
+
+        Ok(Self { primary, secondary, accent, outline })
+    }
+
+    fn color_of(&self, cell: Cell) -> [u8; 4] {
+        match cell {
+            Cell::Empty => [0, 0, 0, 0],
+            Cell::Body => self.primary.to_hex().to_be_bytes(),
+            Cell::Shade => self.secondary.to_hex().to_be_bytes(),
+            Cell::Accent => self.accent.to_hex().to_be_bytes(),
+            Cell::Outline => self.outline.to_hex().to_be_bytes(),
+        }
+    }
+}
+
+// 属性的基础色(色相、饱和度、明度),属性名不区分大小写;未知属性按名称散列出一个色相
+fn type_hsv(type_name: &str) -> (f32, f32, f32) {
+    match type_name.to_ascii_lowercase().as_str() {
+        "normal" => (40.0, 0.25, 0.85),
+        "fire" => (15.0, 0.85, 0.95),
+        "water" => (215.0, 0.75, 0.9),
+        "grass" => (110.0, 0.7, 0.8),
+        "electric" => (52.0, 0.85, 0.98),
+        "ice" => (185.0, 0.45, 0.95),
+        "fighting" => (0.0, 0.75, 0.7),
+        "poison" => (285.0, 0.6, 0.7),
+        "ground" => (35.0, 0.6, 0.75),
+        "flying" => (230.0, 0.4, 0.95),
+        "psychic" => (325.0, 0.6, 0.95),
+        "bug" => (75.0, 0.75, 0.7),
+        "rock" => (45.0, 0.5, 0.6),
+        "ghost" => (265.0, 0.45, 0.55),
+        "dragon" => (250.0, 0.75, 0.8),
+        "dark" => (25.0, 0.35, 0.35),
+        "steel" => (200.0, 0.15, 0.75),
+        "fairy" => (330.0, 0.4, 0.98),
+        other => {
+            let hash = other.bytes().fold(0x811C_9DC5u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193));
+            ((hash % 360) as f32, 0.6, 0.85)
+        },
+    }
+}
+
+// RGBA8像素,逐行存储,透明处alpha为0
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreatureSprite {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+    pub palette: CreaturePalette,
+}
+
+impl CreatureSprite {
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let offset = ((y * self.width + x) * 4) as usize;
+        self.pixels[offset..offset + 4].try_into().unwrap()
+    }
+
+    // 转为纹理数据,可直接用TextureManager::create_texture_from_data上传
+    #[cfg(feature = "graphics-wip")]
+    pub fn to_texture_data(&self) -> crate::graphics::texture::TextureData {
+        crate::graphics::texture::TextureData {
+            data: self.pixels.clone(),
+            width: self.width,
+            height: self.height,
+            format: crate::graphics::texture::TextureFormat::RGBA8,
+            mip_level: 0,
+            array_layer: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CreatureSpriteGenerator {
+    size: u32,
+}
+
+impl CreatureSpriteGenerator {
+    /// size为输出图片的边长,必须是16的正整数倍
+    pub fn new(size: u32) -> CreatureEngineResult<Self> {
+        if size == 0 || !(size as usize).is_multiple_of(GRID) {
+            return Err(CreatureEngineError::ConfigError(format!("精灵尺寸必须是{}的倍数: {}", GRID, size)));
+        }
+        Ok(Self { size })
+    }
+
+    pub fn generate<S: AsRef<str>>(&self, seed: u64, types: &[S]) -> CreatureEngineResult<CreatureSprite> {
+        let palette = CreaturePalette::generate(seed, types)?;
+        let cells = generate_cells(seed);
+
+        let scale = self.size as usize / GRID;
+        let mut pixels = Vec::with_capacity((self.size * self.size * 4) as usize);
+        for y in 0..self.size as usize {
+            for x in 0..self.size as usize {
+                pixels.extend_from_slice(&palette.color_of(cells[y / scale][x / scale]));
+            }
+        }
+
+        Ok(CreatureSprite {
+            width: self.size,
+            height: self.size,
+            pixels,
+            palette,
+        })
+    }
+}
+
+impl Default for CreatureSpriteGenerator {
+    fn default() -> Self {
+        Self { size: 64 }
+    }
+}
+
+// 生成左右对称的轮廓:越靠近中心越可能是身体,再加上阴影、眼睛和描边
+fn generate_cells(seed: u64) -> [[Cell; GRID]; GRID] {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut cells = [[Cell::Empty; GRID]; GRID];
+    let half = GRID / 2;
+    let center = GRID as f32 / 2.0;
+
+    // 四周留一格给描边,只生成左半边再镜像
+    for (y, row) in cells.iter_mut().enumerate().take(GRID - 1).skip(1) {
+        for (x, cell) in row.iter_mut().enumerate().take(half).skip(1) {
+            let dx = (x as f32 + 0.5 - center) / (center - 1.0);
+            let dy = (y as f32 + 0.5 - center) / (center - 1.0);
+            let chance = (1.15 - (dx * dx + dy * dy).sqrt()).clamp(0.0, 0.95);
+            if rng.gen::<f32>() < chance {
+                *cell = if rng.gen_bool(0.3) { Cell::Shade } else { Cell::Body };
+            }
+        }
+    }
+
+    // 中心区域始终是身体,避免生成出零散的碎块
+    for row in cells.iter_mut().take(half + 2).skip(half - 2) {
+        for cell in row.iter_mut().take(half).skip(half - 2) {
+            if *cell == Cell::Empty {
+                *cell = Cell::Body;
+            }
+        }
+    }
+
+    // 在上半部分放一对眼睛
+    let eye_y = rng.gen_range(half - 3..half);
+    let eye_x = rng.gen_range(half - 3..half - 1);
+    cells[eye_y][eye_x] = Cell::Accent;
+
+    for row in cells.iter_mut() {
+        for x in 0..half {
+            row[GRID - 1 - x] = row[x];
+        }
+    }
+
+    let filled = cells;
+    for (y, row) in cells.iter_mut().enumerate() {
+        for (x, cell) in row.iter_mut().enumerate() {
+            if *cell != Cell::Empty {
+                continue;
+            }
+            let touches_body = [(0, 1), (2, 1), (1, 0), (1, 2)].iter().any(|&(ox, oy)| {
+                let (nx, ny) = ((x + ox).wrapping_sub(1), (y + oy).wrapping_sub(1));
+                nx < GRID && ny < GRID && filled[ny][nx] != Cell::Empty
+            });
+            if touches_body {
+                *cell = Cell::Outline;
+            }
+        }
+    }
+
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_sprite() {
+        let generator = CreatureSpriteGenerator::new(32).unwrap();
+        let sprite = generator.generate(42, &["fire"]).unwrap();
+        assert_eq!(sprite.pixels.len(), 32 * 32 * 4);
+        assert_eq!(sprite, generator.generate(42, &["Fire"]).unwrap());
+        assert_ne!(sprite.pixels, generator.generate(43, &["fire"]).unwrap().pixels);
+
+        // 左右对称,四角留空
+        for y in 0..32 {
+            for x in 0..16 {
+                assert_eq!(sprite.pixel(x, y), sprite.pixel(31 - x, y));
+            }
+        }
+        assert_eq!(sprite.pixel(0, 0)[3], 0);
+        assert_eq!(sprite.pixel(16, 16)[3], 255);
+
+        assert!(CreatureSpriteGenerator::new(20).is_err());
+        assert!(generator.generate::<&str>(42, &[]).is_err());
+    }
+
+    #[test]
+    fn test_types_shift_palette() {
+        let generator = CreatureSpriteGenerator::default();
+        let fire = generator.generate(7, &["fire"]).unwrap();
+        let water = generator.generate(7, &["water"]).unwrap();
+
+        // 同一种子下轮廓相同,只有颜色随属性变化
+        let alpha = |sprite: &CreatureSprite| sprite.pixels.iter().skip(3).step_by(4).copied().collect::<Vec<_>>();
+        assert_eq!(alpha(&fire), alpha(&water));
+        assert_ne!(fire.pixels, water.pixels);
+
+        let (fire_hue, _, _) = fire.palette.primary.to_hsv();
+        let (water_hue, _, _) = water.palette.primary.to_hsv();
+        assert!(!(30.0..=355.0).contains(&fire_hue), "火属性色相: {}", fire_hue);
+        assert!((200.0..230.0).contains(&water_hue), "水属性色相: {}", water_hue);
+
+        // 第二属性决定副色
+        let dual = CreaturePalette::generate(7, &["fire", "grass"]).unwrap();
+        let (secondary_hue, _, _) = dual.secondary.to_hsv();
+        assert!((95.0..125.0).contains(&secondary_hue), "草属性色相: {}", secondary_hue);
+        assert_eq!(dual.primary, fire.palette.primary);
+    }
+}
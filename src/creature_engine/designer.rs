@@ -0,0 +1,255 @@
+/*
+ * Pokemon Go - Custom Species Designer
+ * 开发心理过程:
+ * 1. 同人生物不应该走一套独立的数据通路,注册成普通的PokemonSpecies后就能直接用于战斗和能力值计算
+ * 2. 注册前校验种族值总和、属性组合、特性和技能表,不合法的设计不会进入种族数据库
+ * 3. 自定义种族的ID在内置编号之外动态分配,也允许导入时指定ID,重复时报错
+ */
+
+use super::{CreatureConfig, CreatureEngineError, CreatureEngineResult};
+use crate::pokemon::species::{
+    self, Color, EggGroup, GenderRatio, GrowthRate, LearnMethod, LearnableMove, PokemonSpecies, PokemonType, Shape,
+};
+use crate::pokemon::{AbilityId, BaseStats, SpeciesId};
+
+// 自定义种族最多两个属性
+const MAX_TYPES: usize = 2;
+
+#[derive(Debug, Clone)]
+pub struct CreatureDesigner {
+    config: CreatureConfig,
+}
+
+impl CreatureDesigner {
+    pub fn new(config: CreatureConfig) -> Self {
+        Self { config }
+    }
+
+    /// 校验设计并注册为新种族,返回动态分配的种族ID
+    pub fn new_species(
+        &self,
+        name: &str,
+        base_stats: BaseStats,
+        types: Vec<PokemonType>,
+        abilities: Vec<AbilityId>,
+        learnset: Vec<LearnableMove>,
+    ) -> CreatureEngineResult<SpeciesId> {
+        let design = self.build_species(0, name, base_stats, types, abilities, learnset)?;
+        let registered = species::register_species_with_new_id(design)
+            .map_err(|e| CreatureEngineError::ConfigError(e.to_string()))?;
+        Ok(registered.id)
+    }
+
+    /// 以指定ID注册,用于导入已有编号的同人种族;ID已被占用时报错
+    pub fn new_species_with_id(
+        &self,
+        id: SpeciesId,
+        name: &str,
+        base_stats: BaseStats,
+        types: Vec<PokemonType>,
+        abilities: Vec<AbilityId>,
+        learnset: Vec<LearnableMove>,
+    ) -> CreatureEngineResult<SpeciesId> {
+        let design = self.build_species(id, name, base_stats, types, abilities, learnset)?;
+        let registered = species::register_species(design)
+            .map_err(|e| CreatureEngineError::ConfigError(e.to_string()))?;
+        Ok(registered.id)
+    }
+
+    fn build_species(
+        &self,
+        id: SpeciesId,
+        name: &str,
+        base_stats: BaseStats,
+        types: Vec<PokemonType>,
+        abilities: Vec<AbilityId>,
+        learnset: Vec<LearnableMove>,
+    ) -> CreatureEngineResult<PokemonSpecies> {
+        if name.trim().is_empty() {
+            return Err(CreatureEngineError::ValidationError("种族名称不能为空".to_string()));
+        }
+        let total = self.validate_base_stats(&base_stats)?;
+        self.validate_types(&types)?;
+        if abilities.is_empty() {
+            return Err(CreatureEngineError::ValidationError("至少需要一个特性".to_string()));
+        }
+        self.validate_learnset(&learnset)?;
+
+        Ok(PokemonSpecies {
+            id,
+            name: name.trim().to_string(),
+            base_stats,
+            types,
+            abilities,
+            hidden_ability: None,
+            catch_rate: 45,
+            // 与内置种族大致相当:种族值总和越高,击败后获得的经验越多
+            base_experience: total / 5,
+            base_friendship: 70,
+            growth_rate: GrowthRate::MediumFast,
+            // 自定义种族不与内置种族孵蛋
+            egg_groups: vec![EggGroup::Undiscovered],
+            gender_ratio: GenderRatio::Equal,
+            height: 100,
+            weight: 100,
+            color: Color::Gray,
+            shape: Shape::Upright,
+            habitat: None,
+            // 第0世代表示非官方种族
+            generation: 0,
+            is_legendary: false,
+            is_mythical: false,
+            evolution_chain: None,
+            learnable_moves: learnset,
+        })
+    }
+
+    fn validate_base_stats(&self, stats: &BaseStats) -> CreatureEngineResult<u32> {
+        let values = [
+            stats.hp,
+            stats.attack,
+            stats.defense,
+            stats.special_attack,
+            stats.special_defense,
+            stats.speed,
+        ];
+        if values.iter().any(|&value| value == 0 || value > 255) {
+            return Err(CreatureEngineError::BalanceError("每项种族值必须在1到255之间".to_string()));
+        }
+
+        let total: u32 = values.iter().map(|&value| value as u32).sum();
+        if total < self.config.min_base_stats || total > self.config.max_base_stats {
+            return Err(CreatureEngineError::BalanceError(format!(
+                "种族值总和 {} 超出范围 [{}, {}]",
+                total, self.config.min_base_stats, self.config.max_base_stats
+            )));
+        }
+        Ok(total)
+    }
+
+    fn validate_types(&self, types: &[PokemonType]) -> CreatureEngineResult<()> {
+        if types.is_empty() || types.len() > MAX_TYPES {
+            return Err(CreatureEngineError::ValidationError(format!("属性数量必须为1或{}个", MAX_TYPES)));
+        }
+        if types.len() == MAX_TYPES && types[0] == types[1] {
+            return Err(CreatureEngineError::ValidationError(format!("重复的属性: {:?}", types[0])));
+        }
+        Ok(())
+    }
+
+    fn validate_learnset(&self, learnset: &[LearnableMove]) -> CreatureEngineResult<()> {
+        for (index, learnable) in learnset.iter().enumerate() {
+            if learnable.learn_method == LearnMethod::LevelUp {
+                match learnable.level {
+                    Some(level) if level >= 1 && level <= self.config.max_level => {},
+                    _ => {
+                        return Err(CreatureEngineError::ValidationError(format!(
+                            "技能 {} 的习得等级无效: {:?}",
+                            learnable.move_id, learnable.level
+                        )));
+                    },
+                }
+            }
+            let duplicated = learnset[..index].iter().any(|other| {
+                other.move_id == learnable.move_id
+                    && other.learn_method == learnable.learn_method
+                    && other.level == learnable.level
+            });
+            if duplicated {
+                return Err(CreatureEngineError::ValidationError(format!("技能 {} 重复", learnable.move_id)));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for CreatureDesigner {
+    fn default() -> Self {
+        Self::new(CreatureConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pokemon::Pokemon;
+
+    fn stats(value: u16) -> BaseStats {
+        BaseStats {
+            hp: value,
+            attack: value,
+            defense: value,
+            special_attack: value,
+            special_defense: value,
+            speed: value,
+        }
+    }
+
+    fn level_up(move_id: u16, level: u8) -> LearnableMove {
+        LearnableMove {
+            move_id,
+            learn_method: LearnMethod::LevelUp,
+            level: Some(level),
+            machine_id: None,
+        }
+    }
+
+    #[test]
+    fn test_custom_species_spawns_pokemon() {
+        let designer = CreatureDesigner::default();
+        let id = designer
+            .new_species("熔岩水母", stats(80), vec![PokemonType::Fire, PokemonType::Water], vec![7], vec![level_up(1, 10)])
+            .unwrap();
+        assert!(id >= species::CUSTOM_SPECIES_ID_START);
+
+        let registered = PokemonSpecies::get(id).unwrap();
+        assert_eq!(registered.name, "熔岩水母");
+        assert_eq!(registered.types, vec![PokemonType::Fire, PokemonType::Water]);
+        assert_eq!(PokemonSpecies::get_by_name("熔岩水母").map(|species| species.id), Some(id));
+
+        // 注册后走普通的个体生成流程
+        let pokemon = Pokemon::new(id, 5, None, "小智".to_string(), "真新镇".to_string()).unwrap();
+        assert_eq!(pokemon.species_id, id);
+        assert_eq!(pokemon.ability_id, 7);
+        assert_eq!(pokemon.get_display_name(), "熔岩水母");
+        assert!(pokemon.get_stats().is_ok());
+
+        // 再注册一个会分配到不同的ID
+        let other = designer
+            .new_species("冰晶蛾", stats(70), vec![PokemonType::Ice], vec![8], Vec::new())
+            .unwrap();
+        assert_ne!(other, id);
+    }
+
+    #[test]
+    fn test_invalid_designs_rejected() {
+        let designer = CreatureDesigner::default();
+        let design = |types: Vec<PokemonType>, base: u16| {
+            designer.new_species("测试种族", stats(base), types, vec![1], Vec::new())
+        };
+
+        // 属性组合: 重复、过多、缺失
+        assert!(matches!(design(vec![PokemonType::Fire, PokemonType::Fire], 80), Err(CreatureEngineError::ValidationError(_))));
+        assert!(design(vec![PokemonType::Fire, PokemonType::Water, PokemonType::Grass], 80).is_err());
+        assert!(design(Vec::new(), 80).is_err());
+
+        // 种族值总和超出范围
+        assert!(matches!(design(vec![PokemonType::Normal], 200), Err(CreatureEngineError::BalanceError(_))));
+        assert!(matches!(design(vec![PokemonType::Normal], 20), Err(CreatureEngineError::BalanceError(_))));
+
+        // 没有特性或习得等级非法
+        assert!(designer.new_species("测试种族", stats(80), vec![PokemonType::Normal], Vec::new(), Vec::new()).is_err());
+        assert!(designer
+            .new_species("测试种族", stats(80), vec![PokemonType::Normal], vec![1], vec![level_up(1, 0)])
+            .is_err());
+
+        // 内置种族和已注册的ID不能重复使用
+        assert!(designer.new_species_with_id(25, "假皮卡丘", stats(80), vec![PokemonType::Electric], vec![1], Vec::new()).is_err());
+        let id = species::CUSTOM_SPECIES_ID_START - 1;
+        designer.new_species_with_id(id, "导入种族", stats(80), vec![PokemonType::Dark], vec![1], Vec::new()).unwrap();
+        assert!(matches!(
+            designer.new_species_with_id(id, "导入种族", stats(80), vec![PokemonType::Dark], vec![1], Vec::new()),
+            Err(CreatureEngineError::ConfigError(_))
+        ));
+    }
+}
@@ -21,6 +21,9 @@ pub mod rarity_system;
 pub mod trait_system;
 pub mod mutation;
 pub mod validator;
+pub mod appearance;
+#[cfg(feature = "pokemon-wip")]
+pub mod designer;
 
 pub use generator::*;
 pub use templates::*;
@@ -30,6 +33,9 @@ pub use rarity_system::*;
 pub use trait_system::*;
 pub use mutation::*;
 pub use validator::*;
+pub use appearance::*;
+#[cfg(feature = "pokemon-wip")]
+pub use designer::CreatureDesigner;
 
 #[derive(Debug, Clone, Error)]
 pub enum CreatureEngineError {
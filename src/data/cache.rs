@@ -2,7 +2,7 @@
 // 开发心理：缓存系统提供内存中的数据存储，减少磁盘IO，提升性能
 // 设计原则：LRU淘汰、内存限制、线程安全、统计监控
 
-use std::collections::{HashMap, LinkedList};
+use std::collections::{HashMap, VecDeque};
 use std::hash::Hash;
 use std::any::{Any, TypeId};
 use std::sync::{Arc, Mutex, RwLock};
@@ -15,7 +15,7 @@ pub struct DataCache {
     storage: Arc<RwLock<HashMap<String, CacheEntry>>>,
     
     // LRU访问顺序
-    access_order: Arc<Mutex<LinkedList<String>>>,
+    access_order: Arc<Mutex<VecDeque<String>>>,
     
     // 缓存配置
     config: CacheConfig,
@@ -80,7 +80,7 @@ impl DataCache {
         
         Ok(Self {
             storage: Arc::new(RwLock::new(HashMap::new())),
-            access_order: Arc::new(Mutex::new(LinkedList::new())),
+            access_order: Arc::new(Mutex::new(VecDeque::new())),
             config,
             statistics: Arc::new(Mutex::new(CacheStatistics::default())),
         })
@@ -88,6 +88,14 @@ impl DataCache {
     
     // 设置缓存项
     pub fn set<T>(&self, key: String, value: T) -> Result<(), GameError>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        self.set_with_ttl(key, value, self.config.default_ttl)
+    }
+    
+    // 设置缓存项并指定过期时间，None表示不会过期
+    pub fn set_with_ttl<T>(&self, key: String, value: T, ttl: Option<std::time::Duration>) -> Result<(), GameError>
     where
         T: Clone + Send + Sync + 'static,
     {
@@ -102,7 +110,7 @@ impl DataCache {
             created_at: now,
             last_accessed: now,
             access_count: 0,
-            ttl: self.config.default_ttl,
+            ttl,
         };
         
         // 检查是否需要清理空间
@@ -175,7 +183,7 @@ impl DataCache {
                 }
                 
                 // 尝试获取数据
-                if let Ok(data) = entry.data.downcast_ref::<T>() {
+                if let Some(data) = entry.data.downcast_ref::<T>() {
                     stats.hits += 1;
                     Some(data.clone())
                 } else {
@@ -503,7 +511,7 @@ impl CacheBuilder {
         
         Ok(DataCache {
             storage: Arc::new(RwLock::new(HashMap::new())),
-            access_order: Arc::new(Mutex::new(LinkedList::new())),
+            access_order: Arc::new(Mutex::new(VecDeque::new())),
             config,
             statistics: Arc::new(Mutex::new(CacheStatistics::default())),
         })
@@ -93,19 +93,27 @@ pub enum DataSourceType {
 
 impl DataLoader {
     pub fn new() -> Result<Self, GameError> {
+        Self::with_root(super::DEFAULT_DATA_DIR)
+    }
+    
+    // 游戏数据从root下的子目录读取，音频、纹理和配置仍然使用各自的目录
+    pub fn with_root(root: impl AsRef<Path>) -> Result<Self, GameError> {
+        let root = root.as_ref();
         let mut data_paths = HashMap::new();
         
         // 设置默认数据路径
-        data_paths.insert(DataType::Pokemon, PathBuf::from("data/pokemon"));
-        data_paths.insert(DataType::Moves, PathBuf::from("data/moves"));
-        data_paths.insert(DataType::Items, PathBuf::from("data/items"));
-        data_paths.insert(DataType::Maps, PathBuf::from("data/maps"));
-        data_paths.insert(DataType::NPCs, PathBuf::from("data/npcs"));
-        data_paths.insert(DataType::Quests, PathBuf::from("data/quests"));
+        data_paths.insert(DataType::Pokemon, root.join("pokemon"));
+        data_paths.insert(DataType::Moves, root.join("moves"));
+        data_paths.insert(DataType::Items, root.join("items"));
+        data_paths.insert(DataType::Maps, root.join("maps"));
+        data_paths.insert(DataType::NPCs, root.join("npcs"));
+        data_paths.insert(DataType::Quests, root.join("quests"));
         data_paths.insert(DataType::Audio, PathBuf::from("assets/audio"));
         data_paths.insert(DataType::Textures, PathBuf::from("assets/textures"));
-        data_paths.insert(DataType::Translations, PathBuf::from("data/translations"));
+        data_paths.insert(DataType::Translations, root.join("translations"));
         data_paths.insert(DataType::Config, PathBuf::from("config"));
+        data_paths.insert(DataType::Abilities, root.join("abilities"));
+        data_paths.insert(DataType::TypeChart, root.join("types"));
         
         // 确保数据目录存在
         for path in data_paths.values() {
@@ -149,8 +157,8 @@ impl DataLoader {
         // 构建文件路径
         let file_path = self.build_file_path(data_type, id)?;
         
-        // 检查文件是否存在
-        if !file_path.exists() {
+        // 检查文件是否存在，file_path不带扩展名，要按支持的格式逐个查找
+        if self.find_data_file(data_type, id).is_none() {
             self.load_statistics.failed_loads += 1;
             return Err(GameError::Data(format!("数据文件不存在: {}", file_path.display())));
         }
@@ -214,6 +222,14 @@ impl DataLoader {
         Ok(())
     }
     
+    // 查找数据文件，返回第一个存在的格式对应的路径
+    pub fn find_data_file(&self, data_type: DataType, id: &str) -> Option<PathBuf> {
+        let file_path = self.build_file_path(data_type, id).ok()?;
+        self.supported_formats.iter()
+            .map(|format| self.try_format_extension(&file_path, *format))
+            .find(|path| path.exists())
+    }
+    
     // 批量加载数据
     pub fn load_batch<T>(&mut self, data_type: DataType, ids: &[String]) -> Result<HashMap<String, T>, GameError>
     where
@@ -522,4 +538,21 @@ mod tests {
         assert_eq!(loaded_data["name"], "Pikachu");
         assert_eq!(loaded_data["type"], "Electric");
     }
+    
+    #[test]
+    fn test_with_root_finds_file_by_extension() {
+        let dir = tempdir().unwrap();
+        let mut loader = DataLoader::with_root(dir.path()).unwrap();
+        
+        assert!(loader.find_data_file(DataType::TypeChart, "type_chart").is_none());
+        assert!(loader.load_data::<serde_json::Value>(DataType::TypeChart, "type_chart").is_err());
+        
+        // 文件名带扩展名，按ID查找时要补上扩展名
+        let path = dir.path().join("types").join("type_chart.json");
+        fs::write(&path, r#"{"Fire": {"Grass": 2.0}}"#).unwrap();
+        assert_eq!(loader.find_data_file(DataType::TypeChart, "type_chart"), Some(path));
+        
+        let chart: serde_json::Value = loader.load_data(DataType::TypeChart, "type_chart").unwrap();
+        assert_eq!(chart["Fire"]["Grass"], 2.0);
+    }
 }
\ No newline at end of file
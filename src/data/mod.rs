@@ -2,9 +2,11 @@
 // 开发心理：数据层负责游戏数据的加载、缓存、序列化、版本管理
 // 设计原则：数据分层、缓存优化、版本兼容、异步加载
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
 use log::{debug, warn, error, info};
 use crate::core::error::GameError;
 
@@ -12,6 +14,12 @@ pub mod loader;
 pub mod cache;
 pub mod serializer;
 pub mod database;
+pub mod registry;
+
+pub use registry::DataRegistry;
+
+// 默认数据目录
+pub const DEFAULT_DATA_DIR: &str = "data";
 
 // 数据管理器
 pub struct DataManager {
@@ -80,6 +88,22 @@ pub enum DataType {
     Textures,       // 纹理数据
     Translations,   // 翻译数据
     Config,         // 配置数据
+    Abilities,      // 特性数据
+    TypeChart,      // 属性相克表
+}
+
+impl DataType {
+    // 整张读取的数据表的文件ID，只有种族、技能、道具、特性和属性相克表按整张表读取
+    pub fn table_id(&self) -> Option<&'static str> {
+        match self {
+            DataType::Pokemon => Some("species"),
+            DataType::Moves => Some("moves"),
+            DataType::Items => Some("items"),
+            DataType::Abilities => Some("abilities"),
+            DataType::TypeChart => Some("type_chart"),
+            _ => None,
+        }
+    }
 }
 
 // 数据元信息
@@ -111,7 +135,12 @@ unsafe impl Sync for DataPackage {}
 
 impl DataManager {
     pub fn new() -> Result<Self, GameError> {
-        let loader = loader::DataLoader::new()?;
+        Self::with_root(DEFAULT_DATA_DIR)
+    }
+    
+    // 从指定的数据目录读取游戏数据
+    pub fn with_root(root: impl AsRef<Path>) -> Result<Self, GameError> {
+        let loader = loader::DataLoader::with_root(root)?;
         let cache = cache::DataCache::new(100 * 1024 * 1024)?; // 100MB缓存
         let serializer = serializer::DataSerializer::new();
         
@@ -141,7 +170,7 @@ impl DataManager {
     // 加载数据
     pub fn load_data<T>(&mut self, data_type: DataType, id: &str) -> Result<T, GameError> 
     where
-        T: for<'de> Deserialize<'de> + Clone + 'static,
+        T: for<'de> Deserialize<'de> + Clone + Send + Sync + 'static,
     {
         let cache_key = format!("{}:{}", data_type as u8, id);
         
@@ -210,7 +239,7 @@ impl DataManager {
     // 保存数据
     pub fn save_data<T>(&mut self, data_type: DataType, id: &str, data: &T) -> Result<(), GameError>
     where
-        T: Serialize + Clone + 'static,
+        T: Serialize + Clone + Send + Sync + 'static,
     {
         let cache_key = format!("{}:{}", data_type as u8, id);
         
@@ -233,7 +262,7 @@ impl DataManager {
     // 批量加载数据
     pub fn load_data_batch<T>(&mut self, data_type: DataType, ids: &[String]) -> Result<HashMap<String, T>, GameError>
     where
-        T: for<'de> Deserialize<'de> + Clone + 'static,
+        T: for<'de> Deserialize<'de> + Clone + Send + Sync + 'static,
     {
         let mut results = HashMap::new();
         let mut cache_hits = 0;
@@ -259,6 +288,58 @@ impl DataManager {
         Ok(results)
     }
     
+    // 整张数据表在缓存中的键，和单条数据一样使用"类型:ID"
+    fn table_key(data_type: DataType) -> Result<(String, &'static str), GameError> {
+        let id = data_type.table_id()
+            .ok_or_else(|| GameError::Data(format!("{:?} 不是整张读取的数据表", data_type)))?;
+        Ok((format!("{}:{}", data_type as u8, id), id))
+    }
+    
+    // 数据目录中是否有这张数据表的文件
+    pub fn has_table_file(&self, data_type: DataType) -> bool {
+        data_type.table_id()
+            .is_some_and(|id| self.loader.find_data_file(data_type, id).is_some())
+    }
+    
+    // 取出缓存中的数据表，没有缓存或按其他类型缓存时返回None
+    pub fn cached_table<T>(&self, data_type: DataType) -> Option<Arc<T>>
+    where
+        T: Send + Sync + 'static,
+    {
+        let (cache_key, _) = Self::table_key(data_type).ok()?;
+        self.cache.get::<Arc<T>>(&cache_key)
+    }
+    
+    // 从数据文件读取整张数据表，不读也不写缓存
+    pub fn read_table<T>(&mut self, data_type: DataType) -> Result<T, GameError>
+    where
+        T: DeserializeOwned,
+    {
+        let (_, id) = Self::table_key(data_type)?;
+        let start_time = std::time::Instant::now();
+        let data = self.loader.load_data::<T>(data_type, id)?;
+        
+        let type_name = std::any::type_name::<T>();
+        self.load_time_stats.insert(type_name.to_string(), start_time.elapsed().as_secs_f32());
+        self.total_data_loaded += 1;
+        
+        Ok(data)
+    }
+    
+    // 把数据表放入缓存，数据表不会过期，只会被重新加载的数据替换
+    pub fn cache_table<T>(&mut self, data_type: DataType, data: Arc<T>) -> Result<(), GameError>
+    where
+        T: Send + Sync + 'static,
+    {
+        let (cache_key, _) = Self::table_key(data_type)?;
+        self.cache.set_with_ttl(cache_key, data, None)
+    }
+    
+    // 从缓存中移除数据表
+    pub fn evict_table(&mut self, data_type: DataType) -> bool {
+        Self::table_key(data_type).is_ok_and(|(cache_key, _)| self.cache.remove(&cache_key))
+    }
+    
     // 预加载数据
     pub fn preload_data(&mut self, data_types: &[DataType]) -> Result<(), GameError> {
         for &data_type in data_types {
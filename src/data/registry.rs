@@ -0,0 +1,286 @@
+// 游戏数据注册表
+// 开发心理：种族、技能、特性、道具和属性相克表散落在各模块的硬编码表里，改一个数值就要重新编译，也没有统一的读取入口
+// 设计原则：数据文件的读取和缓存都交给DataManager，注册表只负责整张数据表的类型检查、内置数据兜底和全部重新加载；任何一张表读取失败时全部保留旧数据
+
+use super::{DataManager, DataType, DEFAULT_DATA_DIR};
+use crate::core::error::GameError;
+use crate::core::services::Services;
+use log::{debug, info, warn};
+use serde::de::DeserializeOwned;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+// 重新读取成功后才执行的替换操作，保证全部读取成功后再统一替换
+type PendingReload = Box<dyn FnOnce(&mut DataManager) -> Result<(), GameError> + Send>;
+type ReloadFn = Box<dyn Fn(&mut DataManager, DataType) -> Result<PendingReload, GameError> + Send>;
+
+// 已加载的数据表，记录读取时使用的类型和重新读取的方法
+struct LoadedTable {
+    type_id: TypeId,
+    type_name: &'static str,
+    reload: ReloadFn,
+}
+
+struct RegistryState {
+    manager: DataManager,
+    tables: HashMap<DataType, LoadedTable>,
+    load_counts: HashMap<DataType, u32>,
+}
+
+impl RegistryState {
+    // 同一张数据表必须始终用同一个类型读取
+    fn check_type<T: 'static>(&self, data_type: DataType) -> Result<(), GameError> {
+        match self.tables.get(&data_type) {
+            Some(table) if table.type_id != TypeId::of::<T>() => Err(GameError::Data(format!(
+                "{:?} 数据已按 {} 加载，不能读取为 {}",
+                data_type, table.type_name, std::any::type_name::<T>()
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn store<T: DeserializeOwned + Send + Sync + 'static>(
+        &mut self,
+        data_type: DataType,
+        data: T,
+        builtin: Option<fn() -> T>,
+    ) -> Result<Arc<T>, GameError> {
+        let data = Arc::new(data);
+        self.manager.cache_table(data_type, data.clone())?;
+        self.tables.entry(data_type).or_insert_with(|| LoadedTable {
+            type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>(),
+            reload: Box::new(move |manager, data_type| read_for_reload(manager, data_type, builtin)),
+        });
+        *self.load_counts.entry(data_type).or_insert(0) += 1;
+        Ok(data)
+    }
+}
+
+// 重新读取数据表；有内置数据的表在数据文件被删掉后退回内置数据
+fn read_for_reload<T: DeserializeOwned + Send + Sync + 'static>(
+    manager: &mut DataManager,
+    data_type: DataType,
+    builtin: Option<fn() -> T>,
+) -> Result<PendingReload, GameError> {
+    let data = match builtin {
+        Some(builtin) if !manager.has_table_file(data_type) => builtin(),
+        _ => manager.read_table::<T>(data_type)?,
+    };
+    Ok(Box::new(move |manager: &mut DataManager| manager.cache_table(data_type, Arc::new(data))))
+}
+
+pub struct DataRegistry {
+    state: Mutex<RegistryState>,
+}
+
+impl DataRegistry {
+    pub fn new(root: impl AsRef<Path>) -> Result<Self, GameError> {
+        Ok(Self {
+            state: Mutex::new(RegistryState {
+                manager: DataManager::with_root(root)?,
+                tables: HashMap::new(),
+                load_counts: HashMap::new(),
+            }),
+        })
+    }
+
+    // 全局注册表，读取默认数据目录
+    pub fn global() -> &'static DataRegistry {
+        Services::shared::<DataRegistry>()
+            .get_or_init(|| DataRegistry::new(DEFAULT_DATA_DIR).expect("创建数据管理器失败"))
+    }
+
+    // 取出一张数据表，第一次访问时通过DataManager从文件加载
+    pub fn get<T: DeserializeOwned + Send + Sync + 'static>(&self, data_type: DataType) -> Result<Arc<T>, GameError> {
+        let mut state = self.state.lock().unwrap();
+        state.check_type::<T>(data_type)?;
+        if let Some(data) = state.manager.cached_table::<T>(data_type) {
+            return Ok(data);
+        }
+
+        let data = state.manager.read_table::<T>(data_type)?;
+        debug!("加载游戏数据: {:?}", data_type);
+        state.store(data_type, data, None)
+    }
+
+    // 取出一张数据表，数据目录中没有这张表或文件损坏时使用内置数据，内置数据同样会被缓存
+    pub fn get_or_builtin<T>(&self, data_type: DataType, builtin: fn() -> T) -> Arc<T>
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+    {
+        let mut state = self.state.lock().unwrap();
+        if let Err(e) = state.check_type::<T>(data_type) {
+            warn!("{}，使用内置数据", e);
+            return Arc::new(builtin());
+        }
+        if let Some(data) = state.manager.cached_table::<T>(data_type) {
+            return data;
+        }
+
+        let data = if state.manager.has_table_file(data_type) {
+            state.manager.read_table::<T>(data_type).unwrap_or_else(|e| {
+                warn!("读取 {:?} 数据失败，使用内置数据: {}", data_type, e);
+                builtin()
+            })
+        } else {
+            debug!("数据目录中没有 {:?} 数据，使用内置数据", data_type);
+            builtin()
+        };
+
+        state.store(data_type, data, Some(builtin)).unwrap_or_else(|e| {
+            warn!("缓存 {:?} 数据失败: {}", data_type, e);
+            Arc::new(builtin())
+        })
+    }
+
+    pub fn is_loaded(&self, data_type: DataType) -> bool {
+        self.state.lock().unwrap().tables.contains_key(&data_type)
+    }
+
+    // 某张数据表被读取的次数，包括重新加载
+    pub fn load_count(&self, data_type: DataType) -> u32 {
+        self.state.lock().unwrap().load_counts.get(&data_type).copied().unwrap_or(0)
+    }
+
+    // 重新读取所有已加载过的数据表，返回重新加载的表数；之前取出的Arc仍然指向旧数据
+    pub fn reload_all(&self) -> Result<usize, GameError> {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+
+        let mut pending = Vec::with_capacity(state.tables.len());
+        for (&data_type, table) in &state.tables {
+            pending.push((data_type, (table.reload)(&mut state.manager, data_type)?));
+        }
+
+        let reloaded = pending.len();
+        for (data_type, replace) in pending {
+            replace(&mut state.manager)?;
+            *state.load_counts.entry(data_type).or_insert(0) += 1;
+        }
+
+        info!("重新加载游戏数据: {} 张表", reloaded);
+        Ok(reloaded)
+    }
+
+    // 清空缓存，下次访问时重新加载
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        let data_types: Vec<DataType> = state.tables.drain().map(|(data_type, _)| data_type).collect();
+        for data_type in data_types {
+            state.manager.evict_table(data_type);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use tempfile::TempDir;
+
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    struct ItemData {
+        id: u32,
+        name: String,
+        price: u32,
+    }
+
+    type TypeChart = HashMap<String, HashMap<String, f32>>;
+
+    fn write(dir: &TempDir, data_type: DataType, json: &str) {
+        let sub_dir = match data_type {
+            DataType::Items => "items",
+            DataType::Moves => "moves",
+            DataType::TypeChart => "types",
+            _ => unreachable!(),
+        };
+        let file = format!("{}.json", data_type.table_id().unwrap());
+        std::fs::write(dir.path().join(sub_dir).join(file), json).unwrap();
+    }
+
+    #[test]
+    fn test_first_access_loads_then_cached() {
+        let dir = TempDir::new().unwrap();
+        let registry = DataRegistry::new(dir.path()).unwrap();
+        write(&dir, DataType::Items, r#"[{"id": 1, "name": "精灵球", "price": 200}]"#);
+        write(&dir, DataType::TypeChart, r#"{"Fire": {"Grass": 2.0, "Water": 0.5}}"#);
+
+        assert!(!registry.is_loaded(DataType::Items));
+        assert_eq!(registry.load_count(DataType::Items), 0);
+
+        let items = registry.get::<Vec<ItemData>>(DataType::Items).unwrap();
+        assert_eq!((items[0].id, items[0].name.as_str()), (1, "精灵球"));
+        assert_eq!(registry.load_count(DataType::Items), 1);
+
+        // 第二次访问命中缓存，文件被删掉也不影响
+        std::fs::remove_file(dir.path().join("items").join("items.json")).unwrap();
+        let again = registry.get::<Vec<ItemData>>(DataType::Items).unwrap();
+        assert!(Arc::ptr_eq(&items, &again));
+        assert_eq!(registry.load_count(DataType::Items), 1);
+
+        // 按错误的类型读取已缓存的数据会报错
+        assert!(registry.get::<Vec<String>>(DataType::Items).is_err());
+
+        let chart = registry.get::<TypeChart>(DataType::TypeChart).unwrap();
+        assert_eq!(chart["Fire"]["Grass"], 2.0);
+
+        // 缺少的文件不会被缓存
+        assert!(registry.get::<Vec<ItemData>>(DataType::Moves).is_err());
+        assert!(!registry.is_loaded(DataType::Moves));
+
+        // 不是整张读取的数据类型直接报错
+        assert!(registry.get::<Vec<ItemData>>(DataType::Maps).is_err());
+    }
+
+    #[test]
+    fn test_reload_all_picks_up_changes() {
+        let dir = TempDir::new().unwrap();
+        let registry = DataRegistry::new(dir.path()).unwrap();
+        write(&dir, DataType::Items, r#"[{"id": 1, "name": "伤药", "price": 300}]"#);
+
+        let before = registry.get::<Vec<ItemData>>(DataType::Items).unwrap();
+        write(&dir, DataType::Items, r#"[{"id": 1, "name": "伤药", "price": 250}]"#);
+        assert_eq!(registry.get::<Vec<ItemData>>(DataType::Items).unwrap()[0].price, 300);
+
+        // 只重新加载访问过的数据
+        assert_eq!(registry.reload_all().unwrap(), 1);
+        assert_eq!(registry.get::<Vec<ItemData>>(DataType::Items).unwrap()[0].price, 250);
+        assert_eq!(before[0].price, 300);
+        assert_eq!(registry.load_count(DataType::Items), 2);
+
+        // 文件损坏时重新加载失败，保留旧数据
+        write(&dir, DataType::Items, "[{");
+        assert!(registry.reload_all().is_err());
+        assert_eq!(registry.get::<Vec<ItemData>>(DataType::Items).unwrap()[0].price, 250);
+    }
+
+    #[test]
+    fn test_builtin_used_until_file_appears() {
+        fn builtin_moves() -> Vec<ItemData> {
+            vec![ItemData { id: 1, name: "撞击".to_string(), price: 0 }]
+        }
+
+        let dir = TempDir::new().unwrap();
+        let registry = DataRegistry::new(dir.path()).unwrap();
+
+        // 没有数据文件时使用内置数据，并且只构建一次
+        let moves = registry.get_or_builtin(DataType::Moves, builtin_moves);
+        assert_eq!(moves[0].name, "撞击");
+        assert!(Arc::ptr_eq(&moves, &registry.get_or_builtin(DataType::Moves, builtin_moves)));
+        assert_eq!(registry.load_count(DataType::Moves), 1);
+
+        // 放入数据文件后重新加载，改为读取文件
+        write(&dir, DataType::Moves, r#"[{"id": 1, "name": "拍击", "price": 0}]"#);
+        assert_eq!(registry.reload_all().unwrap(), 1);
+        assert_eq!(registry.get_or_builtin(DataType::Moves, builtin_moves)[0].name, "拍击");
+
+        // 清空后重新从文件读取
+        registry.clear();
+        assert!(!registry.is_loaded(DataType::Moves));
+        assert_eq!(registry.get_or_builtin(DataType::Moves, builtin_moves)[0].name, "拍击");
+        assert_eq!(registry.load_count(DataType::Moves), 3);
+    }
+}
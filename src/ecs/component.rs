@@ -1,83 +1,223 @@
-// 组件管理器 (简化版本用于编译)
+// 组件管理器
+// 开发心理：系统每帧都按组件类型遍历实体，按实体存放组件会让遍历到处跳转
+// 设计原则：每种组件一个稀疏集合，组件紧密排列在数组中，实体到下标的映射保证增删查都是O(1)
 use std::collections::HashMap;
 use std::any::{Any, TypeId};
 use crate::core::error::GameError;
 use super::{EntityId, ComponentId, Component};
 
+// 单一组件类型的稀疏集合存储
+pub struct ComponentStorage<T: Component> {
+    pub(super) entities: Vec<EntityId>,         // 与components一一对应
+    pub(super) components: Vec<T>,
+    pub(super) index: HashMap<EntityId, usize>, // 实体 -> 在数组中的下标
+}
+
+impl<T: Component> ComponentStorage<T> {
+    pub fn new() -> Self {
+        Self {
+            entities: Vec::new(),
+            components: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    // 插入组件，实体已有该组件时替换并返回旧值
+    pub fn insert(&mut self, entity_id: EntityId, component: T) -> Option<T> {
+        if let Some(&i) = self.index.get(&entity_id) {
+            return Some(std::mem::replace(&mut self.components[i], component));
+        }
+        self.index.insert(entity_id, self.components.len());
+        self.entities.push(entity_id);
+        self.components.push(component);
+        None
+    }
+
+    // 移除组件，用最后一个元素填补空位
+    pub fn remove(&mut self, entity_id: EntityId) -> Option<T> {
+        let i = self.index.remove(&entity_id)?;
+        self.entities.swap_remove(i);
+        let component = self.components.swap_remove(i);
+        if let Some(&moved) = self.entities.get(i) {
+            self.index.insert(moved, i);
+        }
+        Some(component)
+    }
+
+    pub fn get(&self, entity_id: EntityId) -> Option<&T> {
+        self.index.get(&entity_id).map(|&i| &self.components[i])
+    }
+
+    pub fn get_mut(&mut self, entity_id: EntityId) -> Option<&mut T> {
+        let i = *self.index.get(&entity_id)?;
+        Some(&mut self.components[i])
+    }
+
+    pub fn contains(&self, entity_id: EntityId) -> bool {
+        self.index.contains_key(&entity_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    pub fn entities(&self) -> &[EntityId] {
+        &self.entities
+    }
+}
+
+impl<T: Component> Default for ComponentStorage<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 类型擦除后的存储，用于按TypeId统一管理
+trait ErasedStorage: Send + Sync {
+    fn remove_entity(&mut self, entity_id: EntityId) -> bool;
+    fn contains(&self, entity_id: EntityId) -> bool;
+    fn len(&self) -> usize;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Component> ErasedStorage for ComponentStorage<T> {
+    fn remove_entity(&mut self, entity_id: EntityId) -> bool {
+        self.remove(entity_id).is_some()
+    }
+
+    fn contains(&self, entity_id: EntityId) -> bool {
+        ComponentStorage::contains(self, entity_id)
+    }
+
+    fn len(&self) -> usize {
+        ComponentStorage::len(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
 pub struct ComponentManager {
-    components: HashMap<EntityId, HashMap<ComponentId, Box<dyn Component>>>,
+    storages: HashMap<ComponentId, Box<dyn ErasedStorage>>,
 }
 
 impl ComponentManager {
     pub fn new() -> Self {
         Self {
-            components: HashMap::new(),
+            storages: HashMap::new(),
         }
     }
-    
+
+    pub fn storage<T: Component>(&self) -> Option<&ComponentStorage<T>> {
+        self.storages
+            .get(&TypeId::of::<T>())?
+            .as_any()
+            .downcast_ref::<ComponentStorage<T>>()
+    }
+
+    pub fn storage_mut<T: Component>(&mut self) -> Option<&mut ComponentStorage<T>> {
+        self.storages
+            .get_mut(&TypeId::of::<T>())?
+            .as_any_mut()
+            .downcast_mut::<ComponentStorage<T>>()
+    }
+
+    // 插入组件，返回被替换的旧组件
+    pub fn insert<T: Component>(&mut self, entity_id: EntityId, component: T) -> Option<T> {
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(ComponentStorage::<T>::new()) as Box<dyn ErasedStorage>)
+            .as_any_mut()
+            .downcast_mut::<ComponentStorage<T>>()
+            .expect("组件存储类型与TypeId不一致")
+            .insert(entity_id, component)
+    }
+
+    pub fn remove<T: Component>(&mut self, entity_id: EntityId) -> Option<T> {
+        self.storage_mut::<T>()?.remove(entity_id)
+    }
+
     pub fn add_component<T: Component>(&mut self, entity_id: EntityId, component: T) -> Result<(), GameError> {
-        let type_id = TypeId::of::<T>();
-        self.components
-            .entry(entity_id)
-            .or_insert_with(HashMap::new)
-            .insert(type_id, Box::new(component));
+        self.insert(entity_id, component);
         Ok(())
     }
-    
+
     pub fn remove_component(&mut self, entity_id: EntityId, component_id: ComponentId) -> Result<(), GameError> {
-        if let Some(entity_components) = self.components.get_mut(&entity_id) {
-            entity_components.remove(&component_id);
+        if let Some(storage) = self.storages.get_mut(&component_id) {
+            storage.remove_entity(entity_id);
         }
         Ok(())
     }
-    
+
     pub fn get_component<T: Component>(&self, entity_id: EntityId) -> Option<&T> {
-        let type_id = TypeId::of::<T>();
-        self.components
-            .get(&entity_id)?
-            .get(&type_id)?
-            .as_any()
-            .downcast_ref::<T>()
+        self.storage::<T>()?.get(entity_id)
     }
-    
+
     pub fn get_component_mut<T: Component>(&mut self, entity_id: EntityId) -> Option<&mut T> {
-        let type_id = TypeId::of::<T>();
-        self.components
-            .get_mut(&entity_id)?
-            .get_mut(&type_id)?
-            .as_any_mut()
-            .downcast_mut::<T>()
+        self.storage_mut::<T>()?.get_mut(entity_id)
     }
-    
+
     pub fn has_component<T: Component>(&self, entity_id: EntityId) -> bool {
-        let type_id = TypeId::of::<T>();
-        self.components
-            .get(&entity_id)
-            .map(|components| components.contains_key(&type_id))
-            .unwrap_or(false)
+        self.has_component_by_id(entity_id, TypeId::of::<T>())
     }
-    
+
     pub fn has_component_by_id(&self, entity_id: EntityId, component_id: ComponentId) -> bool {
-        self.components
-            .get(&entity_id)
-            .map(|components| components.contains_key(&component_id))
+        self.storages
+            .get(&component_id)
+            .map(|storage| storage.contains(entity_id))
             .unwrap_or(false)
     }
-    
+
     pub fn get_entity_components(&self, entity_id: EntityId) -> Vec<ComponentId> {
-        self.components
-            .get(&entity_id)
-            .map(|components| components.keys().copied().collect())
-            .unwrap_or_default()
+        self.storages
+            .iter()
+            .filter(|(_, storage)| storage.contains(entity_id))
+            .map(|(&component_id, _)| component_id)
+            .collect()
     }
-    
+
     pub fn get_component_stats(&self) -> HashMap<ComponentId, usize> {
-        let mut stats = HashMap::new();
-        for components in self.components.values() {
-            for &component_id in components.keys() {
-                *stats.entry(component_id).or_insert(0) += 1;
-            }
+        self.storages
+            .iter()
+            .filter(|(_, storage)| storage.len() > 0)
+            .map(|(&component_id, storage)| (component_id, storage.len()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::impl_component;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Health(u32);
+
+    impl_component!(Health);
+
+    #[test]
+    fn test_swap_remove_keeps_index() {
+        let mut storage = ComponentStorage::new();
+        for entity_id in 1..=3 {
+            storage.insert(entity_id, Health(entity_id as u32 * 10));
         }
-        stats
+
+        // 删除第一个后，最后一个被移到下标0
+        assert_eq!(storage.remove(1), Some(Health(10)));
+        assert_eq!(storage.entities(), &[3, 2]);
+        assert_eq!(storage.get(3), Some(&Health(30)));
+        assert_eq!(storage.insert(2, Health(5)), Some(Health(20)));
+        assert_eq!(storage.len(), 2);
+        assert_eq!(storage.remove(1), None);
     }
-}
\ No newline at end of file
+}
@@ -13,6 +13,7 @@ pub mod component;
 pub mod system;
 pub mod world;
 pub mod query;
+pub mod schedule;
 
 // 实体ID类型
 pub type EntityId = u64;
@@ -33,6 +34,7 @@ pub struct ECSWorld {
     
     // 系统管理
     system_manager: system::SystemManager,
+    schedule: schedule::Schedule,
     
     // 查询缓存
     query_cache: HashMap<String, query::QueryResult>,
@@ -89,12 +91,7 @@ pub trait System: Send + Sync {
     fn enabled(&self) -> bool { true }
 }
 
-// 查询特征
-pub trait Query {
-    type Item;
-    fn matches(&self, entity: EntityId, world: &ECSWorld) -> bool;
-    fn get(&self, entity: EntityId, world: &ECSWorld) -> Option<Self::Item>;
-}
+pub use query::QueryData;
 
 impl ECSWorld {
     pub fn new() -> Self {
@@ -106,6 +103,7 @@ impl ECSWorld {
             entity_manager: entity::EntityManager::new(config.max_entities),
             component_manager: component::ComponentManager::new(),
             system_manager: system::SystemManager::new(),
+            schedule: schedule::Schedule::default(),
             query_cache: HashMap::new(),
             config,
             statistics: ECSStatistics::default(),
@@ -125,6 +123,11 @@ impl ECSWorld {
         Ok(entity_id)
     }
     
+    // 生成一个空实体，再用insert_component挂上组件
+    pub fn spawn(&mut self) -> Result<EntityId, GameError> {
+        self.create_entity()
+    }
+    
     // 销毁实体
     pub fn destroy_entity(&mut self, entity_id: EntityId) -> Result<(), GameError> {
         // 移除所有组件
@@ -149,44 +152,52 @@ impl ECSWorld {
         Ok(())
     }
     
-    // 添加组件
-    pub fn add_component<T: Component>(&mut self, entity_id: EntityId, component: T) -> Result<(), GameError> {
+    // 插入组件，实体已有同类组件时替换并返回旧组件
+    pub fn insert_component<T: Component>(&mut self, entity_id: EntityId, component: T) -> Result<Option<T>, GameError> {
         // 检查实体是否存在
         if !self.entity_manager.exists(entity_id) {
             return Err(GameError::ECS(format!("实体不存在: {}", entity_id)));
         }
         
-        self.component_manager.add_component(entity_id, component)?;
+        let replaced = self.component_manager.insert(entity_id, component);
         
         // 清除查询缓存
         if self.config.enable_query_caching {
             self.invalidate_query_cache();
         }
         
-        if self.config.statistics_enabled {
+        if self.config.statistics_enabled && replaced.is_none() {
             self.statistics.components_added += 1;
         }
         
         debug!("添加组件: {} -> {}", entity_id, std::any::type_name::<T>());
-        Ok(())
+        Ok(replaced)
     }
     
-    // 移除组件
-    pub fn remove_component<T: Component>(&mut self, entity_id: EntityId) -> Result<(), GameError> {
-        let component_type = TypeId::of::<T>();
-        self.component_manager.remove_component(entity_id, component_type)?;
+    // 添加组件
+    pub fn add_component<T: Component>(&mut self, entity_id: EntityId, component: T) -> Result<(), GameError> {
+        self.insert_component(entity_id, component).map(|_| ())
+    }
+    
+    // 移除组件，返回被移除的组件
+    pub fn remove_component<T: Component>(&mut self, entity_id: EntityId) -> Result<Option<T>, GameError> {
+        if !self.entity_manager.exists(entity_id) {
+            return Err(GameError::ECS(format!("实体不存在: {}", entity_id)));
+        }
+        
+        let removed = self.component_manager.remove::<T>(entity_id);
         
         // 清除查询缓存
         if self.config.enable_query_caching {
             self.invalidate_query_cache();
         }
         
-        if self.config.statistics_enabled {
+        if self.config.statistics_enabled && removed.is_some() {
             self.statistics.components_removed += 1;
         }
         
         debug!("移除组件: {} -> {}", entity_id, std::any::type_name::<T>());
-        Ok(())
+        Ok(removed)
     }
     
     // 获取组件
@@ -230,6 +241,26 @@ impl ECSWorld {
         self.system_manager.set_system_enabled(system_id, false)
     }
     
+    // 设置分阶段的系统调度
+    pub fn set_schedule(&mut self, schedule: schedule::Schedule) {
+        self.schedule = schedule;
+    }
+    
+    // 按阶段和顺序约束执行调度中的系统
+    pub fn run_schedule(&mut self, delta_time: f32) -> Result<(), GameError> {
+        // 运行期间把调度取出来，系统才能拿到整个世界的可变引用
+        let mut schedule = std::mem::take(&mut self.schedule);
+        let result = schedule.run(self, delta_time);
+        self.schedule = schedule;
+        let executed = result?;
+        
+        self.entity_manager.cleanup_destroyed_entities();
+        if self.config.statistics_enabled {
+            self.statistics.systems_executed += executed as u64;
+        }
+        Ok(())
+    }
+    
     // 更新世界
     pub fn update(&mut self, delta_time: f32) -> Result<(), GameError> {
         let frame_start = std::time::Instant::now();
@@ -252,30 +283,29 @@ impl ECSWorld {
         Ok(())
     }
     
-    // 查询实体
-    pub fn query<Q: Query>(&mut self, query: Q) -> Vec<(EntityId, Q::Item)> {
+    // 查询拥有一组组件的实体，例如 world.query::<(&Position, &mut Velocity)>()
+    // 同一组件在查询中被可变借用两次会panic
+    pub fn query<'w, Q: QueryData>(&'w mut self) -> Vec<(EntityId, Q::Item<'w>)> {
+        query::check_access::<Q>();
+        
         // 检查查询缓存
         let query_key = format!("{:?}", std::any::type_name::<Q>());
         
-        if self.config.enable_query_caching {
-            if let Some(cached_result) = self.query_cache.get(&query_key) {
-                if self.config.statistics_enabled {
-                    self.statistics.query_cache_hits += 1;
-                }
-                // 简化实现：实际应该返回缓存的结果
-            }
+        if self.config.enable_query_caching && self.query_cache.contains_key(&query_key) && self.config.statistics_enabled {
+            self.statistics.query_cache_hits += 1;
         }
         
-        // 执行查询
-        let mut results = Vec::new();
-        
-        for entity_id in self.entity_manager.get_all_entities() {
-            if query.matches(entity_id, self) {
-                if let Some(item) = query.get(entity_id, self) {
-                    results.push((entity_id, item));
-                }
-            }
-        }
+        // 执行查询，从组件最少的存储出发
+        let results: Vec<(EntityId, Q::Item<'w>)> = match Q::prepare(&mut self.component_manager) {
+            // SAFETY: 各组件存储是独立分配的，check_access已排除冲突的借用；
+            // 结果的生命周期绑定在&'w mut self上，存活期间世界不能被修改
+            Some(fetch) => unsafe {
+                Q::entities(fetch).iter()
+                    .filter_map(|&entity_id| Q::fetch(fetch, entity_id).map(|item| (entity_id, item)))
+                    .collect()
+            },
+            None => Vec::new(),
+        };
         
         // 缓存查询结果
         if self.config.enable_query_caching {
@@ -568,4 +598,40 @@ mod tests {
         let results = query.execute(&world);
         assert!(results.is_empty());
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_two_component_query() {
+        let mut world = ECSWorld::new();
+        
+        let moving = world.spawn().unwrap();
+        world.insert_component(moving, Position { x: 0.0, y: 0.0, z: 0.0 }).unwrap();
+        world.insert_component(moving, Velocity { dx: 1.0, dy: 2.0, dz: 0.0 }).unwrap();
+        
+        let still = world.spawn().unwrap();
+        world.insert_component(still, Position { x: 5.0, y: 5.0, z: 5.0 }).unwrap();
+        
+        let removed = world.spawn().unwrap();
+        world.insert_component(removed, Position { x: 0.0, y: 0.0, z: 0.0 }).unwrap();
+        world.insert_component(removed, Velocity { dx: 9.0, dy: 9.0, dz: 9.0 }).unwrap();
+        assert!(world.remove_component::<Velocity>(removed).unwrap().is_some());
+        
+        // 只有同时拥有两种组件的实体会被匹配
+        for (_, (position, velocity)) in world.query::<(&mut Position, &Velocity)>() {
+            position.x += velocity.dx;
+            position.y += velocity.dy;
+        }
+        
+        let matched: Vec<EntityId> = world.query::<(&Position, &Velocity)>().into_iter().map(|(id, _)| id).collect();
+        assert_eq!(matched, vec![moving]);
+        assert_eq!(*world.get_component::<Position>(moving).unwrap(), Position { x: 1.0, y: 2.0, z: 0.0 });
+        assert_eq!(world.get_component::<Position>(still).unwrap().x, 5.0);
+        assert_eq!(world.query::<&Position>().len(), 3);
+    }
+    
+    #[test]
+    #[should_panic]
+    fn test_query_rejects_aliased_mutable_access() {
+        let mut world = ECSWorld::new();
+        world.query::<(&mut Position, &Position)>();
+    }
+}
@@ -1,5 +1,9 @@
-// 查询系统 (简化版本用于编译)
-use super::{EntityId, ECSWorld};
+// 查询系统
+// 开发心理：系统只关心拥有某几种组件的实体，需要一次拿到这些组件的引用，其中部分可以修改
+// 设计原则：查询类型由&T、&mut T和它们的元组组成，从最小的组件集合出发逐个匹配，同一组件不允许同时可变借用
+use super::component::{ComponentManager, ComponentStorage};
+use super::{Component, ComponentId, EntityId, ECSWorld};
+use std::any::TypeId;
 
 #[derive(Debug, Clone)]
 pub struct QueryResult {
@@ -7,6 +11,123 @@ pub struct QueryResult {
     pub timestamp: std::time::Instant,
 }
 
+// 可查询的组件组合，例如 (&Position, &mut Velocity)
+pub trait QueryData {
+    type Item<'w>;
+    type Fetch: Copy;
+
+    // 记录访问的组件类型及是否可变
+    fn access(access: &mut Vec<(ComponentId, bool)>);
+
+    // 取得各组件存储的指针，任一组件没有存储时返回None
+    fn prepare(components: &mut ComponentManager) -> Option<Self::Fetch>;
+
+    // 候选实体：组件最少的那个存储中的实体
+    //
+    // Safety: fetch必须来自仍然有效的ComponentManager
+    unsafe fn entities<'w>(fetch: Self::Fetch) -> &'w [EntityId];
+
+    // Safety: 同上，且调用方需保证同一组件不会被重复可变借用
+    unsafe fn fetch<'w>(fetch: Self::Fetch, entity_id: EntityId) -> Option<Self::Item<'w>>;
+}
+
+impl<T: Component> QueryData for &T {
+    type Item<'w> = &'w T;
+    type Fetch = *const ComponentStorage<T>;
+
+    fn access(access: &mut Vec<(ComponentId, bool)>) {
+        access.push((TypeId::of::<T>(), false));
+    }
+
+    fn prepare(components: &mut ComponentManager) -> Option<Self::Fetch> {
+        components.storage::<T>().map(|storage| storage as *const _)
+    }
+
+    unsafe fn entities<'w>(fetch: Self::Fetch) -> &'w [EntityId] {
+        &(*fetch).entities
+    }
+
+    unsafe fn fetch<'w>(fetch: Self::Fetch, entity_id: EntityId) -> Option<Self::Item<'w>> {
+        (*fetch).get(entity_id)
+    }
+}
+
+impl<T: Component> QueryData for &mut T {
+    type Item<'w> = &'w mut T;
+    type Fetch = *mut ComponentStorage<T>;
+
+    fn access(access: &mut Vec<(ComponentId, bool)>) {
+        access.push((TypeId::of::<T>(), true));
+    }
+
+    fn prepare(components: &mut ComponentManager) -> Option<Self::Fetch> {
+        components.storage_mut::<T>().map(|storage| storage as *mut _)
+    }
+
+    unsafe fn entities<'w>(fetch: Self::Fetch) -> &'w [EntityId] {
+        &(*fetch).entities
+    }
+
+    unsafe fn fetch<'w>(fetch: Self::Fetch, entity_id: EntityId) -> Option<Self::Item<'w>> {
+        // 只通过下标取单个元素，不为整个存储创建可变引用，已返回的其他实体的引用保持有效
+        let i = *(*fetch).index.get(&entity_id)?;
+        Some(&mut *(*fetch).components.as_mut_ptr().add(i))
+    }
+}
+
+macro_rules! impl_query_data_tuple {
+    ($($name:ident),+) => {
+        #[allow(non_snake_case)]
+        impl<$($name: QueryData),+> QueryData for ($($name,)+) {
+            type Item<'w> = ($(<$name as QueryData>::Item<'w>,)+);
+            type Fetch = ($(<$name as QueryData>::Fetch,)+);
+
+            fn access(access: &mut Vec<(ComponentId, bool)>) {
+                $(<$name as QueryData>::access(access);)+
+            }
+
+            fn prepare(components: &mut ComponentManager) -> Option<Self::Fetch> {
+                Some(($(<$name as QueryData>::prepare(components)?,)+))
+            }
+
+            unsafe fn entities<'w>(fetch: Self::Fetch) -> &'w [EntityId] {
+                let ($($name,)+) = fetch;
+                let mut smallest: Option<&'w [EntityId]> = None;
+                $(
+                    let candidates = <$name as QueryData>::entities($name);
+                    if smallest.map_or(true, |current| candidates.len() < current.len()) {
+                        smallest = Some(candidates);
+                    }
+                )+
+                smallest.unwrap_or(&[])
+            }
+
+            unsafe fn fetch<'w>(fetch: Self::Fetch, entity_id: EntityId) -> Option<Self::Item<'w>> {
+                let ($($name,)+) = fetch;
+                Some(($(<$name as QueryData>::fetch($name, entity_id)?,)+))
+            }
+        }
+    };
+}
+
+impl_query_data_tuple!(A);
+impl_query_data_tuple!(A, B);
+impl_query_data_tuple!(A, B, C);
+impl_query_data_tuple!(A, B, C, D);
+
+// 检查同一组件是否在查询中既被可变借用又被其他项借用
+pub(super) fn check_access<Q: QueryData>() {
+    let mut access = Vec::new();
+    Q::access(&mut access);
+    for (i, &(component_id, mutable)) in access.iter().enumerate() {
+        let conflict = access[..i].iter()
+            .any(|&(other_id, other_mutable)| other_id == component_id && (mutable || other_mutable));
+        if conflict {
+            panic!("查询 {} 对同一组件存在冲突的借用", std::any::type_name::<Q>());
+        }
+    }
+}
+
 pub struct EntityQuery {
     pub filter: Option<Box<dyn Fn(EntityId) -> bool>>,
 }
@@ -17,10 +138,10 @@ impl EntityQuery {
             filter: None,
         }
     }
-    
+
     pub fn execute(&self, world: &ECSWorld) -> Vec<EntityId> {
         let all_entities = world.get_all_entities();
-        
+
         if let Some(ref filter) = self.filter {
             all_entities.into_iter().filter(|&id| filter(id)).collect()
         } else {
@@ -33,4 +154,4 @@ impl Default for EntityQuery {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,247 @@
+// 系统调度
+// 开发心理：系统之间有先后依赖（先处理输入再移动，先物理再渲染），按注册顺序执行很容易在加系统时被打乱
+// 设计原则：系统按阶段分组，阶段内用before/after约束排序，构建时检查未知标签和循环依赖，运行时只按已排好的顺序执行
+use std::collections::{HashMap, HashSet};
+use log::debug;
+use crate::core::error::GameError;
+use super::{ECSWorld, System};
+
+// 调度阶段，按声明顺序执行
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Stage {
+    Input,
+    Update,
+    Physics,
+    Render,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [Stage::Input, Stage::Update, Stage::Physics, Stage::Render];
+}
+
+struct SystemEntry {
+    stage: Stage,
+    system: Box<dyn System>,
+    before: Vec<String>,
+    after: Vec<String>,
+}
+
+// 调度构建器，before/after作用于最近一次添加的系统，系统以System::name()作为标签
+pub struct ScheduleBuilder {
+    entries: Vec<SystemEntry>,
+}
+
+impl ScheduleBuilder {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn add_system<S: System + 'static>(mut self, stage: Stage, system: S) -> Self {
+        self.entries.push(SystemEntry {
+            stage,
+            system: Box::new(system),
+            before: Vec::new(),
+            after: Vec::new(),
+        });
+        self
+    }
+
+    pub fn before(mut self, label: &str) -> Self {
+        if let Some(entry) = self.entries.last_mut() {
+            entry.before.push(label.to_string());
+        }
+        self
+    }
+
+    pub fn after(mut self, label: &str) -> Self {
+        if let Some(entry) = self.entries.last_mut() {
+            entry.after.push(label.to_string());
+        }
+        self
+    }
+
+    // 排序各阶段的系统，标签重复、引用未知标签、与阶段顺序矛盾或存在循环时报错
+    pub fn build(self) -> Result<Schedule, GameError> {
+        let mut labels = HashMap::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            if labels.insert(entry.system.name().to_string(), index).is_some() {
+                return Err(GameError::ECS(format!("系统标签重复: {}", entry.system.name())));
+            }
+        }
+
+        // 约束统一成 (先执行, 后执行)
+        let mut edges = HashSet::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            let constraints = entry.before.iter().map(|label| (label, true))
+                .chain(entry.after.iter().map(|label| (label, false)));
+            for (label, is_before) in constraints {
+                let other = *labels.get(label).ok_or_else(|| GameError::ECS(format!(
+                    "系统 {} 的顺序约束引用了未知系统: {}", entry.system.name(), label
+                )))?;
+                let (first, second) = if is_before { (index, other) } else { (other, index) };
+                let (first_stage, second_stage) = (self.entries[first].stage, self.entries[second].stage);
+                if first_stage > second_stage {
+                    return Err(GameError::ECS(format!(
+                        "系统 {} 必须先于 {}，但其阶段 {:?} 在 {:?} 之后",
+                        self.entries[first].system.name(), self.entries[second].system.name(), first_stage, second_stage
+                    )));
+                }
+                if first_stage == second_stage {
+                    edges.insert((first, second));
+                }
+            }
+        }
+
+        let order = topological_order(self.entries.len(), &edges).map_err(|cycle| {
+            let names: Vec<&str> = cycle.iter().map(|&i| self.entries[i].system.name()).collect();
+            GameError::ECS(format!("系统顺序存在循环: {}", names.join(", ")))
+        })?;
+
+        let mut slots: Vec<Option<SystemEntry>> = self.entries.into_iter().map(Some).collect();
+        let mut stages: Vec<(Stage, Vec<Box<dyn System>>)> = Stage::ALL.iter().map(|&stage| (stage, Vec::new())).collect();
+        for index in order {
+            let entry = slots[index].take().expect("拓扑排序不会重复输出同一系统");
+            let stage = stages.iter_mut().find(|(stage, _)| *stage == entry.stage).unwrap();
+            stage.1.push(entry.system);
+        }
+
+        Ok(Schedule { stages })
+    }
+}
+
+impl Default for ScheduleBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 按入度为0且注册最早的优先，得到稳定的拓扑序；有环时返回环上的系统
+fn topological_order(count: usize, edges: &HashSet<(usize, usize)>) -> Result<Vec<usize>, Vec<usize>> {
+    let mut in_degree = vec![0usize; count];
+    for &(_, second) in edges {
+        in_degree[second] += 1;
+    }
+
+    let mut order = Vec::with_capacity(count);
+    let mut done = vec![false; count];
+    while order.len() < count {
+        let next = (0..count).find(|&i| !done[i] && in_degree[i] == 0);
+        let Some(next) = next else {
+            return Err((0..count).filter(|&i| !done[i]).collect());
+        };
+        done[next] = true;
+        order.push(next);
+        for &(first, second) in edges {
+            if first == next {
+                in_degree[second] -= 1;
+            }
+        }
+    }
+    Ok(order)
+}
+
+// 已排好顺序的调度
+pub struct Schedule {
+    stages: Vec<(Stage, Vec<Box<dyn System>>)>,
+}
+
+impl Schedule {
+    // 依次执行各阶段的系统，返回执行的系统数
+    pub fn run(&mut self, world: &mut ECSWorld, delta_time: f32) -> Result<usize, GameError> {
+        let mut executed = 0;
+        for (stage, systems) in &mut self.stages {
+            for system in systems.iter_mut().filter(|system| system.enabled()) {
+                debug!("执行系统 {} ({:?})", system.name(), stage);
+                system.update(world, delta_time)?;
+                executed += 1;
+            }
+        }
+        Ok(executed)
+    }
+
+    // 某阶段内系统的执行顺序
+    pub fn system_names(&self, stage: Stage) -> Vec<&str> {
+        self.stages.iter()
+            .filter(|(s, _)| *s == stage)
+            .flat_map(|(_, systems)| systems.iter().map(|system| system.name()))
+            .collect()
+    }
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self {
+            stages: Stage::ALL.iter().map(|&stage| (stage, Vec::new())).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    struct RecordingSystem {
+        name: &'static str,
+        log: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    impl System for RecordingSystem {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn update(&mut self, _world: &mut ECSWorld, _delta_time: f32) -> Result<(), GameError> {
+            self.log.lock().unwrap().push(self.name);
+            Ok(())
+        }
+    }
+
+    fn recording(name: &'static str, log: &Arc<Mutex<Vec<&'static str>>>) -> RecordingSystem {
+        RecordingSystem { name, log: log.clone() }
+    }
+
+    #[test]
+    fn test_systems_run_in_declared_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let schedule = ScheduleBuilder::new()
+            .add_system(Stage::Render, recording("draw", &log))
+            .add_system(Stage::Update, recording("animate", &log)).after("move")
+            .add_system(Stage::Update, recording("move", &log)).after("ai")
+            .add_system(Stage::Update, recording("ai", &log))
+            .add_system(Stage::Input, recording("keyboard", &log)).before("move")
+            .build()
+            .unwrap();
+
+        assert_eq!(schedule.system_names(Stage::Update), vec!["ai", "move", "animate"]);
+
+        let mut world = ECSWorld::new();
+        world.set_schedule(schedule);
+        world.run_schedule(0.016).unwrap();
+        assert_eq!(*log.lock().unwrap(), vec!["keyboard", "ai", "move", "animate", "draw"]);
+    }
+
+    #[test]
+    fn test_invalid_ordering_rejected() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let cyclic = ScheduleBuilder::new()
+            .add_system(Stage::Update, recording("a", &log)).after("b")
+            .add_system(Stage::Update, recording("b", &log)).after("a")
+            .build();
+        assert!(cyclic.is_err());
+
+        let unknown = ScheduleBuilder::new()
+            .add_system(Stage::Update, recording("a", &log)).after("missing")
+            .build();
+        assert!(unknown.is_err());
+
+        // 渲染阶段的系统不能要求先于输入阶段
+        let against_stage = ScheduleBuilder::new()
+            .add_system(Stage::Render, recording("draw", &log)).before("keyboard")
+            .add_system(Stage::Input, recording("keyboard", &log))
+            .build();
+        assert!(against_stage.is_err());
+    }
+}
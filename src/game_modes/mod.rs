@@ -26,8 +26,16 @@ pub struct PokemonSpecies {
 pub type SpeciesId = u32;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use log::{info, debug, warn, error};
+use crate::core::services::Services;
+
+pub mod rules;
+pub mod randomizer;
+
+pub use rules::{FaintOutcome, GameRules, Nuzlocke, Standard};
+pub use randomizer::{Randomizer, RandomizerOptions, TrainerPokemon, WorldData};
 
 // 游戏模式枚举
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -93,6 +101,9 @@ pub enum Reward {
     Title(String),
 }
 
+// 世界、战斗和玩家系统共用的模式管理器句柄
+pub type SharedGameModes = Arc<Mutex<GameModeManager>>;
+
 // 游戏模式管理器
 pub struct GameModeManager {
     current_mode: GameMode,
@@ -101,6 +112,7 @@ pub struct GameModeManager {
     mode_start_time: Instant,
     session_stats: SessionStats,
     transition_stack: Vec<GameMode>,
+    rules: Box<dyn GameRules>,
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -124,12 +136,25 @@ impl GameModeManager {
             mode_start_time: Instant::now(),
             session_stats: SessionStats::default(),
             transition_stack: Vec::new(),
+            rules: Box::new(Standard),
         };
         
         manager.initialize_default_configs();
         manager
     }
     
+    // 新建一个独立的共享句柄，测试或多个存档并存时使用
+    pub fn shared() -> SharedGameModes {
+        Arc::new(Mutex::new(Self::new()))
+    }
+    
+    // 全局模式管理器，各系统默认都挂在它上面
+    pub fn global() -> SharedGameModes {
+        Services::shared::<SharedGameModes>()
+            .get_or_init(Self::shared)
+            .clone()
+    }
+    
     // 初始化默认配置
     fn initialize_default_configs(&mut self) {
         // 主线剧情模式
@@ -301,6 +326,46 @@ impl GameModeManager {
         Ok(())
     }
     
+    // 安装规则模式，之后的遭遇、捕获、濒死都经过它的钩子
+    pub fn set_mode<R: GameRules + 'static>(&mut self, rules: R) {
+        info!("规则模式切换: {} -> {}", self.rules.name(), rules.name());
+        self.rules = Box::new(rules);
+    }
+    
+    pub fn rules(&self) -> &dyn GameRules {
+        self.rules.as_ref()
+    }
+    
+    // 世界加载时交给规则改写世界数据
+    pub fn on_world_load(&mut self, world: &mut WorldData) -> Result<()> {
+        self.rules.on_world_load(world)
+    }
+    
+    // 遭遇野生宝可梦，返回规则处理后实际出现的种类
+    pub fn on_encounter(&mut self, route: &str, species_id: SpeciesId) -> SpeciesId {
+        self.rules.on_encounter(route, species_id)
+    }
+    
+    // 捕获前检查规则，通过后才能投球
+    pub fn check_capture(&self, route: &str, species_id: SpeciesId) -> Result<()> {
+        self.rules.check_capture(route, species_id)
+    }
+    
+    // 战斗开始前检查玩家的宝可梦能否出场
+    pub fn check_battle_pokemon(&self, pokemon_id: u64) -> Result<()> {
+        self.rules.check_battle_pokemon(pokemon_id)
+    }
+    
+    pub fn on_capture(&mut self, route: &str, pokemon_id: u64) {
+        self.rules.on_capture(route, pokemon_id);
+        self.update_stats(StatType::PokemonCaught, 1);
+    }
+    
+    // pokemon_id为None表示野生宝可梦被击倒
+    pub fn on_faint(&mut self, route: &str, pokemon_id: Option<u64>) -> FaintOutcome {
+        self.rules.on_faint(route, pokemon_id)
+    }
+    
     // 推入临时模式
     pub fn push_temporary_mode(&mut self, temp_mode: GameMode) -> Result<()> {
         self.transition_stack.push(self.current_mode);
@@ -546,4 +611,36 @@ mod tests {
         assert_eq!(manager.get_stats().achievements_unlocked.len(), 2);
         assert!(manager.get_stats().achievements_unlocked.contains(&"首次胜利".to_string()));
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_nuzlocke_blocks_second_catch_after_faint() {
+        let mut manager = GameModeManager::new();
+        assert_eq!(manager.rules().name(), "Standard");
+        manager.set_mode(Nuzlocke::new());
+        assert_eq!(manager.rules().name(), "Nuzlocke");
+        
+        // 1号道路首次遭遇的野生宝可梦被击倒，之后不能再在这里捕获
+        let first = manager.on_encounter("route_1", 16);
+        assert_eq!(manager.on_faint("route_1", None), FaintOutcome::Recoverable);
+        let second = manager.on_encounter("route_1", 19);
+        assert!(manager.check_capture("route_1", second).is_err());
+        assert!(manager.check_capture("route_1", first).is_err());
+        
+        // 2号道路只能捕获首次遭遇的宝可梦，且只能捕获一次
+        manager.on_encounter("route_2", 10);
+        manager.on_encounter("route_2", 13);
+        assert!(manager.check_capture("route_2", 13).is_err());
+        manager.check_capture("route_2", 10).unwrap();
+        manager.on_capture("route_2", 7);
+        assert!(manager.check_capture("route_2", 10).is_err());
+        assert_eq!(manager.get_stats().pokemon_caught, 1);
+        
+        // 队伍中的宝可梦濒死即永久失去
+        assert_eq!(manager.on_faint("route_2", Some(7)), FaintOutcome::Release);
+        
+        // 标准规则不限制
+        manager.set_mode(Standard);
+        manager.check_capture("route_1", 19).unwrap();
+        assert_eq!(manager.on_faint("route_1", Some(7)), FaintOutcome::Recoverable);
+    }
+}
@@ -0,0 +1,323 @@
+// 随机化模式
+// 开发心理：随机化玩法要让每局的野生宝可梦、训练家队伍、御三家都不同，但同一种子必须得到完全相同的世界，方便分享和复现
+// 设计原则：构造时按种子生成种类和招式的置换表，世界加载时统一套用；各部分使用独立的随机流，开关某一项不影响其它部分的结果；保证秘传招式和能学会秘传的宝可梦始终可以获得
+
+use super::rules::GameRules;
+use super::SpeciesId;
+use crate::core::{GameError, Result};
+use log::{debug, info};
+use std::collections::{BTreeMap, HashMap};
+
+// 默认参与随机化的种类范围(第一到第三世代)
+pub const RANDOMIZER_SPECIES_COUNT: SpeciesId = 386;
+pub const RANDOMIZER_MOVE_COUNT: u32 = 354;
+
+// 秘传招式：居合斩、飞翔、冲浪、怪力、闪光、攀瀑、碎岩、潜水
+pub const HM_MOVES: [u32; 8] = [15, 19, 57, 70, 148, 127, 249, 291];
+
+// 各部分随机流的盐值
+const SPECIES_STREAM: u64 = 0x5350_4543;
+const MOVE_STREAM: u64 = 0x4D4F_5645;
+const TYPE_STREAM: u64 = 0x5459_5045;
+const HM_STREAM: u64 = 0x484D_5F5F;
+
+// 随机化选项
+#[derive(Debug, Clone)]
+pub struct RandomizerOptions {
+    pub wild_encounters: bool,
+    pub trainer_parties: bool,
+    pub starters: bool,
+    pub moves: bool,                            // 训练家宝可梦的招式
+    pub type_effectiveness: bool,
+    pub species_pool: Vec<SpeciesId>,           // 参与置换的种类，池外的种类保持不变
+    pub move_pool: Vec<u32>,
+    pub protected_moves: Vec<u32>,              // 不参与置换的招式，默认为秘传招式
+    pub required_hm_learners: Vec<Vec<SpeciesId>>, // 每项为能学会某个剧情必需秘传的种类
+}
+
+impl Default for RandomizerOptions {
+    fn default() -> Self {
+        Self {
+            wild_encounters: true,
+            trainer_parties: true,
+            starters: true,
+            moves: true,
+            type_effectiveness: false,
+            species_pool: (1..=RANDOMIZER_SPECIES_COUNT).collect(),
+            move_pool: (1..=RANDOMIZER_MOVE_COUNT).collect(),
+            protected_moves: HM_MOVES.to_vec(),
+            required_hm_learners: Vec::new(),
+        }
+    }
+}
+
+// 训练家队伍中的宝可梦
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainerPokemon {
+    pub species_id: SpeciesId,
+    pub level: u8,
+    pub moves: Vec<u32>,
+}
+
+// 世界加载时交给随机化的数据，用有序容器保证遍历顺序与种子无关
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorldData {
+    pub wild_encounters: BTreeMap<String, Vec<SpeciesId>>,         // 道路 -> 可遭遇的种类
+    pub trainer_parties: BTreeMap<String, Vec<TrainerPokemon>>,    // 训练家 -> 队伍
+    pub starters: Vec<SpeciesId>,
+    pub type_chart: BTreeMap<(String, String), f32>,                // (攻击属性, 防御属性) -> 倍率
+}
+
+// 随机化：按种子固定地替换种类、招式和属性相克
+#[derive(Debug, Clone)]
+pub struct Randomizer {
+    seed: u64,
+    options: RandomizerOptions,
+    species_map: HashMap<SpeciesId, SpeciesId>,
+    move_map: HashMap<u32, u32>,
+}
+
+impl Randomizer {
+    pub fn new(seed: u64, options: RandomizerOptions) -> Self {
+        let species_map = permutation(&options.species_pool, seed ^ SPECIES_STREAM);
+        let movable: Vec<u32> = options.move_pool.iter()
+            .copied()
+            .filter(|id| !options.protected_moves.contains(id))
+            .collect();
+        let move_map = permutation(&movable, seed ^ MOVE_STREAM);
+
+        Self {
+            seed,
+            options,
+            species_map,
+            move_map,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn options(&self) -> &RandomizerOptions {
+        &self.options
+    }
+
+    // 置换是一一对应的，不同的种类不会被替换成同一种
+    pub fn remap_species(&self, species_id: SpeciesId) -> SpeciesId {
+        self.species_map.get(&species_id).copied().unwrap_or(species_id)
+    }
+
+    pub fn remap_move(&self, move_id: u32) -> u32 {
+        self.move_map.get(&move_id).copied().unwrap_or(move_id)
+    }
+
+    // 世界加载时调用，按选项替换各部分数据；秘传学习者无法放置时返回错误
+    pub fn randomize_world(&self, world: &mut WorldData) -> Result<()> {
+        if self.options.wild_encounters {
+            for species in world.wild_encounters.values_mut().flatten() {
+                *species = self.remap_species(*species);
+            }
+        }
+
+        if self.options.trainer_parties || self.options.moves {
+            for pokemon in world.trainer_parties.values_mut().flatten() {
+                if self.options.trainer_parties {
+                    pokemon.species_id = self.remap_species(pokemon.species_id);
+                }
+                if self.options.moves {
+                    for move_id in &mut pokemon.moves {
+                        *move_id = self.remap_move(*move_id);
+                    }
+                }
+            }
+        }
+
+        if self.options.starters {
+            for starter in &mut world.starters {
+                *starter = self.remap_species(*starter);
+            }
+        }
+
+        if self.options.type_effectiveness {
+            // 打乱倍率的分配，整体的克制数量保持不变
+            let mut multipliers: Vec<f32> = world.type_chart.values().copied().collect();
+            fastrand::Rng::with_seed(self.seed ^ TYPE_STREAM).shuffle(&mut multipliers);
+            for (value, multiplier) in world.type_chart.values_mut().zip(multipliers) {
+                *value = multiplier;
+            }
+        }
+
+        self.ensure_hm_learners(world)?;
+        info!("随机化世界完成 (种子: {})", self.seed);
+        Ok(())
+    }
+
+    // 每个剧情必需的秘传至少要有一种能学会它的宝可梦可以获得，否则游戏会卡关
+    // 只替换已有的遭遇位置，且不替换任何秘传学习者，后放置的学习者不会覆盖前面满足的要求
+    fn ensure_hm_learners(&self, world: &mut WorldData) -> Result<()> {
+        let requirements: Vec<&Vec<SpeciesId>> = self.options.required_hm_learners.iter()
+            .filter(|learners| !learners.is_empty())
+            .collect();
+        let is_learner = |species: &SpeciesId| requirements.iter().any(|learners| learners.contains(species));
+
+        let mut free_slots: Vec<(String, usize)> = world.wild_encounters.iter()
+            .flat_map(|(route, species)| {
+                species.iter()
+                    .enumerate()
+                    .filter(|(_, species)| !is_learner(species))
+                    .map(move |(slot, _)| (route.clone(), slot))
+            })
+            .collect();
+
+        let mut rng = fastrand::Rng::with_seed(self.seed ^ HM_STREAM);
+        for learners in &requirements {
+            let obtainable = world.starters.iter()
+                .chain(world.wild_encounters.values().flatten())
+                .any(|species| learners.contains(species));
+            if obtainable {
+                continue;
+            }
+
+            if free_slots.is_empty() {
+                return Err(GameError::GameModeError(format!(
+                    "随机化: 没有可以放置秘传学习者 {:?} 的野生遭遇位置", learners
+                )));
+            }
+            let (route, slot) = free_slots.swap_remove(rng.usize(..free_slots.len()));
+            let learner = learners[rng.usize(..learners.len())];
+            debug!("随机化: 在 {} 的第{}个遭遇位置放入秘传学习者 {}", route, slot, learner);
+            if let Some(species) = world.wild_encounters.get_mut(&route) {
+                species[slot] = learner;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl GameRules for Randomizer {
+    fn name(&self) -> &'static str {
+        "Randomizer"
+    }
+
+    // 遭遇表在世界加载时已经替换过，遭遇时不再重复替换
+    fn on_world_load(&mut self, world: &mut WorldData) -> Result<()> {
+        self.randomize_world(world)
+    }
+}
+
+// 按种子打乱pool，得到pool到自身的一一映射
+fn permutation<T: Copy + Eq + std::hash::Hash>(pool: &[T], seed: u64) -> HashMap<T, T> {
+    let mut shuffled = pool.to_vec();
+    fastrand::Rng::with_seed(seed).shuffle(&mut shuffled);
+    pool.iter().copied().zip(shuffled).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_world() -> WorldData {
+        let mut world = WorldData::default();
+        world.wild_encounters.insert("route_1".to_string(), vec![16, 19, 10]);
+        world.wild_encounters.insert("route_2".to_string(), vec![13, 21, 29, 32]);
+        world.trainer_parties.insert("brock".to_string(), vec![
+            TrainerPokemon { species_id: 74, level: 12, moves: vec![33, 111] },
+            TrainerPokemon { species_id: 95, level: 14, moves: vec![33, 70, 103] },
+        ]);
+        world.starters = vec![1, 4, 7];
+        for (attack, defend, multiplier) in [("Fire", "Grass", 2.0), ("Water", "Fire", 2.0), ("Grass", "Water", 2.0), ("Fire", "Water", 0.5)] {
+            world.type_chart.insert((attack.to_string(), defend.to_string()), multiplier);
+        }
+        world
+    }
+
+    fn randomized(seed: u64, options: RandomizerOptions) -> WorldData {
+        let mut world = sample_world();
+        Randomizer::new(seed, options).randomize_world(&mut world).unwrap();
+        world
+    }
+
+    #[test]
+    fn test_same_seed_same_world() {
+        let options = RandomizerOptions { type_effectiveness: true, ..RandomizerOptions::default() };
+        let first = randomized(2024, options.clone());
+        assert_eq!(first, randomized(2024, options.clone()));
+        assert_ne!(first, sample_world());
+
+        // 不同种子得到不同的世界
+        let other = randomized(2025, options);
+        assert_ne!(first.wild_encounters, other.wild_encounters);
+        assert_ne!(first.starters, other.starters);
+
+        // 御三家仍是三种不同的宝可梦，等级不变，秘传招式保留
+        let mut starters = first.starters.clone();
+        starters.sort_unstable();
+        starters.dedup();
+        assert_eq!(starters.len(), 3);
+        let onix = &first.trainer_parties["brock"][1];
+        assert_eq!(onix.level, 14);
+        assert_eq!(onix.moves[1], 70);
+    }
+
+    #[test]
+    fn test_disabled_parts_untouched() {
+        let options = RandomizerOptions {
+            wild_encounters: false,
+            moves: false,
+            ..RandomizerOptions::default()
+        };
+        let world = randomized(7, options.clone());
+        let original = sample_world();
+        assert_eq!(world.wild_encounters, original.wild_encounters);
+        assert_eq!(world.type_chart, original.type_chart);
+        assert_eq!(world.trainer_parties["brock"][0].moves, vec![33, 111]);
+
+        // 开关其它部分不影响御三家的结果
+        assert_eq!(world.starters, randomized(7, RandomizerOptions::default()).starters);
+
+        let mut rules = Randomizer::new(7, options);
+        assert_eq!(rules.on_encounter("route_1", 16), 16);
+    }
+
+    #[test]
+    fn test_hm_learner_always_obtainable() {
+        // 只有种类400能学会必需的秘传，而它不在任何原始数据中
+        for seed in 0..20 {
+            let options = RandomizerOptions {
+                species_pool: (1..=151).collect(),
+                required_hm_learners: vec![vec![400]],
+                ..RandomizerOptions::default()
+            };
+            let world = randomized(seed, options);
+            assert!(world.wild_encounters.values().flatten().any(|&species| species == 400));
+        }
+    }
+
+    #[test]
+    fn test_hm_learners_use_separate_slots() {
+        let options = RandomizerOptions {
+            wild_encounters: false,
+            starters: false,
+            required_hm_learners: vec![vec![400], vec![401], vec![402]],
+            ..RandomizerOptions::default()
+        };
+        let mut world = WorldData::default();
+        world.wild_encounters.insert("route_1".to_string(), vec![16, 19]);
+        world.wild_encounters.insert("route_2".to_string(), vec![13]);
+
+        // 三个要求各占一个位置，后放置的不会覆盖前面的学习者
+        for seed in 0..20 {
+            let mut world = world.clone();
+            Randomizer::new(seed, options.clone()).randomize_world(&mut world).unwrap();
+            let mut placed: Vec<SpeciesId> = world.wild_encounters.values().flatten().copied().collect();
+            placed.sort_unstable();
+            assert_eq!(placed, vec![400, 401, 402]);
+        }
+
+        // 位置不够或没有野生遭遇时报错，而不是悄悄跳过
+        world.wild_encounters.remove("route_2");
+        assert!(Randomizer::new(1, options.clone()).randomize_world(&mut world).is_err());
+        let mut empty = WorldData::default();
+        assert!(Randomizer::new(1, options).randomize_world(&mut empty).is_err());
+    }
+}
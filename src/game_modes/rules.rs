@@ -0,0 +1,145 @@
+// 游戏规则模式
+// 开发心理：挑战玩法(如Nuzlocke)只改变少数几个时机的规则：遇到野生宝可梦、捕获、濒死，不值得为每种玩法复制一套流程
+// 设计原则：规则作为可替换的钩子对象安装到GameModeManager，默认实现不改变任何行为，各模式只覆盖关心的钩子
+
+use super::{SpeciesId, WorldData};
+use crate::core::{GameError, Result};
+use log::debug;
+use std::collections::{HashMap, HashSet};
+
+// 濒死后的处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaintOutcome {
+    Recoverable,    // 可以在宝可梦中心恢复
+    Release,        // 永久失去，需要从队伍中放生
+}
+
+// 规则钩子，route为事件发生的地图ID
+pub trait GameRules: Send {
+    fn name(&self) -> &'static str;
+
+    // 世界加载时调用，可以改写遭遇表等世界数据；返回错误时调用方应保留原世界
+    fn on_world_load(&mut self, _world: &mut WorldData) -> Result<()> {
+        Ok(())
+    }
+
+    // 遭遇野生宝可梦，返回实际出现的种类
+    fn on_encounter(&mut self, _route: &str, species_id: SpeciesId) -> SpeciesId {
+        species_id
+    }
+
+    // 尝试捕获前检查，返回错误时不允许投球
+    fn check_capture(&self, _route: &str, _species_id: SpeciesId) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_capture(&mut self, _route: &str, _pokemon_id: u64) {}
+
+    // 战斗开始前检查玩家的宝可梦，返回错误时不能出场
+    fn check_battle_pokemon(&self, _pokemon_id: u64) -> Result<()> {
+        Ok(())
+    }
+
+    // 宝可梦濒死，pokemon_id为None表示野生宝可梦被击倒
+    fn on_faint(&mut self, _route: &str, _pokemon_id: Option<u64>) -> FaintOutcome {
+        FaintOutcome::Recoverable
+    }
+}
+
+// 标准规则
+#[derive(Debug, Default)]
+pub struct Standard;
+
+impl GameRules for Standard {
+    fn name(&self) -> &'static str {
+        "Standard"
+    }
+}
+
+// 每条道路的遭遇记录
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RouteStatus {
+    Encountered(SpeciesId), // 已有首次遭遇，尚未处理
+    Caught(u64),
+    Closed,                 // 首次遭遇已逃走或被击倒
+}
+
+// Nuzlocke：每条道路只能捕获首次遭遇的宝可梦，濒死即永久失去
+#[derive(Debug, Default)]
+pub struct Nuzlocke {
+    routes: HashMap<String, RouteStatus>,
+    dead: HashSet<u64>,
+}
+
+impl Nuzlocke {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 该道路是否还能捕获
+    pub fn route_available(&self, route: &str) -> bool {
+        matches!(self.routes.get(route), None | Some(RouteStatus::Encountered(_)))
+    }
+
+    // 首次遭遇逃走时调用，该道路不再能捕获
+    pub fn close_route(&mut self, route: &str) {
+        if self.route_available(route) {
+            self.routes.insert(route.to_string(), RouteStatus::Closed);
+        }
+    }
+
+    pub fn is_dead(&self, pokemon_id: u64) -> bool {
+        self.dead.contains(&pokemon_id)
+    }
+}
+
+impl GameRules for Nuzlocke {
+    fn name(&self) -> &'static str {
+        "Nuzlocke"
+    }
+
+    fn on_encounter(&mut self, route: &str, species_id: SpeciesId) -> SpeciesId {
+        self.routes.entry(route.to_string()).or_insert(RouteStatus::Encountered(species_id));
+        species_id
+    }
+
+    fn check_capture(&self, route: &str, species_id: SpeciesId) -> Result<()> {
+        match self.routes.get(route) {
+            Some(RouteStatus::Encountered(first)) if *first == species_id => Ok(()),
+            Some(RouteStatus::Encountered(_)) | None => Err(GameError::GameModeError(
+                format!("Nuzlocke: {} 只能捕获首次遭遇的宝可梦", route)
+            )),
+            Some(RouteStatus::Caught(_)) | Some(RouteStatus::Closed) => Err(GameError::GameModeError(
+                format!("Nuzlocke: {} 的捕获机会已用完", route)
+            )),
+        }
+    }
+
+    fn on_capture(&mut self, route: &str, pokemon_id: u64) {
+        self.routes.insert(route.to_string(), RouteStatus::Caught(pokemon_id));
+    }
+
+    fn check_battle_pokemon(&self, pokemon_id: u64) -> Result<()> {
+        if self.is_dead(pokemon_id) {
+            return Err(GameError::GameModeError(
+                format!("Nuzlocke: 宝可梦 {} 已永久失去，不能出场", pokemon_id)
+            ));
+        }
+        Ok(())
+    }
+
+    fn on_faint(&mut self, route: &str, pokemon_id: Option<u64>) -> FaintOutcome {
+        match pokemon_id {
+            Some(id) => {
+                self.dead.insert(id);
+                debug!("Nuzlocke: 宝可梦 {} 濒死，永久失去", id);
+                FaintOutcome::Release
+            },
+            None => {
+                // 击倒了首次遭遇的野生宝可梦，失去这条道路的机会
+                self.close_route(route);
+                FaintOutcome::Recoverable
+            },
+        }
+    }
+}
@@ -471,25 +471,37 @@ impl Camera {
     }
     
     // 获取视锥体平面（用于裁剪）
+    // 从视图投影矩阵的行提取六个裁剪平面（法线朝向视锥内部）
+    // glam的投影矩阵深度范围为0..1，近平面直接取第三行
     pub fn get_frustum_planes(&self) -> [Plane; 6] {
         let vp = self.view_projection_matrix;
+        let (row0, row1, row2, row3) = (vp.row(0), vp.row(1), vp.row(2), vp.row(3));
         
         [
             // Left
-            Plane::from_coefficients(vp.w_axis + vp.x_axis),
+            Plane::from_coefficients(row3 + row0),
             // Right
-            Plane::from_coefficients(vp.w_axis - vp.x_axis),
+            Plane::from_coefficients(row3 - row0),
             // Bottom
-            Plane::from_coefficients(vp.w_axis + vp.y_axis),
+            Plane::from_coefficients(row3 + row1),
             // Top
-            Plane::from_coefficients(vp.w_axis - vp.y_axis),
+            Plane::from_coefficients(row3 - row1),
             // Near
-            Plane::from_coefficients(vp.w_axis + vp.z_axis),
+            Plane::from_coefficients(row2),
             // Far
-            Plane::from_coefficients(vp.w_axis - vp.z_axis),
+            Plane::from_coefficients(row3 - row2),
         ]
     }
     
+    // 包围盒是否与视锥相交：只要包围盒完全位于任一平面外侧即可剔除
+    pub fn is_aabb_visible(&self, min: glam::Vec3, max: glam::Vec3) -> bool {
+        self.get_frustum_planes().iter().all(|plane| {
+            // 取包围盒上沿法线方向最远的顶点
+            let farthest = glam::Vec3::select(plane.normal.cmpge(glam::Vec3::ZERO), max, min);
+            plane.distance_to_point(farthest) >= 0.0
+        })
+    }
+    
     // 更新矩阵
     fn update_matrices(&mut self) {
         // 计算有效位置（包括震动偏移）
@@ -681,6 +693,8 @@ pub struct CameraController {
     follow_target: Option<glam::Vec3>,
     follow_offset: glam::Vec3,
     follow_smooth: f32,
+    follow_deadzone: glam::Vec2,      // 死区半宽高，目标在死区内移动时相机不动
+    follow_bounds: Option<glam::Vec4>, // 地图边界 (min_x, min_y, max_x, max_y)
 }
 
 impl CameraController {
@@ -697,6 +711,8 @@ impl CameraController {
             follow_target: None,
             follow_offset: glam::Vec3::new(0.0, 2.0, -5.0),
             follow_smooth: 5.0,
+            follow_deadzone: glam::Vec2::ZERO,
+            follow_bounds: None,
         }
     }
     
@@ -786,6 +802,67 @@ impl CameraController {
         }
     }
     
+    // 平滑系数，越大跟得越紧，0表示直接对齐目标
+    pub fn set_follow_smoothing(&mut self, smoothing: f32) {
+        self.follow_smooth = smoothing.max(0.0);
+    }
+    
+    pub fn set_deadzone(&mut self, half_size: glam::Vec2) {
+        self.follow_deadzone = half_size.abs();
+    }
+    
+    // 限制相机视野不超出地图边界 (min_x, min_y, max_x, max_y)
+    pub fn set_bounds(&mut self, bounds: glam::Vec4) {
+        self.follow_bounds = Some(bounds);
+    }
+    
+    pub fn clear_bounds(&mut self) {
+        self.follow_bounds = None;
+    }
+    
+    // 2D跟随：目标离开死区后按指数平滑追赶，结果限制在地图边界内
+    pub fn follow(&mut self, target_pos: glam::Vec2, delta_time: f32) {
+        let current = self.camera.position.truncate();
+        
+        // 只追赶超出死区的部分，目标在死区内小幅移动时相机保持静止
+        let offset = target_pos - current;
+        let excess = glam::Vec2::new(
+            (offset.x.abs() - self.follow_deadzone.x).max(0.0) * offset.x.signum(),
+            (offset.y.abs() - self.follow_deadzone.y).max(0.0) * offset.y.signum(),
+        );
+        let desired = current + excess;
+        
+        // 指数衰减插值与帧率无关
+        let t = if self.follow_smooth > 0.0 {
+            1.0 - (-self.follow_smooth * delta_time).exp()
+        } else {
+            1.0
+        };
+        let mut next = current.lerp(desired, t);
+        if next.distance_squared(desired) < FOLLOW_SNAP_DISTANCE * FOLLOW_SNAP_DISTANCE {
+            next = desired;
+        }
+        
+        if let Some(bounds) = self.follow_bounds {
+            next = clamp_view_to_bounds(next, self.view_half_extents(), bounds);
+        }
+        
+        self.camera.set_position(next.extend(self.camera.position.z));
+    }
+    
+    // 正交投影下视野的半宽高，透视投影按点处理
+    fn view_half_extents(&self) -> glam::Vec2 {
+        match self.camera.projection {
+            ProjectionType::OrthographicCentered { width, height, .. } => {
+                glam::Vec2::new(width, height) * 0.5
+            },
+            ProjectionType::Orthographic { left, right, bottom, top, .. } => {
+                glam::Vec2::new(right - left, top - bottom) * 0.5
+            },
+            ProjectionType::Perspective { .. } => glam::Vec2::ZERO,
+        }
+    }
+    
     // 更新控制器
     pub fn update(&mut self, delta_time: f32) {
         // 跟随相机逻辑
@@ -813,6 +890,25 @@ impl CameraController {
     }
 }
 
+// 距离目标足够近时直接对齐，避免无限逼近造成的亚像素抖动
+const FOLLOW_SNAP_DISTANCE: f32 = 0.01;
+
+// 将相机中心限制在边界内；地图比视野小时居中显示
+fn clamp_view_to_bounds(center: glam::Vec2, half_extents: glam::Vec2, bounds: glam::Vec4) -> glam::Vec2 {
+    let clamp_axis = |value: f32, min: f32, max: f32, half: f32| {
+        if max - min <= half * 2.0 {
+            (min + max) * 0.5
+        } else {
+            value.clamp(min + half, max - half)
+        }
+    };
+    
+    glam::Vec2::new(
+        clamp_axis(center.x, bounds.x, bounds.z, half_extents.x),
+        clamp_axis(center.y, bounds.y, bounds.w, half_extents.y),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -847,4 +943,64 @@ mod tests {
         assert!(bbox.contains(&glam::Vec3::ZERO));
         assert!(!bbox.contains(&glam::Vec3::new(2.0, 0.0, 0.0)));
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_camera_follow_converges() {
+        let mut controller = CameraController::new(Camera::ortho_2d(320.0, 240.0));
+        controller.set_follow_smoothing(8.0);
+        controller.set_deadzone(glam::Vec2::new(16.0, 8.0));
+        
+        let target = glam::Vec2::new(200.0, -120.0);
+        for _ in 0..300 {
+            controller.follow(target, 1.0 / 60.0);
+        }
+        
+        // 停在死区边缘
+        let position = controller.camera.position.truncate();
+        assert!((position - glam::Vec2::new(184.0, -112.0)).length() < 0.001);
+        
+        // 死区内移动不会带动相机
+        controller.follow(target + glam::Vec2::new(-10.0, 4.0), 1.0 / 60.0);
+        assert_eq!(controller.camera.position.truncate(), position);
+    }
+    
+    #[test]
+    fn test_camera_follow_stays_in_bounds() {
+        let mut controller = CameraController::new(Camera::ortho_2d(320.0, 240.0));
+        controller.set_follow_smoothing(0.0);
+        controller.set_bounds(glam::Vec4::new(0.0, 0.0, 1000.0, 600.0));
+        
+        let targets = [
+            glam::Vec2::new(-500.0, -500.0),
+            glam::Vec2::new(2000.0, 300.0),
+            glam::Vec2::new(500.0, 5000.0),
+            glam::Vec2::new(500.0, 300.0),
+        ];
+        for target in targets {
+            controller.follow(target, 1.0 / 60.0);
+            let position = controller.camera.position.truncate();
+            assert!(position.x - 160.0 >= 0.0 && position.x + 160.0 <= 1000.0);
+            assert!(position.y - 120.0 >= 0.0 && position.y + 120.0 <= 600.0);
+        }
+        assert_eq!(controller.camera.position.truncate(), glam::Vec2::new(500.0, 300.0));
+        
+        // 地图比视野小时居中
+        controller.set_bounds(glam::Vec4::new(0.0, 0.0, 200.0, 100.0));
+        controller.follow(glam::Vec2::new(900.0, 900.0), 1.0 / 60.0);
+        assert_eq!(controller.camera.position.truncate(), glam::Vec2::new(100.0, 50.0));
+    }
+    
+    #[test]
+    fn test_frustum_aabb_visibility() {
+        // 默认相机位于 (0, 0, 5)，朝向 -Z
+        let camera = Camera::perspective(60.0_f32.to_radians(), 16.0 / 9.0, 0.1, 100.0);
+        
+        assert!(camera.is_aabb_visible(glam::Vec3::splat(-0.5), glam::Vec3::splat(0.5)));
+        // 相机后方
+        assert!(!camera.is_aabb_visible(glam::Vec3::new(-0.5, -0.5, 9.5), glam::Vec3::new(0.5, 0.5, 10.5)));
+        // 远平面之外
+        assert!(!camera.is_aabb_visible(glam::Vec3::new(-0.5, -0.5, -200.0), glam::Vec3::new(0.5, 0.5, -199.0)));
+        // 跨越视锥边界的物体保留
+        assert!(camera.is_aabb_visible(glam::Vec3::new(-100.0, -0.5, -1.0), glam::Vec3::new(0.0, 0.5, 0.0)));
+    }
+}
@@ -9,24 +9,26 @@ pub mod texture;
 pub mod camera;
 pub mod sprite;
 pub mod ui;
+pub mod software;
 
 // 重新导出已实现的类型
 pub use renderer2d::{Renderer2D, RenderLayer, RenderCommand, sprite_rendering_system};
 pub use shader::{ShaderManager, ShaderProgram, UniformValue, ShaderId, ShaderType, builtin_shaders};
-pub use texture::{TextureManager, TextureDesc, TextureFormat, TextureFilter, TextureType, TextureId, TextureData};
+pub use texture::{TextureManager, TextureDesc, TextureFormat, TextureFilter, TextureType, TextureId, TextureData, TextureAtlas, AtlasRegion};
 pub use camera::{Camera, CameraController, ProjectionType, CameraType, Ray, Plane};
-pub use sprite::{Sprite, SpriteRenderer, SpriteBatch, SpriteAnimation};
+pub use sprite::{Sprite, SpriteRenderer, SpriteBatch, SpriteAnimation, AnimationFinished};
 pub use ui::{UIRenderer, UIElement, UIManager};
+pub use software::SoftwareRenderer;
 
 use crate::core::{GameError, Result};
 use crate::core::resource_manager::{ResourceManager, ResourceHandle};
+use crate::core::services::{ServiceGuard, Services};
 use std::collections::HashMap;
+use std::sync::MutexGuard;
 use serde::{Deserialize, Serialize};
 use log::{info, debug, warn, error};
 
 // 临时类型定义，避免编译错误  
-pub struct SpriteRenderer;
-pub struct UIRenderer;
 pub struct Shader;
 
 pub trait Renderer {
@@ -36,18 +38,153 @@ pub trait Renderer {
     fn viewport(&mut self, x: i32, y: i32, width: u32, height: u32) -> Result<()> { Ok(()) }
     fn set_vsync(&mut self, vsync: bool) -> Result<()> { Ok(()) }
     fn read_pixels(&self) -> Result<Vec<u8>> { Ok(vec![]) }
+    // 绘制一批共享纹理的精灵，对应一次绘制调用
+    fn draw_sprite_batch(&mut self, sprites: &[Sprite], view_projection: glam::Mat4) -> Result<()> { Ok(()) }
 }
 
+// 精灵渲染器 - 收集一帧内的精灵，按层级和纹理合批提交
+#[derive(Default)]
+pub struct SpriteRenderer {
+    sprites: Vec<Sprite>,
+}
 
 impl SpriteRenderer {
-    pub fn new() -> Result<Self> { Ok(Self) }
-    pub fn add_sprite(&mut self, _sprite: Sprite) -> Result<()> { Ok(()) }
+    pub fn new() -> Result<Self> { Ok(Self::default()) }
+    
+    pub fn add_sprite(&mut self, sprite: Sprite) -> Result<()> {
+        self.sprites.push(sprite);
+        Ok(())
+    }
+    
+    pub fn pending_sprites(&self) -> usize {
+        self.sprites.len()
+    }
+    
+    // 提交本帧精灵，同层级同纹理的相邻精灵合并为一次绘制，返回绘制调用数
+    pub fn flush(&mut self, renderer: &mut dyn Renderer, view_projection: glam::Mat4) -> Result<u32> {
+        // 稳定排序，同一批次内保持提交顺序
+        self.sprites.sort_by_key(|sprite| (sprite.layer, sprite.texture.get_id()));
+        
+        let mut draw_calls = 0;
+        for batch in self.sprites.chunk_by(|a, b| a.layer == b.layer && a.texture.get_id() == b.texture.get_id()) {
+            renderer.draw_sprite_batch(batch, view_projection)?;
+            draw_calls += 1;
+        }
+        
+        self.sprites.clear();
+        Ok(draw_calls)
+    }
+}
+
+// UI渲染器 - 屏幕空间绘制，左上角为原点，y轴向下
+pub struct UIRenderer {
+    batch: SpriteRenderer,
+    screen_size: glam::Vec2,
 }
 
 impl UIRenderer {
-    pub fn new() -> Result<Self> { Ok(Self) }
-    pub fn render(&mut self, _renderer: &mut dyn Renderer) -> Result<()> { Ok(()) }
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            batch: SpriteRenderer::new()?,
+            screen_size: glam::Vec2::ONE,
+        })
+    }
+    
+    pub fn set_screen_size(&mut self, width: u32, height: u32) {
+        self.screen_size = glam::Vec2::new(width.max(1) as f32, height.max(1) as f32);
+    }
+    
+    pub fn pending_sprites(&self) -> usize {
+        self.batch.pending_sprites()
+    }
+    
+    // 提交本帧UI，返回绘制调用数
+    pub fn render(&mut self, renderer: &mut dyn Renderer) -> Result<u32> {
+        let projection = glam::Mat4::orthographic_rh(0.0, self.screen_size.x, self.screen_size.y, 0.0, -1.0, 1.0);
+        self.batch.flush(renderer, projection)
+    }
+    
     pub fn add_text(&mut self, _text: &str, _pos: glam::Vec2, _size: f32, _color: glam::Vec4, _layer: renderer2d::RenderLayer) -> Result<()> { Ok(()) }
+    
+    // 九宫格绘制，rect为 (x, y, width, height)，borders以纹理像素为单位
+    // 四角保持原始大小，边缘和中心拉伸填充，返回绘制的分段数
+    pub fn render_nine_slice(
+        &mut self,
+        texture: &ResourceHandle<texture::Texture>,
+        rect: glam::Vec4,
+        borders: ui::EdgeInsets,
+    ) -> Result<usize> {
+        let texture_size = texture.get()
+            .map(|texture| {
+                let texture = texture.read().unwrap();
+                glam::Vec2::new(texture.desc.width as f32, texture.desc.height as f32)
+            })
+            .ok_or_else(|| GameError::RenderError("九宫格纹理未加载".to_string()))?;
+        
+        let segments = nine_slice_segments(rect, borders, texture_size);
+        for (dest, uv_rect) in &segments {
+            let size = glam::Vec2::new(dest.z, dest.w);
+            self.batch.add_sprite(Sprite {
+                texture: texture.clone(),
+                position: glam::Vec2::new(dest.x, dest.y) + size * 0.5,
+                size,
+                rotation: 0.0,
+                color: glam::Vec4::ONE,
+                layer: renderer2d::RenderLayer::UI,
+                uv_rect: *uv_rect,
+                flip_x: false,
+                flip_y: false,
+            })?;
+        }
+        
+        Ok(segments.len())
+    }
+}
+
+// 计算九宫格分段，返回 (目标矩形 (x, y, w, h), UV矩形 (u1, v1, u2, v2))
+// 目标区域小于两侧边框之和时按比例缩小边框，尺寸为0的分段被跳过
+pub fn nine_slice_segments(
+    rect: glam::Vec4,
+    borders: ui::EdgeInsets,
+    texture_size: glam::Vec2,
+) -> Vec<(glam::Vec4, glam::Vec4)> {
+    let scale_x = (rect.z / (borders.left + borders.right)).min(1.0);
+    let scale_y = (rect.w / (borders.top + borders.bottom)).min(1.0);
+    
+    let columns = [
+        (rect.x, borders.left * scale_x, 0.0, borders.left / texture_size.x),
+        (rect.x + borders.left * scale_x, rect.z - (borders.left + borders.right) * scale_x,
+         borders.left / texture_size.x, 1.0 - borders.right / texture_size.x),
+        (rect.x + rect.z - borders.right * scale_x, borders.right * scale_x,
+         1.0 - borders.right / texture_size.x, 1.0),
+    ];
+    let rows = [
+        (rect.y, borders.top * scale_y, 0.0, borders.top / texture_size.y),
+        (rect.y + borders.top * scale_y, rect.w - (borders.top + borders.bottom) * scale_y,
+         borders.top / texture_size.y, 1.0 - borders.bottom / texture_size.y),
+        (rect.y + rect.w - borders.bottom * scale_y, borders.bottom * scale_y,
+         1.0 - borders.bottom / texture_size.y, 1.0),
+    ];
+    
+    let mut segments = Vec::with_capacity(9);
+    for &(y, height, v1, v2) in &rows {
+        for &(x, width, u1, u2) in &columns {
+            if width > 0.0 && height > 0.0 {
+                segments.push((
+                    glam::Vec4::new(x, y, width, height),
+                    glam::Vec4::new(u1, v1, u2, v2),
+                ));
+            }
+        }
+    }
+    segments
+}
+
+// 按翻转标志镜像UV矩形，翻转即交换对应轴上的起止坐标
+pub fn flip_uv_rect(uv_rect: glam::Vec4, flip_x: bool, flip_y: bool) -> glam::Vec4 {
+    let (u1, u2) = if flip_x { (uv_rect.z, uv_rect.x) } else { (uv_rect.x, uv_rect.z) };
+    let (v1, v2) = if flip_y { (uv_rect.w, uv_rect.y) } else { (uv_rect.y, uv_rect.w) };
+    glam::Vec4::new(u1, v1, u2, v2)
 }
 
 
@@ -66,6 +203,13 @@ pub struct Sprite {
     pub flip_y: bool,
 }
 
+impl Sprite {
+    // 应用翻转后的UV矩形，后端采样纹理时使用
+    pub fn flipped_uv_rect(&self) -> glam::Vec4 {
+        flip_uv_rect(self.uv_rect, self.flip_x, self.flip_y)
+    }
+}
+
 // 渲染器类型
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RendererType {
@@ -117,6 +261,7 @@ pub struct RenderStats {
     pub texture_switches: u32,
     pub shader_switches: u32,
     pub batches_merged: u32,
+    pub objects_culled: u32,
     pub gpu_memory_used: u64,
     pub fps: f64,
     pub frame_time_ms: f64,
@@ -175,6 +320,48 @@ pub struct RenderObject {
     pub receive_shadow: bool,
 }
 
+impl RenderObject {
+    // 网格顶点经过变换后的世界空间包围盒，空网格返回None
+    pub fn world_bounds(&self) -> Option<(glam::Vec3, glam::Vec3)> {
+        let mut points = self.mesh.vertices.iter()
+            .map(|vertex| self.transform.transform_point3(glam::Vec3::from(vertex.position)));
+        let first = points.next()?;
+        
+        Some(points.fold((first, first), |(min, max), point| (min.min(point), max.max(point))))
+    }
+}
+
+// 视锥剔除，返回可见对象的下标；空网格不参与渲染
+pub fn cull_objects(camera: &camera::Camera, objects: &[RenderObject]) -> Vec<usize> {
+    objects.iter()
+        .enumerate()
+        .filter(|(_, object)| object.visible)
+        .filter(|(_, object)| match object.world_bounds() {
+            Some((min, max)) => camera.is_aabb_visible(min, max),
+            None => false,
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+// 透明绘制命令，需要按与相机的距离排序后再混合
+#[derive(Debug, Clone)]
+pub struct TransparentDraw {
+    pub command: RenderCommand,
+    pub position: glam::Vec3,
+    pub layer: renderer2d::RenderLayer,
+}
+
+// 透明对象从远到近排序：按沿视线方向的深度降序，深度相同时低层级先画
+pub fn sort_back_to_front(draws: &mut [TransparentDraw], camera_position: glam::Vec3, view_direction: glam::Vec3) {
+    let depth = |draw: &TransparentDraw| (draw.position - camera_position).dot(view_direction);
+    
+    draws.sort_by(|a, b| {
+        depth(b).total_cmp(&depth(a))
+            .then_with(|| a.layer.cmp(&b.layer))
+    });
+}
+
 // 光照类型
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Light {
@@ -229,7 +416,7 @@ pub struct GraphicsContext {
     
     // 渲染队列
     pub render_queue: RenderQueue,
-    pub transparent_queue: Vec<RenderCommand>,
+    pub transparent_queue: Vec<TransparentDraw>,
     
     // 帧缓冲
     pub main_framebuffer: Option<RenderTarget>,
@@ -252,7 +439,8 @@ impl GraphicsContext {
         let mut texture_manager = texture::TextureManager::new();
         texture_manager.initialize_default_textures()?;
         let sprite_renderer = SpriteRenderer::new()?;
-        let ui_renderer = UIRenderer::new()?;
+        let mut ui_renderer = UIRenderer::new()?;
+        ui_renderer.set_screen_size(config.window_width, config.window_height);
         
         // 创建默认相机
         let camera = camera::Camera::perspective(
@@ -290,9 +478,10 @@ impl GraphicsContext {
     
     fn create_renderer(config: &RenderConfig) -> Result<Box<dyn Renderer>> {
         match config.renderer_type {
-            RendererType::OpenGL => {
-                // TODO: 创建OpenGL渲染器
-                Err(GameError::RenderError("OpenGL渲染器未实现".to_string()))
+            RendererType::OpenGL | RendererType::WebGL => {
+                // GPU后端接入前使用软件渲染，保证有画面输出
+                info!("使用软件渲染后端: {}x{}", config.window_width, config.window_height);
+                Ok(Box::new(software::SoftwareRenderer::new(config.window_width, config.window_height)))
             },
             RendererType::Vulkan => {
                 Err(GameError::RenderError("Vulkan渲染器未实现".to_string()))
@@ -312,6 +501,7 @@ impl GraphicsContext {
         self.stats.texture_switches = 0;
         self.stats.shader_switches = 0;
         self.stats.batches_merged = 0;
+        self.stats.objects_culled = 0;
         
         // 清空渲染队列
         self.render_queue.clear();
@@ -329,14 +519,26 @@ impl GraphicsContext {
     
     // 结束帧渲染
     pub fn end_frame(&mut self) -> Result<()> {
+        // 剔除视锥外的对象，只统计提交的对象
+        for index in self.cull_render_objects() {
+            let mesh = &self.render_objects[index].mesh;
+            self.stats.vertices_rendered += mesh.vertices.len() as u32;
+            self.stats.triangles_rendered += (mesh.indices.len() / 3) as u32;
+        }
+        
         // 执行所有渲染命令
         self.execute_render_queue()?;
         
+        // 提交精灵批次
+        let view_projection = self.camera.get_view_projection_matrix();
+        let sprite_draws = self.sprite_renderer.flush(self.renderer.as_mut(), view_projection)?;
+        self.stats.draw_calls += sprite_draws;
+        
         // 渲染透明对象（从后往前）
         self.render_transparent_objects()?;
         
         // 渲染UI
-        self.ui_renderer.render(&mut **self.renderer)?;
+        self.stats.draw_calls += self.ui_renderer.render(&mut **self.renderer)?;
         
         // 呈现到屏幕
         self.renderer.present()?;
@@ -352,6 +554,11 @@ impl GraphicsContext {
         self.render_objects.push(object);
     }
     
+    // 添加透明绘制，在不透明对象之后按深度排序渲染
+    pub fn add_transparent_draw(&mut self, command: RenderCommand, position: glam::Vec3, layer: renderer2d::RenderLayer) {
+        self.transparent_queue.push(TransparentDraw { command, position, layer });
+    }
+    
     // 渲染精灵
     pub fn render_sprite(
         &mut self,
@@ -361,15 +568,30 @@ impl GraphicsContext {
         rotation: f32,
         color: glam::Vec4,
         layer: renderer2d::RenderLayer,
+    ) -> Result<()> {
+        self.render_sprite_region(texture, glam::Vec4::new(0.0, 0.0, 1.0, 1.0), position, size, rotation, color, layer)
+    }
+    
+    // 渲染图集中的子区域，uv_rect为 (u1, v1, u2, v2)，可直接使用AtlasRegion::uv_rect()
+    // 同一图集的精灵共享纹理，会被合并到同一批次
+    pub fn render_sprite_region(
+        &mut self,
+        atlas: &ResourceHandle<texture::Texture>,
+        uv_rect: glam::Vec4,
+        position: glam::Vec2,
+        size: glam::Vec2,
+        rotation: f32,
+        color: glam::Vec4,
+        layer: renderer2d::RenderLayer,
     ) -> Result<()> {
         self.sprite_renderer.add_sprite(Sprite {
-            texture: texture.clone(),
+            texture: atlas.clone(),
             position,
             size,
             rotation,
             color,
             layer,
-            uv_rect: glam::Vec4::new(0.0, 0.0, 1.0, 1.0),
+            uv_rect,
             flip_x: false,
             flip_y: false,
         })?;
@@ -400,15 +622,19 @@ impl GraphicsContext {
         self.camera = camera;
     }
     
-    // 裁剪检测
+    // 裁剪检测，bounds为z=0平面上的矩形 (min_x, min_y, max_x, max_y)
     pub fn is_visible(&self, bounds: &glam::Vec4) -> bool {
-        // 简单的视锥裁剪检测
-        let view_matrix = self.camera.get_view_matrix();
-        let proj_matrix = self.camera.get_projection_matrix();
-        let vp_matrix = proj_matrix * view_matrix;
-        
-        // TODO: 实现更精确的裁剪检测
-        true
+        self.camera.is_aabb_visible(
+            glam::Vec3::new(bounds.x, bounds.y, 0.0),
+            glam::Vec3::new(bounds.z, bounds.w, 0.0),
+        )
+    }
+    
+    // 剔除视锥外的渲染对象，返回可见对象下标并记录剔除数量
+    pub fn cull_render_objects(&mut self) -> Vec<usize> {
+        let visible = cull_objects(&self.camera, &self.render_objects);
+        self.stats.objects_culled += (self.render_objects.len() - visible.len()) as u32;
+        visible
     }
     
     // 批量处理
@@ -480,15 +706,13 @@ impl GraphicsContext {
     }
     
     fn render_transparent_objects(&mut self) -> Result<()> {
-        // 按深度排序透明对象
-        self.transparent_queue.sort_by(|a, b| {
-            // TODO: 实现深度排序
-            std::cmp::Ordering::Equal
-        });
+        // 按深度排序透明对象，保证混合顺序正确
+        sort_back_to_front(&mut self.transparent_queue, self.camera.position, self.camera.get_forward());
         
         // 渲染透明对象
-        for command in &self.transparent_queue {
+        for draw in &self.transparent_queue {
             // TODO: 执行透明对象渲染
+            debug!("渲染透明对象: {:?} 层级={:?}", draw.position, draw.layer);
         }
         
         Ok(())
@@ -534,6 +758,7 @@ impl GraphicsContext {
         
         // 更新相机宽高比
         self.camera.set_aspect_ratio(width as f32 / height as f32);
+        self.ui_renderer.set_screen_size(width, height);
         
         // 重新创建帧缓冲
         if let Some(ref mut framebuffer) = self.main_framebuffer {
@@ -595,46 +820,47 @@ impl Drop for GraphicsContext {
 }
 
 // 全局图形上下文
-static mut GRAPHICS_CONTEXT: Option<GraphicsContext> = None;
-static GRAPHICS_INIT: std::sync::Once = std::sync::Once::new();
-
 pub struct Graphics;
 
+// 全局图形上下文的访问守卫，持有期间独占图形上下文
+pub type GraphicsGuard = ServiceGuard<GraphicsContext>;
+
 impl Graphics {
+    fn lock() -> Result<MutexGuard<'static, Option<GraphicsContext>>> {
+        Services::exclusive::<GraphicsContext>()
+            .lock()
+            .map_err(|_| GameError::RenderError("图形系统锁已损坏".to_string()))
+    }
+    
     pub fn init(config: RenderConfig) -> Result<()> {
-        unsafe {
-            GRAPHICS_INIT.call_once(|| {
-                match GraphicsContext::new(config) {
-                    Ok(context) => {
-                        GRAPHICS_CONTEXT = Some(context);
-                    },
-                    Err(e) => {
-                        error!("图形系统初始化失败: {}", e);
-                    }
-                }
-            });
-        }
+        let mut context = Self::lock()?;
         
-        if unsafe { GRAPHICS_CONTEXT.is_none() } {
-            return Err(GameError::InitializationFailed("图形系统初始化失败".to_string()));
+        if context.is_none() {
+            match GraphicsContext::new(config) {
+                Ok(graphics_context) => {
+                    *context = Some(graphics_context);
+                },
+                Err(e) => {
+                    error!("图形系统初始化失败: {}", e);
+                    return Err(GameError::InitializationFailed("图形系统初始化失败".to_string()));
+                }
+            }
         }
         
         Ok(())
     }
     
-    pub fn instance() -> Result<&'static mut GraphicsContext> {
-        unsafe {
-            GRAPHICS_CONTEXT.as_mut()
-                .ok_or_else(|| GameError::RenderError("图形系统未初始化".to_string()))
-        }
+    pub fn instance() -> Result<GraphicsGuard> {
+        ServiceGuard::new(Self::lock()?)
+            .ok_or_else(|| GameError::RenderError("图形系统未初始化".to_string()))
     }
     
     pub fn cleanup() {
-        unsafe {
-            if let Some(ref mut context) = GRAPHICS_CONTEXT {
-                context.cleanup();
+        if let Ok(mut context) = Self::lock() {
+            if let Some(ref mut graphics_context) = *context {
+                graphics_context.cleanup();
             }
-            GRAPHICS_CONTEXT = None;
+            *context = None;
         }
     }
 }
@@ -712,4 +938,120 @@ mod tests {
         assert_eq!(mesh.vertices.len(), 4);
         assert_eq!(mesh.indices.len(), 6);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_cull_objects() {
+        let camera = camera::Camera::perspective(60.0_f32.to_radians(), 16.0 / 9.0, 0.1, 100.0);
+        let object_at = |translation: glam::Vec3| RenderObject {
+            mesh: create_quad_mesh(),
+            transform: glam::Mat4::from_translation(translation),
+            layer: renderer2d::RenderLayer::default(),
+            visible: true,
+            cast_shadow: false,
+            receive_shadow: false,
+        };
+        
+        let objects = vec![
+            object_at(glam::Vec3::ZERO),              // 视野内
+            object_at(glam::Vec3::new(0.0, 0.0, 10.0)), // 相机后方
+            object_at(glam::Vec3::new(0.0, 0.0, -2.0)), // 视野内
+        ];
+        
+        assert_eq!(cull_objects(&camera, &objects), vec![0, 2]);
+    }
+    
+    #[test]
+    fn test_transparent_back_to_front_order() {
+        let draw = |z: f32, layer: renderer2d::RenderLayer| TransparentDraw {
+            command: RenderCommand::DrawMesh { shader_id: 0, texture_id: None },
+            position: glam::Vec3::new(0.0, 0.0, z),
+            layer,
+        };
+        
+        // 相机在 z=5 朝 -Z 看，z越小越远
+        let mut draws = vec![
+            draw(0.0, renderer2d::RenderLayer::Effects),
+            draw(-3.0, renderer2d::RenderLayer::Terrain),
+            draw(2.0, renderer2d::RenderLayer::Effects),
+            draw(0.0, renderer2d::RenderLayer::Objects),
+        ];
+        sort_back_to_front(&mut draws, glam::Vec3::new(0.0, 0.0, 5.0), glam::Vec3::NEG_Z);
+        
+        let order: Vec<(f32, renderer2d::RenderLayer)> = draws.iter()
+            .map(|draw| (draw.position.z, draw.layer))
+            .collect();
+        assert_eq!(order, vec![
+            (-3.0, renderer2d::RenderLayer::Terrain),
+            (0.0, renderer2d::RenderLayer::Objects),
+            (0.0, renderer2d::RenderLayer::Effects),
+            (2.0, renderer2d::RenderLayer::Effects),
+        ]);
+    }
+    
+    #[test]
+    fn test_headless_frame_batches_sprites() {
+        ResourceManager::init().unwrap();
+        
+        let config = RenderConfig {
+            window_width: 64,
+            window_height: 64,
+            ..Default::default()
+        };
+        let mut context = GraphicsContext::new(config).unwrap();
+        context.set_camera(camera::Camera::ortho_2d(64.0, 64.0));
+        
+        let grass = ResourceManager::instance().store_resource(
+            "grass".to_string(),
+            texture::Texture::new(100, "grass".to_string(), TextureDesc::default()),
+        );
+        
+        context.begin_frame().unwrap();
+        let red = glam::Vec4::new(1.0, 0.0, 0.0, 1.0);
+        context.render_sprite(&grass, glam::Vec2::new(-16.0, 0.0), glam::Vec2::splat(8.0), 0.0, red, renderer2d::RenderLayer::Objects).unwrap();
+        context.render_sprite(&grass, glam::Vec2::new(16.0, 0.0), glam::Vec2::splat(8.0), 0.0, red, renderer2d::RenderLayer::Objects).unwrap();
+        context.end_frame().unwrap();
+        
+        // 同纹理同层级的两个精灵合并为一次绘制
+        assert_eq!(context.get_stats().draw_calls, 1);
+        
+        let pixels = context.capture_screenshot().unwrap();
+        let pixel = |x: usize, y: usize| &pixels[(y * 64 + x) * 4..(y * 64 + x) * 4 + 4];
+        assert_eq!(pixel(16, 32), &[255, 0, 0, 255]);
+        assert_eq!(pixel(48, 32), &[255, 0, 0, 255]);
+        // 背景为清屏颜色
+        assert_eq!(pixel(0, 0), &[51, 77, 204, 255]);
+    }
+    
+    #[test]
+    fn test_flip_uv_rect() {
+        let uv = glam::Vec4::new(0.25, 0.0, 0.5, 0.5);
+        
+        assert_eq!(flip_uv_rect(uv, false, false), uv);
+        assert_eq!(flip_uv_rect(uv, true, false), glam::Vec4::new(0.5, 0.0, 0.25, 0.5));
+        assert_eq!(flip_uv_rect(uv, false, true), glam::Vec4::new(0.25, 0.5, 0.5, 0.0));
+        assert_eq!(flip_uv_rect(uv, true, true), glam::Vec4::new(0.5, 0.5, 0.25, 0.0));
+    }
+    
+    #[test]
+    fn test_nine_slice_segments() {
+        let texture_size = glam::Vec2::new(32.0, 32.0);
+        let segments = nine_slice_segments(glam::Vec4::new(10.0, 20.0, 200.0, 100.0), ui::EdgeInsets::all(8.0), texture_size);
+        assert_eq!(segments.len(), 9);
+        
+        // 四角保持纹理中的原始尺寸
+        let (top_left, top_left_uv) = segments[0];
+        assert_eq!(top_left, glam::Vec4::new(10.0, 20.0, 8.0, 8.0));
+        assert_eq!(top_left_uv, glam::Vec4::new(0.0, 0.0, 0.25, 0.25));
+        let (bottom_right, _) = segments[8];
+        assert_eq!(bottom_right, glam::Vec4::new(202.0, 112.0, 8.0, 8.0));
+        
+        // 中心拉伸填满剩余区域
+        let (center, center_uv) = segments[4];
+        assert_eq!(center, glam::Vec4::new(18.0, 28.0, 184.0, 84.0));
+        assert_eq!(center_uv, glam::Vec4::new(0.25, 0.25, 0.75, 0.75));
+        
+        // 只有左右边框时退化为三段
+        let horizontal = nine_slice_segments(glam::Vec4::new(0.0, 0.0, 100.0, 20.0), ui::EdgeInsets::horizontal_vertical(8.0, 0.0), texture_size);
+        assert_eq!(horizontal.len(), 3);
+    }
+}
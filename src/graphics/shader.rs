@@ -54,6 +54,39 @@ pub struct ShaderProgram {
     pub native_handle: Option<u32>, // OpenGL/Vulkan等的原生句柄
     pub last_modified: std::time::SystemTime,
     pub file_paths: Vec<PathBuf>,
+    pub uniform_values: HashMap<String, UniformValue>, // 已设置的uniform值，重载后重新应用
+}
+
+impl ShaderProgram {
+    // 设置uniform值，类型必须与着色器声明一致
+    pub fn set_uniform(&mut self, name: &str, value: UniformValue) -> Result<()> {
+        let uniform_info = self.uniforms.get(name)
+            .ok_or_else(|| GameError::RenderError(format!("Uniform不存在: {} (着色器: {})", name, self.name)))?;
+        
+        if !uniform_info.uniform_type.accepts(&value) {
+            return Err(GameError::RenderError(
+                format!("Uniform类型不匹配: {} 期望 {:?}，得到 {}",
+                       name, uniform_info.uniform_type, value.type_name())
+            ));
+        }
+        
+        if let UniformValue::TextureArray(ref textures) = value {
+            if textures.len() > uniform_info.count {
+                return Err(GameError::RenderError(
+                    format!("Uniform数组越界: {} 最多 {} 个元素，得到 {}", name, uniform_info.count, textures.len())
+                ));
+            }
+        }
+        
+        // TODO: 实际的uniform设置调用
+        debug!("设置uniform: {} = {:?}", name, value);
+        self.uniform_values.insert(name.to_string(), value);
+        Ok(())
+    }
+    
+    pub fn get_uniform(&self, name: &str) -> Option<&UniformValue> {
+        self.uniform_values.get(name)
+    }
 }
 
 // Uniform信息
@@ -100,6 +133,34 @@ pub enum UniformType {
     SamplerArray,
 }
 
+impl UniformType {
+    // 判断uniform值是否可以赋给该类型
+    pub fn accepts(&self, value: &UniformValue) -> bool {
+        matches!((self, value),
+            (UniformType::Bool, UniformValue::Bool(_)) |
+            (UniformType::Int, UniformValue::Int(_)) |
+            (UniformType::UInt, UniformValue::UInt(_)) |
+            (UniformType::Float, UniformValue::Float(_)) |
+            (UniformType::Vec2, UniformValue::Vec2(_)) |
+            (UniformType::Vec3, UniformValue::Vec3(_)) |
+            (UniformType::Vec4, UniformValue::Vec4(_)) |
+            (UniformType::IVec2, UniformValue::IVec2(_)) |
+            (UniformType::IVec3, UniformValue::IVec3(_)) |
+            (UniformType::IVec4, UniformValue::IVec4(_)) |
+            (UniformType::UVec2, UniformValue::UVec2(_)) |
+            (UniformType::UVec3, UniformValue::UVec3(_)) |
+            (UniformType::UVec4, UniformValue::UVec4(_)) |
+            (UniformType::Mat2, UniformValue::Mat2(_)) |
+            (UniformType::Mat3, UniformValue::Mat3(_)) |
+            (UniformType::Mat4, UniformValue::Mat4(_)) |
+            (UniformType::Sampler2D, UniformValue::Texture(_)) |
+            (UniformType::SamplerCube, UniformValue::Texture(_)) |
+            (UniformType::Sampler3D, UniformValue::Texture(_)) |
+            (UniformType::SamplerArray, UniformValue::TextureArray(_))
+        )
+    }
+}
+
 // Attribute类型
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AttributeType {
@@ -140,6 +201,31 @@ pub enum UniformValue {
     TextureArray(Vec<u32>),
 }
 
+impl UniformValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            UniformValue::Bool(_) => "Bool",
+            UniformValue::Int(_) => "Int",
+            UniformValue::UInt(_) => "UInt",
+            UniformValue::Float(_) => "Float",
+            UniformValue::Vec2(_) => "Vec2",
+            UniformValue::Vec3(_) => "Vec3",
+            UniformValue::Vec4(_) => "Vec4",
+            UniformValue::IVec2(_) => "IVec2",
+            UniformValue::IVec3(_) => "IVec3",
+            UniformValue::IVec4(_) => "IVec4",
+            UniformValue::UVec2(_) => "UVec2",
+            UniformValue::UVec3(_) => "UVec3",
+            UniformValue::UVec4(_) => "UVec4",
+            UniformValue::Mat2(_) => "Mat2",
+            UniformValue::Mat3(_) => "Mat3",
+            UniformValue::Mat4(_) => "Mat4",
+            UniformValue::Texture(_) => "Texture",
+            UniformValue::TextureArray(_) => "TextureArray",
+        }
+    }
+}
+
 // 着色器编译错误
 #[derive(Debug, Clone)]
 pub struct ShaderCompileError {
@@ -149,6 +235,19 @@ pub struct ShaderCompileError {
     pub source_file: Option<PathBuf>,
 }
 
+impl std::fmt::Display for ShaderCompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}着色器编译失败", self.shader_type)?;
+        if let Some(ref path) = self.source_file {
+            write!(f, " {}", path.display())?;
+        }
+        if let Some(line) = self.line_number {
+            write!(f, " 第{}行", line)?;
+        }
+        write!(f, ": {}", self.error_message)
+    }
+}
+
 // 着色器管理器
 pub struct ShaderManager {
     shaders: HashMap<ShaderId, ShaderProgram>,
@@ -158,6 +257,7 @@ pub struct ShaderManager {
     include_cache: HashMap<String, String>,
     watch_files: bool,
     file_watcher: Option<FileWatcher>,
+    compile_errors: HashMap<ShaderId, ShaderCompileError>, // 最近一次重载失败的错误
 }
 
 // 文件监视器（简化实现）
@@ -212,6 +312,7 @@ impl ShaderManager {
             } else {
                 None
             },
+            compile_errors: HashMap::new(),
         }
     }
     
@@ -299,22 +400,8 @@ impl ShaderManager {
     
     // 设置uniform值
     pub fn set_uniform(&mut self, shader_id: ShaderId, name: &str, value: UniformValue) -> Result<()> {
-        if let Some(shader) = self.shaders.get(&shader_id) {
-            if let Some(uniform_info) = shader.uniforms.get(name) {
-                // 检查类型匹配
-                if !self.is_uniform_type_compatible(&uniform_info.uniform_type, &value) {
-                    return Err(GameError::RenderError(
-                        format!("Uniform类型不匹配: {} 期望 {:?}，得到 {:?}",
-                               name, uniform_info.uniform_type, self.get_uniform_value_type(&value))
-                    ));
-                }
-                
-                // TODO: 实际的uniform设置调用
-                debug!("设置uniform: {} = {:?}", name, value);
-                Ok(())
-            } else {
-                Err(GameError::RenderError(format!("Uniform不存在: {}", name)))
-            }
+        if let Some(shader) = self.shaders.get_mut(&shader_id) {
+            shader.set_uniform(name, value)
         } else {
             Err(GameError::RenderError(format!("着色器不存在: {}", shader_id)))
         }
@@ -328,6 +415,69 @@ impl ShaderManager {
         Ok(())
     }
     
+    // 从磁盘重新编译着色器，编译失败时保留旧程序并记录错误
+    pub fn reload(&mut self, shader_id: ShaderId) -> Result<()> {
+        let (name, file_paths) = match self.shaders.get(&shader_id) {
+            Some(shader) => (shader.name.clone(), shader.file_paths.clone()),
+            None => return Err(GameError::RenderError(format!("着色器不存在: {}", shader_id))),
+        };
+        
+        if file_paths.len() < 2 {
+            return Err(GameError::RenderError(format!("着色器没有源文件，无法重新加载: {}", name)));
+        }
+        
+        // include文件也可能被修改
+        self.include_cache.clear();
+        let source = create_basic_vertex_fragment_source(
+            &self.read_shader_file(&file_paths[0])?,
+            &self.read_shader_file(&file_paths[1])?,
+        );
+        
+        let stages = match self.compile_stages(&source) {
+            Ok(stages) => stages,
+            Err(mut compile_error) => {
+                compile_error.source_file = match compile_error.shader_type {
+                    ShaderType::Vertex => Some(file_paths[0].clone()),
+                    ShaderType::Fragment => Some(file_paths[1].clone()),
+                    _ => None,
+                };
+                
+                error!("着色器重新加载失败，继续使用旧版本: {}: {}", name, compile_error);
+                let message = compile_error.to_string();
+                self.compile_errors.insert(shader_id, compile_error);
+                return Err(GameError::RenderError(message));
+            }
+        };
+        
+        let native_handle = self.link_shader_program(stages)?;
+        let (uniforms, attributes) = self.reflect_shader_interface(native_handle)?;
+        
+        if let Some(shader) = self.shaders.get_mut(&shader_id) {
+            shader.stages = stages;
+            shader.uniforms = uniforms;
+            shader.attributes = attributes;
+            shader.native_handle = Some(native_handle);
+            shader.last_modified = std::time::SystemTime::now();
+            
+            // 重新应用仍然有效的uniform值
+            let previous_values = std::mem::take(&mut shader.uniform_values);
+            for (uniform_name, value) in previous_values {
+                if let Err(e) = shader.set_uniform(&uniform_name, value) {
+                    warn!("重载后丢弃uniform {}: {}", uniform_name, e);
+                }
+            }
+        }
+        
+        self.compile_errors.remove(&shader_id);
+        info!("着色器重新加载成功: {} (ID: {})", name, shader_id);
+        Ok(())
+    }
+    
+    // 获取最近一次重新加载的编译错误
+    pub fn get_compile_error(&self, shader_id: ShaderId) -> Option<&ShaderCompileError> {
+        self.compile_errors.get(&shader_id)
+    }
+    
     // 检查文件更改并重新加载
     pub fn check_for_changes(&mut self) -> Result<Vec<ShaderId>> {
         let mut reloaded_shaders = Vec::new();
@@ -353,27 +503,17 @@ impl ShaderManager {
                     }
                 }
                 
-                // 重新加载着色器
+                // 重新加载着色器，失败的着色器保留旧版本，不中断其他着色器
                 for shader_id in shaders_to_reload {
-                    if let Some(shader) = self.shaders.get(&shader_id) {
-                        let name = shader.name.clone();
-                        let file_paths = shader.file_paths.clone();
-                        
-                        if file_paths.len() >= 2 {
-                            // 移除旧的缓存条目
-                            self.shader_cache.remove(&name);
-                            
-                            // 重新加载
-                            match self.load_from_file(&name, &file_paths[0], &file_paths[1]) {
-                                Ok(_) => {
-                                    info!("着色器热重载成功: {}", name);
-                                    reloaded_shaders.push(shader_id);
-                                },
-                                Err(e) => {
-                                    error!("着色器热重载失败: {}: {}", name, e);
-                                }
-                            }
-                        }
+                    if self.reload(shader_id).is_ok() {
+                        reloaded_shaders.push(shader_id);
+                    }
+                }
+                
+                // 更新时间戳，避免同一修改被反复重载
+                if let Some(ref mut watcher) = self.file_watcher {
+                    for changed_file in changed_files {
+                        watcher.watch_file(changed_file)?;
                     }
                 }
             }
@@ -387,6 +527,7 @@ impl ShaderManager {
         if let Some(shader) = self.shaders.remove(&shader_id) {
             // 从缓存中移除
             self.shader_cache.remove(&shader.name);
+            self.compile_errors.remove(&shader_id);
             
             // TODO: 释放GPU资源
             debug!("删除着色器: {} (ID: {})", shader.name, shader_id);
@@ -413,6 +554,7 @@ impl ShaderManager {
         self.shaders.clear();
         self.shader_cache.clear();
         self.include_cache.clear();
+        self.compile_errors.clear();
         self.next_id = 1;
     }
     
@@ -481,6 +623,36 @@ impl ShaderManager {
         self.next_id += 1;
         
         // 编译各个阶段
+        let stages = self.compile_stages(&source)
+            .map_err(|e| GameError::RenderError(e.to_string()))?;
+        
+        // 链接程序
+        let native_handle = self.link_shader_program(stages)?;
+        
+        // 反射uniform和attribute信息
+        let (uniforms, attributes) = self.reflect_shader_interface(native_handle)?;
+        
+        // 创建着色器程序对象
+        let shader_program = ShaderProgram {
+            id: shader_id,
+            name: name.to_string(),
+            stages,
+            uniforms,
+            attributes,
+            native_handle: Some(native_handle),
+            last_modified: std::time::SystemTime::now(),
+            file_paths: Vec::new(),
+            uniform_values: HashMap::new(),
+        };
+        
+        self.shaders.insert(shader_id, shader_program);
+        
+        debug!("着色器程序创建成功: {} (ID: {}, 句柄: {})", name, shader_id, native_handle);
+        Ok(shader_id)
+    }
+    
+    // 编译所有阶段，返回阶段位掩码
+    fn compile_stages(&self, source: &ShaderSource) -> std::result::Result<u32, ShaderCompileError> {
         let mut stages = 0u32;
         
         // 顶点着色器
@@ -511,39 +683,23 @@ impl ShaderManager {
             }
         }
         
-        // 链接程序
-        let native_handle = self.link_shader_program(stages)?;
-        
-        // 反射uniform和attribute信息
-        let (uniforms, attributes) = self.reflect_shader_interface(native_handle)?;
-        
-        // 创建着色器程序对象
-        let shader_program = ShaderProgram {
-            id: shader_id,
-            name: name.to_string(),
-            stages,
-            uniforms,
-            attributes,
-            native_handle: Some(native_handle),
-            last_modified: std::time::SystemTime::now(),
-            file_paths: Vec::new(),
-        };
-        
-        self.shaders.insert(shader_id, shader_program);
-        
-        debug!("着色器程序创建成功: {} (ID: {}, 句柄: {})", name, shader_id, native_handle);
-        Ok(shader_id)
+        Ok(stages)
     }
     
-    fn compile_shader_stage(&self, shader_type: ShaderType, source: &str) -> Result<u32> {
+    fn compile_shader_stage(&self, shader_type: ShaderType, source: &str) -> std::result::Result<u32, ShaderCompileError> {
         // TODO: 实际的着色器编译
         // 这里应该调用OpenGL/Vulkan等API进行编译
         
         debug!("编译着色器阶段: {:?}", shader_type);
         
         // 模拟编译过程
-        if source.contains("ERROR") {
-            return Err(GameError::RenderError("着色器编译错误".to_string()));
+        if let Some((index, line)) = source.lines().enumerate().find(|(_, line)| line.contains("ERROR")) {
+            return Err(ShaderCompileError {
+                shader_type,
+                error_message: format!("无法识别的语句 '{}'", line.trim()),
+                line_number: Some(index as u32 + 1),
+                source_file: None,
+            });
         }
         
         Ok(fastrand::u32(1000..9999)) // 返回模拟的句柄
@@ -596,33 +752,7 @@ impl ShaderManager {
     }
     
     fn is_uniform_type_compatible(&self, expected: &UniformType, value: &UniformValue) -> bool {
-        match (expected, value) {
-            (UniformType::Bool, UniformValue::Bool(_)) => true,
-            (UniformType::Int, UniformValue::Int(_)) => true,
-            (UniformType::UInt, UniformValue::UInt(_)) => true,
-            (UniformType::Float, UniformValue::Float(_)) => true,
-            (UniformType::Vec2, UniformValue::Vec2(_)) => true,
-            (UniformType::Vec3, UniformValue::Vec3(_)) => true,
-            (UniformType::Vec4, UniformValue::Vec4(_)) => true,
-            (UniformType::Mat4, UniformValue::Mat4(_)) => true,
-            (UniformType::Sampler2D, UniformValue::Texture(_)) => true,
-            _ => false,
-        }
-    }
-    
-    fn get_uniform_value_type(&self, value: &UniformValue) -> &'static str {
-        match value {
-            UniformValue::Bool(_) => "Bool",
-            UniformValue::Int(_) => "Int",
-            UniformValue::UInt(_) => "UInt",
-            UniformValue::Float(_) => "Float",
-            UniformValue::Vec2(_) => "Vec2",
-            UniformValue::Vec3(_) => "Vec3",
-            UniformValue::Vec4(_) => "Vec4",
-            UniformValue::Mat4(_) => "Mat4",
-            UniformValue::Texture(_) => "Texture",
-            _ => "Unknown",
-        }
+        expected.accepts(value)
     }
 }
 
@@ -754,4 +884,56 @@ mod tests {
         assert_eq!(source.fragment_source, "fragment code");
         assert!(source.geometry_source.is_none());
     }
+    
+    #[test]
+    fn test_set_uniform_rejects_type_mismatch() {
+        let mut manager = ShaderManager::new(".");
+        let source = create_basic_vertex_fragment_source(
+            builtin_shaders::BASIC_2D_VERTEX,
+            builtin_shaders::BASIC_2D_FRAGMENT,
+        );
+        let shader_id = manager.create_from_source("basic_2d", source).unwrap();
+        let shader = manager.get_shader_mut(shader_id).unwrap();
+        
+        assert!(shader.set_uniform("u_mvp_matrix", UniformValue::Mat4(glam::Mat4::IDENTITY)).is_ok());
+        
+        let result = shader.set_uniform("u_mvp_matrix", UniformValue::Float(1.0));
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("Mat4"));
+        assert!(message.contains("Float"));
+        
+        // 失败的设置不覆盖已有值
+        assert!(matches!(shader.get_uniform("u_mvp_matrix"), Some(UniformValue::Mat4(_))));
+        assert!(shader.set_uniform("u_missing", UniformValue::Int(0)).is_err());
+        assert!(manager.set_uniform(shader_id, "u_texture", UniformValue::Vec2(glam::Vec2::ZERO)).is_err());
+    }
+    
+    #[test]
+    fn test_reload_reports_compile_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let vertex_path = dir.path().join("sprite.vert");
+        let fragment_path = dir.path().join("sprite.frag");
+        std::fs::write(&vertex_path, builtin_shaders::BASIC_2D_VERTEX).unwrap();
+        std::fs::write(&fragment_path, builtin_shaders::BASIC_2D_FRAGMENT).unwrap();
+        
+        let mut manager = ShaderManager::new(dir.path());
+        let shader_id = manager.load_from_file("sprite", &vertex_path, &fragment_path).unwrap();
+        manager.set_uniform(shader_id, "u_mvp_matrix", UniformValue::Mat4(glam::Mat4::IDENTITY)).unwrap();
+        
+        std::fs::write(&fragment_path, "#version 330 core\nvoid main() {\n    ERROR\n}\n").unwrap();
+        assert!(manager.reload(shader_id).is_err());
+        
+        let compile_error = manager.get_compile_error(shader_id).unwrap();
+        assert_eq!(compile_error.shader_type, ShaderType::Fragment);
+        assert_eq!(compile_error.line_number, Some(3));
+        assert_eq!(compile_error.source_file.as_deref(), Some(fragment_path.as_path()));
+        
+        // 旧程序仍然可用
+        assert!(manager.use_shader(shader_id).is_ok());
+        
+        std::fs::write(&fragment_path, builtin_shaders::BASIC_2D_FRAGMENT).unwrap();
+        manager.reload(shader_id).unwrap();
+        assert!(manager.get_compile_error(shader_id).is_none());
+        assert!(manager.get_shader(shader_id).unwrap().get_uniform("u_mvp_matrix").is_some());
+    }
 }
\ No newline at end of file
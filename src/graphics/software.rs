@@ -0,0 +1,152 @@
+// 软件渲染后端
+// 开发心理：GPU后端还没接入前也要能看到画面，测试和无窗口环境同样需要一个可用的渲染器
+// 设计原则：CPU光栅化到RGBA8帧缓冲，接口与GPU后端一致，present后可读回像素
+
+use super::{Renderer, Sprite};
+use crate::core::Result;
+use log::debug;
+
+#[derive(Debug)]
+pub struct SoftwareRenderer {
+    width: u32,
+    height: u32,
+    framebuffer: Vec<u8>, // 正在绘制的帧
+    presented: Vec<u8>,   // 最近一次呈现的帧
+    clear_color: [f32; 4],
+    vsync: bool,
+    frame_draw_calls: u32,
+    frames_presented: u64,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: u32, height: u32) -> Self {
+        let size = (width * height * 4) as usize;
+        Self {
+            width,
+            height,
+            framebuffer: vec![0; size],
+            presented: vec![0; size],
+            clear_color: [0.0, 0.0, 0.0, 1.0],
+            vsync: true,
+            frame_draw_calls: 0,
+            frames_presented: 0,
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    // 上一次呈现的帧中的绘制调用数
+    pub fn frame_draw_calls(&self) -> u32 {
+        self.frame_draw_calls
+    }
+
+    pub fn frames_presented(&self) -> u64 {
+        self.frames_presented
+    }
+
+    // 将精灵四个角投影到屏幕，按包围矩形填充颜色（暂不采样纹理）
+    fn rasterize_sprite(&mut self, sprite: &Sprite, view_projection: glam::Mat4) {
+        let half = sprite.size * 0.5;
+        let rotation = glam::Mat2::from_angle(sprite.rotation);
+        let corners = [
+            glam::Vec2::new(-half.x, -half.y),
+            glam::Vec2::new(half.x, -half.y),
+            glam::Vec2::new(half.x, half.y),
+            glam::Vec2::new(-half.x, half.y),
+        ];
+
+        let mut min = glam::Vec2::splat(f32::MAX);
+        let mut max = glam::Vec2::splat(f32::MIN);
+        for corner in corners {
+            let world = (sprite.position + rotation * corner).extend(0.0);
+            let ndc = view_projection.project_point3(world);
+            // NDC -> 像素坐标（左上角为原点）
+            let screen = glam::Vec2::new(
+                (ndc.x + 1.0) * 0.5 * self.width as f32,
+                (1.0 - ndc.y) * 0.5 * self.height as f32,
+            );
+            min = min.min(screen);
+            max = max.max(screen);
+        }
+
+        let x0 = min.x.max(0.0).round() as u32;
+        let y0 = min.y.max(0.0).round() as u32;
+        let x1 = (max.x.round().max(0.0) as u32).min(self.width);
+        let y1 = (max.y.round().max(0.0) as u32).min(self.height);
+
+        let color = sprite.color.clamp(glam::Vec4::ZERO, glam::Vec4::ONE);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let index = ((y * self.width + x) * 4) as usize;
+                blend_pixel(&mut self.framebuffer[index..index + 4], color);
+            }
+        }
+    }
+}
+
+// 源颜色按alpha混合到目标像素
+fn blend_pixel(pixel: &mut [u8], color: glam::Vec4) {
+    let alpha = color.w;
+    for (channel, source) in pixel.iter_mut().take(3).zip([color.x, color.y, color.z]) {
+        let blended = source * alpha + (*channel as f32 / 255.0) * (1.0 - alpha);
+        *channel = (blended * 255.0).round() as u8;
+    }
+    pixel[3] = 255;
+}
+
+fn to_byte(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+impl Renderer for SoftwareRenderer {
+    fn clear_color(&mut self, r: f32, g: f32, b: f32, a: f32) -> Result<()> {
+        self.clear_color = [r, g, b, a];
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        let color = self.clear_color.map(to_byte);
+        for pixel in self.framebuffer.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&color);
+        }
+        self.frame_draw_calls = 0;
+        Ok(())
+    }
+
+    fn present(&mut self) -> Result<()> {
+        self.presented.copy_from_slice(&self.framebuffer);
+        self.frames_presented += 1;
+        debug!("软件渲染呈现第 {} 帧: {} 次绘制", self.frames_presented, self.frame_draw_calls);
+        Ok(())
+    }
+
+    fn viewport(&mut self, _x: i32, _y: i32, width: u32, height: u32) -> Result<()> {
+        if (width, height) != (self.width, self.height) {
+            *self = Self {
+                clear_color: self.clear_color,
+                vsync: self.vsync,
+                ..Self::new(width, height)
+            };
+        }
+        Ok(())
+    }
+
+    fn set_vsync(&mut self, vsync: bool) -> Result<()> {
+        self.vsync = vsync;
+        Ok(())
+    }
+
+    fn read_pixels(&self) -> Result<Vec<u8>> {
+        Ok(self.presented.clone())
+    }
+
+    fn draw_sprite_batch(&mut self, sprites: &[Sprite], view_projection: glam::Mat4) -> Result<()> {
+        for sprite in sprites {
+            self.rasterize_sprite(sprite, view_projection);
+        }
+        self.frame_draw_calls += 1;
+        Ok(())
+    }
+}
@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use log::{debug, warn, error};
 use crate::core::error::GameError;
+use crate::core::event_system::{Event, EventSystem};
+use crate::graphics::texture::AtlasRegion;
 use crate::graphics::renderer::{Renderer2D, TextureInfo, BlendMode};
 use glam::{Vec2, Vec3, Vec4, Mat4};
 
@@ -56,6 +58,24 @@ pub struct TextureRegion {
     pub height: f32,    // 高度 (UV空间)
 }
 
+impl TextureRegion {
+    // UV矩形 (u1, v1, u2, v2)
+    pub fn uv_rect(&self) -> Vec4 {
+        Vec4::new(self.u, self.v, self.u + self.width, self.v + self.height)
+    }
+}
+
+impl From<&AtlasRegion> for TextureRegion {
+    fn from(region: &AtlasRegion) -> Self {
+        Self {
+            u: region.u1,
+            v: region.v1,
+            width: region.u2 - region.u1,
+            height: region.v2 - region.v1,
+        }
+    }
+}
+
 // 精灵数据
 #[derive(Debug, Clone)]
 pub struct Sprite {
@@ -118,6 +138,139 @@ pub struct SpriteAnimation {
     pub total_duration: f32,
     pub loop_mode: AnimationLoopMode,
     pub events: Vec<AnimationEvent>, // 动画事件
+    
+    // 播放状态
+    current_frame: usize,
+    frame_elapsed: f32,
+    ping_pong_forward: bool,
+    finished: bool,
+}
+
+// 帧时长下限，防止时长为0的帧导致死循环
+const MIN_FRAME_DURATION: f32 = 0.001;
+
+// 单次播放的动画结束事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnimationFinished {
+    pub animation_id: AnimationId,
+    pub animation_name: String,
+    pub sprite_id: Option<SpriteId>,
+}
+
+impl Event for AnimationFinished {
+    fn event_type(&self) -> &'static str { "AnimationFinished" }
+    fn as_any(&self) -> &dyn std::any::Any { self }
+}
+
+impl SpriteAnimation {
+    pub fn new(id: AnimationId, name: String, frames: Vec<AnimationFrame>, loop_mode: AnimationLoopMode) -> Self {
+        let total_duration = frames.iter().map(|f| f.duration).sum();
+        
+        let mut animation = Self {
+            id,
+            name,
+            frames,
+            total_duration,
+            loop_mode,
+            events: Vec::new(),
+            current_frame: 0,
+            frame_elapsed: 0.0,
+            ping_pong_forward: true,
+            finished: false,
+        };
+        animation.reset();
+        animation
+    }
+    
+    // 回到起始帧（反向模式从最后一帧开始）
+    pub fn reset(&mut self) {
+        self.current_frame = match self.loop_mode {
+            AnimationLoopMode::Reverse => self.frames.len().saturating_sub(1),
+            _ => 0,
+        };
+        self.frame_elapsed = 0.0;
+        self.ping_pong_forward = true;
+        self.finished = false;
+    }
+    
+    // 按每帧时长推进，单次模式播放完最后一帧时返回结束事件
+    pub fn update(&mut self, delta_time: f32) -> Option<AnimationFinished> {
+        if self.finished || self.frames.is_empty() {
+            return None;
+        }
+        
+        self.frame_elapsed += delta_time;
+        loop {
+            let duration = self.frames[self.current_frame].duration.max(MIN_FRAME_DURATION);
+            if self.frame_elapsed < duration {
+                return None;
+            }
+            
+            self.frame_elapsed -= duration;
+            if !self.advance_frame() {
+                self.finished = true;
+                self.frame_elapsed = 0.0;
+                return Some(AnimationFinished {
+                    animation_id: self.id,
+                    animation_name: self.name.clone(),
+                    sprite_id: None,
+                });
+            }
+        }
+    }
+    
+    // 切换到下一帧，单次模式已在最后一帧时返回false
+    fn advance_frame(&mut self) -> bool {
+        let last = self.frames.len() - 1;
+        
+        match self.loop_mode {
+            AnimationLoopMode::None => {
+                if self.current_frame >= last {
+                    return false;
+                }
+                self.current_frame += 1;
+            }
+            AnimationLoopMode::Loop => {
+                self.current_frame = (self.current_frame + 1) % self.frames.len();
+            }
+            AnimationLoopMode::Reverse => {
+                self.current_frame = if self.current_frame == 0 { last } else { self.current_frame - 1 };
+            }
+            AnimationLoopMode::PingPong => {
+                if last == 0 {
+                    return true;
+                }
+                
+                // 到达两端时反向，端点帧不重复播放
+                if self.ping_pong_forward && self.current_frame == last {
+                    self.ping_pong_forward = false;
+                } else if !self.ping_pong_forward && self.current_frame == 0 {
+                    self.ping_pong_forward = true;
+                }
+                
+                if self.ping_pong_forward {
+                    self.current_frame += 1;
+                } else {
+                    self.current_frame -= 1;
+                }
+            }
+        }
+        
+        true
+    }
+    
+    pub fn current_frame_index(&self) -> usize {
+        self.current_frame
+    }
+    
+    // 当前帧在图集中的纹理区域
+    pub fn current_region(&self) -> Option<TextureRegion> {
+        self.frames.get(self.current_frame).map(|frame| frame.texture_region)
+    }
+    
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
 }
 
 // 动画循环模式
@@ -469,16 +622,8 @@ impl SpriteManager {
         let animation_id = self.next_animation_id;
         self.next_animation_id += 1;
         
-        let total_duration = frames.iter().map(|f| f.duration).sum();
-        
-        let animation = SpriteAnimation {
-            id: animation_id,
-            name: name.clone(),
-            frames,
-            total_duration,
-            loop_mode,
-            events: Vec::new(),
-        };
+        let animation = SpriteAnimation::new(animation_id, name.clone(), frames, loop_mode);
+        let total_duration = animation.total_duration;
         
         self.animations.insert(animation_id, animation);
         self.animation_cache.insert(name.clone(), animation_id);
@@ -685,6 +830,7 @@ impl SpriteManager {
     
     fn update_animations(&mut self, delta_time: f32) -> Result<(), GameError> {
         let mut events_to_trigger = Vec::new();
+        let mut finished_animations = Vec::new();
         
         for sprite in self.sprites.values_mut() {
             if sprite.state != SpriteState::Animated {
@@ -742,6 +888,11 @@ impl SpriteManager {
                     if animation_finished {
                         sprite.state = SpriteState::Visible;
                         sprite.current_animation = None;
+                        finished_animations.push(AnimationFinished {
+                            animation_id,
+                            animation_name: animation.name.clone(),
+                            sprite_id: Some(sprite.id),
+                        });
                     }
                 }
             }
@@ -752,6 +903,12 @@ impl SpriteManager {
             self.trigger_animation_event(sprite_id, &event);
         }
         
+        for finished in finished_animations {
+            if let Err(e) = EventSystem::queue(finished) {
+                warn!("发送动画结束事件失败: {}", e);
+            }
+        }
+        
         Ok(())
     }
     
@@ -1130,6 +1287,59 @@ mod tests {
         assert_eq!(animation.total_duration, 0.2);
     }
     
+    fn frames(count: usize, duration: f32) -> Vec<AnimationFrame> {
+        (0..count)
+            .map(|i| AnimationFrame {
+                texture_region: TextureRegion { u: i as f32 * 0.25, v: 0.0, width: 0.25, height: 1.0 },
+                duration,
+                offset: Vec2::ZERO,
+                color_tint: Vec4::ONE,
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_animation_loop_wraparound() {
+        let mut animation = SpriteAnimation::new(1, "walk".to_string(), frames(3, 0.1), AnimationLoopMode::Loop);
+        
+        assert!(animation.update(0.25).is_none());
+        assert_eq!(animation.current_frame_index(), 2);
+        
+        // 最后一帧之后回到第一帧
+        assert!(animation.update(0.1).is_none());
+        assert_eq!(animation.current_frame_index(), 0);
+        assert_eq!(animation.current_region().unwrap().uv_rect(), Vec4::new(0.0, 0.0, 0.25, 1.0));
+        assert!(!animation.is_finished());
+    }
+    
+    #[test]
+    fn test_animation_ping_pong_reverses() {
+        let mut animation = SpriteAnimation::new(1, "idle".to_string(), frames(3, 0.1), AnimationLoopMode::PingPong);
+        
+        let mut visited = vec![animation.current_frame_index()];
+        for _ in 0..6 {
+            animation.update(0.1);
+            visited.push(animation.current_frame_index());
+        }
+        
+        assert_eq!(visited, vec![0, 1, 2, 1, 0, 1, 2]);
+    }
+    
+    #[test]
+    fn test_animation_once_finishes() {
+        let mut animation = SpriteAnimation::new(7, "faint".to_string(), frames(2, 0.1), AnimationLoopMode::None);
+        
+        assert!(animation.update(0.15).is_none());
+        let finished = animation.update(0.1).unwrap();
+        assert_eq!(finished.animation_id, 7);
+        assert_eq!(finished.animation_name, "faint");
+        
+        // 停在最后一帧，不再重复发送结束事件
+        assert!(animation.is_finished());
+        assert_eq!(animation.current_frame_index(), 1);
+        assert!(animation.update(1.0).is_none());
+    }
+    
     #[test]
     fn test_texture_atlas() {
         let mut manager = SpriteManager::new();
@@ -158,7 +158,7 @@ pub struct TextureData {
 }
 
 // 纹理对象
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Texture {
     pub id: TextureId,
     pub name: String,
@@ -281,6 +281,160 @@ pub struct AtlasRect {
     pub height: u32,
 }
 
+// 图集中相邻纹理之间的间隔，避免线性过滤时采样到相邻纹理
+const ATLAS_PADDING: u32 = 1;
+
+impl AtlasRegion {
+    fn from_rect(rect: &AtlasRect, atlas_width: u32, atlas_height: u32) -> Self {
+        Self {
+            x: rect.x,
+            y: rect.y,
+            width: rect.width,
+            height: rect.height,
+            u1: rect.x as f32 / atlas_width as f32,
+            v1: rect.y as f32 / atlas_height as f32,
+            u2: (rect.x + rect.width) as f32 / atlas_width as f32,
+            v2: (rect.y + rect.height) as f32 / atlas_height as f32,
+        }
+    }
+    
+    // UV矩形 (u1, v1, u2, v2)，与Sprite::uv_rect一致
+    pub fn uv_rect(&self) -> glam::Vec4 {
+        glam::Vec4::new(self.u1, self.v1, self.u2, self.v2)
+    }
+}
+
+impl AtlasRect {
+    pub fn overlaps(&self, other: &AtlasRect) -> bool {
+        self.x < other.x + other.width && other.x < self.x + self.width &&
+        self.y < other.y + other.height && other.y < self.y + self.height
+    }
+}
+
+impl TextureAtlas {
+    // 将多张RGBA8纹理打包到一张图集中，返回图集像素数据和每张纹理的区域
+    pub fn pack(textures: &[(String, TextureData)], max_size: u32) -> Result<(TextureData, HashMap<String, AtlasRegion>)> {
+        for (id, texture) in textures {
+            if texture.format != TextureFormat::RGBA8 {
+                return Err(GameError::TextureError(format!("图集只支持RGBA8纹理: {} ({:?})", id, texture.format)));
+            }
+            // 像素数据必须和宽高一致，否则下面逐行拷贝会越界
+            let expected = texture.width as u64 * texture.height as u64 * 4;
+            if texture.data.len() as u64 != expected {
+                return Err(GameError::TextureError(format!(
+                    "纹理数据长度与尺寸不符: {} ({} 字节, {}x{} 需要 {} 字节)",
+                    id, texture.data.len(), texture.width, texture.height, expected
+                )));
+            }
+        }
+        
+        let sizes: Vec<(u32, u32)> = textures.iter()
+            .map(|(_, texture)| (texture.width, texture.height))
+            .collect();
+        let (width, height, rects) = pack_rects(&sizes, max_size)
+            .ok_or_else(|| GameError::TextureError(format!("纹理无法装入 {}x{} 的图集", max_size, max_size)))?;
+        
+        // 逐行拷贝像素到图集
+        let mut data = vec![0u8; (width * height * 4) as usize];
+        for ((_, texture), rect) in textures.iter().zip(&rects) {
+            let row_bytes = (texture.width * 4) as usize;
+            for row in 0..texture.height {
+                let src = (row * texture.width * 4) as usize;
+                let dst = (((rect.y + row) * width + rect.x) * 4) as usize;
+                data[dst..dst + row_bytes].copy_from_slice(&texture.data[src..src + row_bytes]);
+            }
+        }
+        
+        let regions = textures.iter()
+            .zip(&rects)
+            .map(|((id, _), rect)| (id.clone(), AtlasRegion::from_rect(rect, width, height)))
+            .collect();
+        
+        debug!("打包纹理图集: {} 张纹理 -> {}x{}", textures.len(), width, height);
+        
+        Ok((TextureData {
+            data,
+            width,
+            height,
+            format: TextureFormat::RGBA8,
+            mip_level: 0,
+            array_layer: 0,
+        }, regions))
+    }
+    
+    pub fn get_region(&self, name: &str) -> Option<&AtlasRegion> {
+        self.regions.get(name)
+    }
+}
+
+// 矩形装箱（Guillotine算法，最短边优先）
+// 从能容纳总面积的最小2的幂尺寸开始尝试，放不下时交替扩大宽高，直到max_size
+pub fn pack_rects(sizes: &[(u32, u32)], max_size: u32) -> Option<(u32, u32, Vec<AtlasRect>)> {
+    if sizes.iter().any(|&(w, h)| w + ATLAS_PADDING > max_size || h + ATLAS_PADDING > max_size) {
+        return None;
+    }
+    
+    let total_area: u64 = sizes.iter()
+        .map(|&(w, h)| (w + ATLAS_PADDING) as u64 * (h + ATLAS_PADDING) as u64)
+        .sum();
+    let mut side = 1u32;
+    while (side as u64 * side as u64) < total_area && side < max_size {
+        side *= 2;
+    }
+    
+    let (mut width, mut height) = (side.min(max_size), side.min(max_size));
+    loop {
+        if let Some(rects) = try_pack(sizes, width, height) {
+            return Some((width, height, rects));
+        }
+        
+        if width <= height && width < max_size {
+            width = (width * 2).min(max_size);
+        } else if height < max_size {
+            height = (height * 2).min(max_size);
+        } else {
+            return None;
+        }
+    }
+}
+
+fn try_pack(sizes: &[(u32, u32)], width: u32, height: u32) -> Option<Vec<AtlasRect>> {
+    // 先放大的，减少碎片
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by(|&a, &b| sizes[b].1.cmp(&sizes[a].1).then(sizes[b].0.cmp(&sizes[a].0)));
+    
+    let mut free_space = vec![AtlasRect { x: 0, y: 0, width, height }];
+    let mut placed = vec![AtlasRect { x: 0, y: 0, width: 0, height: 0 }; sizes.len()];
+    
+    for index in order {
+        let (w, h) = sizes[index];
+        let (padded_w, padded_h) = (w + ATLAS_PADDING, h + ATLAS_PADDING);
+        
+        let best = free_space.iter()
+            .enumerate()
+            .filter(|(_, free)| free.width >= padded_w && free.height >= padded_h)
+            .min_by_key(|(_, free)| (free.width - padded_w).min(free.height - padded_h))
+            .map(|(i, _)| i)?;
+        let free = free_space.swap_remove(best);
+        
+        placed[index] = AtlasRect { x: free.x, y: free.y, width: w, height: h };
+        
+        // 沿较短的剩余边切分，剩余两块互不重叠
+        let right_w = free.width - padded_w;
+        let bottom_h = free.height - padded_h;
+        let (right, bottom) = if right_w < bottom_h {
+            (AtlasRect { x: free.x + padded_w, y: free.y, width: right_w, height: padded_h },
+             AtlasRect { x: free.x, y: free.y + padded_h, width: free.width, height: bottom_h })
+        } else {
+            (AtlasRect { x: free.x + padded_w, y: free.y, width: right_w, height: free.height },
+             AtlasRect { x: free.x, y: free.y + padded_h, width: padded_w, height: bottom_h })
+        };
+        free_space.extend([right, bottom].into_iter().filter(|rect| rect.width > 0 && rect.height > 0));
+    }
+    
+    Some(placed)
+}
+
 impl TextureManager {
     pub fn new() -> Self {
         Self {
@@ -445,6 +599,28 @@ impl TextureManager {
         Ok(texture_id)
     }
     
+    // 打包纹理并创建图集，共享图集的精灵可以合并为一次绘制
+    pub fn create_atlas(&mut self, name: &str, textures: &[(String, TextureData)], max_size: u32) -> Result<TextureId> {
+        let (atlas_data, regions) = TextureAtlas::pack(textures, max_size)?;
+        let (width, height) = (atlas_data.width, atlas_data.height);
+        let texture_id = self.create_texture_from_texture_data(name, atlas_data, None)?;
+        
+        self.texture_atlas = Some(TextureAtlas {
+            texture_id,
+            regions,
+            width,
+            height,
+            free_space: Vec::new(),
+        });
+        
+        Ok(texture_id)
+    }
+    
+    // 获取当前图集
+    pub fn get_atlas(&self) -> Option<&TextureAtlas> {
+        self.texture_atlas.as_ref()
+    }
+    
     // 获取纹理
     pub fn get_texture(&self, texture_id: TextureId) -> Option<&Texture> {
         self.textures.get(&texture_id)
@@ -871,6 +1047,73 @@ mod tests {
         assert_eq!(texture_data.format, TextureFormat::RGBA8);
     }
     
+    #[test]
+    fn test_pack_rects_without_overlap() {
+        let sizes: Vec<(u32, u32)> = (0..40)
+            .map(|i| (8 + (i * 7) % 41, 8 + (i * 13) % 29))
+            .collect();
+        
+        let (width, height, rects) = pack_rects(&sizes, 512).unwrap();
+        assert!(width <= 512 && height <= 512);
+        assert_eq!(rects.len(), sizes.len());
+        
+        for (i, rect) in rects.iter().enumerate() {
+            assert_eq!((rect.width, rect.height), sizes[i]);
+            assert!(rect.x + rect.width <= width && rect.y + rect.height <= height);
+            for other in &rects[i + 1..] {
+                assert!(!rect.overlaps(other), "{:?} 与 {:?} 重叠", rect, other);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_pack_rects_respects_max_size() {
+        assert!(pack_rects(&[(300, 300)], 256).is_none());
+        assert!(pack_rects(&vec![(100, 100); 10], 256).is_none());
+        
+        let (width, height, _) = pack_rects(&vec![(60, 60); 16], 256).unwrap();
+        assert!(width <= 256 && height <= 256);
+    }
+    
+    #[test]
+    fn test_texture_atlas_pack_copies_pixels() {
+        let red = TextureData {
+            data: [255, 0, 0, 255].repeat(4 * 4),
+            width: 4,
+            height: 4,
+            format: TextureFormat::RGBA8,
+            mip_level: 0,
+            array_layer: 0,
+        };
+        let checker = create_checker_texture(8, 2);
+        
+        let (atlas, regions) = TextureAtlas::pack(
+            &[("red".to_string(), red), ("checker".to_string(), checker)],
+            64,
+        ).unwrap();
+        
+        let region = &regions["red"];
+        let offset = ((region.y * atlas.width + region.x) * 4) as usize;
+        assert_eq!(&atlas.data[offset..offset + 4], &[255, 0, 0, 255]);
+        assert!(region.u2 > region.u1 && region.v2 > region.v1);
+        assert_eq!(region.uv_rect().z, region.u2);
+    }
+    
+    #[test]
+    fn test_texture_atlas_pack_rejects_short_data() {
+        let truncated = TextureData {
+            data: vec![255; 4 * 4 * 4 - 1],
+            width: 4,
+            height: 4,
+            format: TextureFormat::RGBA8,
+            mip_level: 0,
+            array_layer: 0,
+        };
+        
+        let result = TextureAtlas::pack(&[("truncated".to_string(), truncated)], 64);
+        assert!(matches!(result, Err(GameError::TextureError(_))));
+    }
+    
     #[test]
     fn test_texture_format_compression() {
         let texture = Texture::new(1, "test".to_string(), TextureDesc {
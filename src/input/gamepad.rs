@@ -99,6 +99,31 @@ pub struct VibrationEffect {
     pub duration_ms: u32,   // 持续时间
 }
 
+// 力反馈状态 - 强度随时间线性衰减到0
+#[derive(Debug, Clone, Copy)]
+pub struct RumbleState {
+    pub low_freq: f32,      // 低频马达初始强度 (0.0-1.0)
+    pub high_freq: f32,     // 高频马达初始强度 (0.0-1.0)
+    pub duration: f32,      // 持续时间(秒)
+    pub elapsed: f32,
+}
+
+impl RumbleState {
+    // 当前强度 (低频, 高频)
+    pub fn intensity(&self) -> (f32, f32) {
+        if self.duration <= 0.0 || self.elapsed >= self.duration {
+            return (0.0, 0.0);
+        }
+        
+        let remaining = 1.0 - self.elapsed / self.duration;
+        (self.low_freq * remaining, self.high_freq * remaining)
+    }
+    
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
 // 手柄状态
 #[derive(Debug, Clone)]
 pub struct GamepadState {
@@ -118,6 +143,7 @@ pub struct GamepadState {
     
     // 振动状态
     pub vibration: Option<VibrationEffect>,
+    pub rumble: Option<RumbleState>,
     
     // 配置
     pub dead_zone: f32,
@@ -216,6 +242,16 @@ impl GamepadManager {
                     }
                 }
             }
+            
+            // 力反馈自动衰减
+            if let Some(rumble) = &mut gamepad.rumble {
+                rumble.elapsed += delta_time;
+                
+                if rumble.is_finished() {
+                    gamepad.rumble = None;
+                    debug!("力反馈结束: ID={}", gamepad.id);
+                }
+            }
         }
         
         // 应用轴过滤
@@ -241,6 +277,7 @@ impl GamepadManager {
             axes: HashMap::new(),
             axes_raw: HashMap::new(),
             vibration: None,
+            rumble: None,
             dead_zone: self.default_dead_zone,
             trigger_threshold: self.default_trigger_threshold,
         };
@@ -358,10 +395,43 @@ impl GamepadManager {
         }
     }
     
+    // 设置力反馈，强度在持续时间内衰减到0；不支持振动的手柄静默忽略
+    pub fn set_rumble(&mut self, gamepad_id: u32, low_freq: f32, high_freq: f32, duration: std::time::Duration) -> Result<(), String> {
+        let enable_vibration = self.enable_vibration;
+        let gamepad = self.gamepads.get_mut(&gamepad_id)
+            .ok_or_else(|| format!("手柄不存在: {}", gamepad_id))?;
+        
+        if !enable_vibration || !gamepad.gamepad_type.supports_rumble() {
+            debug!("手柄不支持或已禁用力反馈，忽略: ID={} 类型={:?}", gamepad_id, gamepad.gamepad_type);
+            return Ok(());
+        }
+        
+        gamepad.rumble = Some(RumbleState {
+            low_freq: low_freq.clamp(0.0, 1.0),
+            high_freq: high_freq.clamp(0.0, 1.0),
+            duration: duration.as_secs_f32(),
+            elapsed: 0.0,
+        });
+        
+        // TODO: 调用实际的力反馈API
+        debug!("设置力反馈: ID={} 低频={:.2} 高频={:.2} 时长={:?}", 
+               gamepad_id, low_freq, high_freq, duration);
+        Ok(())
+    }
+    
+    // 获取当前力反馈强度 (低频, 高频)
+    pub fn get_rumble_intensity(&self, gamepad_id: u32) -> (f32, f32) {
+        self.gamepads.get(&gamepad_id)
+            .and_then(|g| g.rumble.as_ref())
+            .map(|rumble| rumble.intensity())
+            .unwrap_or((0.0, 0.0))
+    }
+    
     // 停止振动
     pub fn stop_vibration(&mut self, gamepad_id: u32) -> Result<(), String> {
         if let Some(gamepad) = self.gamepads.get_mut(&gamepad_id) {
             gamepad.vibration = None;
+            gamepad.rumble = None;
             // TODO: 停止实际的振动
             debug!("停止振动: ID={}", gamepad_id);
             Ok(())
@@ -380,6 +450,11 @@ impl GamepadManager {
         self.gamepads.values().filter(|g| g.connected).collect()
     }
     
+    // 主手柄：ID最小的已连接手柄，绑定未指定手柄时使用
+    pub fn primary_gamepad(&self) -> Option<u32> {
+        self.gamepads.values().filter(|g| g.connected).map(|g| g.id).min()
+    }
+    
     // 检查按键状态
     pub fn is_button_pressed(&self, gamepad_id: u32, button: &GamepadButton) -> bool {
         self.gamepads.get(&gamepad_id)
@@ -429,6 +504,7 @@ impl GamepadManager {
             // 停止所有振动
             for gamepad in self.gamepads.values_mut() {
                 gamepad.vibration = None;
+                gamepad.rumble = None;
             }
         }
     }
@@ -529,6 +605,11 @@ pub enum StickType {
 
 // 便利方法
 impl GamepadType {
+    // 通用/未知手柄没有可靠的力反馈接口
+    pub fn supports_rumble(&self) -> bool {
+        !matches!(self, GamepadType::Generic | GamepadType::Unknown)
+    }
+    
     pub fn from_name(name: &str) -> Self {
         let name_lower = name.to_lowercase();
         if name_lower.contains("xbox") {
@@ -622,4 +703,32 @@ mod tests {
         assert!(vector.x > 0.0);
         assert!(vector.y > 0.0);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_rumble_decays_to_zero() {
+        let mut manager = GamepadManager::new();
+        manager.add_gamepad(0, "Xbox One".to_string(), GamepadType::XboxOne);
+        
+        manager.set_rumble(0, 1.0, 0.5, std::time::Duration::from_millis(500)).unwrap();
+        assert_eq!(manager.get_rumble_intensity(0), (1.0, 0.5));
+        
+        manager.update(0.25);
+        let (low, high) = manager.get_rumble_intensity(0);
+        assert!((low - 0.5).abs() < 0.001);
+        assert!((high - 0.25).abs() < 0.001);
+        
+        manager.update(0.3);
+        assert_eq!(manager.get_rumble_intensity(0), (0.0, 0.0));
+        assert!(manager.get_gamepad(0).unwrap().rumble.is_none());
+    }
+    
+    #[test]
+    fn test_rumble_unsupported_pad_is_noop() {
+        let mut manager = GamepadManager::new();
+        manager.add_gamepad(0, "Generic".to_string(), GamepadType::Generic);
+        
+        assert!(manager.set_rumble(0, 1.0, 1.0, std::time::Duration::from_secs(1)).is_ok());
+        assert_eq!(manager.get_rumble_intensity(0), (0.0, 0.0));
+        assert!(manager.set_rumble(7, 1.0, 1.0, std::time::Duration::from_secs(1)).is_err());
+    }
+}
@@ -6,17 +6,23 @@ pub mod keyboard;
 pub mod mouse;
 pub mod gamepad;
 pub mod touch;
+pub mod replay;
 
 pub use keyboard::{KeyboardManager, KeyCode, KeyState};
-pub use mouse::{MouseManager, MouseButton, MouseState};
+pub use mouse::{MouseManager, MouseButton, MouseState, MouseGesture};
 pub use gamepad::{GamepadManager, GamepadButton, GamepadAxis, GamepadId};
-pub use touch::{TouchManager, TouchEvent, TouchPhase, TouchId};
+pub use touch::{TouchManager, TouchEvent, TouchPhase, TouchId, TouchGesture, TouchGestureThresholds, SwipeDirection};
+pub use replay::{InputRecording, RecordedInputEvent, InputRecorder, InputPlayback};
 
 use crate::core::{GameError, Result};
 use crate::core::event_system::{Event, EventSystem};
+use crate::core::services::{ServiceGuard, Services};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use log::{debug, warn};
+use std::fs;
+use std::path::Path;
+use std::sync::MutexGuard;
+use log::{debug, info, warn};
 
 // 输入事件类型
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -104,7 +110,7 @@ pub enum InputAction {
 }
 
 // 输入绑定
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum InputBinding {
     Key(KeyCode),
     MouseButton(MouseButton),
@@ -113,16 +119,81 @@ pub enum InputBinding {
     Combination(Vec<InputBinding>), // 组合键
 }
 
+impl InputBinding {
+    // 组合键展开为单个按键，普通绑定返回自身
+    fn parts(&self) -> Vec<&InputBinding> {
+        match self {
+            InputBinding::Combination(bindings) => bindings.iter().flat_map(|b| b.parts()).collect(),
+            binding => vec![binding],
+        }
+    }
+    
+    // 两个绑定是否会被同一次输入触发：完全相同、组合键按键集合相同，
+    // 或单键是另一个组合键的一部分（按下组合键时单键动作也会触发）
+    pub fn overlaps(&self, other: &InputBinding) -> bool {
+        let self_parts = self.parts();
+        let other_parts = other.parts();
+        
+        let contains_all = |outer: &[&InputBinding], inner: &[&InputBinding]| {
+            inner.iter().all(|part| outer.contains(part))
+        };
+        
+        match (self, other) {
+            (InputBinding::Combination(_), InputBinding::Combination(_)) => {
+                contains_all(&self_parts, &other_parts) && contains_all(&other_parts, &self_parts)
+            },
+            _ => contains_all(&self_parts, &other_parts) || contains_all(&other_parts, &self_parts),
+        }
+    }
+    
+    // 同一设备类型的绑定占用同一个槽位
+    fn same_slot(&self, other: &InputBinding) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+// 摇杆死区模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DeadzoneMode {
+    Axial,  // 每个轴单独判断，斜向推动时容易吸附到坐标轴
+    #[default]
+    Radial, // 按X/Y合成幅度判断，并在内外死区之间重新映射
+}
+
+// 对摇杆向量应用死区，返回处理后的向量
+pub fn apply_stick_deadzone(stick: glam::Vec2, mode: DeadzoneMode, inner: f32, outer: f32) -> glam::Vec2 {
+    match mode {
+        DeadzoneMode::Axial => {
+            let axis = |value: f32| if value.abs() < inner { 0.0 } else { value };
+            glam::Vec2::new(axis(stick.x), axis(stick.y))
+        },
+        DeadzoneMode::Radial => {
+            let magnitude = stick.length();
+            if magnitude < inner || magnitude <= f32::EPSILON {
+                return glam::Vec2::ZERO;
+            }
+            
+            // 内死区映射到0，外死区及以外饱和为1
+            let range = (outer - inner).max(f32::EPSILON);
+            let scaled = ((magnitude - inner) / range).min(1.0);
+            stick / magnitude * scaled
+        }
+    }
+}
+
 // 输入配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InputConfig {
     pub bindings: HashMap<InputAction, Vec<InputBinding>>,
     pub mouse_sensitivity: f32,
     pub gamepad_deadzone: f32,
+    pub gamepad_outer_deadzone: f32,
+    pub deadzone_mode: DeadzoneMode,
     pub enable_mouse_acceleration: bool,
     pub enable_key_repeat: bool,
     pub double_click_time: f32,
     pub long_press_time: f32,
+    pub touch_gestures: TouchGestureThresholds,
 }
 
 impl Default for InputConfig {
@@ -193,11 +264,161 @@ impl Default for InputConfig {
             bindings,
             mouse_sensitivity: 1.0,
             gamepad_deadzone: 0.15,
+            gamepad_outer_deadzone: 0.95,
+            deadzone_mode: DeadzoneMode::Radial,
             enable_mouse_acceleration: false,
             enable_key_repeat: true,
             double_click_time: 0.3,
             long_press_time: 0.8,
+            touch_gestures: TouchGestureThresholds::default(),
+        }
+    }
+}
+
+// 必须保留至少一个绑定的动作
+pub const ESSENTIAL_ACTIONS: [InputAction; 6] = [
+    InputAction::Confirm,
+    InputAction::Cancel,
+    InputAction::MoveUp,
+    InputAction::MoveDown,
+    InputAction::MoveLeft,
+    InputAction::MoveRight,
+];
+
+// 键位配置文件格式 - TOML的表键只能是字符串，绑定以列表形式保存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct InputConfigFile {
+    mouse_sensitivity: f32,
+    gamepad_deadzone: f32,
+    #[serde(default = "default_outer_deadzone")]
+    gamepad_outer_deadzone: f32,
+    #[serde(default)]
+    deadzone_mode: DeadzoneMode,
+    enable_mouse_acceleration: bool,
+    enable_key_repeat: bool,
+    double_click_time: f32,
+    long_press_time: f32,
+    #[serde(default)]
+    touch_gestures: TouchGestureThresholds,
+    bindings: Vec<ActionBindings>,
+}
+
+fn default_outer_deadzone() -> f32 {
+    0.95
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ActionBindings {
+    action: InputAction,
+    bindings: Vec<InputBinding>,
+}
+
+impl InputConfig {
+    // 从TOML文件加载键位，文件缺失或损坏时回退到默认配置
+    pub fn load_from(path: &Path) -> Self {
+        if !path.exists() {
+            info!("键位配置文件不存在，使用默认配置: {:?}", path);
+            return Self::default();
+        }
+        
+        match Self::try_load_from(path) {
+            Ok(config) => {
+                info!("成功加载键位配置: {:?}", path);
+                config
+            },
+            Err(e) => {
+                warn!("键位配置文件无效，使用默认配置: {:?} ({})", path, e);
+                Self::default()
+            }
+        }
+    }
+    
+    fn try_load_from(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let file: InputConfigFile = toml::from_str(&content)?;
+        
+        let config = Self {
+            bindings: file.bindings
+                .into_iter()
+                .map(|entry| (entry.action, entry.bindings))
+                .collect(),
+            mouse_sensitivity: file.mouse_sensitivity,
+            gamepad_deadzone: file.gamepad_deadzone,
+            gamepad_outer_deadzone: file.gamepad_outer_deadzone,
+            deadzone_mode: file.deadzone_mode,
+            enable_mouse_acceleration: file.enable_mouse_acceleration,
+            enable_key_repeat: file.enable_key_repeat,
+            double_click_time: file.double_click_time,
+            long_press_time: file.long_press_time,
+            touch_gestures: file.touch_gestures,
+        };
+        
+        config.validate()?;
+        Ok(config)
+    }
+    
+    // 保存键位到TOML文件
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        self.validate()?;
+        
+        let mut bindings: Vec<ActionBindings> = self.bindings
+            .iter()
+            .map(|(action, bindings)| ActionBindings {
+                action: action.clone(),
+                bindings: bindings.clone(),
+            })
+            .collect();
+        // 固定顺序，便于玩家手动编辑和版本对比
+        bindings.sort_by_key(|entry| format!("{:?}", entry.action));
+        
+        let file = InputConfigFile {
+            mouse_sensitivity: self.mouse_sensitivity,
+            gamepad_deadzone: self.gamepad_deadzone,
+            gamepad_outer_deadzone: self.gamepad_outer_deadzone,
+            deadzone_mode: self.deadzone_mode,
+            enable_mouse_acceleration: self.enable_mouse_acceleration,
+            enable_key_repeat: self.enable_key_repeat,
+            double_click_time: self.double_click_time,
+            long_press_time: self.long_press_time,
+            touch_gestures: self.touch_gestures,
+            bindings,
+        };
+        
+        let content = toml::to_string_pretty(&file).map_err(|e| {
+            GameError::ConfigError(format!("序列化键位配置失败: {}", e))
+        })?;
+        
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
         }
+        
+        fs::write(path, content)?;
+        debug!("键位配置已保存到: {:?}", path);
+        Ok(())
+    }
+    
+    // 检查必要动作都至少有一个绑定
+    pub fn validate(&self) -> Result<()> {
+        for action in ESSENTIAL_ACTIONS.iter() {
+            let bound = self.bindings
+                .get(action)
+                .map(|bindings| !bindings.is_empty())
+                .unwrap_or(false);
+            
+            if !bound {
+                return Err(GameError::ConfigError(format!("必要动作未绑定按键: {:?}", action)));
+            }
+        }
+        
+        if !(0.0..1.0).contains(&self.gamepad_deadzone) {
+            return Err(GameError::ConfigError("手柄死区必须在0.0-1.0之间".to_string()));
+        }
+        
+        if self.gamepad_outer_deadzone <= self.gamepad_deadzone || self.gamepad_outer_deadzone > 1.0 {
+            return Err(GameError::ConfigError("手柄外死区必须大于内死区且不超过1.0".to_string()));
+        }
+        
+        Ok(())
     }
 }
 
@@ -273,6 +494,10 @@ pub struct InputManager {
     // 输入锁定（用于UI等场景）
     input_locked: bool,
     locked_actions: std::collections::HashSet<InputAction>,
+    
+    // 输入录制与回放
+    recorder: Option<InputRecorder>,
+    playback: Option<InputPlayback>,
 }
 
 impl InputManager {
@@ -290,6 +515,8 @@ impl InputManager {
             buffer_duration: 1.0, // 1秒缓冲
             input_locked: false,
             locked_actions: std::collections::HashSet::new(),
+            recorder: None,
+            playback: None,
         })
     }
     
@@ -297,6 +524,19 @@ impl InputManager {
     pub fn update(&mut self, delta_time: f32) -> Result<()> {
         self.delta_time = delta_time;
         
+        // 回放：在录制时的同一帧注入事件
+        if let Some(playback) = self.playback.as_mut() {
+            let events = playback.next_frame_events();
+            let finished = playback.is_finished();
+            for event in &events {
+                self.handle_event(event)?;
+            }
+            if finished {
+                info!("输入回放结束");
+                self.playback = None;
+            }
+        }
+        
         // 更新各个输入设备
         self.keyboard.update(delta_time);
         self.mouse.update(delta_time);
@@ -315,11 +555,49 @@ impl InputManager {
         // 更新输入缓冲区
         self.update_input_buffer(delta_time);
         
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.advance_frame(delta_time);
+        }
+        
         Ok(())
     }
     
+    // 开始录制输入事件，stop_recording时写入文件
+    pub fn start_recording(&mut self, path: &Path) {
+        if self.recorder.is_some() {
+            warn!("已有输入录制在进行，将被新的录制替换");
+        }
+        self.recorder = Some(InputRecorder::new(path));
+    }
+    
+    pub fn stop_recording(&mut self) -> Result<InputRecording> {
+        let recorder = self.recorder.take()
+            .ok_or_else(|| GameError::SystemError("当前没有进行中的输入录制".to_string()))?;
+        recorder.finish()
+    }
+    
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+    
+    // 加载录制文件，从下一次update开始按帧注入事件
+    pub fn play_recording(&mut self, path: &Path) -> Result<()> {
+        let recording = InputRecording::load(path)?;
+        info!("开始回放输入: {:?} ({} 个事件)", path, recording.events.len());
+        self.playback = Some(InputPlayback::new(recording));
+        Ok(())
+    }
+    
+    pub fn is_playing_recording(&self) -> bool {
+        self.playback.is_some()
+    }
+    
     // 处理输入事件
     pub fn handle_event(&mut self, event: &InputEvent) -> Result<()> {
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(event);
+        }
+        
         match event {
             InputEvent::KeyPressed { key, repeat } => {
                 self.keyboard.handle_key_pressed(*key, *repeat);
@@ -427,9 +705,16 @@ impl InputManager {
     
     // 配置管理
     pub fn set_config(&mut self, config: InputConfig) {
+        self.mouse.set_double_click_time(config.double_click_time);
+        self.touch.set_gesture_thresholds(&config.touch_gestures);
         self.config = config;
     }
     
+    // 本帧识别出的触摸手势
+    pub fn get_touch_gestures(&self) -> &[TouchGesture] {
+        self.touch.get_frame_gestures()
+    }
+    
     pub fn get_config(&self) -> &InputConfig {
         &self.config
     }
@@ -438,6 +723,43 @@ impl InputManager {
         &mut self.config
     }
     
+    // 运行时改键：替换动作中同类型设备的绑定，返回与新绑定冲突的其他动作
+    pub fn rebind(&mut self, action: InputAction, binding: InputBinding) -> Vec<InputAction> {
+        let bindings = self.config.bindings.entry(action.clone()).or_default();
+        
+        match bindings.iter().position(|existing| existing.same_slot(&binding)) {
+            Some(index) => bindings[index] = binding.clone(),
+            None => bindings.push(binding.clone()),
+        }
+        
+        // 清除旧绑定残留的按压状态
+        self.current_state.action_states.remove(&action);
+        self.current_state.action_press_duration.remove(&action);
+        
+        let conflicts: Vec<InputAction> = self.conflicts_for(&binding)
+            .into_iter()
+            .filter(|other| *other != action)
+            .collect();
+        
+        if !conflicts.is_empty() {
+            warn!("按键绑定冲突: {:?} 与 {:?} 共用 {:?}", action, conflicts, binding);
+        }
+        
+        conflicts
+    }
+    
+    // 查找使用了该绑定（含组合键重叠）的所有动作
+    pub fn conflicts_for(&self, binding: &InputBinding) -> Vec<InputAction> {
+        let mut actions: Vec<InputAction> = self.config.bindings
+            .iter()
+            .filter(|(_, bindings)| bindings.iter().any(|existing| existing.overlaps(binding)))
+            .map(|(action, _)| action.clone())
+            .collect();
+        
+        actions.sort_by_key(|action| format!("{:?}", action));
+        actions
+    }
+    
     // 输入锁定
     pub fn lock_input(&mut self) {
         self.input_locked = true;
@@ -475,6 +797,46 @@ impl InputManager {
         self.input_buffer.clear();
     }
     
+    // 缓冲区保留时长，长序列（如秘籍）需要更长的保留时间
+    pub fn set_buffer_duration(&mut self, seconds: f32) {
+        self.buffer_duration = seconds.max(0.0);
+    }
+    
+    // 检查缓冲区在时间窗口内是否连续按顺序包含该动作序列
+    pub fn match_sequence(&self, sequence: &[InputAction], window: f32) -> bool {
+        self.find_sequence(sequence, window, false)
+    }
+    
+    // 同上，但允许序列中间夹杂其他动作
+    pub fn match_sequence_with_noise(&self, sequence: &[InputAction], window: f32) -> bool {
+        self.find_sequence(sequence, window, true)
+    }
+    
+    fn find_sequence(&self, sequence: &[InputAction], window: f32, allow_noise: bool) -> bool {
+        if sequence.is_empty() {
+            return true;
+        }
+        
+        // 缓冲区按时间先后排列
+        let recent: Vec<&InputAction> = self.input_buffer.iter()
+            .filter(|(_, age)| *age <= window)
+            .map(|(action, _)| action)
+            .collect();
+        
+        if allow_noise {
+            let mut expected = sequence.iter().peekable();
+            for action in recent {
+                if expected.peek() == Some(&action) {
+                    expected.next();
+                }
+            }
+            expected.peek().is_none()
+        } else {
+            recent.windows(sequence.len())
+                .any(|run| run.iter().zip(sequence).all(|(a, b)| *a == b))
+        }
+    }
+    
     // 获取原始设备状态
     pub fn get_keyboard(&self) -> &KeyboardManager {
         &self.keyboard
@@ -547,16 +909,36 @@ impl InputManager {
                 Ok(if self.mouse.is_button_pressed(button) { 1.0 } else { 0.0 })
             },
             InputBinding::GamepadButton { gamepad_id, button } => {
-                Ok(if self.gamepad.is_button_pressed(*gamepad_id, button) { 1.0 } else { 0.0 })
+                let Some(gamepad_id) = self.resolve_gamepad(*gamepad_id) else {
+                    return Ok(0.0);
+                };
+                Ok(if self.gamepad.is_button_pressed(gamepad_id, button) { 1.0 } else { 0.0 })
             },
             InputBinding::GamepadAxis { gamepad_id, axis, threshold } => {
-                let axis_value = self.gamepad.get_axis_value(*gamepad_id, axis);
+                let Some(gamepad_id) = self.resolve_gamepad(*gamepad_id) else {
+                    return Ok(0.0);
+                };
+                let axis_value = self.gamepad.get_axis_value(gamepad_id, axis);
                 
-                // 应用死区
-                let adjusted_value = if axis_value.abs() < self.config.gamepad_deadzone {
-                    0.0
-                } else {
-                    axis_value
+                // 应用死区：摇杆轴与同一摇杆的另一轴合成向量后处理，其他轴按标量处理
+                let adjusted_value = match Self::stick_partner(axis) {
+                    Some((partner, is_x)) => {
+                        let partner_value = self.gamepad.get_axis_value(gamepad_id, &partner);
+                        let stick = if is_x {
+                            glam::Vec2::new(axis_value, partner_value)
+                        } else {
+                            glam::Vec2::new(partner_value, axis_value)
+                        };
+                        let adjusted = apply_stick_deadzone(
+                            stick,
+                            self.config.deadzone_mode,
+                            self.config.gamepad_deadzone,
+                            self.config.gamepad_outer_deadzone,
+                        );
+                        if is_x { adjusted.x } else { adjusted.y }
+                    },
+                    None if axis_value.abs() < self.config.gamepad_deadzone => 0.0,
+                    None => axis_value,
                 };
                 
                 // 检查阈值
@@ -581,6 +963,22 @@ impl InputManager {
         }
     }
     
+    // 绑定未指定手柄时使用主手柄，没有已连接的手柄时返回None
+    fn resolve_gamepad(&self, gamepad_id: Option<GamepadId>) -> Option<GamepadId> {
+        gamepad_id.or_else(|| self.gamepad.primary_gamepad())
+    }
+    
+    // 摇杆轴对应的另一轴，以及当前轴是否为X轴
+    fn stick_partner(axis: &GamepadAxis) -> Option<(GamepadAxis, bool)> {
+        match axis {
+            GamepadAxis::LeftStickX => Some((GamepadAxis::LeftStickY, true)),
+            GamepadAxis::LeftStickY => Some((GamepadAxis::LeftStickX, false)),
+            GamepadAxis::RightStickX => Some((GamepadAxis::RightStickY, true)),
+            GamepadAxis::RightStickY => Some((GamepadAxis::RightStickX, false)),
+            _ => None,
+        }
+    }
+    
     fn update_input_buffer(&mut self, delta_time: f32) {
         // 更新缓冲区中动作的年龄
         for (_, age) in &mut self.input_buffer {
@@ -592,44 +990,45 @@ impl InputManager {
     }
 }
 
-// 便利函数：创建全局输入管理器
-static mut INPUT_MANAGER: Option<InputManager> = None;
-static INPUT_MANAGER_INIT: std::sync::Once = std::sync::Once::new();
-
+// 全局输入管理器
 pub struct Input;
 
+// 全局输入管理器的访问守卫，持有期间独占输入管理器
+pub type InputGuard = ServiceGuard<InputManager>;
+
 impl Input {
+    fn lock() -> Result<MutexGuard<'static, Option<InputManager>>> {
+        Services::exclusive::<InputManager>()
+            .lock()
+            .map_err(|_| GameError::SystemError("输入系统锁已损坏".to_string()))
+    }
+    
     pub fn initialize() -> Result<()> {
-        unsafe {
-            INPUT_MANAGER_INIT.call_once(|| {
-                match InputManager::new() {
-                    Ok(manager) => {
-                        INPUT_MANAGER = Some(manager);
-                    },
-                    Err(e) => {
-                        log::error!("输入系统初始化失败: {}", e);
-                    }
-                }
-            });
-        }
+        let mut manager = Self::lock()?;
         
-        if unsafe { INPUT_MANAGER.is_none() } {
-            return Err(GameError::InitializationFailed("输入系统初始化失败".to_string()));
+        if manager.is_none() {
+            match InputManager::new() {
+                Ok(input_manager) => {
+                    *manager = Some(input_manager);
+                },
+                Err(e) => {
+                    log::error!("输入系统初始化失败: {}", e);
+                    return Err(GameError::InitializationFailed("输入系统初始化失败".to_string()));
+                }
+            }
         }
         
         Ok(())
     }
     
-    pub fn instance() -> Result<&'static mut InputManager> {
-        unsafe {
-            INPUT_MANAGER.as_mut()
-                .ok_or_else(|| GameError::SystemError("输入系统未初始化".to_string()))
-        }
+    pub fn instance() -> Result<InputGuard> {
+        ServiceGuard::new(Self::lock()?)
+            .ok_or_else(|| GameError::SystemError("输入系统未初始化".to_string()))
     }
     
     pub fn cleanup() {
-        unsafe {
-            INPUT_MANAGER = None;
+        if let Ok(mut manager) = Self::lock() {
+            *manager = None;
         }
     }
 }
@@ -659,6 +1058,240 @@ mod tests {
         assert!(state.is_action_just_pressed(&InputAction::Confirm));
     }
     
+    #[test]
+    fn test_input_config_file_round_trip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("input.toml");
+        
+        let mut config = InputConfig::default();
+        config.bindings.insert(InputAction::MoveUp, vec![
+            InputBinding::Key(KeyCode::I),
+            InputBinding::Combination(vec![
+                InputBinding::Key(KeyCode::LeftShift),
+                InputBinding::Key(KeyCode::Up),
+            ]),
+        ]);
+        config.bindings.insert(InputAction::Custom("Bike".to_string()), vec![InputBinding::Key(KeyCode::B)]);
+        config.save_to(&path).unwrap();
+        
+        let loaded = InputConfig::load_from(&path);
+        assert_eq!(loaded.bindings.get(&InputAction::MoveUp), config.bindings.get(&InputAction::MoveUp));
+        assert_eq!(
+            loaded.bindings.get(&InputAction::Custom("Bike".to_string())),
+            Some(&vec![InputBinding::Key(KeyCode::B)])
+        );
+        assert_eq!(loaded.bindings.len(), config.bindings.len());
+    }
+    
+    #[test]
+    fn test_input_config_validation_and_fallback() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("input.toml");
+        
+        let mut config = InputConfig::default();
+        config.bindings.insert(InputAction::Cancel, Vec::new());
+        assert!(config.validate().is_err());
+        assert!(config.save_to(&path).is_err());
+        
+        // 损坏的文件回退到默认配置
+        fs::write(&path, "bindings = [[[").unwrap();
+        let loaded = InputConfig::load_from(&path);
+        assert_eq!(
+            loaded.bindings.get(&InputAction::Cancel),
+            InputConfig::default().bindings.get(&InputAction::Cancel)
+        );
+    }
+    
+    #[test]
+    fn test_rebind_reports_conflicts() {
+        let mut manager = InputManager::new().unwrap();
+        
+        // 默认配置中Space已绑定到Confirm
+        let conflicts = manager.rebind(InputAction::Interact, InputBinding::Key(KeyCode::Space));
+        assert_eq!(conflicts, vec![InputAction::Confirm]);
+        
+        let actions = manager.conflicts_for(&InputBinding::Key(KeyCode::Space));
+        assert!(actions.contains(&InputAction::Confirm));
+        assert!(actions.contains(&InputAction::Interact));
+        
+        // 同类型设备的绑定被替换，手柄绑定保留
+        manager.rebind(InputAction::Cancel, InputBinding::Key(KeyCode::X));
+        let cancel = manager.get_config().bindings.get(&InputAction::Cancel).unwrap();
+        assert_eq!(cancel[0], InputBinding::Key(KeyCode::X));
+        assert_eq!(cancel.len(), 3);
+    }
+    
+    #[test]
+    fn test_combination_binding_conflicts() {
+        let mut manager = InputManager::new().unwrap();
+        let combo = InputBinding::Combination(vec![
+            InputBinding::Key(KeyCode::LeftControl),
+            InputBinding::Key(KeyCode::Space),
+        ]);
+        
+        assert!(manager.rebind(InputAction::DebugCapture, combo.clone()).contains(&InputAction::Confirm));
+        
+        // 按键顺序不同的组合键视为同一绑定
+        let reversed = InputBinding::Combination(vec![
+            InputBinding::Key(KeyCode::Space),
+            InputBinding::Key(KeyCode::LeftControl),
+        ]);
+        assert!(manager.conflicts_for(&reversed).contains(&InputAction::DebugCapture));
+        assert!(!manager.conflicts_for(&InputBinding::Key(KeyCode::LeftControl)).contains(&InputAction::Confirm));
+    }
+    
+    #[test]
+    fn test_match_sequence_in_order() {
+        let mut manager = InputManager::new().unwrap();
+        let sequence = [InputAction::MoveUp, InputAction::MoveUp, InputAction::MoveDown, InputAction::MoveDown];
+        
+        for action in sequence.iter() {
+            manager.add_to_buffer(action.clone());
+            manager.update_input_buffer(0.1);
+        }
+        
+        assert!(manager.match_sequence(&sequence, 1.0));
+        assert!(!manager.match_sequence(
+            &[InputAction::MoveUp, InputAction::MoveDown, InputAction::MoveUp, InputAction::MoveDown],
+            1.0,
+        ));
+        
+        // 超出时间窗口的输入不计入
+        assert!(!manager.match_sequence(&sequence, 0.25));
+    }
+    
+    #[test]
+    fn test_match_sequence_with_noise() {
+        let mut manager = InputManager::new().unwrap();
+        for action in [InputAction::MoveLeft, InputAction::Interact, InputAction::MoveDown, InputAction::MoveRight] {
+            manager.add_to_buffer(action);
+        }
+        
+        let quarter_circle = [InputAction::MoveLeft, InputAction::MoveDown, InputAction::MoveRight];
+        assert!(!manager.match_sequence(&quarter_circle, 1.0));
+        assert!(manager.match_sequence_with_noise(&quarter_circle, 1.0));
+        assert!(!manager.match_sequence_with_noise(
+            &[InputAction::MoveRight, InputAction::MoveLeft],
+            1.0,
+        ));
+    }
+    
+    #[test]
+    fn test_radial_deadzone() {
+        let config = InputConfig::default();
+        assert_eq!(config.deadzone_mode, DeadzoneMode::Radial);
+        let (inner, outer) = (config.gamepad_deadzone, config.gamepad_outer_deadzone);
+        
+        // 合成幅度约0.14，落在内死区内
+        let small = apply_stick_deadzone(glam::Vec2::new(0.1, 0.1), DeadzoneMode::Radial, inner, outer);
+        assert_eq!(small, glam::Vec2::ZERO);
+        
+        // 斜向推动保持方向，幅度在内外死区之间重新映射
+        let diagonal = apply_stick_deadzone(glam::Vec2::new(0.6, 0.6), DeadzoneMode::Radial, inner, outer);
+        let expected = (0.6f32.hypot(0.6) - inner) / (outer - inner);
+        assert!((diagonal.length() - expected).abs() < 1e-5);
+        assert!((diagonal.x - diagonal.y).abs() < 1e-6);
+        
+        // 超出外死区时饱和为1
+        let saturated = apply_stick_deadzone(glam::Vec2::new(0.0, 0.97), DeadzoneMode::Radial, inner, outer);
+        assert!((saturated.y - 1.0).abs() < 1e-6);
+        
+        // 轴向模式只看单轴
+        let axial = apply_stick_deadzone(glam::Vec2::new(0.1, 0.6), DeadzoneMode::Axial, inner, outer);
+        assert_eq!(axial, glam::Vec2::new(0.0, 0.6));
+    }
+    
+    #[test]
+    fn test_unassigned_gamepad_binding_uses_primary_pad() {
+        let mut manager = InputManager::new().unwrap();
+        let binding = InputBinding::GamepadAxis {
+            gamepad_id: None,
+            axis: GamepadAxis::LeftStickX,
+            threshold: 0.5,
+        };
+        
+        // 没有连接手柄时绑定不触发
+        assert_eq!(manager.evaluate_binding(&binding).unwrap(), 0.0);
+        
+        manager.gamepad.add_gamepad(3, "Test".to_string(), gamepad::GamepadType::Generic);
+        manager.gamepad.handle_axis_changed(3, GamepadAxis::LeftStickX, 0.9);
+        assert!(manager.evaluate_binding(&binding).unwrap() > 0.5);
+    }
+    
+    #[test]
+    fn test_recording_playback_reproduces_action_states() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("replay.json");
+        
+        // 每帧之前注入的事件，空列表表示该帧无输入
+        let script = vec![
+            vec![InputEvent::KeyPressed { key: KeyCode::W, repeat: false }],
+            vec![],
+            vec![InputEvent::KeyPressed { key: KeyCode::D, repeat: false }],
+            vec![InputEvent::KeyReleased { key: KeyCode::W }],
+            vec![InputEvent::KeyReleased { key: KeyCode::D }],
+        ];
+        let snapshot = |manager: &InputManager| {
+            (
+                manager.is_action_pressed(&InputAction::MoveUp),
+                manager.is_action_pressed(&InputAction::MoveRight),
+                manager.is_action_just_pressed(&InputAction::MoveRight),
+            )
+        };
+        
+        let mut recorder = InputManager::new().unwrap();
+        recorder.start_recording(&path);
+        let mut recorded_states = Vec::new();
+        for events in &script {
+            for event in events {
+                recorder.handle_event(event).unwrap();
+            }
+            recorder.update(0.016).unwrap();
+            recorded_states.push(snapshot(&recorder));
+        }
+        let recording = recorder.stop_recording().unwrap();
+        assert_eq!(recording.events.len(), 4);
+        assert_eq!(recording.frame_count, script.len() as u64);
+        
+        let mut player = InputManager::new().unwrap();
+        player.play_recording(&path).unwrap();
+        let mut replayed_states = Vec::new();
+        for _ in 0..script.len() {
+            player.update(0.016).unwrap();
+            replayed_states.push(snapshot(&player));
+        }
+        
+        assert_eq!(replayed_states, recorded_states);
+        assert!(recorded_states[0].0 && recorded_states[2].2);
+        assert!(!player.is_playing_recording());
+    }
+    
+    #[test]
+    fn test_global_input_concurrent_queries() {
+        Input::initialize().unwrap();
+        
+        let handles: Vec<_> = (0..2)
+            .map(|i| {
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        let mut input = Input::instance().unwrap();
+                        if i == 0 {
+                            input.handle_event(&InputEvent::KeyPressed { key: KeyCode::W, repeat: false }).unwrap();
+                            input.update(0.016).unwrap();
+                        }
+                        let _ = input.is_action_pressed(&InputAction::MoveUp);
+                    }
+                })
+            })
+            .collect();
+        
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        
+        assert!(Input::instance().unwrap().is_action_pressed(&InputAction::MoveUp));
+    }
+    
     #[test]
     fn test_input_binding_evaluation() {
         // 这里需要实际的设备状态才能测试
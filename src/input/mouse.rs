@@ -3,7 +3,7 @@
 // 设计原则：事件驱动、坐标转换、双击检测、拖拽支持
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use log::debug;
 
 // 鼠标按键定义
@@ -36,6 +36,15 @@ pub struct MouseEvent {
     pub timestamp: std::time::Instant,
 }
 
+// 鼠标手势 - 由按键和移动事件合成，供UI控件使用
+#[derive(Debug, Clone, PartialEq)]
+pub enum MouseGesture {
+    DoubleClick { button: MouseButton, position: glam::Vec2 },
+    DragStarted { button: MouseButton, start: glam::Vec2 },
+    DragMoved { button: MouseButton, start: glam::Vec2, position: glam::Vec2, delta: glam::Vec2 },
+    DragEnded { button: MouseButton, start: glam::Vec2, end: glam::Vec2 },
+}
+
 // 鼠标管理器
 pub struct MouseManager {
     // 按键状态
@@ -57,15 +66,21 @@ pub struct MouseManager {
     enable_acceleration: bool,
     acceleration_factor: f32,
     
-    // 双击检测
+    // 双击检测（以帧时间累计计时，保证回放和测试结果确定）
+    clock: f32,
     double_click_time: f32,
     double_click_distance: f32,
-    last_click_time: HashMap<MouseButton, std::time::Instant>,
+    last_click_time: HashMap<MouseButton, f32>,
     last_click_position: HashMap<MouseButton, glam::Vec2>,
     
     // 拖拽检测
     drag_threshold: f32,
     dragging_buttons: HashMap<MouseButton, glam::Vec2>, // 按键 -> 拖拽起始位置
+    active_drags: HashSet<MouseButton>,                 // 已超过阈值的拖拽
+    
+    // 手势：处理事件时先进入待处理队列，update时转为本帧手势
+    pending_gestures: Vec<MouseGesture>,
+    frame_gestures: Vec<MouseGesture>,
     
     // 事件历史
     recent_events: Vec<MouseEvent>,
@@ -91,12 +106,16 @@ impl MouseManager {
             scroll_sensitivity: 1.0,
             enable_acceleration: false,
             acceleration_factor: 1.5,
+            clock: 0.0,
             double_click_time: 0.3, // 300ms
             double_click_distance: 5.0, // 5像素
             last_click_time: HashMap::new(),
             last_click_position: HashMap::new(),
             drag_threshold: 3.0, // 3像素
             dragging_buttons: HashMap::new(),
+            active_drags: HashSet::new(),
+            pending_gestures: Vec::new(),
+            frame_gestures: Vec::new(),
             recent_events: Vec::new(),
             max_event_history: 100,
             constraint_area: None,
@@ -107,6 +126,11 @@ impl MouseManager {
     
     // 更新鼠标状态（每帧调用）
     pub fn update(&mut self, delta_time: f32) {
+        self.clock += delta_time;
+        
+        // 本帧手势
+        self.frame_gestures = std::mem::take(&mut self.pending_gestures);
+        
         // 保存上一帧位置
         self.previous_position = self.current_position;
         
@@ -146,7 +170,10 @@ impl MouseManager {
         self.button_press_times.insert(button, current_time);
         
         // 检测双击
-        let is_double_click = self.check_double_click(button, position, current_time);
+        let is_double_click = self.check_double_click(button, position);
+        if is_double_click {
+            self.pending_gestures.push(MouseGesture::DoubleClick { button, position });
+        }
         
         // 开始拖拽检测
         if !is_double_click {
@@ -176,7 +203,12 @@ impl MouseManager {
         self.button_press_times.remove(&button);
         
         // 停止拖拽
-        self.dragging_buttons.remove(&button);
+        if let Some(start) = self.dragging_buttons.remove(&button) {
+            if self.active_drags.remove(&button) {
+                debug!("结束拖拽: {:?}", button);
+                self.pending_gestures.push(MouseGesture::DragEnded { button, start, end: position });
+            }
+        }
         
         // 记录事件
         let event = MouseEvent {
@@ -202,7 +234,7 @@ impl MouseManager {
         self.position_delta = delta;
         
         // 更新拖拽状态
-        self.update_drag_state();
+        self.update_drag_state(delta);
         
         // 记录事件
         let event = MouseEvent {
@@ -288,6 +320,23 @@ impl MouseManager {
         self.dragging_buttons.contains_key(button)
     }
     
+    // 检查拖拽是否已超过阈值（按下但未移动时is_dragging也为真）
+    pub fn is_drag_active(&self, button: &MouseButton) -> bool {
+        self.active_drags.contains(button)
+    }
+    
+    // 本帧合成的手势
+    pub fn get_gestures(&self) -> &[MouseGesture] {
+        &self.frame_gestures
+    }
+    
+    // 本帧是否发生双击
+    pub fn is_double_clicked(&self, button: &MouseButton) -> bool {
+        self.frame_gestures.iter().any(|gesture| {
+            matches!(gesture, MouseGesture::DoubleClick { button: b, .. } if b == button)
+        })
+    }
+    
     // 获取拖拽距离
     pub fn get_drag_distance(&self, button: &MouseButton) -> Option<f32> {
         self.dragging_buttons.get(button).map(|start_pos| {
@@ -331,6 +380,11 @@ impl MouseManager {
         self.double_click_distance = distance.max(1.0);
     }
     
+    // 设置双击时间窗口（秒）
+    pub fn set_double_click_time(&mut self, time: f32) {
+        self.double_click_time = time.max(0.1);
+    }
+    
     // 设置拖拽阈值
     pub fn set_drag_threshold(&mut self, threshold: f32) {
         self.drag_threshold = threshold.max(1.0);
@@ -391,52 +445,71 @@ impl MouseManager {
         self.last_click_time.clear();
         self.last_click_position.clear();
         self.dragging_buttons.clear();
+        self.active_drags.clear();
+        self.pending_gestures.clear();
+        self.frame_gestures.clear();
         self.recent_events.clear();
         self.position_delta = glam::Vec2::ZERO;
         self.scroll_delta = glam::Vec2::ZERO;
     }
     
     // 私有方法
-    fn check_double_click(&mut self, button: MouseButton, position: glam::Vec2, current_time: std::time::Instant) -> bool {
+    fn check_double_click(&mut self, button: MouseButton, position: glam::Vec2) -> bool {
         if let (Some(&last_time), Some(&last_pos)) = (
             self.last_click_time.get(&button),
             self.last_click_position.get(&button)
         ) {
-            let time_diff = current_time.duration_since(last_time).as_secs_f32();
+            let time_diff = self.clock - last_time;
             let distance = (position - last_pos).length();
             
             if time_diff <= self.double_click_time && distance <= self.double_click_distance {
                 debug!("检测到双击: {:?}", button);
+                // 第三次点击重新开始计数，不会再次触发双击
+                self.last_click_time.remove(&button);
+                self.last_click_position.remove(&button);
                 return true;
             }
         }
         
         // 更新最后点击信息
-        self.last_click_time.insert(button, current_time);
+        self.last_click_time.insert(button, self.clock);
         self.last_click_position.insert(button, position);
         
         false
     }
     
-    fn update_drag_state(&mut self) {
+    fn update_drag_state(&mut self, delta: glam::Vec2) {
         let mut to_remove = Vec::new();
         
         for (&button, &start_pos) in &self.dragging_buttons {
-            let distance = (self.current_position - start_pos).length();
+            // 如果按键已释放，停止拖拽
+            if !self.is_button_pressed(&button) {
+                to_remove.push(button);
+                continue;
+            }
+            
+            if self.active_drags.contains(&button) {
+                self.pending_gestures.push(MouseGesture::DragMoved {
+                    button,
+                    start: start_pos,
+                    position: self.current_position,
+                    delta,
+                });
+                continue;
+            }
             
             // 如果移动距离超过阈值，开始拖拽
+            let distance = (self.current_position - start_pos).length();
             if distance > self.drag_threshold {
                 debug!("开始拖拽: {:?} 距离: {:.1}", button, distance);
-            }
-            
-            // 如果按键已释放，停止拖拽
-            if !self.is_button_pressed(&button) {
-                to_remove.push(button);
+                self.active_drags.insert(button);
+                self.pending_gestures.push(MouseGesture::DragStarted { button, start: start_pos });
             }
         }
         
         for button in to_remove {
             self.dragging_buttons.remove(&button);
+            self.active_drags.remove(&button);
         }
     }
     
@@ -571,4 +644,71 @@ mod tests {
         assert!(drag_distance.is_some());
         assert!(drag_distance.unwrap() > manager.drag_threshold);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_quick_clicks_produce_double_click() {
+        let mut manager = MouseManager::new();
+        let position = glam::Vec2::new(50.0, 50.0);
+        
+        manager.handle_button_pressed(MouseButton::Left, position);
+        manager.handle_button_released(MouseButton::Left, position);
+        manager.update(0.1);
+        assert!(!manager.is_double_clicked(&MouseButton::Left));
+        
+        manager.handle_button_pressed(MouseButton::Left, position + glam::Vec2::new(1.0, 0.0));
+        manager.handle_button_released(MouseButton::Left, position);
+        manager.update(0.016);
+        assert!(manager.is_double_clicked(&MouseButton::Left));
+        
+        // 双击只在发生的那一帧可见
+        manager.update(0.016);
+        assert!(!manager.is_double_clicked(&MouseButton::Left));
+    }
+    
+    #[test]
+    fn test_slow_clicks_are_not_double_click() {
+        let mut manager = MouseManager::new();
+        manager.set_double_click_time(0.3);
+        let position = glam::Vec2::new(50.0, 50.0);
+        
+        manager.handle_button_pressed(MouseButton::Left, position);
+        manager.handle_button_released(MouseButton::Left, position);
+        manager.update(0.5);
+        
+        manager.handle_button_pressed(MouseButton::Left, position);
+        manager.handle_button_released(MouseButton::Left, position);
+        manager.update(0.016);
+        assert!(!manager.is_double_clicked(&MouseButton::Left));
+        assert!(manager.get_gestures().is_empty());
+    }
+    
+    #[test]
+    fn test_drag_gesture_events() {
+        let mut manager = MouseManager::new();
+        let start = glam::Vec2::new(10.0, 10.0);
+        
+        manager.handle_button_pressed(MouseButton::Left, start);
+        manager.handle_mouse_moved(start + glam::Vec2::new(1.0, 0.0), glam::Vec2::new(1.0, 0.0));
+        assert!(!manager.is_drag_active(&MouseButton::Left));
+        
+        manager.handle_mouse_moved(start + glam::Vec2::new(10.0, 0.0), glam::Vec2::new(9.0, 0.0));
+        manager.handle_mouse_moved(start + glam::Vec2::new(20.0, 0.0), glam::Vec2::new(10.0, 0.0));
+        assert!(manager.is_drag_active(&MouseButton::Left));
+        
+        let end = start + glam::Vec2::new(20.0, 0.0);
+        manager.handle_button_released(MouseButton::Left, end);
+        manager.update(0.016);
+        
+        assert_eq!(manager.get_gestures(), &[
+            MouseGesture::DragStarted { button: MouseButton::Left, start },
+            MouseGesture::DragMoved {
+                button: MouseButton::Left,
+                start,
+                position: end,
+                delta: glam::Vec2::new(10.0, 0.0),
+            },
+            MouseGesture::DragEnded { button: MouseButton::Left, start, end },
+        ]);
+        assert!(!manager.is_drag_active(&MouseButton::Left));
+    }
+}
@@ -0,0 +1,168 @@
+// 输入录制与回放
+// 开发心理：复现玩家报告的问题需要完全一致的输入序列，自动化测试也需要可重复的输入
+// 设计原则：按帧序号记录而非真实时间，回放时在相同帧注入事件以保证确定性
+
+use super::InputEvent;
+use crate::core::{GameError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::{debug, info};
+
+// 录制文件格式版本
+const RECORDING_VERSION: u32 = 1;
+
+// 单条录制的输入事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedInputEvent {
+    pub frame: u64,     // 相对录制开始的帧偏移
+    pub timestamp: f32, // 相对录制开始的时间(秒)，仅用于调试查看
+    pub event: InputEvent,
+}
+
+// 完整的输入录制
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputRecording {
+    pub version: u32,
+    pub frame_count: u64,
+    pub events: Vec<RecordedInputEvent>,
+}
+
+impl InputRecording {
+    pub fn new() -> Self {
+        Self {
+            version: RECORDING_VERSION,
+            frame_count: 0,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let recording: InputRecording = serde_json::from_str(&content).map_err(|e| {
+            GameError::ConfigError(format!("解析输入录制失败: {:?} ({})", path, e))
+        })?;
+
+        if recording.version > RECORDING_VERSION {
+            return Err(GameError::ConfigError(format!(
+                "不支持的输入录制版本: {} (当前支持 {})",
+                recording.version, RECORDING_VERSION
+            )));
+        }
+
+        Ok(recording)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| {
+            GameError::ConfigError(format!("序列化输入录制失败: {}", e))
+        })?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+impl Default for InputRecording {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 录制器 - 由InputManager在处理事件和帧更新时驱动
+#[derive(Debug)]
+pub struct InputRecorder {
+    path: PathBuf,
+    recording: InputRecording,
+    frame: u64,
+    elapsed: f32,
+}
+
+impl InputRecorder {
+    pub fn new(path: &Path) -> Self {
+        info!("开始录制输入: {:?}", path);
+        Self {
+            path: path.to_path_buf(),
+            recording: InputRecording::new(),
+            frame: 0,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn record(&mut self, event: &InputEvent) {
+        self.recording.events.push(RecordedInputEvent {
+            frame: self.frame,
+            timestamp: self.elapsed,
+            event: event.clone(),
+        });
+    }
+
+    pub fn advance_frame(&mut self, delta_time: f32) {
+        self.frame += 1;
+        self.elapsed += delta_time;
+    }
+
+    pub fn event_count(&self) -> usize {
+        self.recording.events.len()
+    }
+
+    // 结束录制并写入文件
+    pub fn finish(mut self) -> Result<InputRecording> {
+        self.recording.frame_count = self.frame;
+        self.recording.save(&self.path)?;
+        info!("输入录制已保存: {:?} ({} 个事件, {} 帧)", self.path, self.recording.events.len(), self.frame);
+        Ok(self.recording)
+    }
+}
+
+// 回放器 - 每帧取出该帧应注入的事件
+#[derive(Debug)]
+pub struct InputPlayback {
+    pending: VecDeque<RecordedInputEvent>,
+    frame: u64,
+    frame_count: u64,
+}
+
+impl InputPlayback {
+    pub fn new(recording: InputRecording) -> Self {
+        let mut events = recording.events;
+        // 同一帧内保持录制顺序
+        events.sort_by_key(|recorded| recorded.frame);
+
+        Self {
+            pending: events.into(),
+            frame: 0,
+            frame_count: recording.frame_count,
+        }
+    }
+
+    // 取出当前帧的事件并推进到下一帧
+    pub fn next_frame_events(&mut self) -> Vec<InputEvent> {
+        let mut events = Vec::new();
+        while self.pending.front().is_some_and(|recorded| recorded.frame <= self.frame) {
+            if let Some(recorded) = self.pending.pop_front() {
+                events.push(recorded.event);
+            }
+        }
+
+        if !events.is_empty() {
+            debug!("回放第 {} 帧: {} 个事件", self.frame, events.len());
+        }
+
+        self.frame += 1;
+        events
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.pending.is_empty() && self.frame >= self.frame_count
+    }
+
+    pub fn current_frame(&self) -> u64 {
+        self.frame
+    }
+}
@@ -64,6 +64,55 @@ pub enum SwipeDirection {
     DownRight,
 }
 
+// 高层手势，供UI直接消费（屏幕坐标，y轴向下）
+#[derive(Debug, Clone, PartialEq)]
+pub enum TouchGesture {
+    Tap { position: glam::Vec2 },
+    DoubleTap { position: glam::Vec2 },
+    Swipe { direction: SwipeDirection, distance: f32, velocity: glam::Vec2 },
+    Pinch { center: glam::Vec2, scale: f32 },
+}
+
+impl TouchGesture {
+    pub fn from_event(event: &GestureEvent) -> Option<Self> {
+        match &event.gesture_type {
+            GestureType::Tap => Some(TouchGesture::Tap { position: event.position }),
+            GestureType::DoubleTap => Some(TouchGesture::DoubleTap { position: event.position }),
+            GestureType::Swipe(direction) => Some(TouchGesture::Swipe {
+                direction: *direction,
+                distance: event.delta.length(),
+                velocity: event.velocity,
+            }),
+            GestureType::Pinch => Some(TouchGesture::Pinch { center: event.position, scale: event.scale }),
+            _ => None,
+        }
+    }
+}
+
+// 手势识别阈值 - 可序列化，随InputConfig保存
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TouchGestureThresholds {
+    pub tap_max_duration: f32,       // 点击最大时长(秒)
+    pub tap_max_distance: f32,       // 点击最大移动距离(像素)
+    pub double_tap_interval: f32,    // 双击最大间隔(秒)
+    pub swipe_min_distance: f32,     // 滑动最小距离(像素)
+    pub swipe_max_duration: f32,     // 滑动最大时长(秒)
+    pub pinch_min_scale_change: f32, // 触发缩放的最小比例变化
+}
+
+impl Default for TouchGestureThresholds {
+    fn default() -> Self {
+        Self {
+            tap_max_duration: 0.3,
+            tap_max_distance: 20.0,
+            double_tap_interval: 0.4,
+            swipe_min_distance: 50.0,
+            swipe_max_duration: 0.5,
+            pinch_min_scale_change: 0.05,
+        }
+    }
+}
+
 // 手势事件
 #[derive(Debug, Clone)]
 pub struct GestureEvent {
@@ -94,7 +143,9 @@ pub struct TouchConfig {
     pub double_tap_interval: f32,   // 双击间隔
     pub long_press_duration: f32,   // 长按时长
     pub swipe_min_distance: f32,    // 最小滑动距离
+    pub swipe_max_duration: f32,    // 最大滑动时长
     pub swipe_max_angle: f32,       // 滑动角度容差
+    pub pinch_min_scale_change: f32, // 最小缩放变化
     
     // 多点触控参数
     pub max_touch_points: usize,    // 最大触摸点数
@@ -125,6 +176,8 @@ pub struct TouchManager {
     // 事件历史
     touch_events: Vec<TouchEvent>,
     gesture_events: Vec<GestureEvent>,
+    processed_event_count: usize, // 已交给识别器的触摸事件数
+    frame_gestures: Vec<TouchGesture>,
     
     // 统计信息
     total_touches: u64,
@@ -145,6 +198,8 @@ impl TouchManager {
             current_gestures: Vec::new(),
             touch_events: Vec::new(),
             gesture_events: Vec::new(),
+            processed_event_count: 0,
+            frame_gestures: Vec::new(),
             total_touches: 0,
             max_simultaneous_touches: 0,
             last_update: std::time::Instant::now(),
@@ -155,8 +210,8 @@ impl TouchManager {
         manager
     }
     
-    // 更新触摸状态
-    pub fn update(&mut self, delta_time: f32) {
+    // 更新触摸状态，返回本帧识别出的手势
+    pub fn update(&mut self, delta_time: f32) -> Vec<TouchGesture> {
         let current_time = std::time::Instant::now();
         
         // 性能监控
@@ -178,6 +233,30 @@ impl TouchManager {
         
         // 应用平滑滤波
         self.apply_smoothing();
+        
+        self.frame_gestures.clone()
+    }
+    
+    // 本帧识别出的手势
+    pub fn get_frame_gestures(&self) -> &[TouchGesture] {
+        &self.frame_gestures
+    }
+    
+    // 应用手势阈值，并用新阈值重建内置识别器
+    pub fn set_gesture_thresholds(&mut self, thresholds: &TouchGestureThresholds) {
+        self.config.tap_max_duration = thresholds.tap_max_duration;
+        self.config.tap_max_distance = thresholds.tap_max_distance;
+        self.config.double_tap_interval = thresholds.double_tap_interval;
+        self.config.swipe_min_distance = thresholds.swipe_min_distance;
+        self.config.swipe_max_duration = thresholds.swipe_max_duration;
+        self.config.pinch_min_scale_change = thresholds.pinch_min_scale_change;
+        
+        // 内置识别器总是排在最前面，自定义识别器保留
+        let default_count = DEFAULT_RECOGNIZER_COUNT.min(self.gesture_recognizers.len());
+        let custom: Vec<_> = self.gesture_recognizers.drain(default_count..).collect();
+        self.gesture_recognizers.clear();
+        self.setup_default_recognizers();
+        self.gesture_recognizers.extend(custom);
     }
     
     // 处理触摸开始
@@ -385,6 +464,7 @@ impl TouchManager {
     
     // 私有方法
     fn setup_default_recognizers(&mut self) {
+        // 数量需与DEFAULT_RECOGNIZER_COUNT保持一致
         // 基础手势识别器
         self.add_gesture_recognizer(Box::new(TapRecognizer::new(self.config.clone())));
         self.add_gesture_recognizer(Box::new(PanRecognizer::new(self.config.clone())));
@@ -406,13 +486,23 @@ impl TouchManager {
     
     fn run_gesture_recognition(&mut self) {
         self.current_gestures.clear();
+        self.frame_gestures.clear();
+        
+        // 只把上次识别之后的新事件交给识别器，避免同一次触摸被重复识别
+        let new_events = &self.touch_events[self.processed_event_count.min(self.touch_events.len())..];
         
         for recognizer in &mut self.gesture_recognizers {
-            if let Some(gesture) = recognizer.recognize(&self.active_touches, &self.touch_events) {
+            if let Some(gesture) = recognizer.recognize(&self.active_touches, new_events) {
+                if let Some(touch_gesture) = TouchGesture::from_event(&gesture) {
+                    debug!("识别手势: {:?}", touch_gesture);
+                    self.frame_gestures.push(touch_gesture);
+                }
                 self.current_gestures.push(gesture.clone());
                 self.gesture_events.push(gesture);
             }
         }
+        
+        self.processed_event_count = self.touch_events.len();
     }
     
     fn cleanup_events(&mut self) {
@@ -431,6 +521,9 @@ impl TouchManager {
             let excess = self.touch_events.len() - self.config.event_history_size;
             self.touch_events.drain(0..excess);
         }
+        
+        // 识别后清理，剩余事件都已处理过
+        self.processed_event_count = self.touch_events.len();
     }
     
     fn apply_smoothing(&mut self) {
@@ -448,8 +541,12 @@ impl TouchManager {
     }
 }
 
+// 内置识别器数量（点击、拖拽、缩放、滑动）
+const DEFAULT_RECOGNIZER_COUNT: usize = 4;
+
 // 手势识别器接口
-pub trait GestureRecognizer {
+// 需要Send：触摸管理器随InputManager存放在全局互斥锁中
+pub trait GestureRecognizer: Send {
     fn recognize(
         &mut self,
         active_touches: &HashMap<u64, TouchPoint>,
@@ -531,31 +628,149 @@ impl GestureRecognizer for TapRecognizer {
 
 // 其他手势识别器的简化实现
 struct PanRecognizer { config: TouchConfig }
-struct PinchRecognizer { config: TouchConfig }
-struct SwipeRecognizer { config: TouchConfig }
 
 impl PanRecognizer {
     fn new(config: TouchConfig) -> Self { Self { config } }
 }
-impl PinchRecognizer {
-    fn new(config: TouchConfig) -> Self { Self { config } }
-}
-impl SwipeRecognizer {
-    fn new(config: TouchConfig) -> Self { Self { config } }
-}
 
 impl GestureRecognizer for PanRecognizer {
     fn recognize(&mut self, _active_touches: &HashMap<u64, TouchPoint>, _touch_events: &[TouchEvent]) -> Option<GestureEvent> { None }
     fn reset(&mut self) {}
 }
 
+// 双指缩放识别器
+struct PinchRecognizer {
+    config: TouchConfig,
+    touch_pair: Option<(u64, u64)>,
+    start_distance: f32,
+    last_scale: f32,
+}
+
+impl PinchRecognizer {
+    fn new(config: TouchConfig) -> Self {
+        Self {
+            config,
+            touch_pair: None,
+            start_distance: 0.0,
+            last_scale: 1.0,
+        }
+    }
+}
+
 impl GestureRecognizer for PinchRecognizer {
-    fn recognize(&mut self, _active_touches: &HashMap<u64, TouchPoint>, _touch_events: &[TouchEvent]) -> Option<GestureEvent> { None }
-    fn reset(&mut self) {}
+    fn recognize(&mut self, active_touches: &HashMap<u64, TouchPoint>, _touch_events: &[TouchEvent]) -> Option<GestureEvent> {
+        if active_touches.len() != 2 {
+            self.reset();
+            return None;
+        }
+        
+        let mut ids: Vec<u64> = active_touches.keys().copied().collect();
+        ids.sort_unstable();
+        let (first, second) = (&active_touches[&ids[0]], &active_touches[&ids[1]]);
+        let distance = (first.position - second.position).length();
+        let center = (first.position + second.position) / 2.0;
+        
+        // 新的一对触摸点，记录初始距离
+        if self.touch_pair != Some((ids[0], ids[1])) {
+            self.touch_pair = Some((ids[0], ids[1]));
+            self.start_distance = distance;
+            self.last_scale = 1.0;
+            return None;
+        }
+        
+        if self.start_distance <= f32::EPSILON {
+            return None;
+        }
+        
+        let scale = distance / self.start_distance;
+        if (scale - 1.0).abs() < self.config.pinch_min_scale_change || (scale - self.last_scale).abs() <= f32::EPSILON {
+            return None;
+        }
+        self.last_scale = scale;
+        
+        let now = std::time::Instant::now();
+        Some(GestureEvent {
+            gesture_type: GestureType::Pinch,
+            position: center,
+            delta: glam::Vec2::ZERO,
+            scale,
+            rotation: 0.0,
+            velocity: glam::Vec2::ZERO,
+            duration: now.duration_since(first.start_time.min(second.start_time)).as_secs_f32(),
+            touch_points: ids,
+            timestamp: now,
+        })
+    }
+    
+    fn reset(&mut self) {
+        self.touch_pair = None;
+        self.start_distance = 0.0;
+        self.last_scale = 1.0;
+    }
+}
+
+// 滑动识别器
+struct SwipeRecognizer { config: TouchConfig }
+
+impl SwipeRecognizer {
+    fn new(config: TouchConfig) -> Self { Self { config } }
+    
+    // 接近坐标轴（在角度容差内）的滑动归为四方向，其余归为斜向；屏幕坐标y轴向下
+    fn direction(&self, delta: glam::Vec2) -> SwipeDirection {
+        let angle = delta.y.atan2(delta.x);
+        let tolerance = self.config.swipe_max_angle;
+        let near = |target: f32| {
+            let diff = (angle - target).abs();
+            diff.min(std::f32::consts::TAU - diff) <= tolerance
+        };
+        
+        if near(0.0) {
+            SwipeDirection::Right
+        } else if near(std::f32::consts::PI) {
+            SwipeDirection::Left
+        } else if near(std::f32::consts::FRAC_PI_2) {
+            SwipeDirection::Down
+        } else if near(-std::f32::consts::FRAC_PI_2) {
+            SwipeDirection::Up
+        } else {
+            match (delta.x > 0.0, delta.y > 0.0) {
+                (true, true) => SwipeDirection::DownRight,
+                (true, false) => SwipeDirection::UpRight,
+                (false, true) => SwipeDirection::DownLeft,
+                (false, false) => SwipeDirection::UpLeft,
+            }
+        }
+    }
 }
 
 impl GestureRecognizer for SwipeRecognizer {
-    fn recognize(&mut self, _active_touches: &HashMap<u64, TouchPoint>, _touch_events: &[TouchEvent]) -> Option<GestureEvent> { None }
+    fn recognize(&mut self, _active_touches: &HashMap<u64, TouchPoint>, touch_events: &[TouchEvent]) -> Option<GestureEvent> {
+        for event in touch_events.iter().rev() {
+            if event.touch_point.state != TouchState::Ended {
+                continue;
+            }
+            
+            let delta = event.touch_point.position - event.touch_point.start_position;
+            let duration = event.timestamp.duration_since(event.touch_point.start_time).as_secs_f32();
+            
+            if delta.length() >= self.config.swipe_min_distance && duration <= self.config.swipe_max_duration {
+                return Some(GestureEvent {
+                    gesture_type: GestureType::Swipe(self.direction(delta)),
+                    position: event.touch_point.position,
+                    delta,
+                    scale: 1.0,
+                    rotation: 0.0,
+                    velocity: delta / duration.max(0.001),
+                    duration,
+                    touch_points: vec![event.touch_point.id],
+                    timestamp: event.timestamp,
+                });
+            }
+        }
+        
+        None
+    }
+    
     fn reset(&mut self) {}
 }
 
@@ -578,7 +793,9 @@ impl Default for TouchConfig {
             double_tap_interval: 0.4,
             long_press_duration: 1.0,
             swipe_min_distance: 50.0,
+            swipe_max_duration: 0.5,
             swipe_max_angle: 0.5, // 约30度
+            pinch_min_scale_change: 0.05,
             max_touch_points: 10,
             touch_merge_distance: 30.0,
             update_frequency: 60.0,
@@ -643,4 +860,58 @@ mod tests {
         let center = manager.get_touch_center().unwrap();
         assert_eq!(center, glam::Vec2::new(50.0, 50.0));
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_left_swipe_gesture() {
+        let mut manager = TouchManager::new();
+        
+        manager.handle_touch_started(1, glam::Vec2::new(300.0, 200.0), 1.0);
+        manager.handle_touch_moved(1, glam::Vec2::new(220.0, 204.0), 1.0);
+        manager.handle_touch_ended(1, glam::Vec2::new(150.0, 205.0));
+        
+        let gestures = manager.update(0.016);
+        assert!(gestures.iter().any(|g| matches!(g, TouchGesture::Swipe { direction: SwipeDirection::Left, .. })));
+        assert!(!gestures.iter().any(|g| matches!(g, TouchGesture::Tap { .. })));
+        
+        // 同一次滑动不会在下一帧重复触发
+        assert!(manager.update(0.016).is_empty());
+    }
+    
+    #[test]
+    fn test_pinch_in_gesture() {
+        let mut manager = TouchManager::new();
+        
+        manager.handle_touch_started(1, glam::Vec2::new(100.0, 300.0), 1.0);
+        manager.handle_touch_started(2, glam::Vec2::new(300.0, 300.0), 1.0);
+        assert!(manager.update(0.016).is_empty());
+        
+        manager.handle_touch_moved(1, glam::Vec2::new(150.0, 300.0), 1.0);
+        manager.handle_touch_moved(2, glam::Vec2::new(250.0, 300.0), 1.0);
+        let gestures = manager.update(0.016);
+        
+        match gestures.as_slice() {
+            [TouchGesture::Pinch { center, scale }] => {
+                assert!((*scale - 0.5).abs() < 0.001);
+                assert_eq!(*center, glam::Vec2::new(200.0, 300.0));
+            },
+            other => panic!("预期缩放手势，实际: {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_tap_and_threshold_config() {
+        let mut manager = TouchManager::new();
+        manager.handle_touch_started(1, glam::Vec2::new(10.0, 10.0), 1.0);
+        manager.handle_touch_ended(1, glam::Vec2::new(12.0, 10.0));
+        assert_eq!(manager.update(0.016), vec![TouchGesture::Tap { position: glam::Vec2::new(12.0, 10.0) }]);
+        
+        // 提高最小滑动距离后，短滑动不再被识别
+        manager.set_gesture_thresholds(&TouchGestureThresholds {
+            swipe_min_distance: 500.0,
+            ..TouchGestureThresholds::default()
+        });
+        manager.handle_touch_started(2, glam::Vec2::new(300.0, 200.0), 1.0);
+        manager.handle_touch_ended(2, glam::Vec2::new(150.0, 200.0));
+        assert!(manager.update(0.016).is_empty());
+    }
+}
@@ -52,11 +52,11 @@ mod engine;
 
 #[cfg(feature = "custom-engine")]
 use crate::core::app::PokemonApp;
-use crate::states::{loading::LoadingPlugin, menu::MenuPlugin};
+use crate::states::{loading::LoadingPlugin, menu::MenuPlugin, battle::BattleStatePlugin};
+use crate::audio::PokemonAudioPlugin;
 // 注意：以下插件需要实现后再启用
 // use crate::graphics::renderer::PokemonRendererPlugin;
 // use crate::input::PokemonInputPlugin;
-// use crate::audio::PokemonAudioPlugin;
 // use crate::data::PokemonDataPlugin;
 // use crate::player::PokemonPlayerPlugin;
 
@@ -110,11 +110,12 @@ fn main() {
     app.add_plugins(PokemonApp);
     
     app.add_plugins(LoadingPlugin)
-        .add_plugins(MenuPlugin);
+        .add_plugins(MenuPlugin)
+        .add_plugins(BattleStatePlugin)
+        .add_plugins(PokemonAudioPlugin);
         // 注意：以下插件需要实现后再启用
         // .add_plugins(PokemonRendererPlugin)
         // .add_plugins(PokemonInputPlugin)
-        // .add_plugins(PokemonAudioPlugin)
         // .add_plugins(PokemonDataPlugin)
         // .add_plugins(PokemonPlayerPlugin)
         // .add_plugins(PokemonWorldPlugin)
@@ -148,8 +149,13 @@ fn setup_game(
 ) {
     tracing::info!("设置游戏初始状态");
     
-    // 添加游戏配置资源
-    commands.insert_resource(core::config::GameConfig::default());
+    // 添加游戏配置资源：默认值 -> 配置文件 -> 环境变量 -> 命令行
+    let args: Vec<String> = std::env::args().collect();
+    let config = core::config::ConfigManager::load_from_args(&args).unwrap_or_else(|e| {
+        tracing::error!("加载配置失败，使用默认配置: {}", e);
+        core::config::GameConfig::default()
+    });
+    commands.insert_resource(config);
     commands.insert_resource(core::time::GameTimer::default());
     
     // 初始化ECS世界
@@ -0,0 +1,253 @@
+// 连接加密
+// 开发心理：enable_encryption默认开启，但数据一直明文传输，同一网络上的任何人都能看到聊天内容和对战操作
+// 设计原则：连接建立时双方交换一次性X25519公钥，由共享密钥为两个方向各派生一个ChaCha20-Poly1305密钥；每条消息带上递增的计数器作为nonce，篡改或用错密钥的消息无法通过认证
+
+use crate::core::{GameError, Result};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use rand::rngs::OsRng;
+use sha2::{Digest, Sha256};
+use x25519_dalek::{EphemeralSecret, PublicKey};
+
+pub const PUBLIC_KEY_LEN: usize = 32;
+// 密文前的计数器u64
+const COUNTER_LEN: usize = 8;
+// Poly1305认证标签
+const TAG_LEN: usize = 16;
+// 接收端记录的计数器窗口，更早的消息一律视为重放
+const REPLAY_WINDOW: u64 = 64;
+
+// 每条加密消息比明文多出的字节数
+pub const ENCRYPTION_OVERHEAD: usize = COUNTER_LEN + TAG_LEN;
+
+// 握手中的角色，决定哪个方向的密钥用于发送
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandshakeRole {
+    Client,
+    Server,
+}
+
+// 一次性的密钥交换，完成后私钥即被丢弃
+pub struct KeyExchange {
+    secret: EphemeralSecret,
+    public_key: PublicKey,
+}
+
+impl KeyExchange {
+    pub fn new() -> Self {
+        let secret = EphemeralSecret::random_from_rng(OsRng);
+        let public_key = PublicKey::from(&secret);
+        Self { secret, public_key }
+    }
+
+    pub fn public_key(&self) -> [u8; PUBLIC_KEY_LEN] {
+        self.public_key.to_bytes()
+    }
+
+    // 用对端公钥算出共享密钥并派生会话密钥；公钥长度不对或是低阶点时握手失败
+    pub fn finish(self, peer_public_key: &[u8], role: HandshakeRole) -> Result<SessionCipher> {
+        let peer_bytes: [u8; PUBLIC_KEY_LEN] = peer_public_key
+            .try_into()
+            .map_err(|_| GameError::NetworkError(format!("握手公钥长度无效: {} 字节", peer_public_key.len())))?;
+        let peer = PublicKey::from(peer_bytes);
+        let own = self.public_key.to_bytes();
+
+        let shared = self.secret.diffie_hellman(&peer);
+        if !shared.was_contributory() {
+            return Err(GameError::NetworkError("握手公钥无效".to_string()));
+        }
+
+        // 公钥按客户端、服务器的顺序参与派生，两端得到相同的结果
+        let (client_key, server_key) = match role {
+            HandshakeRole::Client => (own, peer_bytes),
+            HandshakeRole::Server => (peer_bytes, own),
+        };
+        let derive = |label: &[u8]| {
+            let mut hasher = Sha256::new();
+            hasher.update(label);
+            hasher.update(shared.as_bytes());
+            hasher.update(client_key);
+            hasher.update(server_key);
+            ChaCha20Poly1305::new(&hasher.finalize())
+        };
+        let client_to_server = derive(b"pokemongo client->server");
+        let server_to_client = derive(b"pokemongo server->client");
+
+        let (send, receive) = match role {
+            HandshakeRole::Client => (client_to_server, server_to_client),
+            HandshakeRole::Server => (server_to_client, client_to_server),
+        };
+        Ok(SessionCipher {
+            send,
+            receive,
+            send_counter: 0,
+            highest_received: None,
+            received_window: 0,
+        })
+    }
+}
+
+impl Default for KeyExchange {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 握手完成后的对称加密，两个方向使用不同的密钥，计数器不会在同一密钥下重复
+pub struct SessionCipher {
+    send: ChaCha20Poly1305,
+    receive: ChaCha20Poly1305,
+    send_counter: u64,
+    // 已收到的最大计数器，以及它之前REPLAY_WINDOW个计数器是否收到过(第i位对应highest - i)
+    highest_received: Option<u64>,
+    received_window: u64,
+}
+
+fn nonce(counter: u64) -> Nonce {
+    let mut bytes = [0u8; 12];
+    bytes[4..].copy_from_slice(&counter.to_be_bytes());
+    Nonce::clone_from_slice(&bytes)
+}
+
+impl SessionCipher {
+    // 输出: [计数器u64][密文+认证标签]
+    pub fn encrypt(&mut self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let counter = self.send_counter;
+        self.send_counter = counter
+            .checked_add(1)
+            .ok_or_else(|| GameError::NetworkError("加密计数器耗尽".to_string()))?;
+
+        let ciphertext = self.send
+            .encrypt(&nonce(counter), plaintext)
+            .map_err(|_| GameError::NetworkError("加密失败".to_string()))?;
+
+        let mut data = Vec::with_capacity(COUNTER_LEN + ciphertext.len());
+        data.extend_from_slice(&counter.to_be_bytes());
+        data.extend_from_slice(&ciphertext);
+        Ok(data)
+    }
+
+    // 计数器随消息发送，UDP乱序到达的消息也能解密；重复的和落在窗口之前的计数器被拒绝
+    pub fn decrypt(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        if data.len() < ENCRYPTION_OVERHEAD {
+            return Err(GameError::NetworkError(format!("加密消息过短: {} 字节", data.len())));
+        }
+        let counter = u64::from_be_bytes(data[..COUNTER_LEN].try_into().unwrap());
+        if self.is_replay(counter) {
+            return Err(GameError::NetworkError(format!("拒绝重放的消息: 计数器 {}", counter)));
+        }
+        let plaintext = self.receive
+            .decrypt(&nonce(counter), &data[COUNTER_LEN..])
+            .map_err(|_| GameError::NetworkError("消息认证失败".to_string()))?;
+
+        // 认证通过后才记录，伪造的消息不能推动窗口
+        self.mark_received(counter);
+        Ok(plaintext)
+    }
+
+    fn is_replay(&self, counter: u64) -> bool {
+        match self.highest_received {
+            Some(highest) if counter <= highest => {
+                let offset = highest - counter;
+                offset >= REPLAY_WINDOW || self.received_window & (1 << offset) != 0
+            },
+            _ => false,
+        }
+    }
+
+    fn mark_received(&mut self, counter: u64) {
+        match self.highest_received {
+            Some(highest) if counter <= highest => {
+                self.received_window |= 1 << (highest - counter);
+            },
+            Some(highest) => {
+                let shift = counter - highest;
+                self.received_window = if shift >= REPLAY_WINDOW { 1 } else { (self.received_window << shift) | 1 };
+                self.highest_received = Some(counter);
+            },
+            None => {
+                self.received_window = 1;
+                self.highest_received = Some(counter);
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handshake() -> (SessionCipher, SessionCipher) {
+        let client = KeyExchange::new();
+        let server = KeyExchange::new();
+        let client_public = client.public_key();
+        let server_public = server.public_key();
+        (
+            client.finish(&server_public, HandshakeRole::Client).unwrap(),
+            server.finish(&client_public, HandshakeRole::Server).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_encrypted_round_trip() {
+        let (mut client, mut server) = handshake();
+        let plaintext = "用皮卡丘换你的伊布".as_bytes();
+
+        let first = client.encrypt(plaintext).unwrap();
+        let second = client.encrypt(plaintext).unwrap();
+        assert_eq!(first.len(), plaintext.len() + ENCRYPTION_OVERHEAD);
+        assert_ne!(first, second);
+        assert!(!first.windows(plaintext.len()).any(|window| window == plaintext));
+
+        // 乱序到达也能解密
+        assert_eq!(server.decrypt(&second).unwrap(), plaintext);
+        assert_eq!(server.decrypt(&first).unwrap(), plaintext);
+        assert_eq!(client.decrypt(&server.encrypt(b"ok").unwrap()).unwrap(), b"ok");
+
+        // 自己发出的消息不能用自己的接收密钥解开，被篡改的消息认证失败
+        assert!(client.decrypt(&first).is_err());
+        let mut tampered = first.clone();
+        *tampered.last_mut().unwrap() ^= 0x01;
+        assert!(server.decrypt(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_handshake_rejects_invalid_keys() {
+        assert!(KeyExchange::new().finish(&[1, 2, 3], HandshakeRole::Client).is_err());
+        // 全零公钥是低阶点，得不到有效的共享密钥
+        assert!(KeyExchange::new().finish(&[0u8; PUBLIC_KEY_LEN], HandshakeRole::Server).is_err());
+
+        // 不同会话的密钥互不相通
+        let (mut client, _) = handshake();
+        let (_, mut other_server) = handshake();
+        assert!(other_server.decrypt(&client.encrypt(b"secret").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_replayed_messages_rejected() {
+        let (mut client, mut server) = handshake();
+
+        let message = client.encrypt(b"trade pikachu").unwrap();
+        assert_eq!(server.decrypt(&message).unwrap(), b"trade pikachu");
+        assert!(server.decrypt(&message).is_err());
+
+        // 窗口内乱序到达的消息仍可解密一次，早于窗口的消息被拒绝
+        let stale = client.encrypt(b"stale").unwrap();
+        let late = client.encrypt(b"late").unwrap();
+        let mut latest = Vec::new();
+        for _ in 0..REPLAY_WINDOW - 1 {
+            latest = client.encrypt(b"move").unwrap();
+        }
+        assert!(server.decrypt(&latest).is_ok());
+        assert!(server.decrypt(&stale).is_err());
+        assert_eq!(server.decrypt(&late).unwrap(), b"late");
+        assert!(server.decrypt(&late).is_err());
+
+        // 认证失败的消息不会占用计数器
+        let genuine = client.encrypt(b"ok").unwrap();
+        let mut forged = genuine.clone();
+        *forged.last_mut().unwrap() ^= 0x01;
+        assert!(server.decrypt(&forged).is_err());
+        assert_eq!(server.decrypt(&genuine).unwrap(), b"ok");
+    }
+}
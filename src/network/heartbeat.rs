@@ -0,0 +1,195 @@
+// 心跳与往返时延估计
+// 开发心理：只靠最后活动时间判断超时，安静但正常的连接会被误断，而对端死掉后要等很久才发现；连接的RTT一直是0
+// 设计原则：两端定时互发带序号的ping，收到pong时按序号算出往返时间；平滑RTT和抖动沿用TCP的指数加权算法，连续多个ping没有回应就判定超时
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// 心跳包负载：种类u8 + 序号u32；空负载是客户端用来登记UDP地址的保活包
+const KIND_PING: u8 = 0;
+const KIND_PONG: u8 = 1;
+
+// 平滑系数，与TCP的RTO估计(RFC 6298)相同
+const RTT_GAIN: f64 = 0.125;
+const JITTER_GAIN: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeartbeatPacket {
+    Ping(u32),
+    Pong(u32),
+}
+
+impl HeartbeatPacket {
+    pub fn encode(&self) -> Vec<u8> {
+        let (kind, sequence) = match *self {
+            HeartbeatPacket::Ping(sequence) => (KIND_PING, sequence),
+            HeartbeatPacket::Pong(sequence) => (KIND_PONG, sequence),
+        };
+        let mut data = Vec::with_capacity(5);
+        data.push(kind);
+        data.extend_from_slice(&sequence.to_be_bytes());
+        data
+    }
+
+    // 格式不对的心跳包直接忽略
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let sequence = u32::from_be_bytes(data.get(1..5)?.try_into().ok()?);
+        match data[0] {
+            KIND_PING => Some(HeartbeatPacket::Ping(sequence)),
+            KIND_PONG => Some(HeartbeatPacket::Pong(sequence)),
+            _ => None,
+        }
+    }
+}
+
+// 连接质量的当前估计
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConnectionHealth {
+    pub rtt_ms: f64,                // 平滑后的往返时间
+    pub jitter_ms: f64,             // 往返时间的平均偏差
+    pub last_reply: Option<Instant>,
+}
+
+#[derive(Debug)]
+pub struct HeartbeatMonitor {
+    interval: Duration,
+    max_missed: u32,
+    next_sequence: u32,
+    last_sent: Option<Instant>,
+    outstanding: VecDeque<(u32, Instant)>,  // 尚未收到回应的ping
+    smoothed_rtt: Option<f64>,
+    jitter: f64,
+    last_reply: Option<Instant>,
+}
+
+impl HeartbeatMonitor {
+    pub fn new(interval: Duration, max_missed: u32) -> Self {
+        Self {
+            interval,
+            max_missed,
+            next_sequence: 0,
+            last_sent: None,
+            outstanding: VecDeque::new(),
+            smoothed_rtt: None,
+            jitter: 0.0,
+            last_reply: None,
+        }
+    }
+
+    // 到了发送间隔时返回要发出的ping
+    pub fn poll(&mut self, now: Instant) -> Option<HeartbeatPacket> {
+        if self.last_sent.is_some_and(|sent| now.saturating_duration_since(sent) < self.interval) {
+            return None;
+        }
+
+        let sequence = self.next_sequence;
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        self.last_sent = Some(now);
+        self.outstanding.push_back((sequence, now));
+        Some(HeartbeatPacket::Ping(sequence))
+    }
+
+    // 处理收到的pong，返回这次的往返时间(毫秒)；未知或过期的序号返回None
+    pub fn on_pong(&mut self, sequence: u32, now: Instant) -> Option<f64> {
+        let index = self.outstanding.iter().position(|&(outstanding, _)| outstanding == sequence)?;
+        let (_, sent) = self.outstanding[index];
+        // 更早的ping不会再有回应，连接已经确认存活
+        self.outstanding.drain(..=index);
+
+        let sample = now.saturating_duration_since(sent).as_secs_f64() * 1000.0;
+        match self.smoothed_rtt {
+            Some(rtt) => {
+                self.jitter += JITTER_GAIN * ((rtt - sample).abs() - self.jitter);
+                self.smoothed_rtt = Some(rtt + RTT_GAIN * (sample - rtt));
+            },
+            None => self.smoothed_rtt = Some(sample),
+        }
+        self.last_reply = Some(now);
+        Some(sample)
+    }
+
+    // 发出后超过一个间隔仍没有回应的ping数
+    pub fn missed(&self, now: Instant) -> u32 {
+        self.outstanding.iter()
+            .filter(|(_, sent)| now.saturating_duration_since(*sent) >= self.interval)
+            .count() as u32
+    }
+
+    pub fn is_timed_out(&self, now: Instant) -> bool {
+        self.missed(now) >= self.max_missed
+    }
+
+    // 还没有任何RTT样本时返回None
+    pub fn health(&self) -> Option<ConnectionHealth> {
+        self.smoothed_rtt.map(|rtt_ms| ConnectionHealth {
+            rtt_ms,
+            jitter_ms: self.jitter,
+            last_reply: self.last_reply,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_secs(5);
+
+    fn ms(value: u64) -> Duration {
+        Duration::from_millis(value)
+    }
+
+    #[test]
+    fn test_rtt_estimated_from_heartbeat_replies() {
+        let start = Instant::now();
+        let mut monitor = HeartbeatMonitor::new(INTERVAL, 3);
+        assert!(monitor.health().is_none());
+
+        let Some(HeartbeatPacket::Ping(first)) = monitor.poll(start) else {
+            panic!("第一次更新应立即发出ping");
+        };
+        assert!(monitor.poll(start + ms(10)).is_none());
+
+        // 第一个样本直接作为平滑RTT
+        let sample = monitor.on_pong(first, start + ms(40)).unwrap();
+        assert!((sample - 40.0).abs() < 1e-6);
+        assert!((monitor.health().unwrap().rtt_ms - 40.0).abs() < 1e-6);
+
+        // 之后按1/8权重平滑，抖动按1/4权重
+        let Some(HeartbeatPacket::Ping(second)) = monitor.poll(start + INTERVAL) else {
+            panic!("间隔到达后应发出ping");
+        };
+        monitor.on_pong(second, start + INTERVAL + ms(60)).unwrap();
+        let health = monitor.health().unwrap();
+        assert!((health.rtt_ms - 42.5).abs() < 1e-6);
+        assert!((health.jitter_ms - 5.0).abs() < 1e-6);
+        assert_eq!(health.last_reply, Some(start + INTERVAL + ms(60)));
+
+        // 重复或未知的pong不影响估计
+        assert!(monitor.on_pong(second, start + INTERVAL + ms(500)).is_none());
+        assert_eq!(HeartbeatPacket::decode(&HeartbeatPacket::Pong(9).encode()), Some(HeartbeatPacket::Pong(9)));
+        assert_eq!(HeartbeatPacket::decode(&[]), None);
+    }
+
+    #[test]
+    fn test_missed_heartbeats_time_out() {
+        let start = Instant::now();
+        let mut monitor = HeartbeatMonitor::new(INTERVAL, 3);
+
+        for round in 0..3 {
+            assert!(monitor.poll(start + INTERVAL * round).is_some());
+        }
+        assert_eq!(monitor.missed(start + INTERVAL * 2), 2);
+        assert!(!monitor.is_timed_out(start + INTERVAL * 2));
+        assert!(monitor.is_timed_out(start + INTERVAL * 3));
+
+        // 收到最新ping的回应后，之前的也不再算作丢失
+        let mut monitor = HeartbeatMonitor::new(INTERVAL, 3);
+        monitor.poll(start);
+        let Some(HeartbeatPacket::Ping(latest)) = monitor.poll(start + INTERVAL) else {
+            panic!("间隔到达后应发出ping");
+        };
+        monitor.on_pong(latest, start + INTERVAL + ms(20));
+        assert_eq!(monitor.missed(start + INTERVAL * 10), 0);
+    }
+}
@@ -8,13 +8,17 @@
  */
 
 use bevy::prelude::*;
-use std::collections::{HashMap, VecDeque, BinaryHeap};
-use std::cmp::{Ordering, Reverse};
-use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-use uuid::Uuid;
+use chrono::Timelike;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
 use serde::{Serialize, Deserialize};
+use log::{debug, info};
+use crate::battle::{BattleConfig, BattleFormat, BattleType, LevelCapMode};
 use crate::core::error::{GameResult, GameError};
 
+pub type PlayerId = u64;
+pub type RoomId = u64;
+
 // 匹配状态
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MatchmakingState {
@@ -53,12 +57,12 @@ pub enum PlayerTier {
 // 匹配请求
 #[derive(Debug, Clone)]
 pub struct MatchmakingRequest {
-    pub player_id: Uuid,
+    pub player_id: PlayerId,
     pub game_mode: GameMode,
     pub rating: u32,
     pub tier: PlayerTier,
     pub preferences: MatchmakingPreferences,
-    pub team: Option<Vec<Uuid>>, // 组队匹配
+    pub team: Option<Vec<PlayerId>>, // 组队匹配
     pub timestamp: Instant,
     pub expanded_search_time: Duration,
 }
@@ -70,7 +74,7 @@ pub struct MatchmakingPreferences {
     pub preferred_regions: Vec<String>,
     pub max_ping: u32,
     pub language: String,
-    pub avoid_players: Vec<Uuid>,
+    pub avoid_players: Vec<PlayerId>,
     pub allow_cross_platform: bool,
     pub priority_queue: bool,
 }
@@ -104,7 +108,7 @@ pub struct MatchmakingResult {
 // 匹配的玩家
 #[derive(Debug, Clone)]
 pub struct MatchedPlayer {
-    pub player_id: Uuid,
+    pub player_id: PlayerId,
     pub rating: u32,
     pub tier: PlayerTier,
     pub team_id: u8,
@@ -141,7 +145,7 @@ impl MatchmakingQueue {
         }
     }
 
-    pub fn remove_request(&mut self, player_id: Uuid) -> bool {
+    pub fn remove_request(&mut self, player_id: PlayerId) -> bool {
         let removed_normal = self.requests.iter()
             .position(|r| r.player_id == player_id)
             .map(|pos| self.requests.remove(pos))
@@ -225,9 +229,9 @@ pub struct MatchmakingStats {
 // 匹配事件
 #[derive(Debug, Clone)]
 pub enum MatchmakingEvent {
-    PlayerJoinedQueue { player_id: Uuid, game_mode: GameMode },
-    PlayerLeftQueue { player_id: Uuid, game_mode: GameMode },
-    MatchFound { match_id: String, players: Vec<Uuid> },
+    PlayerJoinedQueue { player_id: PlayerId, game_mode: GameMode },
+    PlayerLeftQueue { player_id: PlayerId, game_mode: GameMode },
+    MatchFound { match_id: String, players: Vec<PlayerId> },
     MatchFailed { reason: String },
     QueueUpdated { game_mode: GameMode, size: usize },
 }
@@ -252,22 +256,22 @@ pub enum ServerStatus {
 }
 
 // 匹配管理器
+#[derive(Resource)]
 pub struct MatchmakingManager {
     config: MatchmakingConfig,
     queues: HashMap<GameMode, MatchmakingQueue>,
-    active_searches: HashMap<Uuid, MatchmakingState>,
+    active_searches: HashMap<PlayerId, MatchmakingState>,
     completed_matches: HashMap<String, MatchmakingResult>,
     servers: HashMap<String, GameServer>,
     stats: MatchmakingStats,
-    event_sender: tokio::sync::mpsc::UnboundedSender<MatchmakingEvent>,
+    events: VecDeque<MatchmakingEvent>,
+    match_counter: u64,
     last_matchmaking_run: Instant,
 }
 
 impl MatchmakingManager {
     // 创建匹配管理器
     pub fn new() -> GameResult<Self> {
-        let (event_sender, _) = tokio::sync::mpsc::unbounded_channel();
-        
         let mut queues = HashMap::new();
         for &mode in &[
             GameMode::Casual1v1, GameMode::Ranked1v1, GameMode::Double2v2,
@@ -283,7 +287,8 @@ impl MatchmakingManager {
             completed_matches: HashMap::new(),
             servers: HashMap::new(),
             stats: MatchmakingStats::default(),
-            event_sender,
+            events: VecDeque::new(),
+            match_counter: 0,
             last_matchmaking_run: Instant::now(),
         })
     }
@@ -302,16 +307,16 @@ impl MatchmakingManager {
     // 加入匹配队列
     pub fn join_queue(
         &mut self, 
-        player_id: Uuid, 
+        player_id: PlayerId, 
         game_mode: GameMode,
         rating: u32,
         preferences: MatchmakingPreferences,
-        team: Option<Vec<Uuid>>
+        team: Option<Vec<PlayerId>>
     ) -> GameResult<()> {
         
         // 检查玩家是否已在队列中
         if self.active_searches.contains_key(&player_id) {
-            return Err(GameError::Matchmaking("玩家已在匹配队列中".to_string()));
+            return Err(GameError::NetworkError("玩家已在匹配队列中".to_string()));
         }
 
         let tier = Self::rating_to_tier(rating);
@@ -333,7 +338,7 @@ impl MatchmakingManager {
             self.active_searches.insert(player_id, MatchmakingState::Searching);
             
             // 发送事件
-            let _ = self.event_sender.send(MatchmakingEvent::PlayerJoinedQueue {
+            self.events.push_back(MatchmakingEvent::PlayerJoinedQueue {
                 player_id,
                 game_mode,
             });
@@ -346,7 +351,7 @@ impl MatchmakingManager {
     }
 
     // 离开匹配队列
-    pub fn leave_queue(&mut self, player_id: Uuid) -> GameResult<()> {
+    pub fn leave_queue(&mut self, player_id: PlayerId) -> GameResult<()> {
         // 从所有队列中移除玩家
         let mut removed_from_mode = None;
         
@@ -360,7 +365,7 @@ impl MatchmakingManager {
         if let Some(mode) = removed_from_mode {
             self.active_searches.remove(&player_id);
             
-            let _ = self.event_sender.send(MatchmakingEvent::PlayerLeftQueue {
+            self.events.push_back(MatchmakingEvent::PlayerLeftQueue {
                 player_id,
                 game_mode: mode,
             });
@@ -382,11 +387,19 @@ impl MatchmakingManager {
 
         self.last_matchmaking_run = now;
 
-        // 处理每个游戏模式的队列
-        for (&game_mode, queue) in &mut self.queues {
-            if !queue.is_empty() {
-                self.process_queue(game_mode, queue)?;
-            }
+        // 处理每个游戏模式的队列，处理期间先取出队列避免同时借用
+        let modes: Vec<GameMode> = self.queues.keys().copied().collect();
+        for game_mode in modes {
+            let Some(mut queue) = self.queues.remove(&game_mode) else {
+                continue;
+            };
+            let result = if queue.is_empty() {
+                Ok(())
+            } else {
+                self.process_queue(game_mode, &mut queue)
+            };
+            self.queues.insert(game_mode, queue);
+            result?;
         }
 
         // 清理过期的搜索
@@ -418,15 +431,19 @@ impl MatchmakingManager {
             }
         }
 
-        // 尝试创建匹配
-        if let Some(match_result) = self.find_best_match(game_mode, &candidates)? {
-            self.create_match(match_result)?;
-        } else {
-            // 如果没有找到合适的匹配，将候选者放回队列
-            for candidate in candidates {
+        // 尝试创建匹配，未被选中的候选者放回队列
+        let match_result = self.find_best_match(game_mode, &candidates)?;
+        let matched: Vec<PlayerId> = match_result.iter()
+            .flat_map(|result| result.players.iter().map(|p| p.player_id))
+            .collect();
+        for candidate in candidates {
+            if !matched.contains(&candidate.player_id) {
                 queue.add_request(candidate);
             }
         }
+        if let Some(match_result) = match_result {
+            self.create_match(match_result)?;
+        }
 
         Ok(())
     }
@@ -487,7 +504,7 @@ impl MatchmakingManager {
                     let server = self.select_best_server(&[player1, player2])?;
                     
                     best_match = Some(MatchmakingResult {
-                        match_id: Uuid::new_v4().to_string(),
+                        match_id: String::new(), // 由create_match分配
                         players: vec![
                             MatchedPlayer {
                                 player_id: player1.player_id,
@@ -525,34 +542,35 @@ impl MatchmakingManager {
     }
 
     // 2v2匹配
-    fn find_2v2_match(&self, candidates: &[MatchmakingRequest]) -> GameResult<Option<MatchmakingResult>> {
+    fn find_2v2_match(&self, _candidates: &[MatchmakingRequest]) -> GameResult<Option<MatchmakingResult>> {
         // TODO: 实现2v2匹配逻辑
         // 需要考虑队伍平衡、组队玩家等
         Ok(None)
     }
 
     // 多人匹配
-    fn find_multi_match(&self, candidates: &[MatchmakingRequest]) -> GameResult<Option<MatchmakingResult>> {
+    fn find_multi_match(&self, _candidates: &[MatchmakingRequest]) -> GameResult<Option<MatchmakingResult>> {
         // TODO: 实现多人匹配逻辑
         Ok(None)
     }
 
     // 锦标赛匹配
-    fn find_tournament_match(&self, candidates: &[MatchmakingRequest]) -> GameResult<Option<MatchmakingResult>> {
+    fn find_tournament_match(&self, _candidates: &[MatchmakingRequest]) -> GameResult<Option<MatchmakingResult>> {
         // TODO: 实现锦标赛匹配逻辑
         Ok(None)
     }
 
     // 自定义匹配
-    fn find_custom_match(&self, candidates: &[MatchmakingRequest]) -> GameResult<Option<MatchmakingResult>> {
+    fn find_custom_match(&self, _candidates: &[MatchmakingRequest]) -> GameResult<Option<MatchmakingResult>> {
         // TODO: 实现自定义匹配逻辑
         Ok(None)
     }
 
     // 创建匹配
-    fn create_match(&mut self, match_result: MatchmakingResult) -> GameResult<()> {
+    fn create_match(&mut self, mut match_result: MatchmakingResult) -> GameResult<()> {
+        match_result.match_id = self.next_match_id();
         let match_id = match_result.match_id.clone();
-        let player_ids: Vec<Uuid> = match_result.players.iter().map(|p| p.player_id).collect();
+        let player_ids: Vec<PlayerId> = match_result.players.iter().map(|p| p.player_id).collect();
 
         // 更新玩家状态
         for player_id in &player_ids {
@@ -563,13 +581,14 @@ impl MatchmakingManager {
         self.completed_matches.insert(match_id.clone(), match_result);
 
         // 发送匹配成功事件
-        let _ = self.event_sender.send(MatchmakingEvent::MatchFound {
+        let player_count = player_ids.len();
+        self.events.push_back(MatchmakingEvent::MatchFound {
             match_id,
             players: player_ids,
         });
 
         self.stats.successful_matches += 1;
-        info!("创建匹配成功: 玩家数量 {}", player_ids.len());
+        info!("创建匹配成功: 玩家数量 {}", player_count);
 
         Ok(())
     }
@@ -666,12 +685,10 @@ impl MatchmakingManager {
             }
 
             let mut score = 0.0f32;
-            let mut total_ping = 0u32;
 
-            // 计算所有玩家到这个服务器的总延迟
+            // 计算所有玩家到这个服务器的延迟
             for player in players {
                 let ping = self.estimate_ping(player, server);
-                total_ping += ping;
                 
                 // 延迟惩罚
                 score -= (ping as f32 / 300.0) * self.config.ping_weight;
@@ -696,7 +713,7 @@ impl MatchmakingManager {
             }
         }
 
-        best_server.ok_or_else(|| GameError::Matchmaking("没有可用的服务器".to_string()))
+        best_server.ok_or_else(|| GameError::NetworkError("没有可用的服务器".to_string()))
     }
 
     // 估算延迟
@@ -736,24 +753,19 @@ impl MatchmakingManager {
 
     // 清理过期搜索
     fn cleanup_expired_searches(&mut self) -> GameResult<()> {
-        let expired_players: Vec<Uuid> = self.active_searches
-            .iter()
-            .filter_map(|(player_id, state)| {
-                if *state == MatchmakingState::Searching {
-                    // 检查是否超时
-                    // 这里需要从队列中获取请求时间
-                    Some(*player_id)
-                } else {
-                    None
-                }
-            })
+        let max_search_time = self.config.max_search_time;
+        let expired_players: Vec<PlayerId> = self.queues
+            .values()
+            .flat_map(|queue| queue.requests.iter().chain(queue.priority_requests.iter()))
+            .filter(|request| request.timestamp.elapsed() >= max_search_time)
+            .map(|request| request.player_id)
             .collect();
 
         for player_id in expired_players {
             self.leave_queue(player_id)?;
             self.active_searches.insert(player_id, MatchmakingState::Failed);
             
-            let _ = self.event_sender.send(MatchmakingEvent::MatchFailed {
+            self.events.push_back(MatchmakingEvent::MatchFailed {
                 reason: "搜索超时".to_string(),
             });
             
@@ -843,7 +855,7 @@ impl MatchmakingManager {
     }
 
     // 获取匹配状态
-    pub fn get_player_state(&self, player_id: Uuid) -> Option<MatchmakingState> {
+    pub fn get_player_state(&self, player_id: PlayerId) -> Option<MatchmakingState> {
         self.active_searches.get(&player_id).copied()
     }
 
@@ -863,6 +875,16 @@ impl MatchmakingManager {
         &self.stats
     }
 
+    // 取出下一条匹配事件，由网络层转发给玩家
+    pub fn poll_event(&mut self) -> Option<MatchmakingEvent> {
+        self.events.pop_front()
+    }
+
+    fn next_match_id(&mut self) -> String {
+        self.match_counter += 1;
+        format!("match_{}", self.match_counter)
+    }
+
     // 设置服务器状态
     pub fn set_server_status(&mut self, server_id: &str, status: ServerStatus) -> GameResult<()> {
         if let Some(server) = self.servers.get_mut(server_id) {
@@ -891,15 +913,15 @@ pub fn matchmaking_system(
 // 便捷函数
 impl MatchmakingManager {
     // 快速匹配（使用默认偏好）
-    pub fn quick_match(&mut self, player_id: Uuid, game_mode: GameMode, rating: u32) -> GameResult<()> {
+    pub fn quick_match(&mut self, player_id: PlayerId, game_mode: GameMode, rating: u32) -> GameResult<()> {
         self.join_queue(player_id, game_mode, rating, MatchmakingPreferences::default(), None)
     }
 
     // 组队匹配
     pub fn team_match(
         &mut self, 
-        team_leader: Uuid,
-        team_members: Vec<Uuid>,
+        team_leader: PlayerId,
+        team_members: Vec<PlayerId>,
         game_mode: GameMode,
         rating: u32
     ) -> GameResult<()> {
@@ -934,4 +956,269 @@ impl MatchmakingManager {
         let current_hour = chrono::Local::now().hour() as u8;
         self.queues.values().any(|queue| queue.peak_hours.contains(&current_hour))
     }
-}
\ No newline at end of file
+}
+
+// ---------------------------------------------------------------------------
+// 对战房间匹配
+// 联机对战需要先把水平相近、规则一致的玩家凑到一起，等太久的玩家要能得到明确的结果而不是一直转圈
+// 队列按进入顺序处理，先来的玩家优先配对分差最小的对手；配对成功后建立房间并固定双方约定的战斗规则
+// 时间由调用方传入，入队和超时判断使用同一时钟
+
+// 玩家的匹配条件
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchCriteria {
+    pub rating: u32,
+    pub max_rating_gap: u32,        // 能接受的最大分差
+    pub battle_type: BattleType,
+    pub level_cap: Option<u8>,
+}
+
+impl MatchCriteria {
+    // 规则必须一致，分差要在双方都能接受的范围内
+    pub fn is_compatible(&self, other: &MatchCriteria) -> bool {
+        self.battle_type == other.battle_type
+            && self.level_cap == other.level_cap
+            && self.rating.abs_diff(other.rating) <= self.max_rating_gap.min(other.max_rating_gap)
+    }
+
+    // 双方约定的战斗规则
+    fn battle_config(&self) -> BattleConfig {
+        BattleConfig {
+            battle_type: self.battle_type,
+            battle_format: BattleFormat::Online,
+            level_cap: self.level_cap,
+            // 在线对战的等级上限按平级规则处理，高等级的宝可梦临时降到上限
+            level_cap_mode: LevelCapMode::Scale,
+            ..BattleConfig::default()
+        }
+    }
+}
+
+// 对战房间
+#[derive(Debug, Clone)]
+pub struct GameRoom {
+    pub room_id: RoomId,
+    pub players: [PlayerId; 2],
+    pub config: BattleConfig,
+    pub created_at: Instant,
+}
+
+// 对战匹配的结果，每个相关玩家各收到一条
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueueEvent {
+    MatchFound { player: PlayerId, opponent: PlayerId, room_id: RoomId },
+    TimedOut { player: PlayerId },
+    Cancelled { player: PlayerId },
+}
+
+#[derive(Debug, Clone)]
+struct QueuedPlayer {
+    player: PlayerId,
+    criteria: MatchCriteria,
+    queued_at: Instant,
+}
+
+pub struct MatchmakingService {
+    timeout: Duration,
+    queue: VecDeque<QueuedPlayer>,
+    rooms: HashMap<RoomId, GameRoom>,
+    next_room_id: RoomId,
+    events: VecDeque<QueueEvent>,
+    stats: MatchmakingStats,
+}
+
+impl MatchmakingService {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            queue: VecDeque::new(),
+            rooms: HashMap::new(),
+            next_room_id: 1,
+            events: VecDeque::new(),
+            stats: MatchmakingStats::default(),
+        }
+    }
+
+    // 加入匹配队列，已在队列或房间中的玩家不能重复加入；now与update使用同一时钟
+    pub fn queue(&mut self, player: PlayerId, criteria: MatchCriteria, now: Instant) -> GameResult<()> {
+        if self.is_queued(player) {
+            return Err(GameError::NetworkError(format!("玩家 {} 已在匹配队列中", player)));
+        }
+        if self.room_of(player).is_some() {
+            return Err(GameError::NetworkError(format!("玩家 {} 已在对战房间中", player)));
+        }
+
+        debug!("玩家 {} 加入匹配 (分数: {})", player, criteria.rating);
+        self.queue.push_back(QueuedPlayer {
+            player,
+            criteria,
+            queued_at: now,
+        });
+        self.stats.total_requests += 1;
+        self.stats.peak_concurrent_searches = self.stats.peak_concurrent_searches.max(self.queue.len());
+        Ok(())
+    }
+
+    // 退出匹配队列，返回玩家是否在队列中
+    pub fn cancel(&mut self, player: PlayerId) -> bool {
+        let Some(index) = self.queue.iter().position(|queued| queued.player == player) else {
+            return false;
+        };
+        self.queue.remove(index);
+        self.events.push_back(QueueEvent::Cancelled { player });
+        true
+    }
+
+    // 配对兼容的玩家，然后移除等待超时的玩家
+    pub fn update(&mut self, now: Instant) {
+        self.match_players(now);
+
+        let timeout = self.timeout;
+        let (expired, waiting): (Vec<_>, Vec<_>) = self.queue
+            .drain(..)
+            .partition(|queued| now.saturating_duration_since(queued.queued_at) >= timeout);
+        self.queue = waiting.into();
+
+        for queued in expired {
+            debug!("玩家 {} 匹配超时", queued.player);
+            self.stats.failed_matches += 1;
+            self.events.push_back(QueueEvent::TimedOut { player: queued.player });
+        }
+    }
+
+    pub fn poll_event(&mut self) -> Option<QueueEvent> {
+        self.events.pop_front()
+    }
+
+    pub fn is_queued(&self, player: PlayerId) -> bool {
+        self.queue.iter().any(|queued| queued.player == player)
+    }
+
+    pub fn queue_len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn stats(&self) -> &MatchmakingStats {
+        &self.stats
+    }
+
+    pub fn room(&self, room_id: RoomId) -> Option<&GameRoom> {
+        self.rooms.get(&room_id)
+    }
+
+    pub fn room_of(&self, player: PlayerId) -> Option<&GameRoom> {
+        self.rooms.values().find(|room| room.players.contains(&player))
+    }
+
+    // 对战结束后关闭房间，双方可以重新匹配
+    pub fn close_room(&mut self, room_id: RoomId) -> Option<GameRoom> {
+        self.rooms.remove(&room_id)
+    }
+
+    // 先来的玩家优先，在后面的玩家中选分差最小的兼容对手
+    fn match_players(&mut self, now: Instant) {
+        let mut index = 0;
+        while index < self.queue.len() {
+            let current = &self.queue[index];
+            let opponent = self.queue.iter()
+                .enumerate()
+                .skip(index + 1)
+                .filter(|(_, other)| current.criteria.is_compatible(&other.criteria))
+                .min_by_key(|(_, other)| current.criteria.rating.abs_diff(other.criteria.rating))
+                .map(|(other_index, _)| other_index);
+
+            match opponent {
+                Some(other_index) => {
+                    // 先移除后面的，前面的下标不受影响
+                    let second = self.queue.remove(other_index).unwrap();
+                    let first = self.queue.remove(index).unwrap();
+                    self.create_room(first, second, now);
+                },
+                None => index += 1,
+            }
+        }
+    }
+
+    fn create_room(&mut self, first: QueuedPlayer, second: QueuedPlayer, now: Instant) {
+        let room_id = self.next_room_id;
+        self.next_room_id += 1;
+
+        self.stats.successful_matches += 1;
+        self.rooms.insert(room_id, GameRoom {
+            room_id,
+            players: [first.player, second.player],
+            config: first.criteria.battle_config(),
+            created_at: now,
+        });
+        self.events.push_back(QueueEvent::MatchFound { player: first.player, opponent: second.player, room_id });
+        self.events.push_back(QueueEvent::MatchFound { player: second.player, opponent: first.player, room_id });
+        info!("匹配成功: {} vs {} (房间 {})", first.player, second.player, room_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn criteria(rating: u32, battle_type: BattleType) -> MatchCriteria {
+        MatchCriteria {
+            rating,
+            max_rating_gap: 100,
+            battle_type,
+            level_cap: Some(50),
+        }
+    }
+
+    #[test]
+    fn test_compatible_players_matched() {
+        let now = Instant::now();
+        let mut service = MatchmakingService::new(Duration::from_secs(30));
+        service.queue(1, criteria(1500, BattleType::Single), now).unwrap();
+        service.queue(2, criteria(1900, BattleType::Single), now).unwrap();
+        service.queue(3, criteria(1560, BattleType::Single), now).unwrap();
+        assert!(service.queue(1, criteria(1500, BattleType::Single), now).is_err());
+
+        service.update(now);
+        assert_eq!(service.poll_event(), Some(QueueEvent::MatchFound { player: 1, opponent: 3, room_id: 1 }));
+        assert_eq!(service.poll_event(), Some(QueueEvent::MatchFound { player: 3, opponent: 1, room_id: 1 }));
+        assert_eq!(service.poll_event(), None);
+
+        let room = service.room_of(3).unwrap();
+        assert_eq!(room.players, [1, 3]);
+        assert_eq!(room.config.battle_type, BattleType::Single);
+        assert_eq!(room.config.battle_format, BattleFormat::Online);
+        assert_eq!(room.config.level_cap, Some(50));
+
+        // 分差过大的玩家继续等待，可以主动取消
+        assert!(service.is_queued(2));
+        assert!(service.cancel(2));
+        assert_eq!(service.poll_event(), Some(QueueEvent::Cancelled { player: 2 }));
+        assert!(!service.cancel(2));
+        assert_eq!(service.stats().total_requests, 3);
+        assert_eq!(service.stats().successful_matches, 1);
+    }
+
+    #[test]
+    fn test_incompatible_pair_times_out() {
+        let timeout = Duration::from_secs(30);
+        let now = Instant::now();
+        let mut service = MatchmakingService::new(timeout);
+        service.queue(1, criteria(1500, BattleType::Single), now).unwrap();
+        service.queue(2, criteria(1500, BattleType::Double), now + Duration::from_secs(10)).unwrap();
+
+        // 超时按各自入队时间计算
+        service.update(now + timeout - Duration::from_secs(1));
+        assert_eq!(service.poll_event(), None);
+        assert_eq!(service.queue_len(), 2);
+
+        service.update(now + timeout);
+        assert_eq!(service.poll_event(), Some(QueueEvent::TimedOut { player: 1 }));
+        assert_eq!(service.poll_event(), None);
+
+        service.update(now + timeout + Duration::from_secs(10));
+        assert_eq!(service.poll_event(), Some(QueueEvent::TimedOut { player: 2 }));
+        assert_eq!(service.queue_len(), 0);
+        assert_eq!(service.stats().failed_matches, 2);
+        assert!(service.room_of(1).is_none());
+    }
+}
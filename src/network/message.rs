@@ -0,0 +1,340 @@
+// 协议消息编解码
+// 开发心理：Message特征只能序列化，收到的字节不知道该还原成哪种消息，也无法发现对端协议版本不一致或数据损坏
+// 设计原则：每条消息前加带版本、类型、标志、长度和校验和的消息头；按包类型注册解码函数，收到消息时查表还原成具体类型；压缩与否写在标志里，接收方按标志解压，两端的压缩设置不必一致
+
+use super::transport::{packet_type_from_byte, packet_type_to_byte};
+use super::{Message, PacketType};
+use crate::assets::compression::{CompressionConfig, Compressor, ZlibCompressor};
+use crate::core::{GameError, Result};
+use std::any::Any;
+use std::collections::HashMap;
+use std::io::Read;
+
+// 消息头：协议版本u32 + 包类型u8 + 标志u8 + 负载长度u32 + 校验和u32，均为大端
+pub const MESSAGE_HEADER_LEN: usize = 14;
+
+// 负载经过zlib压缩，长度和校验和针对压缩后的数据
+pub const FLAG_COMPRESSED: u8 = 0x01;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageHeader {
+    pub protocol_version: u32,
+    pub packet_type: PacketType,
+    pub flags: u8,
+    pub length: u32,
+    pub checksum: u32,
+}
+
+impl MessageHeader {
+    pub fn new(protocol_version: u32, packet_type: PacketType, flags: u8, payload: &[u8]) -> Self {
+        Self {
+            protocol_version,
+            packet_type,
+            flags,
+            length: payload.len() as u32,
+            checksum: checksum(payload),
+        }
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.flags & FLAG_COMPRESSED != 0
+    }
+
+    pub fn to_bytes(&self) -> [u8; MESSAGE_HEADER_LEN] {
+        let mut bytes = [0u8; MESSAGE_HEADER_LEN];
+        bytes[0..4].copy_from_slice(&self.protocol_version.to_be_bytes());
+        bytes[4] = packet_type_to_byte(self.packet_type);
+        bytes[5] = self.flags;
+        bytes[6..10].copy_from_slice(&self.length.to_be_bytes());
+        bytes[10..14].copy_from_slice(&self.checksum.to_be_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < MESSAGE_HEADER_LEN {
+            return Err(GameError::NetworkError(format!("消息头不完整: {} 字节", bytes.len())));
+        }
+        Ok(Self {
+            protocol_version: u32::from_be_bytes(bytes[0..4].try_into().unwrap()),
+            packet_type: packet_type_from_byte(bytes[4])?,
+            flags: bytes[5],
+            length: u32::from_be_bytes(bytes[6..10].try_into().unwrap()),
+            checksum: u32::from_be_bytes(bytes[10..14].try_into().unwrap()),
+        })
+    }
+}
+
+// 编码后的消息；saved_bytes为压缩节省的字节数，未压缩时为0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedMessage {
+    pub data: Vec<u8>,
+    pub saved_bytes: u64,
+}
+
+// 消息解码失败的原因；版本不一致需要断开连接，其余情况丢弃该消息即可
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    VersionMismatch { expected: u32, actual: u32 },
+    Malformed(String),
+}
+
+impl From<DecodeError> for GameError {
+    fn from(error: DecodeError) -> Self {
+        match error {
+            DecodeError::VersionMismatch { expected, actual } => {
+                GameError::NetworkError(format!("协议版本不一致: 期望 {}, 收到 {}", expected, actual))
+            },
+            DecodeError::Malformed(reason) => GameError::NetworkError(reason),
+        }
+    }
+}
+
+// FNV-1a，结果与平台和编译器版本无关
+pub fn checksum(data: &[u8]) -> u32 {
+    data.iter().fold(0x811C_9DC5u32, |hash, &byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193))
+}
+
+// 序列化消息并加上消息头，不压缩
+pub fn encode_message<T: Message>(protocol_version: u32, message: &T) -> Result<Vec<u8>> {
+    Ok(encode_payload(protocol_version, T::packet_type(), &message.serialize()?, None)?.data)
+}
+
+// 给已序列化的负载加上消息头；负载不小于compression_threshold时尝试压缩，压缩后不变小则原样发送
+pub fn encode_payload(
+    protocol_version: u32,
+    packet_type: PacketType,
+    payload: &[u8],
+    compression_threshold: Option<usize>,
+) -> Result<EncodedMessage> {
+    let compressed = match compression_threshold {
+        Some(threshold) if payload.len() >= threshold => {
+            Some(ZlibCompressor.compress(payload, &CompressionConfig::default())?)
+                .filter(|compressed| compressed.len() < payload.len())
+        },
+        _ => None,
+    };
+
+    let (flags, body, saved_bytes) = match &compressed {
+        Some(compressed) => (FLAG_COMPRESSED, compressed.as_slice(), (payload.len() - compressed.len()) as u64),
+        None => (0, payload, 0),
+    };
+
+    let mut data = Vec::with_capacity(MESSAGE_HEADER_LEN + body.len());
+    data.extend_from_slice(&MessageHeader::new(protocol_version, packet_type, flags, body).to_bytes());
+    data.extend_from_slice(body);
+    Ok(EncodedMessage { data, saved_bytes })
+}
+
+// 检查消息头并取出负载，压缩的负载会被解压；负载(解压后)超过max_payload_size的消息被拒绝
+pub fn decode_envelope(
+    protocol_version: u32,
+    data: &[u8],
+    max_payload_size: usize,
+) -> std::result::Result<(MessageHeader, Vec<u8>), DecodeError> {
+    let header = MessageHeader::from_bytes(data).map_err(|e| DecodeError::Malformed(e.to_string()))?;
+    if header.protocol_version != protocol_version {
+        return Err(DecodeError::VersionMismatch {
+            expected: protocol_version,
+            actual: header.protocol_version,
+        });
+    }
+
+    let payload = &data[MESSAGE_HEADER_LEN..];
+    if payload.len() != header.length as usize {
+        return Err(DecodeError::Malformed(format!("消息长度不符: 头部 {}, 实际 {}", header.length, payload.len())));
+    }
+    if payload.len() > max_payload_size {
+        return Err(DecodeError::Malformed(format!("消息过大: {} 字节", payload.len())));
+    }
+    if checksum(payload) != header.checksum {
+        return Err(DecodeError::Malformed("消息校验和错误".to_string()));
+    }
+    if header.is_compressed() {
+        return Ok((header, inflate_limited(payload, max_payload_size)?));
+    }
+    Ok((header, payload.to_vec()))
+}
+
+// 解压时最多读取limit + 1字节，超出即拒绝；很小的压缩包可以解出极大的数据，不能先全部解压再检查
+fn inflate_limited(compressed: &[u8], limit: usize) -> std::result::Result<Vec<u8>, DecodeError> {
+    let mut inflated = Vec::new();
+    flate2::read::ZlibDecoder::new(compressed)
+        .take(limit as u64 + 1)
+        .read_to_end(&mut inflated)
+        .map_err(|e| DecodeError::Malformed(format!("Zlib数据损坏: {}", e)))?;
+    if inflated.len() > limit {
+        return Err(DecodeError::Malformed(format!("解压后的消息超过 {} 字节", limit)));
+    }
+    Ok(inflated)
+}
+
+type DecodeFn = fn(&[u8]) -> Result<Box<dyn Any + Send>>;
+
+fn decode_boxed<T: Message + Send + 'static>(payload: &[u8]) -> Result<Box<dyn Any + Send>> {
+    Ok(Box::new(T::deserialize(payload)?))
+}
+
+// 包类型 -> 解码函数
+#[derive(Default)]
+pub struct MessageRegistry {
+    decoders: HashMap<PacketType, DecodeFn>,
+}
+
+impl MessageRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 同一包类型重复注册时后注册的生效
+    pub fn register<T: Message + Send + 'static>(&mut self) {
+        self.decoders.insert(T::packet_type(), decode_boxed::<T>);
+    }
+
+    pub fn is_registered(&self, packet_type: PacketType) -> bool {
+        self.decoders.contains_key(&packet_type)
+    }
+
+    // 解码为注册的具体类型，由调用方downcast
+    pub fn decode(&self, packet_type: PacketType, payload: &[u8]) -> Result<Box<dyn Any + Send>> {
+        let decoder = self.decoders
+            .get(&packet_type)
+            .ok_or_else(|| GameError::NetworkError(format!("未注册的包类型: {:?}", packet_type)))?;
+        decoder(payload)
+    }
+
+    // 检查消息头后解码整条消息
+    pub fn decode_message(
+        &self,
+        protocol_version: u32,
+        data: &[u8],
+        max_payload_size: usize,
+    ) -> Result<(PacketType, Box<dyn Any + Send>)> {
+        let (header, payload) = decode_envelope(protocol_version, data, max_payload_size)?;
+        Ok((header.packet_type, self.decode(header.packet_type, &payload)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct ChatLine {
+        sender: u64,
+        text: String,
+    }
+
+    impl Message for ChatLine {
+        fn packet_type() -> PacketType {
+            PacketType::Message
+        }
+
+        fn serialize(&self) -> Result<Vec<u8>> {
+            bincode::serialize(self).map_err(|e| GameError::NetworkError(e.to_string()))
+        }
+
+        fn deserialize(data: &[u8]) -> Result<Self> {
+            bincode::deserialize(data).map_err(|e| GameError::NetworkError(e.to_string()))
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Ping {
+        sent_at_ms: u64,
+    }
+
+    impl Message for Ping {
+        fn packet_type() -> PacketType {
+            PacketType::Heartbeat
+        }
+
+        fn serialize(&self) -> Result<Vec<u8>> {
+            Ok(self.sent_at_ms.to_be_bytes().to_vec())
+        }
+
+        fn deserialize(data: &[u8]) -> Result<Self> {
+            let bytes = data.try_into().map_err(|_| GameError::NetworkError("Ping长度错误".to_string()))?;
+            Ok(Self { sent_at_ms: u64::from_be_bytes(bytes) })
+        }
+    }
+
+    const LIMIT: usize = 65536;
+
+    fn registry() -> MessageRegistry {
+        let mut registry = MessageRegistry::new();
+        registry.register::<ChatLine>();
+        registry.register::<Ping>();
+        registry
+    }
+
+    #[test]
+    fn test_message_round_trip() {
+        let registry = registry();
+
+        let chat = ChatLine { sender: 3, text: "要交换宝可梦吗？".to_string() };
+        let (packet_type, decoded) = registry.decode_message(1, &encode_message(1, &chat).unwrap(), LIMIT).unwrap();
+        assert_eq!(packet_type, PacketType::Message);
+        assert_eq!(decoded.downcast_ref::<ChatLine>(), Some(&chat));
+
+        let ping = Ping { sent_at_ms: 123_456 };
+        let (packet_type, decoded) = registry.decode_message(1, &encode_message(1, &ping).unwrap(), LIMIT).unwrap();
+        assert_eq!(packet_type, PacketType::Heartbeat);
+        assert_eq!(decoded.downcast_ref::<Ping>(), Some(&ping));
+
+        // 没有注册的包类型无法解码
+        assert!(registry.decode(PacketType::Connect, &[]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_bad_envelope() {
+        let data = encode_message(2, &Ping { sent_at_ms: 1 }).unwrap();
+        assert_eq!(
+            decode_envelope(1, &data, LIMIT).unwrap_err(),
+            DecodeError::VersionMismatch { expected: 1, actual: 2 }
+        );
+
+        let mut corrupted = data.clone();
+        *corrupted.last_mut().unwrap() ^= 0xFF;
+        assert!(matches!(decode_envelope(2, &corrupted, LIMIT), Err(DecodeError::Malformed(_))));
+        assert!(matches!(decode_envelope(2, &data[..data.len() - 1], LIMIT), Err(DecodeError::Malformed(_))));
+        assert!(matches!(decode_envelope(2, &data[..4], LIMIT), Err(DecodeError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_large_payload_compressed_round_trip() {
+        let registry = registry();
+        let chat = ChatLine { sender: 1, text: "皮卡丘".repeat(2000) };
+        let payload = chat.serialize().unwrap();
+
+        let encoded = encode_payload(1, PacketType::Message, &payload, Some(512)).unwrap();
+        assert!(encoded.saved_bytes > 0);
+        assert_eq!(encoded.data.len() as u64 + encoded.saved_bytes, (MESSAGE_HEADER_LEN + payload.len()) as u64);
+
+        let (header, decoded) = decode_envelope(1, &encoded.data, LIMIT).unwrap();
+        assert!(header.is_compressed());
+        assert_eq!(decoded, payload);
+        let (_, message) = registry.decode_message(1, &encoded.data, LIMIT).unwrap();
+        assert_eq!(message.downcast_ref::<ChatLine>(), Some(&chat));
+
+        // 低于阈值的消息不压缩，关闭压缩的一端也能解开压缩过的消息
+        let small = encode_payload(1, PacketType::Heartbeat, &[1, 2, 3], Some(512)).unwrap();
+        assert_eq!(small.saved_bytes, 0);
+        assert!(!decode_envelope(1, &small.data, LIMIT).unwrap().0.is_compressed());
+    }
+
+    #[test]
+    fn test_rejects_oversized_inflated_payload() {
+        // 1MB的零压缩后只有约1KB，解压后远超上限
+        let bomb = vec![0u8; 1 << 20];
+        let encoded = encode_payload(1, PacketType::Message, &bomb, Some(512)).unwrap();
+        assert!(encoded.data.len() < LIMIT);
+        assert!(matches!(decode_envelope(1, &encoded.data, LIMIT), Err(DecodeError::Malformed(_))));
+        assert_eq!(decode_envelope(1, &encoded.data, bomb.len()).unwrap().1.len(), bomb.len());
+
+        // 未压缩的负载同样受上限约束
+        let plain = encode_payload(1, PacketType::Message, &[7; 100], None).unwrap();
+        assert!(decode_envelope(1, &plain.data, 99).is_err());
+        assert!(decode_envelope(1, &plain.data, 100).is_ok());
+    }
+}
@@ -6,19 +6,39 @@
 // pub mod client;
 // pub mod server;
 // pub mod protocol;
-// pub mod matchmaking;
+pub mod transport;
+pub mod heartbeat;
+pub mod encryption;
+pub mod message;
+pub mod reliability;
+pub mod rate_limit;
+pub mod snapshot;
+// 房间持有战斗规则，依赖战斗模块
+#[cfg(feature = "battle-wip")]
+pub mod matchmaking;
 
 // 重新导出主要类型 - 待模块实现后再启用
 // pub use client::{NetworkClient, ClientState, ConnectionStatus};
 // pub use server::{NetworkServer, ServerConfig, SessionManager};
 // pub use protocol::{Message, PacketType, MessageHandler, Serializable};
-// pub use matchmaking::{MatchmakingService, MatchRequest, GameRoom};
+pub use transport::{NetworkClient, NetworkServer, TransportEvent, SERVER_CONNECTION_ID};
+pub use message::{decode_envelope, encode_message, encode_payload, DecodeError, EncodedMessage, MessageHeader, MessageRegistry};
+pub use heartbeat::{ConnectionHealth, HeartbeatMonitor};
+pub use encryption::{HandshakeRole, KeyExchange, SessionCipher};
+pub use reliability::ReliableChannel;
+pub use rate_limit::TokenBucket;
+pub use snapshot::{EntitySnapshot, SnapshotAck, SnapshotBroadcaster, SnapshotBuffer, WorldSnapshot};
+#[cfg(feature = "battle-wip")]
+pub use matchmaking::{GameRoom, MatchCriteria, MatchmakingEvent, MatchmakingManager, MatchmakingService, QueueEvent};
 
 use crate::core::{GameError, Result};
 use crate::core::event_system::{Event, EventSystem, EventPriority};
+use crate::core::services::{ServiceGuard, Services};
+use crate::world::{EntityId, WorldEntity};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::{IpAddr, SocketAddr};
+use std::sync::MutexGuard;
 use std::time::{Duration, Instant, SystemTime};
 use log::{info, debug, warn, error};
 
@@ -29,6 +49,8 @@ pub enum PacketType {
     Message,
     Connect,
     Disconnect,
+    Snapshot,       // 实体状态快照
+    SnapshotAck,    // 客户端对快照的确认
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -42,66 +64,13 @@ pub enum ConnectionStatus {
 pub trait Message {
     fn packet_type() -> PacketType;
     fn serialize(&self) -> Result<Vec<u8>>;
+    fn deserialize(data: &[u8]) -> Result<Self> where Self: Sized;
 }
 
-pub trait MessageHandler {
+pub trait MessageHandler: Send {
     fn handle_message(&self, connection_id: u64, data: &[u8]) -> Result<()>;
 }
 
-// 临时结构体定义
-pub struct NetworkClient;
-pub struct NetworkServer;
-
-impl NetworkClient {
-    pub fn new(_config: NetworkConfig) -> Result<Self> {
-        Ok(Self)
-    }
-    
-    pub fn connect(&mut self, _address: &str, _port: u16) -> Result<()> {
-        Ok(())
-    }
-    
-    pub fn disconnect(&mut self, _reason: DisconnectReason) -> Result<()> {
-        Ok(())
-    }
-    
-    pub fn update(&mut self, _delta_time: Duration) -> Result<()> {
-        Ok(())
-    }
-    
-    pub fn send(&mut self, _data: &[u8], _method: DeliveryMethod) -> Result<()> {
-        Ok(())
-    }
-    
-    pub fn get_status(&self) -> ConnectionStatus {
-        ConnectionStatus::Disconnected
-    }
-}
-
-impl NetworkServer {
-    pub fn new(_config: NetworkConfig) -> Result<Self> {
-        Ok(Self)
-    }
-    
-    pub fn update(&mut self, _delta_time: Duration) -> Result<()> {
-        Ok(())
-    }
-    
-    pub fn send_to_client(&mut self, _connection_id: u64, _data: &[u8], _method: DeliveryMethod) -> Result<()> {
-        Ok(())
-    }
-    
-    pub fn kick_client(&mut self, _connection_id: u64, _reason: &str) -> Result<()> {
-        Ok(())
-    }
-    
-    pub fn ban_address(&mut self, _addr: IpAddr, _duration: Duration, _reason: &str) -> Result<()> {
-        Ok(())
-    }
-    
-    pub fn shutdown(&mut self) {}
-}
-
 // 网络配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkConfig {
@@ -110,11 +79,16 @@ pub struct NetworkConfig {
     pub max_connections: u32,
     pub timeout_ms: u64,
     pub heartbeat_interval_ms: u64,
+    pub max_missed_heartbeats: u32,     // 连续这么多个心跳没有回应就断开
     pub max_packet_size: usize,
     pub enable_compression: bool,
-    pub enable_encryption: bool,
+    pub compression_threshold: usize,   // 不小于此大小的负载才尝试压缩
+    pub enable_encryption: bool,        // 连接时交换密钥并加密消息，拒绝不加密的对端
     pub protocol_version: u32,
     pub rate_limit: RateLimitConfig,
+    pub reliable_over_udp: bool,    // 可靠消息也走UDP，由可靠层重发和排序
+    pub resend_timeout_ms: u64,
+    pub max_resends: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -132,9 +106,11 @@ impl Default for NetworkConfig {
             server_port: 7777,
             max_connections: 1000,
             timeout_ms: 30000,
-            heartbeat_interval_ms: 5000,
+            heartbeat_interval_ms: crate::constants::HEARTBEAT_INTERVAL_MS,
+            max_missed_heartbeats: 3,
             max_packet_size: 65536,
             enable_compression: true,
+            compression_threshold: 512,
             enable_encryption: true,
             protocol_version: 1,
             rate_limit: RateLimitConfig {
@@ -143,6 +119,9 @@ impl Default for NetworkConfig {
                 burst_size: 50,
                 ban_duration_seconds: 300,
             },
+            reliable_over_udp: false,
+            resend_timeout_ms: 200,
+            max_resends: 10,
         }
     }
 }
@@ -155,6 +134,8 @@ pub struct NetworkStats {
     pub packets_sent: u64,
     pub packets_received: u64,
     pub packets_dropped: u64,
+    pub retransmits: u64,
+    pub compression_saved_bytes: u64,
     pub connection_attempts: u64,
     pub successful_connections: u64,
     pub failed_connections: u64,
@@ -172,6 +153,7 @@ pub struct ConnectionInfo {
     pub connected_at: SystemTime,
     pub last_activity: Instant,
     pub rtt_ms: f64,
+    pub jitter_ms: f64,
     pub packet_loss: f64,
     pub bytes_sent: u64,
     pub bytes_received: u64,
@@ -277,6 +259,7 @@ pub struct NetworkManager {
     stats: NetworkStats,
     connections: HashMap<u64, ConnectionInfo>,
     message_handlers: HashMap<PacketType, Box<dyn MessageHandler>>,
+    message_registry: MessageRegistry,
     
     // 客户端组件
     client: Option<NetworkClient>,
@@ -288,6 +271,10 @@ pub struct NetworkManager {
     outbound_queue: std::collections::VecDeque<QueuedMessage>,
     inbound_queue: std::collections::VecDeque<ReceivedMessage>,
     
+    // 实体快照同步：服务器按客户端确认生成增量，客户端缓存快照用于插值
+    snapshot_broadcaster: Option<SnapshotBroadcaster>,
+    snapshot_buffer: Option<SnapshotBuffer>,
+    
     // 性能监控
     last_stats_update: Instant,
     bytes_sent_last_second: u64,
@@ -321,6 +308,7 @@ impl NetworkManager {
             stats: NetworkStats::default(),
             connections: HashMap::new(),
             message_handlers: HashMap::new(),
+            message_registry: MessageRegistry::new(),
             
             client: None,
             server: None,
@@ -328,6 +316,9 @@ impl NetworkManager {
             outbound_queue: std::collections::VecDeque::new(),
             inbound_queue: std::collections::VecDeque::new(),
             
+            snapshot_broadcaster: None,
+            snapshot_buffer: None,
+            
             last_stats_update: Instant::now(),
             bytes_sent_last_second: 0,
             bytes_received_last_second: 0,
@@ -436,15 +427,80 @@ impl NetworkManager {
     }
     
     // 注册消息处理器
-    pub fn register_handler<T: Message + 'static>(
+    pub fn register_handler<T: Message + Send + 'static>(
         &mut self,
         handler: Box<dyn MessageHandler>,
     ) {
         let packet_type = T::packet_type();
+        self.message_registry.register::<T>();
         self.message_handlers.insert(packet_type, handler);
         debug!("注册消息处理器: {:?}", packet_type);
     }
     
+    // 服务器端开启快照广播
+    pub fn enable_snapshot_broadcast(&mut self, interval: Duration) {
+        self.message_registry.register::<WorldSnapshot>();
+        self.message_registry.register::<SnapshotAck>();
+        self.snapshot_broadcaster = Some(SnapshotBroadcaster::new(interval));
+    }
+    
+    // 客户端开启快照接收和插值
+    pub fn enable_snapshot_interpolation(&mut self, interpolation_delay: Duration, max_extrapolation: Duration) {
+        self.message_registry.register::<WorldSnapshot>();
+        self.message_registry.register::<SnapshotAck>();
+        self.snapshot_buffer = Some(SnapshotBuffer::new(interpolation_delay, max_extrapolation));
+    }
+    
+    // 客户端收到的快照，渲染时从这里取样
+    pub fn snapshot_buffer(&self) -> Option<&SnapshotBuffer> {
+        self.snapshot_buffer.as_ref()
+    }
+    
+    // 服务器每帧调用：到达广播间隔时给每个连接发送相对其最后确认快照的增量
+    pub fn broadcast_snapshots(&mut self, entities: &HashMap<EntityId, WorldEntity>, delta_time: Duration) -> Result<()> {
+        let Some(ref mut broadcaster) = self.snapshot_broadcaster else {
+            return Ok(());
+        };
+        if !broadcaster.update(entities, delta_time) {
+            return Ok(());
+        }
+        
+        let snapshots: Vec<(u64, WorldSnapshot)> = self.connections
+            .keys()
+            .filter_map(|&connection_id| broadcaster.snapshot_for(connection_id).map(|snapshot| (connection_id, snapshot)))
+            .collect();
+        for (connection_id, snapshot) in snapshots {
+            self.send_message(connection_id, &snapshot, MessagePriority::Normal, DeliveryMethod::UnreliableSequenced)?;
+        }
+        
+        Ok(())
+    }
+    
+    // 快照和快照确认由管理器自己处理，不经过注册的消息处理器
+    fn handle_snapshot_message(&mut self, message: &ReceivedMessage) -> Result<()> {
+        match message.packet_type {
+            PacketType::SnapshotAck => {
+                if let Some(ref mut broadcaster) = self.snapshot_broadcaster {
+                    let ack = <SnapshotAck as Message>::deserialize(&message.data)?;
+                    broadcaster.acknowledge(message.connection_id, ack.tick);
+                }
+            },
+            PacketType::Snapshot => {
+                let Some(ref mut buffer) = self.snapshot_buffer else {
+                    return Ok(());
+                };
+                let snapshot = <WorldSnapshot as Message>::deserialize(&message.data)?;
+                if buffer.push(&snapshot) {
+                    let ack = SnapshotAck { tick: snapshot.tick };
+                    self.send_message(message.connection_id, &ack, MessagePriority::High, DeliveryMethod::Unreliable)?;
+                }
+            },
+            _ => {},
+        }
+        
+        Ok(())
+    }
+    
     // 更新网络状态
     pub fn update(&mut self, delta_time: Duration) -> Result<()> {
         // 更新客户端
@@ -457,6 +513,9 @@ impl NetworkManager {
             server.update(delta_time)?;
         }
         
+        // 收取传输层的连接变化和消息
+        self.poll_transport();
+        
         // 处理发送队列
         self.process_outbound_queue()?;
         
@@ -472,6 +531,129 @@ impl NetworkManager {
         Ok(())
     }
     
+    // 服务器的连接变化同步到连接表，收到的消息放入接收队列
+    fn poll_transport(&mut self) {
+        let mut events = Vec::new();
+        let mut received = Vec::new();
+        
+        if let Some(ref mut server) = self.server {
+            while let Some(event) = server.poll_event() {
+                events.push(event);
+            }
+            while let Some(message) = server.recv() {
+                received.push(message);
+            }
+        }
+        
+        if let Some(ref mut client) = self.client {
+            while let Some(data) = client.recv() {
+                received.push((SERVER_CONNECTION_ID, data));
+            }
+        }
+        
+        self.stats.retransmits = self.server.as_ref().map_or(0, |server| server.retransmits())
+            + self.client.as_ref().map_or(0, |client| client.retransmits());
+        
+        // 心跳测得的往返时延；收到心跳回应也算连接活跃
+        if let Some(ref server) = self.server {
+            for (&connection_id, connection) in &mut self.connections {
+                if let Some(health) = server.connection_health(connection_id) {
+                    connection.rtt_ms = health.rtt_ms;
+                    connection.jitter_ms = health.jitter_ms;
+                    if let Some(last_reply) = health.last_reply {
+                        connection.last_activity = connection.last_activity.max(last_reply);
+                    }
+                }
+            }
+        }
+        if let Some(health) = self.client.as_ref().and_then(|client| client.health()) {
+            self.stats.average_rtt_ms = health.rtt_ms;
+        }
+        
+        for event in events {
+            match event {
+                TransportEvent::Connected { connection_id, address } => {
+                    self.add_connection(ConnectionInfo {
+                        connection_id,
+                        remote_address: address,
+                        connected_at: SystemTime::now(),
+                        last_activity: Instant::now(),
+                        rtt_ms: 0.0,
+                        jitter_ms: 0.0,
+                        packet_loss: 0.0,
+                        bytes_sent: 0,
+                        bytes_received: 0,
+                        is_authenticated: false,
+                        user_id: None,
+                        username: None,
+                    });
+                },
+                TransportEvent::Disconnected { connection_id, reason } => {
+                    self.remove_connection(connection_id, reason);
+                },
+            }
+        }
+        
+        for (connection_id, data) in received {
+            self.accept_raw_message(connection_id, &data);
+        }
+    }
+    
+    // 检查消息头，合法的消息放入接收队列；协议版本不一致时断开连接
+    fn accept_raw_message(&mut self, connection_id: u64, data: &[u8]) {
+        let (header, payload) = match decode_envelope(self.config.protocol_version, data, self.config.max_packet_size) {
+            Ok(envelope) => envelope,
+            Err(DecodeError::VersionMismatch { expected, actual }) => {
+                warn!("连接 {} 协议版本不一致: 期望 {}, 收到 {}", connection_id, expected, actual);
+                self.drop_for_protocol_error(connection_id);
+                return;
+            },
+            Err(DecodeError::Malformed(reason)) => {
+                self.stats.packets_dropped += 1;
+                warn!("丢弃连接 {} 的无效消息: {}", connection_id, reason);
+                return;
+            },
+        };
+        
+        // 已注册类型的消息必须能解码
+        if self.message_registry.is_registered(header.packet_type) {
+            if let Err(e) = self.message_registry.decode(header.packet_type, &payload) {
+                self.stats.packets_dropped += 1;
+                warn!("丢弃连接 {} 的无法解码的消息: {}", connection_id, e);
+                return;
+            }
+        }
+        
+        self.inbound_queue.push_back(ReceivedMessage {
+            connection_id,
+            packet_type: header.packet_type,
+            data: payload,
+            received_at: Instant::now(),
+        });
+    }
+    
+    fn drop_for_protocol_error(&mut self, connection_id: u64) {
+        if let Some(ref mut server) = self.server {
+            if let Err(e) = server.kick_client(connection_id, "协议版本不一致") {
+                debug!("断开连接 {} 失败: {}", connection_id, e);
+            }
+            self.remove_connection(connection_id, DisconnectReason::ProtocolError);
+        } else if let Some(ref mut client) = self.client {
+            let _ = client.disconnect(DisconnectReason::ProtocolError);
+            if let Err(e) = EventSystem::dispatch(NetworkDisconnectedEvent {
+                connection_id,
+                reason: DisconnectReason::ProtocolError,
+            }) {
+                warn!("发送断开连接事件失败: {}", e);
+            }
+        }
+    }
+    
+    // 获取消息解码注册表
+    pub fn message_registry(&self) -> &MessageRegistry {
+        &self.message_registry
+    }
+    
     // 处理发送队列
     fn process_outbound_queue(&mut self) -> Result<()> {
         // 按优先级排序
@@ -479,8 +661,8 @@ impl NetworkManager {
         messages.sort_by(|a, b| b.priority.cmp(&a.priority));
         
         for message in messages {
-            // 检查连接是否有效
-            if !self.connections.contains_key(&message.connection_id) {
+            // 检查连接是否有效(客户端只有一条到服务器的连接)
+            if self.server.is_some() && !self.connections.contains_key(&message.connection_id) {
                 continue;
             }
             
@@ -493,14 +675,23 @@ impl NetworkManager {
     
     // 发送原始消息
     fn send_raw_message(&mut self, message: QueuedMessage) -> Result<()> {
+        // 加消息头，开启压缩时较大的负载先压缩
+        let compression_threshold = self.config.enable_compression.then_some(self.config.compression_threshold);
+        let encoded = encode_payload(
+            self.config.protocol_version,
+            message.packet_type,
+            &message.data,
+            compression_threshold,
+        )?;
+        
         let result = if let Some(ref mut server) = self.server {
             server.send_to_client(
                 message.connection_id,
-                &message.data,
+                &encoded.data,
                 message.delivery_method,
             )
         } else if let Some(ref mut client) = self.client {
-            client.send(&message.data, message.delivery_method)
+            client.send(&encoded.data, message.delivery_method)
         } else {
             Err(GameError::NetworkError("没有可用的网络连接".to_string()))
         };
@@ -508,8 +699,9 @@ impl NetworkManager {
         match result {
             Ok(_) => {
                 self.stats.packets_sent += 1;
-                self.stats.bytes_sent += message.data.len() as u64;
-                self.bytes_sent_last_second += message.data.len() as u64;
+                self.stats.bytes_sent += encoded.data.len() as u64;
+                self.stats.compression_saved_bytes += encoded.saved_bytes;
+                self.bytes_sent_last_second += encoded.data.len() as u64;
             },
             Err(e) => {
                 self.stats.packets_dropped += 1;
@@ -544,6 +736,10 @@ impl NetworkManager {
             connection.bytes_received += message.data.len() as u64;
         }
         
+        if let Err(e) = self.handle_snapshot_message(&message) {
+            warn!("处理连接 {} 的快照消息失败: {}", message.connection_id, e);
+        }
+        
         // 查找消息处理器
         if let Some(handler) = self.message_handlers.get(&message.packet_type) {
             handler.handle_message(message.connection_id, &message.data)?;
@@ -623,6 +819,9 @@ impl NetworkManager {
     
     // 移除连接
     pub fn remove_connection(&mut self, connection_id: u64, reason: DisconnectReason) {
+        if let Some(ref mut broadcaster) = self.snapshot_broadcaster {
+            broadcaster.remove_client(connection_id);
+        }
         if let Some(_) = self.connections.remove(&connection_id) {
             // 发送断开连接事件
             if let Err(e) = EventSystem::dispatch(NetworkDisconnectedEvent {
@@ -727,34 +926,37 @@ impl Drop for NetworkManager {
 }
 
 // 全局网络管理器
-static mut NETWORK_MANAGER: Option<NetworkManager> = None;
-static NETWORK_INIT: std::sync::Once = std::sync::Once::new();
-
 pub struct Network;
 
+// 全局网络管理器的访问守卫，持有期间独占网络管理器
+pub type NetworkGuard = ServiceGuard<NetworkManager>;
+
 impl Network {
+    fn lock() -> Result<MutexGuard<'static, Option<NetworkManager>>> {
+        Services::exclusive::<NetworkManager>()
+            .lock()
+            .map_err(|_| GameError::NetworkError("网络系统锁已损坏".to_string()))
+    }
+    
     pub fn init(config: NetworkConfig) -> Result<()> {
-        unsafe {
-            NETWORK_INIT.call_once(|| {
-                NETWORK_MANAGER = Some(NetworkManager::new(config));
-            });
+        let mut manager = Self::lock()?;
+        if manager.is_none() {
+            *manager = Some(NetworkManager::new(config));
         }
         Ok(())
     }
     
-    pub fn instance() -> Result<&'static mut NetworkManager> {
-        unsafe {
-            NETWORK_MANAGER.as_mut()
-                .ok_or_else(|| GameError::NetworkError("网络系统未初始化".to_string()))
-        }
+    pub fn instance() -> Result<NetworkGuard> {
+        ServiceGuard::new(Self::lock()?)
+            .ok_or_else(|| GameError::NetworkError("网络系统未初始化".to_string()))
     }
     
     pub fn cleanup() {
-        unsafe {
-            if let Some(ref mut manager) = NETWORK_MANAGER {
-                manager.shutdown();
+        if let Ok(mut manager) = Self::lock() {
+            if let Some(ref mut network_manager) = *manager {
+                network_manager.shutdown();
             }
-            NETWORK_MANAGER = None;
+            *manager = None;
         }
     }
 }
@@ -815,4 +1017,49 @@ mod tests {
         assert!(is_local_address(IpAddr::from_str("192.168.1.1").unwrap()));
         assert!(!is_local_address(IpAddr::from_str("8.8.8.8").unwrap()));
     }
+    
+    #[test]
+    fn test_snapshot_broadcast_uses_acked_baseline() {
+        use crate::core::event_system::EventSystem;
+        
+        EventSystem::init().unwrap();
+        let mut manager = NetworkManager::new(NetworkConfig::default());
+        manager.enable_snapshot_broadcast(Duration::from_millis(50));
+        manager.add_connection(ConnectionInfo {
+            connection_id: 5,
+            remote_address: "127.0.0.1:7777".parse().unwrap(),
+            connected_at: SystemTime::now(),
+            last_activity: Instant::now(),
+            rtt_ms: 0.0,
+            jitter_ms: 0.0,
+            packet_loss: 0.0,
+            bytes_sent: 0,
+            bytes_received: 0,
+            is_authenticated: false,
+            user_id: None,
+            username: None,
+        });
+        
+        let sent_snapshot = |manager: &mut NetworkManager| {
+            manager.broadcast_snapshots(&HashMap::new(), Duration::from_millis(50)).unwrap();
+            let message = manager.outbound_queue.pop_back().unwrap();
+            assert_eq!(message.packet_type, PacketType::Snapshot);
+            <WorldSnapshot as Message>::deserialize(&message.data).unwrap()
+        };
+        
+        // 没有确认时发完整快照，确认后以确认的快照为基准
+        let first = sent_snapshot(&mut manager);
+        assert_eq!(first.baseline, None);
+        manager.handle_received_message(ReceivedMessage {
+            connection_id: 5,
+            packet_type: PacketType::SnapshotAck,
+            data: Message::serialize(&SnapshotAck { tick: first.tick }).unwrap(),
+            received_at: Instant::now(),
+        }).unwrap();
+        assert_eq!(sent_snapshot(&mut manager).baseline, Some(first.tick));
+        
+        // 断开后重新连上的同一ID从完整快照开始
+        manager.remove_connection(5, DisconnectReason::Timeout);
+        assert!(manager.snapshot_broadcaster.as_ref().unwrap().snapshot_for(5).unwrap().baseline.is_none());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,70 @@
+// 连接限流
+// 开发心理：RateLimitConfig一直只是配置项，恶意客户端可以无限制地刷包拖垮服务器
+// 设计原则：每个连接一个令牌桶，允许短时间突发，持续超速才判定违规；时间由调用方传入，方便测试
+
+use super::RateLimitConfig;
+use std::time::Instant;
+
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    refill_per_second: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    // 初始时桶是满的
+    pub fn new(capacity: u32, refill_per_second: u32, now: Instant) -> Self {
+        let capacity = capacity.max(1) as f64;
+        Self {
+            capacity,
+            refill_per_second: refill_per_second as f64,
+            tokens: capacity,
+            last_refill: now,
+        }
+    }
+
+    // 按包数限流：突发上限为burst_size，持续速率为max_packets_per_second
+    pub fn for_packets(config: &RateLimitConfig, now: Instant) -> Self {
+        Self::new(config.burst_size, config.max_packets_per_second, now)
+    }
+
+    // 取一个令牌，桶空时返回false
+    pub fn try_take(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_bucket_allows_burst_then_refills() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(3, 10, start);
+
+        assert!((0..3).all(|_| bucket.try_take(start)));
+        assert!(!bucket.try_take(start));
+
+        // 每秒补充10个，150毫秒后补回一个半
+        assert!(bucket.try_take(start + Duration::from_millis(150)));
+        assert!(!bucket.try_take(start + Duration::from_millis(150)));
+
+        // 补充不超过桶的容量
+        let later = start + Duration::from_secs(60);
+        assert!((0..3).all(|_| bucket.try_take(later)));
+        assert!(!bucket.try_take(later));
+    }
+}
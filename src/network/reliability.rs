@@ -0,0 +1,347 @@
+// UDP可靠传输层
+// 开发心理：ReliableOrdered只是一个枚举值，走UDP的消息丢了就是丢了，对战指令这类消息不能靠运气送达
+// 设计原则：与套接字无关，只处理字节和时间，方便模拟丢包测试；每种投递方式有独立的序号空间，可靠消息重发直到收到确认，有序消息缓存到缺口补齐再交付
+
+use super::DeliveryMethod;
+use crate::core::{GameError, Result};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::time::{Duration, Instant};
+
+// 包头：种类u8 + 投递方式u8 + 序号u32
+pub const RELIABLE_HEADER_LEN: usize = 6;
+
+const KIND_DATA: u8 = 0;
+const KIND_ACK: u8 = 1;
+
+// 序号为u32，单个连接发出四十亿条消息之前不会回绕，这里不处理回绕
+
+// 接收窗口：序号领先next_expected达到这个数的可靠包直接丢弃且不确认，等窗口前移后由对方重发；
+// 否则对端乱填的序号会让ahead和ordered_buffer无限增长
+pub const RECEIVE_WINDOW: u32 = 1024;
+
+fn method_to_byte(method: DeliveryMethod) -> u8 {
+    match method {
+        DeliveryMethod::Unreliable => 0,
+        DeliveryMethod::UnreliableSequenced => 1,
+        DeliveryMethod::Reliable => 2,
+        DeliveryMethod::ReliableOrdered => 3,
+    }
+}
+
+fn method_from_byte(byte: u8) -> Result<DeliveryMethod> {
+    match byte {
+        0 => Ok(DeliveryMethod::Unreliable),
+        1 => Ok(DeliveryMethod::UnreliableSequenced),
+        2 => Ok(DeliveryMethod::Reliable),
+        3 => Ok(DeliveryMethod::ReliableOrdered),
+        _ => Err(GameError::NetworkError(format!("未知的投递方式: {}", byte))),
+    }
+}
+
+fn encode_packet(kind: u8, method: DeliveryMethod, sequence: u32, payload: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(RELIABLE_HEADER_LEN + payload.len());
+    packet.push(kind);
+    packet.push(method_to_byte(method));
+    packet.extend_from_slice(&sequence.to_be_bytes());
+    packet.extend_from_slice(payload);
+    packet
+}
+
+#[derive(Debug, Clone)]
+struct PendingPacket {
+    packet: Vec<u8>,
+    last_sent: Instant,
+    resends: u32,
+}
+
+// 已收到的可靠序号：next_expected之前的全部收到，之后的记录在ahead中
+#[derive(Debug, Default)]
+struct ReceiveWindow {
+    next_expected: u32,
+    ahead: BTreeSet<u32>,
+}
+
+impl ReceiveWindow {
+    fn too_far_ahead(&self, sequence: u32) -> bool {
+        sequence >= self.next_expected.saturating_add(RECEIVE_WINDOW)
+    }
+
+    // 第一次收到该序号时返回true
+    fn mark(&mut self, sequence: u32) -> bool {
+        if sequence < self.next_expected || !self.ahead.insert(sequence) {
+            return false;
+        }
+        while self.ahead.remove(&self.next_expected) {
+            self.next_expected += 1;
+        }
+        true
+    }
+}
+
+// 一条连接两端各持有一个
+#[derive(Debug)]
+pub struct ReliableChannel {
+    resend_timeout: Duration,
+    max_resends: u32,
+    next_sequence: [u32; 4],                    // 按投递方式分开编号
+    pending: BTreeMap<(u8, u32), PendingPacket>, // (投递方式, 序号) -> 等待确认的包
+    outgoing_acks: VecDeque<Vec<u8>>,
+    reliable_window: ReceiveWindow,
+    ordered_window: ReceiveWindow,
+    ordered_buffer: BTreeMap<u32, Vec<u8>>,     // 缺口补齐前先到的有序消息
+    next_ordered: u32,
+    latest_sequenced: Option<u32>,
+    retransmits: u64,
+    failed: bool,
+}
+
+impl ReliableChannel {
+    pub fn new(resend_timeout: Duration, max_resends: u32) -> Self {
+        Self {
+            resend_timeout,
+            max_resends,
+            next_sequence: [0; 4],
+            pending: BTreeMap::new(),
+            outgoing_acks: VecDeque::new(),
+            reliable_window: ReceiveWindow::default(),
+            ordered_window: ReceiveWindow::default(),
+            ordered_buffer: BTreeMap::new(),
+            next_ordered: 0,
+            latest_sequenced: None,
+            retransmits: 0,
+            failed: false,
+        }
+    }
+
+    // 给负载加上包头，返回需要立即发送的包；可靠消息同时记入待确认列表
+    pub fn send(&mut self, method: DeliveryMethod, payload: &[u8], now: Instant) -> Vec<u8> {
+        let method_byte = method_to_byte(method);
+        let sequence = self.next_sequence[method_byte as usize];
+        self.next_sequence[method_byte as usize] += 1;
+
+        let packet = encode_packet(KIND_DATA, method, sequence, payload);
+        if is_reliable(method) {
+            self.pending.insert((method_byte, sequence), PendingPacket {
+                packet: packet.clone(),
+                last_sent: now,
+                resends: 0,
+            });
+        }
+        packet
+    }
+
+    // 处理收到的包，返回可以交付给上层的负载
+    pub fn receive(&mut self, packet: &[u8]) -> Result<Vec<Vec<u8>>> {
+        if packet.len() < RELIABLE_HEADER_LEN {
+            return Err(GameError::NetworkError("可靠层包头不完整".to_string()));
+        }
+        let kind = packet[0];
+        let method = method_from_byte(packet[1])?;
+        let sequence = u32::from_be_bytes(packet[2..6].try_into().unwrap());
+        let payload = &packet[RELIABLE_HEADER_LEN..];
+
+        if kind == KIND_ACK {
+            self.pending.remove(&(method_to_byte(method), sequence));
+            return Ok(Vec::new());
+        }
+        if kind != KIND_DATA {
+            return Err(GameError::NetworkError(format!("未知的可靠层包种类: {}", kind)));
+        }
+
+        let window = match method {
+            DeliveryMethod::Reliable => Some(&self.reliable_window),
+            DeliveryMethod::ReliableOrdered => Some(&self.ordered_window),
+            _ => None,
+        };
+        if window.is_some_and(|window| window.too_far_ahead(sequence)) {
+            return Ok(Vec::new());
+        }
+
+        // 重复的可靠包也要再确认一次，对方可能没收到上次的确认
+        if is_reliable(method) {
+            self.outgoing_acks.push_back(encode_packet(KIND_ACK, method, sequence, &[]));
+        }
+
+        let mut delivered = Vec::new();
+        match method {
+            DeliveryMethod::Unreliable => delivered.push(payload.to_vec()),
+            DeliveryMethod::UnreliableSequenced => {
+                // 比已交付的更旧的包直接丢弃
+                if self.latest_sequenced.is_none_or(|latest| sequence > latest) {
+                    self.latest_sequenced = Some(sequence);
+                    delivered.push(payload.to_vec());
+                }
+            },
+            DeliveryMethod::Reliable => {
+                if self.reliable_window.mark(sequence) {
+                    delivered.push(payload.to_vec());
+                }
+            },
+            DeliveryMethod::ReliableOrdered => {
+                if self.ordered_window.mark(sequence) {
+                    self.ordered_buffer.insert(sequence, payload.to_vec());
+                }
+                while let Some(payload) = self.ordered_buffer.remove(&self.next_ordered) {
+                    delivered.push(payload);
+                    self.next_ordered += 1;
+                }
+            },
+        }
+        Ok(delivered)
+    }
+
+    // 取出需要发送的确认和到期的重发包；重发次数用尽时通道标记为失败
+    pub fn poll(&mut self, now: Instant) -> Vec<Vec<u8>> {
+        let mut packets: Vec<Vec<u8>> = self.outgoing_acks.drain(..).collect();
+
+        for pending in self.pending.values_mut() {
+            if now.duration_since(pending.last_sent) < self.resend_timeout {
+                continue;
+            }
+            if pending.resends >= self.max_resends {
+                self.failed = true;
+                continue;
+            }
+            pending.resends += 1;
+            pending.last_sent = now;
+            self.retransmits += 1;
+            packets.push(pending.packet.clone());
+        }
+        packets
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn retransmits(&self) -> u64 {
+        self.retransmits
+    }
+
+    // 有可靠消息在最大重发次数后仍未确认
+    pub fn has_failed(&self) -> bool {
+        self.failed
+    }
+}
+
+pub(crate) fn is_reliable(method: DeliveryMethod) -> bool {
+    matches!(method, DeliveryMethod::Reliable | DeliveryMethod::ReliableOrdered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESEND: Duration = Duration::from_millis(100);
+
+    // 按丢包规则把packets交给接收端，返回交付的负载
+    fn deliver(
+        receiver: &mut ReliableChannel,
+        packets: Vec<Vec<u8>>,
+        mut drop: impl FnMut(&[u8]) -> bool,
+    ) -> Vec<Vec<u8>> {
+        packets.into_iter()
+            .filter(|packet| !drop(packet))
+            .flat_map(|packet| receiver.receive(&packet).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_ordered_delivery_survives_drops() {
+        let mut sender = ReliableChannel::new(RESEND, 5);
+        let mut receiver = ReliableChannel::new(RESEND, 5);
+        let start = Instant::now();
+
+        let packets: Vec<_> = (0u8..5)
+            .map(|i| sender.send(DeliveryMethod::ReliableOrdered, &[i], start))
+            .collect();
+
+        // 丢掉第1和第3条，后到的先缓存
+        let mut sent = 0;
+        let delivered = deliver(&mut receiver, packets, |_| {
+            sent += 1;
+            sent == 2 || sent == 4
+        });
+        assert_eq!(delivered, vec![vec![0]]);
+
+        // 确认送回发送端，超时后只重发未确认的两条
+        deliver(&mut sender, receiver.poll(start), |_| false);
+        assert_eq!(sender.pending_count(), 2);
+        assert!(sender.poll(start + RESEND / 2).is_empty());
+
+        let resent = sender.poll(start + RESEND);
+        assert_eq!(resent.len(), 2);
+        assert_eq!(sender.retransmits(), 2);
+
+        let delivered = deliver(&mut receiver, resent, |_| false);
+        assert_eq!(delivered, vec![vec![1], vec![2], vec![3], vec![4]]);
+        deliver(&mut sender, receiver.poll(start + RESEND), |_| false);
+        assert_eq!(sender.pending_count(), 0);
+        assert!(!sender.has_failed());
+    }
+
+    #[test]
+    fn test_lost_ack_does_not_duplicate() {
+        let mut sender = ReliableChannel::new(RESEND, 5);
+        let mut receiver = ReliableChannel::new(RESEND, 5);
+        let start = Instant::now();
+
+        let packet = sender.send(DeliveryMethod::Reliable, b"swap", start);
+        assert_eq!(deliver(&mut receiver, vec![packet], |_| false), vec![b"swap".to_vec()]);
+
+        // 确认丢失，重发的包再次被确认但不重复交付
+        deliver(&mut sender, receiver.poll(start), |_| true);
+        let resent = sender.poll(start + RESEND);
+        assert!(deliver(&mut receiver, resent, |_| false).is_empty());
+        deliver(&mut sender, receiver.poll(start + RESEND), |_| false);
+        assert_eq!(sender.pending_count(), 0);
+
+        // 不可靠消息不需要确认，也不会重发
+        let packet = sender.send(DeliveryMethod::Unreliable, b"pos", start);
+        assert_eq!(deliver(&mut receiver, vec![packet], |_| false), vec![b"pos".to_vec()]);
+        assert!(receiver.poll(start).is_empty());
+        assert!(sender.poll(start + RESEND * 10).is_empty());
+    }
+
+    #[test]
+    fn test_sequenced_drops_stale_and_reliable_gives_up() {
+        let mut sender = ReliableChannel::new(RESEND, 2);
+        let mut receiver = ReliableChannel::new(RESEND, 2);
+        let start = Instant::now();
+
+        let old = sender.send(DeliveryMethod::UnreliableSequenced, b"old", start);
+        let new = sender.send(DeliveryMethod::UnreliableSequenced, b"new", start);
+        assert_eq!(deliver(&mut receiver, vec![new, old], |_| false), vec![b"new".to_vec()]);
+
+        // 对方一直收不到时重发两次后放弃
+        sender.send(DeliveryMethod::Reliable, b"lost", start);
+        for round in 1..=3 {
+            sender.poll(start + RESEND * round);
+        }
+        assert_eq!(sender.retransmits(), 2);
+        assert!(sender.has_failed());
+    }
+
+    #[test]
+    fn test_drops_packets_beyond_receive_window() {
+        let mut receiver = ReliableChannel::new(RESEND, 5);
+        let start = Instant::now();
+
+        // 领先整整一个窗口的有序包不缓存也不确认
+        let far = encode_packet(KIND_DATA, DeliveryMethod::ReliableOrdered, RECEIVE_WINDOW, b"far");
+        assert!(receiver.receive(&far).unwrap().is_empty());
+        assert!(receiver.ordered_buffer.is_empty());
+        assert!(receiver.poll(start).is_empty());
+
+        // 窗口内最远的包照常缓存并确认
+        let edge = encode_packet(KIND_DATA, DeliveryMethod::ReliableOrdered, RECEIVE_WINDOW - 1, b"edge");
+        assert!(receiver.receive(&edge).unwrap().is_empty());
+        assert_eq!(receiver.ordered_buffer.len(), 1);
+        assert_eq!(receiver.poll(start).len(), 1);
+
+        let far = encode_packet(KIND_DATA, DeliveryMethod::Reliable, u32::MAX, b"far");
+        assert!(receiver.receive(&far).unwrap().is_empty());
+        assert!(receiver.reliable_window.ahead.is_empty());
+        assert!(receiver.poll(start).is_empty());
+    }
+}
@@ -0,0 +1,459 @@
+// 实体状态快照与插值
+// 开发心理：联机大地图上其他玩家的位置按网络包到达的节奏跳动，包一抖动画面就一顿一顿的
+// 设计原则：服务器定时广播相对各客户端最后确认快照的增量，没有确认时发完整快照；客户端缓存若干快照，渲染时往回退一个插值延迟，在前后两个快照之间插值；丢包导致快照断档时按最后的速度短暂外推
+
+use super::{Message, PacketType};
+use crate::core::{GameError, Result};
+use crate::world::{EntityId, WorldEntity};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::f32::consts::{PI, TAU};
+use std::time::Duration;
+
+// 单个实体需要同步的状态
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EntitySnapshot {
+    pub entity_id: EntityId,
+    pub position: Vec3,
+    pub rotation: f32,
+    pub active: bool,
+}
+
+impl EntitySnapshot {
+    pub fn from_entity(entity: &WorldEntity) -> Self {
+        Self {
+            entity_id: entity.id,
+            position: entity.position,
+            rotation: entity.rotation,
+            active: entity.active,
+        }
+    }
+}
+
+// 服务器保留的历史状态数量，客户端确认的快照早于这个范围时改发完整快照
+const SNAPSHOT_HISTORY: usize = 32;
+
+// 一次广播的增量：只包含相对基准快照变化的实体
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    pub tick: u64,
+    pub baseline: Option<u64>,  // 增量相对的快照tick，None表示完整快照
+    pub server_time: f64,       // 服务器时间(秒)
+    pub changed: Vec<EntitySnapshot>,
+    pub removed: Vec<EntityId>,
+}
+
+impl Message for WorldSnapshot {
+    fn packet_type() -> PacketType {
+        PacketType::Snapshot
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| GameError::NetworkError(format!("快照序列化失败: {}", e)))
+    }
+
+    fn deserialize(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data).map_err(|e| GameError::NetworkError(format!("快照反序列化失败: {}", e)))
+    }
+}
+
+// 客户端收到快照后回复确认，服务器之后以它为基准生成增量
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotAck {
+    pub tick: u64,
+}
+
+impl Message for SnapshotAck {
+    fn packet_type() -> PacketType {
+        PacketType::SnapshotAck
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| GameError::NetworkError(format!("快照确认序列化失败: {}", e)))
+    }
+
+    fn deserialize(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data).map_err(|e| GameError::NetworkError(format!("快照确认反序列化失败: {}", e)))
+    }
+}
+
+// 服务器端：按固定间隔记录完整状态，给每个客户端生成相对其最后确认快照的增量
+#[derive(Debug)]
+pub struct SnapshotBroadcaster {
+    interval: Duration,
+    since_last: Duration,
+    server_time: f64,
+    tick: u64,
+    history: VecDeque<(u64, HashMap<EntityId, EntitySnapshot>)>,   // 最近记录的完整状态，tick递增
+    acked: HashMap<u64, u64>,                                       // 客户端连接ID -> 最后确认的tick
+}
+
+impl SnapshotBroadcaster {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            since_last: interval,   // 第一次更新立即广播
+            server_time: 0.0,
+            tick: 0,
+            history: VecDeque::new(),
+            acked: HashMap::new(),
+        }
+    }
+
+    // 到达广播间隔时记录当前状态并返回true，之后用snapshot_for给各客户端取增量
+    pub fn update(&mut self, entities: &HashMap<EntityId, WorldEntity>, delta_time: Duration) -> bool {
+        self.server_time += delta_time.as_secs_f64();
+        self.since_last += delta_time;
+        if self.since_last < self.interval {
+            return false;
+        }
+        self.since_last = Duration::ZERO;
+        self.tick += 1;
+
+        let state = entities.values()
+            .map(|entity| (entity.id, EntitySnapshot::from_entity(entity)))
+            .collect();
+        self.history.push_back((self.tick, state));
+        while self.history.len() > SNAPSHOT_HISTORY {
+            self.history.pop_front();
+        }
+        true
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    // 当前状态相对客户端最后确认快照的增量；没有确认或确认的快照已过期时发完整快照。
+    // 没有变化也照常返回，客户端靠它推进时间线
+    pub fn snapshot_for(&self, client_id: u64) -> Option<WorldSnapshot> {
+        let (tick, current) = self.history.back()?;
+        let baseline = self.acked.get(&client_id)
+            .and_then(|acked| self.history.iter().find(|(tick, _)| tick == acked));
+
+        let (baseline, changed, removed) = match baseline {
+            Some((baseline_tick, previous)) => {
+                let changed = current.values()
+                    .filter(|snapshot| previous.get(&snapshot.entity_id) != Some(*snapshot))
+                    .copied()
+                    .collect();
+                let removed = previous.keys()
+                    .copied()
+                    .filter(|id| !current.contains_key(id))
+                    .collect();
+                (Some(*baseline_tick), changed, removed)
+            },
+            None => (None, current.values().copied().collect(), Vec::new()),
+        };
+
+        Some(WorldSnapshot {
+            tick: *tick,
+            baseline,
+            server_time: self.server_time,
+            changed,
+            removed,
+        })
+    }
+
+    // 只接受比已确认的更新、且服务器还记得的快照
+    pub fn acknowledge(&mut self, client_id: u64, tick: u64) {
+        if !self.history.iter().any(|(recorded, _)| *recorded == tick) {
+            return;
+        }
+        let acked = self.acked.entry(client_id).or_insert(tick);
+        *acked = (*acked).max(tick);
+    }
+
+    // 客户端断开后忘掉它的确认，重新连上时从完整快照开始
+    pub fn remove_client(&mut self, client_id: u64) {
+        self.acked.remove(&client_id);
+    }
+}
+
+// 增量合并后的完整状态
+#[derive(Debug, Clone)]
+struct BufferedSnapshot {
+    tick: u64,
+    server_time: f64,
+    entities: HashMap<EntityId, EntitySnapshot>,
+}
+
+// 客户端：缓存快照并按时间插值
+#[derive(Debug)]
+pub struct SnapshotBuffer {
+    snapshots: VecDeque<BufferedSnapshot>,
+    capacity: usize,
+    interpolation_delay: f64,
+    max_extrapolation: f64,
+}
+
+impl SnapshotBuffer {
+    // 插值延迟一般取两到三个广播间隔，保证渲染时刻前后都有快照
+    pub fn new(interpolation_delay: Duration, max_extrapolation: Duration) -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            capacity: 32,
+            interpolation_delay: interpolation_delay.as_secs_f64(),
+            max_extrapolation: max_extrapolation.as_secs_f64(),
+        }
+    }
+
+    // 乱序到达的旧快照和基准已不在缓存里的增量直接丢弃；返回是否接受，接受的快照需要回复确认
+    pub fn push(&mut self, snapshot: &WorldSnapshot) -> bool {
+        if self.snapshots.back().is_some_and(|latest| snapshot.server_time <= latest.server_time) {
+            return false;
+        }
+        let mut entities = match snapshot.baseline {
+            Some(baseline) => match self.snapshots.iter().find(|buffered| buffered.tick == baseline) {
+                Some(buffered) => buffered.entities.clone(),
+                None => return false,
+            },
+            None => HashMap::new(),
+        };
+
+        for entity in &snapshot.changed {
+            entities.insert(entity.entity_id, *entity);
+        }
+        for id in &snapshot.removed {
+            entities.remove(id);
+        }
+
+        self.snapshots.push_back(BufferedSnapshot {
+            tick: snapshot.tick,
+            server_time: snapshot.server_time,
+            entities,
+        });
+        while self.snapshots.len() > self.capacity {
+            self.snapshots.pop_front();
+        }
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn latest_server_time(&self) -> Option<f64> {
+        self.snapshots.back().map(|snapshot| snapshot.server_time)
+    }
+
+    // server_time为客户端估计的当前服务器时间，实际取样时刻要减去插值延迟
+    pub fn sample(&self, server_time: f64, entity_id: EntityId) -> Option<EntitySnapshot> {
+        let render_time = server_time - self.interpolation_delay;
+
+        let next_index = self.snapshots.iter().position(|snapshot| snapshot.server_time >= render_time);
+        match next_index {
+            // 比最早的快照还早，只能用最早的
+            Some(0) => self.snapshots[0].entities.get(&entity_id).copied(),
+            Some(index) => {
+                let from = &self.snapshots[index - 1];
+                let to = &self.snapshots[index];
+                let t = ((render_time - from.server_time) / (to.server_time - from.server_time)) as f32;
+                interpolate_entity(from, to, entity_id, t)
+            },
+            // 快照断档，按最后两帧的速度外推
+            None => self.extrapolate(render_time, entity_id),
+        }
+    }
+
+    // 所有实体在同一时刻的状态
+    pub fn sample_all(&self, server_time: f64) -> Vec<EntitySnapshot> {
+        let Some(latest) = self.snapshots.back() else {
+            return Vec::new();
+        };
+        latest.entities.keys()
+            .filter_map(|&id| self.sample(server_time, id))
+            .collect()
+    }
+
+    fn extrapolate(&self, render_time: f64, entity_id: EntityId) -> Option<EntitySnapshot> {
+        let latest = self.snapshots.back()?;
+        let current = *latest.entities.get(&entity_id)?;
+
+        let previous = self.snapshots.len()
+            .checked_sub(2)
+            .map(|index| &self.snapshots[index])
+            .filter(|previous| previous.entities.contains_key(&entity_id));
+        let Some(previous) = previous else {
+            return Some(current);
+        };
+
+        // 最多外推max_extrapolation，之后停在原地等新快照
+        let span = (latest.server_time - previous.server_time) as f32;
+        let ahead = (render_time - latest.server_time).min(self.max_extrapolation) as f32;
+        let velocity = (current.position - previous.entities[&entity_id].position) / span;
+        Some(EntitySnapshot {
+            position: current.position + velocity * ahead,
+            ..current
+        })
+    }
+}
+
+fn interpolate_entity(from: &BufferedSnapshot, to: &BufferedSnapshot, entity_id: EntityId, t: f32) -> Option<EntitySnapshot> {
+    match (from.entities.get(&entity_id), to.entities.get(&entity_id)) {
+        (Some(a), Some(b)) => Some(EntitySnapshot {
+            entity_id,
+            position: a.position.lerp(b.position, t),
+            rotation: lerp_angle(a.rotation, b.rotation, t),
+            active: if t < 1.0 { a.active } else { b.active },
+        }),
+        // 刚出现的实体从出现时刻开始显示，刚移除的实体显示到移除前
+        (None, Some(b)) => Some(*b),
+        (Some(a), None) => Some(*a),
+        (None, None) => None,
+    }
+}
+
+// 沿较短的方向插值角度(弧度)
+fn lerp_angle(from: f32, to: f32, t: f32) -> f32 {
+    let difference = (to - from + PI).rem_euclid(TAU) - PI;
+    from + difference * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(server_time: f64, x: f32) -> WorldSnapshot {
+        WorldSnapshot {
+            tick: (server_time * 10.0) as u64,
+            baseline: None,
+            server_time,
+            changed: vec![EntitySnapshot {
+                entity_id: 7,
+                position: Vec3::new(x, 0.0, 0.0),
+                rotation: 0.0,
+                active: true,
+            }],
+            removed: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_interpolates_between_snapshots() {
+        let mut buffer = SnapshotBuffer::new(Duration::from_millis(100), Duration::from_millis(250));
+        buffer.push(&snapshot(1.0, 0.0));
+        buffer.push(&snapshot(1.1, 10.0));
+
+        // 当前服务器时间减去100ms插值延迟后落在两个快照之间
+        let middle = buffer.sample(1.15, 7).unwrap();
+        assert!((middle.position.x - 5.0).abs() < 1e-3);
+        let quarter = buffer.sample(1.125, 7).unwrap();
+        assert!((quarter.position.x - 2.5).abs() < 1e-3);
+
+        // 早于最早快照时停在最早的位置
+        assert_eq!(buffer.sample(0.5, 7).unwrap().position.x, 0.0);
+
+        // 乱序到达的旧快照被丢弃
+        buffer.push(&snapshot(1.05, 99.0));
+        assert_eq!(buffer.len(), 2);
+    }
+
+    #[test]
+    fn test_extrapolates_on_packet_loss() {
+        let mut buffer = SnapshotBuffer::new(Duration::from_millis(100), Duration::from_millis(250));
+        buffer.push(&snapshot(1.0, 0.0));
+        buffer.push(&snapshot(1.1, 10.0));
+
+        // 之后的快照丢失，按每秒100的速度继续前进
+        let ahead = buffer.sample(1.25, 7).unwrap();
+        assert!((ahead.position.x - 15.0).abs() < 1e-3);
+
+        // 外推时间有上限
+        let capped = buffer.sample(5.0, 7).unwrap();
+        assert!((capped.position.x - 35.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_broadcaster_diffs_against_acked_snapshot() {
+        use crate::world::EntityType;
+        use glam::Vec2;
+
+        let mut entity = WorldEntity {
+            id: 1,
+            entity_type: EntityType::Player,
+            position: Vec3::ZERO,
+            rotation: 0.0,
+            scale: Vec2::ONE,
+            active: true,
+            persistent: false,
+            components: HashMap::new(),
+        };
+        let mut entities = HashMap::from([(1, entity.clone())]);
+
+        let mut broadcaster = SnapshotBroadcaster::new(Duration::from_millis(50));
+        assert!(broadcaster.snapshot_for(1).is_none());
+        assert!(broadcaster.update(&entities, Duration::from_millis(16)));
+        assert!(!broadcaster.update(&entities, Duration::from_millis(16)));
+
+        // 还没有确认，发完整快照
+        let first = broadcaster.snapshot_for(1).unwrap();
+        assert_eq!(first.baseline, None);
+        assert_eq!(first.changed.len(), 1);
+        broadcaster.acknowledge(1, first.tick);
+
+        // 相对已确认的快照没有变化
+        assert!(broadcaster.update(&entities, Duration::from_millis(40)));
+        let unchanged = broadcaster.snapshot_for(1).unwrap();
+        assert_eq!(unchanged.baseline, Some(first.tick));
+        assert!(unchanged.changed.is_empty());
+
+        // unchanged的确认丢失，下一次仍以first为基准，移动过的实体照样发出
+        entity.position.x = 3.0;
+        entities.insert(1, entity);
+        assert!(broadcaster.update(&entities, Duration::from_millis(50)));
+        let moved = broadcaster.snapshot_for(1).unwrap();
+        assert_eq!(moved.baseline, Some(first.tick));
+        assert_eq!(moved.changed[0].position.x, 3.0);
+
+        // 新加入的客户端拿到完整快照
+        let joined = broadcaster.snapshot_for(2).unwrap();
+        assert_eq!(joined.baseline, None);
+        assert_eq!(joined.changed.len(), 1);
+
+        // 未知的tick和比已确认更旧的tick都不改变基准
+        broadcaster.acknowledge(1, moved.tick);
+        broadcaster.acknowledge(1, first.tick);
+        broadcaster.acknowledge(1, 999);
+        entities.clear();
+        assert!(broadcaster.update(&entities, Duration::from_millis(50)));
+        let removed = broadcaster.snapshot_for(1).unwrap();
+        assert_eq!(removed.baseline, Some(moved.tick));
+        assert_eq!(removed.removed, vec![1]);
+
+        // 客户端按增量还原出的状态与服务器一致
+        let mut buffer = SnapshotBuffer::new(Duration::ZERO, Duration::ZERO);
+        for snapshot in [&first, &unchanged, &moved] {
+            let bytes = Message::serialize(snapshot).unwrap();
+            assert!(buffer.push(&<WorldSnapshot as Message>::deserialize(&bytes).unwrap()));
+        }
+        assert_eq!(buffer.sample(moved.server_time, 1).unwrap().position.x, 3.0);
+        assert!(buffer.push(&removed));
+        assert!(buffer.sample_all(removed.server_time).is_empty());
+
+        // 断开后重新从完整快照开始
+        broadcaster.remove_client(1);
+        assert_eq!(broadcaster.snapshot_for(1).unwrap().baseline, None);
+    }
+
+    #[test]
+    fn test_buffer_drops_delta_with_unknown_baseline() {
+        let mut buffer = SnapshotBuffer::new(Duration::ZERO, Duration::ZERO);
+        let mut delta = snapshot(1.0, 5.0);
+        delta.baseline = Some(42);
+        assert!(!buffer.push(&delta));
+        assert!(buffer.is_empty());
+
+        assert!(buffer.push(&snapshot(1.1, 0.0)));
+        delta.tick = 12;
+        delta.server_time = 1.2;
+        delta.baseline = Some(11);
+        assert!(buffer.push(&delta));
+        assert_eq!(buffer.sample(1.2, 7).unwrap().position.x, 5.0);
+    }
+}
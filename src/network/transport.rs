@@ -0,0 +1,1012 @@
+// 网络传输层
+// 开发心理：客户端和服务器原本只是返回Ok(())的空壳，联机功能无从测试，需要先有一个真正能收发数据的最小实现
+// 设计原则：可靠消息走TCP，不可靠消息走UDP；TCP上用长度前缀分帧，处理半包和粘包；套接字全部非阻塞，由游戏循环每帧轮询
+
+use super::encryption::{HandshakeRole, KeyExchange, SessionCipher, ENCRYPTION_OVERHEAD};
+use super::heartbeat::{ConnectionHealth, HeartbeatMonitor, HeartbeatPacket};
+use super::rate_limit::TokenBucket;
+use super::reliability::{is_reliable, ReliableChannel, RELIABLE_HEADER_LEN};
+use super::{ConnectionStatus, DeliveryMethod, DisconnectReason, NetworkConfig, PacketType};
+use crate::core::{GameError, Result};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+use log::{debug, info, warn};
+
+// 客户端一侧，服务器所在连接的ID；服务器分配的连接ID从1开始
+pub const SERVER_CONNECTION_ID: u64 = 0;
+
+// 帧头：4字节大端长度，长度包含1字节包类型
+const FRAME_HEADER_LEN: usize = 4;
+// 数据报头：8字节连接ID + 1字节包类型
+const DATAGRAM_HEADER_LEN: usize = 9;
+// UDP单个数据报的最大负载
+const MAX_DATAGRAM_LEN: usize = 65507;
+const READ_CHUNK_SIZE: usize = 4096;
+
+fn io_error(context: &str, error: io::Error) -> GameError {
+    GameError::NetworkError(format!("{}: {}", context, error))
+}
+
+pub(crate) fn packet_type_to_byte(packet_type: PacketType) -> u8 {
+    match packet_type {
+        PacketType::Heartbeat => 0,
+        PacketType::Message => 1,
+        PacketType::Connect => 2,
+        PacketType::Disconnect => 3,
+        PacketType::Snapshot => 4,
+        PacketType::SnapshotAck => 5,
+    }
+}
+
+pub(crate) fn packet_type_from_byte(byte: u8) -> Result<PacketType> {
+    match byte {
+        0 => Ok(PacketType::Heartbeat),
+        1 => Ok(PacketType::Message),
+        2 => Ok(PacketType::Connect),
+        3 => Ok(PacketType::Disconnect),
+        4 => Ok(PacketType::Snapshot),
+        5 => Ok(PacketType::SnapshotAck),
+        _ => Err(GameError::NetworkError(format!("未知的包类型: {}", byte))),
+    }
+}
+
+// 可靠通道上的一帧: [长度u32][包类型u8][数据]
+pub fn encode_frame(packet_type: PacketType, data: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + 1 + data.len());
+    frame.extend_from_slice(&(data.len() as u32 + 1).to_be_bytes());
+    frame.push(packet_type_to_byte(packet_type));
+    frame.extend_from_slice(data);
+    frame
+}
+
+// 不可靠通道上的一个数据报: [连接ID u64][包类型u8][数据]
+fn encode_datagram(connection_id: u64, packet_type: PacketType, data: &[u8]) -> Vec<u8> {
+    let mut datagram = Vec::with_capacity(DATAGRAM_HEADER_LEN + data.len());
+    datagram.extend_from_slice(&connection_id.to_be_bytes());
+    datagram.push(packet_type_to_byte(packet_type));
+    datagram.extend_from_slice(data);
+    datagram
+}
+
+fn decode_datagram(datagram: &[u8]) -> Result<(u64, PacketType, Vec<u8>)> {
+    if datagram.len() < DATAGRAM_HEADER_LEN {
+        return Err(GameError::NetworkError("数据报过短".to_string()));
+    }
+    let connection_id = u64::from_be_bytes(datagram[..8].try_into().unwrap());
+    let packet_type = packet_type_from_byte(datagram[8])?;
+    Ok((connection_id, packet_type, datagram[DATAGRAM_HEADER_LEN..].to_vec()))
+}
+
+// 从字节流中拆出完整的帧，不完整的部分留到下次读取后再拼接
+#[derive(Debug)]
+pub struct FrameDecoder {
+    buffer: Vec<u8>,
+    max_frame_size: usize,
+}
+
+impl FrameDecoder {
+    pub fn new(max_frame_size: usize) -> Self {
+        Self {
+            buffer: Vec::new(),
+            max_frame_size,
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    // 长度为0或超过上限时视为协议错误
+    pub fn next_frame(&mut self) -> Result<Option<(PacketType, Vec<u8>)>> {
+        if self.buffer.len() < FRAME_HEADER_LEN {
+            return Ok(None);
+        }
+
+        let len = u32::from_be_bytes(self.buffer[..FRAME_HEADER_LEN].try_into().unwrap()) as usize;
+        if len == 0 || len > self.max_frame_size + 1 {
+            return Err(GameError::NetworkError(format!("非法的帧长度: {}", len)));
+        }
+        if self.buffer.len() < FRAME_HEADER_LEN + len {
+            return Ok(None);
+        }
+
+        let frame: Vec<u8> = self.buffer.drain(..FRAME_HEADER_LEN + len).skip(FRAME_HEADER_LEN).collect();
+        let packet_type = packet_type_from_byte(frame[0])?;
+        Ok(Some((packet_type, frame[1..].to_vec())))
+    }
+}
+
+// 一条非阻塞TCP连接，一次写不完的数据留在缓冲区，下次更新时继续写
+#[derive(Debug)]
+struct TcpChannel {
+    stream: TcpStream,
+    decoder: FrameDecoder,
+    write_buffer: Vec<u8>,
+}
+
+impl TcpChannel {
+    fn new(stream: TcpStream, max_packet_size: usize) -> Result<Self> {
+        stream.set_nonblocking(true).map_err(|e| io_error("设置非阻塞失败", e))?;
+        stream.set_nodelay(true).map_err(|e| io_error("设置TCP_NODELAY失败", e))?;
+        Ok(Self {
+            stream,
+            decoder: FrameDecoder::new(max_packet_size),
+            write_buffer: Vec::new(),
+        })
+    }
+
+    fn send(&mut self, packet_type: PacketType, data: &[u8]) -> Result<()> {
+        self.write_buffer.extend_from_slice(&encode_frame(packet_type, data));
+        self.flush()
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        while !self.write_buffer.is_empty() {
+            match self.stream.write(&self.write_buffer) {
+                Ok(0) => return Err(GameError::NetworkError("连接已关闭".to_string())),
+                Ok(written) => {
+                    self.write_buffer.drain(..written);
+                },
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(io_error("发送失败", e)),
+            }
+        }
+        Ok(())
+    }
+
+    // 读出所有已到达的数据并拆帧；对端关闭连接时返回false
+    fn receive(&mut self, frames: &mut Vec<(PacketType, Vec<u8>)>) -> Result<bool> {
+        let mut chunk = [0u8; READ_CHUNK_SIZE];
+        let open = loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => break false,
+                Ok(read) => self.decoder.push(&chunk[..read]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break true,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(io_error("接收失败", e)),
+            }
+        };
+
+        while let Some(frame) = self.decoder.next_frame()? {
+            frames.push(frame);
+        }
+        Ok(open)
+    }
+
+    fn close(mut self, packet: Option<(PacketType, &[u8])>) {
+        if let Some((packet_type, data)) = packet {
+            let _ = self.send(packet_type, data);
+        }
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+// 加密后的消息比明文长，帧长上限要留出余量
+fn encryption_overhead(config: &NetworkConfig) -> usize {
+    if config.enable_encryption { ENCRYPTION_OVERHEAD } else { 0 }
+}
+
+fn max_frame_size(config: &NetworkConfig) -> usize {
+    config.max_packet_size + encryption_overhead(config)
+}
+
+fn check_packet_size(config: &NetworkConfig, data: &[u8], method: DeliveryMethod) -> Result<()> {
+    if data.len() > config.max_packet_size {
+        return Err(GameError::NetworkError(format!("消息过大: {} 字节", data.len())));
+    }
+    let datagram_len = data.len() + DATAGRAM_HEADER_LEN + RELIABLE_HEADER_LEN + encryption_overhead(config);
+    if uses_udp(config, method) && datagram_len > MAX_DATAGRAM_LEN {
+        return Err(GameError::NetworkError(format!("消息超过UDP数据报上限: {} 字节", data.len())));
+    }
+    Ok(())
+}
+
+// 开启reliable_over_udp时可靠消息也走UDP，由可靠层负责重发和排序
+fn uses_udp(config: &NetworkConfig, method: DeliveryMethod) -> bool {
+    !is_reliable(method) || config.reliable_over_udp
+}
+
+fn new_reliable_channel(config: &NetworkConfig) -> ReliableChannel {
+    ReliableChannel::new(Duration::from_millis(config.resend_timeout_ms), config.max_resends)
+}
+
+fn new_heartbeat_monitor(config: &NetworkConfig) -> HeartbeatMonitor {
+    HeartbeatMonitor::new(Duration::from_millis(config.heartbeat_interval_ms), config.max_missed_heartbeats)
+}
+
+// 要求加密时，握手完成前的消息既不能发出也不能接受
+fn seal(cipher: Option<&mut SessionCipher>, required: bool, data: &[u8]) -> Result<Vec<u8>> {
+    match cipher {
+        Some(cipher) => cipher.encrypt(data),
+        None if required => Err(GameError::NetworkError("加密握手尚未完成".to_string())),
+        None => Ok(data.to_vec()),
+    }
+}
+
+fn open_payload(cipher: Option<&mut SessionCipher>, required: bool, data: Vec<u8>) -> Result<Vec<u8>> {
+    match cipher {
+        Some(cipher) => cipher.decrypt(&data),
+        None if required => Err(GameError::NetworkError("收到未加密的消息".to_string())),
+        None => Ok(data),
+    }
+}
+
+// 发送缓冲区满时直接丢弃，可靠层会在超时后重发
+fn send_datagram_to(udp: &UdpSocket, address: Option<SocketAddr>, datagram: &[u8]) -> Result<()> {
+    let result = match address {
+        Some(address) => udp.send_to(datagram, address),
+        None => udp.send(datagram),
+    };
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::WouldBlock => {
+            debug!("UDP发送缓冲区已满，丢弃数据报");
+            Ok(())
+        },
+        Err(e) => Err(io_error("UDP发送失败", e)),
+    }
+}
+
+// 网络客户端：连接后先等待服务器通过TCP分配连接ID，之后才能发送UDP数据报
+pub struct NetworkClient {
+    config: NetworkConfig,
+    status: ConnectionStatus,
+    connection_id: Option<u64>,
+    tcp: Option<TcpChannel>,
+    udp: Option<UdpSocket>,
+    reliability: ReliableChannel,
+    received: VecDeque<Vec<u8>>,
+    heartbeat: HeartbeatMonitor,
+    cipher: Option<SessionCipher>,
+}
+
+impl NetworkClient {
+    pub fn new(config: NetworkConfig) -> Result<Self> {
+        Ok(Self {
+            reliability: new_reliable_channel(&config),
+            heartbeat: new_heartbeat_monitor(&config),
+            config,
+            status: ConnectionStatus::Disconnected,
+            connection_id: None,
+            tcp: None,
+            udp: None,
+            received: VecDeque::new(),
+            cipher: None,
+        })
+    }
+
+    pub fn connect(&mut self, address: &str, port: u16) -> Result<()> {
+        if self.status != ConnectionStatus::Disconnected {
+            return Err(GameError::NetworkError("客户端已连接".to_string()));
+        }
+
+        let server_address = (address, port)
+            .to_socket_addrs()
+            .map_err(|e| io_error("解析服务器地址失败", e))?
+            .next()
+            .ok_or_else(|| GameError::NetworkError(format!("无法解析服务器地址: {}", address)))?;
+
+        let timeout = Duration::from_millis(self.config.timeout_ms);
+        let stream = TcpStream::connect_timeout(&server_address, timeout)
+            .map_err(|e| io_error("连接服务器失败", e))?;
+
+        let local_ip = match server_address.ip() {
+            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        };
+        let udp = UdpSocket::bind((local_ip, 0)).map_err(|e| io_error("绑定UDP端口失败", e))?;
+        udp.connect(server_address).map_err(|e| io_error("UDP连接失败", e))?;
+        udp.set_nonblocking(true).map_err(|e| io_error("设置非阻塞失败", e))?;
+
+        self.tcp = Some(TcpChannel::new(stream, max_frame_size(&self.config))?);
+        self.udp = Some(udp);
+        self.reliability = new_reliable_channel(&self.config);
+        self.heartbeat = new_heartbeat_monitor(&self.config);
+        self.status = ConnectionStatus::Connecting;
+
+        info!("正在连接服务器: {}", server_address);
+        Ok(())
+    }
+
+    pub fn disconnect(&mut self, reason: DisconnectReason) -> Result<()> {
+        if let Some(tcp) = self.tcp.take() {
+            tcp.close(Some((PacketType::Disconnect, &[])));
+            debug!("断开连接: {:?}", reason);
+        }
+        self.reset();
+        Ok(())
+    }
+
+    pub fn update(&mut self, _delta_time: Duration) -> Result<()> {
+        let Some(tcp) = self.tcp.as_mut() else {
+            return Ok(());
+        };
+
+        let mut frames = Vec::new();
+        let open = match tcp.flush().and_then(|_| tcp.receive(&mut frames)) {
+            Ok(open) => open,
+            Err(e) => {
+                warn!("连接出错: {}", e);
+                false
+            },
+        };
+
+        let now = Instant::now();
+        let mut closed = !open;
+        let mut protocol_error = false;
+        for (packet_type, data) in frames {
+            match packet_type {
+                PacketType::Connect => {
+                    if let Err(e) = self.complete_handshake(&data) {
+                        warn!("握手失败: {}", e);
+                        protocol_error = true;
+                        break;
+                    }
+                },
+                PacketType::Message => match open_payload(self.cipher.as_mut(), self.config.enable_encryption, data) {
+                    Ok(message) => self.received.push_back(message),
+                    Err(e) => {
+                        warn!("服务器消息无法解密: {}", e);
+                        protocol_error = true;
+                        break;
+                    },
+                },
+                PacketType::Disconnect => closed = true,
+                // 快照类型只出现在消息头里，不会单独成帧
+                PacketType::Snapshot | PacketType::SnapshotAck => {
+                    warn!("收到意外的帧类型: {:?}", packet_type);
+                    protocol_error = true;
+                    break;
+                },
+                PacketType::Heartbeat => match HeartbeatPacket::decode(&data) {
+                    Some(HeartbeatPacket::Ping(sequence)) => {
+                        if let Err(e) = self.send_frame(PacketType::Heartbeat, &HeartbeatPacket::Pong(sequence).encode()) {
+                            warn!("回应心跳失败: {}", e);
+                            closed = true;
+                        }
+                    },
+                    Some(HeartbeatPacket::Pong(sequence)) => {
+                        self.heartbeat.on_pong(sequence, now);
+                    },
+                    None => {},
+                },
+            }
+        }
+
+        if protocol_error {
+            return self.disconnect(DisconnectReason::ProtocolError);
+        }
+        if closed {
+            info!("服务器关闭了连接");
+            self.tcp = None;
+            self.reset();
+            return Ok(());
+        }
+
+        self.receive_datagrams();
+
+        if self.status != ConnectionStatus::Connected {
+            return Ok(());
+        }
+
+        for packet in self.reliability.poll(Instant::now()) {
+            self.send_datagram(PacketType::Message, &packet)?;
+        }
+        if self.reliability.has_failed() {
+            warn!("可靠消息多次重发仍未确认，断开连接");
+            return self.disconnect(DisconnectReason::Timeout);
+        }
+
+        if self.heartbeat.is_timed_out(now) {
+            warn!("连续 {} 个心跳没有回应，断开连接", self.heartbeat.missed(now));
+            return self.disconnect(DisconnectReason::Timeout);
+        }
+        if let Some(ping) = self.heartbeat.poll(now) {
+            self.send_frame(PacketType::Heartbeat, &ping.encode())?;
+            self.send_keepalive()?;
+        }
+
+        Ok(())
+    }
+
+    // 默认可靠方式走TCP，不可靠方式经可靠层走UDP
+    pub fn send(&mut self, data: &[u8], method: DeliveryMethod) -> Result<()> {
+        check_packet_size(&self.config, data, method)?;
+        let data = seal(self.cipher.as_mut(), self.config.enable_encryption, data)?;
+
+        if !uses_udp(&self.config, method) {
+            return self.send_frame(PacketType::Message, &data);
+        }
+
+        if self.connection_id.is_none() {
+            return Err(GameError::NetworkError("尚未完成握手，无法发送UDP数据".to_string()));
+        }
+        let packet = self.reliability.send(method, &data, Instant::now());
+        self.send_datagram(PacketType::Message, &packet)
+    }
+
+    // 取出一条收到的消息
+    pub fn recv(&mut self) -> Option<Vec<u8>> {
+        self.received.pop_front()
+    }
+
+    pub fn get_status(&self) -> ConnectionStatus {
+        self.status
+    }
+
+    pub fn connection_id(&self) -> Option<u64> {
+        self.connection_id
+    }
+
+    // 本次连接中UDP可靠消息的重发次数
+    pub fn retransmits(&self) -> u64 {
+        self.reliability.retransmits()
+    }
+
+    // 到服务器的往返时延，收到第一个心跳回应前为None
+    pub fn health(&self) -> Option<ConnectionHealth> {
+        self.heartbeat.health()
+    }
+
+    // 服务器发来[连接ID u64]，要求加密时后面跟着服务器的公钥，客户端回复自己的公钥
+    fn complete_handshake(&mut self, data: &[u8]) -> Result<()> {
+        if data.len() < 8 {
+            return Err(GameError::NetworkError("握手数据无效".to_string()));
+        }
+        let (id_bytes, server_key) = data.split_at(8);
+        let connection_id = u64::from_be_bytes(id_bytes.try_into().unwrap());
+
+        if self.config.enable_encryption {
+            if server_key.is_empty() {
+                return Err(GameError::NetworkError("服务器不支持加密".to_string()));
+            }
+            let exchange = KeyExchange::new();
+            let public_key = exchange.public_key();
+            self.cipher = Some(exchange.finish(server_key, HandshakeRole::Client)?);
+            self.send_frame(PacketType::Connect, &public_key)?;
+        }
+
+        self.connection_id = Some(connection_id);
+        self.status = ConnectionStatus::Connected;
+        info!("已连接服务器，连接ID: {}", connection_id);
+
+        // 立即发一个保活包，让服务器记下本端的UDP地址
+        self.send_keepalive()
+    }
+
+    fn send_frame(&mut self, packet_type: PacketType, data: &[u8]) -> Result<()> {
+        self.tcp
+            .as_mut()
+            .ok_or_else(|| GameError::NetworkError("尚未连接服务器".to_string()))?
+            .send(packet_type, data)
+    }
+
+    // 空的UDP心跳，用于登记本端UDP地址和保持NAT映射
+    fn send_keepalive(&mut self) -> Result<()> {
+        self.send_datagram(PacketType::Heartbeat, &[])
+    }
+
+    fn send_datagram(&self, packet_type: PacketType, data: &[u8]) -> Result<()> {
+        let (Some(udp), Some(connection_id)) = (&self.udp, self.connection_id) else {
+            return Err(GameError::NetworkError("尚未完成握手，无法发送UDP数据".to_string()));
+        };
+        send_datagram_to(udp, None, &encode_datagram(connection_id, packet_type, data))
+    }
+
+    fn receive_datagrams(&mut self) {
+        let Some(udp) = &self.udp else {
+            return;
+        };
+
+        let mut buffer = vec![0u8; MAX_DATAGRAM_LEN];
+        loop {
+            match udp.recv(&mut buffer) {
+                Ok(len) => match decode_datagram(&buffer[..len]) {
+                    Ok((_, PacketType::Message, packet)) => match self.reliability.receive(&packet) {
+                        Ok(delivered) => {
+                            for payload in delivered {
+                                match open_payload(self.cipher.as_mut(), self.config.enable_encryption, payload) {
+                                    Ok(message) => self.received.push_back(message),
+                                    Err(e) => debug!("丢弃无法解密的数据报: {}", e),
+                                }
+                            }
+                        },
+                        Err(e) => debug!("丢弃无效数据报: {}", e),
+                    },
+                    Ok(_) => {},
+                    Err(e) => debug!("丢弃无效数据报: {}", e),
+                },
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    debug!("UDP接收失败: {}", e);
+                    break;
+                },
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.udp = None;
+        self.cipher = None;
+        self.connection_id = None;
+        self.status = ConnectionStatus::Disconnected;
+    }
+}
+
+// 服务器上连接的变化，由网络管理器取走
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransportEvent {
+    Connected { connection_id: u64, address: SocketAddr },
+    Disconnected { connection_id: u64, reason: DisconnectReason },
+}
+
+struct ServerConnection {
+    channel: TcpChannel,
+    address: SocketAddr,
+    udp_address: Option<SocketAddr>,    // 收到该连接的第一个数据报后记录
+    reliability: ReliableChannel,
+    rate_limiter: TokenBucket,          // 每个收到的包(含心跳)消耗一个令牌
+    heartbeat: HeartbeatMonitor,
+    key_exchange: Option<KeyExchange>,  // 等待客户端回复公钥
+    cipher: Option<SessionCipher>,
+    accepted_at: Instant,
+}
+
+// 网络服务器：TCP和UDP监听同一端口
+pub struct NetworkServer {
+    config: NetworkConfig,
+    listener: TcpListener,
+    udp: UdpSocket,
+    connections: HashMap<u64, ServerConnection>,
+    next_connection_id: u64,
+    banned: HashMap<IpAddr, Instant>,   // 地址 -> 解封时间
+    received: VecDeque<(u64, Vec<u8>)>,
+    events: VecDeque<TransportEvent>,
+    retransmits: u64,
+}
+
+impl NetworkServer {
+    // 端口为0时由系统分配，可通过local_addr查询
+    pub fn new(config: NetworkConfig) -> Result<Self> {
+        let listener = TcpListener::bind((config.server_address.as_str(), config.server_port))
+            .map_err(|e| io_error("监听TCP端口失败", e))?;
+        listener.set_nonblocking(true).map_err(|e| io_error("设置非阻塞失败", e))?;
+
+        let address = listener.local_addr().map_err(|e| io_error("获取监听地址失败", e))?;
+        let udp = UdpSocket::bind(address).map_err(|e| io_error("监听UDP端口失败", e))?;
+        udp.set_nonblocking(true).map_err(|e| io_error("设置非阻塞失败", e))?;
+
+        info!("服务器监听: {}", address);
+        Ok(Self {
+            config,
+            listener,
+            udp,
+            connections: HashMap::new(),
+            next_connection_id: SERVER_CONNECTION_ID + 1,
+            banned: HashMap::new(),
+            received: VecDeque::new(),
+            events: VecDeque::new(),
+            retransmits: 0,
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.listener.local_addr().map_err(|e| io_error("获取监听地址失败", e))
+    }
+
+    pub fn update(&mut self, _delta_time: Duration) -> Result<()> {
+        self.accept_connections();
+        self.expire_handshakes();
+        self.receive_frames();
+        self.receive_datagrams();
+        self.flush_reliability();
+        self.send_heartbeats();
+        Ok(())
+    }
+
+    pub fn send_to_client(&mut self, connection_id: u64, data: &[u8], method: DeliveryMethod) -> Result<()> {
+        check_packet_size(&self.config, data, method)?;
+
+        let connection = self.connections
+            .get_mut(&connection_id)
+            .ok_or_else(|| GameError::NetworkError(format!("连接不存在: {}", connection_id)))?;
+        let data = seal(connection.cipher.as_mut(), self.config.enable_encryption, data)?;
+
+        match connection.udp_address {
+            Some(udp_address) if uses_udp(&self.config, method) => {
+                let packet = connection.reliability.send(method, &data, Instant::now());
+                send_datagram_to(&self.udp, Some(udp_address), &encode_datagram(connection_id, PacketType::Message, &packet))
+            },
+            // 还不知道客户端的UDP地址时，走UDP的消息也改走TCP
+            _ => connection.channel.send(PacketType::Message, &data),
+        }
+    }
+
+    // 取出一条收到的消息及其来源连接
+    pub fn recv(&mut self) -> Option<(u64, Vec<u8>)> {
+        self.received.pop_front()
+    }
+
+    pub fn poll_event(&mut self) -> Option<TransportEvent> {
+        self.events.pop_front()
+    }
+
+    pub fn connection_count(&self) -> usize {
+        self.connections.len()
+    }
+
+    // 所有连接累计的UDP可靠消息重发次数
+    pub fn retransmits(&self) -> u64 {
+        self.retransmits
+    }
+
+    // 连接的往返时延，收到第一个心跳回应前为None
+    pub fn connection_health(&self, connection_id: u64) -> Option<ConnectionHealth> {
+        self.connections.get(&connection_id)?.heartbeat.health()
+    }
+
+    pub fn kick_client(&mut self, connection_id: u64, reason: &str) -> Result<()> {
+        let connection = self.connections
+            .remove(&connection_id)
+            .ok_or_else(|| GameError::NetworkError(format!("连接不存在: {}", connection_id)))?;
+        connection.channel.close(Some((PacketType::Disconnect, reason.as_bytes())));
+        info!("踢出连接 {}: {}", connection_id, reason);
+        Ok(())
+    }
+
+    // 封禁期间拒绝该地址的新连接，并断开其现有连接
+    pub fn ban_address(&mut self, addr: IpAddr, duration: Duration, reason: &str) -> Result<()> {
+        self.banned.insert(addr, Instant::now() + duration);
+
+        let banned_connections: Vec<u64> = self.connections.iter()
+            .filter(|(_, connection)| connection.address.ip() == addr)
+            .map(|(&connection_id, _)| connection_id)
+            .collect();
+        for connection_id in banned_connections {
+            self.kick_client(connection_id, reason)?;
+        }
+
+        info!("封禁地址 {} {:?}: {}", addr, duration, reason);
+        Ok(())
+    }
+
+    pub fn shutdown(&mut self) {
+        for (_, connection) in self.connections.drain() {
+            connection.channel.close(Some((PacketType::Disconnect, &[])));
+        }
+        self.received.clear();
+    }
+
+    fn accept_connections(&mut self) {
+        loop {
+            let (stream, address) = match self.listener.accept() {
+                Ok(accepted) => accepted,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    warn!("接受连接失败: {}", e);
+                    break;
+                },
+            };
+
+            self.banned.retain(|_, until| *until > Instant::now());
+            if self.banned.contains_key(&address.ip()) {
+                debug!("拒绝被封禁的地址: {}", address);
+                continue;
+            }
+            if self.connections.len() >= self.config.max_connections as usize {
+                warn!("连接数已满，拒绝: {}", address);
+                continue;
+            }
+
+            let connection_id = self.next_connection_id;
+            let mut channel = match TcpChannel::new(stream, max_frame_size(&self.config)) {
+                Ok(channel) => channel,
+                Err(e) => {
+                    warn!("初始化连接失败 {}: {}", address, e);
+                    continue;
+                },
+            };
+
+            // 握手: [连接ID u64]，要求加密时附上服务器的一次性公钥
+            let key_exchange = self.config.enable_encryption.then(KeyExchange::new);
+            let mut handshake = connection_id.to_be_bytes().to_vec();
+            if let Some(exchange) = &key_exchange {
+                handshake.extend_from_slice(&exchange.public_key());
+            }
+            if let Err(e) = channel.send(PacketType::Connect, &handshake) {
+                warn!("发送握手失败 {}: {}", address, e);
+                continue;
+            }
+
+            self.next_connection_id += 1;
+            // 加密连接在收到客户端公钥后才算建立
+            if key_exchange.is_none() {
+                self.events.push_back(TransportEvent::Connected { connection_id, address });
+            }
+            self.connections.insert(connection_id, ServerConnection {
+                channel,
+                address,
+                udp_address: None,
+                reliability: new_reliable_channel(&self.config),
+                rate_limiter: TokenBucket::for_packets(&self.config.rate_limit, Instant::now()),
+                heartbeat: new_heartbeat_monitor(&self.config),
+                key_exchange,
+                cipher: None,
+                accepted_at: Instant::now(),
+            });
+            debug!("新连接 {}: {}", connection_id, address);
+        }
+    }
+
+    fn receive_frames(&mut self) {
+        let now = Instant::now();
+        let mut closed = Vec::new();
+
+        for (&connection_id, connection) in &mut self.connections {
+            let mut frames = Vec::new();
+            let result = connection.channel.flush().and_then(|_| connection.channel.receive(&mut frames));
+
+            for (packet_type, data) in frames {
+                if !connection.rate_limiter.try_take(now) {
+                    closed.push((connection_id, DisconnectReason::RateLimited));
+                    break;
+                }
+                match packet_type {
+                    PacketType::Message => match open_payload(connection.cipher.as_mut(), self.config.enable_encryption, data) {
+                        Ok(message) => self.received.push_back((connection_id, message)),
+                        Err(e) => {
+                            warn!("连接 {} 的消息无法解密: {}", connection_id, e);
+                            closed.push((connection_id, DisconnectReason::ProtocolError));
+                            break;
+                        },
+                    },
+                    PacketType::Disconnect => closed.push((connection_id, DisconnectReason::UserRequested)),
+                    PacketType::Snapshot | PacketType::SnapshotAck => {
+                        warn!("连接 {} 发来意外的帧类型: {:?}", connection_id, packet_type);
+                        closed.push((connection_id, DisconnectReason::ProtocolError));
+                        break;
+                    },
+                    PacketType::Heartbeat => match HeartbeatPacket::decode(&data) {
+                        Some(HeartbeatPacket::Ping(sequence)) => {
+                            let pong = HeartbeatPacket::Pong(sequence).encode();
+                            if let Err(e) = connection.channel.send(PacketType::Heartbeat, &pong) {
+                                warn!("连接 {} 回应心跳失败: {}", connection_id, e);
+                                closed.push((connection_id, DisconnectReason::NetworkError));
+                                break;
+                            }
+                        },
+                        Some(HeartbeatPacket::Pong(sequence)) => {
+                            connection.heartbeat.on_pong(sequence, now);
+                        },
+                        None => {},
+                    },
+                    PacketType::Connect => {
+                        let Some(exchange) = connection.key_exchange.take() else {
+                            continue;
+                        };
+                        match exchange.finish(&data, HandshakeRole::Server) {
+                            Ok(cipher) => {
+                                connection.cipher = Some(cipher);
+                                self.events.push_back(TransportEvent::Connected { connection_id, address: connection.address });
+                                debug!("连接 {} 完成加密握手", connection_id);
+                            },
+                            Err(e) => {
+                                warn!("连接 {} 握手失败: {}", connection_id, e);
+                                closed.push((connection_id, DisconnectReason::ProtocolError));
+                                break;
+                            },
+                        }
+                    },
+                }
+            }
+
+            match result {
+                Ok(true) => {},
+                Ok(false) => closed.push((connection_id, DisconnectReason::UserRequested)),
+                Err(e) => {
+                    warn!("连接 {} 出错: {}", connection_id, e);
+                    closed.push((connection_id, DisconnectReason::NetworkError));
+                },
+            }
+        }
+
+        for (connection_id, reason) in closed {
+            if reason == DisconnectReason::RateLimited {
+                self.disconnect_rate_limited(connection_id);
+            } else if let Some(connection) = self.connections.remove(&connection_id) {
+                connection.channel.close(None);
+                self.events.push_back(TransportEvent::Disconnected { connection_id, reason });
+                debug!("连接 {} 已断开: {:?}", connection_id, reason);
+            }
+        }
+    }
+
+    // 超速的连接被断开，其地址封禁ban_duration_seconds秒
+    fn disconnect_rate_limited(&mut self, connection_id: u64) {
+        let Some(connection) = self.connections.remove(&connection_id) else {
+            return;
+        };
+
+        let ban_duration = Duration::from_secs(self.config.rate_limit.ban_duration_seconds);
+        self.banned.insert(connection.address.ip(), Instant::now() + ban_duration);
+        connection.channel.close(Some((PacketType::Disconnect, "发送过于频繁".as_bytes())));
+        self.events.push_back(TransportEvent::Disconnected {
+            connection_id,
+            reason: DisconnectReason::RateLimited,
+        });
+        warn!("连接 {} ({}) 超过发送速率限制，封禁 {:?}", connection_id, connection.address, ban_duration);
+    }
+
+    fn receive_datagrams(&mut self) {
+        let mut buffer = vec![0u8; MAX_DATAGRAM_LEN];
+        loop {
+            let (len, source) = match self.udp.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    debug!("UDP接收失败: {}", e);
+                    break;
+                },
+            };
+
+            let (connection_id, packet_type, data) = match decode_datagram(&buffer[..len]) {
+                Ok(datagram) => datagram,
+                Err(e) => {
+                    debug!("丢弃无效数据报 {}: {}", source, e);
+                    continue;
+                },
+            };
+
+            // 只接受来自该连接TCP地址同一主机的数据报
+            let Some(connection) = self.connections.get_mut(&connection_id)
+                .filter(|connection| connection.address.ip() == source.ip())
+            else {
+                debug!("丢弃未知连接的数据报: {} ({})", connection_id, source);
+                continue;
+            };
+
+            connection.udp_address = Some(source);
+            if !connection.rate_limiter.try_take(Instant::now()) {
+                self.disconnect_rate_limited(connection_id);
+                continue;
+            }
+            if packet_type != PacketType::Message {
+                continue;
+            }
+            match connection.reliability.receive(&data) {
+                Ok(delivered) => {
+                    for payload in delivered {
+                        match open_payload(connection.cipher.as_mut(), self.config.enable_encryption, payload) {
+                            Ok(message) => self.received.push_back((connection_id, message)),
+                            Err(e) => debug!("丢弃无法解密的数据报 {}: {}", source, e),
+                        }
+                    }
+                },
+                Err(e) => debug!("丢弃无效数据报 {}: {}", source, e),
+            }
+        }
+    }
+
+    // 超过timeout_ms仍未回复公钥的客户端视为不支持加密
+    fn expire_handshakes(&mut self) {
+        let timeout = Duration::from_millis(self.config.timeout_ms);
+        let now = Instant::now();
+        let expired: Vec<u64> = self.connections.iter()
+            .filter(|(_, connection)| {
+                connection.key_exchange.is_some() && now.saturating_duration_since(connection.accepted_at) >= timeout
+            })
+            .map(|(&connection_id, _)| connection_id)
+            .collect();
+
+        for connection_id in expired {
+            if let Some(connection) = self.connections.remove(&connection_id) {
+                warn!("连接 {} 未完成加密握手", connection_id);
+                connection.channel.close(Some((PacketType::Disconnect, "需要加密连接".as_bytes())));
+                self.events.push_back(TransportEvent::Disconnected {
+                    connection_id,
+                    reason: DisconnectReason::ProtocolError,
+                });
+            }
+        }
+    }
+
+    // 定时向每个连接发ping，连续多个没有回应的连接按超时断开
+    fn send_heartbeats(&mut self) {
+        let now = Instant::now();
+        let mut timed_out = Vec::new();
+
+        for (&connection_id, connection) in &mut self.connections {
+            if connection.heartbeat.is_timed_out(now) {
+                timed_out.push(connection_id);
+                continue;
+            }
+            if let Some(ping) = connection.heartbeat.poll(now) {
+                if let Err(e) = connection.channel.send(PacketType::Heartbeat, &ping.encode()) {
+                    debug!("连接 {} 发送心跳失败: {}", connection_id, e);
+                }
+            }
+        }
+
+        for connection_id in timed_out {
+            if let Some(connection) = self.connections.remove(&connection_id) {
+                warn!("连接 {} 心跳超时", connection_id);
+                connection.channel.close(Some((PacketType::Disconnect, &[])));
+                self.events.push_back(TransportEvent::Disconnected {
+                    connection_id,
+                    reason: DisconnectReason::Timeout,
+                });
+            }
+        }
+    }
+
+    // 发送确认和到期的重发；重发次数用尽的连接按超时断开
+    fn flush_reliability(&mut self) {
+        let now = Instant::now();
+        let mut failed = Vec::new();
+
+        for (&connection_id, connection) in &mut self.connections {
+            let Some(udp_address) = connection.udp_address else {
+                continue;
+            };
+
+            let before = connection.reliability.retransmits();
+            for packet in connection.reliability.poll(now) {
+                let datagram = encode_datagram(connection_id, PacketType::Message, &packet);
+                if let Err(e) = send_datagram_to(&self.udp, Some(udp_address), &datagram) {
+                    debug!("连接 {} 发送失败: {}", connection_id, e);
+                }
+            }
+            self.retransmits += connection.reliability.retransmits() - before;
+
+            if connection.reliability.has_failed() {
+                failed.push(connection_id);
+            }
+        }
+
+        for connection_id in failed {
+            if let Some(connection) = self.connections.remove(&connection_id) {
+                warn!("连接 {} 的可靠消息多次重发仍未确认", connection_id);
+                connection.channel.close(Some((PacketType::Disconnect, &[])));
+                self.events.push_back(TransportEvent::Disconnected {
+                    connection_id,
+                    reason: DisconnectReason::Timeout,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_decoder_handles_partial_and_joined_frames() {
+        let mut stream = encode_frame(PacketType::Message, b"hello");
+        stream.extend(encode_frame(PacketType::Heartbeat, b""));
+        stream.extend(encode_frame(PacketType::Message, b"world"));
+
+        // 逐字节送入，模拟最坏情况下的半包
+        let mut decoder = FrameDecoder::new(64);
+        let mut frames = Vec::new();
+        for byte in &stream {
+            decoder.push(std::slice::from_ref(byte));
+            while let Some(frame) = decoder.next_frame().unwrap() {
+                frames.push(frame);
+            }
+        }
+        assert_eq!(frames, vec![
+            (PacketType::Message, b"hello".to_vec()),
+            (PacketType::Heartbeat, Vec::new()),
+            (PacketType::Message, b"world".to_vec()),
+        ]);
+
+        // 超过上限的长度视为协议错误
+        let mut decoder = FrameDecoder::new(4);
+        decoder.push(&encode_frame(PacketType::Message, b"too long"));
+        assert!(decoder.next_frame().is_err());
+    }
+}
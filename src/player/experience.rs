@@ -0,0 +1,113 @@
+// 玩家经验曲线
+// 开发心理：升级节奏需要策划反复调整，经验公式写死在代码里每次都要重新编译
+// 设计原则：曲线作为可序列化的配置，统一提供"某等级所需总经验"和它的逆运算
+
+use serde::{Deserialize, Serialize};
+
+pub const MAX_PLAYER_LEVEL: u32 = 100;
+
+// 经验曲线，公式类曲线描述从level升到level+1所需的经验
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ExperienceCurve {
+    Linear { base: u64, increment: u64 },       // base + increment * (level - 1)
+    Quadratic { linear: u64, quadratic: u64 },  // linear * level + quadratic * level²
+    Table(Vec<u64>),                            // 第i项为升到i+2级所需的总经验，递增
+}
+
+impl Default for ExperienceCurve {
+    fn default() -> Self {
+        ExperienceCurve::Quadratic { linear: 1000, quadratic: 50 }
+    }
+}
+
+impl ExperienceCurve {
+    pub fn max_level(&self) -> u32 {
+        match self {
+            ExperienceCurve::Table(thresholds) => (thresholds.len() as u32 + 1).min(MAX_PLAYER_LEVEL),
+            _ => MAX_PLAYER_LEVEL,
+        }
+    }
+
+    // 从level升到下一级所需的经验，满级时为u64::MAX
+    pub fn experience_to_next(&self, level: u32) -> u64 {
+        let level = level.max(1);
+        if level >= self.max_level() {
+            return u64::MAX;
+        }
+        let l = level as u64;
+        match self {
+            ExperienceCurve::Linear { base, increment } => base.saturating_add(increment.saturating_mul(l - 1)),
+            ExperienceCurve::Quadratic { linear, quadratic } => {
+                linear.saturating_mul(l).saturating_add(quadratic.saturating_mul(l * l))
+            },
+            ExperienceCurve::Table(_) => {
+                self.experience_for_level(level + 1).saturating_sub(self.experience_for_level(level))
+            },
+        }
+    }
+
+    // 达到level所需的总经验，1级为0
+    pub fn experience_for_level(&self, level: u32) -> u64 {
+        let level = level.clamp(1, self.max_level());
+        match self {
+            ExperienceCurve::Table(thresholds) => {
+                if level == 1 { 0 } else { thresholds[level as usize - 2] }
+            },
+            _ => (1..level).fold(0u64, |total, l| total.saturating_add(self.experience_to_next(l))),
+        }
+    }
+
+    // 拥有total_experience总经验时的等级
+    pub fn level_for_experience(&self, total_experience: u64) -> u32 {
+        let mut level = 1;
+        let mut required = 0u64;
+        while level < self.max_level() {
+            required = required.saturating_add(self.experience_to_next(level));
+            if total_experience < required {
+                break;
+            }
+            level += 1;
+        }
+        level
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inverse_round_trip() {
+        let curves = [
+            ExperienceCurve::default(),
+            ExperienceCurve::Linear { base: 100, increment: 10 },
+            ExperienceCurve::Table(vec![50, 150, 400, 1000]),
+        ];
+
+        for curve in &curves {
+            for level in 1..=curve.max_level() {
+                let required = curve.experience_for_level(level);
+                assert_eq!(curve.level_for_experience(required), level, "{:?} 等级 {}", curve, level);
+                if level > 1 {
+                    assert_eq!(curve.level_for_experience(required - 1), level - 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_curves_match_formula() {
+        let quadratic = ExperienceCurve::default();
+        assert_eq!(quadratic.experience_to_next(1), 1050);
+        assert_eq!(quadratic.experience_for_level(3), 1050 + 2200);
+
+        let linear = ExperienceCurve::Linear { base: 100, increment: 10 };
+        assert_eq!(linear.experience_for_level(3), 100 + 110);
+
+        let table = ExperienceCurve::Table(vec![50, 150, 400]);
+        assert_eq!(table.max_level(), 4);
+        assert_eq!(table.experience_to_next(2), 100);
+        assert_eq!(table.experience_to_next(4), u64::MAX);
+        assert_eq!(table.level_for_experience(10_000), 4);
+    }
+}
@@ -4,9 +4,12 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use log::{debug, warn, error};
 use crate::core::error::GameError;
 
+pub type ItemId = u32;
+
 // 物品类型
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ItemType {
@@ -20,6 +23,34 @@ pub enum ItemType {
     Misc,          // 其他
 }
 
+// 背包口袋
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Pocket {
+    #[default]
+    Items,          // 道具
+    PokeBalls,      // 精灵球
+    TMs,            // 招式学习器
+    Berries,        // 树果
+    KeyItems,       // 重要道具
+}
+
+impl Pocket {
+    pub fn from_item_type(item_type: ItemType) -> Self {
+        match item_type {
+            ItemType::Pokeball => Pocket::PokeBalls,
+            ItemType::TM => Pocket::TMs,
+            ItemType::Berry => Pocket::Berries,
+            ItemType::KeyItem => Pocket::KeyItems,
+            _ => Pocket::Items,
+        }
+    }
+    
+    // 重要道具每种只能有一个，且不能丢弃
+    pub fn is_unique(self) -> bool {
+        self == Pocket::KeyItems
+    }
+}
+
 // 物品稀有度
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ItemRarity {
@@ -59,6 +90,8 @@ pub struct ItemEffect {
 pub struct InventoryItem {
     pub item_id: u32,
     pub quantity: u32,
+    #[serde(default)]
+    pub pocket: Pocket,
     pub obtained_date: std::time::SystemTime,
 }
 
@@ -66,7 +99,7 @@ pub struct InventoryItem {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Inventory {
     pub items: HashMap<u32, InventoryItem>,
-    pub capacity: HashMap<ItemType, u32>,     // 各类型容量限制
+    pub capacity: HashMap<Pocket, u32>,       // 各口袋可容纳的物品种类数
     pub sort_order: Vec<u32>,                 // 排序顺序
     
     // 统计
@@ -78,14 +111,11 @@ pub struct Inventory {
 impl Inventory {
     pub fn new() -> Self {
         let mut capacity = HashMap::new();
-        capacity.insert(ItemType::Pokeball, 50);
-        capacity.insert(ItemType::Medicine, 30);
-        capacity.insert(ItemType::Berry, 20);
-        capacity.insert(ItemType::TM, 20);
-        capacity.insert(ItemType::KeyItem, 50);
-        capacity.insert(ItemType::Battle, 20);
-        capacity.insert(ItemType::Evolution, 10);
-        capacity.insert(ItemType::Misc, 30);
+        capacity.insert(Pocket::Items, 50);
+        capacity.insert(Pocket::PokeBalls, 30);
+        capacity.insert(Pocket::TMs, 100);
+        capacity.insert(Pocket::Berries, 64);
+        capacity.insert(Pocket::KeyItems, 50);
         
         Self {
             items: HashMap::new(),
@@ -97,28 +127,48 @@ impl Inventory {
         }
     }
     
-    // 添加物品
-    pub fn add_item(&mut self, item_id: u32, quantity: u32, item_data: &Item) -> Result<u32, GameError> {
-        // 检查容量限制
-        let current_count = self.get_item_type_count(item_data.item_type);
-        let type_capacity = self.capacity.get(&item_data.item_type).unwrap_or(&50);
-        
-        if current_count >= *type_capacity && !self.items.contains_key(&item_id) {
-            return Err(GameError::Inventory("背包空间不足".to_string()));
+    // 添加物品，物品数据从默认物品数据库查询，返回实际添加的数量
+    pub fn add_item(&mut self, item_id: ItemId, quantity: u32) -> Result<u32, GameError> {
+        let item_database = ItemDatabase::global();
+        let item_data = item_database
+            .get_item(item_id)
+            .ok_or_else(|| GameError::Inventory(format!("未知物品: {}", item_id)))?;
+        self.add_item_with_data(item_id, quantity, item_data)
+    }
+    
+    // 按给定的物品数据添加，超出堆叠上限的部分不会加入
+    pub fn add_item_with_data(&mut self, item_id: ItemId, quantity: u32, item_data: &Item) -> Result<u32, GameError> {
+        if quantity == 0 {
+            return Ok(0);
         }
         
+        let pocket = Pocket::from_item_type(item_data.item_type);
+        let max_stack = if pocket.is_unique() { 1 } else { item_data.max_stack.max(1) };
+        
         let actual_quantity = if let Some(existing) = self.items.get_mut(&item_id) {
+            if pocket.is_unique() {
+                return Err(GameError::Inventory(format!("重要道具只能持有一个: {}", item_data.name)));
+            }
             // 物品已存在，增加数量
-            let max_can_add = item_data.max_stack - existing.quantity;
-            let added = quantity.min(max_can_add);
+            let room = max_stack.saturating_sub(existing.quantity);
+            if room == 0 {
+                return Err(GameError::Inventory(format!("{} 已达到堆叠上限 {}", item_data.name, max_stack)));
+            }
+            let added = quantity.min(room);
             existing.quantity += added;
             added
         } else {
-            // 新物品
-            let added = quantity.min(item_data.max_stack);
+            // 新物品需要口袋有空位
+            let pocket_capacity = self.pocket_capacity(pocket);
+            if self.pocket_slot_count(pocket) >= pocket_capacity {
+                return Err(GameError::Inventory(format!("{:?} 口袋已满 ({})", pocket, pocket_capacity)));
+            }
+            
+            let added = quantity.min(max_stack);
             self.items.insert(item_id, InventoryItem {
                 item_id,
                 quantity: added,
+                pocket,
                 obtained_date: std::time::SystemTime::now(),
             });
             
@@ -136,9 +186,25 @@ impl Inventory {
         Ok(actual_quantity)
     }
     
-    // 移除物品
-    pub fn remove_item(&mut self, item_id: u32, quantity: u32) -> Result<u32, GameError> {
+    // 还能放入多少个该物品，口袋已满或堆叠已满时为0
+    pub fn room_for(&self, item_id: ItemId, item_data: &Item) -> u32 {
+        let pocket = Pocket::from_item_type(item_data.item_type);
+        let max_stack = if pocket.is_unique() { 1 } else { item_data.max_stack.max(1) };
+        
+        match self.items.get(&item_id) {
+            Some(existing) => max_stack.saturating_sub(existing.quantity),
+            None if self.pocket_slot_count(pocket) < self.pocket_capacity(pocket) => max_stack,
+            None => 0,
+        }
+    }
+    
+    // 移除物品，重要道具不能移除
+    pub fn remove_item(&mut self, item_id: ItemId, quantity: u32) -> Result<u32, GameError> {
         if let Some(item) = self.items.get_mut(&item_id) {
+            if item.pocket.is_unique() {
+                return Err(GameError::Inventory(format!("重要道具不能丢弃: {}", item_id)));
+            }
+            
             let removed = quantity.min(item.quantity);
             item.quantity -= removed;
             
@@ -155,22 +221,54 @@ impl Inventory {
         }
     }
     
-    // 使用物品
+    // 使用物品，重要道具使用后不消耗
     pub fn use_item(&mut self, item_id: u32, quantity: u32) -> Result<u32, GameError> {
+        if self.items.get(&item_id).map_or(false, |item| item.pocket.is_unique()) {
+            debug!("使用重要道具: ID={}", item_id);
+            return Ok(0);
+        }
+        
         let used = self.remove_item(item_id, quantity)?;
         self.total_items_used += used;
         debug!("使用物品: ID={} 数量={}", item_id, used);
         Ok(used)
     }
     
+    // 物品数量
+    pub fn count(&self, item_id: ItemId) -> u32 {
+        self.items.get(&item_id).map_or(0, |item| item.quantity)
+    }
+    
+    // 是否至少拥有一个
+    pub fn has(&self, item_id: ItemId) -> bool {
+        self.count(item_id) > 0
+    }
+    
     // 获取物品数量
     pub fn get_item_quantity(&self, item_id: u32) -> u32 {
-        self.items.get(&item_id).map_or(0, |item| item.quantity)
+        self.count(item_id)
     }
     
     // 检查是否拥有物品
     pub fn has_item(&self, item_id: u32, quantity: u32) -> bool {
-        self.get_item_quantity(item_id) >= quantity
+        self.count(item_id) >= quantity
+    }
+    
+    // 口袋中的物品种类数
+    pub fn pocket_slot_count(&self, pocket: Pocket) -> u32 {
+        self.items.values().filter(|item| item.pocket == pocket).count() as u32
+    }
+    
+    pub fn pocket_capacity(&self, pocket: Pocket) -> u32 {
+        self.capacity.get(&pocket).copied().unwrap_or(50)
+    }
+    
+    // 口袋中的物品ID，按背包排序顺序
+    pub fn pocket_items(&self, pocket: Pocket) -> Vec<ItemId> {
+        self.sort_order.iter()
+            .filter(|id| self.items.get(id).map_or(false, |item| item.pocket == pocket))
+            .copied()
+            .collect()
     }
     
     // 获取按类型分组的物品
@@ -185,11 +283,9 @@ impl Inventory {
             .collect()
     }
     
-    // 获取某类型物品的数量
+    // 获取某类型物品所在口袋的种类数量
     pub fn get_item_type_count(&self, item_type: ItemType) -> u32 {
-        // 简化实现：返回该类型的唯一物品种类数量
-        // 实际可能需要考虑堆叠等复杂情况
-        self.items.len() as u32 // 简化版本
+        self.pocket_slot_count(Pocket::from_item_type(item_type))
     }
     
     // 排序背包
@@ -262,11 +358,21 @@ impl Inventory {
     }
 }
 
-// 物品数据库
+// 物品数据库，数据文件是物品数组，读入后按ID索引
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "Vec<Item>")]
 pub struct ItemDatabase {
     items: HashMap<u32, Item>,
 }
 
+impl From<Vec<Item>> for ItemDatabase {
+    fn from(items: Vec<Item>) -> Self {
+        Self {
+            items: items.into_iter().map(|item| (item.id, item)).collect(),
+        }
+    }
+}
+
 impl ItemDatabase {
     pub fn new() -> Self {
         let mut database = Self {
@@ -276,6 +382,11 @@ impl ItemDatabase {
         database
     }
     
+    // 默认物品数据库，通过数据注册表读取data/items/items.json，没有时使用内置物品
+    pub fn global() -> Arc<ItemDatabase> {
+        crate::data::DataRegistry::global().get_or_builtin(crate::data::DataType::Items, ItemDatabase::new)
+    }
+    
     // 初始化默认物品
     fn initialize_default_items(&mut self) {
         // 精灵球
@@ -316,6 +427,44 @@ impl ItemDatabase {
             consumable: true,
         });
         
+        // 高级球
+        self.add_item(Item {
+            id: 3,
+            name: "高级球".to_string(),
+            description: "性能很好的精灵球，比超级球更容易捕获Pokemon".to_string(),
+            item_type: ItemType::Pokeball,
+            rarity: ItemRarity::Rare,
+            max_stack: 99,
+            buy_price: 800,
+            sell_price: 400,
+            effects: vec![ItemEffect {
+                effect_type: "catch_rate".to_string(),
+                value: 200,
+                target: "wild_pokemon".to_string(),
+            }],
+            usable_in_battle: true,
+            consumable: true,
+        });
+        
+        // 大师球
+        self.add_item(Item {
+            id: 4,
+            name: "大师球".to_string(),
+            description: "必定能捕获野生Pokemon的最强精灵球".to_string(),
+            item_type: ItemType::Pokeball,
+            rarity: ItemRarity::Legendary,
+            max_stack: 99,
+            buy_price: 0,
+            sell_price: 0,
+            effects: vec![ItemEffect {
+                effect_type: "guaranteed_catch".to_string(),
+                value: 1,
+                target: "wild_pokemon".to_string(),
+            }],
+            usable_in_battle: true,
+            consumable: true,
+        });
+        
         // 伤药
         self.add_item(Item {
             id: 101,
@@ -354,6 +503,59 @@ impl ItemDatabase {
             consumable: true,
         });
         
+        // 橙橙果
+        self.add_item(Item {
+            id: 201,
+            name: "橙橙果".to_string(),
+            description: "让Pokemon携带后可以恢复10 HP".to_string(),
+            item_type: ItemType::Berry,
+            rarity: ItemRarity::Common,
+            max_stack: 99,
+            buy_price: 80,
+            sell_price: 40,
+            effects: vec![ItemEffect {
+                effect_type: "heal_hp".to_string(),
+                value: 10,
+                target: "pokemon".to_string(),
+            }],
+            usable_in_battle: true,
+            consumable: true,
+        });
+        
+        // 招式学习器01
+        self.add_item(Item {
+            id: 301,
+            name: "招式学习器01".to_string(),
+            description: "让Pokemon学会猛撞".to_string(),
+            item_type: ItemType::TM,
+            rarity: ItemRarity::Uncommon,
+            max_stack: 1,
+            buy_price: 3000,
+            sell_price: 1500,
+            effects: vec![ItemEffect {
+                effect_type: "teach_move".to_string(),
+                value: 36,
+                target: "pokemon".to_string(),
+            }],
+            usable_in_battle: false,
+            consumable: false,
+        });
+        
+        // 自行车
+        self.add_item(Item {
+            id: 401,
+            name: "自行车".to_string(),
+            description: "可以比跑步更快地移动".to_string(),
+            item_type: ItemType::KeyItem,
+            rarity: ItemRarity::Rare,
+            max_stack: 1,
+            buy_price: 0,
+            sell_price: 0,
+            effects: Vec::new(),
+            usable_in_battle: false,
+            consumable: false,
+        });
+        
         debug!("初始化物品数据库: {} 个物品", self.items.len());
     }
     
@@ -450,12 +652,9 @@ mod tests {
     #[test]
     fn test_item_add_remove() {
         let mut inventory = Inventory::new();
-        let database = ItemDatabase::new();
-        
-        let pokeball = database.get_item(1).unwrap();
         
         // 添加物品
-        let added = inventory.add_item(1, 5, pokeball).unwrap();
+        let added = inventory.add_item(1, 5).unwrap();
         assert_eq!(added, 5);
         assert_eq!(inventory.get_item_quantity(1), 5);
         
@@ -474,17 +673,48 @@ mod tests {
     #[test]
     fn test_item_stacking() {
         let mut inventory = Inventory::new();
-        let database = ItemDatabase::new();
-        
-        let pokeball = database.get_item(1).unwrap();
         
         // 添加到堆叠上限
-        let added1 = inventory.add_item(1, 50, pokeball).unwrap();
+        let added1 = inventory.add_item(1, 50).unwrap();
         assert_eq!(added1, 50);
         
-        let added2 = inventory.add_item(1, 60, pokeball).unwrap();
+        let added2 = inventory.add_item(1, 60).unwrap();
         assert_eq!(added2, 49); // 只能再添加49个，因为max_stack是99
         assert_eq!(inventory.get_item_quantity(1), 99);
+        
+        // 堆叠已满时拒绝添加，数量不变
+        assert!(inventory.add_item(1, 1).is_err());
+        assert_eq!(inventory.count(1), 99);
+        assert_eq!(inventory.pocket_items(Pocket::PokeBalls), vec![1]);
+    }
+    
+    #[test]
+    fn test_pocket_overflow() {
+        let mut inventory = Inventory::new();
+        inventory.capacity.insert(Pocket::Items, 1);
+        
+        inventory.add_item(101, 3).unwrap();
+        // 道具口袋只有一个空位
+        assert!(inventory.add_item(102, 1).is_err());
+        assert!(!inventory.has(102));
+        // 其它口袋不受影响
+        assert_eq!(inventory.add_item(201, 5).unwrap(), 5);
+        assert_eq!(inventory.pocket_slot_count(Pocket::Berries), 1);
+        
+        assert!(inventory.add_item(999, 1).is_err());
+    }
+    
+    #[test]
+    fn test_key_item_unique_and_permanent() {
+        let mut inventory = Inventory::new();
+        
+        assert_eq!(inventory.add_item(401, 3).unwrap(), 1);
+        assert!(inventory.add_item(401, 1).is_err());
+        assert_eq!(inventory.count(401), 1);
+        
+        assert!(inventory.remove_item(401, 1).is_err());
+        assert_eq!(inventory.use_item(401, 1).unwrap(), 0);
+        assert!(inventory.has(401));
     }
     
     #[test]
@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use log::{debug, warn, error};
 use crate::core::error::GameError;
+use crate::core::event_system::EventSystem;
+use crate::game_modes::{GameModeManager, SharedGameModes};
 #[cfg(feature = "pokemon-wip")]
 use crate::pokemon::stats::PokemonStats;
 #[cfg(feature = "pokemon-wip")]
@@ -61,8 +63,12 @@ pub struct EffortValues {
 use glam::Vec2;
 
 pub mod inventory;
+pub mod experience;
 pub mod profile;
 pub mod progress;
+pub mod shop;
+pub mod storage;
+pub mod trade;
 
 // 玩家ID类型
 pub type PlayerId = u64;
@@ -100,6 +106,39 @@ pub struct PokemonTeam {
     pub active_team: Vec<u64>,      // 战斗队伍 (最多6只)
     pub storage: HashMap<u64, PokemonInstance>, // 存储系统中的Pokemon
     pub next_pokemon_id: u64,
+    #[serde(default)]
+    pub boxes: storage::PokemonStorage,         // 不在队伍中的Pokemon所在的盒子
+}
+
+impl PokemonTeam {
+    pub fn is_full(&self) -> bool {
+        self.active_team.len() >= storage::MAX_TEAM_SIZE
+    }
+    
+    // 从队伍存入盒子，队伍至少保留一只
+    pub fn deposit_from_team(&mut self, pokemon_id: u64) -> Result<(usize, usize), GameError> {
+        let index = self.active_team.iter()
+            .position(|&id| id == pokemon_id)
+            .ok_or_else(|| GameError::Player(format!("队伍中没有该Pokemon: {}", pokemon_id)))?;
+        if self.active_team.len() <= 1 {
+            return Err(GameError::Player("队伍中至少需要保留一只Pokemon".to_string()));
+        }
+        
+        let location = self.boxes.deposit(pokemon_id)?;
+        self.active_team.remove(index);
+        Ok(location)
+    }
+    
+    // 从盒子取回队伍，队伍已满时不做任何改动
+    pub fn withdraw_to_team(&mut self, pokemon_id: u64) -> Result<(), GameError> {
+        if self.is_full() {
+            return Err(GameError::Player(format!("队伍已满 ({}只)", storage::MAX_TEAM_SIZE)));
+        }
+        
+        self.boxes.withdraw(pokemon_id)?;
+        self.active_team.push(pokemon_id);
+        Ok(())
+    }
 }
 
 // Pokemon实例
@@ -128,6 +167,8 @@ pub struct PokemonInstance {
     pub effort_values: EffortValues,
     pub friendship: u8,
     pub original_trainer: String,
+    #[serde(default)]
+    pub trainer_id: PlayerId,       // 当前持有者，交换后改变
     pub catch_date: std::time::SystemTime,
     pub pokeball_type: u32,
     pub status_condition: Option<u32>,
@@ -172,6 +213,8 @@ pub struct Player {
     
     // 背包系统
     pub inventory: inventory::Inventory,
+    #[serde(default)]
+    pub money: u64,
     
     // 游戏进度
     pub progress: progress::GameProgress,
@@ -188,6 +231,13 @@ pub struct Player {
     pub last_save: std::time::SystemTime,
 }
 
+// 初始金钱与持有上限
+pub const STARTING_MONEY: u64 = 3000;
+pub const MAX_MONEY: u64 = 9_999_999;
+
+// 单次位置更新超过该距离(米)视为传送，不计入行走距离
+pub const MAX_WALK_STEP: f32 = 20.0;
+
 // 图鉴条目
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PokedexEntry {
@@ -206,6 +256,12 @@ pub struct PlayerManager {
     player_cache: HashMap<PlayerId, Player>,
     save_timer: f32,
     auto_save_interval: f32,
+    playtime_accumulator: f64,  // 尚未计入playtime的不足一秒的时间
+    save_directory: std::path::PathBuf,
+    experience_curve: experience::ExperienceCurve,
+    
+    // 规则模式，捕获经过它的钩子
+    game_modes: SharedGameModes,
     
     // 统计
     total_saves: u64,
@@ -219,6 +275,10 @@ impl PlayerManager {
             player_cache: HashMap::new(),
             save_timer: 0.0,
             auto_save_interval: 300.0, // 5分钟自动保存
+            playtime_accumulator: 0.0,
+            save_directory: std::path::PathBuf::from("saves"),
+            experience_curve: experience::ExperienceCurve::default(),
+            game_modes: GameModeManager::global(),
             total_saves: 0,
             last_save_time: std::time::Instant::now(),
         }
@@ -241,7 +301,7 @@ impl PlayerManager {
             level_info: PlayerLevel {
                 level: 1,
                 experience: 0,
-                experience_to_next: 1000,
+                experience_to_next: self.experience_curve.experience_to_next(1),
                 total_experience: 0,
             },
             location: PlayerLocation {
@@ -254,9 +314,11 @@ impl PlayerManager {
                 active_team: Vec::new(),
                 storage: HashMap::new(),
                 next_pokemon_id: 1,
+                boxes: storage::PokemonStorage::default(),
             },
             pokedex: HashMap::new(),
             inventory: inventory::Inventory::new(),
+            money: STARTING_MONEY,
             progress: progress::GameProgress::new(),
             stats: PlayerStats {
                 pokemon_caught: 0,
@@ -318,6 +380,36 @@ impl PlayerManager {
         Ok(())
     }
     
+    pub fn set_save_directory<P: AsRef<std::path::Path>>(&mut self, directory: P) {
+        self.save_directory = directory.as_ref().to_path_buf();
+    }
+    
+    // 保存当前玩家到存档槽
+    pub fn save_to_slot(&mut self, index: u8) -> Result<crate::save::SaveSlot, GameError> {
+        let player = self.current_player.as_mut()
+            .ok_or_else(|| GameError::Player("没有当前玩家".to_string()))?;
+        player.last_save = std::time::SystemTime::now();
+        let summary = crate::save::write_slot(&self.save_directory, index, player)?;
+        self.total_saves += 1;
+        self.last_save_time = std::time::Instant::now();
+        Ok(summary)
+    }
+    
+    // 从存档槽加载玩家
+    pub fn load_from_slot(&mut self, index: u8) -> Result<(), GameError> {
+        let mut player = crate::save::read_slot(&self.save_directory, index)?;
+        player.last_login = std::time::SystemTime::now();
+        self.player_cache.insert(player.id, player.clone());
+        self.current_player = Some(player);
+        self.playtime_accumulator = 0.0;
+        Ok(())
+    }
+    
+    // 读档菜单用的存档槽摘要
+    pub fn list_slots(&self) -> Result<Vec<crate::save::SaveSlot>, GameError> {
+        crate::save::list_slots(&self.save_directory)
+    }
+    
     // 获取当前玩家
     pub fn get_current_player(&self) -> Option<&Player> {
         self.current_player.as_ref()
@@ -328,20 +420,22 @@ impl PlayerManager {
         self.current_player.as_mut()
     }
     
-    // 添加Pokemon到队伍
-    pub fn add_pokemon_to_team(&mut self, pokemon: PokemonInstance) -> Result<u64, GameError> {
+    // 添加Pokemon到队伍，不计入捕获统计(捕获请用record_capture)
+    pub fn add_pokemon_to_team(&mut self, mut pokemon: PokemonInstance) -> Result<u64, GameError> {
         if let Some(ref mut player) = self.current_player {
             let pokemon_id = pokemon.id;
+            pokemon.trainer_id = player.id;
             
-            // 添加到存储
-            player.pokemon_team.storage.insert(pokemon_id, pokemon);
-            
-            // 如果队伍未满，添加到战斗队伍
-            if player.pokemon_team.active_team.len() < 6 {
+            // 如果队伍未满，添加到战斗队伍，否则放进盒子
+            if !player.pokemon_team.is_full() {
                 player.pokemon_team.active_team.push(pokemon_id);
+            } else {
+                player.pokemon_team.boxes.deposit(pokemon_id)?;
             }
             
-            player.stats.pokemon_caught += 1;
+            // 添加到存储
+            player.pokemon_team.storage.insert(pokemon_id, pokemon);
+            
             debug!("添加Pokemon到队伍: ID {}", pokemon_id);
             Ok(pokemon_id)
         } else {
@@ -349,6 +443,64 @@ impl PlayerManager {
         }
     }
     
+    pub fn experience_curve(&self) -> &experience::ExperienceCurve {
+        &self.experience_curve
+    }
+    
+    // 切换经验曲线，按总经验重新计算当前玩家的等级
+    pub fn set_experience_curve(&mut self, curve: experience::ExperienceCurve) {
+        if let Some(ref mut player) = self.current_player {
+            let info = &mut player.level_info;
+            info.level = curve.level_for_experience(info.total_experience);
+            info.experience = info.total_experience - curve.experience_for_level(info.level);
+            info.experience_to_next = curve.experience_to_next(info.level);
+        }
+        self.experience_curve = curve;
+    }
+    
+    // 记录一次捕获：队伍/盒子、存储、图鉴和统计只在这里更新一次
+    pub fn record_capture(&mut self, species_id: u32, pokemon: PokemonInstance) -> Result<u64, GameError> {
+        if pokemon.species_id != species_id {
+            return Err(GameError::Player(format!(
+                "捕获的种类不一致: {} != {}", pokemon.species_id, species_id
+            )));
+        }
+        
+        let pokemon_id = self.add_pokemon_to_team(pokemon)?;
+        self.update_pokedex(species_id, true, true)?;
+        
+        // 规则模式按玩家所在的地图记录这条道路已捕获
+        if let Some(ref player) = self.current_player {
+            self.game_modes.lock().unwrap().on_capture(&player.location.map_id, pokemon_id);
+        }
+        Ok(pokemon_id)
+    }
+    
+    pub fn set_game_modes(&mut self, game_modes: SharedGameModes) {
+        self.game_modes = game_modes;
+    }
+    
+    // 放生规则要求永久失去的宝可梦(战斗结束时的released_pokemon)，从队伍、盒子和存储中移除，返回实际移除的数量
+    pub fn release_pokemon(&mut self, pokemon_ids: &[u64]) -> Result<usize, GameError> {
+        let player = self.current_player.as_mut()
+            .ok_or_else(|| GameError::Player("没有当前玩家".to_string()))?;
+        let team = &mut player.pokemon_team;
+        
+        let mut released = 0;
+        for &pokemon_id in pokemon_ids {
+            if team.storage.remove(&pokemon_id).is_none() {
+                continue;
+            }
+            team.active_team.retain(|&id| id != pokemon_id);
+            if team.boxes.contains(pokemon_id) {
+                team.boxes.withdraw(pokemon_id)?;
+            }
+            released += 1;
+            debug!("放生Pokemon: ID {}", pokemon_id);
+        }
+        Ok(released)
+    }
+    
     // 获得经验值
     pub fn gain_experience(&mut self, amount: u64) -> Result<Vec<u32>, GameError> {
         if let Some(ref mut player) = self.current_player {
@@ -364,7 +516,7 @@ impl PlayerManager {
                 levels_gained.push(player.level_info.level);
                 
                 // 计算下一级所需经验
-                player.level_info.experience_to_next = self.calculate_experience_to_next_level(player.level_info.level);
+                player.level_info.experience_to_next = self.experience_curve.experience_to_next(player.level_info.level);
                 
                 debug!("玩家升级到 {} 级!", player.level_info.level);
             }
@@ -378,9 +530,13 @@ impl PlayerManager {
     // 更新玩家位置
     pub fn update_location(&mut self, map_id: String, position: Vec2) -> Result<(), GameError> {
         if let Some(ref mut player) = self.current_player {
-            // 计算移动距离
+            // 计算移动距离，换地图或瞬移不算行走
             let distance = (position - player.location.position).length();
-            player.stats.distance_walked += distance as f64;
+            if map_id == player.location.map_id && distance <= MAX_WALK_STEP {
+                player.stats.distance_walked += distance as f64;
+            } else {
+                debug!("位置跳变 {:.1}米 ({} -> {})，不计入行走距离", distance, player.location.map_id, map_id);
+            }
             
             player.location.map_id = map_id;
             player.location.position = position;
@@ -395,46 +551,43 @@ impl PlayerManager {
     // 更新Pokedex
     pub fn update_pokedex(&mut self, species_id: u32, seen: bool, caught: bool) -> Result<(), GameError> {
         if let Some(ref mut player) = self.current_player {
-            let entry = player.pokedex.entry(species_id).or_insert(PokedexEntry {
-                species_id,
-                seen: false,
-                caught: false,
-                first_seen_date: None,
-                first_caught_date: None,
-                times_encountered: 0,
-                times_caught: 0,
-            });
-            
-            if seen && !entry.seen {
-                entry.seen = true;
-                entry.first_seen_date = Some(std::time::SystemTime::now());
-                player.stats.pokemon_seen += 1;
-                debug!("首次发现Pokemon: species_id {}", species_id);
-            }
-            
+            player.record_pokedex(species_id, seen, caught);
             if caught {
-                entry.times_caught += 1;
-                if !entry.caught {
-                    entry.caught = true;
-                    entry.first_caught_date = Some(std::time::SystemTime::now());
-                    debug!("首次捕获Pokemon: species_id {}", species_id);
-                }
-            }
-            
-            if seen {
-                entry.times_encountered += 1;
+                player.stats.pokemon_caught += 1;
             }
-            
-            Ok(())
         } else {
-            Err(GameError::Player("没有当前玩家".to_string()))
+            return Err(GameError::Player("没有当前玩家".to_string()));
+        }
+        
+        self.check_achievements()?;
+        Ok(())
+    }
+    
+    // 统计变化后检查成就，新解锁的成就发出AchievementUnlockedEvent，返回其ID
+    pub fn check_achievements(&mut self) -> Result<Vec<u32>, GameError> {
+        let player = self.current_player.as_mut()
+            .ok_or_else(|| GameError::Player("没有当前玩家".to_string()))?;
+        
+        let unlocked = player.progress.evaluate_achievements(&player.stats);
+        let ids = unlocked.iter().map(|event| event.achievement_id).collect();
+        for event in unlocked {
+            debug!("解锁成就: {}", event.name);
+            if let Err(e) = EventSystem::dispatch(event) {
+                warn!("发送成就解锁事件失败: {}", e);
+            }
         }
+        
+        Ok(ids)
     }
     
     // 更新游戏时间
     pub fn update(&mut self, delta_time: f32) -> Result<(), GameError> {
         if let Some(ref mut player) = self.current_player {
-            player.stats.playtime += delta_time as u64;
+            // 每帧的delta不足一秒，累计到整秒再计入
+            self.playtime_accumulator += delta_time.max(0.0) as f64;
+            let whole_seconds = self.playtime_accumulator.floor();
+            player.stats.playtime += whole_seconds as u64;
+            self.playtime_accumulator -= whole_seconds;
         }
         
         // 自动保存检查
@@ -459,22 +612,17 @@ impl PlayerManager {
         timestamp + fastrand::u64(0..1000)
     }
     
-    fn calculate_experience_to_next_level(&self, level: u32) -> u64 {
-        // 简化的经验公式
-        (level as u64 * 1000) + (level as u64 * level as u64 * 50)
-    }
-    
     fn load_player_from_file(&self, player_id: PlayerId) -> Result<Player, GameError> {
-        let filename = format!("saves/player_{}.json", player_id);
+        let filename = self.save_directory.join(format!("player_{}.json", player_id));
         
         match std::fs::read_to_string(&filename) {
             Ok(data) => {
-                match serde_json::from_str::<Player>(&data) {
+                match crate::save::player_from_json(&data) {
                     Ok(mut player) => {
                         player.last_login = std::time::SystemTime::now();
                         Ok(player)
                     },
-                    Err(e) => Err(GameError::Player(format!("反序列化失败: {}", e))),
+                    Err(e) => Err(GameError::Player(format!("读取存档失败: {}", e))),
                 }
             },
             Err(e) => Err(GameError::Player(format!("读取文件失败: {}", e))),
@@ -483,11 +631,11 @@ impl PlayerManager {
     
     fn save_player_to_file(&self, player: &Player) -> Result<(), GameError> {
         // 确保保存目录存在
-        std::fs::create_dir_all("saves").ok();
+        std::fs::create_dir_all(&self.save_directory).ok();
         
-        let filename = format!("saves/player_{}.json", player.id);
+        let filename = self.save_directory.join(format!("player_{}.json", player.id));
         
-        match serde_json::to_string_pretty(player) {
+        match crate::save::player_to_json(player) {
             Ok(data) => {
                 match std::fs::write(&filename, data) {
                     Ok(_) => Ok(()),
@@ -500,6 +648,55 @@ impl PlayerManager {
 }
 
 impl Player {
+    // 记录图鉴的发现和捕获
+    pub fn record_pokedex(&mut self, species_id: u32, seen: bool, caught: bool) {
+        let entry = self.pokedex.entry(species_id).or_insert(PokedexEntry {
+            species_id,
+            seen: false,
+            caught: false,
+            first_seen_date: None,
+            first_caught_date: None,
+            times_encountered: 0,
+            times_caught: 0,
+        });
+        
+        if seen && !entry.seen {
+            entry.seen = true;
+            entry.first_seen_date = Some(std::time::SystemTime::now());
+            self.stats.pokemon_seen += 1;
+            debug!("首次发现Pokemon: species_id {}", species_id);
+        }
+        
+        if caught {
+            entry.times_caught += 1;
+            if !entry.caught {
+                entry.caught = true;
+                entry.first_caught_date = Some(std::time::SystemTime::now());
+                debug!("首次捕获Pokemon: species_id {}", species_id);
+            }
+        }
+        
+        if seen {
+            entry.times_encountered += 1;
+        }
+    }
+    
+    // 支出金钱，余额不足时不扣款，返回剩余金额
+    pub fn spend(&mut self, amount: u64) -> Result<u64, GameError> {
+        if amount > self.money {
+            return Err(GameError::Player(format!("金钱不足: 需要 {}，持有 {}", amount, self.money)));
+        }
+        self.money -= amount;
+        Ok(self.money)
+    }
+    
+    // 获得金钱，超过上限的部分舍弃，返回实际获得的金额
+    pub fn earn(&mut self, amount: u64) -> u64 {
+        let earned = amount.min(MAX_MONEY.saturating_sub(self.money));
+        self.money += earned;
+        earned
+    }
+    
     // 获取战斗队伍中的Pokemon
     pub fn get_active_pokemon(&self) -> Vec<&PokemonInstance> {
         self.pokemon_team
@@ -564,9 +761,35 @@ impl Default for PlayerStats {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     
+    #[cfg(not(feature = "pokemon-wip"))]
+    pub(crate) fn test_pokemon(id: u64, species_id: u32, trainer: &str) -> PokemonInstance {
+        PokemonInstance {
+            id,
+            species_id,
+            nickname: None,
+            level: 20,
+            experience: 0,
+            stats: PokemonStats { hp: 50, attack: 30, defense: 30, special_attack: 30, special_defense: 30, speed: 30 },
+            types: DualType { primary: 0, secondary: None },
+            moves: Vec::new(),
+            ability: 0,
+            nature: Nature { id: 0, name: "勤奋".to_string() },
+            individual_values: IndividualValues { hp: 0, attack: 0, defense: 0, special_attack: 0, special_defense: 0, speed: 0 },
+            effort_values: EffortValues { hp: 0, attack: 0, defense: 0, special_attack: 0, special_defense: 0, speed: 0 },
+            friendship: 70,
+            original_trainer: trainer.to_string(),
+            trainer_id: 0,
+            catch_date: std::time::SystemTime::now(),
+            pokeball_type: 1,
+            status_condition: None,
+            held_item: None,
+            is_shiny: false,
+        }
+    }
+    
     #[test]
     fn test_player_manager_creation() {
         let manager = PlayerManager::new();
@@ -607,6 +830,7 @@ mod tests {
     
     #[test]
     fn test_pokedex_update() {
+        EventSystem::init().unwrap();
         let mut manager = PlayerManager::new();
         manager.create_player("test".to_string(), "Test".to_string()).unwrap();
         
@@ -626,4 +850,111 @@ mod tests {
         assert_eq!(entry.times_encountered, 2);
         assert_eq!(entry.times_caught, 1);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_withdraw_into_full_team_fails() {
+        let mut team = PokemonTeam {
+            active_team: (1..=6).collect(),
+            storage: HashMap::new(),
+            next_pokemon_id: 8,
+            boxes: storage::PokemonStorage::default(),
+        };
+        team.boxes.deposit(7).unwrap();
+        
+        assert!(team.withdraw_to_team(7).is_err());
+        assert_eq!(team.active_team.len(), 6);
+        assert!(team.boxes.contains(7));
+        
+        // 先存入一只腾出位置
+        assert_eq!(team.deposit_from_team(3).unwrap(), (0, 1));
+        team.withdraw_to_team(7).unwrap();
+        assert_eq!(team.active_team, vec![1, 2, 4, 5, 6, 7]);
+        assert!(!team.boxes.contains(7));
+    }
+    
+    #[cfg(not(feature = "pokemon-wip"))]
+    #[test]
+    fn test_capture_counted_once() {
+        EventSystem::init().unwrap();
+        let mut manager = PlayerManager::new();
+        manager.create_player("test".to_string(), "Test".to_string()).unwrap();
+        
+        let pokemon_id = manager.record_capture(16, test_pokemon(1, 16, "test")).unwrap();
+        
+        let player = manager.get_current_player().unwrap();
+        assert_eq!(player.stats.pokemon_caught, 1);
+        assert!(player.pokedex[&16].caught);
+        assert_eq!(player.pokedex[&16].times_caught, 1);
+        assert!(player.progress.achievements[&1].completed);
+        assert_eq!(player.pokemon_team.active_team, vec![pokemon_id]);
+        assert!(player.pokemon_team.storage.contains_key(&pokemon_id));
+        
+        // 种类不一致时不做任何改动
+        assert!(manager.record_capture(19, test_pokemon(2, 16, "test")).is_err());
+        assert_eq!(manager.get_current_player().unwrap().stats.pokemon_caught, 1);
+    }
+    
+    #[cfg(not(feature = "pokemon-wip"))]
+    #[test]
+    fn test_capture_and_release_go_through_game_mode() {
+        EventSystem::init().unwrap();
+        let game_modes = GameModeManager::shared();
+        game_modes.lock().unwrap().set_mode(crate::game_modes::Nuzlocke::new());
+        let mut manager = PlayerManager::new();
+        manager.set_game_modes(game_modes.clone());
+        manager.create_player("test".to_string(), "Test".to_string()).unwrap();
+        let route = manager.get_current_player().unwrap().location.map_id.clone();
+        
+        // 捕获后这条道路的机会用完
+        game_modes.lock().unwrap().on_encounter(&route, 16);
+        let pokemon_id = manager.record_capture(16, test_pokemon(1, 16, "test")).unwrap();
+        assert!(game_modes.lock().unwrap().check_capture(&route, 16).is_err());
+        assert_eq!(game_modes.lock().unwrap().get_stats().pokemon_caught, 1);
+        
+        // 放生后从队伍和存储中移除，不存在的ID忽略
+        manager.add_pokemon_to_team(test_pokemon(2, 19, "test")).unwrap();
+        assert_eq!(manager.release_pokemon(&[pokemon_id, 99]).unwrap(), 1);
+        let team = &manager.get_current_player().unwrap().pokemon_team;
+        assert_eq!(team.active_team, vec![2]);
+        assert!(!team.storage.contains_key(&pokemon_id));
+    }
+    
+    #[test]
+    fn test_playtime_accumulates_small_deltas() {
+        let mut manager = PlayerManager::new();
+        manager.create_player("test".to_string(), "Test".to_string()).unwrap();
+        
+        // 60帧/秒跑10秒
+        for _ in 0..600 {
+            manager.update(1.0 / 60.0).unwrap();
+        }
+        assert_eq!(manager.get_current_player().unwrap().stats.playtime, 10);
+        
+        // 不足一秒的部分保留到下一次
+        manager.update(0.6).unwrap();
+        assert_eq!(manager.get_current_player().unwrap().stats.playtime, 10);
+        manager.update(0.6).unwrap();
+        assert_eq!(manager.get_current_player().unwrap().stats.playtime, 11);
+    }
+    
+    #[test]
+    fn test_teleport_not_counted_as_walking() {
+        let mut manager = PlayerManager::new();
+        manager.create_player("test".to_string(), "Test".to_string()).unwrap();
+        let map_id = manager.get_current_player().unwrap().location.map_id.clone();
+        let start = manager.get_current_player().unwrap().location.position;
+        
+        // 同一地图的小步移动计入
+        manager.update_location(map_id.clone(), start + Vec2::new(3.0, 4.0)).unwrap();
+        assert_eq!(manager.get_current_player().unwrap().stats.distance_walked, 5.0);
+        
+        // 跨地图传送不计入
+        manager.update_location("other_map".to_string(), start).unwrap();
+        assert_eq!(manager.get_current_player().unwrap().stats.distance_walked, 5.0);
+        
+        // 同一地图内的大幅瞬移也不计入
+        manager.update_location("other_map".to_string(), start + Vec2::new(500.0, 0.0)).unwrap();
+        assert_eq!(manager.get_current_player().unwrap().stats.distance_walked, 5.0);
+        assert_eq!(manager.get_current_player().unwrap().location.map_id, "other_map");
+    }
+}
@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use log::{debug, warn};
 use crate::core::error::GameError;
+use crate::core::event_system::Event;
+use super::PlayerStats;
 
 // 游戏进度
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,6 +67,47 @@ pub struct Achievement {
     pub obtained_date: Option<std::time::SystemTime>,
     pub reward_coins: u32,
     pub reward_items: Vec<(u32, u32)>, // (item_id, quantity)
+    #[serde(default)]
+    pub stat: Option<AchievementStat>,  // 由统计数据自动推进，None表示由脚本手动更新
+}
+
+// 成就跟踪的统计项
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AchievementStat {
+    PokemonCaught,
+    PokemonSeen,
+    BattlesWon,
+    PokemonEvolved,
+    TradesCompleted,
+    GymsDefeated,
+    ItemsUsed,
+}
+
+impl AchievementStat {
+    pub fn value(self, stats: &PlayerStats) -> u32 {
+        match self {
+            AchievementStat::PokemonCaught => stats.pokemon_caught,
+            AchievementStat::PokemonSeen => stats.pokemon_seen,
+            AchievementStat::BattlesWon => stats.battles_won,
+            AchievementStat::PokemonEvolved => stats.pokemon_evolved,
+            AchievementStat::TradesCompleted => stats.trades_completed,
+            AchievementStat::GymsDefeated => stats.gyms_defeated,
+            AchievementStat::ItemsUsed => stats.items_used,
+        }
+    }
+}
+
+// 成就解锁事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AchievementUnlockedEvent {
+    pub achievement_id: u32,
+    pub name: String,
+    pub reward_coins: u32,
+}
+
+impl Event for AchievementUnlockedEvent {
+    fn event_type(&self) -> &'static str { "AchievementUnlocked" }
+    fn as_any(&self) -> &dyn std::any::Any { self }
 }
 
 // 成就分类
@@ -169,6 +212,7 @@ impl GameProgress {
                 obtained_date: None,
                 reward_coins: 100,
                 reward_items: vec![(1, 5)], // 5个精灵球
+                stat: Some(AchievementStat::PokemonCaught),
             },
             Achievement {
                 id: 2,
@@ -181,6 +225,7 @@ impl GameProgress {
                 obtained_date: None,
                 reward_coins: 500,
                 reward_items: vec![(2, 3)], // 3个超级球
+                stat: Some(AchievementStat::PokemonCaught),
             },
             Achievement {
                 id: 3,
@@ -193,6 +238,33 @@ impl GameProgress {
                 obtained_date: None,
                 reward_coins: 200,
                 reward_items: vec![(101, 3)], // 3个伤药
+                stat: Some(AchievementStat::BattlesWon),
+            },
+            Achievement {
+                id: 4,
+                name: "捕获达人".to_string(),
+                description: "捕获50只Pokemon".to_string(),
+                category: AchievementCategory::Collector,
+                progress: 0,
+                target: 50,
+                completed: false,
+                obtained_date: None,
+                reward_coins: 2000,
+                reward_items: vec![(2, 10)],
+                stat: Some(AchievementStat::PokemonCaught),
+            },
+            Achievement {
+                id: 5,
+                name: "百战百胜".to_string(),
+                description: "赢得100场战斗".to_string(),
+                category: AchievementCategory::Battler,
+                progress: 0,
+                target: 100,
+                completed: false,
+                obtained_date: None,
+                reward_coins: 5000,
+                reward_items: vec![(102, 10)],
+                stat: Some(AchievementStat::BattlesWon),
             },
         ];
         
@@ -272,6 +344,29 @@ impl GameProgress {
         Ok(false)
     }
     
+    // 按当前统计推进成就，返回本次新解锁的成就事件；已完成的成就不会再次触发
+    pub fn evaluate_achievements(&mut self, stats: &PlayerStats) -> Vec<AchievementUnlockedEvent> {
+        let tracked: Vec<(u32, u32)> = self.achievements.values()
+            .filter(|achievement| !achievement.completed)
+            .filter_map(|achievement| achievement.stat.map(|stat| (achievement.id, stat.value(stats))))
+            .collect();
+        
+        let mut unlocked = Vec::new();
+        for (achievement_id, value) in tracked {
+            if let Ok(true) = self.update_achievement_progress(achievement_id, value) {
+                let achievement = &self.achievements[&achievement_id];
+                unlocked.push(AchievementUnlockedEvent {
+                    achievement_id,
+                    name: achievement.name.clone(),
+                    reward_coins: achievement.reward_coins,
+                });
+            }
+        }
+        
+        unlocked.sort_by_key(|event| event.achievement_id);
+        unlocked
+    }
+    
     // 更新任务进度
     pub fn update_quest_progress(&mut self, objective_type: &str, value: u32) -> Vec<u32> {
         let mut completed_quests = Vec::new();
@@ -470,4 +565,27 @@ mod tests {
         let completed = progress.update_quest_progress("catch_pokemon", 1);
         assert!(!completed.is_empty()); // 应该完成了一些任务
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_achievement_unlocks_once() {
+        let mut progress = GameProgress::new();
+        let mut stats = crate::player::PlayerStats::default();
+        
+        stats.pokemon_caught = 49;
+        let unlocked = progress.evaluate_achievements(&stats);
+        let ids: Vec<u32> = unlocked.iter().map(|event| event.achievement_id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(progress.achievements[&4].progress, 49);
+        
+        // 跨过50只的门槛
+        stats.pokemon_caught = 50;
+        let unlocked = progress.evaluate_achievements(&stats);
+        assert_eq!(unlocked.len(), 1);
+        assert_eq!(unlocked[0].achievement_id, 4);
+        
+        // 再次评估不会重复触发
+        stats.pokemon_caught = 80;
+        assert!(progress.evaluate_achievements(&stats).is_empty());
+        assert!(progress.achievements[&4].completed);
+    }
+}
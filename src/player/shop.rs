@@ -0,0 +1,142 @@
+// 商店交易
+// 开发心理：买卖物品要同时改动金钱和背包，任何一步失败都不能让玩家凭空多出或少掉东西
+// 设计原则：先检查金钱、库存和背包空间，全部满足后再一次性修改，价格取自物品数据库
+
+use super::inventory::{ItemDatabase, ItemId, Pocket};
+use super::{Player, MAX_MONEY};
+use crate::core::error::GameError;
+use log::debug;
+
+// 购买物品，返回花费的金额
+pub fn buy(player: &mut Player, item_id: ItemId, quantity: u32) -> Result<u64, GameError> {
+    let item_database = ItemDatabase::global();
+    let item = item_database
+        .get_item(item_id)
+        .ok_or_else(|| GameError::Inventory(format!("商店没有该物品: {}", item_id)))?;
+    if quantity == 0 {
+        return Ok(0);
+    }
+
+    let cost = (item.buy_price as u64)
+        .checked_mul(quantity as u64)
+        .ok_or_else(|| GameError::Player("购买数量过大".to_string()))?;
+    if cost > player.money {
+        return Err(GameError::Player(format!(
+            "金钱不足，无法购买 {} x{}: 需要 {}，持有 {}",
+            item.name, quantity, cost, player.money
+        )));
+    }
+
+    let room = player.inventory.room_for(item_id, item);
+    if room < quantity {
+        return Err(GameError::Inventory(format!(
+            "背包空间不足，无法购买 {} x{}: 最多还能放入 {}",
+            item.name, quantity, room
+        )));
+    }
+
+    player.spend(cost)?;
+    player.inventory.add_item_with_data(item_id, quantity, item)?;
+    debug!("购买物品: {} x{} 花费 {}", item.name, quantity, cost);
+    Ok(cost)
+}
+
+// 出售物品，返回获得的金额
+pub fn sell(player: &mut Player, item_id: ItemId, quantity: u32) -> Result<u64, GameError> {
+    let item_database = ItemDatabase::global();
+    let item = item_database
+        .get_item(item_id)
+        .ok_or_else(|| GameError::Inventory(format!("商店不收购该物品: {}", item_id)))?;
+    if Pocket::from_item_type(item.item_type).is_unique() || item.sell_price == 0 {
+        return Err(GameError::Inventory(format!("{} 不能出售", item.name)));
+    }
+
+    let owned = player.inventory.count(item_id);
+    if owned < quantity {
+        return Err(GameError::Inventory(format!(
+            "{} 数量不足: 想出售 {}，持有 {}",
+            item.name, quantity, owned
+        )));
+    }
+
+    let proceeds = (item.sell_price as u64)
+        .checked_mul(quantity as u64)
+        .ok_or_else(|| GameError::Player("出售数量过大".to_string()))?;
+    let room = MAX_MONEY.saturating_sub(player.money);
+    if proceeds > room {
+        return Err(GameError::Player(format!(
+            "金钱将超过上限，无法出售 {} x{}: 可得 {}，最多还能获得 {}",
+            item.name, quantity, proceeds, room
+        )));
+    }
+
+    player.inventory.remove_item(item_id, quantity)?;
+    let earned = player.earn(proceeds);
+    debug!("出售物品: {} x{} 获得 {}", item.name, quantity, earned);
+    Ok(earned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::PlayerManager;
+
+    fn test_player() -> Player {
+        let mut manager = PlayerManager::new();
+        manager.create_player("shopper".to_string(), "Shopper".to_string()).unwrap();
+        manager.get_current_player().unwrap().clone()
+    }
+
+    #[test]
+    fn test_purchase_deducts_money() {
+        let mut player = test_player();
+        player.money = 1000;
+
+        // 精灵球 200 x3
+        assert_eq!(buy(&mut player, 1, 3).unwrap(), 600);
+        assert_eq!(player.money, 400);
+        assert_eq!(player.inventory.count(1), 3);
+
+        // 卖回一个 100
+        assert_eq!(sell(&mut player, 1, 1).unwrap(), 100);
+        assert_eq!(player.money, 500);
+        assert_eq!(player.inventory.count(1), 2);
+    }
+
+    #[test]
+    fn test_failed_purchase_leaves_state_unchanged() {
+        let mut player = test_player();
+        player.money = 500;
+
+        let error = buy(&mut player, 2, 1).unwrap_err();
+        assert!(error.to_string().contains("金钱不足"));
+        assert_eq!(player.money, 500);
+        assert!(!player.inventory.has(2));
+
+        // 超过堆叠上限同样不扣款
+        player.money = 100_000;
+        assert!(buy(&mut player, 1, 100).is_err());
+        assert_eq!(player.money, 100_000);
+        assert_eq!(player.inventory.count(1), 0);
+
+        assert!(player.spend(100_001).is_err());
+        assert_eq!(player.earn(u64::MAX), MAX_MONEY - 100_000);
+    }
+
+    #[test]
+    fn test_sell_near_money_cap_keeps_items() {
+        let mut player = test_player();
+        player.money = 1000;
+        buy(&mut player, 1, 3).unwrap();
+
+        // 精灵球卖价 100，只差 150 就到上限时卖两个会溢出
+        player.money = MAX_MONEY - 150;
+        let error = sell(&mut player, 1, 2).unwrap_err();
+        assert!(error.to_string().contains("上限"));
+        assert_eq!(player.money, MAX_MONEY - 150);
+        assert_eq!(player.inventory.count(1), 3);
+
+        assert_eq!(sell(&mut player, 1, 1).unwrap(), 100);
+        assert_eq!(player.inventory.count(1), 2);
+    }
+}
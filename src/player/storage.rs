@@ -0,0 +1,173 @@
+// 宝可梦盒子存储
+// 开发心理：捕获的宝可梦越来越多，平铺的存储无法整理，玩家需要按盒子分类并给盒子命名和换壁纸
+// 设计原则：盒子只记录宝可梦ID和格子位置，宝可梦数据仍由PokemonTeam.storage持有；放入时当前盒子满了自动顺延
+
+use serde::{Deserialize, Serialize};
+use log::debug;
+use crate::core::error::GameError;
+
+pub const BOX_SLOTS: usize = 30;
+pub const DEFAULT_BOX_COUNT: usize = 8;
+pub const MAX_TEAM_SIZE: usize = 6;
+
+// 单个盒子
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageBox {
+    pub name: String,
+    pub wallpaper: String,
+    pub slots: Vec<Option<u64>>,    // 固定数量的格子
+}
+
+impl StorageBox {
+    pub fn new(name: String, slot_count: usize) -> Self {
+        Self {
+            name,
+            wallpaper: "default".to_string(),
+            slots: vec![None; slot_count],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.iter().all(|slot| slot.is_none())
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.slots.iter().all(|slot| slot.is_some())
+    }
+
+    fn first_free_slot(&self) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.is_none())
+    }
+}
+
+// 盒子存储
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PokemonStorage {
+    pub boxes: Vec<StorageBox>,
+    pub current_box: usize,
+}
+
+impl Default for PokemonStorage {
+    fn default() -> Self {
+        Self::new(DEFAULT_BOX_COUNT, BOX_SLOTS)
+    }
+}
+
+impl PokemonStorage {
+    pub fn new(box_count: usize, slots_per_box: usize) -> Self {
+        let boxes = (0..box_count.max(1))
+            .map(|i| StorageBox::new(format!("盒子{}", i + 1), slots_per_box.max(1)))
+            .collect();
+        Self { boxes, current_box: 0 }
+    }
+
+    // 查找宝可梦所在的(盒子, 格子)
+    pub fn locate(&self, pokemon_id: u64) -> Option<(usize, usize)> {
+        self.boxes.iter().enumerate().find_map(|(box_index, storage_box)| {
+            storage_box.slots.iter()
+                .position(|slot| *slot == Some(pokemon_id))
+                .map(|slot| (box_index, slot))
+        })
+    }
+
+    pub fn contains(&self, pokemon_id: u64) -> bool {
+        self.locate(pokemon_id).is_some()
+    }
+
+    pub fn total_stored(&self) -> usize {
+        self.boxes.iter().map(StorageBox::len).sum()
+    }
+
+    // 放入当前盒子，满了依次顺延到后面的盒子，返回(盒子, 格子)
+    pub fn deposit(&mut self, pokemon_id: u64) -> Result<(usize, usize), GameError> {
+        if self.contains(pokemon_id) {
+            return Err(GameError::Player(format!("宝可梦已在盒子中: {}", pokemon_id)));
+        }
+
+        let box_count = self.boxes.len();
+        for offset in 0..box_count {
+            let box_index = (self.current_box + offset) % box_count;
+            if let Some(slot) = self.boxes[box_index].first_free_slot() {
+                self.boxes[box_index].slots[slot] = Some(pokemon_id);
+                debug!("存入宝可梦 {} 到 {} 第{}格", pokemon_id, self.boxes[box_index].name, slot);
+                return Ok((box_index, slot));
+            }
+        }
+
+        Err(GameError::Player("所有盒子都已满".to_string()))
+    }
+
+    // 从盒子中取出
+    pub fn withdraw(&mut self, pokemon_id: u64) -> Result<(), GameError> {
+        let (box_index, slot) = self.locate(pokemon_id)
+            .ok_or_else(|| GameError::Player(format!("盒子中没有该宝可梦: {}", pokemon_id)))?;
+        self.boxes[box_index].slots[slot] = None;
+        debug!("从 {} 取出宝可梦 {}", self.boxes[box_index].name, pokemon_id);
+        Ok(())
+    }
+
+    // 移动到另一个盒子的第一个空格，返回新格子
+    pub fn move_between_boxes(&mut self, pokemon_id: u64, target_box: usize) -> Result<usize, GameError> {
+        let (box_index, slot) = self.locate(pokemon_id)
+            .ok_or_else(|| GameError::Player(format!("盒子中没有该宝可梦: {}", pokemon_id)))?;
+        if box_index == target_box {
+            return Ok(slot);
+        }
+
+        let target = self.boxes.get_mut(target_box)
+            .ok_or_else(|| GameError::Player(format!("盒子不存在: {}", target_box)))?;
+        let target_slot = target.first_free_slot()
+            .ok_or_else(|| GameError::Player(format!("{} 已满", target.name)))?;
+        target.slots[target_slot] = Some(pokemon_id);
+        self.boxes[box_index].slots[slot] = None;
+        Ok(target_slot)
+    }
+
+    pub fn rename_box(&mut self, box_index: usize, name: String) -> Result<(), GameError> {
+        self.box_mut(box_index)?.name = name;
+        Ok(())
+    }
+
+    pub fn set_wallpaper(&mut self, box_index: usize, wallpaper: String) -> Result<(), GameError> {
+        self.box_mut(box_index)?.wallpaper = wallpaper;
+        Ok(())
+    }
+
+    fn box_mut(&mut self, box_index: usize) -> Result<&mut StorageBox, GameError> {
+        self.boxes.get_mut(box_index)
+            .ok_or_else(|| GameError::Player(format!("盒子不存在: {}", box_index)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deposit_rolls_into_next_box() {
+        let mut storage = PokemonStorage::new(3, 2);
+        storage.current_box = 1;
+
+        assert_eq!(storage.deposit(10).unwrap(), (1, 0));
+        assert_eq!(storage.deposit(11).unwrap(), (1, 1));
+        // 当前盒子已满，顺延到下一个盒子
+        assert_eq!(storage.deposit(12).unwrap(), (2, 0));
+        assert_eq!(storage.deposit(13).unwrap(), (2, 1));
+        // 最后一个盒子满了回到第一个
+        assert_eq!(storage.deposit(14).unwrap(), (0, 0));
+        assert!(storage.deposit(14).is_err());
+
+        storage.deposit(15).unwrap();
+        assert!(storage.deposit(16).is_err());
+        assert_eq!(storage.total_stored(), 6);
+
+        storage.withdraw(11).unwrap();
+        assert_eq!(storage.move_between_boxes(12, 1).unwrap(), 1);
+        assert_eq!(storage.locate(12), Some((1, 1)));
+        assert!(storage.move_between_boxes(13, 0).is_err());
+    }
+}
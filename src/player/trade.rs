@@ -0,0 +1,220 @@
+// 宝可梦交换
+// 开发心理：交换会同时修改两个玩家的数据，必须在双方同意、各自确实拥有宝可梦的前提下一次完成
+// 设计原则：先全部校验再修改，收到的宝可梦放在送出宝可梦原来的位置，交换进化只做标记由调用方播放进化流程
+
+use super::{Player, PlayerId};
+use super::storage::PokemonStorage;
+use crate::core::error::GameError;
+use log::debug;
+
+// 金属膜
+pub const METAL_COAT_ITEM_ID: u32 = 233;
+
+// 交换进化：(进化前, 需要携带的道具, 进化后)
+const TRADE_EVOLUTIONS: &[(u32, Option<u32>, u32)] = &[
+    (64, None, 65),                         // 勇基拉 -> 胡地
+    (67, None, 68),                         // 豪力 -> 怪力
+    (75, None, 76),                         // 隆隆石 -> 隆隆岩
+    (93, None, 94),                         // 鬼斯通 -> 耿鬼
+    (95, Some(METAL_COAT_ITEM_ID), 208),    // 大岩蛇 -> 大钢蛇
+    (123, Some(METAL_COAT_ITEM_ID), 212),   // 飞天螳螂 -> 巨钳螳螂
+];
+
+// 交换后满足进化条件时返回进化后的种类
+pub fn trade_evolution_target(species_id: u32, held_item: Option<u32>) -> Option<u32> {
+    TRADE_EVOLUTIONS.iter()
+        .find(|(from, item, _)| *from == species_id && (item.is_none() || *item == held_item))
+        .map(|(_, _, to)| *to)
+}
+
+// 交换结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeOutcome {
+    pub received_by_a: u64,             // 玩家A收到的宝可梦在A处的新ID
+    pub received_by_b: u64,
+    pub evolution_for_a: Option<u32>,   // 玩家A收到的宝可梦可进化成的种类
+    pub evolution_for_b: Option<u32>,
+}
+
+// 宝可梦在玩家处的位置
+#[derive(Debug, Clone, Copy)]
+enum Holding {
+    Team(usize),
+    Box(usize, usize),
+}
+
+fn locate(player: &Player, pokemon_id: u64) -> Option<Holding> {
+    if !player.pokemon_team.storage.contains_key(&pokemon_id) {
+        return None;
+    }
+    if let Some(index) = player.pokemon_team.active_team.iter().position(|&id| id == pokemon_id) {
+        return Some(Holding::Team(index));
+    }
+    player.pokemon_team.boxes.locate(pokemon_id).map(|(box_index, slot)| Holding::Box(box_index, slot))
+}
+
+fn place(boxes: &mut PokemonStorage, active_team: &mut [u64], holding: Holding, pokemon_id: u64) {
+    match holding {
+        Holding::Team(index) => active_team[index] = pokemon_id,
+        Holding::Box(box_index, slot) => boxes.boxes[box_index].slots[slot] = Some(pokemon_id),
+    }
+}
+
+// 交换双方的宝可梦，只能经由TradeSession::complete在双方确认后调用
+fn execute_trade(
+    player_a: &mut Player,
+    pokemon_a_id: u64,
+    player_b: &mut Player,
+    pokemon_b_id: u64,
+) -> Result<TradeOutcome, GameError> {
+    if player_a.id == player_b.id {
+        return Err(GameError::Player("不能和自己交换".to_string()));
+    }
+    let holding_a = locate(player_a, pokemon_a_id)
+        .ok_or_else(|| GameError::Player(format!("{} 没有宝可梦 {}", player_a.display_name, pokemon_a_id)))?;
+    let holding_b = locate(player_b, pokemon_b_id)
+        .ok_or_else(|| GameError::Player(format!("{} 没有宝可梦 {}", player_b.display_name, pokemon_b_id)))?;
+
+    let mut pokemon_a = player_a.pokemon_team.storage.remove(&pokemon_a_id).unwrap();
+    let mut pokemon_b = player_b.pokemon_team.storage.remove(&pokemon_b_id).unwrap();
+
+    // 换成接收方的ID，原训练家保持不变
+    pokemon_a.id = player_b.pokemon_team.next_pokemon_id;
+    player_b.pokemon_team.next_pokemon_id += 1;
+    pokemon_a.trainer_id = player_b.id;
+    pokemon_b.id = player_a.pokemon_team.next_pokemon_id;
+    player_a.pokemon_team.next_pokemon_id += 1;
+    pokemon_b.trainer_id = player_a.id;
+
+    let outcome = TradeOutcome {
+        received_by_a: pokemon_b.id,
+        received_by_b: pokemon_a.id,
+        evolution_for_a: trade_evolution_target(pokemon_b.species_id, pokemon_b.held_item),
+        evolution_for_b: trade_evolution_target(pokemon_a.species_id, pokemon_a.held_item),
+    };
+
+    for (player, holding, pokemon) in [(&mut *player_a, holding_a, pokemon_b), (&mut *player_b, holding_b, pokemon_a)] {
+        player.record_pokedex(pokemon.species_id, true, true);
+        player.stats.trades_completed += 1;
+        let team = &mut player.pokemon_team;
+        place(&mut team.boxes, &mut team.active_team, holding, pokemon.id);
+        team.storage.insert(pokemon.id, pokemon);
+    }
+
+    debug!("交换完成: {} <-> {}", player_a.display_name, player_b.display_name);
+    Ok(outcome)
+}
+
+// 交换提议
+#[derive(Debug, Clone)]
+pub struct TradeOffer {
+    pub player_id: PlayerId,
+    pub pokemon_id: u64,
+    pub confirmed: bool,
+}
+
+// 交换会话：双方都确认后才能执行
+#[derive(Debug, Clone)]
+pub struct TradeSession {
+    pub offer_a: TradeOffer,
+    pub offer_b: TradeOffer,
+}
+
+impl TradeSession {
+    pub fn new(player_a: PlayerId, pokemon_a_id: u64, player_b: PlayerId, pokemon_b_id: u64) -> Self {
+        let offer = |player_id, pokemon_id| TradeOffer { player_id, pokemon_id, confirmed: false };
+        Self {
+            offer_a: offer(player_a, pokemon_a_id),
+            offer_b: offer(player_b, pokemon_b_id),
+        }
+    }
+
+    pub fn confirm(&mut self, player_id: PlayerId) -> Result<(), GameError> {
+        if self.offer_a.player_id == player_id {
+            self.offer_a.confirmed = true;
+        } else if self.offer_b.player_id == player_id {
+            self.offer_b.confirmed = true;
+        } else {
+            return Err(GameError::Player(format!("玩家 {} 不在本次交换中", player_id)));
+        }
+        Ok(())
+    }
+
+    pub fn is_agreed(&self) -> bool {
+        self.offer_a.confirmed && self.offer_b.confirmed
+    }
+
+    pub fn complete(&self, player_a: &mut Player, player_b: &mut Player) -> Result<TradeOutcome, GameError> {
+        if !self.is_agreed() {
+            return Err(GameError::Player("交换双方尚未都确认".to_string()));
+        }
+        if player_a.id != self.offer_a.player_id || player_b.id != self.offer_b.player_id {
+            return Err(GameError::Player("交换玩家与提议不一致".to_string()));
+        }
+        execute_trade(player_a, self.offer_a.pokemon_id, player_b, self.offer_b.pokemon_id)
+    }
+}
+
+#[cfg(all(test, not(feature = "pokemon-wip")))]
+mod tests {
+    use super::*;
+    use crate::player::PlayerManager;
+    use crate::player::tests::test_pokemon;
+
+    fn test_player(name: &str, team: &[(u64, u32)]) -> Player {
+        let mut manager = PlayerManager::new();
+        manager.create_player(name.to_string(), name.to_string()).unwrap();
+        for &(id, species_id) in team {
+            manager.add_pokemon_to_team(test_pokemon(id, species_id, name)).unwrap();
+        }
+        let mut player = manager.get_current_player().unwrap().clone();
+        player.pokemon_team.next_pokemon_id = 100;
+        player
+    }
+
+    #[test]
+    fn test_trade_swaps_ownership() {
+        let mut ash = test_player("ash", &[(1, 25), (2, 1)]);
+        let mut gary = test_player("gary", &[(1, 64)]);
+        ash.id = 1;
+        gary.id = 2;
+
+        let mut session = TradeSession::new(ash.id, 2, gary.id, 1);
+        session.confirm(ash.id).unwrap();
+        assert!(session.complete(&mut ash, &mut gary).is_err());
+        session.confirm(gary.id).unwrap();
+
+        let outcome = session.complete(&mut ash, &mut gary).unwrap();
+
+        // 收到的宝可梦占据送出宝可梦原来的队伍位置
+        assert_eq!(ash.pokemon_team.active_team, vec![1, outcome.received_by_a]);
+        let received = &ash.pokemon_team.storage[&outcome.received_by_a];
+        assert_eq!((received.species_id, received.trainer_id), (64, ash.id));
+        assert_eq!(received.original_trainer, "gary");
+        assert!(!ash.pokemon_team.storage.contains_key(&2));
+        assert!(ash.pokedex[&64].caught);
+
+        assert_eq!(gary.pokemon_team.active_team, vec![outcome.received_by_b]);
+        assert_eq!(gary.pokemon_team.storage[&outcome.received_by_b].species_id, 1);
+        assert_eq!((ash.stats.trades_completed, gary.stats.trades_completed), (1, 1));
+
+        // 不再拥有的宝可梦不能再次交换
+        assert!(execute_trade(&mut ash, 2, &mut gary, outcome.received_by_b).is_err());
+    }
+
+    #[test]
+    fn test_trade_evolution_flagged() {
+        let mut ash = test_player("ash", &[(1, 95)]);
+        let mut gary = test_player("gary", &[(1, 64)]);
+        ash.id = 1;
+        gary.id = 2;
+
+        // 大岩蛇没有携带金属膜，不会进化
+        let outcome = execute_trade(&mut ash, 1, &mut gary, 1).unwrap();
+        assert_eq!(outcome.evolution_for_a, Some(65));
+        assert_eq!(outcome.evolution_for_b, None);
+
+        assert_eq!(trade_evolution_target(95, Some(METAL_COAT_ITEM_ID)), Some(208));
+        assert_eq!(trade_evolution_target(25, None), None);
+    }
+}
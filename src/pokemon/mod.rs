@@ -74,13 +74,6 @@ pub struct PokemonStats {
 
 // PokemonSpecies已在species.rs中定义，这里不需要重复定义
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Move {
-    pub name: String,
-    pub pp: u8,
-    pub secondary_effect: Option<crate::battle::SecondaryEffect>,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EvolutionChain;
 
@@ -131,12 +124,6 @@ impl PokemonStats {
 
 // PokemonSpecies的方法在species.rs中实现
 
-impl Move {
-    pub fn get(_id: MoveId) -> Option<&'static Self> {
-        None
-    }
-}
-
 impl EvolutionChain {
     pub fn check_conditions(&self, _pokemon: &Pokemon) -> bool {
         false
@@ -335,7 +322,7 @@ impl Pokemon {
     }
     
     // 获取种族信息
-    pub fn get_species(&self) -> Result<&'static PokemonSpecies> {
+    pub fn get_species(&self) -> Result<std::sync::Arc<PokemonSpecies>> {
         crate::pokemon::species::get_species(self.species_id)
             .ok_or_else(|| GameError::PokemonError("宝可梦种族数据丢失".to_string()))
     }
@@ -476,11 +463,25 @@ impl Pokemon {
         self.current_hp == 0
     }
     
-    // 应用状态异常
-    pub fn apply_status(&mut self, status: StatusCondition) {
+    // 应用状态异常，返回是否实际生效；属性免疫时不生效
+    pub fn apply_status(&mut self, status: StatusCondition) -> bool {
+        if self.is_immune_to(&status) {
+            debug!("{} 的属性免疫 {:?}", self.get_display_name(), status);
+            return false;
+        }
+        
         // 移除之前的状态异常（某些状态可以覆盖）
         self.status_conditions.retain(|s| !s.conflicts_with(&status));
         self.status_conditions.push(status);
+        true
+    }
+    
+    // 属性带来的状态免疫：火不会灼伤，冰不会冰冻，电不会麻痹，毒和钢不会中毒
+    pub fn is_immune_to(&self, status: &StatusCondition) -> bool {
+        let Ok(species) = self.get_species() else {
+            return false;
+        };
+        species.types.iter().any(|pokemon_type| status.is_immune_type(*pokemon_type))
     }
     
     // 清除状态异常
@@ -617,6 +618,18 @@ pub enum StatType {
 }
 
 impl StatusCondition {
+    // 该属性的宝可梦是否不会陷入此状态
+    pub fn is_immune_type(&self, pokemon_type: PokemonType) -> bool {
+        use StatusCondition::*;
+        matches!(
+            (self, pokemon_type),
+            (Burn, PokemonType::Fire)
+                | (Freeze, PokemonType::Ice)
+                | (Paralysis, PokemonType::Electric)
+                | (Poison | BadlyPoisoned, PokemonType::Poison | PokemonType::Steel)
+        )
+    }
+    
     // 检查状态是否冲突
     pub fn conflicts_with(&self, other: &StatusCondition) -> bool {
         use StatusCondition::*;
@@ -729,6 +742,26 @@ mod tests {
         assert!(!burn1.conflicts_with(&poison));
     }
     
+    #[test]
+    fn test_type_status_immunity() {
+        let mut charmander = Pokemon::new(4, 10, None, "Test".to_string(), "Test Location".to_string()).unwrap();
+        assert!(!charmander.apply_status(StatusCondition::Burn));
+        assert!(!charmander.has_status(&StatusCondition::Burn));
+        assert!(charmander.apply_status(StatusCondition::Poison));
+        
+        // 妙蛙种子是草/毒属性，不会中毒但会灼伤
+        let mut bulbasaur = Pokemon::new(1, 10, None, "Test".to_string(), "Test Location".to_string()).unwrap();
+        assert!(!bulbasaur.apply_status(StatusCondition::Poison));
+        assert!(!bulbasaur.apply_status(StatusCondition::BadlyPoisoned));
+        assert!(!bulbasaur.has_status(&StatusCondition::Poison));
+        assert!(bulbasaur.apply_status(StatusCondition::Burn));
+        
+        let mut pikachu = Pokemon::new(25, 10, None, "Test".to_string(), "Test Location".to_string()).unwrap();
+        assert!(!pikachu.apply_status(StatusCondition::Paralysis));
+        assert!(StatusCondition::Poison.is_immune_type(PokemonType::Steel));
+        assert!(!StatusCondition::Sleep { turns_remaining: 1 }.is_immune_type(PokemonType::Fire));
+    }
+    
     #[test]
     fn test_pokemon_manager() {
         let mut manager = PokemonManager::new();
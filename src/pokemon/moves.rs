@@ -3,10 +3,10 @@
 // 设计原则：数据驱动、可扩展的效果系统、支持自定义技能
 
 use crate::core::{GameError, Result};
-use crate::pokemon::{PokemonType, SpeciesId};
+use crate::pokemon::{PokemonType, SpeciesId, StatusCondition};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use lazy_static::lazy_static;
+use std::sync::Arc;
 use log::{debug, info};
 
 pub type MoveId = u16;
@@ -128,6 +128,20 @@ pub struct SecondaryEffect {
     pub condition: Option<MoveCondition>,
 }
 
+impl SecondaryEffect {
+    // 实际触发概率：附加效果的概率乘以效果自身带的概率
+    pub fn trigger_chance(&self) -> f32 {
+        let inner = match &self.effect {
+            MoveEffect::StatusChange { chance, .. }
+            | MoveEffect::StatChange { chance, .. }
+            | MoveEffect::Flinch { chance }
+            | MoveEffect::Confusion { chance } => *chance,
+            _ => 1.0,
+        };
+        self.chance * inner
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DamageFormula {
     Standard,               // 标准伤害公式
@@ -173,6 +187,21 @@ pub enum StatusEffect {
     None,
 }
 
+impl StatusEffect {
+    // 转换为宝可梦身上的异常状态，睡眠回合数由调用方决定
+    pub fn to_condition(self, sleep_turns: u8) -> StatusCondition {
+        match self {
+            StatusEffect::Burn => StatusCondition::Burn,
+            StatusEffect::Freeze => StatusCondition::Freeze,
+            StatusEffect::Paralysis => StatusCondition::Paralysis,
+            StatusEffect::Poison => StatusCondition::Poison,
+            StatusEffect::BadlyPoisoned => StatusCondition::BadlyPoisoned,
+            StatusEffect::Sleep => StatusCondition::Sleep { turns_remaining: sleep_turns },
+            StatusEffect::None => StatusCondition::None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum StatType {
     Attack,
@@ -184,7 +213,7 @@ pub enum StatType {
     Evasion,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum WeatherType {
     Sun,
     Rain,
@@ -317,49 +346,83 @@ pub struct BattleContext {
     pub consecutive_uses: u8,
 }
 
-// 全局技能数据库
-lazy_static! {
-    static ref MOVE_DATABASE: HashMap<MoveId, Move> = {
-        let mut db = HashMap::new();
-        load_basic_moves(&mut db);
-        debug!("技能数据库初始化完成，共加载了{}个技能", db.len());
-        db
-    };
+// 技能表：数据文件是技能数组，读入后按ID索引
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(from = "Vec<Move>")]
+pub struct MoveTable {
+    moves: HashMap<MoveId, Arc<Move>>,
+}
+
+impl From<Vec<Move>> for MoveTable {
+    fn from(moves: Vec<Move>) -> Self {
+        Self {
+            moves: moves.into_iter().map(|move_data| (move_data.id, Arc::new(move_data))).collect(),
+        }
+    }
+}
+
+impl MoveTable {
+    pub fn get(&self, move_id: MoveId) -> Option<&Arc<Move>> {
+        self.moves.get(&move_id)
+    }
+    
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<Move>> {
+        self.moves.values()
+    }
+    
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+}
+
+// 内置技能数据，数据目录中没有技能表时使用
+fn builtin_moves() -> MoveTable {
+    let mut db = HashMap::new();
+    load_basic_moves(&mut db);
+    debug!("使用内置技能数据，共{}个技能", db.len());
+    MoveTable::from(db.into_values().collect::<Vec<_>>())
 }
 
 // 根据技能ID获取技能数据
-pub fn get_move(move_id: MoveId) -> Option<&'static Move> {
-    MOVE_DATABASE.get(&move_id)
+pub fn get_move(move_id: MoveId) -> Option<Arc<Move>> {
+    get_all_moves().get(move_id).cloned()
 }
 
-// 获取所有技能数据
-pub fn get_all_moves() -> &'static HashMap<MoveId, Move> {
-    &MOVE_DATABASE
+// 获取技能表，通过数据注册表读取data/moves/moves.json，没有时使用内置数据
+pub fn get_all_moves() -> Arc<MoveTable> {
+    crate::data::DataRegistry::global().get_or_builtin(crate::data::DataType::Moves, builtin_moves)
 }
 
 // 根据名称查找技能
-pub fn get_move_by_name(name: &str) -> Option<&'static Move> {
-    MOVE_DATABASE.values()
+pub fn get_move_by_name(name: &str) -> Option<Arc<Move>> {
+    get_all_moves().iter()
         .find(|move_data| move_data.name.eq_ignore_ascii_case(name))
+        .cloned()
 }
 
 // 获取特定属性的所有技能
-pub fn get_moves_by_type(move_type: PokemonType) -> Vec<&'static Move> {
-    MOVE_DATABASE.values()
+pub fn get_moves_by_type(move_type: PokemonType) -> Vec<Arc<Move>> {
+    get_all_moves().iter()
         .filter(|move_data| move_data.move_type == move_type)
+        .cloned()
         .collect()
 }
 
 // 获取特定类别的所有技能
-pub fn get_moves_by_category(category: MoveCategory) -> Vec<&'static Move> {
-    MOVE_DATABASE.values()
+pub fn get_moves_by_category(category: MoveCategory) -> Vec<Arc<Move>> {
+    get_all_moves().iter()
         .filter(|move_data| move_data.category == category)
+        .cloned()
         .collect()
 }
 
 impl Move {
     // 静态方法：根据ID获取技能
-    pub fn get(move_id: MoveId) -> Option<&'static Self> {
+    pub fn get(move_id: MoveId) -> Option<Arc<Self>> {
         get_move(move_id)
     }
     
@@ -840,6 +903,8 @@ mod tests {
         
         let secondary = &ember.secondary_effects[0];
         assert_eq!(secondary.chance, 0.1);
+        // 灼伤效果本身必定生效，整体触发概率就是附加效果的概率
+        assert!((secondary.trigger_chance() - 0.1).abs() < f32::EPSILON);
     }
     
     #[test]
@@ -4,7 +4,9 @@
 
 use super::{BaseStats, AbilityId, MoveId, EvolutionChain, SpeciesId};
 use serde::{Deserialize, Serialize};
+use crate::core::{GameError, Result};
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 use lazy_static::lazy_static;
 use log::debug;
 
@@ -34,7 +36,7 @@ pub struct PokemonSpecies {
     pub learnable_moves: Vec<LearnableMove>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PokemonType {
     Normal,
     Fire,
@@ -162,17 +164,25 @@ pub enum LearnMethod {
 
 impl PokemonSpecies {
     // 根据ID获取种族（静态方法）
-    pub fn get(species_id: SpeciesId) -> Option<&'static Self> {
+    pub fn get(species_id: SpeciesId) -> Option<Arc<Self>> {
         get_species(species_id)
     }
     
     // 获取所有种族
-    pub fn get_all() -> &'static HashMap<SpeciesId, PokemonSpecies> {
+    pub fn get_all() -> Arc<SpeciesTable> {
         get_all_species()
     }
     
-    pub fn get_by_name(name: &str) -> Option<&'static Self> {
-        SPECIES_DATABASE.values().find(|species| species.name.eq_ignore_ascii_case(name))
+    pub fn get_by_name(name: &str) -> Option<Arc<Self>> {
+        get_all_species().iter()
+            .find(|species| species.name.eq_ignore_ascii_case(name))
+            .cloned()
+            .or_else(|| {
+                CUSTOM_SPECIES.read().unwrap()
+                    .values()
+                    .find(|species| species.name.eq_ignore_ascii_case(name))
+                    .cloned()
+            })
     }
     
     pub fn generate_gender(&self) -> crate::pokemon::Gender {
@@ -289,26 +299,97 @@ impl GrowthRate {
     }
 }
 
-// 第一个SPECIES_DATABASE定义已移除，保留下面的定义
+// 种族表：数据文件是种族数组，读入后按ID索引
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(from = "Vec<PokemonSpecies>")]
+pub struct SpeciesTable {
+    species: HashMap<SpeciesId, Arc<PokemonSpecies>>,
+}
+
+impl From<Vec<PokemonSpecies>> for SpeciesTable {
+    fn from(species: Vec<PokemonSpecies>) -> Self {
+        Self {
+            species: species.into_iter().map(|species| (species.id, Arc::new(species))).collect(),
+        }
+    }
+}
+
+impl SpeciesTable {
+    pub fn get(&self, species_id: SpeciesId) -> Option<&Arc<PokemonSpecies>> {
+        self.species.get(&species_id)
+    }
+    
+    pub fn contains(&self, species_id: SpeciesId) -> bool {
+        self.species.contains_key(&species_id)
+    }
+    
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<PokemonSpecies>> {
+        self.species.values()
+    }
+    
+    pub fn len(&self) -> usize {
+        self.species.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.species.is_empty()
+    }
+}
+
+// 内置的第一世代种族数据，数据目录中没有种族表时使用
+fn builtin_species() -> SpeciesTable {
+    let mut db = HashMap::new();
+    add_gen1_pokemon(&mut db);
+    debug!("使用内置种族数据，共{}个种族", db.len());
+    SpeciesTable::from(db.into_values().collect::<Vec<_>>())
+}
+
+// 自定义种族从这个ID开始分配，与内置种族的编号分开
+pub const CUSTOM_SPECIES_ID_START: SpeciesId = 10000;
 
-// 全局种族数据库
+// 运行时注册的自定义种族，不随数据表重新加载而清除
 lazy_static! {
-    static ref SPECIES_DATABASE: HashMap<SpeciesId, PokemonSpecies> = {
-        let mut db = HashMap::new();
-        add_gen1_pokemon(&mut db);
-        debug!("宝可梦种族数据库初始化完成，共加载了{}个种族", db.len());
-        db
-    };
+    static ref CUSTOM_SPECIES: RwLock<HashMap<SpeciesId, Arc<PokemonSpecies>>> = RwLock::new(HashMap::new());
+}
+
+// 根据种族ID获取种族数据，包括运行时注册的自定义种族
+pub fn get_species(species_id: SpeciesId) -> Option<Arc<PokemonSpecies>> {
+    get_all_species().get(species_id).cloned()
+        .or_else(|| CUSTOM_SPECIES.read().unwrap().get(&species_id).cloned())
+}
+
+// 按species.id注册自定义种族，ID已被内置或自定义种族占用时报错
+pub fn register_species(species: PokemonSpecies) -> Result<Arc<PokemonSpecies>> {
+    let mut custom = CUSTOM_SPECIES.write().unwrap();
+    if get_all_species().contains(species.id) || custom.contains_key(&species.id) {
+        return Err(GameError::PokemonError(format!("种族ID {} 已被占用", species.id)));
+    }
+    Ok(insert_custom_species(&mut custom, species))
+}
+
+// 分配一个未使用的自定义种族ID并注册，分配和注册在同一把锁内完成
+pub fn register_species_with_new_id(mut species: PokemonSpecies) -> Result<Arc<PokemonSpecies>> {
+    let mut custom = CUSTOM_SPECIES.write().unwrap();
+    let table = get_all_species();
+    species.id = (CUSTOM_SPECIES_ID_START..=SpeciesId::MAX)
+        .find(|id| !table.contains(*id) && !custom.contains_key(id))
+        .ok_or_else(|| GameError::PokemonError("自定义种族ID已用完".to_string()))?;
+    Ok(insert_custom_species(&mut custom, species))
 }
 
-// 根据种族ID获取种族数据
-pub fn get_species(species_id: SpeciesId) -> Option<&'static PokemonSpecies> {
-    SPECIES_DATABASE.get(&species_id)
+fn insert_custom_species(
+    custom: &mut HashMap<SpeciesId, Arc<PokemonSpecies>>,
+    species: PokemonSpecies,
+) -> Arc<PokemonSpecies> {
+    let species = Arc::new(species);
+    custom.insert(species.id, species.clone());
+    debug!("注册自定义种族: {} (#{})", species.name, species.id);
+    species
 }
 
-// 获取所有种族数据
-pub fn get_all_species() -> &'static HashMap<SpeciesId, PokemonSpecies> {
-    &SPECIES_DATABASE
+// 获取种族表，通过数据注册表读取data/pokemon/species.json，没有时使用内置数据
+pub fn get_all_species() -> Arc<SpeciesTable> {
+    crate::data::DataRegistry::global().get_or_builtin(crate::data::DataType::Pokemon, builtin_species)
 }
 
 fn add_gen1_pokemon(db: &mut HashMap<SpeciesId, PokemonSpecies>) {
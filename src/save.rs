@@ -4,6 +4,9 @@
 
 use crate::core::{GameError, Result};
 use crate::player::Player;
+use crate::player::inventory::{Inventory, ItemDatabase, Pocket};
+use crate::player::progress::GameProgress;
+use crate::player::storage::PokemonStorage;
 use crate::game_modes::{GameMode, GameState};
 #[cfg(feature = "pokemon-wip")]
 use crate::pokemon::Pokemon;
@@ -16,6 +19,7 @@ pub struct Pokemon {
     pub level: u8,
 }
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::fs::{File, create_dir_all};
 use std::io::{BufReader, BufWriter, Write};
@@ -480,6 +484,234 @@ impl SaveManager {
     }
 }
 
+// 玩家存档格式版本
+// 1: 没有版本号的旧存档，背包按物品类型限容，没有金钱、盒子和训练家ID
+// 2: 背包按口袋限容，新增金钱、盒子、训练家ID和成就统计项
+pub const PLAYER_SAVE_VERSION: u32 = 2;
+
+// 玩家存档文件，player保持原始JSON以便按版本迁移
+#[derive(Debug, Serialize, Deserialize)]
+struct PlayerSaveFile {
+    save_version: u32,
+    player: Value,
+}
+
+// 序列化玩家，附带当前存档版本
+pub fn player_to_json(player: &Player) -> Result<String> {
+    let file = PlayerSaveFile {
+        save_version: PLAYER_SAVE_VERSION,
+        player: serde_json::to_value(player)?,
+    };
+    Ok(serde_json::to_string_pretty(&file)?)
+}
+
+// 读取玩家存档，旧版本先逐字段升级再反序列化
+pub fn player_from_json(data: &str) -> Result<Player> {
+    let value: Value = serde_json::from_str(data)?;
+    let (player, version) = match value.get("save_version").and_then(Value::as_u64) {
+        Some(version) => {
+            let file: PlayerSaveFile = serde_json::from_value(value)?;
+            (file.player, version as u32)
+        },
+        None => (value, 1), // 没有版本号的是第一版存档
+    };
+    
+    let player = migrate(player, version)?;
+    serde_json::from_value(player)
+        .map_err(|e| GameError::SaveError(format!("存档反序列化失败: {}", e)))
+}
+
+// 把from_version版本的玩家数据逐版本升级到当前版本
+pub fn migrate(mut player: Value, from_version: u32) -> Result<Value> {
+    if from_version == 0 || from_version > PLAYER_SAVE_VERSION {
+        return Err(GameError::SaveError(format!(
+            "不支持的存档版本 {}，当前游戏支持到版本 {}，请更新游戏",
+            from_version, PLAYER_SAVE_VERSION
+        )));
+    }
+    
+    for version in from_version..PLAYER_SAVE_VERSION {
+        match version {
+            1 => migrate_v1_to_v2(&mut player)?,
+            _ => unreachable!("缺少存档版本 {} 的迁移", version),
+        }
+        info!("玩家存档从版本 {} 升级到 {}", version, version + 1);
+    }
+    
+    Ok(player)
+}
+
+fn migrate_v1_to_v2(player: &mut Value) -> Result<()> {
+    let player = player.as_object_mut()
+        .ok_or_else(|| GameError::SaveError("玩家存档格式错误".to_string()))?;
+    let player_id = player.get("id").and_then(Value::as_u64).unwrap_or(0);
+    
+    player.entry("money").or_insert(json!(crate::player::STARTING_MONEY));
+    
+    // 背包容量从按物品类型改为按口袋，每个物品记录所在口袋
+    if let Some(inventory) = player.get_mut("inventory").and_then(Value::as_object_mut) {
+        inventory.insert("capacity".to_string(), serde_json::to_value(Inventory::new().capacity)?);
+        if let Some(items) = inventory.get_mut("items").and_then(Value::as_object_mut) {
+            let item_database = ItemDatabase::global();
+            for item in items.values_mut().filter_map(Value::as_object_mut) {
+                let pocket = item.get("item_id")
+                    .and_then(Value::as_u64)
+                    .and_then(|item_id| item_database.get_item(item_id as u32))
+                    .map(|data| Pocket::from_item_type(data.item_type))
+                    .unwrap_or_default();
+                item.insert("pocket".to_string(), serde_json::to_value(pocket)?);
+            }
+        }
+    }
+    
+    // 不在队伍中的宝可梦放进盒子，补上训练家ID
+    if let Some(team) = player.get_mut("pokemon_team").and_then(Value::as_object_mut) {
+        let active_team: Vec<u64> = team.get("active_team")
+            .and_then(Value::as_array)
+            .map(|ids| ids.iter().filter_map(Value::as_u64).collect())
+            .unwrap_or_default();
+        
+        let mut boxed = Vec::new();
+        if let Some(storage) = team.get_mut("storage").and_then(Value::as_object_mut) {
+            for pokemon in storage.values_mut().filter_map(Value::as_object_mut) {
+                pokemon.entry("trainer_id").or_insert(json!(player_id));
+                match pokemon.get("id").and_then(Value::as_u64) {
+                    Some(id) if !active_team.contains(&id) => boxed.push(id),
+                    _ => {},
+                }
+            }
+        }
+        
+        boxed.sort_unstable();
+        let mut boxes = PokemonStorage::default();
+        for pokemon_id in boxed {
+            boxes.deposit(pokemon_id)?;
+        }
+        team.insert("boxes".to_string(), serde_json::to_value(boxes)?);
+    }
+    
+    // 内置成就补上统计项，之后才能随统计自动解锁
+    let defaults = GameProgress::new().achievements;
+    let achievements = player.get_mut("progress")
+        .and_then(|progress| progress.get_mut("achievements"))
+        .and_then(Value::as_object_mut);
+    if let Some(achievements) = achievements {
+        for (id, achievement) in achievements.iter_mut() {
+            let stat = id.parse::<u32>().ok()
+                .and_then(|id| defaults.get(&id))
+                .and_then(|default| default.stat);
+            if let Some(achievement) = achievement.as_object_mut() {
+                achievement.entry("stat").or_insert(serde_json::to_value(stat)?);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+// 玩家存档槽位数
+pub const PLAYER_SLOT_COUNT: u8 = 10;
+
+// 存档槽摘要，单独保存以便读档菜单不用解析完整存档
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveSlot {
+    pub index: u8,
+    pub player_name: String,
+    pub saved_at: u64,                      // Unix时间戳(秒)
+    pub playtime: u64,                      // 秒
+    pub badge_count: u32,
+    pub party_preview: Vec<PartyPreview>,
+}
+
+// 队伍预览
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartyPreview {
+    pub species_id: u32,
+    pub level: u8,
+    pub is_shiny: bool,
+}
+
+impl SaveSlot {
+    pub fn from_player(index: u8, player: &Player) -> Result<Self> {
+        let team = &player.pokemon_team;
+        let party_preview = team.active_team.iter()
+            .filter_map(|id| team.storage.get(id))
+            .map(|pokemon| PartyPreview {
+                species_id: pokemon.species_id,
+                level: pokemon.level,
+                is_shiny: pokemon.is_shiny,
+            })
+            .collect();
+        
+        Ok(Self {
+            index,
+            player_name: player.display_name.clone(),
+            saved_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            playtime: player.stats.playtime,
+            badge_count: player.progress.badges.len() as u32,
+            party_preview,
+        })
+    }
+}
+
+fn slot_path(directory: &Path, index: u8) -> PathBuf {
+    directory.join(format!("slot_{:02}.json", index))
+}
+
+fn slot_summary_path(directory: &Path, index: u8) -> PathBuf {
+    directory.join(format!("slot_{:02}.meta.json", index))
+}
+
+fn check_slot_index(index: u8) -> Result<()> {
+    if index >= PLAYER_SLOT_COUNT {
+        return Err(GameError::SaveError(format!(
+            "存档槽 {} 超出范围(0-{})", index, PLAYER_SLOT_COUNT - 1
+        )));
+    }
+    Ok(())
+}
+
+// 把玩家写入存档槽，同时写入摘要
+pub fn write_slot(directory: &Path, index: u8, player: &Player) -> Result<SaveSlot> {
+    check_slot_index(index)?;
+    create_dir_all(directory)?;
+    
+    let summary = SaveSlot::from_player(index, player)?;
+    std::fs::write(slot_path(directory, index), player_to_json(player)?)?;
+    std::fs::write(slot_summary_path(directory, index), serde_json::to_string_pretty(&summary)?)?;
+    
+    info!("玩家 {} 已保存到存档槽 {}", player.display_name, index);
+    Ok(summary)
+}
+
+// 从存档槽读取玩家
+pub fn read_slot(directory: &Path, index: u8) -> Result<Player> {
+    check_slot_index(index)?;
+    let path = slot_path(directory, index);
+    if !path.exists() {
+        return Err(GameError::SaveError(format!("存档槽 {} 不存在", index)));
+    }
+    
+    player_from_json(&std::fs::read_to_string(path)?)
+}
+
+// 列出所有已使用存档槽的摘要，只读取摘要文件
+pub fn list_slots(directory: &Path) -> Result<Vec<SaveSlot>> {
+    let mut slots = Vec::new();
+    for index in 0..PLAYER_SLOT_COUNT {
+        let path = slot_summary_path(directory, index);
+        if !path.exists() {
+            continue;
+        }
+        
+        match serde_json::from_str::<SaveSlot>(&std::fs::read_to_string(&path)?) {
+            Ok(summary) => slots.push(summary),
+            Err(e) => warn!("存档槽 {} 的摘要损坏: {}", index, e),
+        }
+    }
+    Ok(slots)
+}
+
 // 存档信息
 #[derive(Debug, Clone)]
 pub struct SaveInfo {
@@ -614,4 +846,64 @@ mod tests {
         assert_eq!(info.slot, 2);
         assert_eq!(info.player_name, "信息测试");
     }
-}
\ No newline at end of file
+    
+    // 第一版存档：没有版本号，容量按物品类型，没有金钱、盒子和训练家ID
+    #[cfg(not(feature = "pokemon-wip"))]
+    const V1_PLAYER_SAVE: &str = include_str!("../tests/fixtures/saves/player_v1.json");
+    
+    #[cfg(not(feature = "pokemon-wip"))]
+    #[test]
+    fn test_migrate_v1_player_save() {
+        let player = player_from_json(V1_PLAYER_SAVE).unwrap();
+        
+        assert_eq!((player.id, player.username.as_str()), (1001, "oldsave"));
+        assert_eq!(player.money, crate::player::STARTING_MONEY);
+        assert_eq!(player.inventory.count(1), 5);
+        assert_eq!(player.inventory.pocket_items(Pocket::PokeBalls).len(), 1);
+        assert_eq!(player.inventory.pocket_capacity(Pocket::PokeBalls), Inventory::new().pocket_capacity(Pocket::PokeBalls));
+        assert!(player.pokemon_team.boxes.contains(2));
+        assert!(!player.pokemon_team.boxes.contains(1));
+        assert!(player.pokemon_team.storage.values().all(|pokemon| pokemon.trainer_id == player.id));
+        assert!(player.progress.achievements[&1].stat.is_some());
+        
+        // 重新保存后带上当前版本号，可以原样读回
+        let saved = player_to_json(&player).unwrap();
+        assert!(saved.contains("\"save_version\""));
+        assert_eq!(player_from_json(&saved).unwrap().money, player.money);
+    }
+    
+    #[test]
+    fn test_future_save_version_rejected() {
+        let data = json!({ "save_version": PLAYER_SAVE_VERSION + 1, "player": {} }).to_string();
+        let error = player_from_json(&data).unwrap_err();
+        assert!(error.to_string().contains("不支持的存档版本"));
+    }
+    
+    #[cfg(not(feature = "pokemon-wip"))]
+    #[test]
+    fn test_list_slots_returns_summaries() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut manager = crate::player::PlayerManager::new();
+        manager.set_save_directory(temp_dir.path());
+        manager.create_player("slots".to_string(), "Slots".to_string()).unwrap();
+        manager.add_pokemon_to_team(crate::player::tests::test_pokemon(1, 25, "slots")).unwrap();
+        
+        manager.save_to_slot(1).unwrap();
+        manager.get_current_player_mut().unwrap().stats.playtime = 3600;
+        manager.save_to_slot(4).unwrap();
+        assert!(manager.save_to_slot(PLAYER_SLOT_COUNT).is_err());
+        
+        let slots = manager.list_slots().unwrap();
+        assert_eq!(slots.iter().map(|slot| slot.index).collect::<Vec<_>>(), vec![1, 4]);
+        assert_eq!(slots[0].player_name, "Slots");
+        assert_eq!(slots[0].playtime, 0);
+        assert_eq!(slots[1].playtime, 3600);
+        assert_eq!(slots[1].badge_count, 0);
+        assert_eq!(slots[1].party_preview, vec![PartyPreview { species_id: 25, level: 20, is_shiny: false }]);
+        
+        // 读回较早的存档槽
+        manager.load_from_slot(1).unwrap();
+        assert_eq!(manager.get_current_player().unwrap().stats.playtime, 0);
+        assert!(manager.load_from_slot(2).is_err());
+    }
+}
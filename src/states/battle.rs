@@ -23,9 +23,10 @@ pub struct PokemonStats { pub hp: u32, pub attack: u32, pub defense: u32, pub sp
 
 #[derive(Debug, Clone)]
 pub struct DualType(pub Option<PokemonType>, pub Option<PokemonType>);
-use super::{StateHandler, GameStateType, StateTransition};
+use super::{StateHandler, GameStateType, StateTransition, GameState, StateSystemsExt};
 use glam::{Vec2, Vec4};
 use std::collections::HashMap;
+use bevy::prelude::{App, Commands, Plugin, Res, ResMut, Resource, Time};
 
 // 战斗动画管理器
 #[derive(Debug)]
@@ -826,6 +827,52 @@ impl StateHandler for BattleState {
     }
 }
 
+// 战斗场景独占的资源
+const BATTLE_SCENE_ASSETS: [&str; 4] = [
+    "battle/background",
+    "battle/platforms",
+    "battle/hud",
+    "battle/move_effects",
+];
+
+// 进入战斗时加载，离开战斗时释放
+#[derive(Resource, Debug, Default)]
+pub struct BattleSceneResources {
+    pub assets: Vec<String>,
+    pub elapsed: f32,
+}
+
+// Bevy插件：战斗状态的资源加载/释放和战斗期间运行的系统
+pub struct BattleStatePlugin;
+
+impl Plugin for BattleStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.on_state_enter(GameState::Battle, load_battle_resources)
+           .on_state_exit(GameState::Battle, unload_battle_resources)
+           .add_state_systems(GameState::Battle, tick_battle_scene);
+        
+        // 进行中的战斗每帧检查限时
+        #[cfg(feature = "battle-wip")]
+        app.init_resource::<crate::battle::ActiveBattle>()
+           .add_state_systems(GameState::Battle, crate::battle::update_active_battle);
+    }
+}
+
+fn load_battle_resources(mut commands: Commands) {
+    let assets: Vec<String> = BATTLE_SCENE_ASSETS.iter().map(|name| name.to_string()).collect();
+    debug!("加载战斗资源: {:?}", assets);
+    commands.insert_resource(BattleSceneResources { assets, elapsed: 0.0 });
+}
+
+fn unload_battle_resources(mut commands: Commands) {
+    debug!("释放战斗资源");
+    commands.remove_resource::<BattleSceneResources>();
+}
+
+fn tick_battle_scene(time: Res<Time>, mut scene: ResMut<BattleSceneResources>) {
+    scene.elapsed += time.delta_seconds();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
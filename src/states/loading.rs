@@ -15,9 +15,15 @@ use crate::states::Renderer2D;
 use crate::ui::{UIManager, ElementType, UIEvent};
 use crate::input::mouse::MouseEvent;
 use crate::input::gamepad::GamepadEvent;
-use super::{StateHandler, GameStateType, StateTransition};
+use super::{StateHandler, GameStateType, StateTransition, GameState, StateSystemsExt};
+use crate::assets::{AssetRegistry, QueueProgress};
 use glam::{Vec2, Vec4};
 use std::collections::HashMap;
+use bevy::prelude::{App, NextState, Plugin, ResMut, Resource};
+
+// 进度条的位置和满进度时的大小
+const PROGRESS_BAR_POSITION: (f32, f32) = (200.0, 400.0);
+const PROGRESS_BAR_SIZE: (f32, f32) = (400.0, 40.0);
 
 // 加载任务
 #[derive(Debug, Clone)]
@@ -29,6 +35,32 @@ pub struct LoadingTask {
     pub error: Option<String>,
 }
 
+// 后台加载进度的来源
+pub trait LoadProgressSource: Send {
+    fn load_progress(&mut self) -> QueueProgress;
+}
+
+impl LoadProgressSource for AssetRegistry {
+    fn load_progress(&mut self) -> QueueProgress {
+        AssetRegistry::load_progress(self)
+    }
+}
+
+// 全局资源注册表中的后台加载队列
+pub struct GlobalAssetQueue;
+
+impl LoadProgressSource for GlobalAssetQueue {
+    fn load_progress(&mut self) -> QueueProgress {
+        match AssetRegistry::instance() {
+            Ok(mut assets) => assets.load_progress(),
+            Err(e) => {
+                warn!("无法读取资源加载进度: {}", e);
+                QueueProgress::default()
+            }
+        }
+    }
+}
+
 // 加载状态
 pub struct LoadingState {
     name: String,
@@ -38,11 +70,13 @@ pub struct LoadingState {
     tasks: Vec<LoadingTask>,
     current_task_index: usize,
     total_progress: f32,
+    current_label: String,
+    progress_source: Box<dyn LoadProgressSource>,
     
     // UI元素ID
-    progress_bar_id: Option<u32>,
-    status_label_id: Option<u32>,
-    title_label_id: Option<u32>,
+    progress_bar_id: Option<String>,
+    status_label_id: Option<String>,
+    title_label_id: Option<String>,
     
     // 状态
     loading_complete: bool,
@@ -64,12 +98,19 @@ pub struct LoadingState {
 
 impl LoadingState {
     pub fn new() -> Self {
+        Self::with_progress_source(GlobalAssetQueue)
+    }
+    
+    // 指定后台加载进度的来源
+    pub fn with_progress_source<S: LoadProgressSource + 'static>(source: S) -> Self {
         Self {
             name: "LoadingState".to_string(),
             ui_manager: UIManager::new(Vec2::new(800.0, 600.0)),
             tasks: Vec::new(),
             current_task_index: 0,
             total_progress: 0.0,
+            current_label: String::new(),
+            progress_source: Box::new(source),
             progress_bar_id: None,
             status_label_id: None,
             title_label_id: None,
@@ -100,147 +141,93 @@ impl LoadingState {
         debug!("设置下一状态: {:?}", state);
     }
     
-    // 创建默认加载任务
-    fn create_default_tasks(&mut self) {
-        let tasks = vec![
-            LoadingTask {
-                name: "textures".to_string(),
-                description: "加载纹理资源...".to_string(),
-                progress: 0.0,
-                completed: false,
-                error: None,
-            },
-            LoadingTask {
-                name: "audio".to_string(),
-                description: "加载音频资源...".to_string(),
-                progress: 0.0,
-                completed: false,
-                error: None,
-            },
-            LoadingTask {
-                name: "pokemon_data".to_string(),
-                description: "加载Pokemon数据...".to_string(),
-                progress: 0.0,
-                completed: false,
-                error: None,
-            },
-            LoadingTask {
-                name: "game_data".to_string(),
-                description: "初始化游戏数据...".to_string(),
-                progress: 0.0,
-                completed: false,
-                error: None,
-            },
-            LoadingTask {
-                name: "save_data".to_string(),
-                description: "检查存档数据...".to_string(),
-                progress: 0.0,
-                completed: false,
-                error: None,
-            },
-        ];
-        
-        self.set_loading_tasks(tasks);
+    // 总进度（0.0-1.0）
+    pub fn progress(&self) -> f32 {
+        self.total_progress
+    }
+    
+    // 当前加载项的描述，显示在进度条下方
+    pub fn current_label(&self) -> &str {
+        &self.current_label
     }
     
     // 初始化UI
     fn setup_ui(&mut self) -> Result<(), GameError> {
         // 标题
-        self.title_label_id = Some(self.ui_manager.create_element(
-            "title_label".to_string(),
-            ElementType::Label,
-            None,
-        )?);
-        
-        if let Some(title_id) = self.title_label_id {
-            self.ui_manager.set_element_text(title_id, "Pokemon GO - 加载中...".to_string())?;
-            self.ui_manager.set_element_position(title_id, Vec2::new(400.0, 150.0))?;
-            self.ui_manager.set_element_size(title_id, Vec2::new(400.0, 60.0))?;
-        }
+        let title_id = "title_label".to_string();
+        self.ui_manager.create_element(title_id.clone(), ElementType::Text)?;
+        self.ui_manager.set_element_text(&title_id, "Pokemon GO - 加载中...".to_string())?;
+        self.ui_manager.set_element_position(&title_id, (400.0, 150.0))?;
+        self.ui_manager.set_element_size(&title_id, (400.0, 60.0))?;
+        self.title_label_id = Some(title_id);
         
         // 进度条容器
-        let progress_container = self.ui_manager.create_element(
-            "progress_container".to_string(),
-            ElementType::Panel,
-            None,
-        )?;
-        self.ui_manager.set_element_position(progress_container, Vec2::new(200.0, 400.0))?;
-        self.ui_manager.set_element_size(progress_container, Vec2::new(400.0, 40.0))?;
-        
-        // 进度条
-        self.progress_bar_id = Some(self.ui_manager.create_element(
-            "progress_bar".to_string(),
-            ElementType::ProgressBar,
-            Some(progress_container),
-        )?);
-        
-        if let Some(progress_id) = self.progress_bar_id {
-            self.ui_manager.set_element_position(progress_id, Vec2::new(0.0, 0.0))?;
-            self.ui_manager.set_element_size(progress_id, Vec2::new(400.0, 40.0))?;
-        }
+        let container_id = "progress_container";
+        self.ui_manager.create_element(container_id.to_string(), ElementType::Panel)?;
+        self.ui_manager.set_element_position(container_id, PROGRESS_BAR_POSITION)?;
+        self.ui_manager.set_element_size(container_id, PROGRESS_BAR_SIZE)?;
+        
+        // 进度条，宽度随进度变化，初始为0
+        let progress_id = "progress_bar".to_string();
+        self.ui_manager.create_element(progress_id.clone(), ElementType::Panel)?;
+        self.ui_manager.set_element_position(&progress_id, PROGRESS_BAR_POSITION)?;
+        self.ui_manager.set_element_size(&progress_id, (0.0, PROGRESS_BAR_SIZE.1))?;
+        self.progress_bar_id = Some(progress_id);
         
         // 状态标签
-        self.status_label_id = Some(self.ui_manager.create_element(
-            "status_label".to_string(),
-            ElementType::Label,
-            None,
-        )?);
-        
-        if let Some(status_id) = self.status_label_id {
-            self.ui_manager.set_element_text(status_id, "初始化...".to_string())?;
-            self.ui_manager.set_element_position(status_id, Vec2::new(400.0, 480.0))?;
-            self.ui_manager.set_element_size(status_id, Vec2::new(400.0, 30.0))?;
-        }
+        let status_id = "status_label".to_string();
+        self.ui_manager.create_element(status_id.clone(), ElementType::Text)?;
+        self.ui_manager.set_element_text(&status_id, "初始化...".to_string())?;
+        self.ui_manager.set_element_position(&status_id, (400.0, 480.0))?;
+        self.ui_manager.set_element_size(&status_id, (400.0, 30.0))?;
+        self.status_label_id = Some(status_id);
         
         debug!("加载状态UI初始化完成");
         Ok(())
     }
     
-    // 更新进度
+    // 更新进度，手动设置的加载任务和资源注册表中的后台加载合并计算
     fn update_progress(&mut self) {
-        if self.tasks.is_empty() {
-            return;
-        }
-        
-        // 计算总进度
+        let queue = self.progress_source.load_progress();
         let completed_tasks = self.tasks.iter().filter(|t| t.completed).count() as f32;
-        let current_task_progress = if self.current_task_index < self.tasks.len() {
-            self.tasks[self.current_task_index].progress
-        } else {
+        let current_task = self.tasks.get(self.current_task_index).filter(|t| !t.completed);
+        let current_task_progress = current_task.map_or(0.0, |t| t.progress);
+        
+        let total = self.tasks.len() + queue.total;
+        self.total_progress = if total == 0 {
             1.0
+        } else {
+            (completed_tasks + current_task_progress + queue.completed as f32) / total as f32
         };
-        
-        self.total_progress = (completed_tasks + current_task_progress) / self.tasks.len() as f32;
         self.total_progress = self.total_progress.clamp(0.0, 1.0);
         
+        self.current_label = match current_task {
+            Some(task) if self.show_detailed_progress => {
+                format!("{} ({:.0}%)", task.description, task.progress * 100.0)
+            },
+            Some(task) => task.description.clone(),
+            None => queue_label(&queue),
+        };
+        
+        if self.total_progress >= 1.0 && !self.loading_complete {
+            self.loading_complete = true;
+            debug!("所有加载任务完成");
+        }
+        
         // 更新UI
-        if let Some(progress_id) = self.progress_bar_id {
-            let progress_text = format!("{:.0}%", self.total_progress * 100.0);
-            self.ui_manager.set_element_value(progress_id, progress_text).ok();
+        if let Some(progress_id) = &self.progress_bar_id {
+            let width = PROGRESS_BAR_SIZE.0 * self.total_progress;
+            self.ui_manager.set_element_size(progress_id, (width, PROGRESS_BAR_SIZE.1)).ok();
         }
         
-        if let Some(status_id) = self.status_label_id {
-            if self.current_task_index < self.tasks.len() {
-                let current_task = &self.tasks[self.current_task_index];
-                let status_text = if self.show_detailed_progress {
-                    format!("{} ({:.0}%)", current_task.description, current_task.progress * 100.0)
-                } else {
-                    current_task.description.clone()
-                };
-                self.ui_manager.set_element_text(status_id, status_text).ok();
-            } else {
-                self.ui_manager.set_element_text(status_id, "加载完成!".to_string()).ok();
-            }
+        if let Some(status_id) = &self.status_label_id {
+            self.ui_manager.set_element_text(status_id, self.current_label.clone()).ok();
         }
     }
     
     // 模拟加载任务
     fn simulate_loading(&mut self, delta_time: f32) {
         if self.current_task_index >= self.tasks.len() {
-            if !self.loading_complete {
-                self.loading_complete = true;
-                debug!("所有加载任务完成");
-            }
             return;
         }
         
@@ -337,11 +324,6 @@ impl StateHandler for LoadingState {
         self.total_progress = 0.0;
         self.fade_alpha = 0.0;
         
-        // 创建加载任务
-        if self.tasks.is_empty() {
-            self.create_default_tasks();
-        }
-        
         // 初始化UI
         self.setup_ui()?;
         
@@ -378,7 +360,7 @@ impl StateHandler for LoadingState {
         
         // 检查是否完成
         if self.should_transition() {
-            return Ok(StateTransition::Replace(self.next_state));
+            return Ok(StateTransition::Replace(self.next_state.clone()));
         }
         
         Ok(StateTransition::None)
@@ -456,9 +438,45 @@ impl StateHandler for LoadingState {
     }
 }
 
+// 后台加载的进度文字
+fn queue_label(queue: &QueueProgress) -> String {
+    match &queue.current {
+        Some(asset_id) => format!("加载 {} ({}/{})", asset_id, queue.completed, queue.total),
+        None => "加载完成!".to_string(),
+    }
+}
+
+// 加载界面的进度，供Bevy UI的进度条显示
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct LoadingProgress {
+    pub progress: f32,
+    pub label: String,
+}
+
+// Bevy插件：加载状态下跟踪资源注册表的后台加载，全部完成后进入主菜单
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LoadingProgress>()
+           .add_state_systems(GameState::Loading, track_asset_queue);
+    }
+}
+
+fn track_asset_queue(mut loading: ResMut<LoadingProgress>, mut next_state: ResMut<NextState<GameState>>) {
+    let queue = GlobalAssetQueue.load_progress();
+    loading.progress = queue.fraction();
+    loading.label = queue_label(&queue);
+    
+    if queue.is_complete() {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::{Arc, Mutex};
     
     #[test]
     fn test_loading_state_creation() {
@@ -517,4 +535,39 @@ mod tests {
         // 总进度应该是 (1.0 + 0.5) / 2 = 0.75
         assert!((state.total_progress - 0.75).abs() < 0.01);
     }
-}
\ No newline at end of file
+    
+    // 模拟的后台加载队列
+    struct MockQueue(Arc<Mutex<QueueProgress>>);
+    
+    impl LoadProgressSource for MockQueue {
+        fn load_progress(&mut self) -> QueueProgress {
+            self.0.lock().unwrap().clone()
+        }
+    }
+    
+    #[test]
+    fn test_progress_follows_async_queue() {
+        let queue = Arc::new(Mutex::new(QueueProgress { total: 4, ..QueueProgress::default() }));
+        let mut state = LoadingState::with_progress_source(MockQueue(queue.clone()));
+        state.min_loading_time = 0.0;
+        
+        let mut last_progress = -1.0;
+        for completed in 0..4 {
+            {
+                let mut queue = queue.lock().unwrap();
+                queue.completed = completed;
+                queue.current = Some(format!("route{}.json", completed));
+            }
+            
+            assert_eq!(state.update(0.1).unwrap(), StateTransition::None);
+            assert!(state.progress() > last_progress);
+            assert!(state.current_label().contains(&format!("route{}.json", completed)));
+            last_progress = state.progress();
+        }
+        
+        // 全部完成后进入主菜单
+        *queue.lock().unwrap() = QueueProgress { total: 4, completed: 4, current: None };
+        assert_eq!(state.update(0.1).unwrap(), StateTransition::Replace(GameStateType::MainMenu));
+        assert_eq!(state.progress(), 1.0);
+    }
+}
@@ -4,7 +4,8 @@
 
 use log::{debug, warn, error};
 use crate::core::error::GameError;
-use crate::ui::{UIManager, ElementType, UIEvent};
+use crate::ui::{UIManager, ElementType, UIEvent, Menu, MenuResponse};
+use crate::input::InputAction;
 use super::Renderer2D;
 use crate::input::mouse::MouseEvent;
 use crate::input::gamepad::GamepadEvent;
@@ -27,12 +28,12 @@ pub struct MainMenuState {
     ui_manager: UIManager,
     
     // UI元素ID
-    title_label_id: Option<u32>,
-    menu_buttons: Vec<(MenuItem, u32)>,
+    title_label_id: Option<String>,
+    menu_buttons: Vec<(MenuItem, String)>,
     background_image_id: Option<u32>,
     
-    // 状态
-    selected_item: usize,
+    // 导航
+    navigation: Menu<MenuItem>,
     
     // 动画
     title_pulse: f32,
@@ -53,7 +54,7 @@ impl MainMenuState {
             title_label_id: None,
             menu_buttons: Vec::new(),
             background_image_id: None,
-            selected_item: 0,
+            navigation: Menu::new(),
             title_pulse: 0.0,
             button_hover_scale: 1.0,
             background_parallax: Vec2::ZERO,
@@ -70,19 +71,14 @@ impl MainMenuState {
     // 初始化UI
     fn setup_ui(&mut self) -> Result<(), GameError> {
         // 游戏标题
-        self.title_label_id = Some(self.ui_manager.create_element(
-            "title".to_string(),
-            ElementType::Label,
-            None,
-        )?);
+        let title_id = "title".to_string();
+        self.ui_manager.create_element(title_id.clone(), ElementType::Text)?;
+        self.ui_manager.set_element_text(&title_id, "Pokemon GO".to_string())?;
+        self.ui_manager.set_element_position(&title_id, (400.0, 150.0))?;
+        self.ui_manager.set_element_size(&title_id, (400.0, 80.0))?;
+        self.title_label_id = Some(title_id);
         
-        if let Some(title_id) = self.title_label_id {
-            self.ui_manager.set_element_text(title_id, "Pokemon GO".to_string())?;
-            self.ui_manager.set_element_position(title_id, Vec2::new(400.0, 150.0))?;
-            self.ui_manager.set_element_size(title_id, Vec2::new(400.0, 80.0))?;
-        }
-        
-        // 创建菜单按钮
+        // 创建菜单按钮，没有存档时"继续游戏"不可选
         let menu_items = vec![
             (MenuItem::NewGame, "新游戏"),
             (MenuItem::Continue, "继续游戏"),
@@ -94,31 +90,24 @@ impl MainMenuState {
         let button_start_y = 280.0;
         let button_spacing = 60.0;
         
+        self.menu_buttons.clear();
+        self.navigation = Menu::new();
+        let has_save_file = self.has_save_file();
+        
         for (i, (item, text)) in menu_items.iter().enumerate() {
-            let button_id = self.ui_manager.create_element(
-                format!("button_{:?}", item),
-                ElementType::Button,
-                None,
-            )?;
+            self.navigation.add_item(*text, item.clone(), *item != MenuItem::Continue || has_save_file);
             
-            self.ui_manager.set_element_text(button_id, text.to_string())?;
+            // 点击通过UIEvent::Click(元素ID)回到handle_mouse_event
+            let element_id = format!("button_{:?}", item);
+            self.ui_manager.create_element(element_id.clone(), ElementType::Button)?;
+            self.ui_manager.set_element_text(&element_id, text.to_string())?;
             self.ui_manager.set_element_position(
-                button_id,
-                Vec2::new(400.0, button_start_y + i as f32 * button_spacing),
+                &element_id,
+                (400.0, button_start_y + i as f32 * button_spacing),
             )?;
-            self.ui_manager.set_element_size(button_id, Vec2::new(200.0, 45.0))?;
+            self.ui_manager.set_element_size(&element_id, (200.0, 45.0))?;
             
-            self.menu_buttons.push((item.clone(), button_id));
-            
-            // 添加点击事件处理器
-            let item_clone = item.clone();
-            self.ui_manager.add_event_handler(
-                button_id,
-                "click".to_string(),
-                move |_event| {
-                    debug!("菜单项被点击: {:?}", item_clone);
-                },
-            )?;
+            self.menu_buttons.push((item.clone(), element_id));
         }
         
         debug!("主菜单UI初始化完成");
@@ -181,41 +170,21 @@ impl MainMenuState {
         }
     }
     
-    // 键盘导航
-    fn navigate_menu(&mut self, direction: i32) {
-        let old_selected = self.selected_item;
-        
-        if direction > 0 {
-            self.selected_item = (self.selected_item + 1) % self.menu_buttons.len();
-        } else if direction < 0 {
-            self.selected_item = if self.selected_item == 0 {
-                self.menu_buttons.len() - 1
-            } else {
-                self.selected_item - 1
-            };
-        }
-        
-        if old_selected != self.selected_item {
-            // 更新按钮状态
-            if let Some((_, old_button_id)) = self.menu_buttons.get(old_selected) {
-                // 重置旧按钮状态
-            }
-            
-            if let Some((_, new_button_id)) = self.menu_buttons.get(self.selected_item) {
+    // 菜单导航，按键和手柄输入都先转换成输入动作
+    fn apply_action(&mut self, action: &InputAction) -> StateTransition {
+        match self.navigation.handle_action(action) {
+            MenuResponse::Moved => {
                 // 设置新按钮为聚焦状态
-                self.ui_manager.set_focus(Some(*new_button_id)).ok();
-            }
-            
-            debug!("菜单导航: {} -> {}", old_selected, self.selected_item);
-        }
-    }
-    
-    // 确认选择
-    fn confirm_selection(&mut self) -> StateTransition {
-        if let Some((item, _)) = self.menu_buttons.get(self.selected_item) {
-            self.handle_menu_selection(item.clone())
-        } else {
-            StateTransition::None
+                if let Some(index) = self.navigation.focused() {
+                    if let Some((_, element_id)) = self.menu_buttons.get(index) {
+                        self.ui_manager.set_focus(element_id).ok();
+                    }
+                    debug!("菜单导航: {}", index);
+                }
+                StateTransition::None
+            },
+            MenuResponse::Activated(item) => self.handle_menu_selection(item),
+            MenuResponse::Ignored | MenuResponse::Unchanged => StateTransition::None,
         }
     }
 }
@@ -238,7 +207,6 @@ impl StateHandler for MainMenuState {
         // 重置动画状态
         self.title_pulse = 0.0;
         self.background_parallax = Vec2::ZERO;
-        self.selected_item = 0;
         
         Ok(())
     }
@@ -285,7 +253,7 @@ impl StateHandler for MainMenuState {
         self.ui_manager.render(renderer)?;
         
         // 渲染标题特效
-        if let Some(title_id) = self.title_label_id {
+        if self.title_label_id.is_some() {
             let pulse_scale = 1.0 + self.title_pulse.sin() * 0.05;
             let title_color = self.title_color * (0.9 + self.title_pulse.sin() * 0.1);
             
@@ -310,23 +278,28 @@ impl StateHandler for MainMenuState {
     }
     
     fn handle_mouse_event(&mut self, event: &MouseEvent) -> Result<bool, GameError> {
-        // 委托给UI管理器处理
+        // 委托给UI管理器做命中检测，点击事件携带按钮的元素ID
         let is_pressed = event.state == crate::input::mouse::MouseState::Pressed;
-        let handled = if let Some(button) = event.button {
-            self.ui_manager.handle_mouse_event(
-                event.position,
-                Some(button),
-                is_pressed,
-            )?
-        } else {
-            false
-        };
+        if event.button != Some(crate::input::mouse::MouseButton::Left) || !is_pressed {
+            return Ok(false);
+        }
+        self.ui_manager.handle_mouse_event(event.position.x, event.position.y, 0)?;
         
-        if handled {
-            // 检查是否点击了菜单按钮
-            for (item, button_id) in &self.menu_buttons {
-                // 这里应该检查具体哪个按钮被点击
-                // 简化实现
+        let mut handled = false;
+        for ui_event in self.ui_manager.get_events() {
+            let UIEvent::Click(element_id) = ui_event else {
+                continue;
+            };
+            let clicked = self.menu_buttons.iter().position(|(_, id)| *id == element_id);
+            let Some(index) = clicked else {
+                continue;
+            };
+            // 禁用的菜单项(没有存档时的"继续游戏")点击无效
+            if let Some(entry) = self.navigation.items().get(index).filter(|entry| entry.enabled) {
+                debug!("菜单项被点击: {:?}", entry.action);
+                let transition = self.handle_menu_selection(entry.action.clone());
+                // 这里应该将转换传递给状态管理器
+                handled = true;
             }
         }
         
@@ -340,15 +313,15 @@ impl StateHandler for MainMenuState {
         
         match key {
             "ArrowUp" | "w" | "W" => {
-                self.navigate_menu(-1);
+                self.apply_action(&InputAction::MoveUp);
                 Ok(true)
             },
             "ArrowDown" | "s" | "S" => {
-                self.navigate_menu(1);
+                self.apply_action(&InputAction::MoveDown);
                 Ok(true)
             },
             "Return" | "Space" => {
-                let transition = self.confirm_selection();
+                let transition = self.apply_action(&InputAction::Confirm);
                 // 这里应该将转换传递给状态管理器
                 Ok(true)
             },
@@ -365,15 +338,15 @@ impl StateHandler for MainMenuState {
             GamepadEvent::ButtonPressed { button, .. } => {
                 match button.as_str() {
                     "DPadUp" | "LeftStickUp" => {
-                        self.navigate_menu(-1);
+                        self.apply_action(&InputAction::MoveUp);
                         Ok(true)
                     },
                     "DPadDown" | "LeftStickDown" => {
-                        self.navigate_menu(1);
+                        self.apply_action(&InputAction::MoveDown);
                         Ok(true)
                     },
                     "A" | "Cross" => {
-                        let transition = self.confirm_selection();
+                        let transition = self.apply_action(&InputAction::Confirm);
                         Ok(true)
                     },
                     "B" | "Circle" => {
@@ -409,32 +382,34 @@ mod tests {
         let menu = MainMenuState::new();
         assert_eq!(menu.get_type(), GameStateType::MainMenu);
         assert_eq!(menu.get_name(), "MainMenuState");
-        assert_eq!(menu.selected_item, 0);
+        assert_eq!(menu.navigation.focused(), None);
     }
     
     #[test]
     fn test_menu_navigation() {
         let mut menu = MainMenuState::new();
         
-        // 模拟菜单按钮
-        menu.menu_buttons = vec![
-            (MenuItem::NewGame, 1),
-            (MenuItem::Continue, 2),
-            (MenuItem::Settings, 3),
-            (MenuItem::Quit, 4),
-        ];
+        // 模拟菜单项
+        menu.navigation = Menu::new()
+            .with_item("新游戏", MenuItem::NewGame)
+            .with_item("继续游戏", MenuItem::Continue)
+            .with_item("设置", MenuItem::Settings)
+            .with_item("退出游戏", MenuItem::Quit);
         
         // 测试向下导航
-        menu.navigate_menu(1);
-        assert_eq!(menu.selected_item, 1);
+        menu.apply_action(&InputAction::MoveDown);
+        assert_eq!(menu.navigation.focused(), Some(1));
         
         // 测试向上导航
-        menu.navigate_menu(-1);
-        assert_eq!(menu.selected_item, 0);
+        menu.apply_action(&InputAction::MoveUp);
+        assert_eq!(menu.navigation.focused(), Some(0));
         
         // 测试边界处理
-        menu.navigate_menu(-1);
-        assert_eq!(menu.selected_item, 3); // 应该回到最后一个
+        menu.apply_action(&InputAction::MoveUp);
+        assert_eq!(menu.navigation.focused(), Some(3)); // 应该回到最后一个
+        
+        // 确认选择
+        assert_eq!(menu.apply_action(&InputAction::Confirm), StateTransition::Quit);
     }
     
     #[test]
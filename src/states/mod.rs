@@ -122,6 +122,9 @@ pub mod battle;
 pub mod overworld;
 pub mod settings;
 pub mod loading;
+pub mod scoped;
+
+pub use scoped::StateSystemsExt;
 
 // Bevy States枚举 - 符合Bevy状态管理要求
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
@@ -139,6 +142,9 @@ pub enum BevyGameState {
     Credits,        // 制作人员
 }
 
+// App中使用的Bevy状态
+pub type GameState = BevyGameState;
+
 // 状态ID类型
 pub type StateId = u32;
 
@@ -216,6 +222,65 @@ pub struct StateInfo {
     pub update_count: u64,
 }
 
+// 状态转换守卫，返回false时拒绝转换；from/to为None表示空栈
+pub type TransitionGuard = Box<dyn Fn(Option<&GameStateType>, Option<&GameStateType>) -> bool + Send>;
+
+// 滑动过渡中新状态移入的方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlideDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl SlideDirection {
+    // 一整屏的位移
+    fn offset(self, viewport: &Viewport) -> Vec2 {
+        match self {
+            SlideDirection::Left => Vec2::new(-viewport.width, 0.0),
+            SlideDirection::Right => Vec2::new(viewport.width, 0.0),
+            SlideDirection::Up => Vec2::new(0.0, viewport.height),
+            SlideDirection::Down => Vec2::new(0.0, -viewport.height),
+        }
+    }
+}
+
+// 过渡动画，时长以秒计
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransitionStyle {
+    Instant,
+    Fade { duration: f32 },     // 淡出到黑屏后再淡入
+    Slide { direction: SlideDirection, duration: f32 },
+}
+
+impl TransitionStyle {
+    pub fn duration(&self) -> f32 {
+        match self {
+            TransitionStyle::Instant => 0.0,
+            TransitionStyle::Fade { duration } | TransitionStyle::Slide { duration, .. } => *duration,
+        }
+    }
+
+    fn is_timed(&self) -> bool {
+        self.duration() > 0.0
+    }
+}
+
+// 进行中的过渡
+struct ActiveTransition {
+    style: TransitionStyle,
+    elapsed: f32,
+    outgoing: Option<StateInfo>,    // 已离开栈的状态，过渡结束后才退出并释放资源
+    outgoing_in_stack: bool,        // 推入时旧状态仍在栈中，位于新状态之下
+}
+
+impl ActiveTransition {
+    fn progress(&self) -> f32 {
+        (self.elapsed / self.style.duration()).clamp(0.0, 1.0)
+    }
+}
+
 // 状态管理器
 pub struct StateManager {
     // 状态栈
@@ -228,6 +293,11 @@ pub struct StateManager {
     // 状态工厂
     state_factories: HashMap<GameStateType, Box<dyn Fn() -> Box<dyn StateHandler> + Send>>,
     
+    // 转换守卫与过渡动画
+    transition_guards: Vec<TransitionGuard>,
+    transition_styles: HashMap<GameStateType, TransitionStyle>,
+    active_transition: Option<ActiveTransition>,
+    
     // 配置
     max_stack_depth: usize,
     
@@ -248,6 +318,9 @@ impl StateManager {
             next_state_id: 1,
             pending_transitions: Vec::new(),
             state_factories: HashMap::new(),
+            transition_guards: Vec::new(),
+            transition_styles: HashMap::new(),
+            active_transition: None,
             max_stack_depth: 10,
             total_states_created: 0,
             state_transition_count: 0,
@@ -269,20 +342,69 @@ impl StateManager {
         debug!("注册状态工厂: {:?}", state_type);
     }
     
+    // 添加状态转换守卫，所有守卫都允许时转换才会发生
+    pub fn add_transition_guard<F>(&mut self, guard: F)
+    where
+        F: Fn(Option<&GameStateType>, Option<&GameStateType>) -> bool + Send + 'static,
+    {
+        self.transition_guards.push(Box::new(guard));
+    }
+    
+    // 检查从from转换到to是否被允许
+    pub fn can_transition(&self, from: Option<&GameStateType>, to: Option<&GameStateType>) -> bool {
+        self.transition_guards.iter().all(|guard| guard(from, to))
+    }
+    
+    // 设置进入某状态时的过渡动画，弹出该状态时使用同一动画
+    pub fn set_transition_style(&mut self, state_type: GameStateType, style: TransitionStyle) {
+        self.transition_styles.insert(state_type, style);
+    }
+    
+    // 是否正在播放过渡动画
+    pub fn is_transitioning(&self) -> bool {
+        self.active_transition.is_some()
+    }
+    
+    // 当前过渡的进度(0~1)
+    pub fn transition_progress(&self) -> Option<f32> {
+        self.active_transition.as_ref().map(|transition| transition.progress())
+    }
+    
+    // 立即结束当前过渡，退出离开的状态
+    pub fn finish_transition(&mut self) -> Result<(), GameError> {
+        if let Some(transition) = self.active_transition.take() {
+            if let Some(state_info) = transition.outgoing {
+                self.retire_state(state_info)?;
+            }
+            
+            if self.log_transitions {
+                debug!("状态过渡完成: {:?}", self.get_current_state_type());
+            }
+        }
+        
+        Ok(())
+    }
+    
     // 推入状态
     pub fn push_state(&mut self, state_type: GameStateType) -> Result<StateId, GameError> {
+        self.check_guard(Some(&state_type))?;
+        
         if self.state_stack.len() >= self.max_stack_depth {
             return Err(GameError::State(format!("状态栈深度超过限制: {}", self.max_stack_depth)));
         }
         
+        self.finish_transition()?;
+        
         // 暂停当前状态
         if let Some(current_state) = self.state_stack.last_mut() {
             current_state.state.pause()?;
             current_state.paused = true;
         }
         
-        // 创建新状态
-        let state_id = self.create_state(state_type)?;
+        // 创建新状态，旧状态留在栈中，过渡期间继续渲染
+        let style = self.transition_style(&state_type);
+        let state_id = self.create_state(state_type.clone())?;
+        self.start_transition(style, None);
         
         if self.log_transitions {
             debug!("推入状态: {:?} ID={} 栈深度={}", 
@@ -294,52 +416,74 @@ impl StateManager {
     
     // 弹出状态
     pub fn pop_state(&mut self) -> Result<Option<GameStateType>, GameError> {
-        if let Some(mut state_info) = self.state_stack.pop() {
-            let state_type = state_info.state_type;
-            let next_state_type = self.get_current_state_type();
-            
-            // 退出状态
-            state_info.state.exit(next_state_type)?;
-            state_info.state.unload_resources()?;
-            
-            // 恢复前一个状态
-            if let Some(current_state) = self.state_stack.last_mut() {
-                current_state.state.resume()?;
-                current_state.paused = false;
-            }
-            
-            self.state_transition_count += 1;
-            
-            if self.log_transitions {
-                debug!("弹出状态: {:?} 栈深度={}", state_type, self.state_stack.len());
-            }
-            
-            Ok(Some(state_type))
-        } else {
-            Ok(None)
+        if self.state_stack.is_empty() {
+            return Ok(None);
+        }
+        
+        self.check_guard(self.state_below_top().as_ref())?;
+        self.finish_transition()?;
+        
+        let Some(state_info) = self.state_stack.pop() else {
+            return Ok(None);
+        };
+        let state_type = state_info.state_type.clone();
+        
+        // 退出状态，有过渡动画时等动画结束
+        let style = self.transition_style(&state_type);
+        self.leave_state(state_info, style)?;
+        
+        // 恢复前一个状态
+        if let Some(current_state) = self.state_stack.last_mut() {
+            current_state.state.resume()?;
+            current_state.paused = false;
         }
+        
+        self.state_transition_count += 1;
+        
+        if self.log_transitions {
+            debug!("弹出状态: {:?} 栈深度={}", state_type, self.state_stack.len());
+        }
+        
+        Ok(Some(state_type))
     }
     
     // 替换状态
     pub fn replace_state(&mut self, state_type: GameStateType) -> Result<StateId, GameError> {
-        // 先弹出当前状态
-        self.pop_state()?;
+        self.check_guard(Some(&state_type))?;
+        self.finish_transition()?;
         
-        // 然后推入新状态
-        self.push_state(state_type)
-    }
-    
-    // 清空状态栈
-    pub fn clear_states(&mut self) -> Result<(), GameError> {
-        while !self.state_stack.is_empty() {
-            self.pop_state()?;
+        // 先移出当前状态，下层状态保持暂停
+        let style = self.transition_style(&state_type);
+        let outgoing = self.state_stack.pop();
+        if outgoing.is_some() {
+            self.state_transition_count += 1;
         }
+        let outgoing = match outgoing {
+            Some(state_info) if !style.is_timed() => {
+                self.retire_state(state_info)?;
+                None
+            },
+            outgoing => outgoing,
+        };
+        
+        // 然后创建新状态
+        let state_id = self.create_state(state_type.clone())?;
+        self.start_transition(style, outgoing);
         
         if self.log_transitions {
-            debug!("清空状态栈");
+            debug!("替换状态: {:?} ID={}", state_type, state_id);
         }
         
-        Ok(())
+        Ok(state_id)
+    }
+    
+    // 清空状态栈，不播放过渡动画
+    pub fn clear_states(&mut self) -> Result<(), GameError> {
+        if !self.state_stack.is_empty() {
+            self.check_guard(None)?;
+        }
+        
+        self.clear_stack()
     }
     
     // 获取当前状态
@@ -349,7 +493,7 @@ impl StateManager {
     
     // 获取当前状态类型
     pub fn get_current_state_type(&self) -> Option<GameStateType> {
-        self.state_stack.last().map(|s| s.state_type)
+        self.state_stack.last().map(|s| s.state_type.clone())
     }
     
     // 检查状态是否存在
@@ -364,6 +508,26 @@ impl StateManager {
     
     // 处理状态转换
     pub fn handle_transition(&mut self, transition: StateTransition) -> Result<(), GameError> {
+        // 过渡进行中时排队，等过渡结束后处理
+        if self.active_transition.is_some() && transition != StateTransition::None {
+            self.pending_transitions.push(transition);
+            return Ok(());
+        }
+        
+        // 被守卫拒绝的转换直接忽略，不中断游戏循环
+        let target = match &transition {
+            StateTransition::Push(state_type) | StateTransition::Replace(state_type) => Some(Some(state_type.clone())),
+            StateTransition::Pop if !self.state_stack.is_empty() => Some(self.state_below_top()),
+            StateTransition::Clear if !self.state_stack.is_empty() => Some(None),
+            _ => None,
+        };
+        if let Some(to) = target {
+            if let Err(err) = self.check_guard(to.as_ref()) {
+                warn!("{}", err);
+                return Ok(());
+            }
+        }
+        
         match transition {
             StateTransition::None => {},
             StateTransition::Push(state_type) => {
@@ -379,7 +543,8 @@ impl StateManager {
                 self.clear_states()?;
             },
             StateTransition::Quit => {
-                self.clear_states()?;
+                // 退出不受守卫限制
+                self.clear_stack()?;
                 // 在实际实现中，这里应该设置退出标志
                 debug!("请求退出游戏");
             },
@@ -392,6 +557,15 @@ impl StateManager {
     pub fn update(&mut self, delta_time: f32) -> Result<(), GameError> {
         self.current_frame += 1;
         
+        // 推进过渡动画，过渡期间暂停状态更新和排队的转换
+        if let Some(transition) = self.active_transition.as_mut() {
+            transition.elapsed += delta_time;
+            if transition.elapsed < transition.style.duration() {
+                return Ok(());
+            }
+            self.finish_transition()?;
+        }
+        
         // 处理待处理的转换
         let transitions = std::mem::take(&mut self.pending_transitions);
         for transition in transitions {
@@ -416,6 +590,10 @@ impl StateManager {
     
     // 渲染状态
     pub fn render(&mut self, renderer: &mut Renderer2D) -> Result<(), GameError> {
+        if self.active_transition.is_some() {
+            return self.render_transition(renderer);
+        }
+        
         // 找到第一个不透明状态的索引
         let mut start_index = 0;
         for (i, state_info) in self.state_stack.iter().enumerate().rev() {
@@ -497,6 +675,118 @@ impl StateManager {
     }
     
     // 私有方法
+    fn check_guard(&self, to: Option<&GameStateType>) -> Result<(), GameError> {
+        let from = self.state_stack.last().map(|s| &s.state_type);
+        if self.can_transition(from, to) {
+            Ok(())
+        } else {
+            Err(GameError::State(format!("状态转换被守卫拒绝: {:?} -> {:?}", from, to)))
+        }
+    }
+    
+    fn state_below_top(&self) -> Option<GameStateType> {
+        let len = self.state_stack.len();
+        len.checked_sub(2).map(|i| self.state_stack[i].state_type.clone())
+    }
+    
+    fn transition_style(&self, state_type: &GameStateType) -> TransitionStyle {
+        self.transition_styles.get(state_type).copied().unwrap_or(TransitionStyle::Instant)
+    }
+    
+    fn start_transition(&mut self, style: TransitionStyle, outgoing: Option<StateInfo>) {
+        if !style.is_timed() {
+            return;
+        }
+        
+        let outgoing_in_stack = outgoing.is_none() && self.state_stack.len() > 1;
+        self.active_transition = Some(ActiveTransition {
+            style,
+            elapsed: 0.0,
+            outgoing,
+            outgoing_in_stack,
+        });
+    }
+    
+    // 状态离开栈：有过渡动画时保留到动画结束，否则立即退出
+    fn leave_state(&mut self, state_info: StateInfo, style: TransitionStyle) -> Result<(), GameError> {
+        if style.is_timed() {
+            self.start_transition(style, Some(state_info));
+            Ok(())
+        } else {
+            self.retire_state(state_info)
+        }
+    }
+    
+    fn retire_state(&mut self, mut state_info: StateInfo) -> Result<(), GameError> {
+        let next_state_type = self.get_current_state_type();
+        state_info.state.exit(next_state_type)?;
+        state_info.state.unload_resources()
+    }
+    
+    fn clear_stack(&mut self) -> Result<(), GameError> {
+        self.finish_transition()?;
+        
+        while let Some(state_info) = self.state_stack.pop() {
+            self.retire_state(state_info)?;
+            self.state_transition_count += 1;
+        }
+        
+        if self.log_transitions {
+            debug!("清空状态栈");
+        }
+        
+        Ok(())
+    }
+    
+    // 过渡期间同时渲染离开和进入的状态
+    fn render_transition(&mut self, renderer: &mut Renderer2D) -> Result<(), GameError> {
+        let Some(transition) = self.active_transition.as_mut() else {
+            return Ok(());
+        };
+        let progress = transition.progress();
+        
+        let (incoming, below) = match self.state_stack.split_last_mut() {
+            Some((top, rest)) => (Some(top), rest),
+            None => (None, Default::default()),
+        };
+        let outgoing = match transition.outgoing.as_mut() {
+            Some(state_info) => Some(state_info),
+            None if transition.outgoing_in_stack => below.last_mut(),
+            None => None,
+        };
+        
+        match transition.style {
+            TransitionStyle::Instant => {},
+            TransitionStyle::Fade { .. } => {
+                // 前半段淡出离开的状态，后半段淡入进入的状态，中点全黑
+                if let Some(state_info) = outgoing {
+                    state_info.state.render(renderer)?;
+                }
+                if progress >= 0.5 {
+                    if let Some(state_info) = incoming {
+                        state_info.state.render(renderer)?;
+                    }
+                }
+                
+                let alpha = 1.0 - (progress * 2.0 - 1.0).abs();
+                let size = Vec2::new(renderer.viewport.width, renderer.viewport.height);
+                renderer.draw_sprite(Vec2::ZERO, size, [0.0, 0.0, 0.0, alpha]);
+            },
+            TransitionStyle::Slide { direction, .. } => {
+                // 离开的状态移出屏幕，进入的状态从另一侧跟进
+                let shift = direction.offset(&renderer.viewport);
+                if let Some(state_info) = outgoing {
+                    render_with_offset(renderer, state_info, shift * progress)?;
+                }
+                if let Some(state_info) = incoming {
+                    render_with_offset(renderer, state_info, shift * (progress - 1.0))?;
+                }
+            },
+        }
+        
+        Ok(())
+    }
+    
     fn create_state(&mut self, state_type: GameStateType) -> Result<StateId, GameError> {
         let factory = self.state_factories.get(&state_type)
             .ok_or_else(|| GameError::State(format!("未注册的状态类型: {:?}", state_type)))?;
@@ -553,6 +843,16 @@ impl StateManager {
     }
 }
 
+// 平移offset后渲染状态
+fn render_with_offset(renderer: &mut Renderer2D, state_info: &mut StateInfo, offset: Vec2) -> Result<(), GameError> {
+    let mut camera = Camera::new_2d();
+    camera.position = -offset;
+    renderer.push_camera(camera);
+    let result = state_info.state.render(renderer);
+    renderer.pop_camera();
+    result
+}
+
 // 统计信息
 #[derive(Debug, Clone)]
 pub struct StateManagerStats {
@@ -700,4 +1000,63 @@ mod tests {
         manager.handle_transition(StateTransition::Clear).unwrap();
         assert_eq!(manager.get_stack_depth(), 0);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_guard_vetoes_transition() {
+        let menu = GameStateType::Custom("menu".to_string());
+        let battle = GameStateType::Custom("battle".to_string());
+        let mut manager = StateManager::new();
+        manager.register_state_factory(menu.clone(), || Box::new(TestState::new()));
+        manager.register_state_factory(battle.clone(), || Box::new(TestState::new()));
+        
+        // 不允许从菜单直接进入战斗
+        let (guard_menu, guard_battle) = (menu.clone(), battle.clone());
+        manager.add_transition_guard(move |from, to| !(from == Some(&guard_menu) && to == Some(&guard_battle)));
+        
+        manager.push_state(menu.clone()).unwrap();
+        assert!(!manager.can_transition(Some(&menu), Some(&battle)));
+        assert!(manager.push_state(battle.clone()).is_err());
+        
+        // 状态返回的转换被拒绝时忽略，不报错
+        manager.handle_transition(StateTransition::Replace(battle.clone())).unwrap();
+        assert_eq!(manager.get_stack_depth(), 1);
+        assert_eq!(manager.get_current_state_type(), Some(menu));
+        assert!(manager.can_transition(Some(&battle), None));
+    }
+    
+    #[test]
+    fn test_timed_transition_completes_after_duration() {
+        let menu = GameStateType::Custom("menu".to_string());
+        let battle = GameStateType::Custom("battle".to_string());
+        let mut manager = StateManager::new();
+        manager.register_state_factory(menu.clone(), || Box::new(TestState::new()));
+        manager.register_state_factory(battle.clone(), || Box::new(TestState::new()));
+        manager.set_transition_style(battle.clone(), TransitionStyle::Fade { duration: 0.5 });
+        
+        manager.push_state(menu.clone()).unwrap();
+        assert!(!manager.is_transitioning());
+        
+        manager.replace_state(battle.clone()).unwrap();
+        assert_eq!(manager.get_current_state_type(), Some(battle.clone()));
+        assert!(manager.is_transitioning());
+        
+        // 过渡期间离开的状态仍被保留并渲染，新的转换排队等待
+        manager.update(0.3).unwrap();
+        assert!(manager.is_transitioning());
+        assert!(manager.active_transition.as_ref().unwrap().outgoing.is_some());
+        let mut renderer = Renderer2D::new();
+        manager.render(&mut renderer).unwrap();
+        assert!(matches!(renderer.render_commands.last(), Some(RenderCommand::DrawSprite { color, .. }) if color[3] > 0.0));
+        
+        manager.handle_transition(StateTransition::Pop).unwrap();
+        assert_eq!(manager.get_stack_depth(), 1);
+        
+        // 到时后过渡结束，排队的弹出随即执行，并使用战斗状态的过渡动画
+        manager.update(0.3).unwrap();
+        assert_eq!(manager.get_stack_depth(), 0);
+        assert_eq!(manager.transition_progress(), Some(0.0));
+        
+        manager.update(0.5).unwrap();
+        assert!(!manager.is_transitioning());
+    }
+}
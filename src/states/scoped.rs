@@ -0,0 +1,76 @@
+// 按状态划分的Bevy系统
+// 开发心理：很多系统只应在某个状态下运行(战斗逻辑不该在主菜单里跑)，每处手写run_if(in_state(...))既啰嗦又容易漏
+// 设计原则：给App加扩展方法，一次把整组系统注册到某个状态；进入/离开钩子负责加载和释放该状态独占的资源
+
+use bevy::prelude::*;
+
+pub trait StateSystemsExt {
+    // 只在处于state时每帧运行的系统
+    fn add_state_systems<S: States, M>(&mut self, state: S, systems: impl IntoSystemConfigs<M>) -> &mut Self;
+
+    // 进入state时运行一次
+    fn on_state_enter<S: States, M>(&mut self, state: S, systems: impl IntoSystemConfigs<M>) -> &mut Self;
+
+    // 离开state时运行一次
+    fn on_state_exit<S: States, M>(&mut self, state: S, systems: impl IntoSystemConfigs<M>) -> &mut Self;
+}
+
+impl StateSystemsExt for App {
+    fn add_state_systems<S: States, M>(&mut self, state: S, systems: impl IntoSystemConfigs<M>) -> &mut Self {
+        self.add_systems(Update, systems.run_if(in_state(state)))
+    }
+
+    fn on_state_enter<S: States, M>(&mut self, state: S, systems: impl IntoSystemConfigs<M>) -> &mut Self {
+        self.add_systems(OnEnter(state), systems)
+    }
+
+    fn on_state_exit<S: States, M>(&mut self, state: S, systems: impl IntoSystemConfigs<M>) -> &mut Self {
+        self.add_systems(OnExit(state), systems)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::states::battle::{BattleSceneResources, BattleStatePlugin};
+    use crate::states::GameState;
+    use bevy::state::app::StatesPlugin;
+
+    #[derive(Resource, Default)]
+    struct BattleTicks(u32);
+
+    fn count_battle_ticks(mut ticks: ResMut<BattleTicks>) {
+        ticks.0 += 1;
+    }
+
+    fn switch_state(app: &mut App, state: GameState) {
+        app.world_mut().resource_mut::<NextState<GameState>>().set(state);
+        app.update();
+    }
+
+    #[test]
+    fn test_battle_systems_skip_main_menu() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .init_state::<GameState>()
+            .init_resource::<BattleTicks>()
+            .add_state_systems(GameState::Battle, count_battle_ticks)
+            .add_plugins(BattleStatePlugin);
+
+        switch_state(&mut app, GameState::MainMenu);
+        app.update();
+        assert_eq!(app.world().resource::<BattleTicks>().0, 0);
+        assert!(!app.world().contains_resource::<BattleSceneResources>());
+
+        // 进入战斗时加载资源，战斗系统在同一帧开始运行
+        switch_state(&mut app, GameState::Battle);
+        assert_eq!(app.world().resource::<BattleTicks>().0, 1);
+        assert!(app.world().contains_resource::<BattleSceneResources>());
+
+        // 回到主菜单后资源被释放，战斗系统不再运行
+        switch_state(&mut app, GameState::MainMenu);
+        app.update();
+        assert_eq!(app.world().resource::<BattleTicks>().0, 1);
+        assert!(!app.world().contains_resource::<BattleSceneResources>());
+    }
+}
@@ -6,6 +6,10 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use crate::core::error::GameError;
 
+pub mod navigation;
+
+pub use navigation::{FocusDirection, Menu, MenuEntry, MenuResponse};
+
 // 基础UI组件
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UIElement {
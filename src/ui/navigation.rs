@@ -0,0 +1,218 @@
+// 菜单导航模型
+// 开发心理：主菜单、暂停菜单各自维护选中下标和上下键逻辑，循环选择、跳过禁用项这些细节每处都要重写一遍
+// 设计原则：只管焦点和激活，不涉及渲染；菜单项携带任意动作值，激活后做什么由使用方决定
+
+use crate::input::InputAction;
+
+// 焦点移动方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusDirection {
+    Up,
+    Down,
+}
+
+// 可获得焦点的菜单项
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuEntry<T> {
+    pub label: String,
+    pub action: T,
+    pub enabled: bool,
+}
+
+// 输入动作的处理结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuResponse<T> {
+    Ignored,        // 不是菜单关心的动作
+    Unchanged,      // 已处理但焦点没有变化(到达边界或没有可选项)
+    Moved,
+    Activated(T),
+}
+
+#[derive(Debug, Clone)]
+pub struct Menu<T> {
+    items: Vec<MenuEntry<T>>,
+    focused: Option<usize>,
+    wrap: bool,
+}
+
+impl<T: Clone> Menu<T> {
+    // 默认首尾循环
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            focused: None,
+            wrap: true,
+        }
+    }
+
+    pub fn with_item(mut self, label: impl Into<String>, action: T) -> Self {
+        self.add_item(label, action, true);
+        self
+    }
+
+    pub fn with_disabled_item(mut self, label: impl Into<String>, action: T) -> Self {
+        self.add_item(label, action, false);
+        self
+    }
+
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    // 第一个可用项自动获得焦点
+    pub fn add_item(&mut self, label: impl Into<String>, action: T, enabled: bool) {
+        self.items.push(MenuEntry {
+            label: label.into(),
+            action,
+            enabled,
+        });
+        if self.focused.is_none() && enabled {
+            self.focused = Some(self.items.len() - 1);
+        }
+    }
+
+    // 禁用当前焦点项时焦点移到下一个可用项
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) {
+        let Some(item) = self.items.get_mut(index) else {
+            return;
+        };
+        item.enabled = enabled;
+
+        match self.focused {
+            Some(focused) if focused == index && !enabled => {
+                self.focused = self.find_enabled(index, FocusDirection::Down, true);
+            },
+            None if enabled => self.focused = Some(index),
+            _ => {},
+        }
+    }
+
+    pub fn items(&self) -> &[MenuEntry<T>] {
+        &self.items
+    }
+
+    pub fn focused(&self) -> Option<usize> {
+        self.focused
+    }
+
+    pub fn focused_item(&self) -> Option<&MenuEntry<T>> {
+        self.focused.and_then(|index| self.items.get(index))
+    }
+
+    // 移动焦点，跳过禁用项；返回焦点是否变化
+    pub fn move_focus(&mut self, direction: FocusDirection) -> bool {
+        let Some(current) = self.focused else {
+            return false;
+        };
+
+        match self.find_enabled(current, direction, self.wrap) {
+            Some(next) if next != current => {
+                self.focused = Some(next);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    // 激活焦点项，返回它的动作
+    pub fn activate(&self) -> Option<T> {
+        self.focused_item()
+            .filter(|item| item.enabled)
+            .map(|item| item.action.clone())
+    }
+
+    // 按输入动作导航：上下移动焦点，确认激活
+    pub fn handle_action(&mut self, action: &InputAction) -> MenuResponse<T> {
+        let direction = match action {
+            InputAction::MoveUp => FocusDirection::Up,
+            InputAction::MoveDown => FocusDirection::Down,
+            InputAction::Confirm => {
+                return self.activate().map_or(MenuResponse::Unchanged, MenuResponse::Activated);
+            },
+            _ => return MenuResponse::Ignored,
+        };
+
+        if self.move_focus(direction) {
+            MenuResponse::Moved
+        } else {
+            MenuResponse::Unchanged
+        }
+    }
+
+    // 从start开始(不含start)沿direction找下一个可用项，最多绕一圈
+    fn find_enabled(&self, start: usize, direction: FocusDirection, wrap: bool) -> Option<usize> {
+        let len = self.items.len();
+        let mut index = start;
+        for _ in 0..len {
+            index = match direction {
+                FocusDirection::Down if index + 1 < len => index + 1,
+                FocusDirection::Down if wrap => 0,
+                FocusDirection::Up if index > 0 => index - 1,
+                FocusDirection::Up if wrap => len - 1,
+                _ => return None,
+            };
+            if self.items[index].enabled {
+                return Some(index);
+            }
+        }
+        None
+    }
+}
+
+impl<T: Clone> Default for Menu<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pause_menu() -> Menu<&'static str> {
+        Menu::new()
+            .with_item("继续", "resume")
+            .with_disabled_item("保存", "save")
+            .with_item("设置", "settings")
+            .with_item("返回标题", "title")
+    }
+
+    #[test]
+    fn test_focus_wraps_around() {
+        let mut menu = pause_menu();
+        assert_eq!(menu.focused(), Some(0));
+
+        assert_eq!(menu.handle_action(&InputAction::MoveUp), MenuResponse::Moved);
+        assert_eq!(menu.focused(), Some(3));
+        assert_eq!(menu.handle_action(&InputAction::MoveDown), MenuResponse::Moved);
+        assert_eq!(menu.focused(), Some(0));
+        assert_eq!(menu.handle_action(&InputAction::Confirm), MenuResponse::Activated("resume"));
+
+        // 关闭循环后停在边界
+        let mut menu = pause_menu().with_wrap(false);
+        assert!(!menu.move_focus(FocusDirection::Up));
+        assert_eq!(menu.focused(), Some(0));
+        assert_eq!(menu.handle_action(&InputAction::Cancel), MenuResponse::Ignored);
+    }
+
+    #[test]
+    fn test_disabled_item_skipped() {
+        let mut menu = pause_menu();
+        assert!(menu.move_focus(FocusDirection::Down));
+        assert_eq!(menu.focused_item().unwrap().action, "settings");
+        assert!(menu.move_focus(FocusDirection::Up));
+        assert_eq!(menu.focused(), Some(0));
+
+        // 禁用焦点项后焦点移到下一个可用项
+        menu.set_enabled(0, false);
+        assert_eq!(menu.focused(), Some(2));
+        assert_eq!(menu.activate(), Some("settings"));
+
+        // 全部禁用时没有焦点，也无法激活
+        menu.set_enabled(2, false);
+        menu.set_enabled(3, false);
+        assert_eq!(menu.focused(), None);
+        assert_eq!(menu.handle_action(&InputAction::Confirm), MenuResponse::Unchanged);
+    }
+}
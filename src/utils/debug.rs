@@ -924,25 +924,22 @@ impl MemoryTracker {
 
     pub fn update(&mut self, _delta_time: f32) {
         // 每秒记录一次内存快照
-        static mut LAST_SNAPSHOT: Option<Instant> = None;
         let now = Instant::now();
+        let due = self.allocation_history.back()
+            .is_none_or(|last| now.duration_since(last.timestamp).as_secs() >= 1);
         
-        unsafe {
-            if LAST_SNAPSHOT.is_none() || now.duration_since(LAST_SNAPSHOT.unwrap()).as_secs() >= 1 {
-                let snapshot = MemorySnapshot {
-                    timestamp: now,
-                    total_allocated: self.total_allocated,
-                    allocations: self.allocations.iter()
-                        .map(|(k, v)| (k.clone(), v.size))
-                        .collect(),
-                };
-
-                self.allocation_history.push_back(snapshot);
-                if self.allocation_history.len() > 3600 {
-                    self.allocation_history.pop_front();
-                }
+        if due {
+            let snapshot = MemorySnapshot {
+                timestamp: now,
+                total_allocated: self.total_allocated,
+                allocations: self.allocations.iter()
+                    .map(|(k, v)| (k.clone(), v.size))
+                    .collect(),
+            };
 
-                LAST_SNAPSHOT = Some(now);
+            self.allocation_history.push_back(snapshot);
+            if self.allocation_history.len() > 3600 {
+                self.allocation_history.pop_front();
             }
         }
     }
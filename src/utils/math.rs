@@ -0,0 +1,88 @@
+// 游戏数学辅助函数
+// 开发心理：相机跟随、角色移动、音量衰减都在重复写插值和区间换算，各处的写法和边界处理不一致
+// 设计原则：只放与具体系统无关的纯函数，向量直接使用glam类型；除特别说明外不做截断，需要限制范围的调用方自行clamp
+
+use bevy::math::Vec2;
+
+// 线性插值，t不截断，t超出[0, 1]时外推
+pub fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// lerp的逆运算：value在[a, b]中的比例；a == b时返回0
+pub fn inverse_lerp(a: f32, b: f32, value: f32) -> f32 {
+    if a == b {
+        return 0.0;
+    }
+    (value - a) / (b - a)
+}
+
+// 把value从[from_min, from_max]按比例映射到[to_min, to_max]，区间可以是反向的
+pub fn remap(value: f32, from_min: f32, from_max: f32, to_min: f32, to_max: f32) -> f32 {
+    lerp(to_min, to_max, inverse_lerp(from_min, from_max, value))
+}
+
+// x在[edge0, edge1]之间平滑地从0过渡到1，区间外截断
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = inverse_lerp(edge0, edge1, x).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// 向目标移动不超过max_delta的距离，不会越过目标
+pub fn move_towards(current: Vec2, target: Vec2, max_delta: f32) -> Vec2 {
+    let offset = target - current;
+    let distance = offset.length();
+    if distance <= max_delta || distance == 0.0 {
+        return target;
+    }
+    current + offset / distance * max_delta
+}
+
+// 从a转到b的有符号角度(弧度)，逆时针为正，范围[-π, π]；任一向量为零时返回0
+pub fn angle_between(a: Vec2, b: Vec2) -> f32 {
+    a.perp_dot(b).atan2(a.dot(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn test_move_towards_stops_at_target() {
+        let target = Vec2::new(3.0, 4.0);
+        let mut position = Vec2::ZERO;
+
+        position = move_towards(position, target, 2.0);
+        assert!((position - Vec2::new(1.2, 1.6)).length() < 1e-5);
+
+        // 剩余距离小于步长时正好停在目标上
+        for _ in 0..3 {
+            position = move_towards(position, target, 2.0);
+        }
+        assert_eq!(position, target);
+        assert_eq!(move_towards(target, target, 1.0), target);
+    }
+
+    #[test]
+    fn test_remap_and_interpolation() {
+        assert_eq!(remap(5.0, 0.0, 10.0, 100.0, 200.0), 150.0);
+        // 反向区间与区间外的值
+        assert_eq!(remap(2.5, 0.0, 10.0, 1.0, 0.0), 0.75);
+        assert_eq!(remap(20.0, 0.0, 10.0, 0.0, 1.0), 2.0);
+        assert_eq!(remap(-1.0, -2.0, 0.0, 0.0, 100.0), 50.0);
+
+        assert_eq!(inverse_lerp(10.0, 20.0, 15.0), 0.5);
+        assert_eq!(inverse_lerp(3.0, 3.0, 7.0), 0.0);
+        assert_eq!(smoothstep(0.0, 1.0, -1.0), 0.0);
+        assert_eq!(smoothstep(0.0, 1.0, 0.5), 0.5);
+        assert_eq!(smoothstep(0.0, 1.0, 2.0), 1.0);
+    }
+
+    #[test]
+    fn test_angle_between() {
+        assert!((angle_between(Vec2::X, Vec2::Y) - FRAC_PI_2).abs() < 1e-6);
+        assert!((angle_between(Vec2::Y, Vec2::X) + FRAC_PI_2).abs() < 1e-6);
+        assert_eq!(angle_between(Vec2::ZERO, Vec2::X), 0.0);
+    }
+}
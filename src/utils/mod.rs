@@ -3,10 +3,9 @@
 // 设计原则：模块化、高效、易用、跨平台
 
 pub mod logger;
-// 暂时注释掉未实现的子模块，避免编译错误
-// pub mod math;
-// pub mod random;
-// pub mod timer;
+pub mod math;
+pub mod random;
+pub mod timer;
 
 use crate::core::{GameError, Result};
 use serde::{Deserialize, Serialize};
@@ -44,6 +43,101 @@ impl Color {
         Self { r, g, b, a: 1.0 }
     }
     
+    // 线性插值，t限制在[0, 1]内
+    pub fn lerp(&self, other: &Color, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        Self {
+            r: self.r + (other.r - self.r) * t,
+            g: self.g + (other.g - self.g) * t,
+            b: self.b + (other.b - self.b) * t,
+            a: self.a + (other.a - self.a) * t,
+        }
+    }
+    
+    // RGBA8888，例如0xFF0000FF为不透明红色
+    pub fn from_hex(hex: u32) -> Self {
+        let channel = |shift: u32| ((hex >> shift) & 0xFF) as f32 / 255.0;
+        Self {
+            r: channel(24),
+            g: channel(16),
+            b: channel(8),
+            a: channel(0),
+        }
+    }
+    
+    // 超出[0, 1]的分量按边界值转换
+    pub fn to_hex(&self) -> u32 {
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u32;
+        channel(self.r) << 24 | channel(self.g) << 16 | channel(self.b) << 8 | channel(self.a)
+    }
+    
+    // 解析"#RRGGBB"，也接受带透明度的"#RRGGBBAA"
+    pub fn from_hex_str(hex: &str) -> Result<Self> {
+        let digits = hex.strip_prefix('#')
+            .filter(|digits| digits.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| GameError::ConfigError(format!("无效的颜色: {}", hex)))?;
+        
+        match digits.len() {
+            6 => Ok(Self::from_hex(u32::from_str_radix(digits, 16).unwrap() << 8 | 0xFF)),
+            8 => Ok(Self::from_hex(u32::from_str_radix(digits, 16).unwrap())),
+            _ => Err(GameError::ConfigError(format!("无效的颜色: {}", hex))),
+        }
+    }
+    
+    pub fn with_alpha(&self, a: f32) -> Self {
+        Self { a, ..*self }
+    }
+    
+    // h为色相(度，任意值会折算到[0, 360))，s和v在[0, 1]内
+    pub fn from_hsv(h: f32, s: f32, v: f32) -> Self {
+        let h = h.rem_euclid(360.0);
+        let s = s.clamp(0.0, 1.0);
+        let v = v.clamp(0.0, 1.0);
+        
+        let chroma = v * s;
+        let x = chroma * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+        let m = v - chroma;
+        let (r, g, b) = match (h / 60.0) as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        Self::rgb(r + m, g + m, b + m)
+    }
+    
+    // 返回(色相, 饱和度, 明度)；灰色的色相为0
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let delta = max - min;
+        
+        let h = if delta <= f32::EPSILON {
+            0.0
+        } else if max == self.r {
+            60.0 * ((self.g - self.b) / delta).rem_euclid(6.0)
+        } else if max == self.g {
+            60.0 * ((self.b - self.r) / delta + 2.0)
+        } else {
+            60.0 * ((self.r - self.g) / delta + 4.0)
+        };
+        let s = if max <= f32::EPSILON { 0.0 } else { delta / max };
+        (h, s, max)
+    }
+    
+    // 提高明度，色相和饱和度不变
+    pub fn lighten(&self, amount: f32) -> Self {
+        let (h, s, v) = self.to_hsv();
+        Self::from_hsv(h, s, v + amount).with_alpha(self.a)
+    }
+    
+    pub fn darken(&self, amount: f32) -> Self {
+        let (h, s, v) = self.to_hsv();
+        Self::from_hsv(h, s, v - amount).with_alpha(self.a)
+    }
+    
     pub const WHITE: Self = Self { r: 1.0, g: 1.0, b: 1.0, a: 1.0 };
     pub const BLACK: Self = Self { r: 0.0, g: 0.0, b: 0.0, a: 1.0 };
     pub const RED: Self = Self { r: 1.0, g: 0.0, b: 0.0, a: 1.0 };
@@ -56,7 +150,7 @@ pub use logger::*;
 // 暂时注释掉未实现的模块导出
 // pub use math::*;
 // pub use random::*;
-// pub use timer::*;
+pub use timer::{CountdownTimer, FrameRateLimiter, Stopwatch};
 
 // 版本信息结构
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -215,6 +309,24 @@ impl PerformanceMonitor {
         (min, max)
     }
     
+    // 帧时间的百分位数(最近秩法)，p在[0, 100]内；99对应"1% low"，99.9对应"0.1% low"
+    pub fn percentile(&self, p: f64) -> Duration {
+        if self.frame_times.is_empty() {
+            return Duration::ZERO;
+        }
+        
+        let mut sorted = self.frame_times.clone();
+        sorted.sort_unstable();
+        // 减去一个极小值，避免浮点误差让整数秩向上多进一位
+        let rank = (p.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64 - 1e-9).ceil() as usize;
+        sorted[rank.clamp(1, sorted.len()) - 1]
+    }
+    
+    // 帧时间超过阈值的帧数
+    pub fn spike_count(&self, threshold: Duration) -> usize {
+        self.frame_times.iter().filter(|&&frame_time| frame_time > threshold).count()
+    }
+    
     // 记录性能计数器
     pub fn record_counter(&mut self, name: &str, duration: Duration) {
         let counter = self.counters.entry(name.to_string()).or_insert_with(PerformanceCounter::new);
@@ -260,6 +372,10 @@ impl PerformanceMonitor {
         report.push_str(&format!("帧时间范围: {:.2}ms - {:.2}ms\n", 
                                 min_frame.as_secs_f64() * 1000.0, 
                                 max_frame.as_secs_f64() * 1000.0));
+        report.push_str(&format!("1% low: {:.2}ms, 0.1% low: {:.2}ms\n",
+                                self.percentile(99.0).as_secs_f64() * 1000.0,
+                                self.percentile(99.9).as_secs_f64() * 1000.0));
+        report.push_str(&format!("卡顿帧(超过平均帧时间2倍): {}\n", self.spike_count(self.get_average_frame_time() * 2)));
         
         if !self.counters.is_empty() {
             report.push_str("\n=== 性能计数器 ===\n");
@@ -383,6 +499,60 @@ impl StringUtils {
         1.0 - (distance as f64 / max_len as f64)
     }
     
+    // 模糊搜索排序：完全匹配 > 子串 > 子序列 > 拼写相近，同类中越接近查询长度越靠前；
+    // 不相关的候选不出现在结果中，分数在(0, 1]内，按分数从高到低排列
+    pub fn fuzzy_rank<S: AsRef<str>>(query: &str, candidates: &[S]) -> Vec<(String, f64)> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        
+        let mut ranked: Vec<(String, f64)> = candidates.iter()
+            .map(|candidate| candidate.as_ref())
+            .map(|candidate| (candidate.to_string(), Self::fuzzy_score(&query, &candidate.to_lowercase())))
+            .filter(|(_, score)| *score > 0.0)
+            .collect();
+        
+        ranked.sort_by(|(name1, score1), (name2, score2)| {
+            score2.total_cmp(score1).then_with(|| name1.cmp(name2))
+        });
+        ranked
+    }
+    
+    fn fuzzy_score(query: &str, candidate: &str) -> f64 {
+        if query == candidate {
+            return 1.0;
+        }
+        
+        let coverage = query.chars().count() as f64 / candidate.chars().count().max(1) as f64;
+        if candidate.contains(query) {
+            return 0.8 + 0.19 * coverage;
+        }
+        if Self::is_subsequence(query, candidate) {
+            return 0.5 + 0.29 * coverage;
+        }
+        
+        // 拼写错误：与整个候选或其中的单词比较，单词匹配略低于整体匹配
+        let whole = Self::similarity(query, candidate);
+        let best_word = candidate
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty() && *word != candidate)
+            .map(|word| Self::similarity(query, word) * 0.9)
+            .fold(0.0, f64::max);
+        let similarity = whole.max(best_word);
+        
+        if similarity >= 0.5 {
+            0.5 * similarity
+        } else {
+            0.0
+        }
+    }
+    
+    fn is_subsequence(query: &str, candidate: &str) -> bool {
+        let mut remaining = candidate.chars();
+        query.chars().all(|q| remaining.any(|c| c == q))
+    }
+    
     fn edit_distance(s1: &str, s2: &str) -> usize {
         let chars1: Vec<_> = s1.chars().collect();
         let chars2: Vec<_> = s2.chars().collect();
@@ -652,6 +822,29 @@ impl<T> RingBuffer<T> {
         self.capacity
     }
     
+    // 按逻辑顺序取元素，0为最早写入(下一个pop出的)的元素
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.size {
+            return None;
+        }
+        self.buffer[(self.head + index) % self.capacity].as_ref()
+    }
+    
+    // 最早写入的元素
+    pub fn peek(&self) -> Option<&T> {
+        self.get(0)
+    }
+    
+    // 最近写入的元素
+    pub fn peek_back(&self) -> Option<&T> {
+        self.get(self.size.checked_sub(1)?)
+    }
+    
+    // 从最早到最近遍历，不取出元素
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + '_ {
+        (0..self.size).filter_map(move |index| self.get(index))
+    }
+    
     pub fn clear(&mut self) {
         for item in &mut self.buffer {
             *item = None;
@@ -698,6 +891,29 @@ mod tests {
         assert!(fps > 40.0 && fps < 50.0); // 应该在30-60之间
     }
     
+    #[test]
+    fn test_frame_time_percentiles() {
+        let mut monitor = PerformanceMonitor::new(1000);
+        assert_eq!(monitor.percentile(99.0), Duration::ZERO);
+        
+        // 1ms到1000ms各一帧，打乱顺序写入
+        for i in 0..1000u64 {
+            monitor.record_frame(Duration::from_millis((i * 7919) % 1000 + 1));
+        }
+        
+        assert_eq!(monitor.percentile(50.0), Duration::from_millis(500));
+        assert_eq!(monitor.percentile(99.0), Duration::from_millis(990));
+        assert_eq!(monitor.percentile(99.9), Duration::from_millis(999));
+        assert_eq!(monitor.percentile(100.0), Duration::from_millis(1000));
+        assert_eq!(monitor.percentile(0.0), Duration::from_millis(1));
+        
+        assert_eq!(monitor.spike_count(Duration::from_millis(900)), 100);
+        assert_eq!(monitor.spike_count(Duration::from_millis(1000)), 0);
+        
+        let report = monitor.generate_report();
+        assert!(report.contains("1% low: 990.00ms, 0.1% low: 999.00ms"));
+    }
+    
     #[test]
     fn test_string_utils() {
         assert_eq!(StringUtils::camel_to_snake_case("CamelCase"), "camel_case");
@@ -706,6 +922,29 @@ mod tests {
         assert!(StringUtils::similarity("hello", "world") < 1.0);
     }
     
+    #[test]
+    fn test_fuzzy_rank() {
+        let candidates = ["pikachu_shiny", "Raichu", "pichu", "Pikachu", "charmander"];
+        
+        // 拼错的查询仍把目标排在第一位
+        let ranked = StringUtils::fuzzy_rank("pikahcu", &candidates);
+        assert_eq!(ranked[0].0, "Pikachu");
+        assert!(ranked.iter().all(|(name, _)| name != "charmander"));
+        
+        // 子串优先于子序列，越短越接近查询的候选越靠前
+        let ranked = StringUtils::fuzzy_rank("chu", &candidates);
+        let names: Vec<&str> = ranked.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["pichu", "Raichu", "Pikachu", "pikachu_shiny"]);
+        
+        let ranked = StringUtils::fuzzy_rank("PKCH", &candidates);
+        assert_eq!(ranked[0].0, "Pikachu");
+        assert!(ranked[0].1 < 0.8);
+        
+        assert_eq!(StringUtils::fuzzy_rank("charmander", &candidates)[0], ("charmander".to_string(), 1.0));
+        assert!(StringUtils::fuzzy_rank("  ", &candidates).is_empty());
+        assert!(StringUtils::fuzzy_rank("xyz", &candidates).is_empty());
+    }
+    
     #[test]
     fn test_ring_buffer() {
         let mut buffer = RingBuffer::new(3);
@@ -719,6 +958,31 @@ mod tests {
         assert_eq!(buffer.len(), 2);
     }
     
+    #[test]
+    fn test_ring_buffer_iter_after_wrap() {
+        let mut buffer = RingBuffer::new(4);
+        assert_eq!(buffer.peek(), None);
+        assert_eq!(buffer.peek_back(), None);
+        assert_eq!(buffer.iter().count(), 0);
+        
+        // 写满后继续写入，头尾都已绕回数组开头
+        for value in 1..=6 {
+            buffer.push(value);
+        }
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), vec![3, 4, 5, 6]);
+        assert_eq!(buffer.iter().rev().copied().collect::<Vec<_>>(), vec![6, 5, 4, 3]);
+        assert_eq!(buffer.peek(), Some(&3));
+        assert_eq!(buffer.peek_back(), Some(&6));
+        assert_eq!(buffer.get(1), Some(&4));
+        assert_eq!(buffer.get(4), None);
+        
+        // 查看不会取出元素
+        assert_eq!(buffer.len(), 4);
+        assert_eq!(buffer.pop(), Some(3));
+        buffer.push(7);
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), vec![4, 5, 6, 7]);
+    }
+    
     #[test]
     fn test_color() {
         let red = Color::RED;
@@ -733,6 +997,48 @@ mod tests {
         assert_eq!(hex_color.r, 1.0);
         assert_eq!(hex_color.g, 0.0);
         assert_eq!(hex_color.to_hex(), 0xFF0000FF);
+        
+        // t超出范围时取端点
+        assert_eq!(red.lerp(&blue, 2.0), blue);
+        assert_eq!(red.lerp(&blue, -1.0), red);
+    }
+    
+    #[test]
+    fn test_color_hsv() {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        
+        assert_eq!(Color::RED.to_hsv(), (0.0, 1.0, 1.0));
+        assert_eq!(Color::GREEN.to_hsv(), (120.0, 1.0, 1.0));
+        assert_eq!(Color::BLUE.to_hsv(), (240.0, 1.0, 1.0));
+        assert_eq!(Color::from_hsv(120.0, 1.0, 1.0), Color::GREEN);
+        assert_eq!(Color::from_hsv(-120.0, 1.0, 1.0), Color::BLUE);
+        assert_eq!(Color::WHITE.to_hsv(), (0.0, 0.0, 1.0));
+        
+        // RGB -> HSV -> RGB 在误差范围内不变
+        let original = Color::rgb(0.2, 0.45, 0.8);
+        let (h, s, v) = original.to_hsv();
+        let round_trip = Color::from_hsv(h, s, v);
+        assert!(close(round_trip.r, original.r) && close(round_trip.g, original.g) && close(round_trip.b, original.b));
+        
+        // 提亮只改变明度，透明度保留
+        let base = Color::from_hsv(30.0, 0.8, 0.5).with_alpha(0.5);
+        let lighter = base.lighten(0.2);
+        let (lighter_h, lighter_s, lighter_v) = lighter.to_hsv();
+        assert!(close(lighter_h, 30.0) && close(lighter_s, 0.8) && close(lighter_v, 0.7));
+        assert_eq!(lighter.a, 0.5);
+        assert!(close(base.darken(0.9).to_hsv().2, 0.0));
+    }
+    
+    #[test]
+    fn test_color_from_hex_str() {
+        let color = Color::from_hex_str("#3366CC").unwrap();
+        assert_eq!(color.to_hex(), 0x3366CCFF);
+        assert_eq!(color.a, 1.0);
+        assert_eq!(Color::from_hex_str("#00000080").unwrap().to_hex(), 0x00000080);
+        
+        assert!(Color::from_hex_str("3366CC").is_err());
+        assert!(Color::from_hex_str("#36C").is_err());
+        assert!(Color::from_hex_str("#GG0000").is_err());
     }
     
     #[test]
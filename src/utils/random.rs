@@ -9,7 +9,6 @@
 * 7. 提供统计功能，帮助平衡游戏内容
 */
 
-use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use rand::{Rng, SeedableRng};
 use rand::rngs::StdRng;
@@ -26,6 +25,8 @@ pub struct RandomGenerator {
     stats: RandomStats,
     /// 噪声生成器配置
     noise_config: NoiseConfig,
+    /// Box-Muller变换生成的第二个正态样本，留给下次使用
+    next_gaussian: Option<f64>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -76,6 +77,7 @@ impl RandomGenerator {
             seed,
             stats: RandomStats::default(),
             noise_config: NoiseConfig::default(),
+            next_gaussian: None,
         }
     }
 
@@ -89,6 +91,7 @@ impl RandomGenerator {
         self.seed = seed;
         self.rng = StdRng::seed_from_u64(seed);
         self.stats = RandomStats::default();
+        self.next_gaussian = None;
     }
 
     /// 生成随机布尔值
@@ -152,29 +155,21 @@ impl RandomGenerator {
         *self.stats.generation_counts.entry("normal".to_string()).or_insert(0) += 1;
         
         // Box-Muller变换
-        static mut NEXT_GAUSSIAN: Option<f64> = None;
-        static mut HAS_NEXT: bool = false;
-        
-        unsafe {
-            if HAS_NEXT {
-                HAS_NEXT = false;
-                return (NEXT_GAUSSIAN.unwrap() * std_dev as f64 + mean as f64) as f32;
-            } else {
-                let u1 = self.unit_f64();
-                let u2 = self.unit_f64();
-                let z0 = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
-                let z1 = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).sin();
-                
-                NEXT_GAUSSIAN = Some(z1);
-                HAS_NEXT = true;
-                
-                return (z0 * std_dev as f64 + mean as f64) as f32;
-            }
+        if let Some(z1) = self.next_gaussian.take() {
+            return (z1 * std_dev as f64 + mean as f64) as f32;
         }
+        
+        let u1 = self.unit_f64();
+        let u2 = self.unit_f64();
+        let z0 = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+        let z1 = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).sin();
+        self.next_gaussian = Some(z1);
+        
+        (z0 * std_dev as f64 + mean as f64) as f32
     }
 
     /// 从切片中随机选择一个元素
-    pub fn choose<T>(&mut self, items: &[T]) -> Option<&T> {
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
         if items.is_empty() {
             return None;
         }
@@ -186,7 +181,7 @@ impl RandomGenerator {
     }
 
     /// 从切片中随机选择多个元素（不重复）
-    pub fn choose_multiple<T>(&mut self, items: &[T], amount: usize) -> Vec<&T> {
+    pub fn choose_multiple<'a, T>(&mut self, items: &'a [T], amount: usize) -> Vec<&'a T> {
         self.stats.total_generations += 1;
         *self.stats.generation_counts.entry("choose_multiple".to_string()).or_insert(0) += 1;
         
@@ -194,7 +189,7 @@ impl RandomGenerator {
     }
 
     /// 基于权重选择元素
-    pub fn weighted_choose<T>(&mut self, items: &[WeightedItem<T>]) -> Option<&T> {
+    pub fn weighted_choose<'a, T>(&mut self, items: &'a [WeightedItem<T>]) -> Option<&'a T> {
         if items.is_empty() {
             return None;
         }
@@ -221,18 +216,25 @@ impl RandomGenerator {
     }
 
     /// 基于权重选择多个元素
-    pub fn weighted_choose_multiple<T>(&mut self, items: &[WeightedItem<T>], amount: usize) -> Vec<&T> {
+    pub fn weighted_choose_multiple<'a, T>(&mut self, items: &'a [WeightedItem<T>], amount: usize) -> Vec<&'a T> {
         let mut selected = Vec::new();
-        let mut remaining_items = items.to_vec();
+        // 剩余候选在items中的下标，选中后移除，保证不重复
+        let mut remaining: Vec<usize> = (0..items.len()).collect();
 
         for _ in 0..amount.min(items.len()) {
-            if let Some(chosen) = self.weighted_choose(&remaining_items) {
-                // 找到选中的元素并添加到结果
-                if let Some(pos) = remaining_items.iter().position(|item| std::ptr::eq(&item.item, chosen)) {
-                    selected.push(chosen);
-                    remaining_items.remove(pos);
-                }
+            let total_weight: f32 = remaining.iter().map(|&index| items[index].weight).sum();
+            if total_weight <= 0.0 {
+                break;
             }
+
+            let mut random_weight = self.range_f32(0.0, total_weight);
+            let position = remaining.iter()
+                .position(|&index| {
+                    random_weight -= items[index].weight;
+                    random_weight <= 0.0
+                })
+                .unwrap_or(remaining.len() - 1);
+            selected.push(&items[remaining.remove(position)].item);
         }
 
         selected
@@ -269,7 +271,7 @@ impl RandomGenerator {
 
     /// 2D Perlin噪声
     pub fn noise_2d(&mut self, x: f32, y: f32) -> f32 {
-        self.perlin_noise_2d(x, y, &self.noise_config.clone())
+        self.perlin_noise_2d(x, y, &self.noise_config)
     }
 
     /// 带配置的2D Perlin噪声
@@ -293,7 +295,7 @@ impl RandomGenerator {
     }
 
     /// 简化的Perlin噪声实现
-    fn perlin_noise_2d(&mut self, x: f32, y: f32, config: &NoiseConfig) -> f32 {
+    fn perlin_noise_2d(&self, x: f32, y: f32, config: &NoiseConfig) -> f32 {
         // 简化的噪声实现，实际可以使用更复杂的算法
         let xi = (x.floor() as i32) & 255;
         let yi = (y.floor() as i32) & 255;
@@ -311,8 +313,9 @@ impl RandomGenerator {
         let u = self.fade(xf);
         let v = self.fade(yf);
         
-        let x1 = self.lerp(aa as f32, ba as f32, u);
-        let x2 = self.lerp(ab as f32, bb as f32, u);
+        // 哈希值在[0, 255]，先归一化到[0, 1]
+        let x1 = self.lerp(aa as f32 / 255.0, ba as f32 / 255.0, u);
+        let x2 = self.lerp(ab as f32 / 255.0, bb as f32 / 255.0, u);
         self.lerp(x1, x2, v) * 2.0 - 1.0 // 转换到 [-1, 1] 范围
     }
 
@@ -537,6 +540,33 @@ mod tests {
         assert_eq!(rng2.range(1, 10), val1);
     }
 
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut first = RandomGenerator::with_seed(2024);
+        let mut second = RandomGenerator::with_seed(2024);
+        let pokemon = ["皮卡丘", "妙蛙种子", "小火龙", "杰尼龟", "伊布"];
+
+        for _ in 0..100 {
+            assert_eq!(first.range(0, 1000), second.range(0, 1000));
+            assert_eq!(first.chance(0.5), second.chance(0.5));
+            assert_eq!(first.choose(&pokemon), second.choose(&pokemon));
+        }
+
+        let mut first_order: Vec<u32> = (0..20).collect();
+        let mut second_order = first_order.clone();
+        first.shuffle(&mut first_order);
+        second.shuffle(&mut second_order);
+        assert_eq!(first_order, second_order);
+
+        // 重置种子后从头重现同一序列
+        let expected: Vec<i32> = (0..10).map(|_| first.range(0, 1000)).collect();
+        first.set_seed(2024);
+        let replay: Vec<i32> = (0..10).map(|_| first.range(0, 1000)).collect();
+        let mut fresh = RandomGenerator::with_seed(2024);
+        assert_eq!(replay, (0..10).map(|_| fresh.range(0, 1000)).collect::<Vec<_>>());
+        assert_ne!(expected, replay);
+    }
+
     #[test]
     fn test_weighted_choice() {
         let mut rng = RandomGenerator::with_seed(54321);
@@ -0,0 +1,284 @@
+// 计时工具
+// 开发心理：战斗回合限时、技能冷却、帧率限制各自手写计时逻辑，暂停和大步长帧的处理各不相同
+// 设计原则：倒计时和秒表都由调用方传入帧间隔推进，暂停、时间缩放和测试都由调用方掌控；只有帧率限制器读取真实时钟
+
+use std::time::{Duration, Instant};
+
+// 倒计时，到点时触发；重复模式下一次大步长的更新可能触发多次
+#[derive(Debug, Clone, PartialEq)]
+pub struct CountdownTimer {
+    duration: Duration,
+    elapsed: Duration,
+    repeating: bool,
+    paused: bool,
+    finished: bool,
+}
+
+impl CountdownTimer {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            elapsed: Duration::ZERO,
+            repeating: false,
+            paused: false,
+            finished: false,
+        }
+    }
+
+    pub fn repeating(duration: Duration) -> Self {
+        Self {
+            repeating: true,
+            ..Self::new(duration)
+        }
+    }
+
+    // 推进计时，返回本次触发的次数；一次性的倒计时最多触发一次
+    pub fn tick(&mut self, delta: Duration) -> u32 {
+        if self.paused || self.finished {
+            return 0;
+        }
+
+        self.elapsed += delta;
+        if self.elapsed < self.duration {
+            return 0;
+        }
+
+        if !self.repeating || self.duration.is_zero() {
+            self.elapsed = self.duration;
+            self.finished = !self.repeating;
+            return 1;
+        }
+
+        let fired = (self.elapsed.as_nanos() / self.duration.as_nanos()) as u32;
+        self.elapsed = Duration::from_nanos((self.elapsed.as_nanos() % self.duration.as_nanos()) as u64);
+        fired
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // 重新从头计时
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+        self.finished = false;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.duration.saturating_sub(self.elapsed)
+    }
+
+    // 当前周期的进度 [0, 1]
+    pub fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+// 秒表，暂停期间的帧间隔不计入
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stopwatch {
+    elapsed: Duration,
+    paused: bool,
+    last_lap_at: Duration,
+    laps: Vec<Duration>,
+}
+
+impl Stopwatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn tick(&mut self, delta: Duration) {
+        if !self.paused {
+            self.elapsed += delta;
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    // 记录一圈并返回距上一圈的时间
+    pub fn lap(&mut self) -> Duration {
+        let lap = self.elapsed - self.last_lap_at;
+        self.last_lap_at = self.elapsed;
+        self.laps.push(lap);
+        lap
+    }
+
+    pub fn laps(&self) -> &[Duration] {
+        &self.laps
+    }
+
+    // 清零并清空圈数，暂停状态不变
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+        self.last_lap_at = Duration::ZERO;
+        self.laps.clear();
+    }
+}
+
+// 帧率限制器：按固定节拍安排每帧的截止时间，落后超过一帧时从当前时间重新对齐，避免之后连续不等待地追帧
+#[derive(Debug, Clone)]
+pub struct FrameRateLimiter {
+    frame_time: Duration,
+    next_frame: Option<Instant>,
+    last_frame: Option<Instant>,
+}
+
+impl FrameRateLimiter {
+    pub fn new(target_fps: u32) -> Self {
+        Self {
+            frame_time: Self::frame_time_for(target_fps),
+            next_frame: None,
+            last_frame: None,
+        }
+    }
+
+    fn frame_time_for(target_fps: u32) -> Duration {
+        Duration::from_secs_f64(1.0 / target_fps.max(1) as f64)
+    }
+
+    pub fn set_target_fps(&mut self, target_fps: u32) {
+        self.frame_time = Self::frame_time_for(target_fps);
+        self.next_frame = None;
+    }
+
+    pub fn frame_time(&self) -> Duration {
+        self.frame_time
+    }
+
+    // 距离下一帧开始还需等待的时间，第一帧不需要等待
+    pub fn time_until_next_frame(&self, now: Instant) -> Duration {
+        self.next_frame.map_or(Duration::ZERO, |next| next.saturating_duration_since(now))
+    }
+
+    // 标记一帧开始并安排下一帧，返回距上一帧的实际间隔
+    pub fn begin_frame(&mut self, now: Instant) -> Duration {
+        let next = match self.next_frame {
+            Some(next) if now.saturating_duration_since(next) < self.frame_time => next + self.frame_time,
+            _ => now + self.frame_time,
+        };
+        self.next_frame = Some(next);
+
+        let delta = self.last_frame.map_or(Duration::ZERO, |last| now.saturating_duration_since(last));
+        self.last_frame = Some(now);
+        delta
+    }
+
+    // 睡眠到下一帧开始，返回距上一帧的实际间隔
+    pub fn wait(&mut self) -> Duration {
+        let wait = self.time_until_next_frame(Instant::now());
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+        self.begin_frame(Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(value: u64) -> Duration {
+        Duration::from_millis(value)
+    }
+
+    #[test]
+    fn test_repeating_timer_fires_per_period() {
+        // 10秒内每帧16ms，1秒周期的计时器应触发10次
+        let mut timer = CountdownTimer::repeating(ms(1000));
+        let fired: u32 = (0..625).map(|_| timer.tick(ms(16))).sum();
+        assert_eq!(fired, 10);
+        assert!(!timer.is_finished());
+
+        // 一次大步长补上错过的周期，余数留到下一周期
+        assert_eq!(timer.tick(ms(2500)), 2);
+        assert_eq!(timer.remaining(), ms(500));
+
+        // 暂停期间不计时
+        timer.pause();
+        assert_eq!(timer.tick(ms(5000)), 0);
+        timer.resume();
+        assert_eq!(timer.tick(ms(500)), 1);
+
+        let mut countdown = CountdownTimer::new(ms(300));
+        assert_eq!(countdown.tick(ms(1000)), 1);
+        assert!(countdown.is_finished());
+        assert_eq!(countdown.tick(ms(1000)), 0);
+        countdown.reset();
+        assert_eq!(countdown.remaining(), ms(300));
+    }
+
+    #[test]
+    fn test_stopwatch_accumulates_across_pause() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.tick(ms(300));
+        assert_eq!(stopwatch.lap(), ms(300));
+
+        stopwatch.pause();
+        stopwatch.tick(ms(10_000));
+        assert_eq!(stopwatch.elapsed(), ms(300));
+
+        stopwatch.resume();
+        stopwatch.tick(ms(200));
+        stopwatch.tick(ms(150));
+        assert_eq!(stopwatch.elapsed(), ms(650));
+        assert_eq!(stopwatch.lap(), ms(350));
+        assert_eq!(stopwatch.laps(), &[ms(300), ms(350)]);
+
+        stopwatch.reset();
+        assert_eq!(stopwatch.elapsed(), Duration::ZERO);
+        assert!(stopwatch.laps().is_empty());
+    }
+
+    #[test]
+    fn test_frame_rate_limiter_schedules_frames() {
+        let start = Instant::now();
+        let mut limiter = FrameRateLimiter::new(50);
+        assert_eq!(limiter.frame_time(), ms(20));
+        assert_eq!(limiter.time_until_next_frame(start), Duration::ZERO);
+
+        assert_eq!(limiter.begin_frame(start), Duration::ZERO);
+        assert_eq!(limiter.time_until_next_frame(start + ms(5)), ms(15));
+
+        // 稍晚开始的帧不推迟后续节拍
+        assert_eq!(limiter.begin_frame(start + ms(22)), ms(22));
+        assert_eq!(limiter.time_until_next_frame(start + ms(30)), ms(10));
+
+        // 落后超过一帧后从当前时间重新对齐
+        limiter.begin_frame(start + ms(200));
+        assert_eq!(limiter.time_until_next_frame(start + ms(200)), ms(20));
+    }
+}
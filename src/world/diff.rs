@@ -0,0 +1,225 @@
+// 世界差异存档
+// 开发心理：每次自动保存都把整个世界写成JSON太大太慢，玩家一次游玩通常只改动少量标志和实体
+// 设计原则：手动保存写完整基线，自动保存只记录相对基线变化的地图、实体、事件触发器、环境、标志和变量，加载时基线+差异还原
+
+use super::environment::Environment;
+use super::events::EventTrigger;
+use super::map::GameMap;
+use super::{EntityId, MapId, World, WorldEntity, WorldId, WorldTime, WeatherSystem};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// 事件队列和监听器是运行时状态，只有触发器进入差异
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldDiff {
+    pub world_id: WorldId,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub changed_flags: HashMap<String, bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_flags: Vec<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub changed_variables: HashMap<String, i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_variables: Vec<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub changed_entities: HashMap<EntityId, WorldEntity>,  // 新增或修改过的实体
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_entities: Vec<EntityId>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub changed_maps: HashMap<MapId, GameMap>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_maps: Vec<MapId>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub changed_triggers: HashMap<String, EventTrigger>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_triggers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<Environment>,   // 与基线不同时整体记录
+    pub next_entity_id: EntityId,
+    pub current_map: Option<MapId>,
+    pub world_time: WorldTime,
+    pub weather: WeatherSystem,
+}
+
+impl WorldDiff {
+    pub fn between(baseline: &World, world: &World) -> Self {
+        let (changed_flags, removed_flags) = diff_maps(&baseline.world_flags, &world.world_flags, |a, b| a == b);
+        let (changed_variables, removed_variables) = diff_maps(&baseline.world_variables, &world.world_variables, |a, b| a == b);
+        let (changed_entities, removed_entities) = diff_maps(&baseline.entities, &world.entities, serialized_equal);
+        let (changed_maps, removed_maps) = diff_maps(&baseline.maps, &world.maps, serialized_equal);
+        let (changed_triggers, removed_triggers) = diff_maps(baseline.events.triggers(), world.events.triggers(), serialized_equal);
+        let environment = (!serialized_equal(&baseline.environment, &world.environment))
+            .then(|| world.environment.clone());
+
+        Self {
+            world_id: world.id,
+            changed_flags,
+            removed_flags,
+            changed_variables,
+            removed_variables,
+            changed_entities,
+            removed_entities,
+            changed_maps,
+            removed_maps,
+            changed_triggers,
+            removed_triggers,
+            environment,
+            next_entity_id: world.next_entity_id,
+            current_map: world.current_map,
+            world_time: world.world_time.clone(),
+            weather: world.weather.clone(),
+        }
+    }
+
+    // 没有任何标志、变量、实体、地图、触发器或环境变化
+    pub fn is_empty(&self) -> bool {
+        self.changed_flags.is_empty()
+            && self.removed_flags.is_empty()
+            && self.changed_variables.is_empty()
+            && self.removed_variables.is_empty()
+            && self.changed_entities.is_empty()
+            && self.removed_entities.is_empty()
+            && self.changed_maps.is_empty()
+            && self.removed_maps.is_empty()
+            && self.changed_triggers.is_empty()
+            && self.removed_triggers.is_empty()
+            && self.environment.is_none()
+    }
+
+    pub fn apply(&self, baseline: &World) -> World {
+        let mut world = baseline.clone();
+
+        world.world_flags.extend(self.changed_flags.iter().map(|(k, v)| (k.clone(), *v)));
+        for flag in &self.removed_flags {
+            world.world_flags.remove(flag);
+        }
+        world.world_variables.extend(self.changed_variables.iter().map(|(k, v)| (k.clone(), *v)));
+        for variable in &self.removed_variables {
+            world.world_variables.remove(variable);
+        }
+        world.entities.extend(self.changed_entities.iter().map(|(id, entity)| (*id, entity.clone())));
+        for entity_id in &self.removed_entities {
+            world.entities.remove(entity_id);
+        }
+        world.maps.extend(self.changed_maps.iter().map(|(id, map)| (*id, map.clone())));
+        for map_id in &self.removed_maps {
+            world.maps.remove(map_id);
+        }
+        for trigger in self.changed_triggers.values() {
+            world.events.add_trigger(trigger.clone());
+        }
+        for trigger_id in &self.removed_triggers {
+            world.events.remove_trigger(trigger_id);
+        }
+        if let Some(environment) = &self.environment {
+            world.environment = environment.clone();
+        }
+
+        world.next_entity_id = self.next_entity_id;
+        world.current_map = self.current_map;
+        world.world_time = self.world_time.clone();
+        world.weather = self.weather.clone();
+        world
+    }
+}
+
+// 实体、地图等没有实现PartialEq，按序列化结果比较
+fn serialized_equal<T: Serialize>(a: &T, b: &T) -> bool {
+    match (serde_json::to_value(a), serde_json::to_value(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn diff_maps<K, V>(
+    baseline: &HashMap<K, V>,
+    current: &HashMap<K, V>,
+    equal: impl Fn(&V, &V) -> bool,
+) -> (HashMap<K, V>, Vec<K>)
+where
+    K: Clone + Eq + std::hash::Hash,
+    V: Clone,
+{
+    let changed = current.iter()
+        .filter(|(key, value)| baseline.get(*key).is_none_or(|old| !equal(old, value)))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    let removed = baseline.keys()
+        .filter(|key| !current.contains_key(*key))
+        .cloned()
+        .collect();
+    (changed, removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{EntityType, WorldManager};
+    use glam::Vec3;
+
+    #[test]
+    fn test_single_flag_diff_restores() {
+        let mut manager = WorldManager::new();
+        let world_id = manager.create_world("测试".to_string(), "测试".to_string()).unwrap();
+        manager.load_world(world_id).unwrap();
+        for i in 0..100 {
+            manager.create_entity(EntityType::NPC, Vec3::new(i as f32, 0.0, 0.0), Vec::new()).unwrap();
+        }
+
+        let baseline = manager.get_current_world().unwrap().clone();
+        let mut world = baseline.clone();
+        world.world_flags.insert("got_pokedex".to_string(), true);
+
+        let diff = WorldDiff::between(&baseline, &world);
+        assert_eq!(diff.changed_flags.len(), 1);
+        assert!(diff.changed_entities.is_empty());
+
+        let diff_size = serde_json::to_string(&diff).unwrap().len();
+        let full_size = serde_json::to_string(&world).unwrap().len();
+        assert!(diff_size * 10 < full_size, "差异 {} 字节, 完整 {} 字节", diff_size, full_size);
+
+        let restored = diff.apply(&baseline);
+        assert_eq!(restored.world_flags, world.world_flags);
+        assert_eq!(restored.entities.len(), world.entities.len());
+        assert!(WorldDiff::between(&world, &restored).is_empty());
+    }
+
+    #[test]
+    fn test_maps_triggers_and_environment_diff_restores() {
+        use crate::world::events::{EventTrigger, TriggerType};
+        use crate::world::environment::BiomeType;
+        use crate::world::map::GameMap;
+        use glam::Vec2;
+
+        let mut manager = WorldManager::new();
+        let world_id = manager.create_world("测试".to_string(), "测试".to_string()).unwrap();
+        manager.load_world(world_id).unwrap();
+        let mut baseline = manager.get_current_world().unwrap().clone();
+        baseline.maps.insert(1, GameMap::new(1, "route1".to_string(), Vec2::new(320.0, 320.0)));
+        baseline.maps.insert(2, GameMap::new(2, "route2".to_string(), Vec2::new(320.0, 320.0)));
+        baseline.events.add_trigger(EventTrigger::area("gate", TriggerType::OnEnter, Vec3::ZERO, Vec3::ONE, "rival").once());
+        baseline.events.add_trigger(EventTrigger::area("sign", TriggerType::OnEnter, Vec3::ZERO, Vec3::ONE, "read"));
+
+        let mut world = baseline.clone();
+        world.maps.get_mut(&1).unwrap().properties.insert("visited".to_string(), "true".to_string());
+        world.maps.remove(&2);
+        world.events.check_triggers(Vec3::ZERO, crate::world::events::EventSource::Player(1));
+        world.events.remove_trigger("sign");
+        world.environment.set_biome(BiomeType::Desert).unwrap();
+
+        let diff = WorldDiff::between(&baseline, &world);
+        assert_eq!(diff.changed_maps.keys().collect::<Vec<_>>(), vec![&1]);
+        assert_eq!(diff.removed_maps, vec![2]);
+        assert_eq!(diff.changed_triggers.keys().collect::<Vec<_>>(), vec!["gate"]);
+        assert_eq!(diff.removed_triggers, vec!["sign".to_string()]);
+        assert!(diff.environment.is_some());
+
+        let diff: WorldDiff = serde_json::from_str(&serde_json::to_string(&diff).unwrap()).unwrap();
+        let restored = diff.apply(&baseline);
+        assert_eq!(restored.maps[&1].properties.get("visited").map(String::as_str), Some("true"));
+        assert!(!restored.maps.contains_key(&2));
+        assert_eq!(restored.events.get_trigger("gate").unwrap().triggered_count, 1);
+        assert!(restored.events.get_trigger("sign").is_none());
+        assert!(WorldDiff::between(&world, &restored).is_empty());
+    }
+}
@@ -0,0 +1,242 @@
+// 野生遭遇系统
+// 开发心理：在草丛和水面行走时遇到野生宝可梦是探索的核心乐趣，遭遇内容需要由地图数据决定
+// 设计原则：按地形分区的数据驱动遭遇表，时间和天气只调整权重，随机源由调用方传入便于复现
+
+use super::environment::TimeOfDay;
+use super::map::CollisionType;
+use super::Weather;
+#[cfg(feature = "pokemon-wip")]
+pub use crate::pokemon::SpeciesId;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+// 临时类型定义，直到pokemon模块可用
+#[cfg(not(feature = "pokemon-wip"))]
+pub type SpeciesId = u32;
+
+// 遭遇地形
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EncounterZone {
+    Grass,      // 草丛
+    Water,      // 水面
+}
+
+impl EncounterZone {
+    // 由瓦片的碰撞类型判断地形，其它地形不触发遭遇
+    pub fn from_collision(collision_type: CollisionType) -> Option<Self> {
+        match collision_type {
+            CollisionType::Grass => Some(EncounterZone::Grass),
+            CollisionType::Water => Some(EncounterZone::Water),
+            _ => None,
+        }
+    }
+}
+
+// 遭遇时的环境条件，未知的条件不影响权重
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EncounterConditions {
+    pub time_of_day: Option<TimeOfDay>,
+    pub weather: Option<Weather>,
+}
+
+// 遭遇条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncounterEntry {
+    pub species: SpeciesId,
+    pub min_level: u8,
+    pub max_level: u8,
+    pub weight: u32,
+    // 权重倍率，0表示该条件下不出现
+    #[serde(default)]
+    pub time_multipliers: Vec<(TimeOfDay, f32)>,
+    #[serde(default)]
+    pub weather_multipliers: Vec<(Weather, f32)>,
+}
+
+impl EncounterEntry {
+    pub fn new(species: SpeciesId, min_level: u8, max_level: u8, weight: u32) -> Self {
+        Self {
+            species,
+            min_level: min_level.min(max_level),
+            max_level: max_level.max(min_level),
+            weight,
+            time_multipliers: Vec::new(),
+            weather_multipliers: Vec::new(),
+        }
+    }
+
+    pub fn with_time_multiplier(mut self, time_of_day: TimeOfDay, multiplier: f32) -> Self {
+        self.time_multipliers.push((time_of_day, multiplier.max(0.0)));
+        self
+    }
+
+    pub fn with_weather_multiplier(mut self, weather: Weather, multiplier: f32) -> Self {
+        self.weather_multipliers.push((weather, multiplier.max(0.0)));
+        self
+    }
+
+    // 应用时间和天气倍率后的权重
+    pub fn effective_weight(&self, conditions: &EncounterConditions) -> f32 {
+        let time_multiplier = conditions.time_of_day
+            .and_then(|time| self.time_multipliers.iter().find(|(t, _)| *t == time))
+            .map_or(1.0, |(_, multiplier)| *multiplier);
+        let weather_multiplier = conditions.weather
+            .and_then(|weather| self.weather_multipliers.iter().find(|(w, _)| *w == weather))
+            .map_or(1.0, |(_, multiplier)| *multiplier);
+
+        self.weight as f32 * time_multiplier * weather_multiplier
+    }
+
+    fn roll_level(&self, rng: &mut fastrand::Rng) -> u8 {
+        rng.u8(self.min_level..=self.max_level)
+    }
+}
+
+// 遭遇表
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncounterTable {
+    pub zone: EncounterZone,
+    pub encounter_rate: f32,            // 每步触发遭遇的概率 0.0-1.0
+    pub area: Option<(Vec2, Vec2)>,     // 限定区域(最小点, 最大点)，None表示整张地图
+    pub entries: Vec<EncounterEntry>,
+}
+
+impl EncounterTable {
+    pub fn new(zone: EncounterZone, encounter_rate: f32) -> Self {
+        Self {
+            zone,
+            encounter_rate: encounter_rate.clamp(0.0, 1.0),
+            area: None,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn with_area(mut self, min: Vec2, max: Vec2) -> Self {
+        self.area = Some((min.min(max), min.max(max)));
+        self
+    }
+
+    pub fn add_entry(&mut self, entry: EncounterEntry) {
+        self.entries.push(entry);
+    }
+
+    pub fn contains(&self, position: Vec2) -> bool {
+        self.area.map_or(true, |(min, max)| {
+            position.x >= min.x && position.y >= min.y && position.x < max.x && position.y < max.y
+        })
+    }
+
+    // 按遭遇率判定是否触发，触发后按权重选择
+    pub fn roll(&self, conditions: &EncounterConditions, rng: &mut fastrand::Rng) -> Option<(SpeciesId, u8)> {
+        if rng.f32() >= self.encounter_rate {
+            return None;
+        }
+        self.choose(conditions, rng)
+    }
+
+    // 按权重选择一个条目并随机等级，不考虑遭遇率
+    pub fn choose(&self, conditions: &EncounterConditions, rng: &mut fastrand::Rng) -> Option<(SpeciesId, u8)> {
+        let weights: Vec<f32> = self.entries.iter()
+            .map(|entry| entry.effective_weight(conditions))
+            .collect();
+        let total_weight: f32 = weights.iter().sum();
+        if total_weight <= 0.0 {
+            return None;
+        }
+
+        let mut remaining = rng.f32() * total_weight;
+        let mut chosen = None;
+        for (entry, &weight) in self.entries.iter().zip(&weights) {
+            if weight <= 0.0 {
+                continue;
+            }
+            // 浮点误差时落到最后一个有效条目
+            chosen = Some(entry);
+            if remaining < weight {
+                break;
+            }
+            remaining -= weight;
+        }
+
+        chosen.map(|entry| (entry.species, entry.roll_level(rng)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::map::{CollisionTile, GameMap};
+
+    const DRAWS: usize = 20000;
+
+    fn route_table() -> EncounterTable {
+        let mut table = EncounterTable::new(EncounterZone::Grass, 1.0);
+        table.add_entry(EncounterEntry::new(16, 2, 4, 3));
+        table.add_entry(EncounterEntry::new(19, 3, 5, 1).with_time_multiplier(TimeOfDay::Night, 3.0));
+        table
+    }
+
+    fn share_of(table: &EncounterTable, conditions: &EncounterConditions, species: SpeciesId, seed: u64) -> f32 {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let hits = (0..DRAWS)
+            .filter(|_| table.choose(conditions, &mut rng).unwrap().0 == species)
+            .count();
+        hits as f32 / DRAWS as f32
+    }
+
+    #[test]
+    fn test_weighted_probabilities() {
+        let table = route_table();
+
+        // 3:1 权重
+        let day = EncounterConditions { time_of_day: Some(TimeOfDay::Day), weather: None };
+        assert!((share_of(&table, &day, 16, 7) - 0.75).abs() < 0.02);
+
+        // 夜晚倍率后变为 3:3
+        let night = EncounterConditions { time_of_day: Some(TimeOfDay::Night), weather: None };
+        assert!((share_of(&table, &night, 16, 7) - 0.5).abs() < 0.02);
+
+        // 天气倍率为0时不出现
+        let mut rainy_table = EncounterTable::new(EncounterZone::Grass, 1.0);
+        rainy_table.add_entry(EncounterEntry::new(16, 2, 4, 1).with_weather_multiplier(Weather::Rain, 0.0));
+        rainy_table.add_entry(EncounterEntry::new(60, 5, 5, 1));
+        let rain = EncounterConditions { time_of_day: None, weather: Some(Weather::Rain) };
+        assert_eq!(share_of(&rainy_table, &rain, 60, 11), 1.0);
+    }
+
+    #[test]
+    fn test_levels_within_range() {
+        let table = route_table();
+        let mut rng = fastrand::Rng::with_seed(3);
+        let mut seen_levels = std::collections::HashSet::new();
+
+        for _ in 0..1000 {
+            let (species, level) = table.choose(&EncounterConditions::default(), &mut rng).unwrap();
+            match species {
+                16 => assert!((2..=4).contains(&level)),
+                19 => assert!((3..=5).contains(&level)),
+                _ => unreachable!(),
+            }
+            seen_levels.insert(level);
+        }
+
+        // 范围两端都能取到
+        assert!(seen_levels.contains(&2) && seen_levels.contains(&5));
+    }
+
+    #[test]
+    fn test_roll_encounter_only_in_zone() {
+        let mut map = GameMap::new(1, "1号道路".to_string(), Vec2::new(320.0, 320.0));
+        map.set_collision(1, 1, CollisionTile { collision_type: CollisionType::Grass, ..Default::default() });
+        map.encounter_tables.push(route_table());
+
+        let mut rng = fastrand::Rng::with_seed(5);
+        // 草丛瓦片(1, 1)覆盖 32..64
+        assert!(map.roll_encounter(Vec2::new(40.0, 40.0), &mut rng).is_some());
+        assert!(map.roll_encounter(Vec2::new(8.0, 8.0), &mut rng).is_none());
+
+        // 遭遇率为0时不触发
+        map.encounter_tables[0].encounter_rate = 0.0;
+        assert!(map.roll_encounter(Vec2::new(40.0, 40.0), &mut rng).is_none());
+    }
+}
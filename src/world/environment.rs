@@ -207,6 +207,36 @@ pub enum ParticleBlendMode {
     Screen,         // 屏幕混合
 }
 
+// 时间段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TimeOfDay {
+    Morning,        // 早晨 (5:00-10:00)
+    Day,            // 白天 (10:00-17:00)
+    Evening,        // 傍晚 (17:00-20:00)
+    Night,          // 夜晚 (20:00-5:00)
+}
+
+impl TimeOfDay {
+    pub fn from_hour(hour: u8) -> Self {
+        match hour % 24 {
+            5..=9 => TimeOfDay::Morning,
+            10..=16 => TimeOfDay::Day,
+            17..=19 => TimeOfDay::Evening,
+            _ => TimeOfDay::Night,
+        }
+    }
+
+    // 各时间段的环境光颜色，白天不调色
+    pub fn ambient_tint(self) -> Vec3 {
+        match self {
+            TimeOfDay::Morning => Vec3::new(1.0, 0.92, 0.85),
+            TimeOfDay::Day => Vec3::ONE,
+            TimeOfDay::Evening => Vec3::new(1.0, 0.75, 0.6),
+            TimeOfDay::Night => Vec3::new(0.45, 0.5, 0.8),
+        }
+    }
+}
+
 // 季节
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Season {
@@ -295,6 +325,14 @@ impl Environment {
         }
     }
     
+    // 与世界时间同步，并按时间段调整环境光颜色
+    pub fn set_time_of_day(&mut self, hour: u8, minute: u8) {
+        self.time_of_day = (hour % 24) as f32 + minute.min(59) as f32 / 60.0;
+        let tint = TimeOfDay::from_hour(hour).ambient_tint();
+        let intensity = self.lighting.ambient_light.w;
+        self.lighting.ambient_light = tint.extend(intensity);
+    }
+    
     // 更新环境系统
     pub fn update(&mut self, delta_time: f32) -> Result<(), GameError> {
         // 更新时间
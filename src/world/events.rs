@@ -3,7 +3,7 @@
 // 设计原则：解耦设计、优先级处理、延迟执行、状态同步
 
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque, BinaryHeap};
+use std::collections::{HashMap, HashSet, VecDeque, BinaryHeap};
 use std::cmp::Ordering;
 use log::{debug, warn, error};
 use crate::core::error::GameError;
@@ -85,7 +85,7 @@ pub struct DelayedEvent {
 }
 
 // 事件源
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EventSource {
     Player(u64),
     NPC(u64),
@@ -169,8 +169,59 @@ pub struct EventTrigger {
     pub cooldown: f32,
     pub last_triggered: Option<std::time::SystemTime>,
     pub triggered_count: u32,
-    pub max_triggers: Option<u32>,
+    pub max_triggers: Option<u32>,      // Some(1)表示只触发一次
     pub enabled: bool,
+    // 当前位于区域内的实体，用于判断进入/离开
+    #[serde(default)]
+    pub occupants: HashSet<EventSource>,
+}
+
+impl EventTrigger {
+    // 以position为中心、size为尺寸的区域触发器，默认可重复触发
+    pub fn area(id: &str, trigger_type: TriggerType, position: Vec3, size: Vec3, event: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: id.to_string(),
+            trigger_type,
+            position,
+            size: size.abs(),
+            events: vec![event.to_string()],
+            conditions: Vec::new(),
+            cooldown: 0.0,
+            last_triggered: None,
+            triggered_count: 0,
+            max_triggers: None,
+            enabled: true,
+            occupants: HashSet::new(),
+        }
+    }
+
+    // 只触发一次
+    pub fn once(mut self) -> Self {
+        self.max_triggers = Some(1);
+        self
+    }
+
+    // 不限触发次数
+    pub fn repeatable(mut self) -> Self {
+        self.max_triggers = None;
+        self
+    }
+
+    pub fn with_condition(mut self, condition: TriggerCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    // 地图平面(x, z)上的包围盒检测，边界包含在内
+    pub fn contains(&self, position: Vec3) -> bool {
+        let half = self.size * 0.5;
+        (position.x - self.position.x).abs() <= half.x && (position.z - self.position.z).abs() <= half.z
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.max_triggers.map_or(false, |max_triggers| self.triggered_count >= max_triggers)
+    }
 }
 
 // 触发器类型
@@ -325,21 +376,41 @@ impl EventManager {
         debug!("添加事件触发器: {}", trigger_id);
     }
     
-    // 检查触发器
+    pub fn get_trigger(&self, trigger_id: &str) -> Option<&EventTrigger> {
+        self.triggers.get(trigger_id)
+    }
+    
+    pub fn remove_trigger(&mut self, trigger_id: &str) -> Option<EventTrigger> {
+        self.triggers.remove(trigger_id)
+    }
+    
+    // 触发器的启用状态和触发次数需要随存档保存
+    pub fn triggers(&self) -> &HashMap<String, EventTrigger> {
+        &self.triggers
+    }
+    
+    // 检查触发器：实体进入或离开区域时通过trigger_event派发事件，返回派发的事件类型
     pub fn check_triggers(&mut self, entity_position: Vec3, entity_type: EventSource) -> Vec<String> {
-        let mut triggered_events = Vec::new();
+        let mut fired = Vec::new();
         let current_time = std::time::SystemTime::now();
         
         for trigger in self.triggers.values_mut() {
-            if !trigger.enabled {
-                continue;
-            }
+            let inside = trigger.contains(entity_position)
+                && Self::check_trigger_conditions(&trigger.conditions, &entity_type);
+            let was_inside = if inside {
+                !trigger.occupants.insert(entity_type.clone())
+            } else {
+                trigger.occupants.remove(&entity_type)
+            };
             
-            // 检查触发次数限制
-            if let Some(max_triggers) = trigger.max_triggers {
-                if trigger.triggered_count >= max_triggers {
-                    continue;
-                }
+            // 只在进入/离开的那一刻触发，停留在区域内不会重复触发
+            let crossed = match trigger.trigger_type {
+                TriggerType::OnEnter => inside && !was_inside,
+                TriggerType::OnExit => !inside && was_inside,
+                _ => false,
+            };
+            if !crossed || !trigger.enabled || trigger.is_exhausted() {
+                continue;
             }
             
             // 检查冷却时间
@@ -351,22 +422,21 @@ impl EventManager {
                 }
             }
             
-            // 检查位置触发
-            if trigger.trigger_type == TriggerType::OnEnter {
-                let distance = (entity_position - trigger.position).length();
-                if distance <= trigger.size.x * 0.5 {
-                    // 检查触发条件
-                    if self.check_trigger_conditions(&trigger.conditions, &entity_type) {
-                        for event_type in &trigger.events {
-                            triggered_events.push(event_type.clone());
-                        }
-                        
-                        trigger.last_triggered = Some(current_time);
-                        trigger.triggered_count += 1;
-                        
-                        debug!("触发器激活: {} (位置: {:?})", trigger.id, entity_position);
-                    }
-                }
+            trigger.last_triggered = Some(current_time);
+            trigger.triggered_count += 1;
+            fired.push((trigger.id.clone(), trigger.events.clone()));
+            
+            debug!("触发器激活: {} (位置: {:?})", trigger.id, entity_position);
+        }
+        
+        let mut triggered_events = Vec::new();
+        for (trigger_id, events) in fired {
+            for event_type in events {
+                let mut data = HashMap::new();
+                data.insert("trigger_id".to_string(), EventValue::String(trigger_id.clone()));
+                data.insert("position".to_string(), EventValue::Position(entity_position));
+                self.trigger_event(&event_type, data);
+                triggered_events.push(event_type);
             }
         }
         
@@ -524,7 +594,7 @@ impl EventManager {
         Ok(())
     }
     
-    fn check_trigger_conditions(&self, conditions: &[TriggerCondition], entity_type: &EventSource) -> bool {
+    fn check_trigger_conditions(conditions: &[TriggerCondition], entity_type: &EventSource) -> bool {
         for condition in conditions {
             match condition {
                 TriggerCondition::PlayerOnly => {
@@ -679,4 +749,46 @@ mod tests {
         assert!(matches!(event.source, EventSource::Player(1)));
         assert!(matches!(event.target, Some(EventTarget::World)));
     }
-}
\ No newline at end of file
+    
+    fn walk(manager: &mut EventManager, path: &[f32]) -> usize {
+        path.iter()
+            .map(|&x| manager.check_triggers(Vec3::new(x, 0.0, 0.0), EventSource::Player(1)).len())
+            .sum()
+    }
+    
+    #[test]
+    fn test_once_only_trigger_fires_once() {
+        let mut manager = EventManager::new();
+        manager.add_trigger(
+            EventTrigger::area("gate", TriggerType::OnEnter, Vec3::new(10.0, 0.0, 0.0), Vec3::splat(4.0), "rival_battle").once()
+        );
+        
+        // 进入、停留、离开、再次进入
+        let fired = walk(&mut manager, &[0.0, 9.0, 10.0, 11.0, 20.0, 10.0]);
+        assert_eq!(fired, 1);
+        assert_eq!(manager.get_trigger("gate").unwrap().triggered_count, 1);
+        
+        let event = &manager.event_queue[0];
+        assert_eq!(event.event_type, "rival_battle");
+        assert!(matches!(event.data.get("trigger_id"), Some(EventValue::String(id)) if id == "gate"));
+    }
+    
+    #[test]
+    fn test_repeatable_enter_and_exit_triggers() {
+        let mut manager = EventManager::new();
+        let area = |trigger_type, event| {
+            EventTrigger::area(event, trigger_type, Vec3::ZERO, Vec3::splat(2.0), event)
+                .with_condition(TriggerCondition::PlayerOnly)
+        };
+        manager.add_trigger(area(TriggerType::OnEnter, "enter_cave"));
+        manager.add_trigger(area(TriggerType::OnExit, "leave_cave"));
+        
+        walk(&mut manager, &[5.0, 0.0, 0.5, 5.0, 0.0, 5.0]);
+        let enters = manager.event_queue.iter().filter(|e| e.event_type == "enter_cave").count();
+        let exits = manager.event_queue.iter().filter(|e| e.event_type == "leave_cave").count();
+        assert_eq!((enters, exits), (2, 2));
+        
+        // 不满足条件的实体不会触发
+        assert!(manager.check_triggers(Vec3::ZERO, EventSource::NPC(3)).is_empty());
+    }
+}
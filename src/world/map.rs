@@ -3,9 +3,11 @@
 // 设计原则：分块管理、层级渲染、碰撞优化、内存控制
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 use log::{debug, warn, error};
 use crate::core::error::GameError;
+use super::encounter::{EncounterConditions, EncounterTable, EncounterZone, SpeciesId};
 #[cfg(feature = "graphics-wip")]
 use crate::graphics::renderer::Renderer2D;
 #[cfg(feature = "graphics-wip")]
@@ -50,6 +52,9 @@ pub type MapId = u32;
 pub type ChunkId = u64;
 pub type TileId = u32;
 
+// 碰撞判定容差，避免贴墙时因浮点误差被判定为重叠
+const COLLISION_EPSILON: f32 = 1e-4;
+
 // 游戏地图
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameMap {
@@ -63,6 +68,10 @@ pub struct GameMap {
     // 地图层级
     pub layers: Vec<MapLayer>,
     
+    // 瓦片集，按first_gid升序
+    #[serde(default)]
+    pub tilesets: Vec<Tileset>,
+    
     // 分块系统
     pub chunks: HashMap<ChunkId, MapChunk>,
     pub loaded_chunks: Vec<ChunkId>,
@@ -84,11 +93,32 @@ pub struct GameMap {
     pub music: Option<String>,
     pub weather_override: Option<crate::world::Weather>,
     
+    // 野生遭遇
+    #[serde(default)]
+    pub encounter_tables: Vec<EncounterTable>,
+    
     // 动态内容
     pub dynamic_objects: HashMap<u64, DynamicObject>,
     pub next_object_id: u64,
 }
 
+// 可见瓦片批次，同一图层同一瓦片集的瓦片一次绘制
+#[derive(Debug, Clone)]
+pub struct TileBatch {
+    pub layer_index: usize,
+    pub tileset: Option<String>,
+    pub tiles: Vec<TileInstance>,
+}
+
+// 待绘制的瓦片实例
+#[derive(Debug, Clone, Copy)]
+pub struct TileInstance {
+    pub position: Vec2,             // 已应用视差偏移的左上角位置
+    pub size: Vec2,
+    pub uv_rect: Vec4,              // (u1, v1, u2, v2)
+    pub tile: TileData,
+}
+
 // 地图层级
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MapLayer {
@@ -264,6 +294,7 @@ pub struct MapManager {
     tilesets: HashMap<String, Tileset>,
     
     // 渲染优化
+    view_size: Vec2,                // 视口尺寸(世界坐标)
     frustum_culling: bool,
     occlusion_culling: bool,
     batch_rendering: bool,
@@ -276,9 +307,10 @@ pub struct MapManager {
 }
 
 // 瓦片集
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tileset {
     pub name: String,
+    pub first_gid: TileId,          // 第一个瓦片的全局ID
     pub texture_id: u32,
     pub tile_width: u32,
     pub tile_height: u32,
@@ -286,11 +318,36 @@ pub struct Tileset {
     pub spacing: u32,
     pub tile_count: u32,
     pub columns: u32,
-    pub tile_properties: HashMap<TileId, TileProperties>,
+    pub image: Option<String>,
+    pub image_width: u32,
+    pub image_height: u32,
+    pub tile_properties: HashMap<TileId, TileProperties>, // 键为瓦片集内的局部ID
+}
+
+impl Tileset {
+    pub fn contains(&self, gid: TileId) -> bool {
+        gid >= self.first_gid && gid - self.first_gid < self.tile_count
+    }
+    
+    // 全局ID对应的UV矩形 (u1, v1, u2, v2)
+    pub fn tile_uv_rect(&self, gid: TileId) -> Option<Vec4> {
+        if !self.contains(gid) || self.columns == 0 || self.image_width == 0 || self.image_height == 0 {
+            return None;
+        }
+        
+        let local_id = gid - self.first_gid;
+        let x = self.margin + (local_id % self.columns) * (self.tile_width + self.spacing);
+        let y = self.margin + (local_id / self.columns) * (self.tile_height + self.spacing);
+        let image_size = Vec2::new(self.image_width as f32, self.image_height as f32);
+        
+        let min = Vec2::new(x as f32, y as f32) / image_size;
+        let max = Vec2::new((x + self.tile_width) as f32, (y + self.tile_height) as f32) / image_size;
+        Some(Vec4::new(min.x, min.y, max.x, max.y))
+    }
 }
 
 // 瓦片属性
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TileProperties {
     pub solid: bool,
     pub one_way: bool,
@@ -300,7 +357,7 @@ pub struct TileProperties {
 }
 
 // 动画帧
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct AnimationFrame {
     pub tile_id: TileId,
     pub duration: f32,
@@ -316,6 +373,7 @@ impl GameMap {
             tile_size: Vec2::new(32.0, 32.0),
             chunk_size: Vec2::new(512.0, 512.0),
             layers: Vec::new(),
+            tilesets: Vec::new(),
             chunks: HashMap::new(),
             loaded_chunks: Vec::new(),
             collision_map: CollisionMap {
@@ -331,11 +389,85 @@ impl GameMap {
             ambient_light: Vec4::new(1.0, 1.0, 1.0, 1.0),
             music: None,
             weather_override: None,
+            encounter_tables: Vec::new(),
             dynamic_objects: HashMap::new(),
             next_object_id: 1,
         }
     }
     
+    // 从Tiled地图文件加载，地图ID默认为0，由调用方分配
+    pub fn load_tmx(path: &Path) -> Result<Self, GameError> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| GameError::Map(format!("无法读取地图文件 {:?}: {}", path, e)))?;
+        let name = path.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        
+        super::tmx::parse_tmx(&source, &name)
+            .map_err(|e| GameError::Map(format!("{:?}: {}", path, e)))
+    }
+    
+    // 查找全局瓦片ID所属的瓦片集
+    pub fn tileset_for_gid(&self, gid: TileId) -> Option<&Tileset> {
+        self.tilesets.iter().rev().find(|tileset| tileset.first_gid <= gid)
+    }
+    
+    // 全局瓦片ID在瓦片集中定义的属性
+    pub fn tile_properties(&self, gid: TileId) -> Option<&TileProperties> {
+        let tileset = self.tileset_for_gid(gid)?;
+        tileset.tile_properties.get(&(gid - tileset.first_gid))
+    }
+    
+    // 收集视口内的可见瓦片，按图层顺序分批，每批内瓦片共享同一瓦片集
+    pub fn visible_tile_batches(&self, camera_position: Vec2, view_size: Vec2) -> Vec<TileBatch> {
+        let half_view = view_size * 0.5;
+        let mut batches = Vec::new();
+        
+        for (layer_index, layer) in self.layers.iter().enumerate() {
+            if !layer.visible || layer.layer_type == LayerType::Collision {
+                continue;
+            }
+            
+            // 视差层相对相机偏移，换算回瓦片坐标范围
+            let parallax_offset = camera_position * (Vec2::ONE - layer.parallax_factor);
+            let view_min = ((camera_position - half_view + parallax_offset) / self.tile_size).floor();
+            let view_max = ((camera_position + half_view + parallax_offset) / self.tile_size).ceil();
+            
+            // 瓦片集下标 -> 瓦片，无瓦片集的瓦片排在最后
+            let mut groups: BTreeMap<usize, Vec<TileInstance>> = BTreeMap::new();
+            for y in view_min.y as i32..view_max.y as i32 {
+                for x in view_min.x as i32..view_max.x as i32 {
+                    let tile = match layer.tiles.get(&(x, y)) {
+                        Some(tile) if tile.tile_id != 0 => *tile,
+                        _ => continue,
+                    };
+                    
+                    let tileset_index = self.tilesets.iter().rposition(|tileset| tileset.first_gid <= tile.tile_id);
+                    let uv_rect = tileset_index
+                        .and_then(|index| self.tilesets[index].tile_uv_rect(tile.tile_id))
+                        .unwrap_or(Vec4::new(0.0, 0.0, 1.0, 1.0));
+                    
+                    groups.entry(tileset_index.unwrap_or(usize::MAX)).or_default().push(TileInstance {
+                        position: Vec2::new(x as f32, y as f32) * self.tile_size - parallax_offset,
+                        size: self.tile_size,
+                        uv_rect,
+                        tile,
+                    });
+                }
+            }
+            
+            for (tileset_index, tiles) in groups {
+                batches.push(TileBatch {
+                    layer_index,
+                    tileset: self.tilesets.get(tileset_index).map(|tileset| tileset.name.clone()),
+                    tiles,
+                });
+            }
+        }
+        
+        batches
+    }
+    
     // 添加地图层
     pub fn add_layer(&mut self, layer_type: LayerType, name: String, z_order: i32) -> u32 {
         let layer_id = self.layers.len() as u32;
@@ -424,6 +556,87 @@ impl GameMap {
         None
     }
     
+    // 瓦片是否为实心
+    pub fn is_solid(&self, tile_x: i32, tile_y: i32) -> bool {
+        self.collision_map.tiles.get(&(tile_x, tile_y)).map_or(false, |collision| collision.solid)
+    }
+    
+    // 解析移动，from/to为包围盒左上角；先水平后垂直分轴处理，撞墙时沿墙滑动
+    pub fn resolve_movement(&self, from: Vec2, to: Vec2, size: Vec2) -> Vec2 {
+        let delta = to - from;
+        let after_x = self.sweep_axis(from, delta.x, size, 0);
+        self.sweep_axis(after_x, delta.y, size, 1)
+    }
+    
+    // 沿单轴扫掠包围盒，停在第一个实心瓦片前；扫掠整个路径，高速移动也不会穿墙
+    fn sweep_axis(&self, position: Vec2, delta: f32, size: Vec2, axis: usize) -> Vec2 {
+        let mut target = position;
+        target[axis] += delta;
+        if delta == 0.0 {
+            return target;
+        }
+        
+        let min_tile = (position.min(target) / self.tile_size).floor();
+        let max_tile = ((position.max(target) + size) / self.tile_size).ceil();
+        
+        let mut limit = target[axis];
+        for y in min_tile.y as i32..max_tile.y as i32 {
+            for x in min_tile.x as i32..max_tile.x as i32 {
+                if !self.is_solid(x, y) {
+                    continue;
+                }
+                
+                // 只阻挡前方的瓦片，起始时已重叠的瓦片允许离开
+                let tile_min = Vec2::new(x as f32, y as f32) * self.tile_size;
+                let tile_max = tile_min + self.tile_size;
+                if delta > 0.0 {
+                    if tile_min[axis] >= position[axis] + size[axis] - COLLISION_EPSILON {
+                        limit = limit.min(tile_min[axis] - size[axis]);
+                    }
+                } else if tile_max[axis] <= position[axis] + COLLISION_EPSILON {
+                    limit = limit.max(tile_max[axis]);
+                }
+            }
+        }
+        
+        target[axis] = limit;
+        target
+    }
+    
+    // 位置所在瓦片的遭遇地形
+    pub fn encounter_zone_at(&self, position: Vec2) -> Option<EncounterZone> {
+        let tile = (position / self.tile_size).floor();
+        let collision = self.collision_map.tiles.get(&(tile.x as i32, tile.y as i32))?;
+        EncounterZone::from_collision(collision.collision_type)
+    }
+    
+    // 在草丛或水面行走时每步调用，按地图的天气覆盖判定
+    pub fn roll_encounter(&self, position: Vec2, rng: &mut fastrand::Rng) -> Option<(SpeciesId, u8)> {
+        let conditions = EncounterConditions {
+            time_of_day: None,
+            weather: self.weather_override,
+        };
+        self.roll_encounter_with(position, &conditions, rng)
+    }
+    
+    // 按给定的时间和天气判定遭遇，返回 (种类, 等级)
+    pub fn roll_encounter_with(
+        &self,
+        position: Vec2,
+        conditions: &EncounterConditions,
+        rng: &mut fastrand::Rng,
+    ) -> Option<(SpeciesId, u8)> {
+        let zone = self.encounter_zone_at(position)?;
+        let table = self.encounter_tables.iter()
+            .find(|table| table.zone == zone && table.contains(position))?;
+        
+        let encounter = table.roll(conditions, rng);
+        if let Some((species, level)) = encounter {
+            debug!("野生遭遇: 种类={} 等级={} 地形={:?}", species, level, zone);
+        }
+        encounter
+    }
+    
     // 添加传送点
     pub fn add_warp_point(&mut self, name: String, warp: WarpPoint) {
         self.warp_points.insert(name.clone(), warp);
@@ -555,6 +768,7 @@ impl MapManager {
             chunk_unload_radius: 1500.0,
             max_chunks_per_frame: 4,
             tilesets: HashMap::new(),
+            view_size: Vec2::new(800.0, 600.0),
             frustum_culling: true,
             occlusion_culling: false,
             batch_rendering: true,
@@ -646,13 +860,16 @@ impl MapManager {
             // 清除背景
             renderer.clear(map.background_color)?;
             
-            // 按层级顺序渲染
-            for layer in &map.layers {
-                if !layer.visible {
-                    continue;
+            // 按层级顺序批量渲染可见瓦片
+            let view_size = if self.frustum_culling { self.view_size } else { map.size * 2.0 };
+            for batch in map.visible_tile_batches(camera_position, view_size) {
+                let layer = &map.layers[batch.layer_index];
+                for instance in &batch.tiles {
+                    self.render_tile(renderer, instance.tile, instance.position, instance.size, layer)?;
                 }
                 
-                self.render_layer(renderer, layer, map, camera_position)?;
+                self.tiles_rendered += batch.tiles.len() as u32;
+                self.draw_calls += 1;
             }
             
             // 渲染动态对象
@@ -678,6 +895,11 @@ impl MapManager {
         Ok(())
     }
     
+    // 设置视口尺寸，用于瓦片剔除
+    pub fn set_view_size(&mut self, view_size: Vec2) {
+        self.view_size = view_size;
+    }
+    
     // 获取当前地图
     pub fn get_current_map(&self) -> Option<&GameMap> {
         self.current_map.as_ref()
@@ -741,36 +963,6 @@ impl MapManager {
         Ok(())
     }
     
-    fn render_layer(
-        &mut self,
-        renderer: &mut Renderer2D,
-        layer: &MapLayer,
-        map: &GameMap,
-        camera_position: Vec2,
-    ) -> Result<(), GameError> {
-        // 计算视差偏移
-        let parallax_offset = camera_position * (Vec2::ONE - layer.parallax_factor);
-        
-        // 渲染可见的瓦片
-        for (&(x, y), &tile_data) in &layer.tiles {
-            let tile_position = Vec2::new(
-                x as f32 * map.tile_size.x - parallax_offset.x,
-                y as f32 * map.tile_size.y - parallax_offset.y,
-            );
-            
-            // 视锥剔除
-            if self.frustum_culling && !self.is_tile_visible(tile_position, map.tile_size, camera_position) {
-                continue;
-            }
-            
-            self.render_tile(renderer, tile_data, tile_position, map.tile_size, layer)?;
-            self.tiles_rendered += 1;
-        }
-        
-        self.draw_calls += 1;
-        Ok(())
-    }
-    
     fn render_tile(
         &self,
         renderer: &mut Renderer2D,
@@ -835,21 +1027,7 @@ impl MapManager {
         
         Ok(())
     }
-    
-    fn is_tile_visible(&self, tile_position: Vec2, tile_size: Vec2, camera_position: Vec2) -> bool {
-        // 简化的视锥剔除
-        let screen_bounds = (
-            Vec2::new(-400.0, -300.0), // 假设屏幕尺寸800x600
-            Vec2::new(400.0, 300.0)
-        );
-        
-        let relative_pos = tile_position - camera_position;
-        
-        relative_pos.x + tile_size.x >= screen_bounds.0.x &&
-        relative_pos.x <= screen_bounds.1.x &&
-        relative_pos.y + tile_size.y >= screen_bounds.0.y &&
-        relative_pos.y <= screen_bounds.1.y
-    }
+
 }
 
 // 默认实现
@@ -957,4 +1135,91 @@ mod tests {
         assert_eq!(chunk_x, 1); // 1000 / 512 = ~1
         assert_eq!(chunk_y, 3); // 2000 / 512 = ~3
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_load_tmx_fixture() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/maps/route_small.tmx");
+        let map = GameMap::load_tmx(&path).unwrap();
+        
+        assert_eq!(map.name, "route_small");
+        assert_eq!(map.size, Vec2::new(64.0, 48.0));
+        assert_eq!(map.tile_size, Vec2::new(16.0, 16.0));
+        assert_eq!(map.music.as_deref(), Some("route_1.ogg"));
+        
+        // 图层和瓦片数量
+        let layer_summary: Vec<(&str, LayerType, usize)> = map.layers.iter()
+            .map(|layer| (layer.name.as_str(), layer.layer_type, layer.tiles.len()))
+            .collect();
+        assert_eq!(layer_summary, vec![
+            ("Ground", LayerType::Terrain, 12),
+            ("Decoration", LayerType::Decoration, 2),
+            ("Collision", LayerType::Collision, 4),
+        ]);
+        assert!(!map.layers[2].visible);
+        assert!(map.get_tile(1, 2, 1).unwrap().flip_x);
+        
+        // 瓦片集
+        assert_eq!(map.tilesets.len(), 1);
+        assert_eq!(map.layers[0].tileset.as_deref(), Some("overworld"));
+        assert!(map.tile_properties(4).unwrap().solid);
+        assert_eq!(map.tilesets[0].tile_uv_rect(4), Some(Vec4::new(0.5, 0.5, 1.0, 1.0)));
+        
+        // 碰撞层的4个瓦片加上地面层中标记为solid的瓦片
+        assert_eq!(map.collision_map.tiles.len(), 5);
+        assert!(map.collision_map.tiles[&(3, 2)].solid);
+        
+        // 对象组
+        assert_eq!(map.spawn_points["player_start"], Vec3::new(24.0, 0.0, 40.0));
+        let warp = &map.warp_points["house_door"];
+        assert_eq!(warp.target_map, Some(2));
+        assert_eq!(warp.target_position, Vec3::new(80.0, 0.0, 120.0));
+        assert_eq!(map.dynamic_objects.len(), 1);
+        let sign = map.dynamic_objects.values().next().unwrap();
+        assert_eq!(sign.object_type, "sign");
+        assert_eq!(sign.properties["name"], "route_sign");
+        
+        // 可见瓦片按图层分批，隐藏的碰撞层不参与渲染
+        let batches = map.visible_tile_batches(map.size * 0.5, map.size);
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches.iter().map(|batch| batch.tiles.len()).sum::<usize>(), 14);
+        
+        let corner_batches = map.visible_tile_batches(Vec2::ZERO, Vec2::splat(32.0));
+        assert_eq!(corner_batches[0].tiles.len(), 1);
+    }
+    
+    #[test]
+    fn test_movement_blocked_by_solid_tile() {
+        let mut map = GameMap::new(1, "测试".to_string(), Vec2::new(1000.0, 1000.0));
+        let wall = CollisionTile { collision_type: CollisionType::Solid, solid: true, ..Default::default() };
+        map.set_collision(2, 0, wall);
+        
+        assert!(map.is_solid(2, 0));
+        assert!(!map.is_solid(1, 0));
+        
+        let size = Vec2::new(16.0, 16.0);
+        // 停在墙前，包围盒右边缘贴住瓦片左边缘
+        assert_eq!(map.resolve_movement(Vec2::new(20.0, 8.0), Vec2::new(60.0, 8.0), size), Vec2::new(48.0, 8.0));
+        // 一帧内移动很远也不会穿墙
+        assert_eq!(map.resolve_movement(Vec2::new(20.0, 8.0), Vec2::new(300.0, 8.0), size), Vec2::new(48.0, 8.0));
+        // 离开墙的方向不受阻挡
+        assert_eq!(map.resolve_movement(Vec2::new(48.0, 8.0), Vec2::new(10.0, 8.0), size), Vec2::new(10.0, 8.0));
+    }
+    
+    #[test]
+    fn test_movement_slides_along_wall() {
+        let mut map = GameMap::new(1, "测试".to_string(), Vec2::new(1000.0, 1000.0));
+        let wall = CollisionTile { collision_type: CollisionType::Solid, solid: true, ..Default::default() };
+        for y in 0..5 {
+            map.set_collision(2, y, wall);
+        }
+        
+        // 斜向撞墙：水平分量被挡住，垂直分量保留
+        let size = Vec2::new(16.0, 16.0);
+        let result = map.resolve_movement(Vec2::new(40.0, 40.0), Vec2::new(60.0, 100.0), size);
+        assert_eq!(result, Vec2::new(48.0, 100.0));
+        
+        // 贴墙时沿墙移动不会被卡住
+        let result = map.resolve_movement(Vec2::new(48.0, 100.0), Vec2::new(48.0, 20.0), size);
+        assert_eq!(result, Vec2::new(48.0, 20.0));
+    }
+}
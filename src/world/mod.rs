@@ -6,18 +6,29 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use log::{debug, warn, error};
 use crate::core::error::GameError;
+use crate::game_modes::{GameModeManager, SharedGameModes, WorldData};
 use glam::{Vec2, Vec3};
 
 pub mod map;
+pub mod tmx;
 pub mod npc;
 pub mod environment;
 pub mod events;
+pub mod encounter;
+pub mod spatial;
+pub mod diff;
 
 // 世界ID类型
 pub type WorldId = u32;
 pub type MapId = u32;
 pub type EntityId = u64;
 
+// 跟随AI默认的停止距离
+pub const FOLLOW_STOP_DISTANCE: f32 = 32.0;
+
+// 规则模式已改写过世界数据的标记，避免重复改写(如随机化)
+pub const GAME_RULES_APPLIED_FLAG: &str = "game_rules_applied";
+
 // 世界数据
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct World {
@@ -83,6 +94,7 @@ pub enum EntityComponent {
     Sprite { sprite_id: u32, animation: Option<String> },
     Collider { width: f32, height: f32, solid: bool },
     Movement { speed: f32, direction: Vec2 },
+    PathFollow { waypoints: Vec<Vec2>, next: usize, speed: f32 }, // 由npc::find_path生成的路径
     AI { behavior: String, state: HashMap<String, String> },
     Interaction { interaction_type: String, data: HashMap<String, String> },
     #[cfg(feature = "pokemon-wip")]
@@ -90,6 +102,7 @@ pub enum EntityComponent {
     #[cfg(not(feature = "pokemon-wip"))]
     Pokemon { species_id: u32, level: u8, stats: Option<crate::world::npc::PokemonStats> },
     Item { item_id: u32, quantity: u32 },
+    Trigger { trigger_id: String },     // 对应events::EventTrigger
 }
 
 // 世界时间
@@ -101,6 +114,29 @@ pub struct WorldTime {
     pub time_scale: f32,    // 时间流逝速度倍率
 }
 
+impl WorldTime {
+    pub fn time_of_day(&self) -> environment::TimeOfDay {
+        environment::TimeOfDay::from_hour(self.hour)
+    }
+    
+    // 调试用：直接设置时刻，超出范围的小时按24取模
+    pub fn set_time(&mut self, hour: u8, minute: u8) {
+        self.hour = hour % 24;
+        self.minute = minute.min(59);
+        debug!("设置世界时间: {}:{:02}", self.hour, self.minute);
+    }
+}
+
+impl World {
+    // 当前时间和天气下的遭遇条件
+    pub fn encounter_conditions(&self) -> encounter::EncounterConditions {
+        encounter::EncounterConditions {
+            time_of_day: Some(self.world_time.time_of_day()),
+            weather: Some(self.weather.current_weather),
+        }
+    }
+}
+
 // 天气系统
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WeatherSystem {
@@ -121,6 +157,21 @@ pub enum Weather {
     Sandstorm,  // 沙尘暴
 }
 
+#[cfg(feature = "pokemon-wip")]
+impl Weather {
+    // 野外天气带入战斗时对应的战斗天气，晴朗不带入
+    pub fn to_battle_weather(self) -> Option<crate::pokemon::moves::WeatherType> {
+        use crate::pokemon::moves::WeatherType;
+        match self {
+            Weather::Clear => None,
+            Weather::Rain | Weather::Storm => Some(WeatherType::Rain),
+            Weather::Snow => Some(WeatherType::Hail),
+            Weather::Fog => Some(WeatherType::Fog),
+            Weather::Sandstorm => Some(WeatherType::Sandstorm),
+        }
+    }
+}
+
 // 世界管理器
 pub struct WorldManager {
     // 当前活跃的世界
@@ -129,9 +180,18 @@ pub struct WorldManager {
     // 世界缓存
     world_cache: HashMap<WorldId, World>,
     
+    // 差异存档的基线(最近一次完整保存或从文件加载时的世界)
+    world_baselines: HashMap<WorldId, World>,
+    
     // 加载状态
     loading_maps: Vec<MapId>,
     
+    // 当前世界实体的空间索引
+    spatial_index: spatial::SpatialGrid,
+    
+    // 规则模式，野生遭遇经过它的钩子
+    game_modes: SharedGameModes,
+    
     // 更新计时器
     update_timer: f32,
     auto_save_timer: f32,
@@ -151,7 +211,10 @@ impl WorldManager {
         Self {
             current_world: None,
             world_cache: HashMap::new(),
+            world_baselines: HashMap::new(),
             loading_maps: Vec::new(),
+            spatial_index: spatial::SpatialGrid::default(),
+            game_modes: GameModeManager::global(),
             update_timer: 0.0,
             auto_save_timer: 0.0,
             auto_save_interval: 300.0, // 5分钟
@@ -200,17 +263,23 @@ impl WorldManager {
     
     // 加载世界
     pub fn load_world(&mut self, world_id: WorldId) -> Result<(), GameError> {
-        if let Some(world) = self.world_cache.get(&world_id).cloned() {
+        if let Some(mut world) = self.world_cache.get(&world_id).cloned() {
+            if self.apply_game_rules(&mut world) {
+                self.world_cache.insert(world_id, world.clone());
+            }
             self.current_world = Some(world);
+            self.rebuild_spatial_index();
             debug!("从缓存加载世界: ID={}", world_id);
             return Ok(());
         }
         
         // 从文件加载
         match self.load_world_from_file(world_id) {
-            Ok(world) => {
+            Ok(mut world) => {
+                self.apply_game_rules(&mut world);
                 self.current_world = Some(world.clone());
                 self.world_cache.insert(world_id, world);
+                self.rebuild_spatial_index();
                 debug!("从文件加载世界: ID={}", world_id);
                 Ok(())
             },
@@ -302,6 +371,7 @@ impl WorldManager {
             };
             
             world.entities.insert(entity_id, entity);
+            self.spatial_index.update(entity_id, position);
             self.total_entities_created += 1;
             
             debug!("创建实体: 类型={:?} ID={} 位置={:?}", entity_type, entity_id, position);
@@ -311,10 +381,22 @@ impl WorldManager {
         }
     }
     
+    // 创建触发区域实体，并在事件系统中注册对应的触发器
+    pub fn create_trigger(&mut self, trigger: events::EventTrigger) -> Result<EntityId, GameError> {
+        let component = EntityComponent::Trigger { trigger_id: trigger.id.clone() };
+        let entity_id = self.create_entity(EntityType::Trigger, trigger.position, vec![component])?;
+        
+        if let Some(ref mut world) = self.current_world {
+            world.events.add_trigger(trigger);
+        }
+        Ok(entity_id)
+    }
+    
     // 销毁实体
     pub fn destroy_entity(&mut self, entity_id: EntityId) -> Result<(), GameError> {
         if let Some(ref mut world) = self.current_world {
             if world.entities.remove(&entity_id).is_some() {
+                self.spatial_index.remove(entity_id);
                 debug!("销毁实体: ID={}", entity_id);
                 Ok(())
             } else {
@@ -330,7 +412,16 @@ impl WorldManager {
         self.current_world.as_ref()?.entities.get(&entity_id)
     }
     
-    // 获取实体(可变)
+    // 移动实体并同步空间索引
+    pub fn set_entity_position(&mut self, entity_id: EntityId, position: Vec3) -> Result<(), GameError> {
+        let entity = self.get_entity_mut(entity_id)
+            .ok_or_else(|| GameError::World(format!("实体不存在: {}", entity_id)))?;
+        entity.position = position;
+        self.spatial_index.update(entity_id, position);
+        Ok(())
+    }
+    
+    // 获取实体(可变)，直接修改位置后需调用set_entity_position或等待下一次update同步索引
     pub fn get_entity_mut(&mut self, entity_id: EntityId) -> Option<&mut WorldEntity> {
         self.current_world.as_mut()?.entities.get_mut(&entity_id)
     }
@@ -351,12 +442,13 @@ impl WorldManager {
     // 按位置查找实体
     pub fn find_entities_near(&self, position: Vec3, radius: f32) -> Vec<EntityId> {
         if let Some(world) = &self.current_world {
-            world.entities
-                .iter()
-                .filter(|(_, entity)| {
-                    entity.active && (entity.position - position).length() <= radius
+            self.spatial_index.query_candidates(position, radius)
+                .into_iter()
+                .filter(|id| {
+                    world.entities.get(id).map_or(false, |entity| {
+                        entity.active && (entity.position - position).length() <= radius
+                    })
                 })
-                .map(|(&id, _)| id)
                 .collect()
         } else {
             Vec::new()
@@ -371,44 +463,157 @@ impl WorldManager {
         
         if let Some(ref mut world) = self.current_world {
             // 更新世界时间
-            self.update_world_time(&mut world.world_time, delta_time);
+            Self::update_world_time(&mut world.world_time, delta_time);
             
             // 更新天气
-            self.update_weather(&mut world.weather, delta_time);
+            Self::update_weather(&mut world.weather, delta_time);
             
-            // 更新环境
+            // 更新环境，光照以世界时间为准
             world.environment.update(delta_time)?;
+            world.environment.set_time_of_day(world.world_time.hour, world.world_time.minute);
             
             // 更新事件系统
             world.events.update(delta_time)?;
             
-            // 更新活跃实体
-            for entity in world.entities.values_mut() {
-                if entity.active {
-                    self.update_entity(entity, delta_time)?;
-                }
+            // 更新活跃实体：AI读取本帧开始时的位置快照，之后统一积分移动
+            let positions: HashMap<EntityId, Vec3> = world.entities.values()
+                .filter(|entity| entity.active)
+                .map(|entity| (entity.id, entity.position))
+                .collect();
+            for entity in world.entities.values_mut().filter(|entity| entity.active) {
+                Self::update_entity_ai(entity, &positions);
+            }
+            for entity in world.entities.values_mut().filter(|entity| entity.active) {
+                Self::integrate_movement(entity, delta_time);
+            }
+            
+            // 同步移动后的实体位置
+            for entity in world.entities.values() {
+                self.spatial_index.update(entity.id, entity.position);
+            }
+            
+            // 玩家移动后检查触发区域
+            let players: Vec<(EntityId, Vec3)> = world.entities.values()
+                .filter(|entity| entity.active && entity.entity_type == EntityType::Player)
+                .map(|entity| (entity.id, entity.position))
+                .collect();
+            for (player_id, position) in players {
+                world.events.check_triggers(position, events::EventSource::Player(player_id));
             }
         }
         
         // 自动保存检查
         if self.auto_save_timer >= self.auto_save_interval {
-            self.save_current_world()?;
+            self.autosave_current_world()?;
             self.auto_save_timer = 0.0;
         }
         
         Ok(())
     }
     
-    // 保存当前世界
+    pub fn set_game_modes(&mut self, game_modes: SharedGameModes) {
+        self.game_modes = game_modes;
+    }
+    
+    // 当前地图对应的道路名，规则模式按它记录遭遇和捕获
+    pub fn current_route(&self) -> Option<&str> {
+        let world = self.current_world.as_ref()?;
+        world.maps.get(&world.current_map?).map(|map| map.name.as_str())
+    }
+    
+    // 在当前地图上按世界时间和天气判定野生遭遇，出现的种类交给规则模式处理
+    pub fn roll_encounter(&self, position: Vec2, rng: &mut fastrand::Rng) -> Option<(encounter::SpeciesId, u8)> {
+        let world = self.current_world.as_ref()?;
+        let map = world.maps.get(&world.current_map?)?;
+        let (species, level) = map.roll_encounter_with(position, &world.encounter_conditions(), rng)?;
+        let species = self.game_modes.lock().unwrap().on_encounter(&map.name, species);
+        Some((species, level))
+    }
+    
+    // 保存当前世界：写入完整世界作为新基线，旧的差异随之作废
     pub fn save_current_world(&mut self) -> Result<(), GameError> {
         if let Some(ref world) = self.current_world {
-            self.save_world_to_file(world)?;
-            debug!("保存世界: {} (ID: {})", world.name, world.id);
+            Self::save_world_to_file(world)?;
+            Self::remove_world_diff_file(world.id);
+            self.world_baselines.insert(world.id, world.clone());
+            debug!("保存世界基线: {} (ID: {})", world.name, world.id);
         }
         Ok(())
     }
     
+    // 自动保存：只写相对基线的差异，还没有基线时先做一次完整保存
+    pub fn autosave_current_world(&mut self) -> Result<(), GameError> {
+        let Some(ref world) = self.current_world else {
+            return Ok(());
+        };
+        match self.world_baselines.get(&world.id) {
+            Some(baseline) => {
+                let diff = diff::WorldDiff::between(baseline, world);
+                Self::save_world_diff_to_file(&diff)?;
+                debug!("保存世界差异: {} (ID: {})", world.name, world.id);
+                Ok(())
+            },
+            None => self.save_current_world(),
+        }
+    }
+    
+    // 把各地图的遭遇表交给规则模式改写，返回世界是否被改动；每个世界只改写一次
+    fn apply_game_rules(&self, world: &mut World) -> bool {
+        if world.world_flags.get(GAME_RULES_APPLIED_FLAG).copied().unwrap_or(false) {
+            return false;
+        }
+        
+        // 按地图ID排序，保证同一种子得到相同的结果
+        let mut map_ids: Vec<MapId> = world.maps.keys().copied().collect();
+        map_ids.sort_unstable();
+        
+        let mut data = WorldData::default();
+        for map in map_ids.iter().filter_map(|id| world.maps.get(id)) {
+            let species = map.encounter_tables.iter()
+                .flat_map(|table| table.entries.iter().map(|entry| entry.species));
+            data.wild_encounters.entry(map.name.clone()).or_default().extend(species);
+        }
+        data.wild_encounters.retain(|_, species| !species.is_empty());
+        
+        let original = data.clone();
+        // 规则无法满足时(如秘传学习者放不下)保持原世界，避免卡关
+        if let Err(e) = self.game_modes.lock().unwrap().on_world_load(&mut data) {
+            warn!("规则模式无法改写世界 '{}': {}", world.name, e);
+            return false;
+        }
+        if data == original {
+            return false;
+        }
+        
+        // 按收集时的顺序写回，多出的种类(规则追加的)没有对应的条目，忽略
+        for map_id in &map_ids {
+            let Some(map) = world.maps.get_mut(map_id) else { continue };
+            let Some(species) = data.wild_encounters.get_mut(&map.name) else { continue };
+            let mut species = std::mem::take(species).into_iter();
+            for entry in map.encounter_tables.iter_mut().flat_map(|table| table.entries.iter_mut()) {
+                match species.next() {
+                    Some(new_species) => entry.species = new_species,
+                    None => break,
+                }
+            }
+            data.wild_encounters.insert(map.name.clone(), species.collect());
+        }
+        
+        world.world_flags.insert(GAME_RULES_APPLIED_FLAG.to_string(), true);
+        debug!("规则模式改写了世界 '{}' 的遭遇表", world.name);
+        true
+    }
+    
     // 私有方法
+    fn rebuild_spatial_index(&mut self) {
+        self.spatial_index.clear();
+        if let Some(world) = &self.current_world {
+            for entity in world.entities.values() {
+                self.spatial_index.update(entity.id, entity.position);
+            }
+        }
+    }
+    
     fn generate_world_id(&self) -> WorldId {
         use std::time::{SystemTime, UNIX_EPOCH};
         let timestamp = SystemTime::now()
@@ -418,7 +623,7 @@ impl WorldManager {
         timestamp
     }
     
-    fn update_world_time(&self, world_time: &mut WorldTime, delta_time: f32) {
+    fn update_world_time(world_time: &mut WorldTime, delta_time: f32) {
         let minutes_passed = (delta_time * world_time.time_scale) / 60.0;
         let total_minutes = world_time.minute as f32 + minutes_passed;
         
@@ -435,7 +640,7 @@ impl WorldManager {
         }
     }
     
-    fn update_weather(&self, weather: &mut WeatherSystem, delta_time: f32) {
+    fn update_weather(weather: &mut WeatherSystem, delta_time: f32) {
         weather.weather_duration -= delta_time;
         
         if weather.weather_duration <= 0.0 {
@@ -458,49 +663,98 @@ impl WorldManager {
         }
     }
     
-    fn update_entity(&self, entity: &mut WorldEntity, delta_time: f32) -> Result<(), GameError> {
-        // 更新实体组件
-        for (component_name, component) in &mut entity.components {
+    // AI阶段：只修改实体自身的组件，其它实体的位置从快照读取
+    fn update_entity_ai(entity: &mut WorldEntity, positions: &HashMap<EntityId, Vec3>) {
+        let position = Vec2::new(entity.position.x, entity.position.z);
+        let mut desired_direction = None;
+        
+        for component in entity.components.values() {
+            if let EntityComponent::AI { behavior, state } = component {
+                // 跟随: state["target"]为目标实体ID，进入stop_distance后停下
+                if behavior == "follow" {
+                    let target = state.get("target")
+                        .and_then(|id| id.parse::<EntityId>().ok())
+                        .and_then(|id| positions.get(&id));
+                    let stop_distance = state.get("stop_distance")
+                        .and_then(|distance| distance.parse::<f32>().ok())
+                        .unwrap_or(FOLLOW_STOP_DISTANCE);
+                    
+                    if let Some(target) = target {
+                        let offset = Vec2::new(target.x, target.z) - position;
+                        desired_direction = Some(if offset.length() > stop_distance {
+                            offset.normalize_or_zero()
+                        } else {
+                            Vec2::ZERO
+                        });
+                    }
+                }
+            }
+        }
+        
+        if let Some(desired_direction) = desired_direction {
+            for component in entity.components.values_mut() {
+                if let EntityComponent::Movement { direction, .. } = component {
+                    *direction = desired_direction;
+                }
+            }
+        }
+    }
+    
+    // 移动阶段：按速度和路径积分位置
+    fn integrate_movement(entity: &mut WorldEntity, delta_time: f32) {
+        for component in entity.components.values_mut() {
             match component {
                 EntityComponent::Movement { speed, direction } => {
                     let movement = *direction * *speed * delta_time;
                     entity.position.x += movement.x;
                     entity.position.z += movement.y;
                 },
-                EntityComponent::AI { behavior, state } => {
-                    // 简化的AI更新
-                    if behavior == "random_walk" {
-                        // 随机移动逻辑
-                    }
+                EntityComponent::PathFollow { waypoints, next, speed } => {
+                    let position = Vec2::new(entity.position.x, entity.position.z);
+                    let (position, reached) = npc::advance_along_path(position, waypoints, *next, *speed * delta_time);
+                    entity.position.x = position.x;
+                    entity.position.z = position.y;
+                    *next = reached;
                 },
                 _ => {}
             }
         }
-        
-        Ok(())
     }
     
-    fn load_world_from_file(&self, world_id: WorldId) -> Result<World, GameError> {
-        let filename = format!("worlds/world_{}.json", world_id);
+    fn world_file_path(world_id: WorldId) -> String {
+        format!("worlds/world_{}.json", world_id)
+    }
+    
+    fn world_diff_file_path(world_id: WorldId) -> String {
+        format!("worlds/world_{}.diff.json", world_id)
+    }
+    
+    // 读取基线，存在差异文件时在基线上还原
+    fn load_world_from_file(&mut self, world_id: WorldId) -> Result<World, GameError> {
+        let data = std::fs::read_to_string(Self::world_file_path(world_id))
+            .map_err(|e| GameError::World(format!("读取世界文件失败: {}", e)))?;
+        let baseline: World = serde_json::from_str(&data)
+            .map_err(|e| GameError::World(format!("反序列化世界失败: {}", e)))?;
         
-        match std::fs::read_to_string(&filename) {
+        let world = match std::fs::read_to_string(Self::world_diff_file_path(world_id)) {
             Ok(data) => {
-                match serde_json::from_str::<World>(&data) {
-                    Ok(world) => Ok(world),
-                    Err(e) => Err(GameError::World(format!("反序列化世界失败: {}", e))),
-                }
+                let diff: diff::WorldDiff = serde_json::from_str(&data)
+                    .map_err(|e| GameError::World(format!("反序列化世界差异失败: {}", e)))?;
+                diff.apply(&baseline)
             },
-            Err(e) => Err(GameError::World(format!("读取世界文件失败: {}", e))),
-        }
+            Err(_) => baseline.clone(),
+        };
+        
+        self.world_baselines.insert(world_id, baseline);
+        Ok(world)
     }
     
-    fn save_world_to_file(&self, world: &World) -> Result<(), GameError> {
+    fn save_world_to_file(world: &World) -> Result<(), GameError> {
         std::fs::create_dir_all("worlds").ok();
-        let filename = format!("worlds/world_{}.json", world.id);
         
         match serde_json::to_string_pretty(world) {
             Ok(data) => {
-                match std::fs::write(&filename, data) {
+                match std::fs::write(Self::world_file_path(world.id), data) {
                     Ok(_) => Ok(()),
                     Err(e) => Err(GameError::World(format!("写入世界文件失败: {}", e))),
                 }
@@ -509,6 +763,24 @@ impl WorldManager {
         }
     }
     
+    fn save_world_diff_to_file(diff: &diff::WorldDiff) -> Result<(), GameError> {
+        std::fs::create_dir_all("worlds").ok();
+        
+        let data = serde_json::to_string(diff)
+            .map_err(|e| GameError::World(format!("序列化世界差异失败: {}", e)))?;
+        std::fs::write(Self::world_diff_file_path(diff.world_id), data)
+            .map_err(|e| GameError::World(format!("写入世界差异文件失败: {}", e)))
+    }
+    
+    // 写入新基线后旧的差异不再适用
+    fn remove_world_diff_file(world_id: WorldId) {
+        if let Err(e) = std::fs::remove_file(Self::world_diff_file_path(world_id)) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("删除世界差异文件失败: {}", e);
+            }
+        }
+    }
+    
     fn load_map_from_file(&self, map_id: MapId) -> Result<map::GameMap, GameError> {
         // 简化实现
         Ok(map::GameMap::new(
@@ -606,4 +878,176 @@ mod tests {
         assert_eq!(entity.entity_type, EntityType::NPC);
         assert_eq!(entity.position, Vec3::new(100.0, 0.0, 200.0));
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_time_of_day_buckets() {
+        use environment::TimeOfDay;
+        
+        let buckets = [
+            (5, TimeOfDay::Morning), (9, TimeOfDay::Morning),
+            (10, TimeOfDay::Day), (16, TimeOfDay::Day),
+            (17, TimeOfDay::Evening), (19, TimeOfDay::Evening),
+            (20, TimeOfDay::Night), (23, TimeOfDay::Night), (0, TimeOfDay::Night), (4, TimeOfDay::Night),
+        ];
+        for (hour, expected) in buckets {
+            assert_eq!(TimeOfDay::from_hour(hour), expected, "{}点", hour);
+        }
+    }
+    
+    #[test]
+    fn test_world_time_wraps_at_midnight() {
+        let mut time = WorldTime { day: 1, hour: 12, minute: 0, time_scale: 1.0 };
+        
+        time.set_time(23, 59);
+        assert_eq!(time.time_of_day(), environment::TimeOfDay::Night);
+        
+        // 经过1分钟跨过午夜
+        WorldManager::update_world_time(&mut time, 60.0);
+        assert_eq!((time.day, time.hour, time.minute), (2, 0, 0));
+        assert_eq!(time.time_of_day(), environment::TimeOfDay::Night);
+        
+        time.set_time(29, 75);
+        assert_eq!((time.hour, time.minute), (5, 59));
+        assert_eq!(time.time_of_day(), environment::TimeOfDay::Morning);
+    }
+    
+    #[test]
+    fn test_indexed_query_matches_linear_scan() {
+        let mut manager = WorldManager::new();
+        let world_id = manager.create_world("测试".to_string(), "测试".to_string()).unwrap();
+        manager.load_world(world_id).unwrap();
+        
+        let mut rng = fastrand::Rng::with_seed(42);
+        let random_position = |rng: &mut fastrand::Rng| {
+            Vec3::new(rng.f32() * 1000.0 - 500.0, rng.f32() * 10.0, rng.f32() * 1000.0 - 500.0)
+        };
+        
+        let mut ids = Vec::new();
+        for _ in 0..300 {
+            let position = random_position(&mut rng);
+            ids.push(manager.create_entity(EntityType::NPC, position, Vec::new()).unwrap());
+        }
+        // 移动和销毁一部分实体
+        for &id in ids.iter().step_by(7) {
+            let position = random_position(&mut rng);
+            manager.set_entity_position(id, position).unwrap();
+        }
+        for &id in ids.iter().step_by(11) {
+            manager.destroy_entity(id).unwrap();
+        }
+        
+        let world = manager.get_current_world().unwrap();
+        for _ in 0..50 {
+            let center = random_position(&mut rng);
+            let radius = rng.f32() * 200.0;
+            
+            let mut expected: Vec<EntityId> = world.entities.values()
+                .filter(|entity| entity.active && (entity.position - center).length() <= radius)
+                .map(|entity| entity.id)
+                .collect();
+            let mut found = manager.find_entities_near(center, radius);
+            expected.sort_unstable();
+            found.sort_unstable();
+            assert_eq!(found, expected);
+        }
+    }
+    
+    #[test]
+    fn test_movement_integration_is_exact() {
+        let mut manager = WorldManager::new();
+        let world_id = manager.create_world("测试".to_string(), "测试".to_string()).unwrap();
+        manager.load_world(world_id).unwrap();
+        
+        let start = Vec3::new(100.0, 0.0, 200.0);
+        let (speed, direction, delta_time) = (4.0, Vec2::new(1.0, -0.5), 0.25);
+        let walker = manager.create_entity(
+            EntityType::NPC,
+            start,
+            vec![EntityComponent::Movement { speed, direction }],
+        ).unwrap();
+        let follower = manager.create_entity(
+            EntityType::NPC,
+            Vec3::new(0.0, 0.0, 200.0),
+            vec![
+                EntityComponent::Movement { speed: 10.0, direction: Vec2::ZERO },
+                EntityComponent::AI {
+                    behavior: "follow".to_string(),
+                    state: HashMap::from([("target".to_string(), walker.to_string())]),
+                },
+            ],
+        ).unwrap();
+        
+        manager.update(delta_time).unwrap();
+        
+        let movement = direction * speed * delta_time;
+        let expected = start + Vec3::new(movement.x, 0.0, movement.y);
+        assert_eq!(manager.get_entity(walker).unwrap().position, expected);
+        
+        // 跟随者朝目标本帧开始时的位置移动
+        let follower_position = manager.get_entity(follower).unwrap().position;
+        assert_eq!(follower_position, Vec3::new(10.0 * delta_time, 0.0, 200.0));
+    }
+    
+    // 在当前世界放一张全是草丛、必定遭遇单一种类的地图
+    fn world_with_route(manager: &mut WorldManager, route: &str, species: encounter::SpeciesId) {
+        use map::{CollisionTile, CollisionType, GameMap};
+        
+        let world_id = manager.create_world("测试".to_string(), "测试".to_string()).unwrap();
+        let mut map = GameMap::new(1, route.to_string(), Vec2::new(320.0, 320.0));
+        map.set_collision(1, 1, CollisionTile { collision_type: CollisionType::Grass, ..Default::default() });
+        let mut table = encounter::EncounterTable::new(encounter::EncounterZone::Grass, 1.0);
+        table.add_entry(encounter::EncounterEntry::new(species, 3, 3, 1));
+        map.encounter_tables.push(table);
+        
+        let world = manager.world_cache.get_mut(&world_id).unwrap();
+        world.maps.insert(1, map);
+        world.current_map = Some(1);
+        manager.load_world(world_id).unwrap();
+    }
+    
+    #[test]
+    fn test_encounter_goes_through_game_mode() {
+        let game_modes = GameModeManager::shared();
+        game_modes.lock().unwrap().set_mode(crate::game_modes::Nuzlocke::new());
+        let mut manager = WorldManager::new();
+        manager.set_game_modes(game_modes.clone());
+        world_with_route(&mut manager, "route_1", 16);
+        assert_eq!(manager.current_route(), Some("route_1"));
+        
+        let mut rng = fastrand::Rng::with_seed(1);
+        assert_eq!(manager.roll_encounter(Vec2::new(40.0, 40.0), &mut rng), Some((16, 3)));
+        
+        // Nuzlocke记下了这条道路的首次遭遇
+        let game_modes = game_modes.lock().unwrap();
+        game_modes.check_capture("route_1", 16).unwrap();
+        assert!(game_modes.check_capture("route_1", 19).is_err());
+    }
+    
+    #[test]
+    fn test_randomizer_applied_on_world_load() {
+        use crate::game_modes::{Randomizer, RandomizerOptions};
+        
+        let expected = Randomizer::new(99, RandomizerOptions::default()).remap_species(16);
+        assert_ne!(expected, 16);
+        
+        let game_modes = GameModeManager::shared();
+        game_modes.lock().unwrap().set_mode(Randomizer::new(99, RandomizerOptions::default()));
+        let mut manager = WorldManager::new();
+        manager.set_game_modes(game_modes.clone());
+        world_with_route(&mut manager, "route_1", 16);
+        
+        let mut rng = fastrand::Rng::with_seed(1);
+        assert_eq!(manager.roll_encounter(Vec2::new(40.0, 40.0), &mut rng), Some((expected, 3)));
+        
+        // 再次加载同一个世界不会重复随机化
+        let world_id = manager.get_current_world().unwrap().id;
+        manager.load_world(world_id).unwrap();
+        assert_eq!(manager.roll_encounter(Vec2::new(40.0, 40.0), &mut rng), Some((expected, 3)));
+        
+        // 标准规则下遭遇表不变
+        let mut standard = WorldManager::new();
+        standard.set_game_modes(GameModeManager::shared());
+        world_with_route(&mut standard, "route_1", 16);
+        assert_eq!(standard.roll_encounter(Vec2::new(40.0, 40.0), &mut rng), Some((16, 3)));
+    }
+}
@@ -3,9 +3,11 @@
 // 设计原则：行为树AI、对话系统、状态管理、任务分发
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use log::{debug, warn, error};
 use crate::core::error::GameError;
+use super::map::GameMap;
 #[cfg(feature = "pokemon-wip")]
 use crate::pokemon::stats::PokemonStats;
 
@@ -25,6 +27,9 @@ use glam::{Vec2, Vec3};
 pub type NPCId = u64;
 pub type DialogueId = u32;
 
+// 寻路展开节点上限，无解时避免遍历整张大地图
+const MAX_PATH_SEARCH_NODES: usize = 10_000;
+
 // NPC数据
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NPC {
@@ -45,6 +50,8 @@ pub struct NPC {
     pub interaction_radius: f32,
     pub sight_range: f32,
     pub hearing_range: f32,
+    #[serde(default)]
+    pub path: Vec<Vec2>,                // 剩余路点(地图平面坐标)，非空时优先沿路径行走
     
     // 对话系统
     pub dialogue_tree: Option<DialogueTree>,
@@ -469,6 +476,7 @@ impl NPCManager {
             interaction_radius: 64.0,
             sight_range: 128.0,
             hearing_range: 96.0,
+            path: Vec::new(),
             dialogue_tree: None,
             current_dialogue: None,
             dialogue_history: Vec::new(),
@@ -516,6 +524,25 @@ impl NPCManager {
         self.npcs.get_mut(&npc_id)
     }
     
+    // 让NPC沿A*路径走向目标，无法到达时返回false
+    pub fn walk_to(&mut self, npc_id: NPCId, map: &GameMap, goal: Vec2) -> Result<bool, GameError> {
+        let npc = self.npcs.get_mut(&npc_id)
+            .ok_or_else(|| GameError::World(format!("NPC不存在: {}", npc_id)))?;
+        
+        match find_path(map, Vec2::new(npc.position.x, npc.position.z), goal) {
+            Some(path) => {
+                debug!("NPC '{}' 开始寻路，路点数={}", npc.name, path.len());
+                npc.path = path;
+                Ok(true)
+            },
+            None => {
+                debug!("NPC '{}' 无法到达目标 {:?}", npc.name, goal);
+                npc.path.clear();
+                Ok(false)
+            }
+        }
+    }
+    
     // 开始对话
     pub fn start_dialogue(&mut self, npc_id: NPCId, player_id: u64) -> Result<Option<DialogueNode>, GameError> {
         if let Some(npc) = self.npcs.get_mut(&npc_id) {
@@ -766,6 +793,21 @@ impl NPCManager {
             // 检查玩家距离
             let distance_to_player = (npc.position - player_position).length();
             
+            // 有路径时沿路径行走，暂停其它移动行为
+            if !npc.path.is_empty() {
+                let position = Vec2::new(npc.position.x, npc.position.z);
+                let (new_position, reached) = advance_along_path(position, &npc.path, 0, npc.movement_pattern.speed * delta_time);
+                if new_position != position {
+                    npc.facing_direction = (new_position - position).normalize();
+                }
+                npc.position.x = new_position.x;
+                npc.position.z = new_position.y;
+                npc.path.drain(..reached);
+                
+                Self::update_npc_mood(npc, distance_to_player);
+                return Ok(());
+            }
+            
             // 根据AI行为更新NPC
             match &npc.ai_behavior {
                 AIBehavior::Wander { radius, speed } => {
@@ -800,13 +842,13 @@ impl NPCManager {
             }
             
             // 更新心情
-            self.update_npc_mood(npc, distance_to_player);
+            Self::update_npc_mood(npc, distance_to_player);
         }
         
         Ok(())
     }
     
-    fn update_npc_mood(&self, npc: &mut NPC, distance_to_player: f32) {
+    fn update_npc_mood(npc: &mut NPC, distance_to_player: f32) {
         // 根据与玩家的关系和距离更新心情
         if npc.relationship_with_player > 50 {
             npc.mood = NPCMood::Friendly;
@@ -829,6 +871,102 @@ impl NPCManager {
     }
 }
 
+// A*寻路，在碰撞网格上四方向搜索，start/goal为地图平面坐标
+// 返回从下一个瓦片到目标瓦片的瓦片中心点，已在目标瓦片时返回空路径，无法到达时返回None
+pub fn find_path(map: &GameMap, start: Vec2, goal: Vec2) -> Option<Vec<Vec2>> {
+    let to_tile = |position: Vec2| {
+        let tile = (position / map.tile_size).floor();
+        (tile.x as i32, tile.y as i32)
+    };
+    let start_tile = to_tile(start);
+    let goal_tile = to_tile(goal);
+    
+    let grid_size = (map.size / map.tile_size).ceil();
+    let in_bounds = |(x, y): (i32, i32)| x >= 0 && y >= 0 && x < grid_size.x as i32 && y < grid_size.y as i32;
+    if !in_bounds(goal_tile) || map.is_solid(goal_tile.0, goal_tile.1) {
+        return None;
+    }
+    if start_tile == goal_tile {
+        return Some(Vec::new());
+    }
+    
+    let heuristic = |(x, y): (i32, i32)| (x - goal_tile.0).unsigned_abs() + (y - goal_tile.1).unsigned_abs();
+    
+    // (f, h, 瓦片) 最小堆，f相同时优先更接近目标的节点，结果确定可复现
+    let mut open = BinaryHeap::new();
+    let mut closed = HashSet::new();
+    let mut came_from: HashMap<(i32, i32), (i32, i32)> = HashMap::new();
+    let mut costs: HashMap<(i32, i32), u32> = HashMap::new();
+    
+    costs.insert(start_tile, 0);
+    open.push(Reverse((heuristic(start_tile), heuristic(start_tile), start_tile)));
+    
+    while let Some(Reverse((_, _, current))) = open.pop() {
+        if current == goal_tile {
+            let mut tiles = vec![current];
+            let mut tile = current;
+            while let Some(&previous) = came_from.get(&tile) {
+                if previous == start_tile {
+                    break;
+                }
+                tiles.push(previous);
+                tile = previous;
+            }
+            
+            return Some(tiles.into_iter().rev()
+                .map(|(x, y)| (Vec2::new(x as f32, y as f32) + 0.5) * map.tile_size)
+                .collect());
+        }
+        
+        if !closed.insert(current) {
+            continue;
+        }
+        if closed.len() > MAX_PATH_SEARCH_NODES {
+            warn!("寻路超过节点上限: {:?} -> {:?}", start_tile, goal_tile);
+            return None;
+        }
+        
+        let current_cost = costs[&current];
+        for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+            let next = (current.0 + dx, current.1 + dy);
+            if !in_bounds(next) || closed.contains(&next) || map.is_solid(next.0, next.1) {
+                continue;
+            }
+            
+            let cost = current_cost + 1;
+            if costs.get(&next).map_or(true, |&known| cost < known) {
+                costs.insert(next, cost);
+                came_from.insert(next, current);
+                open.push(Reverse((cost + heuristic(next), heuristic(next), next)));
+            }
+        }
+    }
+    
+    None
+}
+
+// 沿路点移动指定距离，返回新位置和下一个未到达路点的下标
+pub fn advance_along_path(position: Vec2, waypoints: &[Vec2], next: usize, distance: f32) -> (Vec2, usize) {
+    let mut position = position;
+    let mut next = next;
+    let mut remaining = distance.max(0.0);
+    
+    while let Some(&target) = waypoints.get(next) {
+        let offset = target - position;
+        let length = offset.length();
+        if length <= remaining {
+            position = target;
+            remaining -= length;
+            next += 1;
+        } else {
+            position += offset / length * remaining;
+            break;
+        }
+    }
+    
+    (position, next)
+}
+
 impl Default for NPCMemory {
     fn default() -> Self {
         Self {
@@ -902,4 +1040,67 @@ mod tests {
         let shopkeepers = manager.find_npcs_by_type(NPCType::Shopkeeper);
         assert_eq!(shopkeepers.len(), 1); // npc2
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_find_path_around_obstacle() {
+        let mut map = GameMap::new(1, "测试".to_string(), Vec2::new(320.0, 320.0));
+        let wall = super::super::map::CollisionTile { solid: true, ..Default::default() };
+        for y in 0..4 {
+            map.set_collision(2, y, wall);
+        }
+        
+        let start = Vec2::new(16.0, 16.0);  // 瓦片(0, 0)
+        let goal = Vec2::new(144.0, 16.0);  // 瓦片(4, 0)
+        let path = find_path(&map, start, goal).unwrap();
+        
+        // 绕过墙底部：水平4步，上下各4步
+        assert_eq!(path.len(), 12);
+        assert_eq!(*path.last().unwrap(), goal);
+        
+        let mut previous = start;
+        for waypoint in &path {
+            let tile = (*waypoint / map.tile_size).floor();
+            assert!(!map.is_solid(tile.x as i32, tile.y as i32));
+            assert_eq!((*waypoint - previous).abs().element_sum(), 32.0);
+            previous = *waypoint;
+        }
+        
+        // 结果确定
+        assert_eq!(find_path(&map, start, goal), Some(path));
+        assert_eq!(find_path(&map, start, start), Some(Vec::new()));
+    }
+    
+    #[test]
+    fn test_find_path_blocked() {
+        let mut map = GameMap::new(1, "测试".to_string(), Vec2::new(320.0, 320.0));
+        let wall = super::super::map::CollisionTile { solid: true, ..Default::default() };
+        for y in 0..10 {
+            map.set_collision(2, y, wall);
+        }
+        
+        assert_eq!(find_path(&map, Vec2::new(16.0, 16.0), Vec2::new(144.0, 16.0)), None);
+        // 目标在墙内或地图外
+        assert_eq!(find_path(&map, Vec2::new(16.0, 16.0), Vec2::new(80.0, 16.0)), None);
+        assert_eq!(find_path(&map, Vec2::new(16.0, 16.0), Vec2::new(-16.0, 16.0)), None);
+    }
+    
+    #[test]
+    fn test_npc_follows_path() {
+        let map = GameMap::new(1, "测试".to_string(), Vec2::new(320.0, 320.0));
+        let mut manager = NPCManager::new();
+        let npc_id = manager.create_npc("训练师".to_string(), NPCType::Trainer, Vec3::new(16.0, 0.0, 16.0), 1).unwrap();
+        
+        assert!(manager.walk_to(npc_id, &map, Vec2::new(112.0, 16.0)).unwrap());
+        assert_eq!(manager.get_npc(npc_id).unwrap().path.len(), 3);
+        
+        // 速度50，2秒足够走完96像素
+        for _ in 0..20 {
+            manager.update_single_npc_ai(npc_id, 0.1, Vec3::ZERO).unwrap();
+        }
+        
+        let npc = manager.get_npc(npc_id).unwrap();
+        assert!(npc.path.is_empty());
+        assert_eq!(npc.position, Vec3::new(112.0, 0.0, 16.0));
+        assert_eq!(npc.facing_direction, Vec2::X);
+    }
+}
@@ -0,0 +1,118 @@
+// 空间索引
+// 开发心理：实体多起来后每次范围查询都遍历全部实体太慢，附近NPC、触发器、野生宝可梦都要频繁查询
+// 设计原则：地图平面(x, z)上的均匀网格，实体移动时更新所在格子，查询只访问覆盖范围内的格子
+
+use super::EntityId;
+use glam::{Vec2, Vec3};
+use std::collections::{HashMap, HashSet};
+
+pub const DEFAULT_CELL_SIZE: f32 = 64.0;
+
+type Cell = (i32, i32);
+
+#[derive(Debug, Clone)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<Cell, HashSet<EntityId>>,
+    entity_cells: HashMap<EntityId, Cell>,
+}
+
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        Self::new(DEFAULT_CELL_SIZE)
+    }
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(1.0),
+            cells: HashMap::new(),
+            entity_cells: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entity_cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entity_cells.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.entity_cells.clear();
+    }
+
+    // 插入或移动实体，格子不变时不做任何事
+    pub fn update(&mut self, entity_id: EntityId, position: Vec3) {
+        let cell = self.cell_of(Vec2::new(position.x, position.z));
+        match self.entity_cells.insert(entity_id, cell) {
+            Some(old_cell) if old_cell == cell => return,
+            Some(old_cell) => self.remove_from_cell(old_cell, entity_id),
+            None => {}
+        }
+        self.cells.entry(cell).or_default().insert(entity_id);
+    }
+
+    pub fn remove(&mut self, entity_id: EntityId) {
+        if let Some(cell) = self.entity_cells.remove(&entity_id) {
+            self.remove_from_cell(cell, entity_id);
+        }
+    }
+
+    // 返回与圆形范围相交的格子中的实体，调用方仍需按精确距离过滤
+    pub fn query_candidates(&self, center: Vec3, radius: f32) -> Vec<EntityId> {
+        let center = Vec2::new(center.x, center.z);
+        let radius = radius.max(0.0);
+        let min = self.cell_of(center - Vec2::splat(radius));
+        let max = self.cell_of(center + Vec2::splat(radius));
+
+        let mut candidates = Vec::new();
+        for cell_x in min.0..=max.0 {
+            for cell_y in min.1..=max.1 {
+                if let Some(entities) = self.cells.get(&(cell_x, cell_y)) {
+                    candidates.extend(entities.iter().copied());
+                }
+            }
+        }
+        candidates
+    }
+
+    fn cell_of(&self, position: Vec2) -> Cell {
+        (
+            (position.x / self.cell_size).floor() as i32,
+            (position.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    fn remove_from_cell(&mut self, cell: Cell, entity_id: EntityId) {
+        if let Some(entities) = self.cells.get_mut(&cell) {
+            entities.remove(&entity_id);
+            if entities.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moving_entity_changes_cell() {
+        let mut grid = SpatialGrid::new(32.0);
+        grid.update(1, Vec3::new(10.0, 0.0, 10.0));
+        assert_eq!(grid.query_candidates(Vec3::new(10.0, 0.0, 10.0), 1.0), vec![1]);
+
+        grid.update(1, Vec3::new(500.0, 0.0, 500.0));
+        assert!(grid.query_candidates(Vec3::new(10.0, 0.0, 10.0), 1.0).is_empty());
+        assert_eq!(grid.query_candidates(Vec3::new(500.0, 0.0, 500.0), 1.0), vec![1]);
+
+        grid.remove(1);
+        assert!(grid.is_empty());
+        assert!(grid.cells.is_empty());
+    }
+}
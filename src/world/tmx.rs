@@ -0,0 +1,480 @@
+// Tiled地图(.tmx)解析
+// 开发心理：关卡在Tiled中编辑，引擎直接读取tmx才能让地图几何和碰撞与编辑器保持一致
+// 设计原则：先解析成轻量XML树再映射到GameMap，只支持正交地图和内嵌瓦片集，不支持的内容明确报错
+
+use super::map::{
+    AnimationFrame, CollisionTile, CollisionType, GameMap, LayerType, MapId, TileData, TileId,
+    TileProperties, Tileset, WarpPoint,
+};
+use crate::core::error::GameError;
+use base64::Engine;
+use glam::{Vec2, Vec3, Vec4};
+use log::{debug, warn};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::io::Read;
+use std::str::FromStr;
+
+// Tiled在gid高位存储翻转标志
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const GID_MASK: u32 = 0x1FFF_FFFF;
+
+// 图层之间的Z间隔，给运行时插入的图层留出空间
+const LAYER_Z_STEP: i32 = 10;
+
+// 解析tmx文本为游戏地图，地图ID由调用方设置
+pub fn parse_tmx(source: &str, name: &str) -> Result<GameMap, GameError> {
+    let root = parse_xml(source)?;
+    if root.name != "map" {
+        return Err(GameError::Map(format!("TMX根元素应为<map>，得到<{}>", root.name)));
+    }
+
+    let orientation = root.attr("orientation").unwrap_or("orthogonal");
+    if orientation != "orthogonal" {
+        return Err(GameError::Map(format!("不支持的地图方向: {}", orientation)));
+    }
+    if root.attr("infinite") == Some("1") {
+        return Err(GameError::Map("不支持无限地图，请在Tiled中关闭infinite".to_string()));
+    }
+
+    let width: u32 = root.required("width")?;
+    let height: u32 = root.required("height")?;
+    let tile_size = Vec2::new(
+        root.required::<u32>("tilewidth")? as f32,
+        root.required::<u32>("tileheight")? as f32,
+    );
+
+    let mut map = GameMap::new(0, name.to_string(), Vec2::new(width as f32, height as f32) * tile_size);
+    map.tile_size = tile_size;
+    map.collision_map.grid_size = tile_size;
+    if let Some(color) = root.attr("backgroundcolor") {
+        map.background_color = parse_color(color)?;
+    }
+    map.properties = root.properties();
+    map.music = map.properties.get("music").cloned();
+
+    for tileset in root.children_named("tileset") {
+        map.tilesets.push(parse_tileset(tileset)?);
+    }
+    map.tilesets.sort_by_key(|tileset| tileset.first_gid);
+
+    // 图层按文档顺序从下往上叠放
+    let mut z_order = 0;
+    for node in &root.children {
+        match node.name.as_str() {
+            "layer" => {
+                parse_tile_layer(&mut map, node, width, z_order)?;
+                z_order += LAYER_Z_STEP;
+            },
+            "objectgroup" => parse_object_group(&mut map, node)?,
+            "imagelayer" | "group" => warn!("忽略暂不支持的TMX元素: <{}>", node.name),
+            _ => {}
+        }
+    }
+
+    debug!("TMX地图解析完成: '{}' {}x{} 图层={} 瓦片集={}",
+        name, width, height, map.layers.len(), map.tilesets.len());
+    Ok(map)
+}
+
+fn parse_tileset(node: &XmlNode) -> Result<Tileset, GameError> {
+    if let Some(source) = node.attr("source") {
+        return Err(GameError::Map(format!("暂不支持外部瓦片集 {}，请在Tiled中嵌入瓦片集", source)));
+    }
+
+    let mut tile_properties = HashMap::new();
+    for tile in node.children_named("tile") {
+        let id: TileId = tile.required("id")?;
+        let custom_properties = tile.properties();
+        let flag = |key: &str| custom_properties.get(key).map_or(false, |value| value == "true");
+
+        let mut animation_frames = Vec::new();
+        if let Some(animation) = tile.child("animation") {
+            for frame in animation.children_named("frame") {
+                animation_frames.push(AnimationFrame {
+                    tile_id: frame.required("tileid")?,
+                    duration: frame.required::<f32>("duration")? / 1000.0, // Tiled使用毫秒
+                });
+            }
+        }
+
+        tile_properties.insert(id, TileProperties {
+            solid: flag("solid"),
+            one_way: flag("one_way"),
+            animated: !animation_frames.is_empty(),
+            animation_frames,
+            custom_properties,
+        });
+    }
+
+    let image = node.child("image");
+    Ok(Tileset {
+        name: node.attr("name").unwrap_or_default().to_string(),
+        first_gid: node.required("firstgid")?,
+        texture_id: 0,
+        tile_width: node.required("tilewidth")?,
+        tile_height: node.required("tileheight")?,
+        margin: node.optional("margin")?.unwrap_or(0),
+        spacing: node.optional("spacing")?.unwrap_or(0),
+        tile_count: node.required("tilecount")?,
+        columns: node.required("columns")?,
+        image: image.and_then(|image| image.attr("source")).map(str::to_string),
+        image_width: image.map(|image| image.optional("width")).transpose()?.flatten().unwrap_or(0),
+        image_height: image.map(|image| image.optional("height")).transpose()?.flatten().unwrap_or(0),
+        tile_properties,
+    })
+}
+
+fn parse_tile_layer(map: &mut GameMap, node: &XmlNode, map_width: u32, z_order: i32) -> Result<(), GameError> {
+    let name = node.attr("name").unwrap_or("layer").to_string();
+    let layer_type = layer_type_for(&name);
+    let layer_width = node.optional("width")?.unwrap_or(map_width).max(1);
+    let data = node.child("data")
+        .ok_or_else(|| GameError::Map(format!("图层 '{}' 缺少<data>", name)))?;
+    let gids = parse_layer_data(data)?;
+
+    let layer_id = map.add_layer(layer_type, name, z_order);
+    let layer_index = map.layers.iter().position(|layer| layer.id == layer_id).unwrap_or_default();
+    let layer_tileset = gids.iter()
+        .find(|&&gid| gid & GID_MASK != 0)
+        .and_then(|&gid| map.tileset_for_gid(gid & GID_MASK))
+        .map(|tileset| tileset.name.clone());
+
+    let layer = &mut map.layers[layer_index];
+    layer.visible = node.attr("visible") != Some("0");
+    layer.opacity = node.optional("opacity")?.unwrap_or(1.0);
+    layer.parallax_factor = Vec2::new(
+        node.optional("parallaxx")?.unwrap_or(1.0),
+        node.optional("parallaxy")?.unwrap_or(1.0),
+    );
+    layer.tileset = layer_tileset;
+
+    for (index, &raw_gid) in gids.iter().enumerate() {
+        let gid = raw_gid & GID_MASK;
+        if gid == 0 {
+            continue;
+        }
+
+        let x = (index as u32 % layer_width) as i32;
+        let y = (index as u32 / layer_width) as i32;
+        map.set_tile(layer_id, x, y, tile_from_gid(raw_gid))?;
+
+        let solid = layer_type == LayerType::Collision
+            || map.tile_properties(gid).map_or(false, |properties| properties.solid);
+        if solid {
+            map.set_collision(x, y, CollisionTile {
+                collision_type: CollisionType::Solid,
+                solid: true,
+                ..Default::default()
+            });
+        }
+    }
+
+    Ok(())
+}
+
+// TileData先旋转再翻转；Tiled的对角翻转等价于顺时针旋转90度后再水平翻转
+fn tile_from_gid(raw_gid: u32) -> TileData {
+    let flipped_x = raw_gid & FLIPPED_HORIZONTALLY != 0;
+    let flipped_y = raw_gid & FLIPPED_VERTICALLY != 0;
+    let diagonal = raw_gid & FLIPPED_DIAGONALLY != 0;
+
+    TileData {
+        tile_id: raw_gid & GID_MASK,
+        rotation: if diagonal { 1 } else { 0 },
+        flip_x: flipped_x != diagonal,
+        flip_y: flipped_y,
+        ..Default::default()
+    }
+}
+
+fn layer_type_for(name: &str) -> LayerType {
+    let name = name.to_lowercase();
+    if name.contains("collision") || name.contains("碰撞") {
+        LayerType::Collision
+    } else if name.contains("background") || name.contains("背景") {
+        LayerType::Background
+    } else if name.contains("foreground") || name.contains("前景") {
+        LayerType::Foreground
+    } else if name.contains("decoration") || name.contains("装饰") {
+        LayerType::Decoration
+    } else if name.contains("object") || name.contains("物体") {
+        LayerType::Objects
+    } else {
+        LayerType::Terrain
+    }
+}
+
+fn parse_layer_data(data: &XmlNode) -> Result<Vec<u32>, GameError> {
+    match data.attr("encoding") {
+        Some("csv") => data.text
+            .split(',')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(|value| value.parse::<u32>()
+                .map_err(|e| GameError::Map(format!("无效的瓦片ID '{}': {}", value, e))))
+            .collect(),
+        Some("base64") => {
+            let encoded: String = data.text.chars().filter(|c| !c.is_whitespace()).collect();
+            let bytes = base64::engine::general_purpose::STANDARD.decode(encoded)
+                .map_err(|e| GameError::Map(format!("图层数据base64解码失败: {}", e)))?;
+
+            let mut decoded = Vec::new();
+            match data.attr("compression") {
+                None => decoded = bytes,
+                Some("zlib") => {
+                    flate2::read::ZlibDecoder::new(&bytes[..]).read_to_end(&mut decoded)?;
+                },
+                Some("gzip") => {
+                    flate2::read::GzDecoder::new(&bytes[..]).read_to_end(&mut decoded)?;
+                },
+                Some(other) => return Err(GameError::Map(format!("不支持的图层压缩格式: {}", other))),
+            }
+
+            if decoded.len() % 4 != 0 {
+                return Err(GameError::Map(format!("图层数据长度不是4的倍数: {}", decoded.len())));
+            }
+            Ok(decoded.chunks_exact(4)
+                .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect())
+        },
+        // 旧版XML格式，每个瓦片一个<tile gid="..."/>
+        None => data.children_named("tile")
+            .map(|tile| tile.optional("gid").map(|gid| gid.unwrap_or(0)))
+            .collect(),
+        Some(other) => Err(GameError::Map(format!("不支持的图层编码: {}", other))),
+    }
+}
+
+fn parse_object_group(map: &mut GameMap, node: &XmlNode) -> Result<(), GameError> {
+    for object in node.children_named("object") {
+        let name = object.attr("name").unwrap_or_default().to_string();
+        // Tiled 1.9起对象类型写在class属性中
+        let object_type = object.attr("type").or_else(|| object.attr("class")).unwrap_or_default().to_string();
+        let origin = Vec2::new(object.optional("x")?.unwrap_or(0.0), object.optional("y")?.unwrap_or(0.0));
+        let size = Vec2::new(object.optional("width")?.unwrap_or(0.0), object.optional("height")?.unwrap_or(0.0));
+        let mut properties = object.properties();
+
+        // 地图平面(x, y)对应世界坐标(x, 0, y)，矩形对象取中心点
+        let center = origin + size * 0.5;
+        let position = Vec3::new(center.x, 0.0, center.y);
+
+        match object_type.as_str() {
+            "spawn" => {
+                map.spawn_points.insert(name, position);
+            },
+            "warp" => {
+                let target_map = properties.get("target_map")
+                    .map(|value| parse_property::<MapId>("target_map", value))
+                    .transpose()?;
+                let target_x = properties.get("target_x").map(|value| parse_property::<f32>("target_x", value)).transpose()?;
+                let target_y = properties.get("target_y").map(|value| parse_property::<f32>("target_y", value)).transpose()?;
+
+                map.add_warp_point(name.clone(), WarpPoint {
+                    name,
+                    position,
+                    target_map,
+                    target_position: Vec3::new(target_x.unwrap_or(0.0), 0.0, target_y.unwrap_or(0.0)),
+                    direction: Vec2::ZERO,
+                    requirements: Vec::new(),
+                    sound_effect: properties.remove("sound"),
+                    animation: properties.remove("animation"),
+                });
+            },
+            _ => {
+                let rotation: f32 = object.optional("rotation")?.unwrap_or(0.0);
+                let object_id = map.add_dynamic_object(object_type, position);
+                let tile_size = map.tile_size;
+
+                if let Some(dynamic_object) = map.dynamic_objects.get_mut(&object_id) {
+                    if size != Vec2::ZERO {
+                        dynamic_object.scale = size / tile_size;
+                    }
+                    dynamic_object.rotation = rotation.to_radians();
+                    dynamic_object.persistent = true;
+                    if !name.is_empty() {
+                        properties.insert("name".to_string(), name);
+                    }
+                    dynamic_object.properties = properties;
+                }
+            },
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_property<T: FromStr>(key: &str, value: &str) -> Result<T, GameError>
+where
+    T::Err: std::fmt::Display,
+{
+    value.parse::<T>()
+        .map_err(|e| GameError::Map(format!("属性 {}='{}' 无效: {}", key, value, e)))
+}
+
+// 支持 #RRGGBB 和 #AARRGGBB
+fn parse_color(value: &str) -> Result<Vec4, GameError> {
+    let hex = value.trim_start_matches('#');
+    let invalid = || GameError::Map(format!("无效的颜色: {}", value));
+    if !hex.is_ascii() {
+        return Err(invalid());
+    }
+
+    let channel = |start: usize| u8::from_str_radix(&hex[start..start + 2], 16)
+        .map(|channel| channel as f32 / 255.0)
+        .map_err(|_| invalid());
+
+    match hex.len() {
+        6 => Ok(Vec4::new(channel(0)?, channel(2)?, channel(4)?, 1.0)),
+        8 => Ok(Vec4::new(channel(2)?, channel(4)?, channel(6)?, channel(0)?)),
+        _ => Err(invalid()),
+    }
+}
+
+// 轻量XML节点
+#[derive(Debug, Default)]
+struct XmlNode {
+    name: String,
+    attributes: HashMap<String, String>,
+    children: Vec<XmlNode>,
+    text: String,
+}
+
+impl XmlNode {
+    fn from_start(start: &BytesStart) -> Result<Self, GameError> {
+        let mut node = XmlNode {
+            name: String::from_utf8_lossy(start.name().as_ref()).into_owned(),
+            ..Default::default()
+        };
+
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(xml_error)?;
+            let key = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+            let value = attribute.unescape_value().map_err(xml_error)?.into_owned();
+            node.attributes.insert(key, value);
+        }
+
+        Ok(node)
+    }
+
+    fn attr(&self, key: &str) -> Option<&str> {
+        self.attributes.get(key).map(String::as_str)
+    }
+
+    fn optional<T: FromStr>(&self, key: &str) -> Result<Option<T>, GameError>
+    where
+        T::Err: std::fmt::Display,
+    {
+        self.attr(key)
+            .map(|value| value.parse::<T>().map_err(|e| GameError::Map(
+                format!("<{}> 属性 {}='{}' 无效: {}", self.name, key, value, e))))
+            .transpose()
+    }
+
+    fn required<T: FromStr>(&self, key: &str) -> Result<T, GameError>
+    where
+        T::Err: std::fmt::Display,
+    {
+        self.optional(key)?
+            .ok_or_else(|| GameError::Map(format!("<{}> 缺少属性 {}", self.name, key)))
+    }
+
+    fn child(&self, name: &str) -> Option<&XmlNode> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a XmlNode> + 'a {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    // <properties>中的自定义属性，多行文本属性的值写在元素内容中
+    fn properties(&self) -> HashMap<String, String> {
+        self.child("properties")
+            .map(|properties| properties.children_named("property")
+                .filter_map(|property| {
+                    let value = property.attr("value").map(str::to_string).unwrap_or_else(|| property.text.clone());
+                    Some((property.attr("name")?.to_string(), value))
+                })
+                .collect())
+            .unwrap_or_default()
+    }
+}
+
+fn xml_error(error: impl std::fmt::Display) -> GameError {
+    GameError::Map(format!("TMX解析失败: {}", error))
+}
+
+fn parse_xml(source: &str) -> Result<XmlNode, GameError> {
+    let mut reader = Reader::from_str(source);
+    reader.trim_text(true);
+
+    let mut stack: Vec<XmlNode> = Vec::new();
+    let mut root = None;
+
+    loop {
+        let completed = match reader.read_event().map_err(xml_error)? {
+            Event::Start(start) => {
+                stack.push(XmlNode::from_start(&start)?);
+                None
+            },
+            Event::Empty(start) => Some(XmlNode::from_start(&start)?),
+            Event::End(_) => Some(stack.pop().ok_or_else(|| xml_error("多余的结束标签"))?),
+            Event::Text(text) => {
+                if let Some(node) = stack.last_mut() {
+                    node.text.push_str(&text.unescape().map_err(xml_error)?);
+                }
+                None
+            },
+            Event::CData(data) => {
+                if let Some(node) = stack.last_mut() {
+                    node.text.push_str(&String::from_utf8_lossy(&data));
+                }
+                None
+            },
+            Event::Eof => break,
+            _ => None,
+        };
+
+        if let Some(node) = completed {
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(node),
+                None => root = Some(node),
+            }
+        }
+    }
+
+    if !stack.is_empty() {
+        return Err(xml_error("元素未闭合"));
+    }
+    root.ok_or_else(|| xml_error("缺少根元素"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_from_gid_flags() {
+        let tile = tile_from_gid(5 | FLIPPED_HORIZONTALLY);
+        assert_eq!(tile.tile_id, 5);
+        assert!(tile.flip_x && !tile.flip_y);
+        assert_eq!(tile.rotation, 0);
+
+        // Tiled中"顺时针旋转90度"为对角+水平翻转
+        let rotated = tile_from_gid(5 | FLIPPED_DIAGONALLY | FLIPPED_HORIZONTALLY);
+        assert_eq!(rotated.rotation, 1);
+        assert!(!rotated.flip_x && !rotated.flip_y);
+    }
+
+    #[test]
+    fn test_unsupported_map_is_rejected() {
+        let isometric = r#"<map orientation="isometric" width="1" height="1" tilewidth="16" tileheight="16"/>"#;
+        assert!(parse_tmx(isometric, "iso").is_err());
+
+        let unclosed = r#"<map orientation="orthogonal" width="1" height="1" tilewidth="16" tileheight="16">"#;
+        assert!(parse_tmx(unclosed, "broken").is_err());
+    }
+}
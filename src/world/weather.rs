@@ -369,7 +369,7 @@ impl WeatherSystem {
 
     fn apply_time_modifiers(&self, probabilities: &mut HashMap<WeatherCondition, f32>, time_of_day: TimeOfDay) {
         match time_of_day {
-            TimeOfDay::Dawn | TimeOfDay::Dusk => {
+            TimeOfDay::Morning | TimeOfDay::Evening => {
                 *probabilities.get_mut(&WeatherCondition::Fog).unwrap() *= 2.0;
             },
             TimeOfDay::Night => {
@@ -460,12 +460,9 @@ impl WeatherSystem {
         };
 
         let time_modifier = match time_of_day {
-            TimeOfDay::Dawn => -2.0,
-            TimeOfDay::Morning => 0.0,
-            TimeOfDay::Noon => 5.0,
-            TimeOfDay::Afternoon => 3.0,
-            TimeOfDay::Evening => -1.0,
-            TimeOfDay::Dusk => -3.0,
+            TimeOfDay::Morning => -1.0,
+            TimeOfDay::Day => 4.0,
+            TimeOfDay::Evening => -2.0,
             TimeOfDay::Night => -5.0,
         };
 
@@ -844,7 +841,7 @@ impl WeatherSystem {
 
     /// 强制设置天气
     pub fn force_weather(&mut self, condition: WeatherCondition, duration: f32, current_time: f64) -> GameResult<WeatherEvent> {
-        let mut weather_state = self.generate_weather_state(condition, Season::Spring, TimeOfDay::Noon, &mut RandomGenerator::new());
+        let mut weather_state = self.generate_weather_state(condition, Season::Spring, TimeOfDay::Day, &mut RandomGenerator::new());
         weather_state.duration = duration;
         
         self.change_weather(weather_state, current_time, WeatherTrigger::AdminForced)
@@ -0,0 +1,261 @@
+// 网络回环测试
+// 开发心理：传输层的半包处理和握手流程只有在真实套接字上才能验证，单元测试覆盖不到
+// 设计原则：服务器和客户端在同一线程里轮流轮询，端口由系统分配，避免测试之间抢占端口
+#![cfg(feature = "network-wip")]
+
+use pokemongo::network::{ConnectionStatus, DeliveryMethod, DisconnectReason, NetworkClient, NetworkConfig, NetworkServer, TransportEvent};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const FRAME_TIME: Duration = Duration::from_millis(16);
+
+// 轮流更新两端，直到条件满足或超时
+fn pump(server: &mut NetworkServer, client: &mut NetworkClient, mut done: impl FnMut(&mut NetworkServer, &mut NetworkClient) -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        server.update(FRAME_TIME).unwrap();
+        client.update(FRAME_TIME).unwrap();
+        if done(server, client) {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    panic!("回环测试超时");
+}
+
+// 等到客户端收到连接ID、服务器报告连接建立(加密时即双方完成密钥交换)，返回连接ID
+fn wait_connected(server: &mut NetworkServer, client: &mut NetworkClient) -> u64 {
+    let mut connected = None;
+    pump(server, client, |server, client| {
+        if let Some(TransportEvent::Connected { connection_id, .. }) = server.poll_event() {
+            connected = Some(connection_id);
+        }
+        connected.is_some() && client.get_status() == ConnectionStatus::Connected
+    });
+    assert_eq!(client.connection_id(), connected);
+    connected.unwrap()
+}
+
+fn loopback_config() -> NetworkConfig {
+    NetworkConfig {
+        server_address: "127.0.0.1".to_string(),
+        server_port: 0,
+        ..NetworkConfig::default()
+    }
+}
+
+#[test]
+fn test_loopback_echo() {
+    let config = loopback_config();
+    let mut server = NetworkServer::new(config.clone()).unwrap();
+    let port = server.local_addr().unwrap().port();
+
+    let mut client = NetworkClient::new(config).unwrap();
+    client.connect("127.0.0.1", port).unwrap();
+    assert_eq!(client.get_status(), ConnectionStatus::Connecting);
+
+    let connection_id = wait_connected(&mut server, &mut client);
+
+    // 连接后双方立即互发心跳，收到回应即得到往返时延
+    pump(&mut server, &mut client, |server, client| {
+        client.health().is_some() && server.connection_health(connection_id).is_some()
+    });
+    assert!(client.health().unwrap().rtt_ms < 1000.0);
+
+    // 大于单次读取的可靠消息会分多次到达，服务器原样回显
+    let payload: Vec<u8> = (0..20_000u32).map(|i| (i % 251) as u8).collect();
+    client.send(&payload, DeliveryMethod::ReliableOrdered).unwrap();
+
+    let mut request = None;
+    pump(&mut server, &mut client, |server, _| {
+        request = server.recv();
+        request.is_some()
+    });
+    let (from, data) = request.unwrap();
+    assert_eq!(from, connection_id);
+    assert_eq!(data, payload);
+    server.send_to_client(from, &data, DeliveryMethod::Reliable).unwrap();
+
+    let mut echo = None;
+    pump(&mut server, &mut client, |_, client| {
+        echo = client.recv();
+        echo.is_some()
+    });
+    assert_eq!(echo.unwrap(), payload);
+
+    // 不可靠消息走UDP
+    client.send(b"ping", DeliveryMethod::Unreliable).unwrap();
+    let mut ping = None;
+    pump(&mut server, &mut client, |server, _| {
+        ping = server.recv();
+        ping.is_some()
+    });
+    assert_eq!(ping.unwrap(), (connection_id, b"ping".to_vec()));
+
+    server.send_to_client(connection_id, b"pong", DeliveryMethod::Unreliable).unwrap();
+    let mut pong = None;
+    pump(&mut server, &mut client, |_, client| {
+        pong = client.recv();
+        pong.is_some()
+    });
+    assert_eq!(pong.unwrap(), b"pong".to_vec());
+
+    // 客户端断开后服务器报告断开事件
+    client.disconnect(DisconnectReason::UserRequested).unwrap();
+    assert_eq!(client.get_status(), ConnectionStatus::Disconnected);
+    let mut disconnected = false;
+    pump(&mut server, &mut client, |server, _| {
+        disconnected |= matches!(server.poll_event(), Some(TransportEvent::Disconnected { .. }));
+        disconnected
+    });
+    assert_eq!(server.connection_count(), 0);
+}
+
+#[test]
+fn test_flood_triggers_rate_limit_and_ban() {
+    let mut config = loopback_config();
+    config.rate_limit.burst_size = 5;
+    config.rate_limit.max_packets_per_second = 1;
+    let mut server = NetworkServer::new(config.clone()).unwrap();
+    let port = server.local_addr().unwrap().port();
+
+    let mut client = NetworkClient::new(config.clone()).unwrap();
+    client.connect("127.0.0.1", port).unwrap();
+    wait_connected(&mut server, &mut client);
+
+    // 一次发出远超突发上限的消息
+    for i in 0..20u8 {
+        client.send(&[i], DeliveryMethod::Reliable).unwrap();
+    }
+
+    let mut reason = None;
+    pump(&mut server, &mut client, |server, _| {
+        if let Some(TransportEvent::Disconnected { reason: r, .. }) = server.poll_event() {
+            reason = Some(r);
+        }
+        reason.is_some()
+    });
+    assert_eq!(reason, Some(DisconnectReason::RateLimited));
+    pump(&mut server, &mut client, |_, client| client.get_status() == ConnectionStatus::Disconnected);
+
+    // 封禁期间同一地址的新连接在握手前就被关闭
+    let mut retry = NetworkClient::new(config).unwrap();
+    retry.connect("127.0.0.1", port).unwrap();
+    pump(&mut server, &mut retry, |_, retry| retry.get_status() == ConnectionStatus::Disconnected);
+    assert_eq!(retry.connection_id(), None);
+    assert_eq!(server.connection_count(), 0);
+    assert!(server.poll_event().is_none());
+}
+
+// 在客户端和服务器之间转发TCP流量并记下经过的所有字节，相当于链路上的被动监听者；
+// 客户端的UDP数据报只记录不转发，消息都走TCP
+fn start_tap_relay(server_port: u16) -> (u16, Arc<Mutex<Vec<u8>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let relay_port = listener.local_addr().unwrap().port();
+    let captured = Arc::new(Mutex::new(Vec::new()));
+
+    let udp = UdpSocket::bind(("127.0.0.1", relay_port)).unwrap();
+    let tap = Arc::clone(&captured);
+    std::thread::spawn(move || {
+        let mut buffer = [0u8; 65536];
+        while let Ok(read) = udp.recv(&mut buffer) {
+            tap.lock().unwrap().extend_from_slice(&buffer[..read]);
+        }
+    });
+
+    let tap = Arc::clone(&captured);
+    std::thread::spawn(move || {
+        let (client_side, _) = listener.accept().unwrap();
+        let server_side = TcpStream::connect(("127.0.0.1", server_port)).unwrap();
+        let forward = |mut from: TcpStream, mut to: TcpStream, tap: Arc<Mutex<Vec<u8>>>| {
+            std::thread::spawn(move || {
+                let mut buffer = [0u8; 4096];
+                while let Ok(read) = from.read(&mut buffer) {
+                    tap.lock().unwrap().extend_from_slice(&buffer[..read]);
+                    if read == 0 || to.write_all(&buffer[..read]).is_err() {
+                        break;
+                    }
+                }
+            })
+        };
+        forward(client_side.try_clone().unwrap(), server_side.try_clone().unwrap(), Arc::clone(&tap));
+        forward(server_side, client_side, tap);
+    });
+    (relay_port, captured)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+#[test]
+fn test_encrypted_traffic_unreadable_by_observer() {
+    let config = loopback_config();
+    assert!(config.enable_encryption);
+    let mut server = NetworkServer::new(config.clone()).unwrap();
+    let (relay_port, captured) = start_tap_relay(server.local_addr().unwrap().port());
+
+    let mut client = NetworkClient::new(config).unwrap();
+    client.connect("127.0.0.1", relay_port).unwrap();
+    let connection_id = wait_connected(&mut server, &mut client);
+
+    let secret = "我的队伍: 快龙、耿鬼、卡比兽".as_bytes();
+    client.send(secret, DeliveryMethod::ReliableOrdered).unwrap();
+    let mut request = None;
+    pump(&mut server, &mut client, |server, _| {
+        request = server.recv();
+        request.is_some()
+    });
+    assert_eq!(request.unwrap(), (connection_id, secret.to_vec()));
+
+    let reply = "收到，对战见".as_bytes();
+    server.send_to_client(connection_id, reply, DeliveryMethod::Reliable).unwrap();
+    let mut response = None;
+    pump(&mut server, &mut client, |_, client| {
+        response = client.recv();
+        response.is_some()
+    });
+    assert_eq!(response.unwrap(), reply.to_vec());
+
+    // 双方都读到了明文，链路上的字节里却找不到
+    let captured = captured.lock().unwrap();
+    assert!(captured.len() > secret.len() + reply.len());
+    assert!(!contains(&captured, secret));
+    assert!(!contains(&captured, reply));
+}
+
+#[test]
+fn test_unencrypted_peer_rejected() {
+    let config = loopback_config();
+    let mut server = NetworkServer::new(config.clone()).unwrap();
+    let port = server.local_addr().unwrap().port();
+
+    // 不加密的客户端发出的明文消息被当作协议错误
+    let mut plain_client = NetworkClient::new(NetworkConfig { enable_encryption: false, ..config.clone() }).unwrap();
+    plain_client.connect("127.0.0.1", port).unwrap();
+    pump(&mut server, &mut plain_client, |_, client| client.get_status() == ConnectionStatus::Connected);
+    plain_client.send(b"hello", DeliveryMethod::Reliable).unwrap();
+
+    let mut reason = None;
+    pump(&mut server, &mut plain_client, |server, client| {
+        if let Some(TransportEvent::Disconnected { reason: r, .. }) = server.poll_event() {
+            reason = Some(r);
+        }
+        reason.is_some() && client.get_status() == ConnectionStatus::Disconnected
+    });
+    assert_eq!(reason, Some(DisconnectReason::ProtocolError));
+    assert!(server.recv().is_none());
+    assert_eq!(server.connection_count(), 0);
+
+    // 要求加密的客户端拒绝不提供公钥的服务器
+    let mut plain_server = NetworkServer::new(NetworkConfig { enable_encryption: false, ..config.clone() }).unwrap();
+    let port = plain_server.local_addr().unwrap().port();
+    let mut client = NetworkClient::new(config).unwrap();
+    client.connect("127.0.0.1", port).unwrap();
+    pump(&mut plain_server, &mut client, |server, client| {
+        client.get_status() == ConnectionStatus::Disconnected && server.connection_count() == 0
+    });
+    assert_eq!(client.connection_id(), None);
+}